@@ -0,0 +1,220 @@
+//! Benchmarks over a synthetic dbt project.
+//!
+//! Run with `cargo bench -p dbt-serde_yaml --features bench`. The corpus lives
+//! in `benches/data` and is scaled up by repeating its models, so that
+//! results are dominated by steady-state throughput rather than setup.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use dbt_serde_yaml::Value;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SCHEMA_YML: &str = include_str!("data/schema.yml");
+const DBT_PROJECT_YML: &str = include_str!("data/dbt_project.yml");
+
+/// Number of copies of each model in the scaled-up schema file.
+const SCALE: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+struct Schema {
+    version: u32,
+    #[serde(default)]
+    models: Vec<Model>,
+    #[serde(default)]
+    sources: Vec<Source>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Model {
+    name: String,
+    description: Option<String>,
+    config: Option<ModelConfig>,
+    #[serde(default)]
+    columns: Vec<Column>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModelConfig {
+    materialized: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(flatten)]
+    common: CommonConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CommonConfig {
+    meta: Option<Value>,
+    #[serde(flatten)]
+    rest: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Column {
+    name: String,
+    description: Option<String>,
+    data_type: Option<String>,
+    #[serde(default)]
+    data_tests: Vec<Value>,
+    meta: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Source {
+    name: String,
+    #[serde(flatten)]
+    rest: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Project {
+    name: String,
+    version: String,
+    profile: String,
+    #[serde(flatten)]
+    rest: BTreeMap<String, Value>,
+}
+
+/// Builds a large schema file by repeating the models of the corpus file
+/// under distinct names.
+fn scaled_schema() -> String {
+    let mut schema: Value = dbt_serde_yaml::from_str(SCHEMA_YML).unwrap();
+    let models = schema["models"].as_sequence().unwrap().clone();
+    let mut scaled = Vec::with_capacity(models.len() * SCALE);
+    for i in 0..SCALE {
+        for model in &models {
+            let mut model = model.clone();
+            let name = format!("{}_{}", model["name"].as_str().unwrap(), i);
+            model["name"] = Value::from(name);
+            scaled.push(model);
+        }
+    }
+    schema["models"] = Value::from(scaled);
+    dbt_serde_yaml::to_string(&schema).unwrap()
+}
+
+fn bench_from_str(c: &mut Criterion) {
+    let schema = scaled_schema();
+    let mut group = c.benchmark_group("from_str");
+    group.throughput(Throughput::Bytes(schema.len() as u64));
+    group.bench_function("schema_value", |b| {
+        b.iter(|| dbt_serde_yaml::from_str::<Value>(&schema).unwrap());
+    });
+    group.bench_function("schema_typed", |b| {
+        b.iter(|| dbt_serde_yaml::from_str::<Schema>(&schema).unwrap());
+    });
+    group.bench_function("schema_value_with_callback", |b| {
+        b.iter(|| {
+            Value::from_str(&schema, |_, _, _| {
+                dbt_serde_yaml::mapping::DuplicateKey::Error
+            })
+            .unwrap()
+        });
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("from_str");
+    group.throughput(Throughput::Bytes(DBT_PROJECT_YML.len() as u64));
+    group.bench_function("project_typed", |b| {
+        b.iter(|| dbt_serde_yaml::from_str::<Project>(DBT_PROJECT_YML).unwrap());
+    });
+    group.finish();
+}
+
+fn bench_into_typed(c: &mut Criterion) {
+    let schema: Value = dbt_serde_yaml::from_str(&scaled_schema()).unwrap();
+    let mut group = c.benchmark_group("into_typed");
+    group.bench_function("no_callbacks", |b| {
+        b.iter_batched(
+            || schema.clone(),
+            |value| {
+                value
+                    .into_typed::<Schema, _, _>(|_, _, _| {}, |_| Ok(None))
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("with_callbacks", |b| {
+        b.iter_batched(
+            || schema.clone(),
+            |value| {
+                let mut unused_keys = 0;
+                let schema = value
+                    .into_typed::<Schema, _, _>(
+                        |_, _, _| unused_keys += 1,
+                        |v| match v.as_str() {
+                            Some(s) if s.starts_with("{{") => {
+                                Ok(Some(Value::string(s.trim_matches(['{', '}', ' ']).into())))
+                            }
+                            _ => Ok(None),
+                        },
+                    )
+                    .unwrap();
+                (schema, unused_keys)
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("to_typed_with_callbacks", |b| {
+        b.iter(|| {
+            schema
+                .to_typed::<Schema, _, _>(|_, _, _| {}, |_| Ok(None))
+                .unwrap()
+        });
+    });
+    group.finish();
+}
+
+fn bench_flatten(c: &mut Criterion) {
+    let configs: Vec<Value> = dbt_serde_yaml::from_str::<Value>(&scaled_schema()).unwrap()
+        ["models"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|model| model["config"].clone())
+        .collect();
+    let configs = Value::from(configs);
+    c.bench_function("into_typed/flatten_configs", |b| {
+        b.iter_batched(
+            || configs.clone(),
+            |value| {
+                value
+                    .into_typed::<Vec<ModelConfig>, _, _>(|_, _, _| {}, |_| Ok(None))
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let schema_yml = scaled_schema();
+    let value: Value = dbt_serde_yaml::from_str(&schema_yml).unwrap();
+    let typed: Schema = dbt_serde_yaml::from_str(&schema_yml).unwrap();
+    let mut group = c.benchmark_group("to_string");
+    group.throughput(Throughput::Bytes(schema_yml.len() as u64));
+    group.bench_function("schema_value", |b| {
+        b.iter(|| dbt_serde_yaml::to_string(&value).unwrap());
+    });
+    group.bench_function("schema_typed", |b| {
+        b.iter(|| dbt_serde_yaml::to_string(&typed).unwrap());
+    });
+    group.bench_function("schema_to_writer", |b| {
+        b.iter(|| dbt_serde_yaml::to_writer(std::io::sink(), &typed).unwrap());
+    });
+    group.finish();
+
+    c.bench_function("to_value/schema_typed", |b| {
+        b.iter(|| dbt_serde_yaml::to_value(&typed).unwrap());
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_from_str,
+    bench_into_typed,
+    bench_flatten,
+    bench_serialize
+);
+criterion_main!(benches);
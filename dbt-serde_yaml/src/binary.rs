@@ -0,0 +1,74 @@
+//! Base64 codec for the YAML `!!binary` tag.
+//!
+//! The [binary type] holds standard base64 (RFC 4648, with padding). Line
+//! breaks and other whitespace may appear anywhere in the scalar, since long
+//! blobs are usually folded across several lines.
+//!
+//! [binary type]: https://yaml.org/type/binary.html
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as base64 on a single line.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes base64 `text`, ignoring whitespace. Returns `None` if the text is
+/// not valid base64.
+pub(crate) fn decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut n = 0u32;
+    let mut digits = 0;
+    let mut padding = 0;
+    for &byte in text {
+        let sextet = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            // Data after padding.
+            return None;
+        }
+        n = (n << 6) | sextet as u32;
+        digits += 1;
+        if digits == 4 {
+            out.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8]);
+            n = 0;
+            digits = 0;
+        }
+    }
+    match (digits, padding) {
+        (0, 0) => {}
+        (2, 0 | 2) if n & 0xF == 0 => out.push((n >> 4) as u8),
+        (3, 0 | 1) if n & 0x3 == 0 => {
+            out.extend_from_slice(&[(n >> 10) as u8, (n >> 2) as u8]);
+        }
+        _ => return None,
+    }
+    Some(out)
+}
@@ -0,0 +1,138 @@
+//! Panic safety for user-supplied callbacks.
+//!
+//! Deserialization keeps a fair amount of state in thread-locals: the current
+//! source marker, whether field transformation is enabled, the `ShouldBe`
+//! handshake, and the `Value` fast path side channel. If a callback panics
+//! while that state is set up, unwinding through the deserializer would leave
+//! it behind and corrupt the next deserialization on the same thread. Instead,
+//! callbacks are run through [guard], which catches the panic, resets the
+//! state, and turns the panic into an error.
+//!
+//! Nothing is saved for this as each callback starts. The state that scopes
+//! restore as they are dropped, like the config, the `Value` fast path and
+//! field transformation, is put back by the unwinding itself; the rest is
+//! either taken over within a single node, like the `ShouldBe` handshake, and
+//! cleared, or follows the input, like the marker, and is set again from the
+//! span of the value the callback was invoked for.
+//!
+//! A callback may deserialize something that runs callbacks of its own, but
+//! only [MAX_DEPTH] callbacks can run at once on a thread; [guard] fails
+//! rather than run one more.
+//!
+//! Fallible callbacks, which can't return their error through the callback
+//! signatures the deserializer invokes, report it with [fail] instead; [guard]
+//! then turns it into an error located at the callback's path and span. The
+//! path and span are also available to the callback through [with_current].
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::error::Error as StdError;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::NonNull;
+
+use crate::path::Path;
+use crate::{error, shouldbe, spanned, Error, Span};
+
+/// The most callbacks that can run at once on a thread, each from within
+/// the one before.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// Runs the callback `f`, which was invoked for the value at `path` and
+/// `span`, converting a panic into an error.
+pub(crate) fn guard<R>(path: &Path, span: &Span, f: impl FnOnce() -> R) -> Result<R, Error> {
+    let depth = RUNNING.with(|cell| cell.borrow().as_ref().map_or(0, |running| running.depth));
+    if depth == MAX_DEPTH {
+        return Err(error::callback_recursion_limit_exceeded(span.clone(), path));
+    }
+    // Each callback gets its own empty slot for a failure, so that one can
+    // neither outlive the callback nor be mistaken for that of an enclosing
+    // one.
+    let running = Running {
+        path: NonNull::from(path).cast(),
+        span: NonNull::from(span),
+        depth: depth + 1,
+        failure: None,
+    };
+    let outer = RUNNING.with(|cell| cell.borrow_mut().replace(running));
+    let restore = RestoreRunning(outer);
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        reset_state(span);
+        error::callback_panicked(panic_message(payload.as_ref()), span.clone(), path)
+    });
+    let failure = RUNNING.with(|cell| cell.borrow_mut().as_mut()?.failure.take());
+    drop(restore);
+    match failure {
+        Some(err) => Err(error::callback_failed(err, span.clone(), path)),
+        None => result,
+    }
+}
+
+/// Records that the callback currently run by [guard] failed with `err`.
+/// Outside of [guard], there is nothing to report the failure to, and it is
+/// dropped.
+pub(crate) fn fail(err: Box<dyn StdError + 'static + Send + Sync>) {
+    RUNNING.with(|cell| {
+        if let Some(running) = cell.borrow_mut().as_mut() {
+            running.failure = Some(err);
+        }
+    });
+}
+
+/// Calls `f` with the path and span of the value that the callback currently
+/// run by [guard] was invoked for, if any.
+pub(crate) fn with_current<R>(f: impl FnOnce(&Path, &Span) -> R) -> Option<R> {
+    let (path, span) = RUNNING.with(|cell| {
+        let running = cell.borrow();
+        let running = running.as_ref()?;
+        Some((running.path, running.span))
+    })?;
+    // SAFETY: both are borrowed by `guard` for as long as the callback runs,
+    // which is when it is registered as running.
+    Some(unsafe { f(path.as_ref(), span.as_ref()) })
+}
+
+/// The callback run by [guard].
+struct Running {
+    path: NonNull<Path<'static>>,
+    span: NonNull<Span>,
+    /// How many callbacks are running, counting this one.
+    depth: usize,
+    failure: Option<Box<dyn StdError + 'static + Send + Sync>>,
+}
+
+struct RestoreRunning(Option<Running>);
+
+impl Drop for RestoreRunning {
+    fn drop(&mut self) {
+        RUNNING.with(|cell| *cell.borrow_mut() = self.0.take());
+    }
+}
+
+thread_local! {
+    /// The callback run by the innermost [guard], or `None` outside of one.
+    static RUNNING: RefCell<Option<Running>> = const { RefCell::new(None) };
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// Resets the thread-local deserialization state that a callback which
+/// panicked may have left behind, and which no scope puts back, to what it is
+/// at the value at `span`.
+fn reset_state(span: &Span) {
+    spanned::set_marker(span.start);
+    #[cfg(feature = "filename")]
+    if let Some(filename) = &span.filename {
+        spanned::set_filename(std::sync::Arc::clone(filename));
+    }
+    shouldbe::set_expecting_should_be(None);
+    shouldbe::clear_why_not();
+    spanned::take_span();
+}
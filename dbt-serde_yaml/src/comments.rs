@@ -0,0 +1,227 @@
+//! Insertion of comment lines into serialized YAML.
+//!
+//! Comments have no representation in the serializer's event stream, so they
+//! are added to the finished output instead: the output is parsed back, the
+//! path of every node is reconstructed, and the lines returned for each path
+//! are spliced in at line boundaries around the node.
+
+use crate::error::Result;
+use crate::libyaml::parser::{Event, Parser};
+use crate::path::{OwnedPath, Path};
+use std::borrow::Cow;
+
+/// Comment lines to emit around a node; see
+/// [`to_string_with_comments`](crate::to_string_with_comments).
+///
+/// Each line is the text of one comment, without the leading `#`. Lines
+/// containing line breaks are split into several comments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Comments {
+    /// Lines emitted above the node, or above its key if it is the value of a
+    /// mapping entry, or above its `-` if it is a sequence element.
+    pub before: Vec<String>,
+    /// Lines emitted below the last line of the node.
+    pub after: Vec<String>,
+}
+
+struct Frame {
+    path: OwnedPath,
+    mapping: bool,
+    flow: bool,
+    /// Number of complete nodes so far, counting keys and values separately.
+    len: usize,
+    /// The last key, if it was a scalar.
+    key: Option<String>,
+    /// Offset of the last key.
+    key_start: usize,
+    /// Whether this collection is, or is nested in, a mapping key.
+    in_key: bool,
+    /// Offset just past the last complete child.
+    end: usize,
+    /// Comments to emit after the collection, with their indentation.
+    after: Option<(Vec<String>, usize)>,
+}
+
+/// Returns `yaml` with the comments returned by `comments` for each node
+/// inserted around it.
+pub(crate) fn insert(yaml: &str, comments: &mut dyn FnMut(Path<'_>) -> Comments) -> Result<String> {
+    let input = yaml.as_bytes();
+    let mut parser = Parser::new(Cow::Borrowed(input));
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut inserts: Vec<(usize, String)> = Vec::new();
+
+    loop {
+        let (event, mark) = parser.next()?;
+        let start = mark.index() as usize;
+        let (flow, mapping) = match event {
+            Event::StreamEnd => break,
+            Event::StreamStart | Event::DocumentStart | Event::DocumentEnd => continue,
+            Event::SequenceEnd | Event::MappingEnd => {
+                let frame = stack.pop().unwrap();
+                // Flow collections end at their closing bracket; block ones
+                // end with their last child.
+                let end = if frame.flow { start + 1 } else { frame.end };
+                if let Some((lines, indent)) = frame.after {
+                    push_comments(&mut inserts, line_after(input, end), indent, &lines);
+                }
+                node_done(&mut stack, end, None);
+                continue;
+            }
+            Event::SequenceStart(ref sequence) => (sequence.flow, false),
+            Event::MappingStart(ref mapping) => (mapping.flow, true),
+            Event::Scalar(_) | Event::Alias(_) => (false, false),
+        };
+
+        let (path, in_key, anchor) = match stack.last() {
+            None => (OwnedPath::Root, false, start),
+            Some(parent) if parent.mapping && parent.len % 2 == 0 => (OwnedPath::Root, true, start),
+            Some(parent) if parent.mapping => {
+                let path = match &parent.key {
+                    Some(key) => parent.path.clone().push_key(key.as_str()),
+                    None => OwnedPath::Unknown {
+                        parent: Box::new(parent.path.clone()),
+                        borrowed: Default::default(),
+                    },
+                };
+                (path, parent.in_key, parent.key_start)
+            }
+            Some(parent) => {
+                let path = parent.path.clone().push_index(parent.len);
+                (path, parent.in_key, dash_before(input, start))
+            }
+        };
+
+        let mut after = None;
+        if !in_key {
+            let Comments {
+                before,
+                after: after_lines,
+            } = comments(*path.as_path());
+            let line_start = line_start(input, anchor);
+            let indent = anchor - line_start;
+            push_comments(&mut inserts, line_start, indent, &before);
+            if !after_lines.is_empty() {
+                after = Some((after_lines, indent));
+            }
+        }
+
+        match event {
+            Event::Scalar(scalar) => {
+                let end = start + scalar.repr.map_or(0, <[u8]>::len);
+                if let Some((lines, indent)) = after {
+                    push_comments(&mut inserts, line_after(input, end), indent, &lines);
+                }
+                let key = String::from_utf8_lossy(&scalar.value).into_owned();
+                node_done(&mut stack, end, Some((key, start)));
+            }
+            Event::Alias(_) => {
+                if let Some((lines, indent)) = after {
+                    push_comments(&mut inserts, line_after(input, start), indent, &lines);
+                }
+                node_done(&mut stack, start, None);
+            }
+            _ => {
+                let is_key = matches!(stack.last(), Some(p) if p.mapping && p.len % 2 == 0);
+                stack.push(Frame {
+                    path,
+                    mapping,
+                    flow,
+                    len: 0,
+                    key: None,
+                    key_start: start,
+                    in_key: in_key || is_key,
+                    end: start,
+                    after,
+                });
+            }
+        }
+    }
+
+    if inserts.is_empty() {
+        return Ok(yaml.to_owned());
+    }
+
+    // Stable, so that comments at the same offset stay in the order in which
+    // the nodes they belong to were visited.
+    inserts.sort_by_key(|(offset, _)| *offset);
+    let mut out =
+        String::with_capacity(yaml.len() + inserts.iter().map(|(_, s)| s.len()).sum::<usize>());
+    let mut copied = 0;
+    for (offset, text) in &inserts {
+        out.push_str(&yaml[copied..*offset]);
+        if *offset == yaml.len() && !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(text);
+        copied = *offset;
+    }
+    out.push_str(&yaml[copied..]);
+    Ok(out)
+}
+
+/// Records that a child of the innermost collection ended at `end`. `key` is
+/// the text and offset of the child if it is a scalar.
+fn node_done(stack: &mut [Frame], end: usize, key: Option<(String, usize)>) {
+    if let Some(parent) = stack.last_mut() {
+        if parent.mapping && parent.len % 2 == 0 {
+            match key {
+                Some((key, start)) => {
+                    parent.key = Some(key);
+                    parent.key_start = start;
+                }
+                None => parent.key = None,
+            }
+        }
+        parent.len += 1;
+        parent.end = end;
+    }
+}
+
+fn push_comments(
+    inserts: &mut Vec<(usize, String)>,
+    offset: usize,
+    indent: usize,
+    lines: &[String],
+) {
+    let mut text = String::new();
+    for line in lines.iter().flat_map(|line| line.split('\n')) {
+        text.extend(std::iter::repeat_n(' ', indent));
+        text.push('#');
+        if !line.is_empty() {
+            text.push(' ');
+            text.push_str(line);
+        }
+        text.push('\n');
+    }
+    if !text.is_empty() {
+        inserts.push((offset, text));
+    }
+}
+
+/// The offset of the start of the line containing `offset`.
+fn line_start(input: &[u8], offset: usize) -> usize {
+    input[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// The offset of the start of the line after the one containing the byte
+/// before `end`, which is the last byte of a node.
+fn line_after(input: &[u8], end: usize) -> usize {
+    let last = end.saturating_sub(1);
+    input[last..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(input.len(), |newline| last + newline + 1)
+}
+
+/// The offset of the `-` introducing the sequence element at `start`, or
+/// `start` itself for a flow sequence element.
+fn dash_before(input: &[u8], start: usize) -> usize {
+    let before = input[..start].iter().rposition(|&b| b != b' ');
+    match before {
+        Some(dash) if input[dash] == b'-' => dash,
+        _ => start,
+    }
+}
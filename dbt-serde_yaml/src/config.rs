@@ -0,0 +1,685 @@
+//! Deserialization settings gathered into a single value.
+
+use crate::de::{ParseOptions, ScalarSchema};
+use crate::dependency::DependencyEdge;
+use crate::mapping::DuplicateKey;
+use crate::value::{tagged, NormalizedVariant, TaggedValue, TransformPolicy, Value};
+use crate::warnings::Warning;
+use crate::Span;
+use std::cell::RefCell;
+use std::error::Error as StdError;
+use std::fmt;
+use std::mem;
+#[cfg(feature = "filename")]
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// The settings used when deserializing YAML.
+///
+/// A `Config` can be passed explicitly to entry points such as
+/// [`from_str_with_config`](crate::from_str_with_config) and
+/// [`Deserializer::with_config`](crate::Deserializer::with_config). Entry
+/// points that don't take one use the thread's current config, which is the
+/// default one unless set with [Config::enter].
+///
+/// While deserializing, the config in use is also the thread's current one,
+/// so that e.g. a [`Value`](crate::Value) nested in a typed struct honors its
+/// duplicate key policy.
+///
+/// ```
+/// use dbt_serde_yaml::mapping::DuplicateKey;
+/// use dbt_serde_yaml::{Config, ScalarSchema, Value};
+///
+/// let mut config = Config::new();
+/// config.scalar_schema = ScalarSchema::Core;
+/// config.duplicate_keys = DuplicateKey::Overwrite;
+///
+/// let value: Value = dbt_serde_yaml::from_str_with_config("a: 01\na: 02\n", &config).unwrap();
+/// assert_eq!(value["a"], 2);
+///
+/// let _scope = config.enter();
+/// let value: Value = dbt_serde_yaml::from_str("a: 01\na: 03\n").unwrap();
+/// assert_eq!(value["a"], 3);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Config {
+    /// Limits and other options for parsing the input.
+    pub parse_options: ParseOptions,
+    /// How plain scalars are resolved to nulls, booleans, integers and floats.
+    pub scalar_schema: ScalarSchema,
+    /// What to do with duplicate keys when deserializing a
+    /// [`Value`](crate::Value) or [`Mapping`](crate::Mapping) through serde,
+    /// rather than with an explicit callback. The default is
+    /// [`DuplicateKey::Error`].
+    pub duplicate_keys: DuplicateKey,
+    /// Whether a value that the type being deserialized reads and throws
+    /// away, as serde does for a mapping entry it has no use for, is reported
+    /// to the unused key callback of
+    /// [`Value::into_typed`](crate::Value::into_typed) and friends. Entries
+    /// with keys that a struct doesn't have are always reported; this adds
+    /// the values of known keys and other mappings that are deserialized as
+    /// [`IgnoredAny`](serde::de::IgnoredAny). Only values under a string key
+    /// are reported. The default is `false`.
+    pub report_ignored_values: bool,
+    /// Handlers that replace nodes with application-defined tags while
+    /// parsing.
+    pub tag_handlers: TagHandlers,
+    /// Which struct fields are flattened for their name when converting to
+    /// and from a [`Value`](crate::Value).
+    pub flatten_keys: FlattenKeys,
+    /// Decides which of several flatten fields of a struct gets an entry; see
+    /// [FlattenRouter]. The default is `None`, which leaves every entry to
+    /// the fields in declaration order.
+    pub flatten_router: Option<FlattenRouter>,
+    /// Whether deserializing a [`Spanned`](crate::Spanned) with a
+    /// deserializer that does not report source locations, such as
+    /// `serde_json`, is an error rather than leaving its span invalid; see
+    /// [`Spanned::is_foreign`](crate::Spanned::is_foreign). The default is
+    /// `false`.
+    pub strict_spans: bool,
+    /// Whether an enum variant name read from a [`Value`](crate::Value) may
+    /// match a variant only after normalizing its spelling; see
+    /// [`with_lenient_variants`](crate::value::with_lenient_variants). The
+    /// default is `false`.
+    pub lenient_variants: bool,
+    /// Which fields the field transformers of
+    /// [`Value::into_typed`](crate::Value::into_typed) and friends are
+    /// applied to. The default is [`TransformPolicy::RespectVerbatim`].
+    pub transform_policy: TransformPolicy,
+    /// The size in bytes over which the raw values captured by a failed
+    /// [`ShouldBe`](crate::ShouldBe) are spilled to disk; see
+    /// [`spill_raw_values`](crate::spill_raw_values). `None` leaves the
+    /// size set by an enclosing scope in place.
+    #[cfg(feature = "spill")]
+    pub spill_raw_values_over: Option<usize>,
+    /// Where warnings are collected; see
+    /// [`collect_warnings`](crate::collect_warnings). `None` leaves the sink
+    /// of an enclosing scope in place.
+    pub warnings: Option<Sink<Warning>>,
+    /// Where dependencies are collected; see
+    /// [`collect_dependencies`](crate::collect_dependencies). `None` leaves
+    /// the sink of an enclosing scope in place.
+    pub dependencies: Option<Sink<DependencyEdge>>,
+    /// Where the variant names matched by [`Config::lenient_variants`] only
+    /// after normalization are collected. `None` leaves the sink of an
+    /// enclosing scope in place.
+    pub normalized_variants: Option<Sink<NormalizedVariant>>,
+    /// Where the inner type name and span of every deserialized
+    /// [`Spanned`](crate::Spanned) value are collected, for
+    /// [`assert_span_support`](crate::assert_span_support).
+    pub(crate) spans: Option<Sink<(&'static str, Span)>>,
+    /// The filename recorded in captured spans. `None` leaves the filename
+    /// set by an enclosing scope in place.
+    #[cfg(feature = "filename")]
+    pub filename: Option<Arc<PathBuf>>,
+}
+
+impl Config {
+    /// Creates a config with the default settings.
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Returns the thread's current config.
+    ///
+    /// Its filename is the one currently used for span capture, whether it
+    /// was set through a config or with
+    /// [`with_filename`](crate::with_filename).
+    pub fn current() -> Self {
+        Config::clone(&current())
+    }
+
+    /// Makes this the thread's current config until the returned guard is
+    /// dropped, when the previous one is restored.
+    ///
+    /// This is a migration path for code that cannot yet pass a config to
+    /// every entry point it calls. Scopes may be nested.
+    pub fn enter(&self) -> ConfigScope {
+        enter(&Rc::new(self.clone()))
+    }
+}
+
+/// Returns the thread's current config, like [Config::current], but shared
+/// rather than copied.
+pub(crate) fn current() -> Rc<Config> {
+    #[allow(unused_mut)]
+    let mut config = CURRENT.with(|current| current.borrow().clone().unwrap_or_default());
+    #[cfg(feature = "filename")]
+    {
+        let filename = crate::spanned::get_filename();
+        if config.filename != filename {
+            Rc::make_mut(&mut config).filename = filename;
+        }
+    }
+    config
+}
+
+/// Makes `config` the thread's current config, like [Config::enter], sharing
+/// it unless it leaves settings to the enclosing scope.
+pub(crate) fn enter(config: &Rc<Config>) -> ConfigScope {
+    #[cfg(feature = "filename")]
+    let filename = config.filename.as_ref().map(|filename| {
+        let outer = crate::spanned::get_filename();
+        crate::spanned::set_filename(Arc::clone(filename));
+        outer
+    });
+    let mut config = Rc::clone(config);
+    let outer = CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        if let Some(outer) = current.as_ref() {
+            inherit(&mut config, outer);
+        }
+        current.replace(config)
+    });
+    ConfigScope {
+        outer,
+        #[cfg(feature = "filename")]
+        filename,
+    }
+}
+
+/// Takes the settings `config` leaves to an enclosing scope from `outer`,
+/// copying `config` only if there are any.
+fn inherit(config: &mut Rc<Config>, outer: &Config) {
+    macro_rules! inherit {
+        ($($field:ident),*) => {$(
+            if config.$field.is_none() && outer.$field.is_some() {
+                Rc::make_mut(config).$field.clone_from(&outer.$field);
+            }
+        )*};
+    }
+    #[cfg(feature = "spill")]
+    inherit!(spill_raw_values_over);
+    inherit!(warnings, dependencies, normalized_variants, spans);
+}
+
+/// A list that collects reports made while deserializing, such as the
+/// warnings of [`Config::warnings`].
+///
+/// Clones share the same list, so that a sink can be put in a [Config] and
+/// emptied once deserialization is done.
+///
+/// ```
+/// use dbt_serde_yaml::{Config, Sink, Value};
+///
+/// let warnings = Sink::new();
+/// let mut config = Config::new();
+/// config.warnings = Some(warnings.clone());
+///
+/// let _value: Value = dbt_serde_yaml::from_str_with_config("enabled: off\n", &config).unwrap();
+/// let _value: Value = dbt_serde_yaml::from_str_with_config("debug: yes\n", &config).unwrap();
+/// assert_eq!(warnings.take().len(), 2);
+/// assert!(warnings.is_empty());
+/// ```
+pub struct Sink<T>(Arc<Mutex<Vec<T>>>);
+
+impl<T> Sink<T> {
+    /// Creates an empty sink.
+    pub fn new() -> Self {
+        Sink(Arc::default())
+    }
+
+    /// Returns the number of reports in the sink.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if the sink has no reports.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes and returns the reports in the sink, in the order they were
+    /// made.
+    pub fn take(&self) -> Vec<T> {
+        mem::take(&mut *self.lock())
+    }
+
+    pub(crate) fn push(&self, report: T) {
+        self.lock().push(report);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<T>> {
+        // A panic while pushing leaves the list intact.
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<T> Clone for Sink<T> {
+    fn clone(&self) -> Self {
+        Sink(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Default for Sink<T> {
+    fn default() -> Self {
+        Sink::new()
+    }
+}
+
+impl<T> fmt::Debug for Sink<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Sink")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Reports held back from the sinks of the thread's config; see
+/// [hold_reports].
+pub(crate) struct HeldReports {
+    outer: Rc<Config>,
+    held: Rc<Config>,
+    _scope: ConfigScope,
+}
+
+/// Holds back the reports made to the sinks of the thread's current config
+/// until [HeldReports::keep] is called, so that they are dropped otherwise.
+/// Returns `None` if the config has no sinks.
+pub(crate) fn hold_reports() -> Option<HeldReports> {
+    let outer = current();
+    let mut held = Rc::clone(&outer);
+    macro_rules! hold {
+        ($($field:ident),*) => {$(
+            if outer.$field.is_some() {
+                Rc::make_mut(&mut held).$field = Some(Sink::new());
+            }
+        )*};
+    }
+    hold!(warnings, dependencies, normalized_variants, spans);
+    if Rc::ptr_eq(&outer, &held) {
+        return None;
+    }
+    let scope = enter(&held);
+    Some(HeldReports {
+        outer,
+        held,
+        _scope: scope,
+    })
+}
+
+impl HeldReports {
+    /// Passes the reports held back on to the sinks they were made to.
+    pub(crate) fn keep(self) {
+        macro_rules! keep {
+            ($($field:ident),*) => {$(
+                if let (Some(outer), Some(held)) = (&self.outer.$field, &self.held.$field) {
+                    let reports = held.take();
+                    outer.lock().extend(reports);
+                }
+            )*};
+        }
+        keep!(warnings, dependencies, normalized_variants, spans);
+    }
+}
+
+/// Runs `f` with the thread's current config changed by `change`.
+pub(crate) fn with_changed<R>(change: impl FnOnce(&mut Config), f: impl FnOnce() -> R) -> R {
+    let _scope = enter_changed(change);
+    f()
+}
+
+/// Makes the thread's current config changed by `change` current until the
+/// returned guard is dropped.
+pub(crate) fn enter_changed(change: impl FnOnce(&mut Config)) -> ConfigScope {
+    let mut config = current();
+    change(Rc::make_mut(&mut config));
+    enter(&config)
+}
+
+/// Which struct fields are flattened for their name, as if marked
+/// `#[serde(flatten)]`, when serializing to and deserializing from a
+/// [Value].
+///
+/// A flattened field takes the entries of the mapping that no other field
+/// reads, and is serialized by adding its own entries to those of the
+/// struct. Unlike `#[serde(flatten)]`, this works with the field transformers
+/// and unused key callbacks of [`Value::into_typed`](crate::Value::into_typed)
+/// and friends. Mapping keys of the input that are marked like field names
+/// are also passed on to the flattened fields.
+///
+/// The default is [`FlattenKeys::Dunder`] with the `flatten_dunder` feature,
+/// and [`FlattenKeys::None`] without it. Since features are unified across a
+/// build, a library that gets the feature enabled by another one can opt out
+/// with [`FlattenKeys::None`]. The `JsonSchema` derive only knows of the
+/// `flatten_dunder` feature, which it follows at compile time.
+///
+/// ```
+/// use dbt_serde_yaml::{Config, FlattenKeys, Value};
+/// use serde::Deserialize;
+/// use std::collections::BTreeMap;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: String,
+///     extra_fields: BTreeMap<String, Value>,
+/// }
+///
+/// let mut config = Config::new();
+/// config.flatten_keys = FlattenKeys::Affixed {
+///     prefix: "extra_",
+///     suffix: "",
+/// };
+/// let _scope = config.enter();
+///
+/// let value: Value = dbt_serde_yaml::from_str("name: orders\nowner: data\n").unwrap();
+/// let model: Model = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+/// assert_eq!(model.extra_fields["owner"], "data");
+/// assert!(dbt_serde_yaml::is_flatten_key("extra_fields"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum FlattenKeys {
+    /// No field is flattened for its name.
+    None,
+    /// Fields whose name is wrapped in double underscores, like `__rest__`.
+    Dunder,
+    /// Fields whose name starts with `prefix` and ends with `suffix`, with at
+    /// least one character in between.
+    Affixed {
+        /// The start of the name of a flattened field.
+        prefix: &'static str,
+        /// The end of the name of a flattened field.
+        suffix: &'static str,
+    },
+    /// Fields for whose name the function returns true.
+    Custom(fn(&str) -> bool),
+}
+
+impl FlattenKeys {
+    /// Returns true if a field named `name` is flattened.
+    pub fn matches(&self, name: &str) -> bool {
+        match *self {
+            FlattenKeys::None => false,
+            FlattenKeys::Dunder => FlattenKeys::Affixed {
+                prefix: "__",
+                suffix: "__",
+            }
+            .matches(name),
+            FlattenKeys::Affixed { prefix, suffix } => {
+                name.len() > prefix.len() + suffix.len()
+                    && name.starts_with(prefix)
+                    && name.ends_with(suffix)
+            }
+            FlattenKeys::Custom(matches) => matches(name),
+        }
+    }
+}
+
+impl Default for FlattenKeys {
+    fn default() -> Self {
+        if cfg!(feature = "flatten_dunder") {
+            FlattenKeys::Dunder
+        } else {
+            FlattenKeys::None
+        }
+    }
+}
+
+/// A function that picks the flatten field of a struct that gets a mapping
+/// entry, given the key and value of the entry and the names of the flatten
+/// fields in declaration order; see [`Config::flatten_router`].
+///
+/// When a struct has several flatten fields, the entries that none of its
+/// other fields read are handed to the flatten fields in declaration order.
+/// Each one takes the entries it reads: a struct takes those of its own
+/// fields, while a map takes all of them. The entries left over go on to the
+/// next flatten field, and the last one gets all that remain. A flatten
+/// field therefore cannot tell which entries a later one would read, and a
+/// map declared before other flatten fields leaves nothing to them.
+///
+/// A router resolves this by pinning entries to a field ahead of time. It is
+/// called once for each leftover entry before the first flatten field is
+/// deserialized. If it returns the index of a flatten field, the entry
+/// skips the fields before that one, which then gets it after the entries
+/// they left over. If it returns `None` or an index past the last field, the
+/// entry takes the usual route. A router is not called for a struct with a
+/// single flatten field.
+///
+/// ```
+/// use dbt_serde_yaml::{Config, FlattenKeys, Value};
+/// use serde::Deserialize;
+/// use std::collections::BTreeMap;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: String,
+///     __nested__: BTreeMap<String, BTreeMap<String, String>>,
+///     __rest__: BTreeMap<String, String>,
+/// }
+///
+/// // Send mappings to `__nested__` and everything else to `__rest__`.
+/// fn route(_key: &Value, value: &Value, fields: &[&'static str]) -> Option<usize> {
+///     let field = if value.is_mapping() { "__nested__" } else { "__rest__" };
+///     fields.iter().position(|name| *name == field)
+/// }
+///
+/// let mut config = Config::new();
+/// config.flatten_keys = FlattenKeys::Dunder;
+/// config.flatten_router = Some(route);
+/// let _scope = config.enter();
+///
+/// let yaml = "name: orders\nowner: data\nmeta: {team: core}\n";
+/// let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+/// let model: Model = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+/// assert_eq!(model.__nested__["meta"]["team"], "core");
+/// assert_eq!(model.__rest__["owner"], "data");
+/// ```
+pub type FlattenRouter = fn(&Value, &Value, &[&'static str]) -> Option<usize>;
+
+/// A function that replaces a tagged node with a [Value]; see [TagHandlers].
+pub type TagHandler =
+    Arc<dyn Fn(&TaggedValue) -> Result<Value, Box<dyn StdError + Send + Sync>> + Send + Sync>;
+
+/// Handlers for application-defined tags such as `!env_var` or `!ref`.
+///
+/// While parsing, a node whose tag has a handler is read as a
+/// [TaggedValue] and replaced by the value the handler returns, which is then
+/// deserialized into the target type in place of the node. An error returned
+/// by a handler fails deserialization, located at the tagged node. The
+/// replacement takes the span of the tagged node if it has none of its own.
+///
+/// Tags are matched with or without their leading `!`. Only nodes that are
+/// read through serde are replaced, not the values of a tag that serde takes
+/// as an enum variant once the variant has been chosen.
+///
+/// ```
+/// use dbt_serde_yaml::{Config, Value};
+/// use std::collections::BTreeMap;
+///
+/// let mut config = Config::new();
+/// config.tag_handlers.insert("!env_var", |tagged| {
+///     match tagged.value.as_str() {
+///         Some("TARGET") => Ok(Value::from("prod")),
+///         Some(name) => Err(format!("unknown variable {}", name).into()),
+///         None => Err("expected a variable name".into()),
+///     }
+/// });
+///
+/// let yaml = "target: !env_var TARGET\n";
+/// let map: BTreeMap<String, String> = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+/// assert_eq!(map["target"], "prod");
+///
+/// let yaml = "target: !env_var OTHER\n";
+/// let err = dbt_serde_yaml::from_str_with_config::<BTreeMap<String, String>>(yaml, &config)
+///     .unwrap_err();
+/// assert_eq!(err.to_string(), "target: unknown variable OTHER at line 1 column 9");
+/// ```
+#[derive(Clone, Default)]
+pub struct TagHandlers {
+    handlers: Vec<(String, TagHandler)>,
+}
+
+impl TagHandlers {
+    /// Creates an empty set of handlers.
+    pub fn new() -> Self {
+        TagHandlers::default()
+    }
+
+    /// Registers `handler` for nodes tagged `tag`, replacing any handler
+    /// registered for it before.
+    pub fn insert<F>(&mut self, tag: impl AsRef<str>, handler: F)
+    where
+        F: Fn(&TaggedValue) -> Result<Value, Box<dyn StdError + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let tag = tagged::nobang(tag.as_ref()).to_owned();
+        self.remove(&tag);
+        self.handlers.push((tag, Arc::new(handler)));
+    }
+
+    /// Removes the handler for `tag`, returning whether there was one.
+    pub fn remove(&mut self, tag: impl AsRef<str>) -> bool {
+        let tag = tagged::nobang(tag.as_ref());
+        let len = self.handlers.len();
+        self.handlers.retain(|(t, _)| t != tag);
+        self.handlers.len() != len
+    }
+
+    /// Returns true if no handler is registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Returns the handler for `tag`, if any.
+    pub fn get(&self, tag: impl AsRef<str>) -> Option<&TagHandler> {
+        let tag = tagged::nobang(tag.as_ref());
+        self.handlers
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, handler)| handler)
+    }
+}
+
+impl fmt::Debug for TagHandlers {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_set()
+            .entries(self.handlers.iter().map(|(tag, _)| tag))
+            .finish()
+    }
+}
+
+/// A guard that restores the previous thread config when dropped; see
+/// [Config::enter].
+#[must_use = "the config is only in effect until the scope is dropped"]
+pub struct ConfigScope {
+    outer: Option<Rc<Config>>,
+    #[cfg(feature = "filename")]
+    filename: Option<Option<Arc<PathBuf>>>,
+}
+
+impl Drop for ConfigScope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.outer.take());
+        #[cfg(feature = "filename")]
+        if let Some(filename) = self.filename.take() {
+            crate::spanned::restore_filename(filename);
+        }
+    }
+}
+
+/// The duplicate key policy of the thread's current config.
+pub(crate) fn duplicate_keys() -> DuplicateKey {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map_or(DuplicateKey::Error, |config| config.duplicate_keys)
+    })
+}
+
+/// Whether the thread's current config sorts the keys of mappings once they
+/// are read; see [`ParseOptions::sort_keys`].
+pub(crate) fn sort_keys() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|config| config.parse_options.sort_keys)
+    })
+}
+
+/// The handler for `tag` in the thread's current config, if any.
+pub(crate) fn tag_handler(tag: &str) -> Option<TagHandler> {
+    CURRENT.with(|current| {
+        let current = current.borrow();
+        let handlers = &current.as_ref()?.tag_handlers;
+        handlers.get(tag).cloned()
+    })
+}
+
+/// Returns true if a struct field named `name` is flattened under the
+/// thread's current config; see [FlattenKeys].
+pub fn is_flatten_key(name: &str) -> bool {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(config) => config.flatten_keys.matches(name),
+        None => FlattenKeys::default().matches(name),
+    })
+}
+
+/// The flatten router of the thread's current config, if any.
+pub(crate) fn flatten_router() -> Option<FlattenRouter> {
+    CURRENT.with(|current| current.borrow().as_ref()?.flatten_router)
+}
+
+/// Whether the thread's current config requires spans to be captured; see
+/// [`Config::strict_spans`].
+pub(crate) fn strict_spans() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|config| config.strict_spans)
+    })
+}
+
+/// Whether the thread's current config matches enum variants leniently.
+pub(crate) fn lenient_variants() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|config| config.lenient_variants)
+    })
+}
+
+/// The transform policy of the thread's current config.
+pub(crate) fn transform_policy() -> TransformPolicy {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map_or_else(TransformPolicy::default, |config| config.transform_policy)
+    })
+}
+
+/// The size over which the thread's current config spills raw values.
+#[cfg(feature = "spill")]
+pub(crate) fn spill_raw_values_over() -> Option<usize> {
+    CURRENT.with(|current| current.borrow().as_ref()?.spill_raw_values_over)
+}
+
+/// The sink of the thread's current config picked by `field`, if it has one.
+pub(crate) fn sink<T>(field: fn(&Config) -> &Option<Sink<T>>) -> Option<Sink<T>> {
+    CURRENT.with(|current| field(current.borrow().as_ref()?).clone())
+}
+
+/// Whether the thread's current config reports ignored values.
+pub(crate) fn report_ignored_values() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|config| config.report_ignored_values)
+    })
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<Config>>> = const { RefCell::new(None) };
+}
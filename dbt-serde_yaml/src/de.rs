@@ -1,20 +1,30 @@
+use crate::binary;
+use crate::callback;
+use crate::config::{self, Config};
 use crate::error::{self, Error, ErrorImpl};
 use crate::libyaml::error::Mark;
 use crate::libyaml::parser::{MappingStart, Scalar, ScalarStyle, SequenceStart};
 use crate::libyaml::tag::Tag;
 use crate::loader::{Document, Loader};
-use crate::path::Path;
-use crate::spanned;
-use serde::de::value::StrDeserializer;
+use crate::mapping::{Accept, Mapping};
+use crate::path::{OwnedPath, Path, PathComponent};
+use crate::spanned::{self, Marker, Span};
+use crate::trace;
+use crate::value::{self, DuplicateKeyCallback, Value, ValueDeserializer, ValueVisitor};
+use crate::warnings::{self, WarningKind};
+use serde::de::value::{SeqDeserializer, StrDeserializer};
 use serde::de::{
     self, Deserialize, DeserializeOwned, DeserializeSeed, Expected, IgnoredAny, Unexpected, Visitor,
 };
-use std::fmt;
+use std::fmt::{self, Debug};
 use std::io;
+use std::marker::PhantomData;
 use std::mem;
 use std::num::ParseIntError;
+use std::rc::Rc;
 use std::str;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -58,6 +68,350 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 /// ```
 pub struct Deserializer<'de> {
     progress: Progress<'de>,
+    config: Rc<Config>,
+    #[cfg(feature = "filename")]
+    filename: Option<Arc<std::path::PathBuf>>,
+}
+
+/// How plain scalars without an explicit tag are resolved to nulls, booleans,
+/// integers and floats.
+///
+/// # Examples
+///
+/// ```
+/// use dbt_serde_yaml::{Deserializer, ScalarSchema, Value};
+/// use serde::Deserialize;
+///
+/// let value = Value::deserialize(Deserializer::from_str("[01, 0b11]")).unwrap();
+/// assert_eq!(value[0], Value::from("01"));
+/// assert_eq!(value[1], Value::from(3));
+///
+/// let de = Deserializer::from_str("[01, 0b11]").with_scalar_schema(ScalarSchema::Core);
+/// let value = Value::deserialize(de).unwrap();
+/// assert_eq!(value[0], Value::from(1));
+/// assert_eq!(value[1], Value::from("0b11"));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScalarSchema {
+    /// The resolution this crate has always used. It accepts everything the
+    /// YAML 1.2 core schema does, except that integers with leading zeros like
+    /// `01` stay strings. It additionally accepts binary integers like `0b11`
+    /// and signed hexadecimal and octal integers like `-0x1F`.
+    #[default]
+    Lenient,
+    /// Strict [YAML 1.2 core schema] resolution, matching other YAML 1.2
+    /// tooling: `01` is the integer 1, and only unsigned `0x` hexadecimal and
+    /// `0o` octal integers are recognized besides decimal ones.
+    ///
+    /// [YAML 1.2 core schema]: https://yaml.org/spec/1.2.2/#103-core-schema
+    Core,
+}
+
+/// Knobs for hardening the parsing of untrusted YAML.
+///
+/// # Examples
+///
+/// ```
+/// use dbt_serde_yaml::mapping::DuplicateKey;
+/// use dbt_serde_yaml::{DuplicateAnchor, ParseOptions, TabIndentation, Value};
+///
+/// let options = ParseOptions::new()
+///     .tab_indentation(TabIndentation::Reject)
+///     .max_alias_expansions(Some(1_000))
+///     .duplicate_anchors(DuplicateAnchor::Error);
+///
+/// let yaml = "a: &x 1\nb: &x 2\n";
+/// let err = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap_err();
+/// assert_eq!(err.to_string(), "duplicate anchor `x` at line 2 column 4");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub(crate) tab_indentation: TabIndentation,
+    pub(crate) max_alias_expansions: Option<usize>,
+    pub(crate) max_nodes: Option<usize>,
+    pub(crate) max_scalar_bytes: Option<usize>,
+    pub(crate) time_limit: Option<Duration>,
+    pub(crate) duplicate_anchors: DuplicateAnchor,
+    pub(crate) iterative_values: bool,
+    #[cfg(feature = "large_scalars")]
+    pub(crate) large_scalars_over: Option<usize>,
+    #[cfg(feature = "large_scalars")]
+    pub(crate) large_scalars: LargeScalars,
+    pub(crate) sort_keys: bool,
+    pub(crate) origin: Marker,
+}
+
+impl ParseOptions {
+    /// Creates options with the default settings: tabs are left to the YAML
+    /// parser, alias expansion is limited relative to the document size, the
+    /// size of documents and the time spent on them are not limited, anchors
+    /// may be redefined, values are built iteratively, mappings keep the
+    /// order of their keys, and locations are counted from the start of the
+    /// input.
+    pub fn new() -> Self {
+        ParseOptions {
+            tab_indentation: TabIndentation::Unchecked,
+            max_alias_expansions: None,
+            max_nodes: None,
+            max_scalar_bytes: None,
+            time_limit: None,
+            duplicate_anchors: DuplicateAnchor::Allow,
+            iterative_values: true,
+            #[cfg(feature = "large_scalars")]
+            large_scalars_over: None,
+            #[cfg(feature = "large_scalars")]
+            large_scalars: LargeScalars::Share,
+            sort_keys: false,
+            origin: Marker::start(),
+        }
+    }
+
+    /// Sets how tabs in the indentation of a line are treated. The default is
+    /// [`TabIndentation::Unchecked`].
+    pub fn tab_indentation(mut self, tab_indentation: TabIndentation) -> Self {
+        self.tab_indentation = tab_indentation;
+        self
+    }
+
+    /// Sets the maximum number of times aliases may be expanded while
+    /// deserializing a document, beyond which deserialization fails with a
+    /// "repetition limit exceeded" error. `None` (the default) allows 100
+    /// expansions per event in the document.
+    pub fn max_alias_expansions(mut self, max_alias_expansions: Option<usize>) -> Self {
+        self.max_alias_expansions = max_alias_expansions;
+        self
+    }
+
+    /// Sets the maximum number of nodes a document may expand to, counting the
+    /// nodes under an alias again every time the alias is used. Exceeding it
+    /// fails deserialization with a "node limit exceeded" error pointing at
+    /// the first node over the limit. `None` (the default) sets no limit.
+    ///
+    /// Unlike [`max_alias_expansions`](ParseOptions::max_alias_expansions),
+    /// this bounds the size of the resulting value, which is what grows
+    /// exponentially when anchors are nested ("billion laughs").
+    pub fn max_nodes(mut self, max_nodes: Option<usize>) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Sets the maximum total length in bytes of the scalars of a document,
+    /// after quotes and escapes are resolved and counting the scalars under an
+    /// alias again every time the alias is used. Exceeding it fails
+    /// deserialization with a "scalar size limit exceeded" error pointing at
+    /// the scalar that goes over. `None` (the default) sets no limit.
+    pub fn max_scalar_bytes(mut self, max_scalar_bytes: Option<usize>) -> Self {
+        self.max_scalar_bytes = max_scalar_bytes;
+        self
+    }
+
+    /// Sets how long parsing and deserializing each document may take, beyond
+    /// which deserialization fails with a "time limit exceeded" error pointing
+    /// at the event being processed when the time ran out. The clock starts
+    /// when the parser reaches the start of the document. `None` (the default)
+    /// sets no limit.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::{ParseOptions, Value};
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use std::time::Duration;
+    ///
+    /// let yaml = "[".repeat(10_000);
+    /// let options = ParseOptions::new().time_limit(Some(Duration::ZERO));
+    /// let err = Value::from_str_with(&yaml, options, |_, _, _| DuplicateKey::Error).unwrap_err();
+    /// assert_eq!(err.to_string(), "time limit exceeded at line 1 column 1");
+    /// ```
+    pub fn time_limit(mut self, time_limit: Option<Duration>) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Sets what happens when an anchor name is defined more than once in a
+    /// document. The default is [`DuplicateAnchor::Allow`].
+    pub fn duplicate_anchors(mut self, policy: DuplicateAnchor) -> Self {
+        self.duplicate_anchors = policy;
+        self
+    }
+
+    /// Sets whether a [Value] is built with an explicit stack of the
+    /// collections under construction rather than by recursing into them. The
+    /// default is `true`, so that documents nested thousands of levels deep
+    /// deserialize into a `Value` without overflowing the stack. When `false`,
+    /// a `Value` is subject to the same 128-level recursion limit as any other
+    /// type, beyond which deserialization fails with a "recursion limit
+    /// exceeded" error.
+    pub fn iterative_values(mut self, iterative_values: bool) -> Self {
+        self.iterative_values = iterative_values;
+        self
+    }
+
+    /// Sets the length in bytes beyond which string scalars in a [Value] are
+    /// large, and what is done with them. `None` (the default) treats every
+    /// string alike.
+    ///
+    /// This is for documents like dbt manifests whose bulk is in large SQL or
+    /// Jinja strings, most of which are never looked at. With
+    /// [`LargeScalars::Share`], a large string holds on to the buffer the
+    /// parser decoded it into rather than to a copy of it; with
+    /// [`LargeScalars::Elide`], it is left out of the value altogether. Only
+    /// strings that aren't mapping keys or tagged are large, and only in
+    /// values built [iteratively](ParseOptions::iterative_values); types other
+    /// than `Value` are not affected.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::{LargeScalars, ParseOptions, Value};
+    ///
+    /// let yaml = "name: orders\nsql: \"select *\\nfrom raw.orders\"\n";
+    /// let options = ParseOptions::new().large_scalars_over(Some(8), LargeScalars::Share);
+    /// let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+    /// assert_eq!(value["sql"], "select *\nfrom raw.orders");
+    /// ```
+    #[cfg(feature = "large_scalars")]
+    pub fn large_scalars_over(mut self, max_bytes: Option<usize>, large: LargeScalars) -> Self {
+        self.large_scalars_over = max_bytes;
+        self.large_scalars = large;
+        self
+    }
+
+    /// Sets whether each mapping read into a [Value] or [Mapping] is sorted by
+    /// key as with [Mapping::sort_keys] once all of its entries have been
+    /// read, so that iterating over it or serializing it gives the same order
+    /// whatever the order of the source. The default is `false`, which keeps
+    /// keys in the order they appear in the document.
+    ///
+    /// This is a single sort after parsing, not a different kind of mapping:
+    /// lookups by key are hashed either way, and entries inserted afterwards
+    /// are appended as usual. Mappings built other than by deserializing, e.g.
+    /// with [`to_value`](crate::to_value), are not sorted; use
+    /// [Mapping::sort_keys_recursive] for those.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::{ParseOptions, Value};
+    ///
+    /// let yaml = "b: 1\na: {z: 2, y: 3}\n";
+    /// let options = ParseOptions::new().sort_keys(true);
+    /// let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+    /// assert_eq!(value.to_string(), "{a: {y: 3, z: 2}, b: 1}");
+    /// ```
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets where the input starts in an enclosing text it was taken from,
+    /// such as a Jinja block or a fenced code block in a Markdown file. The
+    /// default is [Marker::start].
+    ///
+    /// The locations in spans and errors are then those in the enclosing
+    /// text: the line and index of `origin` are added to every location, and
+    /// so is its column to those on the first line of the input, which is
+    /// taken to start where `origin` is.
+    ///
+    /// Functions that look values up again in their source text by their
+    /// span, such as [`Value::scalar_info`], must then be given the
+    /// enclosing text.
+    pub fn origin(mut self, origin: Marker) -> Self {
+        self.origin = origin;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::new()
+    }
+}
+
+/// How tabs in the indentation of a line are treated.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TabIndentation {
+    /// Leave tabs to the YAML parser, which rejects them in block indentation
+    /// but tolerates them on the continuation lines of flow collections.
+    #[default]
+    Unchecked,
+    /// Reject any line with content whose indentation contains a tab.
+    Reject,
+    /// Treat each tab in the indentation of a line as a single space. This
+    /// also applies to lines inside block scalars, whose leading tabs become
+    /// spaces.
+    AsSpace,
+}
+
+/// What is done with the large strings of a [Value]; see
+/// [`ParseOptions::large_scalars_over`].
+#[cfg(feature = "large_scalars")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LargeScalars {
+    /// Share the buffer the parser decoded the string into, which saves
+    /// copying it.
+    #[default]
+    Share,
+    /// Leave the string out, keeping only its span. The string is
+    /// [elided](crate::value::YamlString::is_elided), and its contents can be
+    /// recovered from the source text with [Value::decode_scalar].
+    Elide,
+}
+
+/// What happens when an anchor name is defined more than once in a document.
+///
+/// # Examples
+///
+/// ```
+/// use dbt_serde_yaml::mapping::DuplicateKey;
+/// use dbt_serde_yaml::{DuplicateAnchor, Marker, ParseOptions, Value};
+/// use std::sync::Arc;
+///
+/// fn warn(name: &str, first: Marker, second: Marker) {
+///     eprintln!("anchor `{name}` defined at {first} is redefined at {second}");
+/// }
+///
+/// let yaml = "a: &x 1\nb: &x 2\nc: *x\n";
+/// let options = ParseOptions::new().duplicate_anchors(DuplicateAnchor::Warn(Arc::new(warn)));
+/// let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+/// assert_eq!(value["c"], 2);
+/// ```
+#[derive(Clone, Default)]
+pub enum DuplicateAnchor {
+    /// Let the later definition shadow the earlier one, so that subsequent
+    /// aliases refer to the most recent definition, as YAML specifies.
+    #[default]
+    Allow,
+    /// Like [`DuplicateAnchor::Allow`], but first call the function with the
+    /// anchor name and the locations of the earlier and the later definition.
+    Warn(AnchorWarning),
+    /// Fail with a "duplicate anchor" error at the later definition.
+    Error,
+}
+
+/// A function told of a redefined anchor by [`DuplicateAnchor::Warn`].
+pub type AnchorWarning = Arc<dyn Fn(&str, Marker, Marker) + Send + Sync>;
+
+/// Policies are equal if they are the same variant, and for
+/// [`DuplicateAnchor::Warn`], the same function, as told by
+/// [`Arc::ptr_eq`].
+impl PartialEq for DuplicateAnchor {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DuplicateAnchor::Allow, DuplicateAnchor::Allow)
+            | (DuplicateAnchor::Error, DuplicateAnchor::Error) => true,
+            (DuplicateAnchor::Warn(a), DuplicateAnchor::Warn(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DuplicateAnchor {}
+
+impl Debug for DuplicateAnchor {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DuplicateAnchor::Allow => formatter.write_str("Allow"),
+            DuplicateAnchor::Warn(_) => formatter.write_str("Warn(..)"),
+            DuplicateAnchor::Error => formatter.write_str("Error"),
+        }
+    }
 }
 
 pub(crate) enum Progress<'de> {
@@ -73,17 +427,38 @@ impl<'de> Deserializer<'de> {
     /// Creates a YAML deserializer from a `&str`.
     pub fn from_str(s: &'de str) -> Self {
         let progress = Progress::Str(s);
-        Deserializer { progress }
+        Deserializer {
+            progress,
+            config: config::current(),
+            #[cfg(feature = "filename")]
+            filename: None,
+        }
+    }
+
+    /// Creates a YAML deserializer from a `&str`, parsed with the given
+    /// options.
+    pub fn from_str_with(s: &'de str, options: ParseOptions) -> Self {
+        Deserializer::from_str(s).with_parse_options(options)
     }
 
     /// Creates a YAML deserializer from a `&[u8]`.
     pub fn from_slice(v: &'de [u8]) -> Self {
         let progress = Progress::Slice(v);
-        Deserializer { progress }
+        Deserializer {
+            progress,
+            config: config::current(),
+            #[cfg(feature = "filename")]
+            filename: None,
+        }
     }
 
     /// Creates a YAML deserializer from an `io::Read`.
     ///
+    /// The input is read incrementally, as the parser gets to it, so only the
+    /// document being deserialized is held in memory rather than the whole
+    /// stream. Read errors and, with [`TabIndentation::Reject`], tabs in
+    /// indentation are reported when they are reached.
+    ///
     /// Reader-based deserializers do not support deserializing borrowed types
     /// like `&str`, since the `std::io::Read` trait has no non-copying methods
     /// -- everything it does involves copying bytes out of the data source.
@@ -92,15 +467,149 @@ impl<'de> Deserializer<'de> {
         R: io::Read + 'de,
     {
         let progress = Progress::Read(Box::new(rdr));
-        Deserializer { progress }
+        Deserializer {
+            progress,
+            config: config::current(),
+            #[cfg(feature = "filename")]
+            filename: None,
+        }
+    }
+
+    /// Sets how plain scalars are resolved to nulls, booleans, integers and
+    /// floats. The default is that of the thread's current [Config], which is
+    /// [`ScalarSchema::Lenient`] unless set otherwise.
+    ///
+    /// When deserializing multiple documents, the schema applies to all of
+    /// them.
+    pub fn with_scalar_schema(mut self, schema: ScalarSchema) -> Self {
+        Rc::make_mut(&mut self.config).scalar_schema = schema;
+        self
+    }
+
+    /// Sets the options used to parse the input. The default is that of the
+    /// thread's current [Config], which is [`ParseOptions::new`] unless set
+    /// otherwise.
+    ///
+    /// When deserializing multiple documents, the options apply to all of
+    /// them.
+    pub fn with_parse_options(mut self, options: ParseOptions) -> Self {
+        Rc::make_mut(&mut self.config).parse_options = options;
+        self
+    }
+
+    /// Sets all settings from `config`, in place of the thread's current
+    /// config which is used by default.
+    ///
+    /// When deserializing multiple documents, the config applies to all of
+    /// them. The config is only in effect while the deserializer runs; spans
+    /// captured by the caller afterwards, like that of a top-level
+    /// [`Value`](crate::Value), do not get its filename unless the config is
+    /// also [entered](Config::enter) around the call.
+    pub fn with_config(mut self, config: &Config) -> Self {
+        self.config = Rc::new(config.clone());
+        self
+    }
+
+    /// Registers `handler` for nodes tagged `tag`, in addition to the
+    /// [`TagHandlers`](crate::TagHandlers) of the config in effect; see there
+    /// for how the handler is used.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::{Deserializer, Value};
+    /// use serde::Deserialize;
+    ///
+    /// let de = Deserializer::from_str("schema: !ref staging\n")
+    ///     .with_tag_handler("ref", |tagged| {
+    ///         let name = tagged.value.as_str().unwrap_or_default();
+    ///         Ok(Value::from(format!("analytics_{}", name)))
+    ///     });
+    /// let value = Value::deserialize(de).unwrap();
+    /// assert_eq!(value["schema"], "analytics_staging");
+    /// ```
+    pub fn with_tag_handler<F>(mut self, tag: impl AsRef<str>, handler: F) -> Self
+    where
+        F: Fn(&value::TaggedValue) -> Result<Value, Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Rc::make_mut(&mut self.config)
+            .tag_handlers
+            .insert(tag, handler);
+        self
+    }
+
+    /// Records `filename` in every span captured while this deserializer
+    /// runs, and prefixes it to the message of any error it returns, like
+    /// [`from_str_named`].
+    ///
+    /// Unlike [`with_filename`](crate::with_filename), this does not rely on
+    /// a scope set up by the caller, so the filename cannot leak to or from
+    /// other deserialization running on the same thread, e.g. in a worker pool
+    /// or interleaved async tasks. As with [Deserializer::with_config], the
+    /// span of a [`Spanned`](crate::Spanned) or [`Value`] wrapping the whole
+    /// document is captured after the deserializer returns, so it does not
+    /// get the filename.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Deserializer;
+    /// use serde::Deserialize;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let de = Deserializer::from_str("a: b\n").with_filename("models.yml");
+    /// let err = BTreeMap::<String, u32>::deserialize(de).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "models.yml: a: invalid type: string \"b\", expected u32 at line 1 column 4",
+    /// );
+    /// ```
+    #[cfg(feature = "filename")]
+    pub fn with_filename(mut self, filename: impl Into<std::path::PathBuf>) -> Self {
+        let filename = Arc::new(filename.into());
+        Rc::make_mut(&mut self.config).filename = Some(Arc::clone(&filename));
+        self.filename = Some(filename);
+        self
+    }
+
+    /// Deserializes a [Value] with `callback` resolving its duplicate keys,
+    /// building it iteratively if the options allow.
+    pub(crate) fn deserialize_value(self, callback: DuplicateKeyCallback) -> Result<Value> {
+        self.de(|state| {
+            if state.document.options.iterative_values {
+                state.build_value(callback, Path::Root, false)
+            } else {
+                de::Deserializer::deserialize_any(
+                    state,
+                    ValueVisitor {
+                        callback,
+                        path: Path::Root,
+                    },
+                )
+            }
+        })
     }
 
     fn de<T>(
         self,
         f: impl for<'document> FnOnce(&mut DeserializerFromEvents<'de, 'document>) -> Result<T>,
+    ) -> Result<T> {
+        #[cfg(feature = "filename")]
+        if let Some(filename) = self.filename.clone() {
+            return self
+                .de_events(f)
+                .map_err(|err| error::in_file(err, filename));
+        }
+        self.de_events(f)
+    }
+
+    fn de_events<T>(
+        self,
+        f: impl for<'document> FnOnce(&mut DeserializerFromEvents<'de, 'document>) -> Result<T>,
     ) -> Result<T> {
         let mut pos = 0;
-        let mut jumpcount = 0;
+        let mut expansion = Expansion::default();
+        let _scope = config::enter(&self.config);
+        let schema = self.config.scalar_schema;
 
         match self.progress {
             Progress::Iterable(_) => return Err(error::new(ErrorImpl::MoreThanOneDocument)),
@@ -108,10 +617,12 @@ impl<'de> Deserializer<'de> {
                 let t = f(&mut DeserializerFromEvents {
                     document: &document,
                     pos: &mut pos,
-                    jumpcount: &mut jumpcount,
+                    expansion: &mut expansion,
                     path: Path::Root,
                     remaining_depth: 128,
                     current_enum: None,
+                    schema,
+                    pair: false,
                 })?;
                 if let Some(parse_error) = document.error {
                     return Err(error::shared(parse_error));
@@ -121,7 +632,11 @@ impl<'de> Deserializer<'de> {
             _ => {}
         }
 
-        let mut loader = Loader::new(self.progress)?;
+        let mut loader = Loader::new(
+            self.progress,
+            self.config.parse_options.clone(),
+            self.config.scalar_schema,
+        )?;
         let document = match loader.next_document() {
             Some(document) => document,
             None => return Err(error::new(ErrorImpl::EndOfStream)),
@@ -129,10 +644,12 @@ impl<'de> Deserializer<'de> {
         let t = f(&mut DeserializerFromEvents {
             document: &document,
             pos: &mut pos,
-            jumpcount: &mut jumpcount,
+            expansion: &mut expansion,
             path: Path::Root,
             remaining_depth: 128,
             current_enum: None,
+            schema,
+            pair: false,
         })?;
         if let Some(parse_error) = document.error {
             return Err(error::shared(parse_error));
@@ -154,12 +671,18 @@ impl Iterator for Deserializer<'_> {
                 let document = loader.next_document()?;
                 return Some(Deserializer {
                     progress: Progress::Document(document),
+                    config: self.config.clone(),
+                    #[cfg(feature = "filename")]
+                    filename: self.filename.clone(),
                 });
             }
             Progress::Document(_) => return None,
             Progress::Fail(err) => {
                 return Some(Deserializer {
                     progress: Progress::Fail(Arc::clone(err)),
+                    config: self.config.clone(),
+                    #[cfg(feature = "filename")]
+                    filename: self.filename.clone(),
                 });
             }
             _ => {}
@@ -167,7 +690,11 @@ impl Iterator for Deserializer<'_> {
 
         let dummy = Progress::Str("");
         let input = mem::replace(&mut self.progress, dummy);
-        match Loader::new(input) {
+        match Loader::new(
+            input,
+            self.config.parse_options.clone(),
+            self.config.scalar_schema,
+        ) {
             Ok(loader) => {
                 self.progress = Progress::Iterable(loader);
                 self.next()
@@ -177,6 +704,9 @@ impl Iterator for Deserializer<'_> {
                 self.progress = Progress::Fail(Arc::clone(&fail));
                 Some(Deserializer {
                     progress: Progress::Fail(fail),
+                    config: self.config.clone(),
+                    #[cfg(feature = "filename")]
+                    filename: self.filename.clone(),
                 })
             }
         }
@@ -433,10 +963,22 @@ pub(crate) enum Event<'de> {
 struct DeserializerFromEvents<'de, 'document> {
     document: &'document Document<'de>,
     pos: &'document mut usize,
-    jumpcount: &'document mut usize,
+    expansion: &'document mut Expansion,
     path: Path<'document>,
     remaining_depth: u8,
     current_enum: Option<CurrentEnum<'document>>,
+    schema: ScalarSchema,
+    /// Whether the node is an entry of a `!!omap` or `!!pairs`, which
+    /// deserializes into a 2-tuple.
+    pair: bool,
+}
+
+/// Running totals shared by a document and every alias expanded within it.
+#[derive(Copy, Clone, Default)]
+struct Expansion {
+    aliases: usize,
+    nodes: usize,
+    scalar_bytes: usize,
 }
 
 #[derive(Copy, Clone)]
@@ -450,6 +992,47 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         self.peek_event_mark().map(|(event, _mark)| event)
     }
 
+    /// Replaces the next node by the value its tag handler returns, if it is
+    /// tagged with one that has a handler in the current config and is not
+    /// already being read as an enum.
+    ///
+    /// Kept out of line so that the deserialize methods calling it don't grow
+    /// their stack frames, which nested documents recurse through.
+    #[inline(never)]
+    fn handle_tag(&mut self) -> Result<Option<(Value, Mark)>> {
+        if self.current_enum.is_some() {
+            return Ok(None);
+        }
+        let (event, mark) = self.peek_event_mark()?;
+        let tag = match event {
+            Event::Scalar(Scalar { tag, .. })
+            | Event::SequenceStart(SequenceStart { tag, .. })
+            | Event::MappingStart(MappingStart { tag, .. }) => parse_tag(tag),
+            _ => None,
+        };
+        let Some((tag, handler)) = tag.and_then(|tag| Some((tag, config::tag_handler(tag)?)))
+        else {
+            return Ok(None);
+        };
+
+        // Read the contents of the node without its tag.
+        self.current_enum = Some(CurrentEnum { name: None, tag });
+        let value = Value::deserialize(&mut *self)?;
+        let span = value.span().clone();
+        let tagged = value::TaggedValue {
+            tag: value::Tag::new(tag),
+            value,
+        };
+        let path = self.path;
+        let replacement = callback::guard(&path, &span, || handler(&tagged))?
+            .map_err(|err| error::callback_failed(err, span.clone(), &path))?;
+        if replacement.span().is_valid() {
+            Ok(Some((replacement, mark)))
+        } else {
+            Ok(Some((replacement.with_span(span), mark)))
+        }
+    }
+
     fn peek_event_mark(&self) -> Result<(&'document Event<'de>, Mark)> {
         match self.document.events.get(*self.pos) {
             Some((event, mark)) => Ok((event, *mark)),
@@ -465,36 +1048,88 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
     }
 
     fn next_event_mark(&mut self) -> Result<(&'document Event<'de>, Mark)> {
-        let res = self.peek_event_mark().map(|(event, mark)| {
-            *self.pos += 1;
-            self.current_enum = None;
-            (event, mark)
-        });
+        let (event, mark) = self.peek_event_mark()?;
+        if let Event::Scalar(_) | Event::SequenceStart(_) | Event::MappingStart(_) = event {
+            self.expansion.nodes += 1;
+            if let Some(max_nodes) = self.document.options.max_nodes {
+                if self.expansion.nodes > max_nodes {
+                    return Err(error::new(ErrorImpl::NodeLimitExceeded(mark.into())));
+                }
+            }
+        }
+        if let Event::Scalar(scalar) = event {
+            self.expansion.scalar_bytes += scalar.value.len();
+            if let Some(max_scalar_bytes) = self.document.options.max_scalar_bytes {
+                if self.expansion.scalar_bytes > max_scalar_bytes {
+                    return Err(error::new(ErrorImpl::ScalarBytesLimitExceeded(mark.into())));
+                }
+            }
+        }
+        if let Some(deadline) = self.document.deadline {
+            if Instant::now() >= deadline {
+                return Err(error::new(ErrorImpl::TimeLimitExceeded(mark.into())));
+            }
+        }
+        *self.pos += 1;
+        self.current_enum = None;
         if let Ok((_, mark)) = self.peek_event_mark() {
             spanned::set_marker(mark);
         }
-        res
+        Ok((event, mark))
     }
 
-    fn jump<'anchor>(
-        &'anchor mut self,
-        pos: &'anchor mut usize,
-    ) -> Result<DeserializerFromEvents<'de, 'anchor>> {
-        *self.jumpcount += 1;
-        if *self.jumpcount > self.document.events.len() * 100 {
-            return Err(error::new(ErrorImpl::RepetitionLimitExceeded));
+    /// Deserializes the node anchored by the alias just consumed, whose
+    /// target is `pos`, with `f`.
+    ///
+    /// Reading the anchored node moves the marker to where that node is
+    /// defined, so it is moved back to the event after the alias once `f` is
+    /// done, for the spans of whatever follows the alias.
+    fn expand_alias<T>(
+        &mut self,
+        mut pos: usize,
+        f: impl FnOnce(&mut DeserializerFromEvents<'de, '_>) -> Result<T>,
+    ) -> Result<T> {
+        self.resolve_alias(&mut pos)?;
+        let t = f(&mut DeserializerFromEvents {
+            document: self.document,
+            pos: &mut pos,
+            expansion: self.expansion,
+            path: Path::Alias { parent: &self.path },
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+            schema: self.schema,
+            pair: self.pair,
+        })?;
+        if let Ok((_, mark)) = self.peek_event_mark() {
+            spanned::set_marker(mark);
+        }
+        Ok(t)
+    }
+
+    /// Replaces the position of the alias just consumed with that of its
+    /// anchored node, counting the expansion against the limit.
+    fn resolve_alias(&mut self, pos: &mut usize) -> Result<()> {
+        self.expansion.aliases += 1;
+        let limit = match self.document.options.max_alias_expansions {
+            Some(limit) => limit,
+            None => self.document.events.len() * 100,
+        };
+        // The alias being expanded is the event just consumed.
+        let mark = self.document.events[*self.pos - 1].1;
+        if self.expansion.aliases > limit {
+            return Err(error::new(ErrorImpl::RepetitionLimitExceeded(mark.into())));
+        }
+        if self.expansion.aliases == limit / 2 + 1 {
+            let kind = WarningKind::AliasExpansions {
+                count: self.expansion.aliases,
+                limit,
+            };
+            warnings::warn(kind, Span::from(Marker::from(mark)));
         }
         match self.document.aliases.get(pos) {
             Some(found) => {
                 *pos = *found;
-                Ok(DeserializerFromEvents {
-                    document: self.document,
-                    pos,
-                    jumpcount: self.jumpcount,
-                    path: Path::Alias { parent: &self.path },
-                    remaining_depth: self.remaining_depth,
-                    current_enum: None,
-                })
+                Ok(())
             }
             None => panic!("unresolved alias: {}", *pos),
         }
@@ -536,7 +1171,260 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         }
     }
 
+    /// Builds the [Value] of the next node like a [ValueVisitor] would, but
+    /// keeping the collections under construction on an explicit stack rather
+    /// than recursing into them, so that the depth of the document is bounded
+    /// by the heap instead of the call stack.
+    ///
+    /// `path` is the path of the node as seen by `callback`, which resolves
+    /// duplicate mapping keys. `tagged_already` is as in `deserialize_any`.
+    fn build_value(
+        &mut self,
+        callback: DuplicateKeyCallback,
+        path: Path,
+        tagged_already: bool,
+    ) -> Result<Value> {
+        let mut stack = Vec::new();
+        let mut tagged_already = tagged_already;
+        loop {
+            match self.build_step(&mut stack, callback, path, tagged_already) {
+                Ok(Some(value)) => return Ok(value),
+                Ok(None) => tagged_already = false,
+                // Fill in the position like `deserialize_any` would for the
+                // innermost collection.
+                Err(err) => {
+                    return Err(match stack.split_last() {
+                        Some((frame, parents)) => {
+                            let de_path = build_path(&self.path, parents, true);
+                            error::fix_mark(err, frame.mark, *de_path.as_path())
+                        }
+                        None => err,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Reads the next node, or the end of the innermost collection in
+    /// `stack`, for [build_value](Self::build_value). Returns the value once
+    /// the outermost node is complete.
+    fn build_step(
+        &mut self,
+        stack: &mut Vec<Frame<'document>>,
+        callback: DuplicateKeyCallback,
+        path: Path,
+        tagged_already: bool,
+    ) -> Result<Option<Value>> {
+        let at_key = match stack.last() {
+            Some(Frame {
+                collection: Collection::Mapping { entry, .. },
+                ..
+            }) => entry.is_none(),
+            _ => false,
+        };
+        if let Some(frame) = stack.last_mut() {
+            match self.peek_event()? {
+                Event::SequenceEnd | Event::MappingEnd | Event::Void
+                    if at_key || matches!(frame.collection, Collection::Sequence(_)) =>
+                {
+                    self.next_event()?;
+                    let frame = stack.pop().unwrap();
+                    let value = match frame.collection {
+                        Collection::Alias { .. } => unreachable!(),
+                        Collection::Sequence(items) => Value::sequence(items),
+                        Collection::Mapping { mut mapping, .. } => {
+                            #[cfg(feature = "arbitrary_precision")]
+                            if let Some(number) = crate::number::from_token_mapping(&mapping) {
+                                return finish_node(
+                                    self.pos,
+                                    &self.document.events,
+                                    stack,
+                                    callback,
+                                    path,
+                                    frame.start,
+                                    Value::number(number),
+                                );
+                            }
+                            if self.document.options.sort_keys {
+                                mapping.sort_keys();
+                            }
+                            Value::mapping(mapping)
+                        }
+                    };
+                    return finish_node(
+                        self.pos,
+                        &self.document.events,
+                        stack,
+                        callback,
+                        path,
+                        frame.start,
+                        value,
+                    );
+                }
+                event => {
+                    if let Collection::Mapping { raw_key, .. } = &mut frame.collection {
+                        if at_key {
+                            *raw_key = match event {
+                                Event::Scalar(scalar) => str::from_utf8(&scalar.value).ok(),
+                                _ => None,
+                            };
+                        }
+                    }
+                }
+            }
+        }
+
+        let start = spanned::get_marker();
+        let (event, mark) = self.peek_event_mark()?;
+        let tag = match event {
+            Event::Scalar(Scalar { tag, .. })
+            | Event::SequenceStart(SequenceStart { tag, .. })
+            | Event::MappingStart(MappingStart { tag, .. }) => tag,
+            _ => &None,
+        };
+        let tagged = !tagged_already && parse_tag(tag).is_some();
+        let value = match event {
+            #[cfg(feature = "large_scalars")]
+            Event::Scalar(scalar) if !tagged && !at_key && self.is_large(scalar) => {
+                self.next_event_mark()?;
+                let style = crate::event::ScalarStyle::from_parser(scalar.style);
+                let string = match self.document.options.large_scalars {
+                    LargeScalars::Share => value::YamlString::shared(
+                        Arc::clone(&scalar.value),
+                        style,
+                    )
+                    .map_err(|_| {
+                        let err =
+                            de::Error::invalid_type(Unexpected::Bytes(&scalar.value), &"a string");
+                        let de_path = build_path(&self.path, stack, true);
+                        error::fix_mark(err, mark, *de_path.as_path())
+                    })?,
+                    LargeScalars::Elide => value::YamlString::elided(style),
+                };
+                Value::String(string, Span::default())
+            }
+            Event::Scalar(scalar) if !tagged => {
+                self.next_event_mark()?;
+                let visitor = ValueVisitor {
+                    callback: &mut *callback,
+                    path,
+                };
+                visit_scalar(visitor, scalar, tagged_already, self.schema).map_err(|err| {
+                    let de_path = build_path(&self.path, stack, true);
+                    error::fix_mark(err, mark, *de_path.as_path())
+                })?
+            }
+            Event::SequenceStart(_) if !tagged => {
+                self.next_event_mark()?;
+                stack.push(Frame {
+                    collection: Collection::Sequence(Vec::new()),
+                    start,
+                    mark,
+                });
+                return Ok(None);
+            }
+            Event::MappingStart(_) if !tagged => {
+                self.next_event_mark()?;
+                stack.push(Frame {
+                    collection: Collection::Mapping {
+                        mapping: Mapping::new(),
+                        raw_key: None,
+                        entry: None,
+                    },
+                    start,
+                    mark,
+                });
+                return Ok(None);
+            }
+            Event::Alias(mut pos) => {
+                self.next_event_mark()?;
+                self.resolve_alias(&mut pos)?;
+                let return_pos = mem::replace(self.pos, pos);
+                stack.push(Frame {
+                    collection: Collection::Alias { return_pos },
+                    start,
+                    mark,
+                });
+                return Ok(None);
+            }
+            // Tagged nodes and empty values are rare enough to be left to the
+            // recursive implementation. The contents of a tagged node are
+            // deserialized as a Value of their own, which comes back here.
+            _ => {
+                let de_path = build_path(&self.path, stack, true);
+                let value_path = build_path(&path, stack, false);
+                let mut node_de = self.at(*de_path.as_path());
+                let visitor = ValueVisitor {
+                    callback: &mut *callback,
+                    path: *value_path.as_path(),
+                };
+                visitor.deserialize(&mut node_de)?
+            }
+        };
+        finish_node(
+            self.pos,
+            &self.document.events,
+            stack,
+            callback,
+            path,
+            start,
+            value,
+        )
+    }
+
+    /// Whether `scalar` is a large string in the value being built; see
+    /// [`ParseOptions::large_scalars_over`].
+    #[cfg(feature = "large_scalars")]
+    fn is_large(&self, scalar: &Scalar) -> bool {
+        struct IsStr;
+
+        impl Visitor<'_> for IsStr {
+            type Value = bool;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_str<E>(self, _: &str) -> Result<bool, E>
+            where
+                E: de::Error,
+            {
+                Ok(true)
+            }
+        }
+
+        match self.document.options.large_scalars_over {
+            Some(max_bytes) if scalar.value.len() > max_bytes => {
+                visit_scalar(IsStr, scalar, false, self.schema).unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns a deserializer reading from the same position at `path`.
+    fn at<'path>(&'path mut self, path: Path<'path>) -> DeserializerFromEvents<'de, 'path> {
+        DeserializerFromEvents {
+            document: self.document,
+            pos: self.pos,
+            expansion: self.expansion,
+            path,
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+            schema: self.schema,
+            pair: false,
+        }
+    }
+
     fn visit_sequence<V>(&mut self, visitor: V, mark: Mark) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_sequence_of(visitor, mark, false)
+    }
+
+    /// Visits a sequence, whose entries are pairs if it is a `!!omap` or
+    /// `!!pairs`.
+    fn visit_sequence_of<V>(&mut self, visitor: V, mark: Mark, pairs: bool) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
@@ -545,6 +1433,7 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
                 empty: false,
                 de,
                 len: 0,
+                pairs,
             };
             let value = visitor.visit_seq(&mut seq)?;
             Ok((value, seq.len))
@@ -571,12 +1460,90 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         Ok(value)
     }
 
+    fn visit_set<V>(&mut self, visitor: V, mark: Mark) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, len) = self.recursion_check(mark, |de| {
+            let mut set = SetAccess { de, len: 0 };
+            let value = visitor.visit_seq(&mut set)?;
+            Ok((value, set.len))
+        })?;
+        self.end_mapping(len)?;
+        Ok(value)
+    }
+
+    fn visit_pair<V>(&mut self, visitor: V, mark: Mark) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, len) = self.recursion_check(mark, |de| {
+            let mut pair = PairAccess {
+                map: MapAccess {
+                    empty: false,
+                    de,
+                    len: 0,
+                    key: None,
+                },
+                taken: 0,
+            };
+            let value = visitor.visit_seq(&mut pair)?;
+            Ok((value, pair.map.len))
+        })?;
+        self.end_mapping(len)?;
+        Ok(value)
+    }
+
+    /// Whether all values of the mapping whose start event was just consumed
+    /// are null, as in `!!set {a, b}`.
+    fn is_set(&self) -> bool {
+        let mut depth = 0usize;
+        let mut is_key = true;
+        for (event, _mark) in &self.document.events[*self.pos..] {
+            let is_value = depth == 0 && !is_key;
+            let complete = match event {
+                Event::SequenceStart(_) | Event::MappingStart(_) => {
+                    if is_value {
+                        return false;
+                    }
+                    depth += 1;
+                    false
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                    depth == 0
+                }
+                Event::Scalar(scalar) => {
+                    if is_value && !is_null_scalar(scalar) {
+                        return false;
+                    }
+                    depth == 0
+                }
+                Event::Alias(_) => {
+                    if is_value {
+                        return false;
+                    }
+                    depth == 0
+                }
+                Event::Void => return false,
+            };
+            if complete {
+                is_key = !is_key;
+            }
+        }
+        false
+    }
+
     fn end_sequence(&mut self, len: usize) -> Result<()> {
         let total = {
             let mut seq = SeqAccess {
                 empty: false,
                 de: self,
                 len,
+                pairs: false,
             };
             while de::SeqAccess::next_element::<IgnoredAny>(&mut seq)?.is_some() {}
             seq.len
@@ -650,10 +1617,144 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
     }
 }
 
+/// A collection under construction by
+/// [build_value](DeserializerFromEvents::build_value).
+struct Frame<'document> {
+    collection: Collection<'document>,
+    start: Option<Marker>,
+    mark: Mark,
+}
+
+// Most frames are mappings, so boxing them would only add allocations.
+#[allow(clippy::large_enum_variant)]
+enum Collection<'document> {
+    Sequence(Vec<Value>),
+    Mapping {
+        mapping: Mapping,
+        /// The text of the current key if it is a scalar, which names the
+        /// entry in the paths of errors like in [MapAccess].
+        raw_key: Option<&'document str>,
+        /// The current key, once read, and what to do with its value.
+        entry: Option<(Value, Accept)>,
+    },
+    /// The node anchored by an alias, read from elsewhere in the document.
+    Alias {
+        /// The position to resume reading from afterwards.
+        return_pos: usize,
+    },
+}
+
+/// Adds the node starting at `start` whose `value` was just read to the
+/// innermost collection in `stack`, or returns it if it is the outermost
+/// one. Finishes the aliases the node was the target of on the way, going
+/// back to where they were read from `pos`.
+fn finish_node(
+    pos: &mut usize,
+    events: &[(Event, Mark)],
+    stack: &mut Vec<Frame>,
+    callback: DuplicateKeyCallback,
+    path: Path,
+    start: Option<Marker>,
+    value: Value,
+) -> Result<Option<Value>> {
+    let mut start = start;
+    let mut value = value;
+    loop {
+        let span = Span::from(start..spanned::get_marker());
+        #[cfg(feature = "filename")]
+        let span = span.maybe_capture_filename();
+        value = value.with_span(span);
+        match stack.last() {
+            Some(Frame {
+                collection: Collection::Alias { return_pos },
+                start: alias_start,
+                ..
+            }) => {
+                *pos = *return_pos;
+                // The anchored node ended where it is defined; the alias ends
+                // where the event after it starts.
+                if let Some((_, mark)) = events.get(*pos) {
+                    spanned::set_marker(*mark);
+                }
+                start = *alias_start;
+                stack.pop();
+            }
+            _ => break,
+        }
+    }
+
+    let Some((frame, parents)) = stack.split_last_mut() else {
+        return Ok(Some(value));
+    };
+    match &mut frame.collection {
+        Collection::Alias { .. } => unreachable!(),
+        Collection::Sequence(items) => items.push(value),
+        Collection::Mapping {
+            mapping,
+            entry: entry @ Some(_),
+            ..
+        } => match entry.take().unwrap() {
+            (key, Accept::Insert) => {
+                mapping.insert(key, value);
+            }
+            (_, Accept::Skip) => {}
+            (key, Accept::KeepDuplicate) => mapping.push_duplicate(key, value),
+        },
+        Collection::Mapping { mapping, entry, .. } => {
+            #[cfg(feature = "intern")]
+            let value = match value {
+                Value::String(string, span) => Value::String(crate::value::intern(&string), span),
+                key => key,
+            };
+            let key = value;
+            // Only build the path of the entry if the callback needs it.
+            let accept = if mapping.contains_key(&key) {
+                let mapping_path = build_path(&path, parents, false);
+                let entry_path = match key.as_str() {
+                    Some(key) => mapping_path.push_key(key),
+                    None => mapping_path.push_unknown(),
+                };
+                mapping.accept_key::<Error>(&key, *entry_path.as_path(), callback)?
+            } else {
+                Accept::Insert
+            };
+            *entry = Some((key, accept));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the path of the node being read within the collections in `stack`
+/// starting at `base`, as seen by the deserializer if `raw`, or else by the
+/// duplicate key callback.
+fn build_path(base: &Path, stack: &[Frame], raw: bool) -> OwnedPath {
+    let mut path = base.to_owned_path();
+    for frame in stack {
+        path = match &frame.collection {
+            Collection::Sequence(items) => path.push_index(items.len()),
+            Collection::Alias { .. } if raw => path.push_alias(),
+            Collection::Alias { .. } => path,
+            // The node is a key, which is at the path of its mapping.
+            Collection::Mapping { entry: None, .. } => path,
+            Collection::Mapping {
+                raw_key,
+                entry: Some((key, _)),
+                ..
+            } => match if raw { *raw_key } else { key.as_str() } {
+                Some(key) => path.push_key(key),
+                None => path.push_unknown(),
+            },
+        };
+    }
+    path
+}
+
 struct SeqAccess<'de, 'document, 'seq> {
     empty: bool,
     de: &'seq mut DeserializerFromEvents<'de, 'document>,
     len: usize,
+    /// Whether the sequence is a `!!omap` or `!!pairs`.
+    pairs: bool,
 }
 
 impl<'de> de::SeqAccess<'de> for SeqAccess<'de, '_, '_> {
@@ -666,22 +1767,26 @@ impl<'de> de::SeqAccess<'de> for SeqAccess<'de, '_, '_> {
         if self.empty {
             return Ok(None);
         }
-        match self.de.peek_event()? {
-            Event::SequenceEnd | Event::Void => Ok(None),
-            _ => {
+        match self.de.peek_event_mark()? {
+            (Event::SequenceEnd | Event::Void, _) => Ok(None),
+            (_, mark) => {
                 let mut element_de = DeserializerFromEvents {
                     document: self.de.document,
                     pos: self.de.pos,
-                    jumpcount: self.de.jumpcount,
+                    expansion: self.de.expansion,
                     path: Path::Seq {
                         parent: &self.de.path,
                         index: self.len,
                     },
                     remaining_depth: self.de.remaining_depth,
                     current_enum: None,
+                    schema: self.de.schema,
+                    pair: self.pairs,
                 };
                 self.len += 1;
-                seed.deserialize(&mut element_de).map(Some)
+                seed.deserialize(&mut element_de)
+                    .map_err(|err| error::fix_mark(err, mark, element_de.path))
+                    .map(Some)
             }
         }
     }
@@ -723,10 +1828,11 @@ impl<'de> de::MapAccess<'de> for MapAccess<'de, '_, '_> {
     where
         V: DeserializeSeed<'de>,
     {
+        let (_, mark) = self.de.peek_event_mark()?;
         let mut value_de = DeserializerFromEvents {
             document: self.de.document,
             pos: self.de.pos,
-            jumpcount: self.de.jumpcount,
+            expansion: self.de.expansion,
             path: if let Some(key) = self.key.and_then(|key| str::from_utf8(key).ok()) {
                 Path::Map {
                     parent: &self.de.path,
@@ -739,8 +1845,89 @@ impl<'de> de::MapAccess<'de> for MapAccess<'de, '_, '_> {
             },
             remaining_depth: self.de.remaining_depth,
             current_enum: None,
+            schema: self.de.schema,
+            pair: false,
         };
+        let _span = trace::enter_top_level_key(&value_de.path);
+        // Errors that the seed raises after reading the value, such as a
+        // failed `#[serde(try_from)]` conversion, have not been located by
+        // the deserializer yet.
         seed.deserialize(&mut value_de)
+            .map_err(|err| error::fix_mark(err, mark, value_de.path))
+    }
+}
+
+/// Presents the keys of a mapping whose values are all null as a sequence, so
+/// that a YAML `!!set` can be deserialized into `HashSet`, `Vec` and the like.
+struct SetAccess<'de, 'document, 'set> {
+    de: &'set mut DeserializerFromEvents<'de, 'document>,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SetAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.de.peek_event_mark()? {
+            (Event::MappingEnd | Event::Void, _) => Ok(None),
+            (_, mark) => {
+                let mut element_de = DeserializerFromEvents {
+                    document: self.de.document,
+                    pos: self.de.pos,
+                    expansion: self.de.expansion,
+                    path: Path::Seq {
+                        parent: &self.de.path,
+                        index: self.len,
+                    },
+                    remaining_depth: self.de.remaining_depth,
+                    current_enum: None,
+                    schema: self.de.schema,
+                    pair: false,
+                };
+                self.len += 1;
+                let element = seed
+                    .deserialize(&mut element_de)
+                    .map_err(|err| error::fix_mark(err, mark, element_de.path))?;
+                // Skip the null value.
+                self.de.ignore_any()?;
+                Ok(Some(element))
+            }
+        }
+    }
+}
+
+/// Presents the single entry of a mapping as a sequence of its key and value,
+/// so that the entries of a YAML `!!omap` or `!!pairs` can be deserialized
+/// into 2-tuples.
+struct PairAccess<'de, 'document, 'map> {
+    map: MapAccess<'de, 'document, 'map>,
+    taken: u8,
+}
+
+impl<'de> de::SeqAccess<'de> for PairAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.taken {
+            0 => {
+                let key = de::MapAccess::next_key_seed(&mut self.map, seed)?;
+                if key.is_some() {
+                    self.taken = 1;
+                }
+                Ok(key)
+            }
+            1 => {
+                self.taken = 2;
+                de::MapAccess::next_value_seed(&mut self.map, seed).map(Some)
+            }
+            _ => Ok(None),
+        }
     }
 }
 
@@ -763,13 +1950,15 @@ impl<'de, 'variant> de::EnumAccess<'de> for EnumAccess<'de, '_, 'variant> {
         let visitor = DeserializerFromEvents {
             document: self.de.document,
             pos: self.de.pos,
-            jumpcount: self.de.jumpcount,
+            expansion: self.de.expansion,
             path: self.de.path,
             remaining_depth: self.de.remaining_depth,
             current_enum: Some(CurrentEnum {
                 name: self.name,
                 tag: self.tag,
             }),
+            schema: self.de.schema,
+            pair: false,
         };
         Ok((variant, visitor))
     }
@@ -858,10 +2047,17 @@ impl<'de> de::VariantAccess<'de> for UnitVariantAccess<'de, '_, '_> {
     }
 }
 
-fn visit_scalar<'de, V>(visitor: V, scalar: &Scalar<'de>, tagged_already: bool) -> Result<V::Value>
+fn visit_scalar<'de, V>(
+    visitor: V,
+    scalar: &Scalar<'de>,
+    tagged_already: bool,
+    schema: ScalarSchema,
+) -> Result<V::Value>
 where
     V: Visitor<'de>,
 {
+    #[cfg(feature = "round_trip")]
+    let _style = value::with_parsing_style(crate::event::ScalarStyle::from_parser(scalar.style));
     let v = match str::from_utf8(&scalar.value) {
         Ok(v) => v,
         Err(_) => {
@@ -878,12 +2074,12 @@ where
                 None => Err(de::Error::invalid_value(Unexpected::Str(v), &"a boolean")),
             };
         } else if tag == Tag::INT {
-            return match visit_int(visitor, v) {
+            return match schema.visit_int(visitor, v) {
                 Ok(result) => result,
                 Err(_) => Err(de::Error::invalid_value(Unexpected::Str(v), &"an integer")),
             };
         } else if tag == Tag::FLOAT {
-            return match parse_f64(v) {
+            return match schema.parse_f64(v) {
                 Some(v) => visitor.visit_f64(v),
                 None => Err(de::Error::invalid_value(Unexpected::Str(v), &"a float")),
             };
@@ -893,10 +2089,10 @@ where
                 None => Err(de::Error::invalid_value(Unexpected::Str(v), &"null")),
             };
         } else if tag.starts_with("!") && scalar.style == ScalarStyle::Plain {
-            return visit_untagged_scalar(visitor, v, scalar.repr, scalar.style);
+            return visit_untagged_scalar(visitor, v, scalar.repr, scalar.style, schema);
         }
     } else if scalar.style == ScalarStyle::Plain {
-        return visit_untagged_scalar(visitor, v, scalar.repr, scalar.style);
+        return visit_untagged_scalar(visitor, v, scalar.repr, scalar.style, schema);
     }
     if let Some(borrowed) = parse_borrowed_str(v, scalar.repr, scalar.style) {
         visitor.visit_borrowed_str(borrowed)
@@ -932,6 +2128,16 @@ fn parse_null(scalar: &[u8]) -> Option<()> {
     }
 }
 
+fn is_null_scalar(scalar: &Scalar) -> bool {
+    if scalar.style != ScalarStyle::Plain {
+        return false;
+    }
+    match &scalar.tag {
+        Some(tag) => tag == Tag::NULL && parse_null(&scalar.value).is_some(),
+        None => scalar.value.is_empty() || parse_null(&scalar.value).is_some(),
+    }
+}
+
 fn parse_bool(scalar: &str) -> Option<bool> {
     match scalar {
         "true" | "True" | "TRUE" => Some(true),
@@ -1084,12 +2290,116 @@ pub(crate) fn parse_f64(scalar: &str) -> Option<f64> {
     if let ".nan" | ".NaN" | ".NAN" = scalar {
         return Some(f64::NAN.copysign(1.0));
     }
-    if let Ok(float) = unpositive.parse::<f64>() {
-        if float.is_finite() {
-            return Some(float);
+    if let Ok(float) = unpositive.parse::<f64>() {
+        if float.is_finite() {
+            return Some(float);
+        }
+    }
+    None
+}
+
+/// Parses an integer of the YAML 1.2 core schema: decimal with an optional
+/// sign, or unsigned `0x` hexadecimal or `0o` octal.
+fn parse_core_int<T>(
+    scalar: &str,
+    from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+) -> Option<T> {
+    let (digits, radix, is_digit): (_, _, fn(&u8) -> bool) =
+        if let Some(rest) = scalar.strip_prefix("0x") {
+            (rest, 16, u8::is_ascii_hexdigit)
+        } else if let Some(rest) = scalar.strip_prefix("0o") {
+            (rest, 8, |b| (b'0'..=b'7').contains(b))
+        } else {
+            (scalar, 10, u8::is_ascii_digit)
+        };
+    let unsigned = match radix {
+        10 => digits.strip_prefix(['-', '+']).unwrap_or(digits),
+        _ => digits,
+    };
+    if unsigned.is_empty() || !unsigned.as_bytes().iter().all(is_digit) {
+        return None;
+    }
+    from_str_radix(digits.strip_prefix('+').unwrap_or(digits), radix).ok()
+}
+
+/// Parses a float of the YAML 1.2 core schema, including `.inf` and `.nan`.
+fn parse_core_f64(scalar: &str) -> Option<f64> {
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = scalar.strip_prefix(['-', '+']).unwrap_or(scalar);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let is_float = digits(integer)
+        && fraction.is_none_or(digits)
+        && (!integer.is_empty() || fraction.is_some_and(|fraction| !fraction.is_empty()))
+        && exponent.is_none_or(|exponent| {
+            let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+            !exponent.is_empty() && digits(exponent)
+        });
+    if is_float {
+        scalar.parse().ok()
+    } else {
+        // Only the spellings of infinity and NaN are left.
+        parse_f64(scalar).filter(|float| !float.is_finite())
+    }
+}
+
+impl ScalarSchema {
+    fn visit_int<'de, V>(self, visitor: V, v: &str) -> Result<Result<V::Value>, V>
+    where
+        V: Visitor<'de>,
+    {
+        if self == ScalarSchema::Lenient {
+            return visit_int(visitor, v);
+        }
+        if let Some(int) = parse_core_int(v, u64::from_str_radix) {
+            return Ok(visitor.visit_u64(int));
+        }
+        if let Some(int) = parse_core_int(v, i64::from_str_radix) {
+            return Ok(visitor.visit_i64(int));
+        }
+        if let Some(int) = parse_core_int(v, u128::from_str_radix) {
+            return Ok(visitor.visit_u128(int));
+        }
+        if let Some(int) = parse_core_int(v, i128::from_str_radix) {
+            return Ok(visitor.visit_i128(int));
+        }
+        Err(visitor)
+    }
+
+    fn parse_signed_int<T>(
+        self,
+        scalar: &str,
+        from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+    ) -> Option<T> {
+        match self {
+            ScalarSchema::Lenient => parse_signed_int(scalar, from_str_radix),
+            ScalarSchema::Core => parse_core_int(scalar, from_str_radix),
+        }
+    }
+
+    fn parse_unsigned_int<T>(
+        self,
+        scalar: &str,
+        from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+    ) -> Option<T> {
+        match self {
+            ScalarSchema::Lenient => parse_unsigned_int(scalar, from_str_radix),
+            ScalarSchema::Core => parse_core_int(scalar, from_str_radix),
+        }
+    }
+
+    fn parse_f64(self, scalar: &str) -> Option<f64> {
+        match self {
+            ScalarSchema::Lenient => parse_f64(scalar),
+            ScalarSchema::Core => parse_core_f64(scalar),
         }
     }
-    None
 }
 
 pub(crate) fn digits_but_not_number(scalar: &str) -> bool {
@@ -1123,6 +2433,7 @@ pub(crate) fn visit_untagged_scalar<'de, V>(
     v: &str,
     repr: Option<&'de [u8]>,
     style: ScalarStyle,
+    schema: ScalarSchema,
 ) -> Result<V::Value>
 where
     V: Visitor<'de>,
@@ -1133,12 +2444,16 @@ where
     if let Some(boolean) = parse_bool(v) {
         return visitor.visit_bool(boolean);
     }
-    let visitor = match visit_int(visitor, v) {
+    let visitor = match schema.visit_int(visitor, v) {
         Ok(result) => return result,
         Err(visitor) => visitor,
     };
-    if !digits_but_not_number(v) {
-        if let Some(float) = parse_f64(v) {
+    if schema == ScalarSchema::Core || !digits_but_not_number(v) {
+        #[cfg(feature = "arbitrary_precision")]
+        if crate::number::is_lossy_decimal(v) {
+            return visitor.visit_map(crate::number::BigNumberAccess::new(v));
+        }
+        if let Some(float) = schema.parse_f64(v) {
             return visitor.visit_f64(float);
         }
     }
@@ -1168,19 +2483,29 @@ fn invalid_type(event: &Event, exp: &dyn Expected) -> Error {
         exp: &'a dyn Expected,
     }
 
-    impl Visitor<'_> for InvalidType<'_> {
+    impl<'de> Visitor<'de> for InvalidType<'_> {
         type Value = Void;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             self.exp.fmt(formatter)
         }
+
+        #[cfg(feature = "arbitrary_precision")]
+        fn visit_map<A>(self, mut map: A) -> Result<Void, A::Error>
+        where
+            A: de::MapAccess<'de>,
+        {
+            let _token = map.next_key::<String>()?;
+            let repr = map.next_value::<String>()?;
+            Err(de::Error::invalid_type(Unexpected::Other(&repr), &self))
+        }
     }
 
     match event {
         Event::Alias(_) => unreachable!(),
         Event::Scalar(scalar) => {
             let get_type = InvalidType { exp };
-            match visit_scalar(get_type, scalar, false) {
+            match visit_scalar(get_type, scalar, false, ScalarSchema::Lenient) {
                 Ok(void) => match void {},
                 Err(invalid_type) => invalid_type,
             }
@@ -1193,6 +2518,20 @@ fn invalid_type(event: &Event, exp: &dyn Expected) -> Error {
     }
 }
 
+fn is_binary(scalar: &Scalar) -> bool {
+    scalar.tag.as_ref().is_some_and(|tag| tag == Tag::BINARY)
+}
+
+fn decode_binary(scalar: &Scalar) -> Result<Vec<u8>> {
+    binary::decode(&scalar.value).ok_or_else(|| {
+        let unexp = match str::from_utf8(&scalar.value) {
+            Ok(v) => Unexpected::Str(v),
+            Err(_) => Unexpected::Bytes(&scalar.value),
+        };
+        de::Error::invalid_value(unexp, &"base64-encoded binary data")
+    })
+}
+
 fn parse_tag(libyaml_tag: &Option<Tag>) -> Option<&str> {
     let mut bytes: &[u8] = libyaml_tag.as_ref()?;
     if let (b'!', rest) = bytes.split_first()? {
@@ -1201,10 +2540,77 @@ fn parse_tag(libyaml_tag: &Option<Tag>) -> Option<&str> {
         }
         str::from_utf8(bytes).ok()
     } else {
-        None
+        Tag::KEPT
+            .iter()
+            .find(|(_, tag)| *bytes == *tag.as_bytes())
+            .map(|(shorthand, _)| *shorthand)
     }
 }
 
+/// Whether `tag` is that of a YAML `!!set`.
+fn is_set_tag(tag: &Option<Tag>) -> bool {
+    tag.as_ref().is_some_and(|tag| *tag == *Tag::SET)
+}
+
+/// Whether `tag` is that of a YAML `!!omap` or `!!pairs`, whose entries are
+/// single-entry mappings.
+fn is_pairs_tag(tag: &Option<Tag>) -> bool {
+    tag.as_ref()
+        .is_some_and(|tag| *tag == *Tag::OMAP || *tag == *Tag::PAIRS)
+}
+
+/// If a [`ShouldBe`](crate::ShouldBe) is being deserialized, buffers the
+/// current subtree into a [Value] and deserializes from that instead, so that
+/// the raw value can be captured and the event stream stays consistent if
+/// deserialization fails.
+macro_rules! maybe_expecting_should_be {
+    ($self:expr, $method:ident, $($args:expr),*) => {
+        if crate::shouldbe::is_expecting_should_be_then_reset() && $self.current_enum.is_none() {
+            let (_event, mark) = $self.peek_event_mark()?;
+            // The target is read from the events, which are only read again
+            // into the raw value if it fails.
+            let start = *$self.pos;
+            let expansion = *$self.expansion;
+            return match (&mut *$self).$method($($args),*) {
+                Ok(value) => Ok(value),
+                Err(err) => {
+                    let err = error::fix_mark(err, mark, $self.path);
+                    *$self.pos = start;
+                    *$self.expansion = expansion;
+                    match Value::deserialize(&mut *$self) {
+                        Ok(value) => {
+                            let msg = err.to_string();
+                            crate::shouldbe::set_why_not(value, err);
+                            // ShouldBe will ignore this and use the error in
+                            // `why_not`, but we still need to return an error
+                            // here nonetheless.
+                            Err(de::Error::custom(msg))
+                        }
+                        // The node can't be read as a raw value either, e.g.
+                        // for a duplicate key; ShouldBe then goes without.
+                        Err(_) => {
+                            *$self.pos = start;
+                            *$self.expansion = expansion;
+                            $self.ignore_any()?;
+                            Err(err)
+                        }
+                    }
+                }
+            };
+        }
+    };
+}
+
+macro_rules! maybe_handle_tag {
+    ($self:expr, $method:ident, $($args:expr),*) => {
+        if let Some((replacement, mark)) = $self.handle_tag()? {
+            return ValueDeserializer::new(replacement)
+                .$method($($args),*)
+                .map_err(|err| error::fix_mark(err, mark, $self.path));
+        }
+    };
+}
+
 impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     type Error = Error;
 
@@ -1212,7 +2618,23 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_any, visitor);
+        maybe_handle_tag!(self, deserialize_any, visitor);
         let tagged_already = self.current_enum.is_some();
+        if self.document.options.iterative_values {
+            if let Some(policy) = value::take_deserializing_value() {
+                let mut duplicate_keys = 0;
+                let mut callback = |path: Path, key: &Value, existing_key: &Value| {
+                    duplicate_keys += 1;
+                    policy(path, key, existing_key)
+                };
+                let value = self.build_value(&mut callback, Path::Root, tagged_already)?;
+                value::save_built_value(value, duplicate_keys);
+                // Succeed rather than fail like the Value deserializers do, so
+                // that the rest of the document is still checked for errors.
+                return visitor.visit_unit();
+            }
+        }
         let (next, mark) = self.next_event_mark()?;
         fn enum_tag(tag: &Option<Tag>, tagged_already: bool) -> Option<&str> {
             if tagged_already {
@@ -1222,7 +2644,9 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
         }
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_any(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_any(visitor))
+                }
                 Event::Scalar(scalar) => {
                     if let Some(tag) = enum_tag(&scalar.tag, tagged_already) {
                         *self.pos -= 1;
@@ -1232,7 +2656,7 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
                             tag,
                         });
                     }
-                    break visit_scalar(visitor, scalar, tagged_already);
+                    break visit_scalar(visitor, scalar, tagged_already, self.schema);
                 }
                 Event::SequenceStart(sequence) => {
                     if let Some(tag) = enum_tag(&sequence.tag, tagged_already) {
@@ -1270,11 +2694,15 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_bool, visitor);
+        maybe_handle_tag!(self, deserialize_bool, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_bool(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_bool(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::BOOL, scalar, tagged_already) =>
                 {
@@ -1316,16 +2744,21 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_i64, visitor);
+        maybe_handle_tag!(self, deserialize_i64, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_i64(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_i64(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::INT, scalar, tagged_already) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(int) = parse_signed_int(value, i64::from_str_radix) {
+                        if let Some(int) = self.schema.parse_signed_int(value, i64::from_str_radix)
+                        {
                             break visitor.visit_i64(int);
                         }
                     }
@@ -1341,16 +2774,21 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_i128, visitor);
+        maybe_handle_tag!(self, deserialize_i128, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_i128(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_i128(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::INT, scalar, tagged_already) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(int) = parse_signed_int(value, i128::from_str_radix) {
+                        if let Some(int) = self.schema.parse_signed_int(value, i128::from_str_radix)
+                        {
                             break visitor.visit_i128(int);
                         }
                     }
@@ -1387,16 +2825,22 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_u64, visitor);
+        maybe_handle_tag!(self, deserialize_u64, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_u64(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_u64(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::INT, scalar, tagged_already) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(int) = parse_unsigned_int(value, u64::from_str_radix) {
+                        if let Some(int) =
+                            self.schema.parse_unsigned_int(value, u64::from_str_radix)
+                        {
                             break visitor.visit_u64(int);
                         }
                     }
@@ -1412,16 +2856,22 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_u128, visitor);
+        maybe_handle_tag!(self, deserialize_u128, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_u128(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_u128(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::INT, scalar, tagged_already) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(int) = parse_unsigned_int(value, u128::from_str_radix) {
+                        if let Some(int) =
+                            self.schema.parse_unsigned_int(value, u128::from_str_radix)
+                        {
                             break visitor.visit_u128(int);
                         }
                     }
@@ -1444,16 +2894,20 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_f64, visitor);
+        maybe_handle_tag!(self, deserialize_f64, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         loop {
             match next {
-                Event::Alias(mut pos) => break self.jump(&mut pos)?.deserialize_f64(visitor),
+                Event::Alias(pos) => {
+                    break self.expand_alias(*pos, |de| de.deserialize_f64(visitor))
+                }
                 Event::Scalar(scalar)
                     if is_plain_or_tagged_literal_scalar(Tag::FLOAT, scalar, tagged_already) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(float) = parse_f64(value) {
+                        if let Some(float) = self.schema.parse_f64(value) {
                             break visitor.visit_f64(float);
                         }
                     }
@@ -1476,6 +2930,8 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_str, visitor);
+        maybe_handle_tag!(self, deserialize_str, visitor);
         let (next, mark) = self.next_event_mark()?;
         match next {
             Event::Scalar(scalar) => {
@@ -1489,7 +2945,7 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
                     Err(invalid_type(next, &visitor))
                 }
             }
-            Event::Alias(mut pos) => self.jump(&mut pos)?.deserialize_str(visitor),
+            Event::Alias(pos) => self.expand_alias(*pos, |de| de.deserialize_str(visitor)),
             other => Err(invalid_type(other, &visitor)),
         }
         .map_err(|err: Error| error::fix_mark(err, mark, self.path))
@@ -1502,18 +2958,31 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
         self.deserialize_str(visitor)
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+        self.deserialize_byte_buf(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    /// Decodes a `!!binary` scalar. A sequence of integers is also accepted,
+    /// which is how byte buffers appear in YAML without the tag.
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+        maybe_expecting_should_be!(self, deserialize_byte_buf, visitor);
+        maybe_handle_tag!(self, deserialize_byte_buf, visitor);
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Alias(pos) => self.expand_alias(*pos, |de| de.deserialize_byte_buf(visitor)),
+            Event::Scalar(scalar) if is_binary(scalar) => {
+                decode_binary(scalar).and_then(|bytes| visitor.visit_byte_buf(bytes))
+            }
+            Event::SequenceStart(_) => self.visit_sequence(visitor, mark),
+            other => Err(invalid_type(other, &visitor)),
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
     }
 
     /// Parses `null` as None and any other values as `Some(...)`.
@@ -1521,10 +2990,12 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_option, visitor);
+        maybe_handle_tag!(self, deserialize_option, visitor);
         let is_some = match self.peek_event()? {
-            Event::Alias(mut pos) => {
+            Event::Alias(pos) => {
                 *self.pos += 1;
-                return self.jump(&mut pos)?.deserialize_option(visitor);
+                return self.expand_alias(*pos, |de| de.deserialize_option(visitor));
             }
             Event::Scalar(scalar) => {
                 let tagged_already = self.current_enum.is_some();
@@ -1567,6 +3038,8 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_unit, visitor);
+        maybe_handle_tag!(self, deserialize_unit, visitor);
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         match next {
@@ -1589,7 +3062,7 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
                     ))
                 }
             }
-            Event::Alias(mut pos) => self.jump(&mut pos)?.deserialize_unit(visitor),
+            Event::Alias(pos) => self.expand_alias(*pos, |de| de.deserialize_unit(visitor)),
             Event::Void => visitor.visit_unit(),
             other => Err(invalid_type(other, &visitor)),
         }
@@ -1608,6 +3081,8 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_newtype_struct, _name, visitor);
+        maybe_handle_tag!(self, deserialize_newtype_struct, _name, visitor);
         let (_event, mark) = self.peek_event_mark()?;
         self.recursion_check(mark, |de| visitor.visit_newtype_struct(de))
     }
@@ -1616,10 +3091,23 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_seq, visitor);
+        maybe_handle_tag!(self, deserialize_seq, visitor);
         let (next, mark) = self.next_event_mark()?;
         match next {
-            Event::Alias(mut pos) => self.jump(&mut pos)?.deserialize_seq(visitor),
-            Event::SequenceStart(_) => self.visit_sequence(visitor, mark),
+            Event::Alias(pos) => self.expand_alias(*pos, |de| de.deserialize_seq(visitor)),
+            Event::SequenceStart(sequence) => {
+                self.visit_sequence_of(visitor, mark, is_pairs_tag(&sequence.tag))
+            }
+            Event::MappingStart(mapping) if is_set_tag(&mapping.tag) && self.is_set() => {
+                self.visit_set(visitor, mark)
+            }
+            Event::Scalar(scalar) if is_binary(scalar) => decode_binary(scalar).and_then(|bytes| {
+                let mut seq = SeqDeserializer::<_, Error>::new(bytes.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }),
             other => {
                 if match other {
                     Event::Void => true,
@@ -1632,6 +3120,7 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
                         empty: true,
                         de: self,
                         len: 0,
+                        pairs: false,
                     })
                 } else {
                     Err(invalid_type(other, &visitor))
@@ -1641,10 +3130,27 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if len == 2 && self.pair {
+            match self.peek_event()? {
+                Event::Alias(pos) => {
+                    *self.pos += 1;
+                    return self.expand_alias(*pos, |de| de.deserialize_tuple(len, visitor));
+                }
+                Event::MappingStart(_) => {
+                    maybe_expecting_should_be!(self, deserialize_tuple, len, visitor);
+                    maybe_handle_tag!(self, deserialize_tuple, len, visitor);
+                    let (_event, mark) = self.next_event_mark()?;
+                    return self
+                        .visit_pair(visitor, mark)
+                        .map_err(|err| error::fix_mark(err, mark, self.path));
+                }
+                _ => {}
+            }
+        }
         self.deserialize_seq(visitor)
     }
 
@@ -1664,9 +3170,11 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_map, visitor);
+        maybe_handle_tag!(self, deserialize_map, visitor);
         let (next, mark) = self.next_event_mark()?;
         match next {
-            Event::Alias(mut pos) => self.jump(&mut pos)?.deserialize_map(visitor),
+            Event::Alias(pos) => self.expand_alias(*pos, |de| de.deserialize_map(visitor)),
             Event::MappingStart(_) => self.visit_mapping(visitor, mark),
             other => {
                 if match other {
@@ -1714,6 +3222,8 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_enum, name, variants, visitor);
+        maybe_handle_tag!(self, deserialize_enum, name, variants, visitor);
         let (next, mark) = self.peek_event_mark()?;
         loop {
             if let Some(current_enum) = self.current_enum {
@@ -1736,10 +3246,9 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
                 break Err(error::new(ErrorImpl::Message(message, None)));
             }
             break match next {
-                Event::Alias(mut pos) => {
+                Event::Alias(pos) => {
                     *self.pos += 1;
-                    self.jump(&mut pos)?
-                        .deserialize_enum(name, variants, visitor)
+                    self.expand_alias(*pos, |de| de.deserialize_enum(name, variants, visitor))
                 }
                 Event::Scalar(scalar) => {
                     if let Some(tag) = parse_tag(&scalar.tag) {
@@ -1794,6 +3303,8 @@ impl<'de> de::Deserializer<'de> for &mut DeserializerFromEvents<'de, '_> {
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_ignored_any, visitor);
+        maybe_handle_tag!(self, deserialize_ignored_any, visitor);
         self.ignore_any()?;
         visitor.visit_unit()
     }
@@ -1812,14 +3323,433 @@ pub fn from_str<'de, T>(s: &'de str) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    spanned::set_marker(spanned::Marker::start());
-    let res = T::deserialize(Deserializer::from_str(s));
-    spanned::reset_marker();
-    res
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_str(s))
+}
+
+/// Deserialize an instance of type `T` from a string of YAML text, parsed with
+/// the given options.
+///
+/// ```
+/// use dbt_serde_yaml::{ParseOptions, TabIndentation};
+/// use std::collections::BTreeMap;
+///
+/// let options = ParseOptions::new().tab_indentation(TabIndentation::AsSpace);
+/// let map: BTreeMap<String, BTreeMap<String, u32>> =
+///     dbt_serde_yaml::from_str_with_options("a:\n\tb: 1\n", options).unwrap();
+/// assert_eq!(map["a"]["b"], 1);
+/// ```
+pub fn from_str_with_options<'de, T>(s: &'de str, options: ParseOptions) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let _marker = spanned::enter_marker(options.origin);
+    T::deserialize(Deserializer::from_str_with(s, options))
+}
+
+/// Deserialize an instance of type `T` from a fragment of YAML text that
+/// starts at `origin` in an enclosing text, such as a Jinja block or a fenced
+/// code block in a Markdown file; see [ParseOptions::origin].
+///
+/// ```
+/// use dbt_serde_yaml::{Marker, Spanned};
+/// use std::collections::BTreeMap;
+///
+/// let markdown = "# Orders\n\n```yaml\nname: orders\nrows: many\n```\n";
+/// let start = markdown.find("name").unwrap();
+/// let end = markdown.rfind("```").unwrap();
+/// let origin = Marker::new(start, 4, 1);
+///
+/// let map: BTreeMap<String, Spanned<String>> =
+///     dbt_serde_yaml::from_str_with_origin(&markdown[start..end], origin).unwrap();
+/// assert_eq!(map["name"].span().start.line, 4);
+/// assert_eq!(map["name"].span().start.index, markdown.find("orders\n").unwrap());
+///
+/// let err = dbt_serde_yaml::from_str_with_origin::<BTreeMap<String, u32>>(&markdown[start..end], origin)
+///     .unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "name: invalid type: string \"orders\", expected u32 at line 4 column 7",
+/// );
+/// ```
+pub fn from_str_with_origin<'de, T>(s: &'de str, origin: Marker) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_str_with_options(s, ParseOptions::new().origin(origin))
+}
+
+/// Deserialize an instance of type `T` from a string of YAML text, with the
+/// given [Config] in place of the thread's current one.
+pub fn from_str_with_config<'de, T>(s: &'de str, config: &Config) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let _scope = config.enter();
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_str(s))
+}
+
+/// Deserialize an instance of type `T` from the node at `path` in a string of
+/// YAML text.
+///
+/// Only the node at `path` is deserialized into `T`; everything around it is
+/// parsed but skipped without being materialized, which makes this cheaper
+/// than going through a [Value] when only a small part of a large document is
+/// needed. Errors report their location and path within the whole document.
+///
+/// ```
+/// use dbt_serde_yaml::path::OwnedPath;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: String,
+/// }
+///
+/// let yaml = "
+/// version: 2
+/// models:
+///   - name: orders
+///     columns: [id, status]
+///   - name: customers
+/// ";
+///
+/// let path: OwnedPath = "models[1].name".parse().unwrap();
+/// let name: String = dbt_serde_yaml::from_str_at(yaml, path.as_path()).unwrap();
+/// assert_eq!(name, "customers");
+///
+/// // Fields that `Model` doesn't have are skipped as well.
+/// let path: OwnedPath = "models".parse().unwrap();
+/// let models: Vec<Model> = dbt_serde_yaml::from_str_at(yaml, path.as_path()).unwrap();
+/// assert_eq!(models.len(), 2);
+///
+/// let path: OwnedPath = "models[2].name".parse().unwrap();
+/// let err = dbt_serde_yaml::from_str_at::<String>(yaml, path.as_path()).unwrap_err();
+/// assert_eq!(err.to_string(), "no value at path models[2].name");
+/// ```
+///
+/// As with [Value::get_path], a tagged node is looked into, and a path with an
+/// unknown component matches nothing.
+pub fn from_str_at<'de, T>(s: &'de str, path: &Path) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let components: Vec<PathComponent> = path.components().collect();
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    let res = AtPath {
+        components: &components,
+        marker: PhantomData,
+    }
+    .deserialize(Deserializer::from_str(s));
+    res?.ok_or_else(|| error::new(ErrorImpl::NoValueAtPath(path.to_string())))
+}
+
+/// Deserializes `T` from the node at the end of `components`, or `None` if
+/// there is no such node, ignoring everything else on the way.
+struct AtPath<'a, 'p, T> {
+    components: &'a [PathComponent<'p>],
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for AtPath<'_, '_, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Option<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match self.components.split_first() {
+            None => T::deserialize(deserializer).map(Some),
+            Some((PathComponent::Alias, rest)) => AtPath {
+                components: rest,
+                marker: self.marker,
+            }
+            .deserialize(deserializer),
+            Some((PathComponent::Unknown, _)) => {
+                IgnoredAny::deserialize(deserializer)?;
+                Ok(None)
+            }
+            Some((&component, rest)) => deserializer.deserialize_any(AtPathVisitor {
+                component,
+                rest: AtPath {
+                    components: rest,
+                    marker: self.marker,
+                },
+            }),
+        }
+    }
+}
+
+/// Looks for `component` in a sequence or mapping, and descends into it with
+/// `rest`.
+struct AtPathVisitor<'a, 'p, T> {
+    component: PathComponent<'p>,
+    rest: AtPath<'a, 'p, T>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for AtPathVisitor<'_, '_, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Option<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, T> Visitor<'de> for AtPathVisitor<'_, '_, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any YAML value")
+    }
+
+    fn visit_bool<E>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E>(self, _v: i64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i128<E>(self, _v: i128) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E>(self, _v: u64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u128<E>(self, _v: u128) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_str<E>(self, _v: &str) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_bytes<E>(self, _v: &[u8]) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut found = None;
+        if let PathComponent::Index(index) = self.component {
+            let mut skipped = 0;
+            while skipped < index && seq.next_element::<IgnoredAny>()?.is_some() {
+                skipped += 1;
+            }
+            if skipped == index {
+                found = seq.next_element_seed(self.rest)?.flatten();
+            }
+        }
+        // The rest of the sequence still has to be consumed.
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(found)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut rest = Some(self.rest);
+        let mut found = None;
+        while let Some(is_component) = map.next_key_seed(KeyIs(self.component))? {
+            match rest.take_if(|_| is_component) {
+                Some(rest) => found = map.next_value_seed(rest)?,
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        // A tagged node: look into its value, like `Value::get_path` does.
+        let (IgnoredAny, variant) = data.variant()?;
+        de::VariantAccess::newtype_variant_seed(variant, self)
+    }
+}
+
+/// Whether a mapping key is the given path component: a string key for
+/// [PathComponent::Key], or an integer key for [PathComponent::Index].
+struct KeyIs<'p>(PathComponent<'p>);
+
+impl<'de> DeserializeSeed<'de> for KeyIs<'_> {
+    type Value = bool;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyIs<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a mapping key")
+    }
+
+    fn visit_bool<E>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(matches!(self.0, PathComponent::Index(index) if i64::try_from(index) == Ok(v)))
+    }
+
+    fn visit_i128<E>(self, _v: i128) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(matches!(self.0, PathComponent::Index(index) if u64::try_from(index) == Ok(v)))
+    }
+
+    fn visit_u128<E>(self, _v: u128) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(self.0 == PathComponent::Key(v))
+    }
+
+    fn visit_bytes<E>(self, _v: &[u8]) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(false)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        IgnoredAny.visit_seq(seq).map(|IgnoredAny| false)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        IgnoredAny.visit_map(map).map(|IgnoredAny| false)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        IgnoredAny.visit_enum(data).map(|IgnoredAny| false)
+    }
+}
+
+/// Deserialize an instance of type `T` from a string of YAML text read from
+/// the file at `filename`.
+///
+/// The filename is recorded in every span captured during the call, as if by
+/// [`with_filename`](crate::with_filename), and is prefixed to the message of
+/// any error returned.
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let err = dbt_serde_yaml::from_str_named::<BTreeMap<String, u32>>("a: b\n", "models.yml")
+///     .unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "models.yml: a: invalid type: string \"b\", expected u32 at line 1 column 4",
+/// );
+/// ```
+#[cfg(feature = "filename")]
+pub fn from_str_named<'de, T>(s: &'de str, filename: impl Into<std::path::PathBuf>) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let filename = Arc::new(filename.into());
+    let _scope = spanned::with_filename(Some(filename.to_path_buf()));
+    from_str(s).map_err(|err| error::in_file(err, filename))
+}
+
+/// Deserialize an instance of type `T` from the YAML file at `path`.
+///
+/// The whole file is read into memory before parsing, which keeps the source
+/// text of scalars available to spans and is faster than reading through
+/// [`from_reader`]. As with [`from_str_named`], `path` is recorded in every
+/// span captured during the call and prefixed to the message of any error
+/// returned, including a failure to read the file.
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Project {
+///     name: String,
+/// }
+///
+/// let project: Project = dbt_serde_yaml::read_file("dbt_project.yml")?;
+/// # Ok::<(), dbt_serde_yaml::Error>(())
+/// ```
+#[cfg(feature = "filename")]
+pub fn read_file<T>(path: impl AsRef<std::path::Path>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let filename = Arc::new(path.to_path_buf());
+    let res = std::fs::read(path)
+        .map_err(|err| error::new(ErrorImpl::Io(err)))
+        .and_then(|bytes| {
+            let _scope = spanned::with_filename(Some(path.to_path_buf()));
+            from_slice(&bytes)
+        });
+    res.map_err(|err| error::in_file(err, filename))
+}
+
+/// Deserialize a [Value] from the YAML file at `path`; see [`read_file`].
+#[cfg(feature = "filename")]
+pub fn read_file_value(path: impl AsRef<std::path::Path>) -> Result<Value> {
+    read_file(path)
 }
 
 /// Deserialize an instance of type `T` from an IO stream of YAML.
 ///
+/// The stream is read incrementally rather than into memory up front; see
+/// [`Deserializer::from_reader`].
+///
 /// This conversion can fail if the structure of the Value does not match the
 /// structure expected by `T`, for example if `T` is a struct type but the Value
 /// contains something other than a YAML map. It can also fail if the structure
@@ -1832,10 +3762,20 @@ where
     R: io::Read,
     T: DeserializeOwned,
 {
-    spanned::set_marker(spanned::Marker::start());
-    let res = T::deserialize(Deserializer::from_reader(rdr));
-    spanned::reset_marker();
-    res
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_reader(rdr))
+}
+
+/// Deserialize an instance of type `T` from an IO stream of YAML, with the
+/// given [Config] in place of the thread's current one.
+pub fn from_reader_with_config<R, T>(rdr: R, config: &Config) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let _scope = config.enter();
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_reader(rdr))
 }
 
 /// Deserialize an instance of type `T` from bytes of YAML text.
@@ -1851,8 +3791,17 @@ pub fn from_slice<'de, T>(v: &'de [u8]) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    spanned::set_marker(spanned::Marker::start());
-    let res = T::deserialize(Deserializer::from_slice(v));
-    spanned::reset_marker();
-    res
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_slice(v))
+}
+
+/// Deserialize an instance of type `T` from bytes of YAML text, with the given
+/// [Config] in place of the thread's current one.
+pub fn from_slice_with_config<'de, T>(v: &'de [u8], config: &Config) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let _scope = config.enter();
+    let _marker = spanned::enter_marker(spanned::Marker::start());
+    T::deserialize(Deserializer::from_slice(v))
 }
@@ -0,0 +1,82 @@
+//! Collection of references between documents, recorded while deserializing.
+//!
+//! Field transformers and custom `Deserialize` implementations that resolve
+//! references to other files or models can report them with
+//! [record_dependency]. Running a deserialization inside
+//! [collect_dependencies] gathers every reported reference, together with the
+//! path and span of the value it was found in, so building a dependency graph
+//! doesn't require a second pass over the input.
+
+use crate::config::{self, Sink};
+use crate::{callback, spanned, Span};
+
+/// A reference from a location in the input to some other entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyEdge {
+    /// The path of the value containing the reference, like `models[0].ref`.
+    pub path: String,
+    /// The span of the value containing the reference.
+    pub span: Span,
+    /// The referenced identifier.
+    pub target: String,
+}
+
+/// Runs `f`, collecting every dependency reported through
+/// [record_dependency] while it runs.
+///
+/// This sets [`Config::dependencies`](crate::Config::dependencies) in the
+/// thread's current config while `f` runs. Calls may be nested; each call
+/// only returns the dependencies reported within its own scope.
+///
+/// ```
+/// use dbt_serde_yaml::{collect_dependencies, record_dependency, Value};
+///
+/// let value: Value = dbt_serde_yaml::from_str("model: \"{{ ref('orders') }}\"").unwrap();
+/// let (result, edges) = collect_dependencies(|| {
+///     value.into_typed::<Value, _, _>(
+///         |_, _, _| {},
+///         |v| {
+///             if let Some(s) = v.as_str() {
+///                 if let Some(name) = s.strip_prefix("{{ ref('") {
+///                     record_dependency(name.trim_end_matches("') }}"));
+///                 }
+///             }
+///             Ok(None)
+///         },
+///     )
+/// });
+///
+/// assert!(result.is_ok());
+/// assert_eq!(edges.len(), 1);
+/// assert_eq!(edges[0].path, "model");
+/// assert_eq!(edges[0].target, "orders");
+/// ```
+pub fn collect_dependencies<R>(f: impl FnOnce() -> R) -> (R, Vec<DependencyEdge>) {
+    let sink = Sink::new();
+    let result = config::with_changed(|config| config.dependencies = Some(sink.clone()), f);
+    (result, sink.take())
+}
+
+/// Reports a reference to `target` from the value currently being
+/// deserialized.
+///
+/// When called from a callback, such as a field transformer, the reference is
+/// attributed to the value the callback was invoked for. Otherwise it is
+/// attributed to the current source location. Does nothing outside of [collect_dependencies].
+pub fn record_dependency(target: impl Into<String>) {
+    let Some(dependencies) = config::sink(|config| &config.dependencies) else {
+        return;
+    };
+    let (path, span) = callback::with_current(|path, span| (path.to_string(), span.clone()))
+        .unwrap_or_else(|| {
+            (
+                ".".to_string(),
+                spanned::get_marker().map(Span::from).unwrap_or_default(),
+            )
+        });
+    dependencies.push(DependencyEdge {
+        path,
+        span,
+        target: target.into(),
+    });
+}
@@ -1,10 +1,15 @@
+use crate::libyaml::error::Missing;
 use crate::libyaml::{emitter, error as libyaml};
-use crate::path::Path;
+use crate::path::{OwnedPath, Path};
 use crate::{Marker, Span};
 use serde::{de, ser};
+use std::any::TypeId;
+use std::cell::OnceCell;
 use std::error::Error as StdError;
 use std::fmt::{self, Debug, Display};
 use std::io;
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
 use std::result;
 use std::string;
 use std::sync::Arc;
@@ -20,15 +25,20 @@ pub(crate) enum ErrorImpl {
     Message(String, Option<Pos>),
 
     Libyaml(libyaml::Error),
+    Syntax(libyaml::Error, Box<SyntaxHint>),
     Io(io::Error),
     FromUtf8(string::FromUtf8Error),
 
     EndOfStream,
     MoreThanOneDocument,
     RecursionLimitExceeded(Marker),
-    RepetitionLimitExceeded,
-    BytesUnsupported,
+    RepetitionLimitExceeded(Marker),
+    NodeLimitExceeded(Marker),
+    ScalarBytesLimitExceeded(Marker),
+    TimeLimitExceeded(Marker),
     UnknownAnchor(Marker),
+    DuplicateAnchor(String, Marker),
+    TabInIndentation(Marker),
     SerializeNestedEnum,
     ScalarInMerge,
     TaggedInMerge,
@@ -36,17 +46,256 @@ pub(crate) enum ErrorImpl {
     SequenceInMergeElement,
     EmptyTag,
     FailedToParseNumber,
+    NumberOutOfRange(String, &'static str),
+    FailedToParsePath,
+    NoValueAtPath(String),
+    NotACollection(String),
+    CallbackPanicked(String, Pos),
+    CallbackFailed(Box<dyn StdError + 'static + Send + Sync>, Pos),
     FlattenNotMapping,
 
     External(Box<dyn StdError + 'static + Send + Sync>),
 
     Shared(Arc<ErrorImpl>),
+    #[cfg(feature = "filename")]
+    InFile(Arc<std::path::PathBuf>, Box<ErrorImpl>),
 }
 
 #[derive(Debug)]
 pub(crate) struct Pos {
     span: Span,
     path: String,
+    segments: Option<Vec<Segment>>,
+}
+
+/// A `Send + Sync` copy of a [Path], from which an [OwnedPath] can be rebuilt.
+#[derive(Debug)]
+pub(crate) enum Segment {
+    Seq(usize),
+    Map(String),
+    Alias,
+    Unknown,
+}
+
+fn segments(path: &Path) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut path = path;
+    loop {
+        path = match path {
+            Path::Root => break,
+            Path::Seq { parent, index } => {
+                segments.push(Segment::Seq(*index));
+                parent
+            }
+            Path::Map { parent, key } => {
+                segments.push(Segment::Map(key.to_string()));
+                parent
+            }
+            Path::Alias { parent } => {
+                segments.push(Segment::Alias);
+                parent
+            }
+            Path::Unknown { parent } => {
+                segments.push(Segment::Unknown);
+                parent
+            }
+        };
+    }
+    segments.reverse();
+    segments
+}
+
+fn owned_path(segments: &[Segment]) -> OwnedPath {
+    segments.iter().fold(OwnedPath::Root, |parent, segment| {
+        let parent = Box::new(parent);
+        let borrowed = OnceCell::new();
+        match segment {
+            Segment::Seq(index) => OwnedPath::Seq {
+                parent,
+                index: *index,
+                borrowed,
+            },
+            Segment::Map(key) => OwnedPath::Map {
+                parent,
+                key: key.clone(),
+                borrowed,
+            },
+            Segment::Alias => OwnedPath::Alias { parent, borrowed },
+            Segment::Unknown => OwnedPath::Unknown { parent, borrowed },
+        }
+    })
+}
+
+/// Structured context for a YAML syntax error, for editor integrations that
+/// want to offer recovery or completions at the error position.
+///
+/// Returned by [`Error::syntax_hint`].
+///
+/// ```
+/// use dbt_serde_yaml::{ExpectedToken, SyntaxContext, Value};
+///
+/// let yaml = "models:\n  - name: orders\n    columns: [id, name\n";
+/// let err = dbt_serde_yaml::from_str::<Value>(yaml).unwrap_err();
+///
+/// let hint = err.syntax_hint().unwrap();
+/// assert_eq!(hint.context(), SyntaxContext::FlowSequenceEntry);
+/// assert_eq!(
+///     hint.expected(),
+///     [ExpectedToken::FlowEntry, ExpectedToken::FlowSequenceEnd],
+/// );
+/// assert_eq!(hint.path().to_string(), "models[0].columns[2]");
+/// ```
+#[derive(Debug)]
+pub struct SyntaxHint {
+    context: SyntaxContext,
+    expected: Vec<ExpectedToken>,
+    segments: Vec<Segment>,
+}
+
+/// The construct the parser was in the middle of when a syntax error
+/// occurred.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SyntaxContext {
+    /// The top level of the stream, outside of any collection.
+    Document,
+    /// A key of a block mapping.
+    BlockMappingKey,
+    /// A value of a block mapping.
+    BlockMappingValue,
+    /// An entry of a block sequence.
+    BlockSequenceEntry,
+    /// A key of a flow mapping `{...}`.
+    FlowMappingKey,
+    /// A value of a flow mapping `{...}`.
+    FlowMappingValue,
+    /// An entry of a flow sequence `[...]`.
+    FlowSequenceEntry,
+}
+
+/// A token that would have been valid at the position of a syntax error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExpectedToken {
+    /// Any node: a scalar, an alias, or the start of a collection.
+    Node,
+    /// A mapping key.
+    Key,
+    /// The `:` separating a mapping key from its value.
+    Value,
+    /// The `-` introducing a block sequence entry.
+    BlockEntry,
+    /// The `,` separating entries of a flow collection.
+    FlowEntry,
+    /// The `]` closing a flow sequence.
+    FlowSequenceEnd,
+    /// The `}` closing a flow mapping.
+    FlowMappingEnd,
+    /// The `---` marker starting a document.
+    DocumentStart,
+}
+
+impl SyntaxHint {
+    /// The construct that was being parsed.
+    pub fn context(&self) -> SyntaxContext {
+        self.context
+    }
+
+    /// The tokens that would have been valid at the error position, as far as
+    /// they can be determined.
+    pub fn expected(&self) -> &[ExpectedToken] {
+        &self.expected
+    }
+
+    /// The path of the node that was being parsed. For a mapping key this is
+    /// the path of the mapping itself.
+    pub fn path(&self) -> OwnedPath {
+        owned_path(&self.segments)
+    }
+}
+
+impl SyntaxContext {
+    /// The tokens that are valid at the start of this construct.
+    fn expected(self) -> Vec<ExpectedToken> {
+        match self {
+            SyntaxContext::Document | SyntaxContext::BlockMappingValue => {
+                vec![ExpectedToken::Node]
+            }
+            SyntaxContext::BlockMappingKey => vec![ExpectedToken::Key],
+            SyntaxContext::BlockSequenceEntry => vec![ExpectedToken::BlockEntry],
+            SyntaxContext::FlowMappingKey => {
+                vec![ExpectedToken::Key, ExpectedToken::FlowMappingEnd]
+            }
+            SyntaxContext::FlowMappingValue => vec![
+                ExpectedToken::Node,
+                ExpectedToken::FlowEntry,
+                ExpectedToken::FlowMappingEnd,
+            ],
+            SyntaxContext::FlowSequenceEntry => vec![
+                ExpectedToken::Node,
+                ExpectedToken::FlowEntry,
+                ExpectedToken::FlowSequenceEnd,
+            ],
+        }
+    }
+}
+
+impl Display for ExpectedToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ExpectedToken::Node => "a node",
+            ExpectedToken::Key => "a mapping key",
+            ExpectedToken::Value => "':'",
+            ExpectedToken::BlockEntry => "'-'",
+            ExpectedToken::FlowEntry => "','",
+            ExpectedToken::FlowSequenceEnd => "']'",
+            ExpectedToken::FlowMappingEnd => "'}'",
+            ExpectedToken::DocumentStart => "'---'",
+        })
+    }
+}
+
+/// Constructs a syntax error raised while parsing `context`, at the node
+/// whose path is given by `segments`.
+pub(crate) fn syntax(err: libyaml::Error, context: SyntaxContext, segments: Vec<Segment>) -> Error {
+    // A missing token makes the hint more specific than what is valid at the
+    // start of the context.
+    let expected = match err.missing() {
+        Some(Missing::Key) => vec![ExpectedToken::Key],
+        Some(Missing::BlockEntry) => vec![ExpectedToken::BlockEntry],
+        Some(Missing::FlowSequenceEntry) => {
+            vec![ExpectedToken::FlowEntry, ExpectedToken::FlowSequenceEnd]
+        }
+        Some(Missing::FlowMappingEntry) => {
+            vec![ExpectedToken::FlowEntry, ExpectedToken::FlowMappingEnd]
+        }
+        Some(Missing::Node) => vec![ExpectedToken::Node],
+        Some(Missing::DocumentStart) => vec![ExpectedToken::DocumentStart],
+        Some(Missing::Value) => vec![ExpectedToken::Value],
+        None => context.expected(),
+    };
+    let hint = SyntaxHint {
+        context,
+        expected,
+        segments,
+    };
+    new(ErrorImpl::Syntax(err, Box::new(hint)))
+}
+
+impl Pos {
+    fn new(span: Span) -> Self {
+        Pos {
+            span,
+            path: ".".to_string(),
+            segments: None,
+        }
+    }
+
+    /// Whether the path is unknown or no more specific than the root, and may
+    /// be filled in by an enclosing deserializer.
+    fn path_is_unknown(&self) -> bool {
+        self.segments.as_ref().is_none_or(Vec::is_empty)
+    }
 }
 
 impl Error {
@@ -78,24 +327,158 @@ impl Error {
         self.0.span()
     }
 
+    /// Returns the logical path of the value the error was raised at, like
+    /// `models[2].config.tags`, if one was recorded.
+    ///
+    /// The path is available for errors raised while deserializing from text
+    /// or from a [`Value`](crate::Value), and does not depend on the span being
+    /// valid. When deserializing from text, it also covers errors that a
+    /// `Deserialize` implementation raises after reading its value, such as a
+    /// failed `#[serde(try_from)]` conversion.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// # use std::collections::BTreeMap;
+    /// let value: Value = dbt_serde_yaml::from_str("models: [{tags: [a]}, {tags: 1}]").unwrap();
+    /// let err = value
+    ///     .into_typed::<BTreeMap<String, Vec<BTreeMap<String, Vec<String>>>>, _, _>(
+    ///         |_, _, _| {},
+    ///         |_| Ok(None),
+    ///     )
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(err.path().unwrap().to_string(), "models[1].tags");
+    /// ```
+    pub fn path(&self) -> Option<OwnedPath> {
+        self.0.path()
+    }
+
+    /// Returns structured context for a YAML syntax error: what was being
+    /// parsed and which tokens would have been valid at the error position.
+    ///
+    /// Returns `None` if this is not a syntax error, or if the error was raised
+    /// while serializing.
+    pub fn syntax_hint(&self) -> Option<&SyntaxHint> {
+        match self.0.as_ref() {
+            ErrorImpl::Syntax(_, hint) => Some(hint),
+            ErrorImpl::Shared(err) => match err.as_ref() {
+                ErrorImpl::Syntax(_, hint) => Some(hint),
+                _ => None,
+            },
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => match err.unshared() {
+                ErrorImpl::Syntax(_, hint) => Some(hint),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns true if this error was caused by a panic in a user-supplied
+    /// callback, such as an unused key callback or a field transformer.
+    ///
+    /// Such panics are caught so that they don't leave the deserializer's
+    /// thread-local state inconsistent; the error message includes the panic
+    /// message and the path at which the callback was invoked.
+    pub fn is_callback_panic(&self) -> bool {
+        match self.0.as_ref() {
+            ErrorImpl::CallbackPanicked(..) => true,
+            ErrorImpl::Shared(err) => matches!(err.as_ref(), ErrorImpl::CallbackPanicked(..)),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => {
+                matches!(err.unshared(), ErrorImpl::CallbackPanicked(..))
+            }
+            _ => false,
+        }
+    }
+
     /// Unwraps the error and returns the underlying error if it is an external
-    /// error; otherwise returns `None`.
+    /// error, including one returned by a fallible callback such as that of
+    /// [`Value::try_into_typed`](crate::Value::try_into_typed); otherwise
+    /// returns `None`.
     pub fn into_external(self) -> Option<Box<dyn StdError + 'static + Send + Sync>> {
-        if let ErrorImpl::External(err) = *self.0 {
-            Some(err)
-        } else {
-            None
+        match *self.0 {
+            ErrorImpl::External(err) | ErrorImpl::CallbackFailed(err, _) => Some(err),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => Error(err).into_external(),
+            _ => None,
         }
     }
 
+    /// Returns the file the error was raised in, if known: the one given to
+    /// [`from_str_named`](crate::from_str_named) or
+    /// [`read_file`](crate::read_file), or else the one recorded in the span
+    /// of the error by [`with_filename`](crate::with_filename).
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// # use std::path::Path;
+    /// let err = dbt_serde_yaml::from_str_named::<Value>("a: [", "models/schema.yml").unwrap_err();
+    /// assert_eq!(err.filename(), Some(Path::new("models/schema.yml")));
+    /// ```
+    #[cfg(feature = "filename")]
+    pub fn filename(&self) -> Option<&std::path::Path> {
+        self.0.filename()
+    }
+
+    /// Returns the error message prefixed by where the error was raised, in
+    /// the `file:line:column: message` form that editors and CI annotations
+    /// recognize.
+    ///
+    /// The file is included if known (see `Error::filename` with the
+    /// `filename` feature), and the line and column if the error has a
+    /// location. An error with neither displays as its bare message.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// # use std::collections::BTreeMap;
+    /// let err = dbt_serde_yaml::from_str::<BTreeMap<String, u32>>("a: b").unwrap_err();
+    /// assert_eq!(
+    ///     err.display_with_location().to_string(),
+    ///     "1:4: a: invalid type: string \"b\", expected u32",
+    /// );
+    ///
+    /// let err = dbt_serde_yaml::from_str::<Value>("a: @b").unwrap_err();
+    /// assert_eq!(
+    ///     err.display_with_location().to_string(),
+    ///     "1:4: found character that cannot start any token, while scanning for the next token",
+    /// );
+    /// ```
+    pub fn display_with_location(&self) -> impl Display + use<'_> {
+        struct WithLocation<'a>(&'a Error);
+        impl Display for WithLocation<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut located = false;
+                #[cfg(feature = "filename")]
+                if let Some(filename) = self.0.filename() {
+                    write!(f, "{}:", filename.display())?;
+                    located = true;
+                }
+                if let Some(mark) = self.0.location() {
+                    if mark.line() != 0 || mark.column() != 0 {
+                        write!(f, "{}:{}:", mark.line(), mark.column())?;
+                        located = true;
+                    }
+                }
+                if located {
+                    f.write_str(" ")?;
+                }
+                self.0 .0.message_no_location(f)
+            }
+        }
+        WithLocation(self)
+    }
+
     /// Returns the error message without the location information.
     pub fn display_no_mark(&self) -> impl Display + use<'_> {
         struct MessageNoMark<'a>(&'a ErrorImpl);
         impl Display for MessageNoMark<'_> {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 match &self.0 {
-                    ErrorImpl::Libyaml(err) => Display::fmt(err, f),
+                    ErrorImpl::Libyaml(err) | ErrorImpl::Syntax(err, _) => Display::fmt(err, f),
                     ErrorImpl::Shared(err) => err.display(f),
+                    #[cfg(feature = "filename")]
+                    ErrorImpl::InFile(_, err) => MessageNoMark(err).fmt(f),
                     _ => self.0.message_no_mark(f),
                 }
             }
@@ -112,21 +495,136 @@ pub(crate) fn shared(shared: Arc<ErrorImpl>) -> Error {
     Error(Box::new(ErrorImpl::Shared(shared)))
 }
 
+/// Attributes `error` to the file at `filename`, which is prefixed to its
+/// message and recorded in its span.
+#[cfg(feature = "filename")]
+pub(crate) fn in_file(error: Error, filename: Arc<std::path::PathBuf>) -> Error {
+    Error(Box::new(ErrorImpl::InFile(filename, error.0)))
+}
+
 pub(crate) fn fix_mark(mut error: Error, mark: libyaml::Mark, path: Path) -> Error {
-    if let ErrorImpl::Message(_, none @ None) = error.0.as_mut() {
-        let span = Span::from(Marker::from(mark));
+    match error.0.as_mut() {
+        ErrorImpl::Message(_, none @ None) => {
+            let span = Span::from(Marker::from(mark));
 
-        #[cfg(feature = "filename")]
-        let span = span.maybe_capture_filename();
+            #[cfg(feature = "filename")]
+            let span = span.maybe_capture_filename();
 
-        *none = Some(Pos {
-            span,
-            path: path.to_string(),
-        });
+            *none = Some(Pos {
+                span,
+                path: path.to_string(),
+                segments: Some(segments(&path)),
+            });
+        }
+        // The error was raised with a span via `custom_at`, or by a Value
+        // deserializer, but without knowledge of the displayed path.
+        ErrorImpl::Message(_, Some(pos)) if pos.path == "." => {
+            pos.path = path.to_string();
+            if pos.path_is_unknown() {
+                pos.segments = Some(segments(&path));
+            }
+        }
+        _ => {}
     }
     error
 }
 
+/// Constructs a custom error located at `span`.
+///
+/// The span is only kept if `E` is this crate's [`Error`]; other error types
+/// receive the plain message.
+pub(crate) fn custom_at<E: de::Error>(msg: impl Display, span: &Span) -> E {
+    if type_id_of::<E>() != TypeId::of::<Error>() {
+        return E::custom(msg);
+    }
+    let pos = Some(span)
+        .filter(|span| span.is_valid())
+        .cloned()
+        .map(Pos::new);
+    let error = ManuallyDrop::new(new(ErrorImpl::Message(msg.to_string(), pos)));
+    // SAFETY: `E` is `Error`, which has no lifetimes that could have been
+    // erased, so the error is moved into a value of its own type.
+    unsafe { mem::transmute_copy::<Error, E>(&error) }
+}
+
+/// Returns the [TypeId] of `T` with its lifetimes erased, so that a type
+/// that is not `'static` can be compared with one that is.
+fn type_id_of<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    // SAFETY: only the lifetime of the trait object is extended, and it is
+    // not used for anything but its type, which `TypeId` cannot tell apart
+    // by lifetime.
+    NonStaticAny::type_id(unsafe {
+        mem::transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(&phantom)
+    })
+}
+
+pub(crate) fn callback_panicked(message: String, span: Span, path: &Path) -> Error {
+    new(ErrorImpl::CallbackPanicked(
+        message,
+        Pos {
+            span,
+            path: path.to_string(),
+            segments: Some(segments(path)),
+        },
+    ))
+}
+
+/// A callback run while [`callback::MAX_DEPTH`](crate::callback::MAX_DEPTH)
+/// others are already running on the thread.
+pub(crate) fn callback_recursion_limit_exceeded(span: Span, path: &Path) -> Error {
+    new(ErrorImpl::Message(
+        "callback recursion limit exceeded".to_owned(),
+        Some(Pos {
+            span,
+            path: path.to_string(),
+            segments: Some(segments(path)),
+        }),
+    ))
+}
+
+/// An unused mapping key rejected by [`UnusedKeys::Deny`](crate::value::UnusedKeys::Deny).
+pub(crate) fn unknown_field(key: &crate::Value, path: &Path) -> Error {
+    new(ErrorImpl::Message(
+        format!("unknown field `{}`", key),
+        Some(Pos {
+            span: key.span().clone(),
+            path: path.to_string(),
+            segments: Some(segments(path)),
+        }),
+    ))
+}
+
+pub(crate) fn callback_failed(
+    err: Box<dyn StdError + 'static + Send + Sync>,
+    span: Span,
+    path: &Path,
+) -> Error {
+    new(ErrorImpl::CallbackFailed(
+        err,
+        Pos {
+            span,
+            path: path.to_string(),
+            segments: Some(segments(path)),
+        },
+    ))
+}
+
 pub(crate) fn set_span(mut error: Error, span: Span) -> Error {
     if let ErrorImpl::Message(_, pos) = error.0.as_mut() {
         if let Some(pos) = pos {
@@ -134,16 +632,53 @@ pub(crate) fn set_span(mut error: Error, span: Span) -> Error {
                 pos.span = span;
             }
         } else {
-            *pos = Some(Pos {
-                span,
-                path: ".".to_string(),
-            })
+            *pos = Some(Pos::new(span))
+        }
+    }
+    error
+}
+
+/// Like [set_span], but also records `path` as the logical path of the error
+/// if a more specific one isn't known yet.
+pub(crate) fn set_location(error: Error, span: Span, path: &Path) -> Error {
+    let mut error = set_span(error, span);
+    if let ErrorImpl::Message(_, Some(pos)) = error.0.as_mut() {
+        if pos.path_is_unknown() {
+            pos.segments = Some(segments(path));
+        }
+    }
+    error
+}
+
+/// Records `path` as the logical and displayed path of the error if neither
+/// is known yet, without touching its span.
+pub(crate) fn set_path(mut error: Error, path: &Path) -> Error {
+    match error.0.as_mut() {
+        ErrorImpl::Message(_, none @ None) => {
+            *none = Some(Pos {
+                span: Span::zero(),
+                path: path.to_string(),
+                segments: Some(segments(path)),
+            });
         }
+        ErrorImpl::Message(_, Some(pos)) if pos.path == "." => {
+            pos.path = path.to_string();
+            if pos.path_is_unknown() {
+                pos.segments = Some(segments(path));
+            }
+        }
+        _ => {}
     }
     error
 }
 
 impl Error {
+    /// Whether this is serde's `invalid type` error, raised when a value has a
+    /// different shape than the type being deserialized.
+    pub(crate) fn is_invalid_type(&self) -> bool {
+        matches!(self.0.as_ref(), ErrorImpl::Message(msg, _) if msg.starts_with("invalid type: "))
+    }
+
     pub(crate) fn shared(self) -> Arc<ErrorImpl> {
         if let ErrorImpl::Shared(err) = *self.0 {
             err
@@ -207,28 +742,96 @@ impl de::Error for Error {
 }
 
 impl ErrorImpl {
+    /// The error itself, or the one it shares.
+    #[cfg(feature = "filename")]
+    fn unshared(&self) -> &ErrorImpl {
+        match self {
+            ErrorImpl::Shared(err) => err,
+            _ => self,
+        }
+    }
+
     fn location(&self) -> Option<Marker> {
         self.span().map(|span| span.start)
     }
 
+    #[cfg(feature = "filename")]
+    fn filename(&self) -> Option<&std::path::Path> {
+        match self {
+            ErrorImpl::InFile(filename, _) => Some(filename),
+            ErrorImpl::Message(_, Some(Pos { span, .. }))
+            | ErrorImpl::CallbackPanicked(_, Pos { span, .. })
+            | ErrorImpl::CallbackFailed(_, Pos { span, .. }) => span.get_filename(),
+            ErrorImpl::Shared(err) => err.filename(),
+            _ => None,
+        }
+    }
+
+    /// Writes the message of the error without any of the locations that
+    /// libyaml includes in the messages of syntax errors.
+    fn message_no_location(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorImpl::Libyaml(err) | ErrorImpl::Syntax(err, _) => {
+                write!(f, "{}", err.problem())?;
+                if let Some(context) = err.context() {
+                    write!(f, ", {}", context)?;
+                }
+                Ok(())
+            }
+            ErrorImpl::Shared(err) => err.message_no_location(f),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => err.message_no_location(f),
+            _ => self.message_no_mark(f),
+        }
+    }
+
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
             ErrorImpl::Io(err) => err.source(),
             ErrorImpl::FromUtf8(err) => err.source(),
             ErrorImpl::Shared(err) => err.source(),
-            ErrorImpl::External(err) => err.source(),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => err.source(),
+            ErrorImpl::External(err) | ErrorImpl::CallbackFailed(err, _) => err.source(),
             _ => None,
         }
     }
 
     fn span(&self) -> Option<Span> {
         match self {
-            ErrorImpl::Message(_, Some(Pos { span, path: _ })) => Some(span.clone()),
-            ErrorImpl::RecursionLimitExceeded(mark) | ErrorImpl::UnknownAnchor(mark) => {
-                Some(Span::from(*mark))
+            ErrorImpl::Message(_, Some(Pos { span, .. }))
+            | ErrorImpl::CallbackPanicked(_, Pos { span, .. })
+            | ErrorImpl::CallbackFailed(_, Pos { span, .. }) => Some(span.clone()),
+            ErrorImpl::RecursionLimitExceeded(mark)
+            | ErrorImpl::RepetitionLimitExceeded(mark)
+            | ErrorImpl::NodeLimitExceeded(mark)
+            | ErrorImpl::ScalarBytesLimitExceeded(mark)
+            | ErrorImpl::TimeLimitExceeded(mark)
+            | ErrorImpl::UnknownAnchor(mark)
+            | ErrorImpl::DuplicateAnchor(_, mark)
+            | ErrorImpl::TabInIndentation(mark) => Some(Span::from(*mark)),
+            ErrorImpl::Libyaml(err) | ErrorImpl::Syntax(err, _) => {
+                Some(Marker::from(err.mark()).into())
             }
-            ErrorImpl::Libyaml(err) => Some(Marker::from(err.mark()).into()),
             ErrorImpl::Shared(err) => err.span(),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(filename, err) => err
+                .span()
+                .map(|span| span.with_filename(Arc::clone(filename))),
+            _ => None,
+        }
+    }
+
+    fn path(&self) -> Option<OwnedPath> {
+        match self {
+            ErrorImpl::Message(_, Some(Pos { segments, .. }))
+            | ErrorImpl::CallbackPanicked(_, Pos { segments, .. })
+            | ErrorImpl::CallbackFailed(_, Pos { segments, .. }) => {
+                segments.as_deref().map(owned_path)
+            }
+            ErrorImpl::Shared(err) => err.path(),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(_, err) => err.path(),
             _ => None,
         }
     }
@@ -236,13 +839,13 @@ impl ErrorImpl {
     fn message_no_mark(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ErrorImpl::Message(msg, None) => f.write_str(msg),
-            ErrorImpl::Message(msg, Some(Pos { span: _, path })) => {
+            ErrorImpl::Message(msg, Some(Pos { path, .. })) => {
                 if path != "." {
                     write!(f, "{}: ", path)?;
                 }
                 f.write_str(msg)
             }
-            ErrorImpl::Libyaml(_) => unreachable!(),
+            ErrorImpl::Libyaml(_) | ErrorImpl::Syntax(..) => unreachable!(),
             ErrorImpl::Io(err) => Display::fmt(err, f),
             ErrorImpl::FromUtf8(err) => Display::fmt(err, f),
             ErrorImpl::EndOfStream => f.write_str("EOF while parsing a value"),
@@ -250,11 +853,15 @@ impl ErrorImpl {
                 "deserializing from YAML containing more than one document is not supported",
             ),
             ErrorImpl::RecursionLimitExceeded(_mark) => f.write_str("recursion limit exceeded"),
-            ErrorImpl::RepetitionLimitExceeded => f.write_str("repetition limit exceeded"),
-            ErrorImpl::BytesUnsupported => {
-                f.write_str("serialization and deserialization of bytes in YAML is not implemented")
-            }
+            ErrorImpl::RepetitionLimitExceeded(_mark) => f.write_str("repetition limit exceeded"),
+            ErrorImpl::NodeLimitExceeded(_mark) => f.write_str("node limit exceeded"),
+            ErrorImpl::ScalarBytesLimitExceeded(_mark) => f.write_str("scalar size limit exceeded"),
+            ErrorImpl::TimeLimitExceeded(_mark) => f.write_str("time limit exceeded"),
             ErrorImpl::UnknownAnchor(_mark) => f.write_str("unknown anchor"),
+            ErrorImpl::DuplicateAnchor(name, _mark) => write!(f, "duplicate anchor `{}`", name),
+            ErrorImpl::TabInIndentation(_mark) => {
+                f.write_str("found a tab character in indentation")
+            }
             ErrorImpl::SerializeNestedEnum => {
                 f.write_str("serializing nested enums in YAML is not supported yet")
             }
@@ -270,16 +877,43 @@ impl ErrorImpl {
             }
             ErrorImpl::EmptyTag => f.write_str("empty YAML tag is not allowed"),
             ErrorImpl::FailedToParseNumber => f.write_str("failed to parse YAML number"),
+            ErrorImpl::NumberOutOfRange(number, ty) => {
+                write!(f, "cannot represent {} as {}", number, ty)
+            }
+            ErrorImpl::FailedToParsePath => f.write_str("failed to parse path"),
+            ErrorImpl::NoValueAtPath(path) => write!(f, "no value at path {}", path),
+            ErrorImpl::NotACollection(path) => {
+                write!(f, "expected a mapping or sequence at path {}", path)
+            }
+            ErrorImpl::CallbackPanicked(msg, Pos { path, .. }) => {
+                if path != "." {
+                    write!(f, "{}: ", path)?;
+                }
+                write!(f, "callback panicked: {}", msg)
+            }
+            ErrorImpl::CallbackFailed(err, Pos { path, .. }) => {
+                if path != "." {
+                    write!(f, "{}: ", path)?;
+                }
+                Display::fmt(err.as_ref(), f)
+            }
             ErrorImpl::External(err) => Display::fmt(err.as_ref(), f),
             ErrorImpl::Shared(_) => unreachable!(),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(..) => unreachable!(),
             ErrorImpl::FlattenNotMapping => write!(f, "expected the flatten field to be a mapping"),
         }
     }
 
     fn display(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ErrorImpl::Libyaml(err) => Display::fmt(err, f),
+            ErrorImpl::Libyaml(err) | ErrorImpl::Syntax(err, _) => Display::fmt(err, f),
             ErrorImpl::Shared(err) => err.display(f),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(filename, err) => {
+                write!(f, "{}: ", filename.display())?;
+                err.display(f)
+            }
             _ => {
                 self.message_no_mark(f)?;
                 if let Some(mark) = self.location() {
@@ -294,8 +928,13 @@ impl ErrorImpl {
 
     fn debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ErrorImpl::Libyaml(err) => Debug::fmt(err, f),
+            ErrorImpl::Libyaml(err) | ErrorImpl::Syntax(err, _) => Debug::fmt(err, f),
             ErrorImpl::Shared(err) => err.debug(f),
+            #[cfg(feature = "filename")]
+            ErrorImpl::InFile(filename, err) => {
+                write!(f, "{}: ", filename.display())?;
+                err.debug(f)
+            }
             _ => {
                 f.write_str("Error(")?;
                 struct MessageNoMark<'a>(&'a ErrorImpl);
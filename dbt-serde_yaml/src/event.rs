@@ -0,0 +1,221 @@
+//! The events of the YAML parser, for consumers that want to walk a document
+//! without building a [`Value`](crate::Value) out of it.
+//!
+//! [`events`] yields the scalars, aliases and the starts and ends of the
+//! collections of each document in the order they appear in the source, each
+//! with its [`Span`], tag and anchor. Nothing is kept once an event has been
+//! yielded, so memory use doesn't grow with the size of the document.
+//!
+//! Unlike [`from_str`](crate::from_str), nothing is resolved: aliases are
+//! reported as such rather than replaced by the node they refer to, `<<`
+//! merge keys are ordinary scalars, and duplicate keys are not detected.
+//!
+//! ```
+//! use dbt_serde_yaml::event::{events, Event};
+//!
+//! let yaml = "
+//! models:
+//!   - name: orders
+//!     columns: [id, status]
+//! ";
+//!
+//! // The keys of all mappings, with the line each is on.
+//! let mut keys = Vec::new();
+//! // For each open collection, whether it is a mapping whose next node is a
+//! // key.
+//! let mut stack: Vec<Option<bool>> = Vec::new();
+//! for event in events(yaml) {
+//!     let (event, span) = event.unwrap();
+//!     match event {
+//!         Event::DocumentStart | Event::DocumentEnd => continue,
+//!         Event::SequenceEnd | Event::MappingEnd => {
+//!             stack.pop();
+//!             continue;
+//!         }
+//!         _ => {}
+//!     }
+//!     let is_key = match stack.last_mut() {
+//!         Some(Some(next_is_key)) => std::mem::replace(next_is_key, !*next_is_key),
+//!         _ => false,
+//!     };
+//!     match event {
+//!         Event::Scalar(scalar) if is_key => keys.push((scalar.value, span.start.line)),
+//!         Event::MappingStart(_) => stack.push(Some(true)),
+//!         Event::SequenceStart(_) => stack.push(None),
+//!         _ => {}
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     keys,
+//!     [
+//!         ("models".to_owned(), 2),
+//!         ("name".to_owned(), 3),
+//!         ("columns".to_owned(), 4),
+//!     ],
+//! );
+//! ```
+
+use crate::error::Error;
+use crate::libyaml::parser::{self, Parser};
+use crate::spanned::{Marker, Span};
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+
+/// Parses `source` into a stream of events.
+///
+/// The iterator stops after the last document, or after the first error.
+pub fn events(source: &str) -> Events<'_> {
+    Events {
+        parser: Some(Parser::new(Cow::Borrowed(source.as_bytes()))),
+    }
+}
+
+/// The iterator returned by [`events`].
+pub struct Events<'input> {
+    parser: Option<Parser<'input>>,
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<(Event, Span), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let parser = self.parser.as_mut()?;
+            let (event, start, end) = match parser.next_with_end() {
+                Ok(next) => next,
+                Err(err) => {
+                    self.parser = None;
+                    return Some(Err(Error::from(err)));
+                }
+            };
+            let event = match event {
+                parser::Event::StreamStart => continue,
+                parser::Event::StreamEnd => {
+                    self.parser = None;
+                    return None;
+                }
+                parser::Event::DocumentStart => Event::DocumentStart,
+                parser::Event::DocumentEnd => Event::DocumentEnd,
+                parser::Event::Alias(anchor) => Event::Alias(lossy(&anchor.0)),
+                parser::Event::Scalar(scalar) => Event::Scalar(Scalar {
+                    value: String::from_utf8(scalar.value.to_vec())
+                        .unwrap_or_else(|err| lossy(err.as_bytes())),
+                    style: ScalarStyle::from_parser(scalar.style),
+                    tag: scalar.tag.map(|tag| lossy(&tag)),
+                    anchor: scalar.anchor.map(|anchor| lossy(&anchor.0)),
+                }),
+                parser::Event::SequenceStart(start) => Event::SequenceStart(CollectionStart {
+                    flow: start.flow,
+                    tag: start.tag.map(|tag| lossy(&tag)),
+                    anchor: start.anchor.map(|anchor| lossy(&anchor.0)),
+                }),
+                parser::Event::SequenceEnd => Event::SequenceEnd,
+                parser::Event::MappingStart(start) => Event::MappingStart(CollectionStart {
+                    flow: start.flow,
+                    tag: start.tag.map(|tag| lossy(&tag)),
+                    anchor: start.anchor.map(|anchor| lossy(&anchor.0)),
+                }),
+                parser::Event::MappingEnd => Event::MappingEnd,
+            };
+            let span = Span::new(Marker::from(start), Marker::from(end));
+            #[cfg(feature = "filename")]
+            let span = span.maybe_capture_filename();
+            return Some(Ok((event, span)));
+        }
+    }
+}
+
+impl Debug for Events<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Events")
+            .field("finished", &self.parser.is_none())
+            .finish()
+    }
+}
+
+/// An event of the YAML parser.
+///
+/// The span of a scalar or alias covers its text, including any tag and
+/// anchor. The span of the start of a collection covers its tag, anchor and,
+/// for a flow collection, its opening bracket; the span of the end of a flow
+/// collection covers its closing bracket, while a block collection ends at the
+/// empty span where the next token, if any, starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The start of a document, explicit with `---` or not.
+    DocumentStart,
+    /// The end of a document, explicit with `...` or not.
+    DocumentEnd,
+    /// A scalar.
+    Scalar(Scalar),
+    /// The start of a sequence, followed by its items.
+    SequenceStart(CollectionStart),
+    /// The end of the innermost sequence that was started.
+    SequenceEnd,
+    /// The start of a mapping, followed by its keys and values alternately.
+    MappingStart(CollectionStart),
+    /// The end of the innermost mapping that was started.
+    MappingEnd,
+    /// An alias to the node with the given anchor, such as `*name`.
+    Alias(String),
+}
+
+/// A scalar [`Event`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scalar {
+    /// The value of the scalar, with quotes, escapes and folding resolved.
+    pub value: String,
+    /// How the scalar is written in the source.
+    pub style: ScalarStyle,
+    /// The tag of the scalar, such as `!Ref`, with `!!` shorthands expanded
+    /// to `tag:yaml.org,2002:`.
+    pub tag: Option<String>,
+    /// The anchor of the scalar, such as `name` for `&name`.
+    pub anchor: Option<String>,
+}
+
+/// The start of a sequence or mapping [`Event`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectionStart {
+    /// Whether the collection is written in flow style, like `[a, b]` or
+    /// `{a: b}`, as opposed to block style.
+    pub flow: bool,
+    /// The tag of the collection, with `!!` shorthands expanded to
+    /// `tag:yaml.org,2002:`.
+    pub tag: Option<String>,
+    /// The anchor of the collection.
+    pub anchor: Option<String>,
+}
+
+/// How a [`Scalar`] is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarStyle {
+    /// Unquoted.
+    Plain,
+    /// Quoted with `'`.
+    SingleQuoted,
+    /// Quoted with `"`.
+    DoubleQuoted,
+    /// A `|` block scalar.
+    Literal,
+    /// A `>` block scalar.
+    Folded,
+}
+
+impl ScalarStyle {
+    pub(crate) fn from_parser(style: parser::ScalarStyle) -> Self {
+        match style {
+            parser::ScalarStyle::Plain => ScalarStyle::Plain,
+            parser::ScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+            parser::ScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+            parser::ScalarStyle::Literal => ScalarStyle::Literal,
+            parser::ScalarStyle::Folded => ScalarStyle::Folded,
+        }
+    }
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
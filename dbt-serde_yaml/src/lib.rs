@@ -116,6 +116,55 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ## Sets and ordered maps
+//!
+//! The YAML `!!set`, `!!omap` and `!!pairs` types load into a [`Value`] as a
+//! [`Value::Tagged`] mapping whose values are all null, and a tagged sequence
+//! of single-entry mappings, respectively. Both deserialize directly into the
+//! natural Rust types: a `!!set` deserializes into any sequence type as its
+//! keys, and the entries of a `!!omap` or `!!pairs` deserialize into
+//! 2-tuples. Untagged mappings do not.
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//!
+//! fn main() -> Result<(), dbt_serde_yaml::Error> {
+//!     let tags: BTreeSet<String> = dbt_serde_yaml::from_str("!!set {nightly, finance}")?;
+//!     assert_eq!(tags, BTreeSet::from(["finance".to_owned(), "nightly".to_owned()]));
+//!
+//!     let steps: Vec<(String, u32)> = dbt_serde_yaml::from_str("!!omap [seed: 1, run: 2]")?;
+//!     assert_eq!(steps, [("seed".to_owned(), 1), ("run".to_owned(), 2)]);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Binary data
+//!
+//! Byte buffers, such as fields using `serde_bytes`, serialize as `!!binary`
+//! scalars holding base64. A `!!binary` scalar deserializes into byte buffers
+//! as well as into any sequence of `u8`, like `Vec<u8>`. Deserializing into a
+//! [`Value`] keeps the base64 text as a string.
+//!
+//! ```
+//! fn main() -> Result<(), dbt_serde_yaml::Error> {
+//!     let bytes: Vec<u8> = dbt_serde_yaml::from_str("!!binary aGVsbG8=")?;
+//!     assert_eq!(bytes, b"hello");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Arbitrary precision
+//!
+//! With the `arbitrary_precision` feature, numbers that do not fit i64, u64 or
+//! f64 exactly as written, such as `123456789012345678901234567890` or
+//! `3.14159265358979323846`, are kept as their original digits inside
+//! [`Number`] instead of being rounded to the nearest f64. Such numbers
+//! serialize back unchanged and are available through
+//! `Number::as_arbitrary_precision`. Like serde_json's feature of the same
+//! name, they pass through `deserialize_any` as a private single-entry map, so
+//! they deserialize into [`Number`] and [`Value`] but not into other
+//! self-describing types.
 
 #![doc(html_root_url = "https://docs.rs/serde_yaml/0.9.34+deprecated")]
 #![deny(missing_docs, unsafe_op_in_unsafe_fn)]
@@ -162,22 +211,47 @@
     clippy::must_use_candidate,
 )]
 
-pub use crate::de::{from_reader, from_slice, from_str, Deserializer};
-pub use crate::error::{Error, Result};
-pub use crate::ser::{to_string, to_writer, Serializer};
+pub use crate::comments::Comments;
+pub use crate::config::{
+    is_flatten_key, Config, ConfigScope, FlattenKeys, FlattenRouter, Sink, TagHandler, TagHandlers,
+};
+#[cfg(feature = "large_scalars")]
+pub use crate::de::LargeScalars;
+pub use crate::de::{
+    from_reader, from_reader_with_config, from_slice, from_slice_with_config, from_str,
+    from_str_at, from_str_with_config, from_str_with_options, from_str_with_origin, AnchorWarning,
+    Deserializer, DuplicateAnchor, ParseOptions, ScalarSchema, TabIndentation,
+};
+pub use crate::dependency::{collect_dependencies, record_dependency, DependencyEdge};
+pub use crate::error::{Error, ExpectedToken, Result, SyntaxContext, SyntaxHint};
+pub use crate::ser::{
+    to_string, to_string_at, to_string_with, to_string_with_comments, to_writer, to_writer_at,
+    to_writer_with, to_writer_with_comments, QuoteStyle, Serializer, SerializerBuilder,
+    SerializerDriver,
+};
 #[doc(inline)]
-pub use crate::spanned::{reset_marker, set_marker, Marker, Span, Spanned};
+pub use crate::spanned::{
+    assert_span_support, reset_marker, set_marker, Marker, Span, SpanContext, SpanContextScope,
+    Spanned,
+};
 
+#[cfg(feature = "filename")]
+pub use crate::de::{from_str_named, read_file, read_file_value};
 #[cfg(feature = "filename")]
 #[doc(inline)]
 pub use crate::spanned::with_filename;
 
 #[doc(inline)]
-pub use crate::shouldbe::{ShouldBe, WhyNot};
+pub use crate::shouldbe::{LenientRaw, ShouldBe, ShouldBeRef, WhyNot};
+#[cfg(feature = "spill")]
+pub use crate::spill::{spill_raw_values, SpilledRaw};
 #[doc(inline)]
-pub use crate::value::{from_value, to_value, Index, Number, Sequence, Value};
+pub use crate::value::{from_value, to_value, to_value_with_spans, Index, Number, Sequence, Value};
 #[doc(inline)]
-pub use crate::verbatim::Verbatim;
+pub use crate::verbatim::{Transformed, Verbatim};
+pub use crate::warnings::{
+    collect_warnings, record_warning, Warning, WarningKind, Warnings, Yaml11Type,
+};
 
 #[doc(inline)]
 pub use crate::mapping::Mapping;
@@ -185,18 +259,33 @@ pub use crate::mapping::Mapping;
 #[doc(inline)]
 pub use crate::path::Path;
 
+mod binary;
+mod callback;
+mod comments;
+mod config;
 mod de;
+mod dependency;
 mod error;
+pub mod event;
 mod libyaml;
 mod loader;
+mod macros;
 pub mod mapping;
 mod number;
 pub mod path;
 mod ser;
 mod shouldbe;
 pub mod spanned;
+#[cfg(feature = "spill")]
+mod spill;
+#[cfg(feature = "test-util")]
+pub mod testing;
+mod trace;
+#[cfg(feature = "validate")]
+pub mod validate;
 pub mod value;
 mod verbatim;
+mod warnings;
 pub mod with;
 
 // Prevent downstream code from implementing the Index trait.
@@ -209,26 +298,117 @@ mod private {
     impl<T> Sealed for &T where T: ?Sized + Sealed {}
 }
 
-#[cfg(feature = "flatten_dunder")]
-#[inline]
-pub(crate) fn is_flatten_key(key: &[u8]) -> bool {
-    key.len() > 4
-        && key[0] == b'_'
-        && key[1] == b'_'
-        && key[key.len() - 2] == b'_'
-        && key[key.len() - 1] == b'_'
-}
-
-#[cfg(not(feature = "flatten_dunder"))]
-#[inline]
-pub(crate) fn is_flatten_key(_key: &[u8]) -> bool {
-    false
-}
-
 pub use dbt_serde_yaml_derive::UntaggedEnumDeserialize;
+pub use dbt_serde_yaml_derive::YamlDeserialize;
 
-/// Private API for internally tagged unit variant deserialization.
+/// Private API consumed by the code generated by `dbt-serde_yaml_derive`.
+///
+/// Derive macro output refers to items *only* through this module, so the
+/// internal module layout of this crate can change freely without breaking
+/// downstream builds. Items here keep their paths and signatures stable
+/// within a major version; anything not re-exported here must not be named by
+/// generated code.
+///
+/// Not public API.
+#[doc(hidden)]
 pub mod __private {
+    pub use crate::path::{OwnedPath, Path};
+    pub use crate::value::{
+        extract_reusable_deserializer_state, extract_tag_and_deserializer_state, DeserializerState,
+        FromValueOptions, Value,
+    };
+    pub use crate::Error;
+    pub use serde;
+    pub use std::vec;
+
+    /// Errors from the variants of an untagged enum that all failed to
+    /// deserialize.
+    ///
+    /// Not public API.
+    #[derive(Default)]
+    pub struct UntaggedErrors {
+        nearest: Option<(&'static str, crate::Error)>,
+    }
+
+    impl UntaggedErrors {
+        /// Not public API.
+        pub fn new() -> Self {
+            UntaggedErrors::default()
+        }
+
+        /// Records the error of a failed variant, keeping the one from the
+        /// variant that got furthest into the input: the deepest path, then
+        /// the latest position, then anything other than a mismatch of the
+        /// value's type. Ties go to the earlier variant.
+        ///
+        /// Not public API.
+        pub fn record(&mut self, variant_name: &'static str, err: crate::Error) {
+            fn rank(err: &crate::Error) -> (usize, usize, bool) {
+                let depth = err.path().map_or(0, |path| path.components().count());
+                let index = err
+                    .span()
+                    .filter(crate::Span::is_valid)
+                    .map_or(0, |span| span.start.index);
+                (depth, index, !err.is_invalid_type())
+            }
+
+            match &self.nearest {
+                Some((_, nearest)) if rank(&err) <= rank(nearest) => {}
+                _ => self.nearest = Some((variant_name, err)),
+            }
+        }
+
+        /// Builds the error reporting that no variant matched, located at the
+        /// nearest variant's error, or else at `span`, the span of the input
+        /// value.
+        ///
+        /// Not public API.
+        pub fn into_error<E: serde::de::Error>(self, enum_name: &str, span: &crate::Span) -> E {
+            match self.nearest {
+                Some((variant_name, err)) => {
+                    let located = err.span().filter(crate::Span::is_valid);
+                    crate::error::custom_at(
+                        format_args!(
+                            "{} (no variant of untagged enum {} matched; closest was {})",
+                            err.display_no_mark(),
+                            enum_name,
+                            variant_name
+                        ),
+                        located.as_ref().unwrap_or(span),
+                    )
+                }
+                None => crate::error::custom_at(
+                    format_args!(
+                        "data did not match any variant of untagged enum {}",
+                        enum_name
+                    ),
+                    span,
+                ),
+            }
+        }
+    }
+
+    /// Reports the unused keys collected while deserializing the variant of
+    /// an untagged enum that matched to `callback`, running it through the
+    /// same guard as every other callback so that its panics and failures
+    /// become errors.
+    ///
+    /// Not public API.
+    pub fn report_unused_keys<E: serde::de::Error>(
+        callback: Option<crate::value::UnusedKeyCallback<'_>>,
+        unused_keys: &[(OwnedPath, Value, Value)],
+    ) -> Result<(), E> {
+        let Some(callback) = callback else {
+            return Ok(());
+        };
+        for (path, key, value) in unused_keys {
+            let path = *path.as_path();
+            crate::callback::guard(&path, value.span(), || callback(path, key, value))
+                .map_err(|err| crate::error::custom_at(err.display_no_mark(), value.span()))?;
+        }
+        Ok(())
+    }
+
     /// Visitor for deserializing an internally tagged unit variant.
     ///
     /// Not public API.
@@ -48,7 +48,10 @@ pub(crate) enum ScalarStyle {
     Any,
     Plain,
     SingleQuoted,
+    DoubleQuoted,
     Literal,
+    #[cfg(feature = "round_trip")]
+    Folded,
 }
 
 #[derive(Debug)]
@@ -79,6 +82,24 @@ impl<'a> Emitter<'a> {
         Emitter { pin }
     }
 
+    /// Must be called before the StreamStart event is emitted.
+    pub fn set_indent(&mut self, indent: usize) {
+        unsafe {
+            let emitter = addr_of_mut!((*self.pin.ptr).sys);
+            sys::yaml_emitter_set_indent(emitter, indent.min(i32::MAX as usize) as i32);
+        }
+    }
+
+    /// Must be called before the StreamStart event is emitted. `None` means
+    /// unlimited.
+    pub fn set_width(&mut self, width: Option<usize>) {
+        unsafe {
+            let emitter = addr_of_mut!((*self.pin.ptr).sys);
+            let width = width.map_or(-1, |width| width.min(i32::MAX as usize) as i32);
+            sys::yaml_emitter_set_width(emitter, width);
+        }
+    }
+
     pub fn emit(&mut self, event: Event) -> Result<(), Error> {
         let mut sys_event = MaybeUninit::<sys::yaml_event_t>::uninit();
         let sys_event = sys_event.as_mut_ptr();
@@ -120,7 +141,10 @@ impl<'a> Emitter<'a> {
                         ScalarStyle::Any => sys::YAML_ANY_SCALAR_STYLE,
                         ScalarStyle::Plain => sys::YAML_PLAIN_SCALAR_STYLE,
                         ScalarStyle::SingleQuoted => sys::YAML_SINGLE_QUOTED_SCALAR_STYLE,
+                        ScalarStyle::DoubleQuoted => sys::YAML_DOUBLE_QUOTED_SCALAR_STYLE,
                         ScalarStyle::Literal => sys::YAML_LITERAL_SCALAR_STYLE,
+                        #[cfg(feature = "round_trip")]
+                        ScalarStyle::Folded => sys::YAML_FOLDED_SCALAR_STYLE,
                     };
                     sys::yaml_scalar_event_initialize(
                         sys_event,
@@ -8,6 +8,7 @@ pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 pub(crate) struct Error {
     kind: sys::yaml_error_type_t,
+    missing: Option<Missing>,
     problem: CStr<'static>,
     problem_offset: u64,
     problem_mark: Mark,
@@ -15,14 +16,54 @@ pub(crate) struct Error {
     context_mark: Mark,
 }
 
+/// A token whose absence a parser error is about.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Missing {
+    Key,
+    BlockEntry,
+    FlowSequenceEntry,
+    FlowMappingEntry,
+    Node,
+    DocumentStart,
+    Value,
+}
+
+impl Missing {
+    /// Recognizes the problems libyaml reports for a missing token, which
+    /// libyaml only describes in prose.
+    fn of(kind: sys::yaml_error_type_t, problem: &[u8]) -> Option<Self> {
+        match (kind, problem) {
+            (sys::YAML_PARSER_ERROR, b"did not find expected key") => Some(Missing::Key),
+            (sys::YAML_PARSER_ERROR, b"did not find expected '-' indicator") => {
+                Some(Missing::BlockEntry)
+            }
+            (sys::YAML_PARSER_ERROR, b"did not find expected ',' or ']'") => {
+                Some(Missing::FlowSequenceEntry)
+            }
+            (sys::YAML_PARSER_ERROR, b"did not find expected ',' or '}'") => {
+                Some(Missing::FlowMappingEntry)
+            }
+            (sys::YAML_PARSER_ERROR, b"did not find expected node content") => Some(Missing::Node),
+            (sys::YAML_PARSER_ERROR, b"did not find expected <document start>") => {
+                Some(Missing::DocumentStart)
+            }
+            (sys::YAML_SCANNER_ERROR, b"could not find expected ':'") => Some(Missing::Value),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub unsafe fn parse_error(parser: *const sys::yaml_parser_t) -> Self {
+        let kind = unsafe { (&(*parser)).error };
+        let problem = match NonNull::new(unsafe { (&(*parser)).problem as *mut _ }) {
+            Some(problem) => unsafe { CStr::from_ptr(problem) },
+            None => CStr::from_bytes_with_nul(b"libyaml parser failed but there is no error\0"),
+        };
         Error {
-            kind: unsafe { (&(*parser)).error },
-            problem: match NonNull::new(unsafe { (&(*parser)).problem as *mut _ }) {
-                Some(problem) => unsafe { CStr::from_ptr(problem) },
-                None => CStr::from_bytes_with_nul(b"libyaml parser failed but there is no error\0"),
-            },
+            kind,
+            missing: Missing::of(kind, problem.to_bytes()),
+            problem,
             problem_offset: unsafe { (&(*parser)).problem_offset },
             problem_mark: Mark {
                 sys: unsafe { (&(*parser)).problem_mark },
@@ -40,6 +81,7 @@ impl Error {
     pub unsafe fn emit_error(emitter: *const sys::yaml_emitter_t) -> Self {
         Error {
             kind: unsafe { (&(*emitter)).error },
+            missing: None,
             problem: match NonNull::new(unsafe { (&(*emitter)).problem as *mut _ }) {
                 Some(problem) => unsafe { CStr::from_ptr(problem) },
                 None => {
@@ -60,6 +102,30 @@ impl Error {
     pub fn mark(&self) -> Mark {
         self.problem_mark
     }
+
+    /// Moves the marks of this error as with [Mark::offset_by]. Reader errors
+    /// are located by `problem_offset` alone and are left unchanged.
+    pub fn offset_by(mut self, origin: Mark) -> Self {
+        if self.kind != sys::YAML_READER_ERROR {
+            self.problem_mark = self.problem_mark.offset_by(origin);
+            self.context_mark = self.context_mark.offset_by(origin);
+        }
+        self
+    }
+
+    pub fn problem(&self) -> CStr<'static> {
+        self.problem
+    }
+
+    /// The token whose absence the error is about, if it is one of those
+    /// recognized.
+    pub fn missing(&self) -> Option<Missing> {
+        self.missing
+    }
+
+    pub fn context(&self) -> Option<CStr<'static>> {
+        self.context
+    }
 }
 
 impl Display for Error {
@@ -120,6 +186,28 @@ pub(crate) struct Mark {
 }
 
 impl Mark {
+    /// Creates a mark from a 0-based line and column.
+    pub fn new(index: u64, line: u64, column: u64) -> Self {
+        let mut sys = unsafe { MaybeUninit::<sys::yaml_mark_t>::zeroed().assume_init() };
+        sys.index = index;
+        sys.line = line;
+        sys.column = column;
+        Mark { sys }
+    }
+
+    /// Returns the position of this mark in an enclosing text in which the
+    /// input starts at `origin`: the index and line are added, and so is the
+    /// column on the first line of the input.
+    pub fn offset_by(self, origin: Mark) -> Self {
+        let mut sys = self.sys;
+        if sys.line == 0 {
+            sys.column += origin.sys.column;
+        }
+        sys.line += origin.sys.line;
+        sys.index += origin.sys.index;
+        Mark { sys }
+    }
+
     pub fn index(&self) -> u64 {
         self.sys.index
     }
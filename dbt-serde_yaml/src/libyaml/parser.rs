@@ -3,10 +3,13 @@ use crate::libyaml::error::{Error, Mark, Result};
 use crate::libyaml::tag::Tag;
 use crate::libyaml::util::Owned;
 use std::borrow::Cow;
+use std::ffi::c_void;
 use std::fmt::{self, Debug};
+use std::io;
 use std::mem::MaybeUninit;
 use std::ptr::{addr_of_mut, NonNull};
 use std::slice;
+use std::sync::Arc;
 use unsafe_libyaml as sys;
 
 pub(crate) struct Parser<'input> {
@@ -16,6 +19,11 @@ pub(crate) struct Parser<'input> {
 struct ParserPinned<'input> {
     sys: sys::yaml_parser_t,
     input: Cow<'input, [u8]>,
+    /// The source of the input when it is read incrementally rather than
+    /// given up front.
+    reader: Option<Box<dyn io::Read + 'input>>,
+    /// The error that made the last read from `reader` fail.
+    read_error: Option<io::Error>,
 }
 
 #[derive(Debug)]
@@ -35,7 +43,9 @@ pub(crate) enum Event<'input> {
 pub(crate) struct Scalar<'input> {
     pub anchor: Option<Anchor>,
     pub tag: Option<Tag>,
-    pub value: Box<[u8]>,
+    /// Shared so that large strings in a `Value` can hold on to it rather
+    /// than to a copy.
+    pub value: Arc<[u8]>,
     pub style: ScalarStyle,
     pub repr: Option<&'input [u8]>,
 }
@@ -44,16 +54,18 @@ pub(crate) struct Scalar<'input> {
 pub(crate) struct SequenceStart {
     pub anchor: Option<Anchor>,
     pub tag: Option<Tag>,
+    pub flow: bool,
 }
 
 #[derive(Debug)]
 pub(crate) struct MappingStart {
     pub anchor: Option<Anchor>,
     pub tag: Option<Tag>,
+    pub flow: bool,
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
-pub(crate) struct Anchor(Box<[u8]>);
+pub(crate) struct Anchor(pub Box<[u8]>);
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(crate) enum ScalarStyle {
@@ -75,12 +87,45 @@ impl<'input> Parser<'input> {
             sys::yaml_parser_set_encoding(parser, sys::YAML_UTF8_ENCODING);
             sys::yaml_parser_set_input_string(parser, input.as_ptr(), input.len() as u64);
             addr_of_mut!((*owned.ptr).input).write(input);
+            addr_of_mut!((*owned.ptr).reader).write(None);
+            addr_of_mut!((*owned.ptr).read_error).write(None);
             Owned::assume_init(owned)
         };
         Parser { pin }
     }
 
+    /// Creates a parser that pulls its input from `reader` as it goes, rather
+    /// than holding all of it at once. Scalars parsed this way have no `repr`.
+    pub fn from_reader(reader: Box<dyn io::Read + 'input>) -> Parser<'input> {
+        let owned = Owned::<ParserPinned>::new_uninit();
+        let pin = unsafe {
+            let parser = addr_of_mut!((*owned.ptr).sys);
+            if sys::yaml_parser_initialize(parser).fail {
+                panic!("malloc error: {}", Error::parse_error(parser));
+            }
+            sys::yaml_parser_set_encoding(parser, sys::YAML_UTF8_ENCODING);
+            addr_of_mut!((*owned.ptr).input).write(Cow::Owned(Vec::new()));
+            addr_of_mut!((*owned.ptr).reader).write(Some(reader));
+            addr_of_mut!((*owned.ptr).read_error).write(None);
+            sys::yaml_parser_set_input(parser, read_handler, owned.ptr.cast());
+            Owned::assume_init(owned)
+        };
+        Parser { pin }
+    }
+
+    /// Takes the error of the reader that made parsing fail, if any.
+    pub fn take_read_error(&mut self) -> Option<io::Error> {
+        unsafe { (*self.pin.ptr).read_error.take() }
+    }
+
     pub fn next(&mut self) -> Result<(Event<'input>, Mark)> {
+        self.next_with_end()
+            .map(|(event, start, _end)| (event, start))
+    }
+
+    /// Like [next](Self::next), but also returns the mark at the end of the
+    /// event.
+    pub fn next_with_end(&mut self) -> Result<(Event<'input>, Mark, Mark)> {
         let mut event = MaybeUninit::<sys::yaml_event_t>::uninit();
         unsafe {
             let parser = addr_of_mut!((*self.pin.ptr).sys);
@@ -92,11 +137,35 @@ impl<'input> Parser<'input> {
                 return Err(Error::parse_error(parser));
             }
             let ret = convert_event(&*event, &(*self.pin.ptr).input);
-            let mark = Mark {
+            let start = Mark {
                 sys: (*event).start_mark,
             };
+            let end = Mark {
+                sys: (*event).end_mark,
+            };
             sys::yaml_event_delete(event);
-            Ok((ret, mark))
+            Ok((ret, start, end))
+        }
+    }
+}
+
+unsafe fn read_handler(data: *mut c_void, buffer: *mut u8, size: u64, size_read: *mut u64) -> i32 {
+    let pinned = data.cast::<ParserPinned>();
+    let Some(reader) = (unsafe { (*pinned).reader.as_mut() }) else {
+        return 0;
+    };
+    let buffer = unsafe { slice::from_raw_parts_mut(buffer, size as usize) };
+    loop {
+        match reader.read(buffer) {
+            Ok(n) => {
+                unsafe { *size_read = n as u64 };
+                return 1;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                unsafe { (*pinned).read_error = Some(err) };
+                return 0;
+            }
         }
     }
 }
@@ -116,7 +185,7 @@ unsafe fn convert_event<'input>(
         sys::YAML_SCALAR_EVENT => Event::Scalar(Scalar {
             anchor: unsafe { optional_anchor(sys.data.scalar.anchor) },
             tag: unsafe { optional_tag(sys.data.scalar.tag) },
-            value: Box::from(unsafe {
+            value: Arc::from(unsafe {
                 slice::from_raw_parts(sys.data.scalar.value, sys.data.scalar.length as usize)
             }),
             style: match unsafe { sys.data.scalar.style } {
@@ -136,11 +205,13 @@ unsafe fn convert_event<'input>(
         sys::YAML_SEQUENCE_START_EVENT => Event::SequenceStart(SequenceStart {
             anchor: unsafe { optional_anchor(sys.data.sequence_start.anchor) },
             tag: unsafe { optional_tag(sys.data.sequence_start.tag) },
+            flow: unsafe { sys.data.sequence_start.style } == sys::YAML_FLOW_SEQUENCE_STYLE,
         }),
         sys::YAML_SEQUENCE_END_EVENT => Event::SequenceEnd,
         sys::YAML_MAPPING_START_EVENT => Event::MappingStart(MappingStart {
             anchor: unsafe { optional_anchor(sys.data.mapping_start.anchor) },
             tag: unsafe { optional_tag(sys.data.mapping_start.tag) },
+            flow: unsafe { sys.data.mapping_start.style } == sys::YAML_FLOW_MAPPING_STYLE,
         }),
         sys::YAML_MAPPING_END_EVENT => Event::MappingEnd,
         sys::YAML_NO_EVENT => unreachable!(),
@@ -10,6 +10,19 @@ impl Tag {
     pub const BOOL: &'static str = "tag:yaml.org,2002:bool";
     pub const INT: &'static str = "tag:yaml.org,2002:int";
     pub const FLOAT: &'static str = "tag:yaml.org,2002:float";
+    pub const BINARY: &'static str = "tag:yaml.org,2002:binary";
+    pub const SET: &'static str = "tag:yaml.org,2002:set";
+    pub const OMAP: &'static str = "tag:yaml.org,2002:omap";
+    pub const PAIRS: &'static str = "tag:yaml.org,2002:pairs";
+
+    /// The tags of the YAML types that are kept in a [Value](crate::Value),
+    /// by their shorthand.
+    pub const KEPT: [(&'static str, &'static str); 4] = [
+        ("!!set", Tag::SET),
+        ("!!omap", Tag::OMAP),
+        ("!!pairs", Tag::PAIRS),
+        ("!!binary", Tag::BINARY),
+    ];
 }
 
 impl Tag {
@@ -1,15 +1,26 @@
-use crate::de::{Event, Progress};
-use crate::error::{self, Error, ErrorImpl, Result};
+use crate::de::{DuplicateAnchor, Event, ParseOptions, Progress, ScalarSchema, TabIndentation};
+use crate::error::{self, Error, ErrorImpl, Result, Segment, SyntaxContext};
 use crate::libyaml::error::Mark;
-use crate::libyaml::parser::{Event as YamlEvent, Parser};
-use crate::spanned;
+use crate::libyaml::parser::{Anchor, Event as YamlEvent, Parser, ScalarStyle};
+use crate::spanned::{self, Marker, Span};
+use crate::trace;
+use crate::warnings::{self, WarningKind};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::io;
+use std::str;
 use std::sync::Arc;
+use std::time::Instant;
 
 pub(crate) struct Loader<'input> {
     parser: Option<Parser<'input>>,
     document_count: usize,
+    options: ParseOptions,
+    /// The schema plain scalars are resolved by, which decides what
+    /// [WarningKind::AmbiguousScalar] says about them.
+    schema: ScalarSchema,
 }
 
 pub(crate) struct Document<'input> {
@@ -17,32 +28,70 @@ pub(crate) struct Document<'input> {
     pub error: Option<Arc<ErrorImpl>>,
     /// Map from alias id to index in events.
     pub aliases: BTreeMap<usize, usize>,
+    /// Limits to enforce while deserializing the document.
+    pub options: ParseOptions,
+    /// When the time allowed for the document by
+    /// [ParseOptions::time_limit] runs out.
+    pub deadline: Option<Instant>,
 }
 
 impl<'input> Loader<'input> {
-    pub fn new(progress: Progress<'input>) -> Result<Self> {
+    pub fn new(
+        progress: Progress<'input>,
+        options: ParseOptions,
+        schema: ScalarSchema,
+    ) -> Result<Self> {
         let input = match progress {
             Progress::Str(s) => Cow::Borrowed(s.as_bytes()),
             Progress::Slice(bytes) => Cow::Borrowed(bytes),
-            Progress::Read(mut rdr) => {
-                let mut buffer = Vec::new();
-                if let Err(io_error) = rdr.read_to_end(&mut buffer) {
-                    return Err(error::new(ErrorImpl::Io(io_error)));
-                }
-                Cow::Owned(buffer)
+            Progress::Read(rdr) => {
+                // Fed to the parser as it asks for more, so that only the
+                // events of the current document are held at once.
+                let rdr = match options.tab_indentation {
+                    TabIndentation::Unchecked if !warnings::is_collecting() => rdr,
+                    _ => Box::new(TabIndentationReader::new(rdr, options.clone())),
+                };
+                return Ok(Loader {
+                    parser: Some(Parser::from_reader(rdr)),
+                    document_count: 0,
+                    options,
+                    schema,
+                });
             }
             Progress::Iterable(_) | Progress::Document(_) => unreachable!(),
             Progress::Fail(err) => return Err(error::shared(err)),
         };
 
+        if options.tab_indentation != TabIndentation::Reject && warnings::is_collecting() {
+            for marker in tab_indentation(&input) {
+                let span = Span::from(relocate(marker, &options));
+                warnings::warn(WarningKind::TabIndentation, span);
+            }
+        }
+        let input = match options.tab_indentation {
+            TabIndentation::Unchecked => input,
+            TabIndentation::Reject => {
+                if let Some(marker) = tab_indentation(&input).next() {
+                    let marker = relocate(marker, &options);
+                    return Err(error::new(ErrorImpl::TabInIndentation(marker)));
+                }
+                input
+            }
+            TabIndentation::AsSpace => expand_tab_indentation(input),
+        };
+
         Ok(Loader {
             parser: Some(Parser::new(input)),
             document_count: 0,
+            options,
+            schema,
         })
     }
 
     pub fn next_document(&mut self) -> Option<Document<'input>> {
+        let _span = trace::enter_parse_document(self.document_count);
         let document = self.next_document_inner()?;
+        trace::document_parsed(document.events.len(), document.error.is_some());
         if let Some((_event, mark)) = document.events.first() {
             spanned::set_marker(*mark);
         }
@@ -60,17 +109,33 @@ impl<'input> Loader<'input> {
         self.document_count += 1;
 
         let mut anchors = BTreeMap::new();
+        let mut structure = Structure::default();
+        let mut budget = Budget::default();
         let mut document = Document {
             events: Vec::new(),
             error: None,
             aliases: BTreeMap::new(),
+            options: self.options.clone(),
+            deadline: self
+                .options
+                .time_limit
+                .and_then(|time_limit| Instant::now().checked_add(time_limit)),
         };
 
+        let origin = origin_mark(&self.options);
+        let collecting = warnings::is_collecting();
         loop {
-            let (event, mark) = match parser.next() {
-                Ok((event, mark)) => (event, mark),
+            let (event, mark, end) = match parser.next_with_end() {
+                Ok((event, mark, end)) => (event, mark.offset_by(origin), end),
                 Err(err) => {
-                    document.error = Some(Error::from(err).shared());
+                    let error = match parser.take_read_error() {
+                        Some(io_error) => read_error(io_error, &self.options),
+                        None => {
+                            let (context, segments) = structure.position(&document.events);
+                            error::syntax(err.offset_by(origin), context, segments)
+                        }
+                    };
+                    document.error = Some(error.shared());
                     return Some(document);
                 }
             };
@@ -102,32 +167,391 @@ impl<'input> Loader<'input> {
                 },
                 YamlEvent::Scalar(mut scalar) => {
                     if let Some(anchor) = scalar.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document.aliases.insert(id, document.events.len());
+                        if let Err(err) =
+                            define_anchor(&mut anchors, &mut document, anchor, mark, &self.options)
+                        {
+                            document.error = Some(err.shared());
+                            return Some(document);
+                        }
+                    }
+                    let yaml11 =
+                        if collecting && scalar.style == ScalarStyle::Plain && scalar.tag.is_none()
+                        {
+                            warnings::yaml11_ambiguity(&scalar.value)
+                        } else {
+                            None
+                        };
+                    if let Some(yaml11) = yaml11 {
+                        let value = String::from_utf8_lossy(&scalar.value).into_owned();
+                        let span = Span::new(mark.into(), end.offset_by(origin).into());
+                        let kind = WarningKind::AmbiguousScalar {
+                            value,
+                            yaml11,
+                            schema: self.schema,
+                        };
+                        warnings::warn(kind, span);
                     }
                     Event::Scalar(scalar)
                 }
                 YamlEvent::SequenceStart(mut sequence_start) => {
                     if let Some(anchor) = sequence_start.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document.aliases.insert(id, document.events.len());
+                        if let Err(err) =
+                            define_anchor(&mut anchors, &mut document, anchor, mark, &self.options)
+                        {
+                            document.error = Some(err.shared());
+                            return Some(document);
+                        }
                     }
                     Event::SequenceStart(sequence_start)
                 }
                 YamlEvent::SequenceEnd => Event::SequenceEnd,
                 YamlEvent::MappingStart(mut mapping_start) => {
                     if let Some(anchor) = mapping_start.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document.aliases.insert(id, document.events.len());
+                        if let Err(err) =
+                            define_anchor(&mut anchors, &mut document, anchor, mark, &self.options)
+                        {
+                            document.error = Some(err.shared());
+                            return Some(document);
+                        }
                     }
                     Event::MappingStart(mapping_start)
                 }
                 YamlEvent::MappingEnd => Event::MappingEnd,
             };
+            if let Err(err) = budget.spend(&event, mark, &document) {
+                // Nothing after a document over budget is worth parsing.
+                self.parser = None;
+                document.error = Some(err.shared());
+                return Some(document);
+            }
+            structure.advance(&event, document.events.len());
             document.events.push((event, mark));
         }
     }
 }
+
+/// What a document has used so far of the limits set by [ParseOptions],
+/// checked as its events are parsed so that a document far over a limit fails
+/// before it is held in memory whole. Aliases are counted again as they are
+/// expanded during deserialization, which is held to the same limits.
+#[derive(Default)]
+struct Budget {
+    nodes: usize,
+    scalar_bytes: usize,
+}
+
+impl Budget {
+    fn spend(&mut self, event: &Event, mark: Mark, document: &Document) -> Result<()> {
+        let options = &document.options;
+        if let Event::Scalar(_) | Event::SequenceStart(_) | Event::MappingStart(_) = event {
+            self.nodes += 1;
+            if options
+                .max_nodes
+                .is_some_and(|max_nodes| self.nodes > max_nodes)
+            {
+                return Err(error::new(ErrorImpl::NodeLimitExceeded(mark.into())));
+            }
+        }
+        if let Event::Scalar(scalar) = event {
+            self.scalar_bytes += scalar.value.len();
+            if options
+                .max_scalar_bytes
+                .is_some_and(|max_scalar_bytes| self.scalar_bytes > max_scalar_bytes)
+            {
+                return Err(error::new(ErrorImpl::ScalarBytesLimitExceeded(mark.into())));
+            }
+        }
+        if document
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(error::new(ErrorImpl::TimeLimitExceeded(mark.into())));
+        }
+        Ok(())
+    }
+}
+
+/// Records an anchor defined by the next event of `document`.
+fn define_anchor(
+    anchors: &mut BTreeMap<Anchor, usize>,
+    document: &mut Document,
+    anchor: Anchor,
+    mark: Mark,
+    options: &ParseOptions,
+) -> Result<()> {
+    if let Some(previous) = anchors.get(&anchor) {
+        let name = String::from_utf8_lossy(&anchor.0);
+        let first = document.events[document.aliases[previous]].1;
+        match &options.duplicate_anchors {
+            DuplicateAnchor::Allow => {}
+            DuplicateAnchor::Warn(warn) => warn(&name, first.into(), mark.into()),
+            DuplicateAnchor::Error => {
+                let name = name.into_owned();
+                return Err(error::new(ErrorImpl::DuplicateAnchor(name, mark.into())));
+            }
+        }
+        let kind = WarningKind::DuplicateAnchor {
+            name: name.into_owned(),
+            first: first.into(),
+        };
+        warnings::warn(kind, Span::from(Marker::from(mark)));
+    }
+    let id = document.aliases.len();
+    anchors.insert(anchor, id);
+    document.aliases.insert(id, document.events.len());
+    Ok(())
+}
+
+/// Returns the [ParseOptions::origin] of the input as a mark.
+fn origin_mark(options: &ParseOptions) -> Mark {
+    let origin = options.origin;
+    Mark::new(
+        origin.index as u64,
+        origin.line.saturating_sub(1) as u64,
+        origin.column.saturating_sub(1) as u64,
+    )
+}
+
+/// Moves `marker`, a location in the input, to where it is in the text the
+/// input starts at [ParseOptions::origin] of.
+fn relocate(marker: Marker, options: &ParseOptions) -> Marker {
+    let mark = Mark::new(
+        marker.index as u64,
+        marker.line.saturating_sub(1) as u64,
+        marker.column.saturating_sub(1) as u64,
+    );
+    Marker::from(mark.offset_by(origin_mark(options)))
+}
+
+/// Returns the locations of the first tab in the indentation of each line
+/// with content.
+fn tab_indentation(input: &[u8]) -> impl Iterator<Item = Marker> + '_ {
+    let mut index = 0;
+    input
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .filter_map(move |(line, text)| {
+            let start = index;
+            index += text.len() + 1;
+            let indent = text
+                .iter()
+                .take_while(|&&b| b == b' ' || b == b'\t')
+                .count();
+            let has_content = text[indent..].iter().any(|b| !b.is_ascii_whitespace());
+            if !has_content {
+                return None;
+            }
+            let column = text[..indent].iter().position(|&b| b == b'\t')?;
+            Some(Marker::new(start + column, line + 1, column + 1))
+        })
+}
+
+/// Replaces each tab in the indentation of a line by a space, keeping every
+/// other byte in place.
+fn expand_tab_indentation(input: Cow<[u8]>) -> Cow<[u8]> {
+    let mut input = input;
+    let mut at_line_start = true;
+    for i in 0..input.len() {
+        match input[i] {
+            b'\n' => at_line_start = true,
+            b'\t' if at_line_start => input.to_mut()[i] = b' ',
+            b' ' => {}
+            _ => at_line_start = false,
+        }
+    }
+    input
+}
+
+/// Applies a [TabIndentation] policy to input that is read incrementally, the
+/// way [tab_indentation] and [expand_tab_indentation] do to input that is held
+/// at once, and reports the tabs it lets through as warnings.
+struct TabIndentationReader<'input> {
+    reader: Box<dyn io::Read + 'input>,
+    options: ParseOptions,
+    /// The position of the next byte to be read.
+    index: usize,
+    line: usize,
+    column: usize,
+    /// Whether only spaces and tabs were read since the start of the line.
+    at_line_start: bool,
+    /// The first tab in the indentation of the current line.
+    tab: Option<Marker>,
+    /// A tab in the indentation of a line with content, reported by the next
+    /// read once the bytes before it have been consumed.
+    found: Option<Marker>,
+}
+
+/// The error by which [TabIndentationReader] rejects its input.
+#[derive(Debug)]
+struct TabInIndentation(Marker);
+
+impl Display for TabInIndentation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "tab character in indentation at {}", self.0)
+    }
+}
+
+impl StdError for TabInIndentation {}
+
+impl<'input> TabIndentationReader<'input> {
+    fn new(reader: Box<dyn io::Read + 'input>, options: ParseOptions) -> Self {
+        TabIndentationReader {
+            reader,
+            options,
+            index: 0,
+            line: 1,
+            column: 1,
+            at_line_start: true,
+            tab: None,
+            found: None,
+        }
+    }
+}
+
+impl io::Read for TabIndentationReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(marker) = self.found {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                TabInIndentation(marker),
+            ));
+        }
+        let n = self.reader.read(buf)?;
+        for i in 0..n {
+            match buf[i] {
+                b'\n' => {
+                    self.line += 1;
+                    self.column = 0;
+                    self.at_line_start = true;
+                    self.tab = None;
+                }
+                b' ' if self.at_line_start => {}
+                b'\t' if self.at_line_start => {
+                    let marker = Marker::new(self.index, self.line, self.column);
+                    self.tab.get_or_insert(marker);
+                    if self.options.tab_indentation == TabIndentation::AsSpace {
+                        buf[i] = b' ';
+                    }
+                }
+                b => {
+                    self.at_line_start = false;
+                    if !b.is_ascii_whitespace() {
+                        if let Some(marker) = self.tab.take() {
+                            if self.options.tab_indentation == TabIndentation::Reject {
+                                self.found = Some(marker);
+                                return if i == 0 { self.read(buf) } else { Ok(i) };
+                            }
+                            let span = Span::from(relocate(marker, &self.options));
+                            warnings::warn(WarningKind::TabIndentation, span);
+                        }
+                    }
+                }
+            }
+            self.index += 1;
+            self.column += 1;
+        }
+        Ok(n)
+    }
+}
+
+fn read_error(io_error: io::Error, options: &ParseOptions) -> Error {
+    let tab = io_error
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<TabInIndentation>());
+    match tab {
+        Some(TabInIndentation(marker)) => {
+            error::new(ErrorImpl::TabInIndentation(relocate(*marker, options)))
+        }
+        None => error::new(ErrorImpl::Io(io_error)),
+    }
+}
+
+/// Tracks the open collections of the document being parsed, to describe where
+/// a syntax error occurred.
+#[derive(Default)]
+struct Structure {
+    stack: Vec<Collection>,
+}
+
+struct Collection {
+    flow: bool,
+    mapping: bool,
+    /// Number of complete nodes so far, counting keys and values separately.
+    len: usize,
+    /// The index in the document's events of the last key, if it was a
+    /// scalar; its text is only needed for an error.
+    key: Option<usize>,
+}
+
+impl Structure {
+    /// Follows `event`, which will be at `index` in the document's events.
+    fn advance(&mut self, event: &Event, index: usize) {
+        match event {
+            Event::Alias(_) | Event::Scalar(_) => self.node_done(event, index),
+            Event::SequenceStart(sequence) => self.open(sequence.flow, false),
+            Event::MappingStart(mapping) => self.open(mapping.flow, true),
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.stack.pop();
+                self.node_done(event, index);
+            }
+            Event::Void => {}
+        }
+    }
+
+    fn open(&mut self, flow: bool, mapping: bool) {
+        self.stack.push(Collection {
+            flow,
+            mapping,
+            len: 0,
+            key: None,
+        });
+    }
+
+    fn node_done(&mut self, event: &Event, index: usize) {
+        if let Some(parent) = self.stack.last_mut() {
+            if parent.mapping && parent.len % 2 == 0 {
+                parent.key = match event {
+                    Event::Scalar(_) => Some(index),
+                    _ => None,
+                };
+            }
+            parent.len += 1;
+        }
+    }
+
+    /// The construct being parsed and the path of the node being parsed,
+    /// given the events of the document so far.
+    fn position(&self, events: &[(Event, Mark)]) -> (SyntaxContext, Vec<Segment>) {
+        let mut segments = Vec::new();
+        for collection in &self.stack {
+            if !collection.mapping {
+                segments.push(Segment::Seq(collection.len));
+            } else if collection.len % 2 == 1 {
+                let key = match collection.key.map(|index| &events[index].0) {
+                    Some(Event::Scalar(scalar)) => str::from_utf8(&scalar.value).ok(),
+                    _ => None,
+                };
+                segments.push(match key {
+                    Some(key) => Segment::Map(key.to_owned()),
+                    None => Segment::Unknown,
+                });
+            } else {
+                // The key itself is being parsed, so the path ends at the
+                // mapping. Anything deeper is inside a complex key.
+                break;
+            }
+        }
+        let context = match self.stack.last() {
+            None => SyntaxContext::Document,
+            Some(collection) => match (collection.flow, collection.mapping, collection.len % 2) {
+                (false, false, _) => SyntaxContext::BlockSequenceEntry,
+                (true, false, _) => SyntaxContext::FlowSequenceEntry,
+                (false, true, 0) => SyntaxContext::BlockMappingKey,
+                (false, true, _) => SyntaxContext::BlockMappingValue,
+                (true, true, 0) => SyntaxContext::FlowMappingKey,
+                (true, true, _) => SyntaxContext::FlowMappingValue,
+            },
+        };
+        (context, segments)
+    }
+}
@@ -0,0 +1,287 @@
+/// Constructs a [`Value`](crate::Value) from a YAML-like literal.
+///
+/// ```
+/// use dbt_serde_yaml::yaml;
+///
+/// let materialized = "table";
+/// let value = yaml!({
+///     "name": "orders",
+///     "config": {
+///         "materialized": materialized,
+///         "enabled": true,
+///         "meta": null,
+///     },
+///     "columns": ["id", "status"],
+///     "tests": [!Ref "customers", !("env_var") ["DBT_TARGET", "dev"]],
+///     1: "a non-string key",
+/// });
+///
+/// let expected: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(r#"
+/// name: orders
+/// config: {materialized: table, enabled: true, meta: null}
+/// columns: [id, status]
+/// tests: [!Ref customers, !env_var [DBT_TARGET, dev]]
+/// 1: a non-string key
+/// "#).unwrap();
+/// assert_eq!(value, expected);
+/// ```
+///
+/// Keys and values may be arbitrary expressions, which are converted with
+/// [`to_value`](crate::to_value) and so must implement `Serialize`. Unlike
+/// with `json!`, keys don't have to be strings.
+///
+/// A value is tagged by preceding it with `!` and the tag, either as an
+/// identifier like `!Ref` or as a parenthesized expression like
+/// `!("env_var")`. Inside a sequence or mapping the tagged value must be a
+/// single token tree, so write `!Tag (-1)` rather than `!Tag -1`.
+///
+/// The macro panics if an interpolated expression fails to serialize. The
+/// values built have no location information.
+#[macro_export]
+macro_rules! yaml {
+    ($($yaml:tt)+) => {
+        $crate::yaml_internal!($($yaml)+)
+    };
+}
+
+// Adapted from `serde_json::json_internal!`: a tt-muncher that walks the
+// tokens of sequences and mappings one element at a time.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! yaml_internal {
+    //////////////////////////////////////////////////////////////////////////
+    // TT muncher for parsing the inside of a sequence [...]. Produces a vec![...]
+    // of the elements.
+    //
+    // Must be invoked as: yaml_internal!(@seq [] $($tt)*)
+    //////////////////////////////////////////////////////////////////////////
+
+    // Done with trailing comma.
+    (@seq [$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+
+    // Done without trailing comma.
+    (@seq [$($elems:expr),*]) => {
+        $crate::__private::vec![$($elems),*]
+    };
+
+    // Next element is `null`.
+    (@seq [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!(null)] $($rest)*)
+    };
+
+    // Next element is `true`.
+    (@seq [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!(true)] $($rest)*)
+    };
+
+    // Next element is `false`.
+    (@seq [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!(false)] $($rest)*)
+    };
+
+    // Next element is a sequence.
+    (@seq [$($elems:expr,)*] [$($seq:tt)*] $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!([$($seq)*])] $($rest)*)
+    };
+
+    // Next element is a mapping.
+    (@seq [$($elems:expr,)*] {$($map:tt)*} $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!({$($map)*})] $($rest)*)
+    };
+
+    // Next element is tagged.
+    (@seq [$($elems:expr,)*] ! $tag:tt $value:tt $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!(! $tag $value)] $($rest)*)
+    };
+
+    // Next element is an expression followed by comma.
+    (@seq [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!($next),] $($rest)*)
+    };
+
+    // Last element is an expression with no trailing comma.
+    (@seq [$($elems:expr,)*] $last:expr) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml_internal!($last)])
+    };
+
+    // Comma after the most recent element.
+    (@seq [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)*] $($rest)*)
+    };
+
+    // Unexpected token after most recent element.
+    (@seq [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+        $crate::yaml_unexpected!($unexpected)
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // TT muncher for parsing the inside of a mapping {...}. Each entry is
+    // inserted into the given map variable.
+    //
+    // Must be invoked as: yaml_internal!(@map $map () ($($tt)*) ($($tt)*))
+    //
+    // We require two copies of the input tokens so that we can match on one
+    // copy and trigger errors on the other copy.
+    //////////////////////////////////////////////////////////////////////////
+
+    // Done.
+    (@map $map:ident () () ()) => {};
+
+    // Insert the current entry followed by trailing comma.
+    (@map $map:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $map.insert($crate::yaml_internal!($($key)+), $value);
+        $crate::yaml_internal!(@map $map () ($($rest)*) ($($rest)*));
+    };
+
+    // Current entry followed by unexpected token.
+    (@map $map:ident [$($key:tt)+] ($value:expr) $unexpected:tt $($rest:tt)*) => {
+        $crate::yaml_unexpected!($unexpected);
+    };
+
+    // Insert the last entry without trailing comma.
+    (@map $map:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $map.insert($crate::yaml_internal!($($key)+), $value);
+    };
+
+    // Next value is `null`.
+    (@map $map:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!(null)) $($rest)*);
+    };
+
+    // Next value is `true`.
+    (@map $map:ident ($($key:tt)+) (: true $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!(true)) $($rest)*);
+    };
+
+    // Next value is `false`.
+    (@map $map:ident ($($key:tt)+) (: false $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!(false)) $($rest)*);
+    };
+
+    // Next value is a sequence.
+    (@map $map:ident ($($key:tt)+) (: [$($seq:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!([$($seq)*])) $($rest)*);
+    };
+
+    // Next value is a mapping.
+    (@map $map:ident ($($key:tt)+) (: {$($inner:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!({$($inner)*})) $($rest)*);
+    };
+
+    // Next value is tagged.
+    (@map $map:ident ($($key:tt)+) (: ! $tag:tt $value:tt $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!(! $tag $value)) $($rest)*);
+    };
+
+    // Next value is an expression followed by comma.
+    (@map $map:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!($value)) , $($rest)*);
+    };
+
+    // Last value is an expression with no trailing comma.
+    (@map $map:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::yaml_internal!(@map $map [$($key)+] ($crate::yaml_internal!($value)));
+    };
+
+    // Missing value for last entry. Trigger a reasonable error message.
+    (@map $map:ident ($($key:tt)+) (:) $copy:tt) => {
+        // "unexpected end of macro invocation"
+        $crate::yaml_internal!();
+    };
+
+    // Missing colon and value for last entry. Trigger a reasonable error
+    // message.
+    (@map $map:ident ($($key:tt)+) () $copy:tt) => {
+        // "unexpected end of macro invocation"
+        $crate::yaml_internal!();
+    };
+
+    // Misplaced colon. Trigger a reasonable error message.
+    (@map $map:ident () (: $($rest:tt)*) ($colon:tt $($copy:tt)*)) => {
+        // Takes no arguments so "no rules expected the token `:`".
+        $crate::yaml_unexpected!($colon);
+    };
+
+    // Found a comma inside a key. Trigger a reasonable error message.
+    (@map $map:ident ($($key:tt)*) (, $($rest:tt)*) ($comma:tt $($copy:tt)*)) => {
+        // Takes no arguments so "no rules expected the token `,`".
+        $crate::yaml_unexpected!($comma);
+    };
+
+    // Key is fully parenthesized. This avoids clippy double_parens false
+    // positives because the parenthesization may be necessary here.
+    (@map $map:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map ($key) (: $($rest)*) (: $($rest)*));
+    };
+
+    // Munch a token into the current key.
+    (@map $map:ident ($($key:tt)*) ($tt:tt $($rest:tt)*) $copy:tt) => {
+        $crate::yaml_internal!(@map $map ($($key)* $tt) ($($rest)*) ($($rest)*));
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // The main implementation.
+    //
+    // Must be invoked as: yaml_internal!($($yaml)+)
+    //////////////////////////////////////////////////////////////////////////
+
+    (null) => {
+        $crate::Value::null()
+    };
+
+    (true) => {
+        $crate::Value::bool(true)
+    };
+
+    (false) => {
+        $crate::Value::bool(false)
+    };
+
+    ([]) => {
+        $crate::Value::sequence($crate::Sequence::new())
+    };
+
+    ([ $($tt:tt)+ ]) => {
+        $crate::Value::sequence($crate::Sequence::from($crate::yaml_internal!(@seq [] $($tt)+)))
+    };
+
+    ({}) => {
+        $crate::Value::mapping($crate::Mapping::new())
+    };
+
+    ({ $($tt:tt)+ }) => {
+        $crate::Value::mapping({
+            let mut map = $crate::Mapping::new();
+            $crate::yaml_internal!(@map map () ($($tt)+) ($($tt)+));
+            map
+        })
+    };
+
+    (! $tag:ident $($value:tt)+) => {
+        $crate::Value::tagged($crate::value::TaggedValue {
+            tag: $crate::value::Tag::new(::core::stringify!($tag)),
+            value: $crate::yaml_internal!($($value)+),
+        })
+    };
+
+    (! ($tag:expr) $($value:tt)+) => {
+        $crate::Value::tagged($crate::value::TaggedValue {
+            tag: $crate::value::Tag::new($tag),
+            value: $crate::yaml_internal!($($value)+),
+        })
+    };
+
+    // Any Serialize type: numbers, strings, struct literals, variables etc.
+    // Must be below every other rule.
+    ($other:expr) => {
+        $crate::to_value(&$other).unwrap()
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! yaml_unexpected {
+    () => {};
+}
@@ -1,8 +1,11 @@
 //! A YAML mapping and its iterator types.
 
+use crate::config;
 use crate::path::Path;
 use crate::value::{DuplicateKeyCallback, ValueVisitor};
-use crate::{private, Value};
+use crate::warnings::{self, WarningKind};
+use crate::{error, private, Span, Value};
+use indexmap::map::MutableKeys;
 use indexmap::IndexMap;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::cmp::Ordering;
@@ -12,9 +15,14 @@ use std::hash::{Hash, Hasher};
 use std::mem;
 
 /// A YAML mapping in which the keys and values are both `dbt_serde_yaml::Value`.
-#[derive(Clone, Default, Eq, PartialEq)]
+#[derive(Clone, Default)]
 pub struct Mapping {
     map: IndexMap<Value, Value>,
+    /// Entries whose keys were already in `map` when they were read with
+    /// [`DuplicateKey::KeepAll`]. Boxed so that they cost a single pointer in
+    /// every `Value`, since they are rare.
+    #[allow(clippy::box_collection)]
+    duplicates: Option<Box<Vec<(Value, Value)>>>,
 }
 
 impl Mapping {
@@ -29,6 +37,7 @@ impl Mapping {
     pub fn with_capacity(capacity: usize) -> Self {
         Mapping {
             map: IndexMap::with_capacity(capacity),
+            duplicates: None,
         }
     }
 
@@ -71,6 +80,46 @@ impl Mapping {
         index.index_into(self)
     }
 
+    /// Returns the span of the key in the map equal to the given one, i.e.
+    /// where that key is written in the source, as opposed to its value.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Mapping;
+    /// let mapping: Mapping = dbt_serde_yaml::from_str("name: orders").unwrap();
+    /// let span = mapping.span_of("name").unwrap();
+    /// assert_eq!((span.start.line, span.start.column), (1, 1));
+    /// ```
+    #[inline]
+    pub fn span_of<I: Index>(&self, index: I) -> Option<&Span> {
+        index.key_into(self).map(Value::span)
+    }
+
+    /// Returns the key stored in the map equal to the given one, along with
+    /// its value.
+    ///
+    /// The stored key carries its own span, so diagnostics can point at where
+    /// the key is written rather than at its value.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Mapping;
+    /// let mapping: Mapping = dbt_serde_yaml::from_str("materialized: tabel").unwrap();
+    /// let (key, value) = mapping.get_key_value("materialized").unwrap();
+    /// assert_eq!(value.as_str(), Some("tabel"));
+    /// assert_eq!(key.span().start.column, 1);
+    /// assert_eq!(value.span().start.column, 15);
+    /// ```
+    #[inline]
+    pub fn get_key_value<I: Index>(&self, index: I) -> Option<(&Value, &Value)> {
+        index.full_into(self).map(|(_, key, value)| (key, value))
+    }
+
+    /// Returns the position of the entry with the given key in the map, along
+    /// with the stored key and its value.
+    #[inline]
+    pub fn get_full<I: Index>(&self, index: I) -> Option<(usize, &Value, &Value)> {
+        index.full_into(self)
+    }
+
     /// Returns the mutable reference corresponding to the key in the map.
     #[inline]
     pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
@@ -159,6 +208,36 @@ impl Mapping {
         self.map.retain(keep);
     }
 
+    /// Sorts the entries of the map by key.
+    ///
+    /// Keys are ordered by type first (null, bool, number, string, sequence,
+    /// mapping, tagged) and then by value, with strings compared bytewise.
+    /// Nested mappings are left untouched; see [Mapping::sort_keys_recursive].
+    ///
+    /// ```
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// use dbt_serde_yaml::Mapping;
+    ///
+    /// let mut mapping: Mapping = dbt_serde_yaml::from_str("b: 1\na: 2\n")?;
+    /// mapping.sort_keys();
+    /// assert_eq!(dbt_serde_yaml::to_string(&mapping)?, "a: 2\nb: 1\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sort_keys(&mut self) {
+        self.map.sort_by(|a, _, b, _| total_cmp(a, b));
+    }
+
+    /// Sorts the entries of the map by key, and likewise those of every
+    /// mapping nested in its values, including inside sequences and tagged
+    /// values.
+    pub fn sort_keys_recursive(&mut self) {
+        self.sort_keys();
+        for value in self.values_mut() {
+            sort_keys_recursive(value);
+        }
+    }
+
     /// Returns the maximum number of key-value pairs the map can hold without
     /// reallocating.
     #[inline]
@@ -202,6 +281,20 @@ impl Mapping {
         }
     }
 
+    /// Visits all key-value pairs in order with mutable access to the keys,
+    /// which must only be used to change their spans: the hash and equality of
+    /// a key don't depend on its span, but do on everything else.
+    pub(crate) fn iter_mut_with_keys(&mut self) -> impl Iterator<Item = (&mut Value, &mut Value)> {
+        self.map.iter_mut2()
+    }
+
+    /// Replaces the entry at `index` by `key` and `value`, keeping its
+    /// position. `key` must not be the key of another entry.
+    pub(crate) fn replace_index(&mut self, index: usize, key: Value, value: Value) {
+        self.map.shift_remove_index(index);
+        self.map.shift_insert(index, key, value);
+    }
+
     /// Return an iterator over the keys of the map.
     pub fn keys(&self) -> Keys<'_> {
         Keys {
@@ -209,6 +302,13 @@ impl Mapping {
         }
     }
 
+    /// Return an iterator over the keys of the map together with their spans.
+    pub fn keys_spanned(&self) -> KeysSpanned<'_> {
+        KeysSpanned {
+            iter: self.map.keys(),
+        }
+    }
+
     /// Return an owning iterator over the keys of the map.
     pub fn into_keys(self) -> IntoKeys {
         IntoKeys {
@@ -223,6 +323,14 @@ impl Mapping {
         }
     }
 
+    /// Return an iterator over the values of the map together with their
+    /// spans.
+    pub fn values_spanned(&self) -> ValuesSpanned<'_> {
+        ValuesSpanned {
+            iter: self.map.values(),
+        }
+    }
+
     /// Return an iterator over mutable references to the values of the map.
     pub fn values_mut(&mut self) -> ValuesMut<'_> {
         ValuesMut {
@@ -250,6 +358,12 @@ pub trait Index: private::Sealed {
     #[doc(hidden)]
     fn index_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value>;
 
+    #[doc(hidden)]
+    fn key_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value>;
+
+    #[doc(hidden)]
+    fn full_into<'a>(&self, v: &'a Mapping) -> Option<(usize, &'a Value, &'a Value)>;
+
     #[doc(hidden)]
     fn index_into_mut<'a>(&self, v: &'a mut Mapping) -> Option<&'a mut Value>;
 
@@ -280,8 +394,16 @@ impl indexmap::Equivalent<Value> for HashLikeValue<'_> {
 // NOTE: This impl must be consistent with Value's Hash impl.
 impl Hash for HashLikeValue<'_> {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        #[cfg(not(feature = "yaml_string"))]
         const STRING: Value = Value::string(String::new());
+        #[cfg(not(feature = "yaml_string"))]
         mem::discriminant(&STRING).hash(state);
+        #[cfg(feature = "yaml_string")]
+        {
+            static STRING: std::sync::LazyLock<mem::Discriminant<Value>> =
+                std::sync::LazyLock::new(|| mem::discriminant(&Value::string(String::new())));
+            STRING.hash(state);
+        }
         self.0.hash(state);
     }
 }
@@ -293,6 +415,12 @@ impl Index for Value {
     fn index_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
         v.map.get(self)
     }
+    fn key_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
+        v.map.get_key_value(self).map(|(key, _)| key)
+    }
+    fn full_into<'a>(&self, v: &'a Mapping) -> Option<(usize, &'a Value, &'a Value)> {
+        v.map.get_full(self)
+    }
     fn index_into_mut<'a>(&self, v: &'a mut Mapping) -> Option<&'a mut Value> {
         v.map.get_mut(self)
     }
@@ -317,6 +445,14 @@ impl Index for str {
     fn index_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
         v.map.get(&HashLikeValue(self))
     }
+    fn key_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
+        v.map
+            .get_key_value(&HashLikeValue(self))
+            .map(|(key, _)| key)
+    }
+    fn full_into<'a>(&self, v: &'a Mapping) -> Option<(usize, &'a Value, &'a Value)> {
+        v.map.get_full(&HashLikeValue(self))
+    }
     fn index_into_mut<'a>(&self, v: &'a mut Mapping) -> Option<&'a mut Value> {
         v.map.get_mut(&HashLikeValue(self))
     }
@@ -341,6 +477,12 @@ impl Index for String {
     fn index_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
         self.as_str().index_into(v)
     }
+    fn key_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
+        self.as_str().key_into(v)
+    }
+    fn full_into<'a>(&self, v: &'a Mapping) -> Option<(usize, &'a Value, &'a Value)> {
+        self.as_str().full_into(v)
+    }
     fn index_into_mut<'a>(&self, v: &'a mut Mapping) -> Option<&'a mut Value> {
         self.as_str().index_into_mut(v)
     }
@@ -368,6 +510,12 @@ where
     fn index_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
         (**self).index_into(v)
     }
+    fn key_into<'a>(&self, v: &'a Mapping) -> Option<&'a Value> {
+        (**self).key_into(v)
+    }
+    fn full_into<'a>(&self, v: &'a Mapping) -> Option<(usize, &'a Value, &'a Value)> {
+        (**self).full_into(v)
+    }
     fn index_into_mut<'a>(&self, v: &'a mut Mapping) -> Option<&'a mut Value> {
         (**self).index_into_mut(v)
     }
@@ -385,7 +533,16 @@ where
     }
 }
 
-#[allow(clippy::derived_hash_with_manual_eq)]
+/// Duplicate entries kept aside with [`DuplicateKey::KeepAll`] are not
+/// compared.
+impl PartialEq for Mapping {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl Eq for Mapping {}
+
 impl Hash for Mapping {
     fn hash<H: Hasher>(&self, state: &mut H) {
         // Hash the kv pairs in a way that is not sensitive to their order.
@@ -400,81 +557,89 @@ impl Hash for Mapping {
     }
 }
 
-impl PartialOrd for Mapping {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let mut self_entries = Vec::from_iter(self);
-        let mut other_entries = Vec::from_iter(other);
-
-        // Sort in an arbitrary order that is consistent with Value's PartialOrd
-        // impl.
-        fn total_cmp(a: &Value, b: &Value) -> Ordering {
-            match (a, b) {
-                (Value::Null(..), Value::Null(..)) => Ordering::Equal,
-                (Value::Null(..), _) => Ordering::Less,
-                (_, Value::Null(..)) => Ordering::Greater,
-
-                (Value::Bool(a, ..), Value::Bool(b, ..)) => a.cmp(b),
-                (Value::Bool(..), _) => Ordering::Less,
-                (_, Value::Bool(..)) => Ordering::Greater,
-
-                (Value::Number(a, ..), Value::Number(b, ..)) => a.total_cmp(b),
-                (Value::Number(..), _) => Ordering::Less,
-                (_, Value::Number(..)) => Ordering::Greater,
-
-                (Value::String(a, ..), Value::String(b, ..)) => a.cmp(b),
-                (Value::String(..), _) => Ordering::Less,
-                (_, Value::String(..)) => Ordering::Greater,
-
-                (Value::Sequence(a, ..), Value::Sequence(b, ..)) => iter_cmp_by(a, b, total_cmp),
-                (Value::Sequence(..), _) => Ordering::Less,
-                (_, Value::Sequence(..)) => Ordering::Greater,
-
-                (Value::Mapping(a, ..), Value::Mapping(b, ..)) => {
-                    iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
-                        total_cmp(ak, bk).then_with(|| total_cmp(av, bv))
-                    })
-                }
-                (Value::Mapping(..), _) => Ordering::Less,
-                (_, Value::Mapping(..)) => Ordering::Greater,
+fn sort_keys_recursive(value: &mut Value) {
+    match value {
+        Value::Sequence(sequence, ..) => sequence.iter_mut().for_each(sort_keys_recursive),
+        Value::Mapping(mapping, ..) => mapping.sort_keys_recursive(),
+        Value::Tagged(tagged, ..) => sort_keys_recursive(&mut tagged.value),
+        _ => {}
+    }
+}
 
-                (Value::Tagged(a, ..), Value::Tagged(b, ..)) => a
-                    .tag
-                    .cmp(&b.tag)
-                    .then_with(|| total_cmp(&a.value, &b.value)),
-            }
+// An arbitrary total order that is consistent with Value's PartialOrd impl.
+pub(crate) fn total_cmp(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null(..), Value::Null(..)) => Ordering::Equal,
+        (Value::Null(..), _) => Ordering::Less,
+        (_, Value::Null(..)) => Ordering::Greater,
+
+        (Value::Bool(a, ..), Value::Bool(b, ..)) => a.cmp(b),
+        (Value::Bool(..), _) => Ordering::Less,
+        (_, Value::Bool(..)) => Ordering::Greater,
+
+        (Value::Number(a, ..), Value::Number(b, ..)) => a.total_cmp(b),
+        (Value::Number(..), _) => Ordering::Less,
+        (_, Value::Number(..)) => Ordering::Greater,
+
+        (Value::String(a, ..), Value::String(b, ..)) => a.cmp(b),
+        (Value::String(..), _) => Ordering::Less,
+        (_, Value::String(..)) => Ordering::Greater,
+
+        (Value::Sequence(a, ..), Value::Sequence(b, ..)) => iter_cmp_by(a, b, total_cmp),
+        (Value::Sequence(..), _) => Ordering::Less,
+        (_, Value::Sequence(..)) => Ordering::Greater,
+
+        (Value::Mapping(a, ..), Value::Mapping(b, ..)) => {
+            iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
+                total_cmp(ak, bk).then_with(|| total_cmp(av, bv))
+            })
         }
+        (Value::Mapping(..), _) => Ordering::Less,
+        (_, Value::Mapping(..)) => Ordering::Greater,
 
-        fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
-        where
-            I: IntoIterator,
-            F: FnMut(I::Item, I::Item) -> Ordering,
-        {
-            let mut this = this.into_iter();
-            let mut other = other.into_iter();
-
-            loop {
-                let x = match this.next() {
-                    None => {
-                        if other.next().is_none() {
-                            return Ordering::Equal;
-                        } else {
-                            return Ordering::Less;
-                        }
-                    }
-                    Some(val) => val,
-                };
-
-                let y = match other.next() {
-                    None => return Ordering::Greater,
-                    Some(val) => val,
-                };
+        (Value::Tagged(a, ..), Value::Tagged(b, ..)) => a
+            .tag
+            .cmp(&b.tag)
+            .then_with(|| total_cmp(&a.value, &b.value)),
+    }
+}
 
-                match cmp(x, y) {
-                    Ordering::Equal => {}
-                    non_eq => return non_eq,
+fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
+where
+    I: IntoIterator,
+    F: FnMut(I::Item, I::Item) -> Ordering,
+{
+    let mut this = this.into_iter();
+    let mut other = other.into_iter();
+
+    loop {
+        let x = match this.next() {
+            None => {
+                if other.next().is_none() {
+                    return Ordering::Equal;
+                } else {
+                    return Ordering::Less;
                 }
             }
+            Some(val) => val,
+        };
+
+        let y = match other.next() {
+            None => return Ordering::Greater,
+            Some(val) => val,
+        };
+
+        match cmp(x, y) {
+            Ordering::Equal => {}
+            non_eq => return non_eq,
         }
+    }
+}
+
+impl PartialOrd for Mapping {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut self_entries = Vec::from_iter(self);
+        let mut other_entries = Vec::from_iter(other);
 
         // While sorting by map key, we get to assume that no two keys are
         // equal, otherwise they wouldn't both be in the map. This is not a safe
@@ -522,6 +687,7 @@ impl FromIterator<(Value, Value)> for Mapping {
     fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
         Mapping {
             map: IndexMap::from_iter(iter),
+            duplicates: None,
         }
     }
 }
@@ -610,6 +776,36 @@ pub struct Keys<'a> {
 
 delegate_iterator!((Keys<'a>) => &'a Value);
 
+macro_rules! spanned_iterator {
+    ($name:ident) => {
+        impl<'a> Iterator for $name<'a> {
+            type Item = (&'a Value, &'a Span);
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.iter.next().map(|value| (value, value.span()))
+            }
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl ExactSizeIterator for $name<'_> {
+            #[inline]
+            fn len(&self) -> usize {
+                self.iter.len()
+            }
+        }
+    };
+}
+
+/// Iterator of the keys of a `&dbt_serde_yaml::Mapping` and their spans.
+pub struct KeysSpanned<'a> {
+    iter: indexmap::map::Keys<'a, Value, Value>,
+}
+
+spanned_iterator!(KeysSpanned);
+
 /// Iterator of the keys of a `dbt_serde_yaml::Mapping`.
 pub struct IntoKeys {
     iter: indexmap::map::IntoKeys<Value, Value>,
@@ -624,6 +820,13 @@ pub struct Values<'a> {
 
 delegate_iterator!((Values<'a>) => &'a Value);
 
+/// Iterator of the values of a `&dbt_serde_yaml::Mapping` and their spans.
+pub struct ValuesSpanned<'a> {
+    iter: indexmap::map::Values<'a, Value, Value>,
+}
+
+spanned_iterator!(ValuesSpanned);
+
 /// Iterator of the values of a `&mut dbt_serde_yaml::Mapping`.
 pub struct ValuesMut<'a> {
     iter: indexmap::map::ValuesMut<'a, Value, Value>,
@@ -790,7 +993,7 @@ impl<'de> Deserialize<'de> for Mapping {
         D: Deserializer<'de>,
     {
         deserializer.deserialize_map(MappingVisitor {
-            callback: &mut |_, _, _| DuplicateKey::Error,
+            callback: &mut |_, _, _| config::duplicate_keys(),
             path: Path::Root,
         })
     }
@@ -803,27 +1006,179 @@ struct DuplicateKeyError<'a> {
 impl Display for DuplicateKeyError<'_> {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter.write_str("duplicate entry ")?;
-        match self.entry.key() {
-            Value::Null(..) => formatter.write_str("with null key"),
-            Value::Bool(boolean, ..) => write!(formatter, "with key `{}`", boolean),
-            Value::Number(number, ..) => write!(formatter, "with key {}", number),
-            Value::String(string, ..) => write!(formatter, "with key {:?}", string),
+        let first = self.entry.key();
+        match first {
+            Value::Null(..) => formatter.write_str("with null key")?,
+            Value::Bool(boolean, ..) => write!(formatter, "with key `{}`", boolean)?,
+            Value::Number(number, ..) => write!(formatter, "with key {}", number)?,
+            Value::String(string, ..) => write!(formatter, "with key {:?}", string)?,
             Value::Sequence(..) | Value::Mapping(..) | Value::Tagged(..) => {
-                formatter.write_str("in YAML map")
+                formatter.write_str("in YAML map")?
             }
         }
+        if first.span().is_valid() {
+            write!(formatter, " (first defined at {})", first.span().start)?;
+        }
+        Ok(())
     }
 }
 
 /// The behavior to take when a duplicate key is encountered during
 /// deserialization.
+///
+/// More behaviors may be added, so a `match` on this enum needs a wildcard
+/// arm.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum DuplicateKey {
     /// Immediately stop deserialization and return an error.
+    #[default]
     Error,
     /// Ignore the duplicate key and continue deserialization.
     Ignore,
     /// Overwrite the existing value with the new value.
     Overwrite,
+    /// Keep the existing value, and set the duplicate entry aside in
+    /// [`Mapping::duplicates`] with the spans of its key and value, so that
+    /// every duplicate can be reported after deserialization.
+    KeepAll,
+    /// Like [`Ignore`](DuplicateKey::Ignore), keeping the first value, and
+    /// report the duplicate as a [`WarningKind::DuplicateKey`] warning to the
+    /// enclosing [collect_warnings](crate::collect_warnings) scope.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::{collect_warnings, Config, Value, WarningKind};
+    ///
+    /// let mut config = Config::new();
+    /// config.duplicate_keys = DuplicateKey::IgnoreAndRecord;
+    ///
+    /// let yaml = "name: orders\nname: customers\n";
+    /// let (value, warnings) = collect_warnings(|| {
+    ///     dbt_serde_yaml::from_str_with_config::<Value>(yaml, &config)
+    /// });
+    ///
+    /// assert_eq!(value.unwrap()["name"], "orders");
+    /// let warning = warnings.iter().next().unwrap();
+    /// assert_eq!(warning.span.start.line, 2);
+    /// match &warning.kind {
+    ///     WarningKind::DuplicateKey { path, first } => {
+    ///         assert_eq!(path.to_string(), "name");
+    ///         assert_eq!(first.line, 1);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    IgnoreAndRecord,
+    /// Like [`Overwrite`](DuplicateKey::Overwrite), keeping the last value,
+    /// and report the duplicate as a [`WarningKind::DuplicateKey`] warning to
+    /// the enclosing [collect_warnings](crate::collect_warnings) scope.
+    OverwriteAndRecord,
+}
+
+/// Reports the duplicate `key`, read at `path`, of `first` to the enclosing
+/// warnings scope.
+fn warn_duplicate_key(path: Path, key: &Value, first: &Value) {
+    if warnings::is_collecting() {
+        let kind = WarningKind::DuplicateKey {
+            path: path.to_owned_path(),
+            first: first.span().start,
+        };
+        warnings::warn(kind, key.span().clone());
+    }
+}
+
+/// What to do with an entry read while deserializing a mapping.
+pub(crate) enum Accept {
+    Insert,
+    Skip,
+    KeepDuplicate,
+}
+
+impl Mapping {
+    /// Decides whether the value of `key`, read at `path` while deserializing
+    /// this mapping, is to be inserted, asking `callback` if the mapping
+    /// already contains an equal key. Fails if the callback rejects the
+    /// duplicate.
+    pub(crate) fn accept_key<E>(
+        &mut self,
+        key: &Value,
+        path: Path,
+        callback: DuplicateKeyCallback,
+    ) -> Result<Accept, E>
+    where
+        E: serde::de::Error,
+    {
+        let Some((existing_key, _)) = self.map.get_key_value(key) else {
+            return Ok(Accept::Insert);
+        };
+        let action =
+            crate::callback::guard(&path, key.span(), || callback(path, key, existing_key))
+                .map_err(|err| error::custom_at(err.display_no_mark(), key.span()))?;
+        match action {
+            DuplicateKey::Error => {
+                let span = key.span().clone();
+                let entry = match self.entry(key.clone()) {
+                    Entry::Occupied(entry) => entry,
+                    Entry::Vacant(_) => unreachable!(),
+                };
+                Err(error::custom_at(DuplicateKeyError { entry }, &span))
+            }
+            DuplicateKey::Ignore => Ok(Accept::Skip),
+            DuplicateKey::Overwrite => Ok(Accept::Insert),
+            DuplicateKey::KeepAll => Ok(Accept::KeepDuplicate),
+            DuplicateKey::IgnoreAndRecord => {
+                warn_duplicate_key(path, key, existing_key);
+                Ok(Accept::Skip)
+            }
+            DuplicateKey::OverwriteAndRecord => {
+                warn_duplicate_key(path, key, existing_key);
+                Ok(Accept::Insert)
+            }
+        }
+    }
+
+    /// Records an entry whose key duplicates one already in the mapping.
+    pub(crate) fn push_duplicate(&mut self, key: Value, value: Value) {
+        self.duplicates
+            .get_or_insert_with(Box::default)
+            .push((key, value));
+    }
+
+    /// The entries that were read after an equal key while deserializing this
+    /// mapping with [`DuplicateKey::KeepAll`], in the order they appear in the
+    /// source. The mapping itself holds the first value of each key.
+    ///
+    /// Duplicates don't take part in comparisons, hashing or serialization of
+    /// the mapping.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let yaml = "name: orders\nname: customers\nname: payments\n";
+    /// let value = Value::from_str(yaml, |_, _, _| DuplicateKey::KeepAll).unwrap();
+    /// let mapping = value.as_mapping().unwrap();
+    /// assert_eq!(mapping["name"], "orders");
+    ///
+    /// let lines: Vec<_> = mapping
+    ///     .duplicates()
+    ///     .iter()
+    ///     .map(|(key, value)| (key.span().start.line, value.as_str().unwrap()))
+    ///     .collect();
+    /// assert_eq!(lines, [(2, "customers"), (3, "payments")]);
+    /// ```
+    pub fn duplicates(&self) -> &[(Value, Value)] {
+        self.duplicates.as_deref().map_or(&[], Vec::as_slice)
+    }
+
+    /// Removes the duplicate entries kept aside by
+    /// [`DuplicateKey::KeepAll`] and returns them.
+    pub fn take_duplicates(&mut self) -> Vec<(Value, Value)> {
+        self.duplicates
+            .take()
+            .map_or_else(Vec::new, |duplicates| *duplicates)
+    }
 }
 
 pub(crate) struct MappingVisitor<'d, 'b> {
@@ -858,6 +1213,11 @@ impl<'de> serde::de::Visitor<'de> for MappingVisitor<'_, '_> {
             callback,
             path: self.path,
         })? {
+            #[cfg(feature = "intern")]
+            let key = match key {
+                Value::String(string, span) => Value::String(crate::value::intern(&string), span),
+                key => key,
+            };
             let path = if let Some(key) = key.as_str() {
                 Path::Map {
                     parent: &self.path,
@@ -867,30 +1227,24 @@ impl<'de> serde::de::Visitor<'de> for MappingVisitor<'_, '_> {
                 Path::Unknown { parent: &self.path }
             };
 
-            if let Some((existing_key, _)) = mapping.map.get_key_value(&key) {
-                match callback(path, &key, existing_key) {
-                    DuplicateKey::Error => {
-                        let entry = match mapping.entry(key) {
-                            Entry::Occupied(entry) => entry,
-                            Entry::Vacant(_) => unreachable!(),
-                        };
-
-                        return Err(serde::de::Error::custom(DuplicateKeyError { entry }));
-                    }
-                    DuplicateKey::Ignore => {
-                        let _ = data.next_value_seed(ValueVisitor { callback, path })?;
-                    }
-                    DuplicateKey::Overwrite => {
-                        let value = data.next_value_seed(ValueVisitor { callback, path })?;
-                        mapping.insert(key, value);
-                    }
+            match mapping.accept_key(&key, path, callback)? {
+                Accept::Insert => {
+                    let value = data.next_value_seed(ValueVisitor { callback, path })?;
+                    mapping.insert(key, value);
+                }
+                Accept::Skip => {
+                    let _ = data.next_value_seed(ValueVisitor { callback, path })?;
+                }
+                Accept::KeepDuplicate => {
+                    let value = data.next_value_seed(ValueVisitor { callback, path })?;
+                    mapping.push_duplicate(key, value);
                 }
-            } else {
-                let value = data.next_value_seed(ValueVisitor { callback, path })?;
-                mapping.insert(key, value);
             }
         }
 
+        if config::sort_keys() {
+            mapping.sort_keys();
+        }
         Ok(mapping)
     }
 }
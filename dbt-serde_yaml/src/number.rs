@@ -1,7 +1,10 @@
 use crate::de;
 use crate::error::{self, Error, ErrorImpl};
+#[cfg(feature = "arbitrary_precision")]
+use serde::de::{IntoDeserializer, MapAccess};
 use serde::de::{Unexpected, Visitor};
 use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt::{self, Display};
 use std::hash::{Hash, Hasher};
@@ -16,15 +19,24 @@ pub struct Number {
 // "N" is a prefix of "NegInt"... this is a false positive.
 // https://github.com/Manishearth/rust-clippy/issues/1241
 #[allow(clippy::enum_variant_names)]
-#[derive(Copy, Clone)]
+#[cfg_attr(not(feature = "arbitrary_precision"), derive(Copy))]
+#[derive(Clone)]
 enum N {
     PosInt(u64),
     /// Always less than zero.
     NegInt(i64),
     /// May be infinite or NaN.
     Float(f64),
+    /// A decimal that does not fit any of the above without losing precision.
+    #[cfg(feature = "arbitrary_precision")]
+    Big(Box<str>),
 }
 
+/// The name of the private newtype struct and map key through which
+/// arbitrary-precision numbers travel through Serde.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) const TOKEN: &str = "$dbt_serde_yaml::private::Number";
+
 impl Number {
     /// Returns true if the `Number` is an integer between `i64::MIN` and
     /// `i64::MAX`.
@@ -58,6 +70,8 @@ impl Number {
             N::PosInt(v) => v <= i64::MAX as u64,
             N::NegInt(_) => true,
             N::Float(_) => false,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => false,
         }
     }
 
@@ -89,6 +103,8 @@ impl Number {
         match self.n {
             N::PosInt(_) => true,
             N::NegInt(_) | N::Float(_) => false,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => false,
         }
     }
 
@@ -121,12 +137,18 @@ impl Number {
         match self.n {
             N::Float(_) => true,
             N::PosInt(_) | N::NegInt(_) => false,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => false,
         }
     }
 
     /// If the `Number` is an integer, represent it as i64 if possible. Returns
     /// None otherwise.
     ///
+    /// An integer greater than `i64::MAX` gives None rather than wrapping
+    /// around; get it with [as_u64](Number::as_u64) or
+    /// [as_i128](Number::as_i128) instead.
+    ///
     /// ```
     /// # fn main() -> dbt_serde_yaml::Result<()> {
     /// let big = i64::MAX as u64 + 10;
@@ -154,6 +176,8 @@ impl Number {
             }
             N::NegInt(n) => Some(n),
             N::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => None,
         }
     }
 
@@ -179,6 +203,92 @@ impl Number {
         match self.n {
             N::PosInt(n) => Some(n),
             N::NegInt(_) | N::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => None,
+        }
+    }
+
+    /// If the `Number` is an integer, represent it as i128. Returns None
+    /// otherwise.
+    ///
+    /// Unlike [as_i64](Number::as_i64) and [as_u64](Number::as_u64), this
+    /// gives every integer that a YAML number holds without the
+    /// `arbitrary_precision` feature, whatever its sign.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// let big: Number = "9223372036854775817".parse().unwrap();
+    /// assert_eq!(big.as_i64(), None);
+    /// assert_eq!(big.as_i128(), Some(9223372036854775817));
+    /// assert_eq!(Number::from(-64).as_i128(), Some(-64));
+    /// assert_eq!(Number::from(2.5).as_i128(), None);
+    /// ```
+    #[inline]
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.n {
+            N::PosInt(n) => Some(n.into()),
+            N::NegInt(n) => Some(n.into()),
+            N::Float(_) => None,
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => repr.parse().ok(),
+        }
+    }
+
+    /// Adds two numbers, returning None if the result doesn't fit.
+    ///
+    /// The sum of two integers is exact, and None if it is outside the range
+    /// from `i64::MIN` to `u64::MAX`. If either number is a float, they are
+    /// added as f64.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// let max = Number::from(u64::MAX);
+    /// assert_eq!(Number::from(i64::MIN).checked_add(&max), Some(Number::from(i64::MAX as u64)));
+    /// assert_eq!(max.checked_add(&Number::from(1)), None);
+    /// assert_eq!(Number::from(1).checked_add(&Number::from(0.5)), Some(Number::from(1.5)));
+    /// ```
+    pub fn checked_add(&self, rhs: &Number) -> Option<Number> {
+        self.checked_op(rhs, i128::checked_add, |a, b| a + b)
+    }
+
+    /// Subtracts `rhs` from this number, returning None if the result doesn't
+    /// fit; see [checked_add](Number::checked_add).
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// assert_eq!(Number::from(0).checked_sub(&Number::from(u64::MAX)), None);
+    /// assert_eq!(Number::from(1).checked_sub(&Number::from(3)), Some(Number::from(-2)));
+    /// ```
+    pub fn checked_sub(&self, rhs: &Number) -> Option<Number> {
+        self.checked_op(rhs, i128::checked_sub, |a, b| a - b)
+    }
+
+    /// Multiplies two numbers, returning None if the result doesn't fit; see
+    /// [checked_add](Number::checked_add).
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// assert_eq!(Number::from(u64::MAX).checked_mul(&Number::from(2)), None);
+    /// assert_eq!(Number::from(-3).checked_mul(&Number::from(4)), Some(Number::from(-12)));
+    /// ```
+    pub fn checked_mul(&self, rhs: &Number) -> Option<Number> {
+        self.checked_op(rhs, i128::checked_mul, |a, b| a * b)
+    }
+
+    fn checked_op(
+        &self,
+        rhs: &Number,
+        int_op: fn(i128, i128) -> Option<i128>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Option<Number> {
+        match (&self.n, &rhs.n) {
+            (N::PosInt(_) | N::NegInt(_), N::PosInt(_) | N::NegInt(_)) => {
+                let n = int_op(self.as_i128()?, rhs.as_i128()?)?;
+                Number::try_from(n).ok()
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            (N::Big(_), _) | (_, N::Big(_)) => None,
+            _ => Some(Number::from(float_op(self.as_f64()?, rhs.as_f64()?))),
         }
     }
 
@@ -218,6 +328,8 @@ impl Number {
             N::PosInt(n) => Some(n as f64),
             N::NegInt(n) => Some(n as f64),
             N::Float(n) => Some(n),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => repr.parse().ok(),
         }
     }
 
@@ -241,6 +353,8 @@ impl Number {
         match self.n {
             N::PosInt(_) | N::NegInt(_) => false,
             N::Float(f) => f.is_nan(),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => false,
         }
     }
 
@@ -265,6 +379,8 @@ impl Number {
         match self.n {
             N::PosInt(_) | N::NegInt(_) => false,
             N::Float(f) => f.is_infinite(),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => false,
         }
     }
 
@@ -288,10 +404,182 @@ impl Number {
         match self.n {
             N::PosInt(_) | N::NegInt(_) => true,
             N::Float(f) => f.is_finite(),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(_) => true,
+        }
+    }
+
+    /// Parses an integer written in the given radix, with an optional leading
+    /// `+` or `-` sign and no prefix.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// #
+    /// assert_eq!(Number::from_str_radix("ff", 16).unwrap(), Number::from(255));
+    ///
+    /// assert_eq!(Number::from_str_radix("-101", 2).unwrap(), Number::from(-5));
+    ///
+    /// assert!(Number::from_str_radix("0xff", 16).is_err());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in the range from 2 to 36.
+    pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, Error> {
+        if let Ok(int) = u64::from_str_radix(src, radix) {
+            return Ok(int.into());
+        }
+        if let Ok(int) = i64::from_str_radix(src, radix) {
+            return Ok(int.into());
+        }
+        Err(error::new(ErrorImpl::FailedToParseNumber))
+    }
+
+    /// Parses a YAML integer literal, using the same rules as the
+    /// deserializer: decimal, or hexadecimal, octal and binary with a `0x`,
+    /// `0o` or `0b` prefix respectively.
+    ///
+    /// Unlike the [`FromStr`] implementation, float literals are rejected.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// #
+    /// assert_eq!(Number::from_int_literal("0x1F").unwrap(), Number::from(31));
+    ///
+    /// assert_eq!(Number::from_int_literal("-0o17").unwrap(), Number::from(-15));
+    ///
+    /// assert!(Number::from_int_literal("1.5").is_err());
+    ///
+    /// // Leading zeros make a string, not a number.
+    /// assert!(Number::from_int_literal("007").is_err());
+    /// ```
+    pub fn from_int_literal(repr: &str) -> Result<Self, Error> {
+        match de::visit_int(NumberVisitor, repr) {
+            Ok(result) => result,
+            Err(NumberVisitor) => Err(error::new(ErrorImpl::FailedToParseNumber)),
+        }
+    }
+
+    /// Parses a YAML float literal, using the same rules as the deserializer,
+    /// including `.inf`, `-.inf` and `.nan`.
+    ///
+    /// Integer literals are accepted and converted to a float.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// #
+    /// assert_eq!(Number::from_float_literal("1e3").unwrap(), Number::from(1000.0));
+    ///
+    /// assert_eq!(Number::from_float_literal("12").unwrap(), Number::from(12.0));
+    ///
+    /// assert!(Number::from_float_literal("-.INF").unwrap().is_infinite());
+    /// ```
+    pub fn from_float_literal(repr: &str) -> Result<Self, Error> {
+        if !de::digits_but_not_number(repr) {
+            #[cfg(feature = "arbitrary_precision")]
+            if is_lossy_decimal(repr) {
+                return Ok(Number::big(repr));
+            }
+            if let Some(float) = de::parse_f64(repr) {
+                return Ok(float.into());
+            }
+        }
+        Err(error::new(ErrorImpl::FailedToParseNumber))
+    }
+
+    /// Parses an integer or float literal whose digits may be separated by
+    /// underscores, as YAML 1.1 allows: `1_000`, `0b1010_0111`, `0x_FF` or
+    /// `1_000.5`.
+    ///
+    /// With the underscores removed, the literal is read as by
+    /// [`from_int_literal`](Self::from_int_literal), or failing that as by
+    /// [`from_float_literal`](Self::from_float_literal). In a decimal literal
+    /// an underscore may not come before the first digit or after the decimal
+    /// point or exponent. The deserializer itself does not accept separators,
+    /// so `1_000` in a document stays a string.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Number;
+    /// #
+    /// assert_eq!(
+    ///     Number::from_literal_with_separators("1_000").unwrap(),
+    ///     Number::from(1000),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Number::from_literal_with_separators("-0x_7F_FF").unwrap(),
+    ///     Number::from(-32767),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Number::from_literal_with_separators("685_230.15").unwrap(),
+    ///     Number::from(685230.15),
+    /// );
+    ///
+    /// assert!(Number::from_literal_with_separators("_1").is_err());
+    /// ```
+    pub fn from_literal_with_separators(repr: &str) -> Result<Self, Error> {
+        let Some(repr) = strip_digit_separators(repr) else {
+            return Err(error::new(ErrorImpl::FailedToParseNumber));
+        };
+        Number::from_int_literal(&repr).or_else(|_| Number::from_float_literal(&repr))
+    }
+
+    /// If the `Number` holds a decimal that does not fit i64, u64 or f64
+    /// without losing precision, returns its digits exactly as written.
+    /// Returns None otherwise.
+    ///
+    /// ```
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// use dbt_serde_yaml::Number;
+    ///
+    /// let a: Number = dbt_serde_yaml::from_str("123456789012345678901234567890")?;
+    /// assert_eq!(a.as_arbitrary_precision(), Some("123456789012345678901234567890"));
+    ///
+    /// let b: Number = dbt_serde_yaml::from_str("3.14159265358979323846")?;
+    /// assert_eq!(b.as_arbitrary_precision(), Some("3.14159265358979323846"));
+    ///
+    /// // Fits in f64.
+    /// let c: Number = dbt_serde_yaml::from_str("3.14")?;
+    /// assert_eq!(c.as_arbitrary_precision(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "arbitrary_precision")]
+    pub fn as_arbitrary_precision(&self) -> Option<&str> {
+        match self.n {
+            N::Big(ref repr) => Some(repr),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    pub(crate) fn big(repr: &str) -> Self {
+        Number {
+            n: N::Big(repr.strip_prefix('+').unwrap_or(repr).into()),
         }
     }
 }
 
+/// Removes the underscores that separate the digits of a YAML 1.1 number
+/// literal, or returns None if one is out of place.
+fn strip_digit_separators(repr: &str) -> Option<Cow<'_, str>> {
+    if !repr.contains('_') {
+        return Some(Cow::Borrowed(repr));
+    }
+    let unsigned = repr.strip_prefix(['-', '+']).unwrap_or(repr);
+    let is_prefixed = ["0x", "0o", "0b"]
+        .iter()
+        .any(|prefix| unsigned.starts_with(prefix));
+    if !is_prefixed {
+        let integer = unsigned.split(['.', 'e', 'E']).next().unwrap_or_default();
+        if unsigned.starts_with('_') || integer.len() < unsigned.rfind('_')? {
+            return None;
+        }
+    }
+    Some(Cow::Owned(repr.replace('_', "")))
+}
+
 impl Display for Number {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self.n {
@@ -306,6 +594,8 @@ impl Display for Number {
                 }
             }
             N::Float(f) => formatter.write_str(ryu::Buffer::new().format_finite(f)),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => formatter.write_str(repr),
         }
     }
 }
@@ -318,6 +608,10 @@ impl FromStr for Number {
             return result;
         }
         if !de::digits_but_not_number(repr) {
+            #[cfg(feature = "arbitrary_precision")]
+            if is_lossy_decimal(repr) {
+                return Ok(Number::big(repr));
+            }
             if let Some(float) = de::parse_f64(repr) {
                 return Ok(float.into());
             }
@@ -328,9 +622,11 @@ impl FromStr for Number {
 
 impl PartialEq for N {
     fn eq(&self, other: &N) -> bool {
-        match (*self, *other) {
+        match (self, other) {
             (N::PosInt(a), N::PosInt(b)) => a == b,
             (N::NegInt(a), N::NegInt(b)) => a == b,
+            #[cfg(feature = "arbitrary_precision")]
+            (N::Big(a), N::Big(b)) => a == b,
             (N::Float(a), N::Float(b)) => {
                 if a.is_nan() && b.is_nan() {
                     // YAML only has one NaN;
@@ -347,13 +643,13 @@ impl PartialEq for N {
 
 impl PartialOrd for N {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match (*self, *other) {
+        match (self, other) {
             (N::Float(a), N::Float(b)) => {
                 if a.is_nan() && b.is_nan() {
                     // YAML only has one NaN
                     Some(Ordering::Equal)
                 } else {
-                    a.partial_cmp(&b)
+                    a.partial_cmp(b)
                 }
             }
             _ => Some(self.total_cmp(other)),
@@ -363,13 +659,13 @@ impl PartialOrd for N {
 
 impl N {
     fn total_cmp(&self, other: &Self) -> Ordering {
-        match (*self, *other) {
-            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
-            (N::NegInt(a), N::NegInt(b)) => a.cmp(&b),
+        match (self, other) {
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(b),
+            (N::NegInt(a), N::NegInt(b)) => a.cmp(b),
             // negint is always less than zero
             (N::NegInt(_), N::PosInt(_)) => Ordering::Less,
             (N::PosInt(_), N::NegInt(_)) => Ordering::Greater,
-            (N::Float(a), N::Float(b)) => a.partial_cmp(&b).unwrap_or_else(|| {
+            (N::Float(a), N::Float(b)) => a.partial_cmp(b).unwrap_or_else(|| {
                 // arbitrarily sort the NaN last
                 if !a.is_nan() {
                     Ordering::Less
@@ -383,6 +679,13 @@ impl N {
             // FIXME: maybe something more sensible?
             (_, N::Float(_)) => Ordering::Less,
             (N::Float(_), _) => Ordering::Greater,
+            // and arbitrary-precision numbers above both
+            #[cfg(feature = "arbitrary_precision")]
+            (N::Big(a), N::Big(b)) => a.cmp(b),
+            #[cfg(feature = "arbitrary_precision")]
+            (_, N::Big(_)) => Ordering::Less,
+            #[cfg(feature = "arbitrary_precision")]
+            (N::Big(_), _) => Ordering::Greater,
         }
     }
 }
@@ -403,13 +706,15 @@ impl Serialize for Number {
             N::PosInt(i) => serializer.serialize_u64(i),
             N::NegInt(i) => serializer.serialize_i64(i),
             N::Float(f) => serializer.serialize_f64(f),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => serializer.serialize_newtype_struct(TOKEN, &**repr),
         }
     }
 }
 
 struct NumberVisitor;
 
-impl Visitor<'_> for NumberVisitor {
+impl<'de> Visitor<'de> for NumberVisitor {
     type Value = Number;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -430,6 +735,27 @@ impl Visitor<'_> for NumberVisitor {
     fn visit_f64<E>(self, value: f64) -> Result<Number, E> {
         Ok(value.into())
     }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_i128<E>(self, value: i128) -> Result<Number, E> {
+        Ok(Number::big(itoa::Buffer::new().format(value)))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_u128<E>(self, value: u128) -> Result<Number, E> {
+        Ok(Number::big(itoa::Buffer::new().format(value)))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_map<A>(self, mut map: A) -> Result<Number, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        match map.next_key::<String>()? {
+            Some(key) if key == TOKEN => Ok(Number::big(&map.next_value::<String>()?)),
+            _ => Err(serde::de::Error::invalid_type(Unexpected::Map, &self)),
+        }
+    }
 }
 
 impl<'de> Deserialize<'de> for Number {
@@ -454,6 +780,8 @@ impl<'de> Deserializer<'de> for Number {
             N::PosInt(i) => visitor.visit_u64(i),
             N::NegInt(i) => visitor.visit_i64(i),
             N::Float(f) => visitor.visit_f64(f),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => visitor.visit_map(BigNumberAccess::new(repr)),
         }
     }
 
@@ -476,6 +804,8 @@ impl<'de> Deserializer<'de> for &Number {
             N::PosInt(i) => visitor.visit_u64(i),
             N::NegInt(i) => visitor.visit_i64(i),
             N::Float(f) => visitor.visit_f64(f),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => visitor.visit_map(BigNumberAccess::new(repr)),
         }
     }
 
@@ -520,6 +850,82 @@ macro_rules! from_unsigned {
 from_signed!(i8 i16 i32 i64 isize);
 from_unsigned!(u8 u16 u32 u64 usize);
 
+impl TryFrom<i128> for Number {
+    type Error = Error;
+
+    /// Converts an integer between `i64::MIN` and `u64::MAX`.
+    fn try_from(i: i128) -> Result<Self, Error> {
+        if let Ok(i) = i64::try_from(i) {
+            Ok(Number::from(i))
+        } else if let Ok(u) = u64::try_from(i) {
+            Ok(Number::from(u))
+        } else {
+            Err(error::new(ErrorImpl::NumberOutOfRange(
+                i.to_string(),
+                "Number",
+            )))
+        }
+    }
+}
+
+macro_rules! try_into_int {
+    ($($int_ty:ident)*) => {
+        $(
+            impl TryFrom<&Number> for $int_ty {
+                type Error = Error;
+
+                /// Converts an integer that is in range for the target type;
+                /// floats are never converted, even if they have no fractional
+                /// part.
+                fn try_from(number: &Number) -> Result<Self, Error> {
+                    number
+                        .as_i128()
+                        .and_then(|i| $int_ty::try_from(i).ok())
+                        .ok_or_else(|| {
+                            error::new(ErrorImpl::NumberOutOfRange(
+                                number.to_string(),
+                                stringify!($int_ty),
+                            ))
+                        })
+                }
+            }
+
+            impl TryFrom<Number> for $int_ty {
+                type Error = Error;
+
+                fn try_from(number: Number) -> Result<Self, Error> {
+                    $int_ty::try_from(&number)
+                }
+            }
+        )*
+    };
+}
+
+try_into_int!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 usize);
+
+impl TryFrom<&Number> for u128 {
+    type Error = Error;
+
+    /// Converts a non-negative integer.
+    fn try_from(number: &Number) -> Result<Self, Error> {
+        let n = match number.n {
+            N::PosInt(n) => Some(n.into()),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => repr.parse().ok(),
+            _ => None,
+        };
+        n.ok_or_else(|| error::new(ErrorImpl::NumberOutOfRange(number.to_string(), "u128")))
+    }
+}
+
+impl TryFrom<Number> for u128 {
+    type Error = Error;
+
+    fn try_from(number: Number) -> Result<Self, Error> {
+        u128::try_from(&number)
+    }
+}
+
 impl From<f32> for Number {
     fn from(f: f32) -> Self {
         Number::from(f as f64)
@@ -548,6 +954,8 @@ impl Hash for Number {
             }
             N::PosInt(u) => u.hash(state),
             N::NegInt(i) => i.hash(state),
+            #[cfg(feature = "arbitrary_precision")]
+            N::Big(ref repr) => repr.hash(state),
         }
     }
 }
@@ -557,5 +965,107 @@ pub(crate) fn unexpected(number: &Number) -> Unexpected<'_> {
         N::PosInt(u) => Unexpected::Unsigned(u),
         N::NegInt(i) => Unexpected::Signed(i),
         N::Float(f) => Unexpected::Float(f),
+        #[cfg(feature = "arbitrary_precision")]
+        N::Big(ref repr) => Unexpected::Other(repr),
+    }
+}
+
+/// Returns whether `repr` is a decimal number that f64 cannot hold exactly as
+/// written: it overflows, or it has more significant digits than survive a
+/// round trip.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) fn is_lossy_decimal(repr: &str) -> bool {
+    let Some(decimal) = normalize_decimal(repr) else {
+        return false;
+    };
+    match repr.parse::<f64>() {
+        Ok(float) if float.is_finite() => {
+            let shortest = ryu::Buffer::new().format_finite(float).to_owned();
+            normalize_decimal(&shortest) != Some(decimal)
+        }
+        _ => true,
+    }
+}
+
+/// Splits a decimal like `-12.50e3` into its sign, significant digits and the
+/// power of ten applying to the first of them, or returns None if `repr` is
+/// not a plain decimal.
+#[cfg(feature = "arbitrary_precision")]
+fn normalize_decimal(repr: &str) -> Option<(bool, String, i64)> {
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let negative = repr.starts_with('-');
+    let unsigned = repr.strip_prefix(['-', '+']).unwrap_or(repr);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            let unsigned = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+            if unsigned.is_empty() || !digits(unsigned) {
+                return None;
+            }
+            (mantissa, exponent.parse::<i64>().ok()?)
+        }
+        None => (unsigned, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if integer.is_empty() && fraction.is_empty() || !digits(integer) || !digits(fraction) {
+        return None;
+    }
+    let all = format!("{integer}{fraction}");
+    let significant = all.trim_start_matches('0');
+    let leading_zeros = all.len() - significant.len();
+    let significant = significant.trim_end_matches('0');
+    if significant.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    let exponent = exponent + integer.len() as i64 - leading_zeros as i64;
+    Some((negative, significant.to_owned(), exponent))
+}
+
+/// Recognizes the single-entry mapping that an arbitrary-precision number
+/// turns into when it passes through a [Visitor] that builds mappings.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) fn from_token_mapping(mapping: &crate::Mapping) -> Option<Number> {
+    if mapping.len() != 1 {
+        return None;
+    }
+    match mapping.get(TOKEN)? {
+        crate::Value::String(repr, ..) => Some(Number::big(repr)),
+        _ => None,
+    }
+}
+
+/// Presents an arbitrary-precision number to a visitor as a single-entry map
+/// keyed by [TOKEN].
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) struct BigNumberAccess<'a> {
+    repr: Option<&'a str>,
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'a> BigNumberAccess<'a> {
+    pub(crate) fn new(repr: &'a str) -> Self {
+        BigNumberAccess { repr: Some(repr) }
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> MapAccess<'de> for BigNumberAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.repr.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(TOKEN.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let repr = self.repr.take().expect("value is missing");
+        seed.deserialize(repr.into_deserializer())
     }
 }
@@ -2,9 +2,12 @@
 
 use std::{
     cell::OnceCell,
-    fmt::{self, Display},
+    fmt::{self, Debug, Display},
+    str::FromStr,
 };
 
+use crate::error::{self, Error, ErrorImpl};
+
 /// A structured representation of a path to the current value in the input,
 /// like `dependencies.serde.typo1`.
 #[derive(Copy, Clone)]
@@ -61,6 +64,44 @@ impl Display for Path<'_> {
 }
 
 impl<'a> Path<'a> {
+    /// Returns the components of this path, starting from the root.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::path::{Path, PathComponent};
+    ///
+    /// let models = Path::Map { parent: &Path::Root, key: "models" };
+    /// let first = Path::Seq { parent: &models, index: 0 };
+    ///
+    /// let components: Vec<_> = first.components().collect();
+    /// assert_eq!(components, [PathComponent::Key("models"), PathComponent::Index(0)]);
+    /// ```
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = PathComponent<'a>> + use<'a> {
+        let mut components = Vec::new();
+        let mut path = *self;
+        loop {
+            path = match path {
+                Path::Root => break,
+                Path::Seq { parent, index } => {
+                    components.push(PathComponent::Index(index));
+                    *parent
+                }
+                Path::Map { parent, key } => {
+                    components.push(PathComponent::Key(key));
+                    *parent
+                }
+                Path::Alias { parent } => {
+                    components.push(PathComponent::Alias);
+                    *parent
+                }
+                Path::Unknown { parent } => {
+                    components.push(PathComponent::Unknown);
+                    *parent
+                }
+            };
+        }
+        components.into_iter().rev()
+    }
+
     /// Returns an owned version of this path.
     pub fn to_owned_path(&self) -> OwnedPath {
         match self {
@@ -87,7 +128,34 @@ impl<'a> Path<'a> {
     }
 }
 
+/// A single step of a [Path], as returned by [Path::components].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathComponent<'a> {
+    /// A sequence index.
+    Index(usize),
+    /// A map key.
+    Key(&'a str),
+    /// An alias.
+    Alias,
+    /// An unknown path.
+    Unknown,
+}
+
 /// An owned version of a [Path].
+///
+/// Unlike [Path], an `OwnedPath` can be stored, for example to report the
+/// paths passed to an unused key callback after deserialization has finished.
+/// It displays the same way as [Path], and can be parsed back from that form:
+///
+/// ```
+/// use dbt_serde_yaml::path::OwnedPath;
+///
+/// let path: OwnedPath = "models[2].config.tags".parse().unwrap();
+/// assert_eq!(path.to_string(), "models[2].config.tags");
+/// ```
+///
+/// Parsing is not exact for every path: aliases are not displayed, and keys
+/// containing `.` or `[` are indistinguishable from nested paths.
 pub enum OwnedPath {
     /// The root of the input.
     Root,
@@ -185,3 +253,237 @@ impl<'a> OwnedPath {
         }
     }
 }
+
+impl OwnedPath {
+    /// Returns the components of this path, starting from the root.
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = PathComponent<'_>> {
+        self.as_path().components()
+    }
+
+    /// Returns the path to the element at `index` of the sequence at this
+    /// path.
+    pub fn push_index(self, index: usize) -> OwnedPath {
+        OwnedPath::Seq {
+            parent: Box::new(self),
+            index,
+            borrowed: OnceCell::new(),
+        }
+    }
+
+    /// Returns the path to the value at `key` of the mapping at this path.
+    pub fn push_key(self, key: impl Into<String>) -> OwnedPath {
+        OwnedPath::Map {
+            parent: Box::new(self),
+            key: key.into(),
+            borrowed: OnceCell::new(),
+        }
+    }
+
+    /// Returns the path to the node anchored by an alias at this path.
+    pub(crate) fn push_alias(self) -> OwnedPath {
+        OwnedPath::Alias {
+            parent: Box::new(self),
+            borrowed: OnceCell::new(),
+        }
+    }
+
+    /// Returns the path to a value at an unknown position, such as that of a
+    /// non-string key, of the mapping at this path.
+    pub(crate) fn push_unknown(self) -> OwnedPath {
+        OwnedPath::Unknown {
+            parent: Box::new(self),
+            borrowed: OnceCell::new(),
+        }
+    }
+}
+
+impl Clone for OwnedPath {
+    fn clone(&self) -> Self {
+        self.as_path().to_owned_path()
+    }
+}
+
+impl PartialEq for OwnedPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.components().eq(other.components())
+    }
+}
+
+impl Eq for OwnedPath {}
+
+impl Display for OwnedPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        Display::fmt(self.as_path(), formatter)
+    }
+}
+
+impl Debug for OwnedPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "OwnedPath({})", self)
+    }
+}
+
+impl FromStr for OwnedPath {
+    type Err = Error;
+
+    fn from_str(repr: &str) -> Result<Self, Self::Err> {
+        let invalid = || error::new(ErrorImpl::FailedToParsePath);
+
+        let mut path = OwnedPath::Root;
+        if repr == "." {
+            return Ok(path);
+        }
+        let mut rest = repr.strip_prefix('.').unwrap_or(repr);
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let index = after[..end].parse().map_err(|_| invalid())?;
+                path = path.push_index(index);
+                rest = &after[end + 1..];
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                path = match &rest[..end] {
+                    "" => return Err(invalid()),
+                    "?" => OwnedPath::Unknown {
+                        parent: Box::new(path),
+                        borrowed: OnceCell::new(),
+                    },
+                    key => path.push_key(key),
+                };
+                rest = &rest[end..];
+            }
+            // A key must be separated from whatever preceded it by a dot.
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.is_empty() || after.starts_with('[') {
+                    return Err(invalid());
+                }
+                rest = after;
+            } else if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+        Ok(path)
+    }
+}
+
+impl From<Path<'_>> for OwnedPath {
+    fn from(path: Path<'_>) -> Self {
+        path.to_owned_path()
+    }
+}
+
+/// A pattern matching [Path]s, written like a displayed path with wildcards.
+///
+/// A `*` component matches any single key, `[*]` any single index, and `**`
+/// any number of components, including none. `?` matches a value at an
+/// unknown position, like that of a non-string key. Aliases are skipped when
+/// matching, as they are when displaying a path.
+///
+/// ```
+/// use dbt_serde_yaml::path::{OwnedPath, PathPattern};
+///
+/// let pattern: PathPattern = "models[*].config.*".parse().unwrap();
+/// let path: OwnedPath = "models[3].config.tags".parse().unwrap();
+/// assert!(pattern.matches(path.as_path()));
+///
+/// let pattern: PathPattern = "**.meta".parse().unwrap();
+/// let path: OwnedPath = "sources[0].tables[1].meta".parse().unwrap();
+/// assert!(pattern.matches(path.as_path()));
+/// assert!(!pattern.matches(OwnedPath::Root.push_key("meta_data").as_path()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    AnyKey,
+    Index(usize),
+    AnyIndex,
+    Unknown,
+    AnyPath,
+}
+
+impl PathPattern {
+    /// Returns whether `path` matches this pattern.
+    pub fn matches(&self, path: &Path<'_>) -> bool {
+        let components: Vec<_> = path
+            .components()
+            .filter(|component| *component != PathComponent::Alias)
+            .collect();
+        matches_from(&self.segments, &components)
+    }
+}
+
+fn matches_from(segments: &[Segment], components: &[PathComponent<'_>]) -> bool {
+    let Some((segment, segments)) = segments.split_first() else {
+        return components.is_empty();
+    };
+    if *segment == Segment::AnyPath {
+        return (0..=components.len()).any(|skip| matches_from(segments, &components[skip..]));
+    }
+    let Some((component, components)) = components.split_first() else {
+        return false;
+    };
+    let matched = match (segment, component) {
+        (Segment::Key(expected), PathComponent::Key(key)) => expected == key,
+        (Segment::AnyKey, PathComponent::Key(_) | PathComponent::Unknown) => true,
+        (Segment::Index(expected), PathComponent::Index(index)) => expected == index,
+        (Segment::AnyIndex, PathComponent::Index(_)) => true,
+        (Segment::Unknown, PathComponent::Unknown) => true,
+        _ => false,
+    };
+    matched && matches_from(segments, components)
+}
+
+impl FromStr for PathPattern {
+    type Err = Error;
+
+    fn from_str(repr: &str) -> Result<Self, Self::Err> {
+        let invalid = || error::new(ErrorImpl::FailedToParsePath);
+
+        let mut segments = Vec::new();
+        if repr == "." {
+            return Ok(PathPattern { segments });
+        }
+        let mut rest = repr.strip_prefix('.').unwrap_or(repr);
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                segments.push(match &after[..end] {
+                    "*" => Segment::AnyIndex,
+                    index => Segment::Index(index.parse().map_err(|_| invalid())?),
+                });
+                rest = &after[end + 1..];
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                segments.push(match &rest[..end] {
+                    "" => return Err(invalid()),
+                    "?" => Segment::Unknown,
+                    "*" => Segment::AnyKey,
+                    "**" => Segment::AnyPath,
+                    key => Segment::Key(key.to_owned()),
+                });
+                rest = &rest[end..];
+            }
+            // A key must be separated from whatever preceded it by a dot.
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.is_empty() || after.starts_with('[') {
+                    return Err(invalid());
+                }
+                rest = after;
+            } else if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+        Ok(PathPattern { segments })
+    }
+}
@@ -2,18 +2,28 @@
 //!
 //! This module provides YAML serialization with the type `Serializer`.
 
+use crate::binary;
+use crate::comments::{self, Comments};
+use crate::config::Config;
+use crate::de::ScalarSchema;
 use crate::error::{self, Error, ErrorImpl};
 use crate::libyaml;
 use crate::libyaml::emitter::{Emitter, Event, Mapping, Scalar, ScalarStyle, Sequence};
+use crate::libyaml::tag::Tag;
+use crate::mapping;
+use crate::path::Path;
 use crate::value::tagged::{self, MaybeTag};
+use crate::value::{self, Value};
 use serde::de::Visitor;
-use serde::ser::{self, Serializer as _};
+use serde::ser;
 use std::fmt::{self, Display};
 use std::io;
 use std::marker::PhantomData;
 use std::mem;
 use std::num;
 use std::str;
+use std::sync::mpsc;
+use std::thread;
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -44,10 +54,169 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 pub struct Serializer<W> {
     depth: usize,
     state: State,
+    quote_style: QuoteStyle,
+    sort_keys: bool,
+    #[cfg(feature = "round_trip")]
+    preserve_scalar_styles: bool,
+    /// Entries of the mapping being buffered for sorting, if any.
+    sorting: Option<SortedEntries>,
+    serializing_key: bool,
     emitter: Emitter<'static>,
     writer: PhantomData<W>,
 }
 
+/// The quoting style used when emitting string scalars.
+///
+/// Mapping keys are always emitted using [`QuoteStyle::Auto`], so that e.g.
+/// struct field names remain unquoted regardless of this setting.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Emit strings unquoted whenever possible, falling back to single quotes
+    /// for strings that would otherwise be read back as a different type
+    /// (e.g. `'true'` or `'1.0'`).
+    #[default]
+    Auto,
+    /// Always emit single-line strings in single quotes.
+    Single,
+    /// Always emit single-line strings in double quotes.
+    Double,
+}
+
+/// A builder for configuring the YAML output produced by a [`Serializer`].
+///
+/// # Example
+///
+/// ```
+/// use dbt_serde_yaml::{QuoteStyle, SerializerBuilder};
+/// use std::collections::BTreeMap;
+///
+/// let mut inner = BTreeMap::new();
+/// inner.insert("name", "my_model");
+/// let mut object = BTreeMap::new();
+/// object.insert("model", inner);
+///
+/// let builder = SerializerBuilder::new()
+///     .indent(4)
+///     .quote_style(QuoteStyle::Double);
+/// let yaml = dbt_serde_yaml::to_string_with(&object, builder).unwrap();
+/// assert_eq!(yaml, "model:\n    name: \"my_model\"\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SerializerBuilder {
+    indent: usize,
+    width: Option<usize>,
+    quote_style: QuoteStyle,
+    sort_keys: bool,
+    #[cfg(feature = "round_trip")]
+    preserve_scalar_styles: bool,
+}
+
+impl SerializerBuilder {
+    /// Creates a builder with the default settings: 2-space indentation,
+    /// unlimited line width, and [`QuoteStyle::Auto`].
+    pub fn new() -> Self {
+        SerializerBuilder {
+            indent: 2,
+            width: None,
+            quote_style: QuoteStyle::Auto,
+            sort_keys: false,
+            #[cfg(feature = "round_trip")]
+            preserve_scalar_styles: false,
+        }
+    }
+
+    /// Sets the number of spaces used for each level of indentation.
+    ///
+    /// Values outside of the range `2..=9` fall back to the default of 2.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets the preferred line width, beyond which long plain and quoted
+    /// scalars are wrapped. `None` (the default) disables wrapping.
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets the quoting style used for string scalars.
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    /// Sets whether mapping keys are emitted in sorted order rather than in
+    /// the order they are serialized, for canonical output that does not
+    /// depend on insertion order. Keys are ordered as by
+    /// [`Mapping::sort_keys`](crate::Mapping::sort_keys).
+    ///
+    /// Mappings and structs with more than one entry are buffered in memory
+    /// to be sorted before being written.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::{SerializerBuilder, Value};
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("b: {d: 1, c: 2}\na: 3\n").unwrap();
+    /// let builder = SerializerBuilder::new().sort_keys(true);
+    /// let yaml = dbt_serde_yaml::to_string_with(&value, builder).unwrap();
+    /// assert_eq!(yaml, "a: 3\nb:\n  c: 2\n  d: 1\n");
+    /// ```
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets whether strings of a [`Value`] parsed from YAML text are written
+    /// in the style they were written in there, e.g. as a `|` block scalar or
+    /// in double quotes, rather than in the style this serializer would pick
+    /// for them. This makes serializing an unmodified `Value` reproduce its
+    /// scalars as they were.
+    ///
+    /// The recorded [style](crate::value::YamlString::style) takes precedence
+    /// over the [quote style](Self::quote_style). Where it can't be used, e.g.
+    /// a block scalar inside a flow collection or a plain string that would
+    /// be read back as a different type, the usual style is picked instead.
+    /// Only the styles of strings are recorded: collections are laid out as
+    /// usual, and the lines of `>` block scalars are folded anew.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::{SerializerBuilder, Value};
+    ///
+    /// let yaml = "sql: |\n  select 1\nname: \"orders\"\nalias: 'o'\n";
+    /// let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    ///
+    /// let builder = SerializerBuilder::new().preserve_scalar_styles(true);
+    /// assert_eq!(dbt_serde_yaml::to_string_with(&value, builder).unwrap(), yaml);
+    /// ```
+    #[cfg(feature = "round_trip")]
+    pub fn preserve_scalar_styles(mut self, preserve_scalar_styles: bool) -> Self {
+        self.preserve_scalar_styles = preserve_scalar_styles;
+        self
+    }
+
+    /// Creates a YAML serializer writing into `writer` with these settings.
+    pub fn build<W>(&self, writer: W) -> Serializer<W>
+    where
+        W: io::Write,
+    {
+        Serializer::new_with(writer, self)
+    }
+}
+
+impl Default for SerializerBuilder {
+    fn default() -> Self {
+        SerializerBuilder::new()
+    }
+}
+
+#[derive(Default)]
+struct SortedEntries {
+    entries: Vec<(Value, Value)>,
+    /// The key passed to `serialize_key`, awaiting its value.
+    key: Option<Value>,
+}
+
 enum State {
     NothingInParticular,
     CheckForTag,
@@ -61,15 +230,30 @@ where
     W: io::Write,
 {
     /// Creates a new YAML serializer.
+    ///
+    /// Use [`SerializerBuilder`] to customize indentation, line width and
+    /// string quoting.
     pub fn new(writer: W) -> Self {
+        Serializer::new_with(writer, &SerializerBuilder::new())
+    }
+
+    fn new_with(writer: W, builder: &SerializerBuilder) -> Self {
         let mut emitter = Emitter::new({
             let writer = Box::new(writer);
             unsafe { mem::transmute::<Box<dyn io::Write>, Box<dyn io::Write>>(writer) }
         });
+        emitter.set_indent(builder.indent);
+        emitter.set_width(builder.width);
         emitter.emit(Event::StreamStart).unwrap();
         Serializer {
             depth: 0,
             state: State::NothingInParticular,
+            quote_style: builder.quote_style,
+            sort_keys: builder.sort_keys,
+            #[cfg(feature = "round_trip")]
+            preserve_scalar_styles: builder.preserve_scalar_styles,
+            sorting: None,
+            serializing_key: false,
             emitter,
             writer: PhantomData,
         }
@@ -145,6 +329,15 @@ where
     fn take_tag(&mut self) -> Option<String> {
         let state = mem::replace(&mut self.state, State::NothingInParticular);
         if let State::FoundTag(mut tag) = state {
+            // The tag of a `!!set` or the like, whose shorthand is found
+            // without its first '!', is written out in full for the emitter to
+            // shorten.
+            if let Some((_, full)) = Tag::KEPT
+                .iter()
+                .find(|(shorthand, _)| shorthand.strip_prefix('!') == Some(&tag))
+            {
+                return Some((*full).to_owned());
+            }
             if !tag.starts_with('!') {
                 tag.insert(0, '!');
             }
@@ -155,6 +348,121 @@ where
         }
     }
 
+    /// Passes this serializer to the `Serialize` impl of `value`, letting a
+    /// [Value] be emitted by [emit_value](Self::emit_value).
+    fn serialize_nested<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value::offer_emit(|| value.serialize(self))
+    }
+
+    fn emit_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let serializing_key = mem::replace(&mut self.serializing_key, true);
+        let result = self.serialize_nested(key);
+        self.serializing_key = serializing_key;
+        result
+    }
+
+    /// Emits a mapping buffered for sorting, with its entries sorted by key.
+    fn emit_sorted(&mut self, mut sorted: SortedEntries) -> Result<()> {
+        sorted
+            .entries
+            .sort_by(|(a, _), (b, _)| mapping::total_cmp(a, b));
+        self.emit_mapping_start()?;
+        for (key, value) in &sorted.entries {
+            self.emit_key(key)?;
+            self.serialize_nested(value)?;
+        }
+        self.emit_mapping_end()
+    }
+
+    /// Emits a [Value] like its `Serialize` impl would, but keeping the
+    /// collections being emitted on an explicit stack rather than recursing
+    /// into them, so that deeply nested values don't overflow the call stack.
+    fn emit_value(&mut self, value: &Value) -> Result<()> {
+        enum Frame<'a> {
+            Sequence(std::slice::Iter<'a, Value>),
+            Mapping {
+                entries: Box<dyn Iterator<Item = (&'a Value, &'a Value)> + 'a>,
+                sorted: bool,
+                /// Whether the value being emitted is that of a tag.
+                tagged: bool,
+            },
+        }
+
+        let mut stack = Vec::new();
+        let mut next = Some(value);
+        loop {
+            if let Some(value) = next.take() {
+                match value {
+                    Value::Sequence(sequence, ..) => {
+                        ser::Serializer::serialize_seq(&mut *self, Some(sequence.len()))?;
+                        stack.push(Frame::Sequence(sequence.iter()));
+                    }
+                    Value::Mapping(mapping, ..) if self.sort_keys && mapping.len() != 1 => {
+                        let mut entries: Vec<_> = mapping.iter().collect();
+                        entries.sort_by(|(a, _), (b, _)| mapping::total_cmp(a, b));
+                        self.emit_mapping_start()?;
+                        stack.push(Frame::Mapping {
+                            entries: Box::new(entries.into_iter()),
+                            sorted: true,
+                            tagged: false,
+                        });
+                    }
+                    Value::Mapping(mapping, ..) => {
+                        ser::Serializer::serialize_map(&mut *self, Some(mapping.len()))?;
+                        stack.push(Frame::Mapping {
+                            entries: Box::new(mapping.iter()),
+                            sorted: false,
+                            tagged: false,
+                        });
+                    }
+                    _ => ser::Serialize::serialize(value, &mut *self)?,
+                }
+                continue;
+            }
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::Sequence(elements)) => match elements.next() {
+                    Some(element) => next = Some(element),
+                    None => {
+                        stack.pop();
+                        ser::SerializeSeq::end(&mut *self)?;
+                    }
+                },
+                Some(Frame::Mapping {
+                    entries,
+                    sorted,
+                    tagged,
+                }) => {
+                    // As in `SerializeMap::serialize_entry`.
+                    if mem::take(tagged) {
+                        self.state = State::AlreadyTagged;
+                    }
+                    match entries.next() {
+                        Some((key, value)) => {
+                            self.emit_key(key)?;
+                            *tagged = !*sorted && matches!(self.state, State::FoundTag(_));
+                            next = Some(value);
+                        }
+                        None if *sorted => {
+                            stack.pop();
+                            self.emit_mapping_end()?;
+                        }
+                        None => {
+                            stack.pop();
+                            ser::SerializeMap::end(&mut *self)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     fn flush_mapping_start(&mut self) -> Result<()> {
         if let State::CheckForTag = self.state {
             self.state = State::NothingInParticular;
@@ -306,62 +614,44 @@ where
     }
 
     fn serialize_str(self, value: &str) -> Result<()> {
-        struct InferScalarStyle;
-
-        impl Visitor<'_> for InferScalarStyle {
-            type Value = ScalarStyle;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("I wonder")
-            }
-
-            fn visit_bool<E>(self, _v: bool) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_i64<E>(self, _v: i64) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_i128<E>(self, _v: i128) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_u64<E>(self, _v: u64) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_u128<E>(self, _v: u128) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
-                Ok(if crate::de::digits_but_not_number(v) {
-                    ScalarStyle::SingleQuoted
-                } else {
-                    ScalarStyle::Any
-                })
-            }
-
-            fn visit_unit<E>(self) -> Result<Self::Value, E> {
-                Ok(ScalarStyle::SingleQuoted)
+        #[cfg(feature = "round_trip")]
+        if let Some(style) = value::serializing_style().filter(|_| self.preserve_scalar_styles) {
+            use crate::event::ScalarStyle as Parsed;
+            let style = match style {
+                Parsed::Plain if is_ambiguous_plain(value) => None,
+                Parsed::Plain => Some(ScalarStyle::Plain),
+                Parsed::SingleQuoted => Some(ScalarStyle::SingleQuoted),
+                Parsed::DoubleQuoted => Some(ScalarStyle::DoubleQuoted),
+                Parsed::Literal => Some(ScalarStyle::Literal),
+                Parsed::Folded => Some(ScalarStyle::Folded),
+            };
+            if let Some(style) = style {
+                return self.emit_scalar(Scalar {
+                    tag: None,
+                    value,
+                    style,
+                });
             }
         }
-
+        let quote_style = if self.serializing_key {
+            QuoteStyle::Auto
+        } else {
+            self.quote_style
+        };
         let style = if value.contains('\n') {
             ScalarStyle::Literal
+        } else if matches!(&self.state, State::FoundTag(tag) if tag == "!binary") {
+            // The base64 of a `!!binary` scalar from a Value, written like
+            // the bytes it holds would be.
+            ScalarStyle::Plain
+        } else if let QuoteStyle::Single = quote_style {
+            ScalarStyle::SingleQuoted
+        } else if let QuoteStyle::Double = quote_style {
+            ScalarStyle::DoubleQuoted
+        } else if is_ambiguous_plain(value) {
+            ScalarStyle::SingleQuoted
         } else {
-            let result = crate::de::visit_untagged_scalar(
-                InferScalarStyle,
-                value,
-                None,
-                libyaml::parser::ScalarStyle::Plain,
-            );
-            result.unwrap_or(ScalarStyle::Any)
+            ScalarStyle::Any
         };
 
         self.emit_scalar(Scalar {
@@ -371,8 +661,12 @@ where
         })
     }
 
-    fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        self.emit_scalar(Scalar {
+            tag: Some(Tag::BINARY.to_owned()),
+            value: &binary::encode(value),
+            style: ScalarStyle::Plain,
+        })
     }
 
     fn serialize_unit(self) -> Result<()> {
@@ -396,11 +690,24 @@ where
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(self)
+        if let Some(value) = value::serializing_value(name, value) {
+            return self.emit_value(value);
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        if name == crate::number::TOKEN {
+            if let Value::String(repr, ..) = value.serialize(crate::value::Serializer)? {
+                return self.emit_scalar(Scalar {
+                    tag: None,
+                    value: &repr,
+                    style: ScalarStyle::Plain,
+                });
+            }
+        }
+        self.serialize_nested(value)
     }
 
     fn serialize_newtype_variant<T>(
@@ -417,7 +724,7 @@ where
             return Err(error::new(ErrorImpl::SerializeNestedEnum));
         }
         self.state = State::FoundTag(variant.to_owned());
-        value.serialize(&mut *self)
+        self.serialize_nested(value)
     }
 
     fn serialize_none(self) -> Result<()> {
@@ -428,7 +735,7 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
-        value.serialize(self)
+        self.serialize_nested(value)
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
@@ -466,7 +773,9 @@ where
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        if len == Some(1) {
+        if self.sort_keys && len != Some(1) {
+            self.sorting = Some(SortedEntries::default());
+        } else if len == Some(1) {
             self.state = if let State::FoundTag(_) = self.state {
                 self.emit_mapping_start()?;
                 State::CheckForDuplicateTag
@@ -480,7 +789,11 @@ where
     }
 
     fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        self.emit_mapping_start()?;
+        if self.sort_keys {
+            self.sorting = Some(SortedEntries::default());
+        } else {
+            self.emit_mapping_start()?;
+        }
         Ok(self)
     }
 
@@ -495,7 +808,11 @@ where
             return Err(error::new(ErrorImpl::SerializeNestedEnum));
         }
         self.state = State::FoundTag(variant.to_owned());
-        self.emit_mapping_start()?;
+        if self.sort_keys {
+            self.sorting = Some(SortedEntries::default());
+        } else {
+            self.emit_mapping_start()?;
+        }
         Ok(self)
     }
 
@@ -534,7 +851,7 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        elem.serialize(&mut **self)
+        self.serialize_nested(elem)
     }
 
     fn end(self) -> Result<()> {
@@ -553,7 +870,7 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        elem.serialize(&mut **self)
+        self.serialize_nested(elem)
     }
 
     fn end(self) -> Result<()> {
@@ -572,7 +889,7 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut **self)
+        self.serialize_nested(value)
     }
 
     fn end(self) -> Result<()> {
@@ -591,7 +908,7 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
-        v.serialize(&mut **self)
+        self.serialize_nested(v)
     }
 
     fn end(self) -> Result<()> {
@@ -610,15 +927,27 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        if let Some(sorting) = &mut self.sorting {
+            sorting.key = Some(value::to_value(key)?);
+            return Ok(());
+        }
         self.flush_mapping_start()?;
-        key.serialize(&mut **self)
+        self.emit_key(key)
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut **self)
+        if let Some(sorting) = &mut self.sorting {
+            let key = sorting
+                .key
+                .take()
+                .expect("serialize_value called before serialize_key");
+            sorting.entries.push((key, value::to_value(value)?));
+            return Ok(());
+        }
+        self.serialize_nested(value)
     }
 
     fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
@@ -626,9 +955,14 @@ where
         K: ?Sized + ser::Serialize,
         V: ?Sized + ser::Serialize,
     {
-        key.serialize(&mut **self)?;
+        if let Some(sorting) = &mut self.sorting {
+            let entry = (value::to_value(key)?, value::to_value(value)?);
+            sorting.entries.push(entry);
+            return Ok(());
+        }
+        self.emit_key(key)?;
         let tagged = matches!(self.state, State::FoundTag(_));
-        value.serialize(&mut **self)?;
+        self.serialize_nested(value)?;
         if tagged {
             self.state = State::AlreadyTagged;
         }
@@ -636,6 +970,9 @@ where
     }
 
     fn end(self) -> Result<()> {
+        if let Some(sorted) = self.sorting.take() {
+            return self.emit_sorted(sorted);
+        }
         if let State::CheckForTag = self.state {
             self.emit_mapping_start()?;
         }
@@ -658,11 +995,19 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
-        self.serialize_str(key)?;
-        value.serialize(&mut **self)
+        if let Some(sorting) = &mut self.sorting {
+            let entry = (Value::from(key), value::to_value(value)?);
+            sorting.entries.push(entry);
+            return Ok(());
+        }
+        self.emit_key(key)?;
+        self.serialize_nested(value)
     }
 
     fn end(self) -> Result<()> {
+        if let Some(sorted) = self.sorting.take() {
+            return self.emit_sorted(sorted);
+        }
         self.emit_mapping_end()
     }
 }
@@ -678,17 +1023,104 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
-        self.serialize_str(field)?;
-        v.serialize(&mut **self)
+        if let Some(sorting) = &mut self.sorting {
+            let entry = (Value::from(field), value::to_value(v)?);
+            sorting.entries.push(entry);
+            return Ok(());
+        }
+        self.emit_key(field)?;
+        self.serialize_nested(v)
     }
 
     fn end(self) -> Result<()> {
+        if let Some(sorted) = self.sorting.take() {
+            return self.emit_sorted(sorted);
+        }
         self.emit_mapping_end()
     }
 }
 
+/// Whether either scalar schema would resolve `value`, written as a plain
+/// scalar, to something other than a string.
+pub(crate) fn is_ambiguous_plain(value: &str) -> bool {
+    struct InferScalarStyle;
+
+    impl<'de> Visitor<'de> for InferScalarStyle {
+        type Value = ScalarStyle;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("I wonder")
+        }
+
+        fn visit_bool<E>(self, _v: bool) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_i64<E>(self, _v: i64) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_i128<E>(self, _v: i128) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_u64<E>(self, _v: u64) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_u128<E>(self, _v: u128) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(if crate::de::digits_but_not_number(v) {
+                ScalarStyle::SingleQuoted
+            } else {
+                ScalarStyle::Any
+            })
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+
+        #[cfg(feature = "arbitrary_precision")]
+        fn visit_map<A>(self, _map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            Ok(ScalarStyle::SingleQuoted)
+        }
+    }
+
+    let infer = |schema| {
+        crate::de::visit_untagged_scalar(
+            InferScalarStyle,
+            value,
+            None,
+            libyaml::parser::ScalarStyle::Plain,
+            schema,
+        )
+        .unwrap_or(ScalarStyle::Any)
+    };
+    !matches!(infer(ScalarSchema::Lenient), ScalarStyle::Any)
+        || !matches!(infer(ScalarSchema::Core), ScalarStyle::Any)
+}
+
 /// Serialize the given data structure as YAML into the IO stream.
 ///
+/// Output is streamed: it is handed to `writer` in chunks of bounded size as
+/// serialization progresses rather than being assembled first. Prefer this
+/// over [`to_string`] for large documents. The exception is
+/// [`SerializerBuilder::sort_keys`], under which every mapping of a type other
+/// than [Value] is converted to a [Value], contents included, until all of its
+/// entries are known; with sorted keys, a document that is a struct or map is
+/// held in memory whole.
+///
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to
 /// return an error.
 pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
@@ -696,8 +1128,145 @@ where
     W: io::Write,
     T: ?Sized + ser::Serialize,
 {
-    let mut serializer = Serializer::new(writer);
-    value.serialize(&mut serializer)
+    to_writer_with(writer, value, SerializerBuilder::new())
+}
+
+/// Serialize the given data structure as YAML into the IO stream, using the
+/// output settings from the given [`SerializerBuilder`].
+///
+/// Like [`to_writer`], output is streamed to `writer` in chunks, unless the
+/// builder sorts keys.
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_writer_with<W, T>(writer: W, value: &T, builder: SerializerBuilder) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + ser::Serialize,
+{
+    let mut serializer = builder.build(writer);
+    serializer.serialize_nested(value)
+}
+
+/// Serializes a value as YAML on a background thread, yielding the output in
+/// chunks as it is produced.
+///
+/// This is the pull-based counterpart to [`to_writer_with`]: serialization
+/// waits until the previous chunk has been taken, so that the caller can hand
+/// each chunk to a sink of its own, like an async writer, without more than a
+/// couple of chunks being held in memory at a time. Sorted keys are buffered
+/// as described in [`to_writer`].
+///
+/// An error from serializing the value is yielded last. A panic in its
+/// `Serialize` impl is resumed on the thread taking the chunks.
+///
+/// Since the value is serialized on another thread, it is moved there and has
+/// to be `Send + 'static`; to serialize borrowed data, share it through an
+/// [`Arc`](std::sync::Arc) or use [`to_writer_with`] instead. The thread runs
+/// with the caller's current [`Config`](crate::Config), as it was when the
+/// driver was created.
+///
+/// ```
+/// use dbt_serde_yaml::{SerializerBuilder, SerializerDriver};
+///
+/// let manifest = vec!["orders".to_owned(), "customers".to_owned()];
+/// let mut yaml = Vec::new();
+/// for chunk in SerializerDriver::new(manifest, SerializerBuilder::new()) {
+///     yaml.extend_from_slice(&chunk.unwrap());
+/// }
+/// assert_eq!(yaml, b"- orders\n- customers\n");
+/// ```
+pub struct SerializerDriver {
+    chunks: mpsc::Receiver<Result<Vec<u8>>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl SerializerDriver {
+    /// The size in bytes from which output is yielded as a chunk.
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Starts serializing `value` with the output settings from `builder`.
+    pub fn new<T>(value: T, builder: SerializerBuilder) -> Self
+    where
+        T: ser::Serialize + Send + 'static,
+    {
+        let (sender, chunks) = mpsc::sync_channel(1);
+        let config = Config::current();
+        let thread = thread::spawn(move || {
+            let _scope = config.enter();
+            let mut writer = ChunkWriter {
+                chunk: Vec::with_capacity(Self::CHUNK_SIZE),
+                sender,
+            };
+            let result = to_writer_with(&mut writer, &value, builder);
+            let last = match result {
+                Ok(()) => Ok(mem::take(&mut writer.chunk)),
+                Err(err) => Err(err),
+            };
+            // The driver is gone if this fails, so nobody is left to tell.
+            let _ = writer.sender.send(last);
+        });
+        SerializerDriver {
+            chunks,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Iterator for SerializerDriver {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) if chunk.is_empty() => {}
+                Ok(chunk) => return Some(chunk),
+                Err(mpsc::RecvError) => {
+                    if let Err(panic) = self.thread.take()?.join() {
+                        std::panic::resume_unwind(panic);
+                    }
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SerializerDriver {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("SerializerDriver")
+            .field("finished", &self.thread.is_none())
+            .finish()
+    }
+}
+
+/// Collects the output of a [SerializerDriver] into chunks and sends them to
+/// the driver.
+struct ChunkWriter {
+    chunk: Vec<u8>,
+    sender: mpsc::SyncSender<Result<Vec<u8>>>,
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= SerializerDriver::CHUNK_SIZE {
+            let chunk = mem::replace(
+                &mut self.chunk,
+                Vec::with_capacity(SerializerDriver::CHUNK_SIZE),
+            );
+            // Stop serializing once the driver has been dropped.
+            self.sender
+                .send(Ok(chunk))
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Serialize the given data structure as a String of YAML.
@@ -705,10 +1274,132 @@ where
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to
 /// return an error.
 pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + ser::Serialize,
+{
+    to_string_with(value, SerializerBuilder::new())
+}
+
+/// Serialize the given data structure as a String of YAML, using the output
+/// settings from the given [`SerializerBuilder`].
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_string_with<T>(value: &T, builder: SerializerBuilder) -> Result<String>
 where
     T: ?Sized + ser::Serialize,
 {
     let mut vec = Vec::with_capacity(128);
-    to_writer(&mut vec, value)?;
+    builder.build(&mut vec).serialize_nested(value)?;
     String::from_utf8(vec).map_err(|error| error::new(ErrorImpl::FromUtf8(error)))
 }
+
+/// Serialize the given data structure as YAML into the IO stream, with
+/// comment lines added around the nodes for which `comments` returns any.
+///
+/// Unlike [`to_writer`], the output is buffered in memory, since comments are
+/// inserted once the whole document has been serialized. See
+/// [`to_string_with_comments`].
+pub fn to_writer_with_comments<W, T, F>(
+    mut writer: W,
+    value: &T,
+    builder: SerializerBuilder,
+    comments: F,
+) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + ser::Serialize,
+    F: FnMut(Path<'_>) -> Comments,
+{
+    let yaml = to_string_with_comments(value, builder, comments)?;
+    writer
+        .write_all(yaml.as_bytes())
+        .map_err(|error| error::new(ErrorImpl::Io(error)))
+}
+
+/// Serialize the given data structure as a String of YAML, with comment lines
+/// added around the nodes for which `comments` returns any.
+///
+/// `comments` is called with the path of every node in the output, except
+/// nodes inside complex mapping keys. Comments before the value of a mapping
+/// entry go above its key, and comments before a sequence element go above
+/// its `-`.
+///
+/// ```
+/// use dbt_serde_yaml::{Comments, SerializerBuilder, Value};
+///
+/// let value: Value = dbt_serde_yaml::from_str("name: orders\nconfig:\n  enabled: true\n").unwrap();
+/// let yaml = dbt_serde_yaml::to_string_with_comments(&value, SerializerBuilder::new(), |path| {
+///     match path.to_string().as_str() {
+///         "config.enabled" => Comments {
+///             before: vec!["added by migration 1.8".to_owned()],
+///             after: Vec::new(),
+///         },
+///         _ => Comments::default(),
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(
+///     yaml,
+///     "name: orders\nconfig:\n  # added by migration 1.8\n  enabled: true\n",
+/// );
+/// ```
+pub fn to_string_with_comments<T, F>(
+    value: &T,
+    builder: SerializerBuilder,
+    mut comments: F,
+) -> Result<String>
+where
+    T: ?Sized + ser::Serialize,
+    F: FnMut(Path<'_>) -> Comments,
+{
+    let yaml = to_string_with(value, builder)?;
+    comments::insert(&yaml, &mut comments)
+}
+
+/// Serialize the subtree of `value` at `path` as a standalone YAML document
+/// into the IO stream.
+///
+/// The subtree is serialized in place, without being cloned, and is indented
+/// as if it were the root of its own document.
+///
+/// Fails if there is no value at `path`; see [`Value::get_path`] for how
+/// paths are resolved.
+pub fn to_writer_at<W>(writer: W, value: &Value, path: &Path) -> Result<()>
+where
+    W: io::Write,
+{
+    to_writer(writer, subtree(value, path)?)
+}
+
+/// Serialize the subtree of `value` at `path` as a standalone YAML document
+/// into a String.
+///
+/// Fails if there is no value at `path`; see [`Value::get_path`] for how
+/// paths are resolved.
+///
+/// ```
+/// use dbt_serde_yaml::path::OwnedPath;
+/// use dbt_serde_yaml::Value;
+///
+/// let value: Value = dbt_serde_yaml::from_str("
+/// models:
+///   - name: orders
+///     config:
+///       materialized: table
+///       tags: [nightly]
+/// ").unwrap();
+///
+/// let path: OwnedPath = "models[0].config".parse().unwrap();
+/// let yaml = dbt_serde_yaml::to_string_at(&value, path.as_path()).unwrap();
+/// assert_eq!(yaml, "materialized: table\ntags:\n- nightly\n");
+/// ```
+pub fn to_string_at(value: &Value, path: &Path) -> Result<String> {
+    to_string(subtree(value, path)?)
+}
+
+fn subtree<'a>(value: &'a Value, path: &Path) -> Result<&'a Value> {
+    value
+        .get_path(path)
+        .ok_or_else(|| error::new(ErrorImpl::NoValueAtPath(path.to_string())))
+}
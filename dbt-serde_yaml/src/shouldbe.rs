@@ -5,6 +5,7 @@
 
 use std::{
     fmt::Debug,
+    ops::{Deref, DerefMut},
     sync::{
         atomic::{self, AtomicPtr},
         Arc,
@@ -16,7 +17,8 @@ use serde::{
     Deserialize, Deserializer, Serialize,
 };
 
-use crate::{Error, Value};
+use crate::path::Path;
+use crate::{config, error, Error, Value};
 
 /// Represents a value that "should be" deserialized to type `T`, or provides
 /// information about why it failed to.
@@ -26,7 +28,7 @@ use crate::{Error, Value};
 /// failing the deserialization process: deserializing into a `ShouldBe<T>` will
 /// *always* succeed, producing a [ShouldBe] object that either wraps a valid
 /// `T` value, or the error (and the corresponding pre-deserialized value, if
-/// deserializing from a [Value]) that caused the failure.
+/// deserializing from a [Value] or from YAML text) that caused the failure.
 ///
 /// You can think of [`ShouldBe<T>`] as a more versatile `Result<T, Error>` that
 /// exposes the equality, ordering, hashing, cloning, and (de)serialization
@@ -105,7 +107,8 @@ use crate::{Error, Value};
 ///
 /// ## Inspecting the raw [Value]
 ///
-/// If a [ShouldBe::ButIsnt] instance was deserialized from a [Value], it will
+/// If a [ShouldBe::ButIsnt] instance was deserialized from a [Value] or from
+/// YAML text using this crate's [Deserializer](crate::Deserializer), it will
 /// also capture the corresponding [Value] object that failed to deserialize.
 /// You can access it via the [ShouldBe::as_ref_raw] method.
 ///
@@ -146,7 +149,7 @@ use crate::{Error, Value};
 /// You can serialize a [`ShouldBe<T>`] instance as long as `T` is serializable.
 /// When serializing a [ShouldBe::AndIs] variant, the inner `T` value is
 /// serialized as usual. When serializing a [ShouldBe::ButIsnt] variant, if it
-/// contains a raw [Value] (i.e., it was deserialized from a [Value]), then the
+/// contains a raw [Value] (see "Inspecting the raw [Value]"), then the
 /// raw [Value] is serialized; otherwise, an error is raised and serialization
 /// fails.
 ///
@@ -166,6 +169,17 @@ use crate::{Error, Value};
 /// }
 /// ```
 ///
+/// # Composing with [`Verbatim`](crate::Verbatim)
+///
+/// `ShouldBe<Verbatim<T>>` and `Verbatim<ShouldBe<T>>` behave identically: the
+/// field transformer is not applied to the value or any of its descendants,
+/// and on failure the captured raw [Value] is the untransformed input. The
+/// [LenientRaw] alias names the recommended spelling.
+///
+/// # Borrowed data
+///
+/// Deserializing a [`ShouldBe<T>`] requires `T: DeserializeOwned`. For types
+/// that borrow from the input, use [ShouldBeRef] instead.
 #[derive(Clone)]
 pub enum ShouldBe<T> {
     /// On successful deserialization, will contain the expected value of type
@@ -173,10 +187,18 @@ pub enum ShouldBe<T> {
     AndIs(T),
 
     /// On failed deserialization, will contain the error and raw value (if
-    /// deserialized from a [Value]) that caused the failure.
+    /// available) that caused the failure.
     ButIsnt(WhyNot),
 }
 
+/// A value that is deserialized without applying the field transformer, and
+/// whose deserialization failures are recovered from.
+///
+/// This is the recommended way to combine [ShouldBe] with
+/// [`Verbatim`](crate::Verbatim); see "Composing with `Verbatim`" on
+/// [ShouldBe].
+pub type LenientRaw<T> = ShouldBe<crate::Verbatim<T>>;
+
 impl<T> ShouldBe<T> {
     /// Returns a reference to the inner `T` value if it exists
     pub fn as_ref(&self) -> Option<&T> {
@@ -203,6 +225,17 @@ impl<T> ShouldBe<T> {
         }
     }
 
+    /// Returns the handle to the raw value if this object represents a failed
+    /// deserialization whose raw value was spilled to disk by
+    /// [`spill_raw_values`](crate::spill_raw_values).
+    #[cfg(feature = "spill")]
+    pub fn spilled_raw(&self) -> Option<&crate::SpilledRaw> {
+        match self {
+            ShouldBe::AndIs(_) => None,
+            ShouldBe::ButIsnt(why_not) => why_not.0.spilled.as_ref(),
+        }
+    }
+
     /// Returns the error message if this object represents a failed
     /// deserialization.
     pub fn as_err_msg(&self) -> Option<&str> {
@@ -242,6 +275,83 @@ impl<T> ShouldBe<T> {
             ShouldBe::ButIsnt(why_not) => why_not.take_err(),
         }
     }
+
+    /// Maps a `ShouldBe<T>` to a `ShouldBe<U>` by applying `f` to the inner
+    /// value, leaving a failed deserialization as is.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::ShouldBe;
+    /// let name: ShouldBe<String> = dbt_serde_yaml::from_str("orders").unwrap();
+    /// assert_eq!(name.map(|name| name.len()).into_inner(), Some(6));
+    ///
+    /// let count: ShouldBe<u32> = dbt_serde_yaml::from_str("many").unwrap();
+    /// let doubled = count.map(|count| count * 2);
+    /// assert_eq!(
+    ///     doubled.as_err_msg().unwrap(),
+    ///     "invalid type: string \"many\", expected u32 at line 1 column 1",
+    /// );
+    /// ```
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ShouldBe<U> {
+        match self {
+            ShouldBe::AndIs(value) => ShouldBe::AndIs(f(value)),
+            ShouldBe::ButIsnt(why_not) => ShouldBe::ButIsnt(why_not),
+        }
+    }
+
+    /// Calls `f` with the inner value and returns its result, or leaves a
+    /// failed deserialization as is.
+    pub fn and_then<U>(self, f: impl FnOnce(T) -> ShouldBe<U>) -> ShouldBe<U> {
+        match self {
+            ShouldBe::AndIs(value) => f(value),
+            ShouldBe::ButIsnt(why_not) => ShouldBe::ButIsnt(why_not),
+        }
+    }
+
+    /// Returns a reference to the target of the inner value, like
+    /// `Option::as_deref`, if it exists.
+    pub fn as_deref(&self) -> Option<&T::Target>
+    where
+        T: Deref,
+    {
+        self.as_ref().map(Deref::deref)
+    }
+
+    /// Returns the inner value, or the error built by `err` from the [Error]
+    /// that caused the failed deserialization.
+    ///
+    /// If the [Error] was already taken with [ShouldBe::take_err], `err`
+    /// receives a new one with the same message.
+    pub fn ok_or_else<E>(self, err: impl FnOnce(Error) -> E) -> Result<T, E> {
+        match self {
+            ShouldBe::AndIs(value) => Ok(value),
+            ShouldBe::ButIsnt(why_not) => Err(err(why_not.into())),
+        }
+    }
+
+    /// Converts into a `Result`, recording `path` as the location of the
+    /// error if it doesn't know one yet.
+    ///
+    /// This is useful for `ShouldBe` values that were not deserialized from
+    /// YAML, such as those built with `From<Result<T, Error>>`, whose errors
+    /// would otherwise not say where they belong.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::Path;
+    /// # use dbt_serde_yaml::ShouldBe;
+    /// # use serde::de::Error as _;
+    /// let port: ShouldBe<u16> = Err(dbt_serde_yaml::Error::custom("out of range")).into();
+    ///
+    /// let path = Path::Map { parent: &Path::Root, key: "port" };
+    /// let err = port.into_result_with_path(&path).unwrap_err();
+    /// assert_eq!(err.to_string(), "port: out of range");
+    /// assert_eq!(err.path().unwrap().to_string(), "port");
+    /// ```
+    pub fn into_result_with_path(self, path: &Path) -> Result<T, Error> {
+        match self {
+            ShouldBe::AndIs(value) => Ok(value),
+            ShouldBe::ButIsnt(why_not) => Err(error::set_path(why_not.into(), path)),
+        }
+    }
 }
 
 impl<T> Debug for ShouldBe<T>
@@ -273,6 +383,15 @@ impl<T> From<T> for ShouldBe<T> {
     }
 }
 
+impl<T> From<Result<T, Error>> for ShouldBe<T> {
+    fn from(result: Result<T, Error>) -> Self {
+        match result {
+            Ok(value) => ShouldBe::AndIs(value),
+            Err(err) => ShouldBe::ButIsnt(WhyNot::new(None, err)),
+        }
+    }
+}
+
 impl<T> From<ShouldBe<T>> for Option<T> {
     fn from(should_be: ShouldBe<T>) -> Self {
         should_be.into_inner()
@@ -357,6 +476,10 @@ where
                 if let Some(raw_value) = why_not.as_ref_raw() {
                     // If we have a raw value, we can serialize it.
                     raw_value.serialize(serializer)
+                } else if let Some(raw_value) = why_not.load_spilled() {
+                    raw_value
+                        .map_err(serde::ser::Error::custom)?
+                        .serialize(serializer)
                 } else {
                     // Otherwise, we have to raise an error.
                     Err(serde::ser::Error::custom(
@@ -376,24 +499,148 @@ where
     where
         D: Deserializer<'de>,
     {
-        // Communicate to the ValueDeserializers that we are expecting a
-        // `ShouldBe` value.
-        EXPECTING_SHOULD_BE.with(|cell| *cell.borrow_mut() = true);
-
-        match T::deserialize(deserializer) {
-            Ok(value) => Ok(ShouldBe::AndIs(value)),
-            Err(err) => {
-                if let Some((raw, err)) = take_why_not() {
-                    Ok(ShouldBe::ButIsnt(WhyNot::new(Some(raw), err)))
-                } else {
-                    let err = Error::custom(err);
-                    Ok(ShouldBe::ButIsnt(WhyNot::new(None, err)))
-                }
+        deserialize_should_be(deserializer, Expecting::ShouldBe)
+    }
+}
+
+/// Deserializes a `T`, recovering from a failure like [ShouldBe::deserialize],
+/// but telling the deserializers of this crate if `T` may borrow from the
+/// input, in which case it must not be deserialized from a temporary copy.
+fn deserialize_should_be<'de, T, D>(
+    deserializer: D,
+    expecting: Expecting,
+) -> Result<ShouldBe<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    // Communicate to the ValueDeserializers that we are expecting a
+    // `ShouldBe` value.
+    let _ = take_why_not();
+    set_expecting_should_be(Some(expecting));
+    // What a failed attempt reported, e.g. a warning about part of the input
+    // that was read before the failure, is dropped along with it.
+    let held = config::hold_reports();
+    let res = T::deserialize(deserializer);
+    // The flag is left set if the deserializer never consumed it, e.g. a
+    // non-`Value` deserializer or the `Value` fast path; it must not leak
+    // into whatever is deserialized next.
+    set_expecting_should_be(None);
+
+    match res {
+        Ok(value) => {
+            if let Some(held) = held {
+                held.keep();
+            }
+            Ok(ShouldBe::AndIs(value))
+        }
+        Err(err) => {
+            if let Some((raw, err)) = take_why_not() {
+                Ok(ShouldBe::ButIsnt(WhyNot::new(Some(raw), err)))
+            } else {
+                let err = Error::custom(err);
+                Ok(ShouldBe::ButIsnt(WhyNot::new(None, err)))
             }
         }
     }
 }
 
+/// A [ShouldBe] for types that borrow from the input, like `&'de str` or
+/// structs with `#[serde(borrow)]` fields.
+///
+/// [`ShouldBe<T>`] requires `T: DeserializeOwned`, because it may deserialize
+/// `T` from a temporary copy of the input, which it keeps as the raw
+/// [Value] on failure. `ShouldBeRef<T>` only requires `T: Deserialize<'de>`
+/// and reads `T` from the input itself: from YAML text, the node is read
+/// again into the raw value only if `T` fails to deserialize. From a
+/// `&'de Value`, both work the same, while from an owned [Value], which has
+/// nothing to borrow from, no raw value is kept on failure.
+///
+/// It dereferences to the [`ShouldBe<T>`] it wraps, so all of its methods are
+/// available.
+///
+/// ```
+/// # use dbt_serde_yaml::{ShouldBeRef, Value};
+/// # use serde_derive::Deserialize;
+/// #[derive(Deserialize, Debug)]
+/// struct Column<'a> {
+///     name: &'a str,
+///     #[serde(borrow)]
+///     tests: Vec<ShouldBeRef<&'a str>>,
+/// }
+///
+/// let yaml = "name: id\ntests: [unique, {not_null: {}}]\n";
+/// let column: Column = dbt_serde_yaml::from_str(yaml).unwrap();
+/// assert_eq!(column.tests[0].as_ref(), Some(&"unique"));
+/// assert!(column.tests[1].isnt());
+/// assert_eq!(column.tests[1].as_ref_raw().unwrap()["not_null"], Value::mapping(Default::default()));
+///
+/// // Borrowing from a `Value` works the same.
+/// let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+/// let column = Column::deserialize(&value).unwrap();
+/// assert_eq!(column.tests[0].as_ref(), Some(&"unique"));
+/// # use serde::Deserialize as _;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShouldBeRef<T>(pub ShouldBe<T>);
+
+impl<T> ShouldBeRef<T> {
+    /// Consumes self, returning the wrapped [`ShouldBe<T>`].
+    pub fn into_should_be(self) -> ShouldBe<T> {
+        self.0
+    }
+}
+
+impl<T> Deref for ShouldBeRef<T> {
+    type Target = ShouldBe<T>;
+
+    fn deref(&self) -> &ShouldBe<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ShouldBeRef<T> {
+    fn deref_mut(&mut self) -> &mut ShouldBe<T> {
+        &mut self.0
+    }
+}
+
+impl<T> From<ShouldBe<T>> for ShouldBeRef<T> {
+    fn from(should_be: ShouldBe<T>) -> Self {
+        ShouldBeRef(should_be)
+    }
+}
+
+impl<T> From<ShouldBeRef<T>> for ShouldBe<T> {
+    fn from(should_be: ShouldBeRef<T>) -> Self {
+        should_be.0
+    }
+}
+
+impl<T> Serialize for ShouldBeRef<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for ShouldBeRef<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_should_be(deserializer, Expecting::ShouldBeRef).map(ShouldBeRef)
+    }
+}
+
 /// An opaque type that captures the reason why a deserialization to a
 /// [`ShouldBe<T>`] failed.
 ///
@@ -405,10 +652,15 @@ struct WhyNotImpl {
     /// The raw value that was attempted to be deserialized.
     ///
     /// This field will *only* be populated when deserializing from a
-    /// [Value]. When deserializing from other deserializers, this field
-    /// will be `None`.
+    /// [Value] or from YAML text. When deserializing from other
+    /// deserializers, this field will be `None`.
     raw: Option<crate::Value>,
 
+    /// The raw value, if it was spilled to disk instead of being kept in
+    /// `raw`.
+    #[cfg(feature = "spill")]
+    spilled: Option<crate::SpilledRaw>,
+
     /// The original error that occurred during deserialization.
     err: AtomicPtr<Error>,
 
@@ -418,10 +670,19 @@ struct WhyNotImpl {
 
 impl WhyNot {
     /// Creates a new [WhyNot] from the given raw value and error.
+    ///
+    /// Within [`spill_raw_values`](crate::spill_raw_values), a raw value over
+    /// the budget is spilled to disk.
     pub fn new(raw: Option<crate::Value>, err: Error) -> Self {
         let err_msg = err.to_string();
+        #[cfg(feature = "spill")]
+        let spilled = raw.as_ref().and_then(crate::spill::maybe_spill);
+        #[cfg(feature = "spill")]
+        let raw = raw.filter(|_| spilled.is_none());
         Self(Arc::new(WhyNotImpl {
             raw,
+            #[cfg(feature = "spill")]
+            spilled,
             err: AtomicPtr::new(Box::into_raw(Box::new(err))),
             err_msg,
         }))
@@ -452,17 +713,50 @@ impl WhyNot {
     fn as_msg(&self) -> &str {
         &self.0.err_msg
     }
+
+    fn as_spilled(&self) -> Option<&Spilled> {
+        #[cfg(feature = "spill")]
+        {
+            self.0.spilled.as_ref()
+        }
+        #[cfg(not(feature = "spill"))]
+        {
+            None
+        }
+    }
+
+    fn load_spilled(&self) -> Option<Result<crate::Value, Error>> {
+        #[cfg(feature = "spill")]
+        {
+            self.0.spilled.as_ref().map(crate::SpilledRaw::load)
+        }
+        #[cfg(not(feature = "spill"))]
+        {
+            None
+        }
+    }
 }
 
+#[cfg(feature = "spill")]
+type Spilled = crate::SpilledRaw;
+
+/// Without the `spill` feature, raw values are never spilled.
+#[cfg(not(feature = "spill"))]
+type Spilled = std::convert::Infallible;
+
 // ----- Value semantics for WhyNot -----
 //
-// `WhyNot` instances are treated as the pair `(raw_value: Option<Value>,
-// err_msg: String)` for the purposes of equality, ordering, and hashing.
-// The `Error` instance is ignored.
+// `WhyNot` instances are treated as the triple `(raw_value: Option<Value>,
+// spilled: Option<SpilledRaw>, err_msg: String)` for the purposes of
+// equality, ordering, and hashing. Spilled raw values compare by the content
+// of their YAML, but never equal a raw value kept in memory. The `Error`
+// instance is ignored.
 
 impl PartialEq for WhyNot {
     fn eq(&self, other: &Self) -> bool {
-        self.as_ref_raw() == other.as_ref_raw() && self.as_msg() == other.as_msg()
+        self.as_ref_raw() == other.as_ref_raw()
+            && self.as_spilled() == other.as_spilled()
+            && self.as_msg() == other.as_msg()
     }
 }
 
@@ -477,7 +771,10 @@ impl PartialOrd for WhyNot {
 impl Ord for WhyNot {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.as_ref_raw().partial_cmp(&other.as_ref_raw()) {
-            Some(std::cmp::Ordering::Equal) | None => self.as_msg().cmp(other.as_msg()),
+            Some(std::cmp::Ordering::Equal) | None => self
+                .as_spilled()
+                .cmp(&other.as_spilled())
+                .then_with(|| self.as_msg().cmp(other.as_msg())),
             Some(ord) => ord,
         }
     }
@@ -486,6 +783,7 @@ impl Ord for WhyNot {
 impl std::hash::Hash for WhyNot {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.as_ref_raw().hash(state);
+        self.as_spilled().hash(state);
         self.as_msg().hash(state);
     }
 }
@@ -562,8 +860,63 @@ where
     }
 }
 
+#[cfg(feature = "schemars")]
+impl<T> schemars::JsonSchema for ShouldBeRef<T>
+where
+    T: schemars::JsonSchema,
+{
+    fn schema_name() -> String {
+        T::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        T::json_schema(generator)
+    }
+
+    fn is_referenceable() -> bool {
+        T::is_referenceable()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        T::schema_id()
+    }
+
+    #[doc(hidden)]
+    fn _schemars_private_non_optional_json_schema(
+        generator: &mut schemars::gen::SchemaGenerator,
+    ) -> schemars::schema::Schema {
+        T::_schemars_private_non_optional_json_schema(generator)
+    }
+
+    #[doc(hidden)]
+    fn _schemars_private_is_option() -> bool {
+        T::_schemars_private_is_option()
+    }
+}
+
+/// The kind of [ShouldBe] being deserialized.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Expecting {
+    ShouldBe,
+    /// A [ShouldBeRef], whose target may borrow from the input, so that it
+    /// must not be deserialized from a temporary copy of it.
+    ShouldBeRef,
+}
+
 pub(crate) fn is_expecting_should_be_then_reset() -> bool {
-    EXPECTING_SHOULD_BE.with(|cell| cell.replace(false))
+    take_expecting_should_be().is_some()
+}
+
+pub(crate) fn take_expecting_should_be() -> Option<Expecting> {
+    EXPECTING_SHOULD_BE.with(std::cell::Cell::take)
+}
+
+pub(crate) fn set_expecting_should_be(expecting: Option<Expecting>) {
+    EXPECTING_SHOULD_BE.with(|cell| cell.set(expecting));
+}
+
+pub(crate) fn clear_why_not() {
+    take_why_not();
 }
 
 fn take_why_not() -> Option<(Value, Error)> {
@@ -575,7 +928,7 @@ pub(crate) fn set_why_not(raw: Value, err: Error) {
 }
 
 thread_local! {
-    static EXPECTING_SHOULD_BE: std::cell::RefCell<bool> = const {std::cell::RefCell::new(false)};
+    static EXPECTING_SHOULD_BE: std::cell::Cell<Option<Expecting>> = const {std::cell::Cell::new(None)};
 
     static WHY_NOT: std::cell::RefCell<Option<(Value, Error)>> = const {std::cell::RefCell::new(None)};
 }
@@ -0,0 +1,126 @@
+use serde::de::DeserializeOwned;
+
+use super::Span;
+use crate::config::{self, Sink};
+use crate::Value;
+
+/// Asserts that every [`Spanned`](crate::Spanned) value inside `T` receives a
+/// usable span when `T` is deserialized from `sample`.
+///
+/// Spans are captured from thread-local state that is only kept up to date
+/// while this crate's deserializers drive deserialization. A custom
+/// `deserialize_with` that detours through another format, or a type that
+/// buffers its input, silently leaves the `Spanned` values below it with
+/// invalid or empty spans. This helper is meant for tests: it deserializes
+/// `T` from `sample` both directly from text and through
+/// [`Value::into_typed`], and panics describing every `Spanned` value whose
+/// span is invalid or empty.
+///
+/// `sample` should give every `Spanned` field a non-empty value, since a null
+/// value legitimately has an empty span. The assertion also fails if `T`
+/// cannot be deserialized from `sample`, or if no `Spanned` values were
+/// deserialized at all.
+///
+/// ```
+/// use dbt_serde_yaml::Spanned;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: Spanned<String>,
+///     tags: Vec<Spanned<String>>,
+/// }
+///
+/// dbt_serde_yaml::assert_span_support::<Model>("name: orders\ntags: [a, b]\n");
+/// ```
+///
+/// A field that is deserialized through an intermediate format fails:
+///
+/// ```should_panic
+/// use dbt_serde_yaml::Spanned;
+/// use serde::Deserializer;
+/// use serde_derive::Deserialize;
+///
+/// fn via_string<'de, D: Deserializer<'de>>(d: D) -> Result<Spanned<String>, D::Error> {
+///     let s: String = serde::Deserialize::deserialize(d)?;
+///     serde::Deserialize::deserialize(serde::de::value::StringDeserializer::<D::Error>::new(s))
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     #[serde(deserialize_with = "via_string")]
+///     name: Spanned<String>,
+/// }
+///
+/// dbt_serde_yaml::assert_span_support::<Model>("name: orders\n");
+/// ```
+#[track_caller]
+pub fn assert_span_support<T>(sample: &str)
+where
+    T: DeserializeOwned,
+{
+    let mut failures = Vec::new();
+
+    let (result, spans) = record_spans(|| crate::from_str::<T>(sample));
+    if let Err(err) = result {
+        panic!(
+            "failed to deserialize {} from text: {}",
+            std::any::type_name::<T>(),
+            err
+        );
+    }
+    let total = spans.len();
+    check("text", spans, &mut failures);
+
+    let value: Value =
+        crate::from_str(sample).unwrap_or_else(|err| panic!("invalid sample: {}", err));
+    let (result, spans) = record_spans(|| value.into_typed::<T, _, _>(|_, _, _| {}, |_| Ok(None)));
+    if let Err(err) = result {
+        panic!(
+            "failed to deserialize {} from a Value: {}",
+            std::any::type_name::<T>(),
+            err
+        );
+    }
+    check("a Value", spans, &mut failures);
+
+    if total == 0 {
+        panic!(
+            "no Spanned values were deserialized in {}",
+            std::any::type_name::<T>()
+        );
+    }
+    if !failures.is_empty() {
+        panic!(
+            "spans are not supported by {}:\n{}",
+            std::any::type_name::<T>(),
+            failures.join("\n")
+        );
+    }
+}
+
+fn check(source: &str, spans: Vec<(&'static str, Span)>, failures: &mut Vec<String>) {
+    for (type_name, span) in spans {
+        if !span.is_valid() || span.start.index() == span.end.index() {
+            failures.push(format!(
+                "  Spanned<{}> deserialized from {} has span {:?}",
+                type_name, source, span
+            ));
+        }
+    }
+}
+
+/// Runs `f`, returning the span and inner type name of every
+/// [`Spanned`](crate::Spanned) value deserialized while it runs.
+fn record_spans<R>(f: impl FnOnce() -> R) -> (R, Vec<(&'static str, Span)>) {
+    let sink = Sink::new();
+    let result = config::with_changed(|config| config.spans = Some(sink.clone()), f);
+    (result, sink.take())
+}
+
+/// Records the span of a deserialized `Spanned<T>`, if recording.
+pub(crate) fn spanned_deserialized<T>(span: &Span) {
+    if let Some(spans) = config::sink(|config| &config.spans) {
+        spans.push((std::any::type_name::<T>(), span.clone()));
+    }
+}
@@ -0,0 +1,139 @@
+use serde::de::{self, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use super::Spanned;
+
+/// An adapter that serializes a [Spanned] together with its span, so that the
+/// span survives a round trip through any serde data format.
+///
+/// A plain [Spanned] serializes as its node alone. `SpannedWithMeta`
+/// serializes as a map of the node and its span instead:
+///
+/// ```yaml
+/// value: orders
+/// span:
+///   start: {index: 6, line: 1, column: 7}
+///   end: {index: 12, line: 1, column: 13}
+///   filename: models.yml  # with the `filename` feature
+/// ```
+///
+/// and deserializes from the same shape, restoring the span rather than
+/// capturing a new one. This is meant for persisting parsed data, e.g. in a
+/// cache, without losing the locations used for diagnostics.
+///
+/// ```
+/// use dbt_serde_yaml::spanned::SpannedWithMeta;
+/// use dbt_serde_yaml::Spanned;
+///
+/// let name: Spanned<String> = dbt_serde_yaml::from_str("orders").unwrap();
+/// let yaml = dbt_serde_yaml::to_string(&SpannedWithMeta::from(name.clone())).unwrap();
+///
+/// let restored: SpannedWithMeta<String> = dbt_serde_yaml::from_str(&yaml).unwrap();
+/// assert_eq!(*restored.0, "orders");
+/// assert_eq!(restored.span(), name.span());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpannedWithMeta<T>(pub Spanned<T>);
+
+impl<T> SpannedWithMeta<T> {
+    /// Consumes the adapter and returns the wrapped [Spanned].
+    pub fn into_inner(self) -> Spanned<T> {
+        self.0
+    }
+}
+
+impl<T> From<Spanned<T>> for SpannedWithMeta<T> {
+    fn from(spanned: Spanned<T>) -> Self {
+        SpannedWithMeta(spanned)
+    }
+}
+
+impl<T> From<SpannedWithMeta<T>> for Spanned<T> {
+    fn from(meta: SpannedWithMeta<T>) -> Self {
+        meta.0
+    }
+}
+
+impl<T> Deref for SpannedWithMeta<T> {
+    type Target = Spanned<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SpannedWithMeta<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> Serialize for SpannedWithMeta<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("SpannedWithMeta", 2)?;
+        state.serialize_field("value", &self.0)?;
+        state.serialize_field("span", &self.0.span)?;
+        state.end()
+    }
+}
+
+impl<'de, T> Deserialize<'de> for SpannedWithMeta<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SpannedWithMetaVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for SpannedWithMetaVisitor<T>
+        where
+            T: Deserialize<'de>,
+        {
+            type Value = SpannedWithMeta<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map with `value` and `span` entries")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut node = None;
+                let mut span = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "value" => node = Some(map.next_value()?),
+                        "span" => span = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                let node = node.ok_or_else(|| de::Error::missing_field("value"))?;
+                Ok(SpannedWithMeta(Spanned::with_span(
+                    node,
+                    span.unwrap_or_default(),
+                )))
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "SpannedWithMeta",
+            &["value", "span"],
+            SpannedWithMetaVisitor(PhantomData),
+        )
+    }
+}
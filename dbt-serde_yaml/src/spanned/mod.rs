@@ -7,8 +7,12 @@ use std::{
     ops::Deref,
 };
 
+mod check;
+mod meta;
 mod span;
 
+pub use check::assert_span_support;
+pub use meta::SpannedWithMeta;
 pub use span::Marker;
 pub use span::Span;
 
@@ -16,11 +20,16 @@ pub use span::Span;
 /// deserialized value.
 ///
 /// NOTE:
-/// - Only works with the dbt_serde_yaml deserializer.
+/// - Only works with the dbt_serde_yaml deserializer. Other deserializers,
+///   such as `serde_json`, leave the span invalid and mark the value as
+///   [foreign](Spanned::is_foreign), or fail if
+///   [`Config::strict_spans`](crate::Config::strict_spans) is set. To keep
+///   spans through another format, wrap the value in [SpannedWithMeta].
 /// - May contain leading and trailing whitespace.
 pub struct Spanned<T> {
     span: Span,
     node: T,
+    foreign: bool,
 }
 
 impl<'de, T> Spanned<T>
@@ -32,11 +41,22 @@ where
         Spanned {
             span: Default::default(),
             node,
+            foreign: false,
         }
     }
 }
 
 impl<T> Spanned<T> {
+    /// Create a `Spanned` value with the given node and span, e.g. to restore
+    /// a value whose span was persisted separately.
+    pub fn with_span(node: T, span: Span) -> Self {
+        Spanned {
+            span,
+            node,
+            foreign: false,
+        }
+    }
+
     /// Transform the inner node by applying the given function.
     pub fn map<U, F>(self, f: F) -> Spanned<U>
     where
@@ -45,6 +65,7 @@ impl<T> Spanned<T> {
         Spanned {
             span: self.span,
             node: f(self.node),
+            foreign: self.foreign,
         }
     }
 
@@ -62,6 +83,34 @@ impl<T> Spanned<T> {
     pub fn has_valid_span(&self) -> bool {
         self.span.is_valid()
     }
+
+    /// True if this [Spanned] was deserialized by a deserializer that does
+    /// not report source locations, i.e. not one of this crate's, so that
+    /// its span is invalid for lack of information rather than by design.
+    ///
+    /// A foreign deserializer driven from within one of this crate's, as by
+    /// a `deserialize_with` that detours through another format, is not
+    /// detected; see [assert_span_support] for catching those in tests. Nor
+    /// is one that runs after a [`Value`](crate::Value) was used directly as
+    /// a deserializer on the same thread, which leaves its last marker
+    /// behind, unlike entry points such as [`from_value`](crate::from_value);
+    /// [reset_marker] clears it.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Spanned;
+    /// use serde::de::value::{Error, StrDeserializer};
+    /// use serde::Deserialize;
+    ///
+    /// let name: Spanned<String> = dbt_serde_yaml::from_str("orders").unwrap();
+    /// assert!(!name.is_foreign());
+    ///
+    /// let name = Spanned::<String>::deserialize(StrDeserializer::<Error>::new("orders")).unwrap();
+    /// assert!(name.is_foreign());
+    /// assert!(!name.has_valid_span());
+    /// ```
+    pub fn is_foreign(&self) -> bool {
+        self.foreign
+    }
 }
 
 impl<T> Deref for Spanned<T> {
@@ -92,6 +141,7 @@ where
         Spanned {
             span: self.span.clone(),
             node: self.node.clone(),
+            foreign: self.foreign,
         }
     }
 }
@@ -114,6 +164,7 @@ where
         Spanned {
             span: Default::default(),
             node: T::default(),
+            foreign: false,
         }
     }
 }
@@ -198,12 +249,26 @@ where
         let start_marker = get_marker();
         let node = T::deserialize(deserializer)?;
         let end_marker = get_marker();
+        // This crate's deserializers always set a marker, so none at either
+        // end means another deserializer is in charge.
+        let foreign = start_marker.is_none() && end_marker.is_none();
+        if foreign && crate::config::strict_spans() {
+            return Err(serde::de::Error::custom(format_args!(
+                "cannot capture the span of Spanned<{}>: the deserializer does not report source locations",
+                std::any::type_name::<T>(),
+            )));
+        }
         let span: Span = (start_marker..end_marker).into();
 
         #[cfg(feature = "filename")]
         let span = span.maybe_capture_filename();
 
-        Ok(Spanned { span, node })
+        check::spanned_deserialized::<T>(&span);
+        Ok(Spanned {
+            span,
+            node,
+            foreign,
+        })
     }
 }
 
@@ -264,6 +329,81 @@ pub fn with_filename(filename: Option<std::path::PathBuf>) -> WithFilenameScope
     WithFilenameScope { original }
 }
 
+/// A snapshot of the source location state used for span capture.
+///
+/// Span capture relies on thread-local state, which does not follow work that
+/// is moved to another thread. A `SpanContext` can be captured on one thread,
+/// sent to another, and entered there to continue with the same state.
+///
+/// ```
+/// use dbt_serde_yaml::{Marker, SpanContext};
+///
+/// dbt_serde_yaml::set_marker(Marker::new(10, 2, 3));
+/// let context = SpanContext::capture();
+///
+/// std::thread::spawn(move || {
+///     let _scope = context.enter();
+///     assert_eq!(SpanContext::capture().marker(), Some(Marker::new(10, 2, 3)));
+/// })
+/// .join()
+/// .unwrap();
+/// # dbt_serde_yaml::reset_marker();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SpanContext {
+    marker: Option<Marker>,
+    #[cfg(feature = "filename")]
+    filename: Option<std::sync::Arc<std::path::PathBuf>>,
+}
+
+impl SpanContext {
+    /// Captures the source location state of the current thread.
+    pub fn capture() -> Self {
+        SpanContext {
+            marker: get_marker(),
+            #[cfg(feature = "filename")]
+            filename: get_filename(),
+        }
+    }
+
+    /// Installs this state on the current thread.
+    ///
+    /// Returns a scope guard that restores the previous state when dropped.
+    pub fn enter(&self) -> SpanContextScope {
+        let original = SpanContext::capture();
+        self.install();
+        SpanContextScope { original }
+    }
+
+    /// The captured source location marker.
+    pub fn marker(&self) -> Option<Marker> {
+        self.marker
+    }
+
+    #[cfg(feature = "filename")]
+    /// The captured source filename.
+    pub fn filename(&self) -> Option<&std::path::Path> {
+        self.filename.as_deref().map(|f| f.as_path())
+    }
+
+    fn install(&self) {
+        MARKER.with(|m| *m.borrow_mut() = self.marker);
+        #[cfg(feature = "filename")]
+        FILENAME.with(|f| *f.borrow_mut() = self.filename.clone());
+    }
+}
+
+/// A scope guard returned by [`SpanContext::enter`].
+pub struct SpanContextScope {
+    original: SpanContext,
+}
+
+impl Drop for SpanContextScope {
+    fn drop(&mut self) {
+        self.original.install();
+    }
+}
+
 /// Set the current source location marker.
 ///
 /// This is called by [Deserializer] implementations to inform the
@@ -282,6 +422,28 @@ pub(crate) fn get_marker() -> Option<Marker> {
     MARKER.with(|m| *m.borrow())
 }
 
+/// Sets the source location marker for the duration of a deserialization.
+///
+/// Returns a scope guard that puts back the marker of the enclosing
+/// deserialization, if any, when dropped. A document deserialized from inside
+/// another one, e.g. by a field transformer or a `Deserialize` impl that
+/// parses embedded YAML, then leaves the spans of the outer document intact.
+pub(crate) fn enter_marker(marker: Marker) -> MarkerScope {
+    let outer = MARKER.with(|m| m.borrow_mut().replace(marker));
+    MarkerScope { outer }
+}
+
+/// A scope guard returned by [enter_marker].
+pub(crate) struct MarkerScope {
+    outer: Option<Marker>,
+}
+
+impl Drop for MarkerScope {
+    fn drop(&mut self) {
+        MARKER.with(|m| *m.borrow_mut() = self.outer);
+    }
+}
+
 pub(crate) fn set_span(span: Span) {
     SPAN.with(|s| *s.borrow_mut() = Some(span));
 }
@@ -302,6 +464,13 @@ pub(crate) fn get_filename() -> Option<std::sync::Arc<std::path::PathBuf>> {
     FILENAME.with(|f| f.borrow().clone())
 }
 
+#[cfg(feature = "filename")]
+/// Restore the source filename to a value previously returned by
+/// [get_filename].
+pub(crate) fn restore_filename(filename: Option<std::sync::Arc<std::path::PathBuf>>) {
+    FILENAME.with(|f| *f.borrow_mut() = filename);
+}
+
 // Internal states for deserialization.
 thread_local! {
     static MARKER: std::cell::RefCell<Option<Marker>> = const {
@@ -1,3 +1,6 @@
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{self, Debug, Display};
 use std::ops::Range;
 #[cfg(feature = "filename")]
@@ -8,6 +11,12 @@ use std::sync::Arc;
 use crate::libyaml::error::Mark;
 
 /// A source span.
+///
+/// Spans serialize as a map of their `start` and `end` [Marker]s, plus their
+/// `filename` with the `filename` feature, and deserialize from the same
+/// shape, so that they can be persisted and restored outside of the process
+/// that parsed the source. When deserializing, missing entries are left at
+/// their [zero](Span::zero) value.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Span {
     /// The start of the span.
@@ -131,6 +140,8 @@ impl From<Marker> for Span {
 }
 
 /// A location in the source string.
+///
+/// Markers serialize as a map of their `index`, `line` and `column`.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Marker {
     /// Offset in bytes from the start of the source string.
@@ -215,3 +226,107 @@ impl Display for Marker {
         write!(f, "line {} column {}", self.line, self.column)
     }
 }
+
+impl Serialize for Span {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Span", 3)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        #[cfg(feature = "filename")]
+        state.serialize_field("filename", &self.get_filename())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Span {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SpanVisitor;
+
+        impl<'de> Visitor<'de> for SpanVisitor {
+            type Value = Span;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a source span")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut span = Span::zero();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "start" => span.start = map.next_value()?,
+                        "end" => span.end = map.next_value()?,
+                        #[cfg(feature = "filename")]
+                        "filename" => {
+                            span.filename = map.next_value::<Option<PathBuf>>()?.map(Arc::new);
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(span)
+            }
+        }
+
+        deserializer.deserialize_struct("Span", &["start", "end", "filename"], SpanVisitor)
+    }
+}
+
+impl Serialize for Marker {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Marker", 3)?;
+        state.serialize_field("index", &self.index)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("column", &self.column)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Marker {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MarkerVisitor;
+
+        impl<'de> Visitor<'de> for MarkerVisitor {
+            type Value = Marker;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a source location")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut marker = Marker::zero();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "index" => marker.index = map.next_value()?,
+                        "line" => marker.line = map.next_value()?,
+                        "column" => marker.column = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(marker)
+            }
+        }
+
+        deserializer.deserialize_struct("Marker", &["index", "line", "column"], MarkerVisitor)
+    }
+}
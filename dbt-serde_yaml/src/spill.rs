@@ -0,0 +1,251 @@
+//! Spilling of large raw values captured by [`ShouldBe`](crate::ShouldBe) to
+//! temporary files.
+//!
+//! When ingesting giant documents leniently, the raw [Value] kept for every
+//! failed subtree can dominate memory use. Inside [spill_raw_values], raw
+//! values whose estimated YAML size exceeds a byte budget are written to a
+//! temporary file instead, and the [`ShouldBe`](crate::ShouldBe) keeps a
+//! [SpilledRaw] handle from which the value can be loaded back on demand.
+//!
+//! The temporary files are closed once written, so the number of spilled
+//! values is not bounded by the process's file descriptor limit.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::sync::Arc;
+
+use tempfile::{NamedTempFile, TempPath};
+
+use crate::config;
+use crate::error::{self, ErrorImpl};
+use crate::{Error, Span, Value};
+
+/// A handle to a raw value that was spilled to a temporary file.
+///
+/// The file is deleted once the last clone of the handle is dropped. Handles
+/// compare, order and hash by the content of the spilled YAML, so two values
+/// spilled from equal raw values are equal.
+#[derive(Clone)]
+pub struct SpilledRaw(Arc<SpilledRawImpl>);
+
+struct SpilledRawImpl {
+    path: TempPath,
+    len: usize,
+    /// A hash of the spilled YAML, which tells most handles apart without
+    /// reading the files back.
+    digest: u64,
+    span: Span,
+}
+
+impl SpilledRaw {
+    /// The path of the temporary file holding the value as YAML.
+    pub fn path(&self) -> &std::path::Path {
+        &self.0.path
+    }
+
+    /// The size of the spilled YAML in bytes.
+    pub fn size(&self) -> usize {
+        self.0.len
+    }
+
+    /// The span the value had in the original input.
+    pub fn span(&self) -> &Span {
+        &self.0.span
+    }
+
+    /// Reads the value back from disk.
+    ///
+    /// Spans in the loaded value refer to the temporary file rather than the
+    /// original input; see [SpilledRaw::span] for the latter.
+    pub fn load(&self) -> Result<Value, Error> {
+        let file = File::open(self.path()).map_err(|err| error::new(ErrorImpl::Io(err)))?;
+        crate::from_reader(file)
+    }
+
+    fn key(&self) -> (usize, u64) {
+        (self.0.len, self.0.digest)
+    }
+
+    /// Compares the spilled YAML of two handles, only reading the files back
+    /// if their sizes and digests are the same.
+    fn compare(&self, other: &Self) -> Ordering {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return Ordering::Equal;
+        }
+        self.key().cmp(&other.key()).then_with(|| {
+            // A file that can't be read back is only equal to itself.
+            compare_files(self.path(), other.path())
+                .unwrap_or_else(|_| self.path().cmp(other.path()))
+        })
+    }
+}
+
+impl PartialEq for SpilledRaw {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SpilledRaw {}
+
+impl PartialOrd for SpilledRaw {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SpilledRaw {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other)
+    }
+}
+
+impl Hash for SpilledRaw {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl Debug for SpilledRaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpilledRaw")
+            .field("path", &self.path())
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+/// Runs `f`, spilling every raw value captured by a failed
+/// [`ShouldBe`](crate::ShouldBe) to a temporary file if its YAML form is
+/// estimated to be larger than `budget` bytes.
+///
+/// The estimate adds up the lengths of the value's scalars, tags and
+/// indicators without serializing it, so it ignores indentation and quoting;
+/// only values over the budget are serialized, straight to their file.
+///
+/// Spilled values are available through
+/// [`ShouldBe::spilled_raw`](crate::ShouldBe::spilled_raw) instead of
+/// [`ShouldBe::as_ref_raw`](crate::ShouldBe::as_ref_raw). If a value cannot be
+/// written to disk it is kept in memory as usual. This sets
+/// [`Config::spill_raw_values_over`](crate::Config::spill_raw_values_over) in
+/// the thread's current config while `f` runs.
+///
+/// ```
+/// use dbt_serde_yaml::{ShouldBe, Value};
+///
+/// let value: Value = dbt_serde_yaml::from_str("[1, 2, 3]").unwrap();
+/// let should_be: ShouldBe<String> = dbt_serde_yaml::spill_raw_values(4, || {
+///     value.to_typed(|_, _, _| {}, |_| Ok(None))
+/// })
+/// .unwrap();
+///
+/// assert!(should_be.as_ref_raw().is_none());
+/// let spilled = should_be.spilled_raw().unwrap();
+/// assert_eq!(spilled.load().unwrap(), value);
+/// ```
+pub fn spill_raw_values<R>(budget: usize, f: impl FnOnce() -> R) -> R {
+    config::with_changed(|config| config.spill_raw_values_over = Some(budget), f)
+}
+
+/// Writes `raw` to a temporary file if it exceeds the current budget.
+pub(crate) fn maybe_spill(raw: &Value) -> Option<SpilledRaw> {
+    let budget = config::spill_raw_values_over()?;
+    let mut remaining = budget;
+    if fits(raw, &mut remaining).is_some() {
+        return None;
+    }
+    let mut writer = DigestWriter {
+        inner: BufWriter::new(NamedTempFile::new().ok()?),
+        len: 0,
+        hasher: DefaultHasher::new(),
+    };
+    crate::to_writer(&mut writer, raw).ok()?;
+    let DigestWriter { inner, len, hasher } = writer;
+    let file = inner.into_inner().ok()?;
+    Some(SpilledRaw(Arc::new(SpilledRawImpl {
+        path: file.into_temp_path(),
+        len,
+        digest: hasher.finish(),
+        span: raw.span().clone(),
+    })))
+}
+
+/// Compares the content of two files without reading either whole.
+fn compare_files(a: &std::path::Path, b: &std::path::Path) -> io::Result<Ordering> {
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    loop {
+        let (chunk_a, chunk_b) = (a.fill_buf()?, b.fill_buf()?);
+        let len = chunk_a.len().min(chunk_b.len());
+        if len == 0 {
+            return Ok(chunk_a.len().cmp(&chunk_b.len()));
+        }
+        match chunk_a[..len].cmp(&chunk_b[..len]) {
+            Ordering::Equal => {}
+            ordering => return Ok(ordering),
+        }
+        a.consume(len);
+        b.consume(len);
+    }
+}
+
+/// Charges the estimated YAML size of `value` against `remaining`, returning
+/// `None` as soon as it runs out.
+fn fits(value: &Value, remaining: &mut usize) -> Option<()> {
+    let size = match value {
+        Value::Null(..) => "null".len(),
+        Value::Bool(true, ..) => "true".len(),
+        Value::Bool(false, ..) => "false".len(),
+        Value::Number(number, ..) => number.to_string().len(),
+        Value::String(string, ..) => string.len(),
+        Value::Sequence(sequence, ..) => {
+            for item in sequence {
+                charge(remaining, "- \n".len())?;
+                fits(item, remaining)?;
+            }
+            "[]".len()
+        }
+        Value::Mapping(mapping, ..) => {
+            for (key, value) in mapping {
+                charge(remaining, ": \n".len())?;
+                fits(key, remaining)?;
+                fits(value, remaining)?;
+            }
+            "{}".len()
+        }
+        Value::Tagged(tagged, ..) => {
+            fits(&tagged.value, remaining)?;
+            tagged.tag.to_string().len() + " ".len()
+        }
+    };
+    charge(remaining, size)
+}
+
+fn charge(remaining: &mut usize, size: usize) -> Option<()> {
+    *remaining = remaining.checked_sub(size)?;
+    Some(())
+}
+
+/// Counts and hashes the bytes written through it.
+struct DigestWriter<W> {
+    inner: W,
+    len: usize,
+    hasher: DefaultHasher,
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.len += written;
+        self.hasher.write(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
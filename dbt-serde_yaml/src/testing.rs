@@ -0,0 +1,140 @@
+//! Helpers for tests that compare [Value]s, enabled by the `test-util`
+//! feature.
+//!
+//! [assert_yaml_eq!](crate::assert_yaml_eq) fails with a diff listing every
+//! path at which the two values differ, together with where each side was
+//! read from, rather than printing both values in full.
+
+use crate::value::{Value, ValueChange};
+use std::borrow::Borrow;
+use std::fmt::{self, Write as _};
+
+/// Asserts that two [Value]s are equal, ignoring spans, like `assert_eq!`.
+///
+/// On failure, the panic message lists each path at which the values differ,
+/// with the left and right values there and their line and column, as given
+/// by [`yaml_diff`](crate::testing::yaml_diff). A message can be added after
+/// the two values, formatted like that of `assert_eq!`.
+///
+/// ```
+/// use dbt_serde_yaml::{assert_yaml_eq, Value};
+///
+/// let left: Value = dbt_serde_yaml::from_str("name: orders\ntags: [nightly]\n").unwrap();
+/// let right: Value = dbt_serde_yaml::from_str("tags: [nightly]\nname: orders\n").unwrap();
+/// assert_yaml_eq!(left, right);
+/// ```
+///
+/// ```should_panic
+/// use dbt_serde_yaml::{assert_yaml_eq, Value};
+///
+/// let left: Value = dbt_serde_yaml::from_str("name: orders\n").unwrap();
+/// let right: Value = dbt_serde_yaml::from_str("name: customers\n").unwrap();
+/// assert_yaml_eq!(left, right, "in {}", "models.yml");
+/// // assertion `left == right` failed: in models.yml
+/// // 1 difference:
+/// //   name:
+/// //     left:  orders (line 1 column 7)
+/// //     right: customers (line 1 column 7)
+/// ```
+#[macro_export]
+macro_rules! assert_yaml_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_yaml_eq(&$left, &$right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::testing::assert_yaml_eq(
+            &$left,
+            &$right,
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Implementation of [assert_yaml_eq!](crate::assert_yaml_eq).
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_yaml_eq<L, R>(left: &L, right: &R, message: Option<fmt::Arguments>)
+where
+    L: Borrow<Value> + ?Sized,
+    R: Borrow<Value> + ?Sized,
+{
+    if let Some(diff) = yaml_diff(left.borrow(), right.borrow()) {
+        match message {
+            Some(message) => panic!("assertion `left == right` failed: {}\n{}", message, diff),
+            None => panic!("assertion `left == right` failed\n{}", diff),
+        }
+    }
+}
+
+/// Describes the differences between two values, or returns `None` if they
+/// are equal, ignoring spans.
+///
+/// Each path at which the values differ, as found by [Value::diff], is listed
+/// with the value on either side, written in flow style, followed by its line
+/// and column if it has a span, or `missing` for a side without a value.
+///
+/// ```
+/// use dbt_serde_yaml::testing::yaml_diff;
+/// use dbt_serde_yaml::Value;
+///
+/// let left: Value = dbt_serde_yaml::from_str("
+/// name: orders
+/// columns: [id, amount]
+/// ").unwrap();
+/// let right: Value = dbt_serde_yaml::from_str("
+/// name: orders
+/// columns: [id]
+/// materialized: table
+/// ").unwrap();
+///
+/// assert_eq!(yaml_diff(&left, &left.clone()), None);
+/// assert_eq!(
+///     yaml_diff(&left, &right).unwrap(),
+///     "\
+/// 2 differences:
+///   columns[1]:
+///     left:  amount (line 3 column 15)
+///     right: missing
+///   materialized:
+///     left:  missing
+///     right: table (line 4 column 15)
+/// ",
+/// );
+/// ```
+pub fn yaml_diff(left: &Value, right: &Value) -> Option<String> {
+    let patch = left.diff(right);
+    if patch.is_empty() {
+        return None;
+    }
+    let count = patch.changes().len();
+    let mut out = String::new();
+    let plural = if count == 1 { "" } else { "s" };
+    let _ = writeln!(out, "{} difference{}:", count, plural);
+    for change in &patch {
+        let (old, new) = match change {
+            ValueChange::Added { value, .. } => (None, Some(value)),
+            ValueChange::Removed { value, .. } => (Some(value), None),
+            ValueChange::Changed { old, new, .. } => (Some(old), Some(new)),
+        };
+        let _ = writeln!(out, "  {}:", change.path());
+        let _ = writeln!(out, "    left:  {}", Side(old));
+        let _ = writeln!(out, "    right: {}", Side(new));
+    }
+    Some(out)
+}
+
+struct Side<'a>(Option<&'a Value>);
+
+impl fmt::Display for Side<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let Some(value) = self.0 else {
+            return formatter.write_str("missing");
+        };
+        write!(formatter, "{}", value)?;
+        let span = value.span();
+        if span.is_valid() {
+            write!(formatter, " ({})", span.start)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+//! Optional `tracing` instrumentation.
+//!
+//! Every helper in this module compiles down to a no-op unless the `tracing`
+//! feature is enabled, so call sites do not need to be feature-gated.
+
+use crate::path::Path;
+
+#[cfg(feature = "tracing")]
+pub(crate) type Entered = Option<tracing::span::EnteredSpan>;
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Entered;
+
+/// Enters a span covering the parsing of a single YAML document.
+#[inline]
+pub(crate) fn enter_parse_document(index: usize) -> Entered {
+    #[cfg(feature = "tracing")]
+    {
+        Some(tracing::debug_span!("dbt_serde_yaml::parse_document", index).entered())
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = index;
+        Entered
+    }
+}
+
+/// Records that a document has been parsed into `events` events.
+#[inline]
+pub(crate) fn document_parsed(events: usize, failed: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(events, failed, "parsed YAML document");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (events, failed);
+}
+
+/// Enters a span covering the typed deserialization of the value at `path`,
+/// if `path` is a top-level mapping key.
+#[inline]
+pub(crate) fn enter_top_level_key(path: &Path) -> Entered {
+    #[cfg(feature = "tracing")]
+    {
+        match path {
+            Path::Map {
+                parent: Path::Root,
+                key,
+            } => {
+                Some(tracing::debug_span!("dbt_serde_yaml::deserialize_key", key = *key).entered())
+            }
+            _ => None,
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = path;
+        Entered
+    }
+}
+
+/// Records an invocation of a field transformer at `path`.
+#[inline]
+pub(crate) fn transformer_invoked(path: &Path, transformed: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(path = %path, transformed, "invoked field transformer");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (path, transformed);
+}
+
+/// Records how many times a user-supplied callback was invoked over the
+/// course of one top-level (de)serialization call.
+#[inline]
+pub(crate) fn callback_count(callback: &'static str, count: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(callback, count, "invoked callback");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (callback, count);
+}
+
+/// Records that the enum variant name `found` at `path` was matched to
+/// `variant` only after normalization.
+#[inline]
+pub(crate) fn variant_normalized(path: &Path, found: &str, variant: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(path = %path, found, variant, "normalized enum variant");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (path, found, variant);
+}
@@ -0,0 +1,784 @@
+//! Validation of [`Value`]s against the JSON schemas generated by schemars.
+//!
+//! Deserializing into a typed struct stops at the first problem, and reports
+//! it in terms of the Rust types. Validating the [`Value`] against the schema
+//! of those types instead reports every problem, each at the path and span of
+//! the node it is about, so that all of them can be pointed out in the YAML
+//! source at once.
+//!
+//! ```
+//! use dbt_serde_yaml::validate::validate;
+//! use dbt_serde_yaml::{JsonSchema, Value};
+//!
+//! #[derive(JsonSchema)]
+//! struct Model {
+//!     name: String,
+//!     #[validate(range(min = 1))]
+//!     threads: u32,
+//! }
+//!
+//! let value: Value = dbt_serde_yaml::from_str("
+//! name: [orders]
+//! threads: 0
+//! ").unwrap();
+//!
+//! let errors = validate(&value, &schemars::schema_for!(Model));
+//! let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+//! assert_eq!(
+//!     errors,
+//!     [
+//!         "name: expected string, found sequence at line 2 column 7",
+//!         "threads: expected a number at least 1, found integer `0` at line 3 column 10",
+//!     ],
+//! );
+//! ```
+//!
+//! Externally tagged enum values such as `!Incremental id` are validated as
+//! the single-entry mapping `{Incremental: id}` that their schema describes.
+//! The `format` keyword is not checked.
+
+use crate::path::{OwnedPath, Path};
+use crate::value::{Mapping, Number, Value};
+use crate::Span;
+use regex::Regex;
+use schemars::schema::{
+    ArrayValidation, InstanceType, NumberValidation, ObjectValidation, RootSchema, Schema,
+    SchemaObject, SingleOrVec, StringValidation, SubschemaValidation,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+/// Bounds the resolution of definitions that refer to themselves when looking
+/// at a schema without a value to descend into.
+const MAX_DEPTH: usize = 32;
+
+/// Checks `value` against `schema`, returning every violation found.
+///
+/// To validate several values against the same schema, create a
+/// [`Validator`] once instead, so that the patterns in the schema are only
+/// compiled once.
+pub fn validate(value: &Value, schema: &RootSchema) -> Vec<ValidationError> {
+    Validator::new(schema).validate(value)
+}
+
+/// A schema ready to validate values against.
+pub struct Validator<'a> {
+    root: &'a RootSchema,
+    patterns: RefCell<HashMap<&'a str, Option<Regex>>>,
+}
+
+/// A violation of a schema by a node of a [`Value`].
+#[derive(Clone, Debug)]
+pub struct ValidationError {
+    path: OwnedPath,
+    span: Span,
+    expected: String,
+    actual: String,
+}
+
+impl ValidationError {
+    /// The path to the offending node, or for a missing property, to where it
+    /// is expected.
+    pub fn path(&self) -> &OwnedPath {
+        &self.path
+    }
+
+    /// The span of the offending node, or for a missing property, of the
+    /// mapping that lacks it.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// What the schema expects, such as `string` or `a number at least 1`.
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// What was found instead, such as ``integer `0` ``.
+    pub fn actual(&self) -> &str {
+        &self.actual
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !matches!(self.path, OwnedPath::Root) {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "expected {}, found {}", self.expected, self.actual)?;
+        if self.span.is_valid() {
+            write!(f, " at {}", self.span.start)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl<'a> Validator<'a> {
+    /// Prepares to validate values against `schema`.
+    pub fn new(schema: &'a RootSchema) -> Self {
+        Validator {
+            root: schema,
+            patterns: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Checks `value` against the schema, returning every violation found.
+    pub fn validate(&self, value: &Value) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        self.check_object(value, Path::Root, &self.root.schema, &mut errors);
+        errors
+    }
+
+    fn check(
+        &self,
+        value: &Value,
+        path: Path,
+        schema: &'a Schema,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        match schema {
+            Schema::Bool(true) => {}
+            Schema::Bool(false) => errors.push(error(path, value, "nothing", actual(value))),
+            Schema::Object(schema) => self.check_object(value, path, schema, errors),
+        }
+    }
+
+    fn check_object(
+        &self,
+        value: &Value,
+        path: Path,
+        schema: &'a SchemaObject,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        // As of draft 7, keywords next to `$ref` are ignored.
+        if let Some(reference) = &schema.reference {
+            if let Some(schema) = self.resolve(reference) {
+                self.check(value, path, schema, errors);
+            }
+            return;
+        }
+
+        let tagged;
+        let value = match value {
+            Value::Tagged(..) if expects(schema, InstanceType::Object) => {
+                tagged = as_mapping(value);
+                &tagged
+            }
+            _ => value,
+        };
+
+        if let Some(types) = &schema.instance_type {
+            if !single_or_vec(types)
+                .iter()
+                .any(|ty| is_instance(value, *ty))
+            {
+                errors.push(error(path, value, describe_types(types), actual(value)));
+                return;
+            }
+        }
+        if let Some(values) = &schema.enum_values {
+            if !values.iter().any(|json| json_eq(value, json)) {
+                let expected = describe_values(values);
+                errors.push(error(path, value, expected, actual(value)));
+                return;
+            }
+        }
+        if let Some(json) = &schema.const_value {
+            if !json_eq(value, json) {
+                let expected = describe_values(std::slice::from_ref(json));
+                errors.push(error(path, value, expected, actual(value)));
+                return;
+            }
+        }
+
+        if let Some(subschemas) = &schema.subschemas {
+            self.check_subschemas(value, path, subschemas, errors);
+        }
+        match value {
+            Value::Number(n, ..) => {
+                if let Some(number) = &schema.number {
+                    check_number(value, n, path, number, errors);
+                }
+            }
+            Value::String(s, ..) => {
+                if let Some(string) = &schema.string {
+                    self.check_string(value, s, path, string, errors);
+                }
+            }
+            Value::Sequence(sequence, ..) => {
+                if let Some(array) = &schema.array {
+                    self.check_array(value, sequence, path, array, errors);
+                }
+            }
+            Value::Mapping(mapping, ..) => {
+                if let Some(object) = &schema.object {
+                    self.check_mapping(value, mapping, path, object, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_subschemas(
+        &self,
+        value: &Value,
+        path: Path,
+        subschemas: &'a SubschemaValidation,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(all_of) = &subschemas.all_of {
+            for schema in all_of {
+                self.check(value, path, schema, errors);
+            }
+        }
+        if let Some(any_of) = &subschemas.any_of {
+            self.check_alternatives(value, path, any_of, false, errors);
+        }
+        if let Some(one_of) = &subschemas.one_of {
+            self.check_alternatives(value, path, one_of, true, errors);
+        }
+        if let Some(not) = &subschemas.not {
+            if self.is_valid(value, path, not) {
+                let expected = format!("anything but {}", self.describe(not));
+                errors.push(error(path, value, expected, actual(value)));
+            }
+        }
+        if let Some(if_schema) = &subschemas.if_schema {
+            let branch = if self.is_valid(value, path, if_schema) {
+                &subschemas.then_schema
+            } else {
+                &subschemas.else_schema
+            };
+            if let Some(schema) = branch {
+                self.check(value, path, schema, errors);
+            }
+        }
+    }
+
+    /// Checks `value` against `anyOf` or, if `exactly_one`, `oneOf`
+    /// alternatives.
+    fn check_alternatives(
+        &self,
+        value: &Value,
+        path: Path,
+        alternatives: &'a [Schema],
+        exactly_one: bool,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let mut failures = Vec::new();
+        for schema in alternatives {
+            let mut branch = Vec::new();
+            self.check(value, path, schema, &mut branch);
+            failures.push((schema, branch));
+        }
+        let matches = failures
+            .iter()
+            .filter(|(_, branch)| branch.is_empty())
+            .count();
+        if matches == 1 || (matches > 1 && !exactly_one) {
+            return;
+        }
+        if matches > 1 {
+            let actual = format!("{} matching {} of them", actual(value), matches);
+            errors.push(error(path, value, "exactly one alternative", actual));
+            return;
+        }
+
+        // Report the problems within the alternative that the value most
+        // looks like, or else that it looks like none of them.
+        let best = failures
+            .into_iter()
+            .filter(|(schema, _)| self.has_type_of(value, schema))
+            .min_by_key(|(_, branch)| branch.len());
+        match best {
+            Some((_, branch)) => errors.extend(branch),
+            None => {
+                let mut expected = Vec::new();
+                for schema in alternatives {
+                    self.describe_into(schema, &mut expected, 0);
+                }
+                errors.push(error(path, value, join(&expected, "or"), actual(value)));
+            }
+        }
+    }
+
+    fn check_string(
+        &self,
+        value: &Value,
+        s: &str,
+        path: Path,
+        string: &'a StringValidation,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let len = s.chars().count();
+        if let Some(min) = string.min_length {
+            if len < min as usize {
+                let expected = format!("a string of at least {} characters", min);
+                errors.push(error(path, value, expected, actual(value)));
+            }
+        }
+        if let Some(max) = string.max_length {
+            if len > max as usize {
+                let expected = format!("a string of at most {} characters", max);
+                errors.push(error(path, value, expected, actual(value)));
+            }
+        }
+        if let Some(pattern) = &string.pattern {
+            let mut patterns = self.patterns.borrow_mut();
+            let regex = patterns
+                .entry(pattern)
+                .or_insert_with(|| Regex::new(pattern).ok());
+            // A pattern that is not a valid regex cannot be checked.
+            if regex.as_ref().is_some_and(|regex| !regex.is_match(s)) {
+                let expected = format!("a string matching `{}`", pattern);
+                errors.push(error(path, value, expected, actual(value)));
+            }
+        }
+    }
+
+    fn check_array(
+        &self,
+        value: &Value,
+        sequence: &[Value],
+        path: Path,
+        array: &'a ArrayValidation,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(min) = array.min_items {
+            if sequence.len() < min as usize {
+                let expected = format!("a sequence of at least {} items", min);
+                let actual = format!("{} items", sequence.len());
+                errors.push(error(path, value, expected, actual));
+            }
+        }
+        if let Some(max) = array.max_items {
+            if sequence.len() > max as usize {
+                let expected = format!("a sequence of at most {} items", max);
+                let actual = format!("{} items", sequence.len());
+                errors.push(error(path, value, expected, actual));
+            }
+        }
+
+        for (index, element) in sequence.iter().enumerate() {
+            let path = Path::Seq {
+                parent: &path,
+                index,
+            };
+            let schema = match &array.items {
+                None => None,
+                Some(SingleOrVec::Single(schema)) => Some(&**schema),
+                Some(SingleOrVec::Vec(schemas)) => {
+                    schemas.get(index).or(array.additional_items.as_deref())
+                }
+            };
+            if let Some(schema) = schema {
+                self.check(element, path, schema, errors);
+            }
+            if array.unique_items == Some(true) && sequence[..index].contains(element) {
+                errors.push(error(path, element, "a unique item", actual(element)));
+            }
+        }
+
+        if let Some(contains) = &array.contains {
+            let found = sequence.iter().enumerate().any(|(index, element)| {
+                let path = Path::Seq {
+                    parent: &path,
+                    index,
+                };
+                self.is_valid(element, path, contains)
+            });
+            if !found {
+                let expected = format!("a sequence containing {}", self.describe(contains));
+                errors.push(error(path, value, expected, actual(value)));
+            }
+        }
+    }
+
+    fn check_mapping(
+        &self,
+        value: &Value,
+        mapping: &Mapping,
+        path: Path,
+        object: &'a ObjectValidation,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(min) = object.min_properties {
+            if mapping.len() < min as usize {
+                let expected = format!("a map of at least {} entries", min);
+                let actual = format!("{} entries", mapping.len());
+                errors.push(error(path, value, expected, actual));
+            }
+        }
+        if let Some(max) = object.max_properties {
+            if mapping.len() > max as usize {
+                let expected = format!("a map of at most {} entries", max);
+                let actual = format!("{} entries", mapping.len());
+                errors.push(error(path, value, expected, actual));
+            }
+        }
+
+        for (key, entry) in mapping {
+            let Some(name) = key.as_str() else {
+                self.check_key(key, Path::Unknown { parent: &path }, object, errors);
+                continue;
+            };
+            let path = Path::Map {
+                parent: &path,
+                key: name,
+            };
+            self.check_key(key, path, object, errors);
+
+            let mut known = false;
+            if let Some(schema) = object.properties.get(name) {
+                known = true;
+                self.check(entry, path, schema, errors);
+            }
+            for (pattern, schema) in &object.pattern_properties {
+                if self.is_match(pattern, name) {
+                    known = true;
+                    self.check(entry, path, schema, errors);
+                }
+            }
+            match object.additional_properties.as_deref() {
+                Some(Schema::Bool(false)) if !known => {
+                    let actual = format!("unknown key `{}`", name);
+                    errors.push(error(path, key, "a known key", actual));
+                }
+                Some(schema) if !known => self.check(entry, path, schema, errors),
+                _ => {}
+            }
+        }
+
+        for name in &object.required {
+            if mapping.get(name.as_str()).is_none() {
+                let path = Path::Map {
+                    parent: &path,
+                    key: name,
+                };
+                errors.push(error(path, value, "a value", "nothing"));
+            }
+        }
+    }
+
+    fn check_key(
+        &self,
+        key: &Value,
+        path: Path,
+        object: &'a ObjectValidation,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        match object.property_names.as_deref() {
+            Some(schema) => self.check(key, path, schema, errors),
+            // Only string keys can match properties.
+            None if key.as_str().is_none() && !object.properties.is_empty() => {
+                errors.push(error(path, key, "a string key", actual(key)));
+            }
+            None => {}
+        }
+    }
+
+    fn is_valid(&self, value: &Value, path: Path, schema: &'a Schema) -> bool {
+        let mut errors = Vec::new();
+        self.check(value, path, schema, &mut errors);
+        errors.is_empty()
+    }
+
+    fn is_match(&self, pattern: &'a str, s: &str) -> bool {
+        let mut patterns = self.patterns.borrow_mut();
+        let regex = patterns
+            .entry(pattern)
+            .or_insert_with(|| Regex::new(pattern).ok());
+        regex.as_ref().is_some_and(|regex| regex.is_match(s))
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&'a Schema> {
+        let name = reference.strip_prefix("#/definitions/")?;
+        self.root.definitions.get(name)
+    }
+
+    /// Whether `value` is of one of the types allowed by `schema`, so that
+    /// the problems found within it are more telling than its type.
+    fn has_type_of(&self, value: &Value, schema: &'a Schema) -> bool {
+        self.has_type_of_depth(value, schema, 0)
+    }
+
+    fn has_type_of_depth(&self, value: &Value, schema: &'a Schema, depth: usize) -> bool {
+        let schema = match schema {
+            Schema::Bool(allowed) => return *allowed,
+            Schema::Object(schema) => schema,
+        };
+        if depth > MAX_DEPTH {
+            return true;
+        }
+        if let Some(reference) = &schema.reference {
+            return self
+                .resolve(reference)
+                .is_none_or(|schema| self.has_type_of_depth(value, schema, depth + 1));
+        }
+        if let Some(types) = &schema.instance_type {
+            let value = match value {
+                Value::Tagged(..) => &as_mapping(value),
+                _ => value,
+            };
+            return single_or_vec(types)
+                .iter()
+                .any(|ty| is_instance(value, *ty));
+        }
+        match schema.subschemas.as_deref() {
+            Some(SubschemaValidation {
+                any_of: Some(alternatives),
+                ..
+            })
+            | Some(SubschemaValidation {
+                one_of: Some(alternatives),
+                ..
+            }) => alternatives
+                .iter()
+                .any(|schema| self.has_type_of_depth(value, schema, depth + 1)),
+            Some(SubschemaValidation {
+                all_of: Some(schemas),
+                ..
+            }) => schemas
+                .iter()
+                .all(|schema| self.has_type_of_depth(value, schema, depth + 1)),
+            _ => true,
+        }
+    }
+
+    /// A short description of the values allowed by `schema`.
+    fn describe(&self, schema: &'a Schema) -> String {
+        let mut alternatives = Vec::new();
+        self.describe_into(schema, &mut alternatives, 0);
+        join(&alternatives, "or")
+    }
+
+    /// Adds the descriptions of the kinds of values allowed by `schema` to
+    /// `alternatives`.
+    fn describe_into(&self, schema: &'a Schema, alternatives: &mut Vec<String>, depth: usize) {
+        let mut add = |description: String| {
+            if !alternatives.contains(&description) {
+                alternatives.push(description);
+            }
+        };
+        let schema = match schema {
+            Schema::Bool(true) => return add("anything".to_owned()),
+            Schema::Bool(false) => return add("nothing".to_owned()),
+            Schema::Object(schema) => schema,
+        };
+        if let Some(reference) = &schema.reference {
+            let name = reference.rsplit('/').next().unwrap_or(reference);
+            return match self.resolve(reference) {
+                Some(schema) if depth < MAX_DEPTH => {
+                    self.describe_into(schema, alternatives, depth + 1)
+                }
+                _ => add(format!("`{}`", name)),
+            };
+        }
+        if let Some(values) = &schema.enum_values {
+            return values.iter().map(describe_value).for_each(add);
+        }
+        if let Some(json) = &schema.const_value {
+            return add(describe_value(json));
+        }
+        if let Some(types) = &schema.instance_type {
+            return single_or_vec(types).iter().map(describe_type).for_each(add);
+        }
+        match schema.subschemas.as_deref() {
+            Some(SubschemaValidation {
+                any_of: Some(schemas),
+                ..
+            })
+            | Some(SubschemaValidation {
+                one_of: Some(schemas),
+                ..
+            }) => {
+                for schema in schemas {
+                    self.describe_into(schema, alternatives, depth + 1);
+                }
+            }
+            Some(SubschemaValidation {
+                all_of: Some(schemas),
+                ..
+            }) if schemas.len() == 1 => self.describe_into(&schemas[0], alternatives, depth + 1),
+            _ => add("a value matching the schema".to_owned()),
+        }
+    }
+}
+
+fn check_number(
+    value: &Value,
+    n: &Number,
+    path: Path,
+    number: &NumberValidation,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(n) = n.as_f64() else {
+        return;
+    };
+    let mut check = |ok: bool, expected: String| {
+        if !ok {
+            errors.push(error(path, value, expected, actual(value)));
+        }
+    };
+    if let Some(min) = number.minimum {
+        check(n >= min, format!("a number at least {}", min));
+    }
+    if let Some(min) = number.exclusive_minimum {
+        check(n > min, format!("a number greater than {}", min));
+    }
+    if let Some(max) = number.maximum {
+        check(n <= max, format!("a number at most {}", max));
+    }
+    if let Some(max) = number.exclusive_maximum {
+        check(n < max, format!("a number less than {}", max));
+    }
+    if let Some(multiple) = number.multiple_of {
+        check(
+            (n / multiple).fract() == 0.0,
+            format!("a multiple of {}", multiple),
+        );
+    }
+}
+
+fn error(
+    path: Path,
+    value: &Value,
+    expected: impl Into<String>,
+    actual: impl Into<String>,
+) -> ValidationError {
+    ValidationError {
+        path: path.to_owned_path(),
+        span: value.span().clone(),
+        expected: expected.into(),
+        actual: actual.into(),
+    }
+}
+
+/// A description of `value` in the words of serde's `Unexpected`.
+fn actual(value: &Value) -> String {
+    match value {
+        Value::Null(..) => "null".to_owned(),
+        Value::Bool(b, ..) => format!("boolean `{}`", b),
+        Value::Number(n, ..) if n.is_f64() => format!("floating point `{}`", n),
+        Value::Number(n, ..) => format!("integer `{}`", n),
+        Value::String(s, ..) => format!("string {:?}", s.as_str()),
+        Value::Sequence(..) => "sequence".to_owned(),
+        Value::Mapping(..) => "map".to_owned(),
+        Value::Tagged(tagged, ..) => format!("tagged value `{}`", tagged.tag),
+    }
+}
+
+fn is_instance(value: &Value, ty: InstanceType) -> bool {
+    match (ty, value) {
+        (InstanceType::Null, Value::Null(..))
+        | (InstanceType::Boolean, Value::Bool(..))
+        | (InstanceType::Number, Value::Number(..))
+        | (InstanceType::String, Value::String(..))
+        | (InstanceType::Array, Value::Sequence(..))
+        | (InstanceType::Object, Value::Mapping(..)) => true,
+        (InstanceType::Integer, Value::Number(n, ..)) => {
+            !n.is_f64()
+                || n.as_f64()
+                    .is_some_and(|n| n.is_finite() && n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn expects(schema: &SchemaObject, ty: InstanceType) -> bool {
+    schema
+        .instance_type
+        .as_ref()
+        .is_some_and(|types| single_or_vec(types).contains(&ty))
+}
+
+fn single_or_vec<T>(v: &SingleOrVec<T>) -> &[T] {
+    match v {
+        SingleOrVec::Single(t) => std::slice::from_ref(&**t),
+        SingleOrVec::Vec(v) => v,
+    }
+}
+
+/// Returns the tagged value `!Tag value` as the mapping `{Tag: value}`.
+fn as_mapping(value: &Value) -> Value {
+    let Value::Tagged(tagged, span) = value else {
+        return value.clone();
+    };
+    let mut mapping = Mapping::new();
+    let tag = tagged.tag.to_string();
+    let tag = tag.strip_prefix('!').unwrap_or(&tag);
+    mapping.insert(Value::string(tag.to_owned()), tagged.value.clone());
+    Value::Mapping(mapping, span.clone())
+}
+
+fn describe_types(types: &SingleOrVec<InstanceType>) -> String {
+    let names: Vec<String> = single_or_vec(types).iter().map(describe_type).collect();
+    join(&names, "or")
+}
+
+fn describe_type(ty: &InstanceType) -> String {
+    match ty {
+        InstanceType::Null => "null",
+        InstanceType::Boolean => "boolean",
+        InstanceType::Object => "map",
+        InstanceType::Array => "sequence",
+        InstanceType::Number => "number",
+        InstanceType::String => "string",
+        InstanceType::Integer => "integer",
+    }
+    .to_owned()
+}
+
+fn describe_values(values: &[serde_json::Value]) -> String {
+    let values: Vec<String> = values.iter().map(describe_value).collect();
+    join(&values, "or")
+}
+
+fn describe_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => format!("`{}`", s),
+        _ => format!("`{}`", value),
+    }
+}
+
+fn join<S: AsRef<str>>(items: &[S], conjunction: &str) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.as_ref().to_owned(),
+        [init @ .., last] => {
+            let init: Vec<&str> = init.iter().map(AsRef::as_ref).collect();
+            format!("{} {} {}", init.join(", "), conjunction, last.as_ref())
+        }
+    }
+}
+
+/// Whether `value` equals the JSON value `json`, as for `enum` and `const`.
+fn json_eq(value: &Value, json: &serde_json::Value) -> bool {
+    match (value, json) {
+        (Value::Null(..), serde_json::Value::Null) => true,
+        (Value::Bool(a, ..), serde_json::Value::Bool(b)) => a == b,
+        (Value::Number(a, ..), serde_json::Value::Number(b)) => {
+            if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                a == b
+            } else if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+                a == b
+            } else {
+                a.as_f64().is_some_and(|a| Some(a) == b.as_f64())
+            }
+        }
+        (Value::String(a, ..), serde_json::Value::String(b)) => a == b,
+        (Value::Sequence(a, ..), serde_json::Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (Value::Mapping(a, ..), serde_json::Value::Object(b)) => {
+            a.len() == b.len()
+                && b.iter()
+                    .all(|(key, b)| a.get(key.as_str()).is_some_and(|a| json_eq(a, b)))
+        }
+        (Value::Tagged(..), serde_json::Value::Object(_)) => json_eq(&as_mapping(value), json),
+        _ => false,
+    }
+}
@@ -3,7 +3,9 @@ use crate::path::{OwnedPath, Path};
 use crate::value::de::borrowed::ValueRefDeserializer;
 use crate::value::tagged::TagStringVisitor;
 use crate::value::TaggedValue;
-use crate::{error, number, spanned, Error, Sequence, Span, Value};
+use crate::{
+    binary, callback, error, number, spanned, trace, Error, Number, Sequence, Span, Value,
+};
 use serde::de::{
     self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, Error as _, Expected, MapAccess,
     SeqAccess, Unexpected, VariantAccess, Visitor,
@@ -12,21 +14,183 @@ use std::fmt;
 
 mod borrowed;
 mod owned;
+mod spanned_value;
 
 pub(crate) use borrowed::{MapRefDeserializer, SeqRefDeserializer};
 pub use owned::ValueDeserializer;
+pub use spanned_value::SpannedValueDeserializer;
 
 /// A type alias for the result of transforming a [Value] into another [Value].
 pub type TransformedResult =
     Result<Option<Value>, Box<dyn std::error::Error + 'static + Send + Sync>>;
 
 /// A callback type for handling duplicate keys during deserialization.
+///
+/// The callback receives the path of the duplicate entry, the duplicate key,
+/// and the key it duplicates. Both keys carry their source locations, so
+/// [`Value::span`] on each gives the position of the duplicate and of the
+/// first definition respectively.
 pub type DuplicateKeyCallback<'d> =
     &'d mut dyn for<'p, 'v> FnMut(Path<'p>, &'v Value, &'v Value) -> DuplicateKey;
 
 /// A callback type for handling unused keys during deserialization.
 pub type UnusedKeyCallback<'u> = &'u mut dyn for<'p, 'v> FnMut(Path<'p>, &'v Value, &'v Value);
 
+/// Accumulates the `(path, key, value)` triples reported for unused keys while
+/// deserializing a [Value].
+///
+/// ```
+/// use dbt_serde_yaml::value::UnusedKeyCollector;
+/// use dbt_serde_yaml::Value;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: String,
+/// }
+///
+/// let value: Value = dbt_serde_yaml::from_str("name: orders\nnmae: typo\n").unwrap();
+/// let mut unused = UnusedKeyCollector::new();
+/// let model: Model = value.into_typed_collecting(&mut unused, |_| Ok(None)).unwrap();
+///
+/// assert_eq!(model.name, "orders");
+/// let unused = unused.into_vec();
+/// assert_eq!(unused.len(), 1);
+/// assert_eq!(unused[0].0.to_string(), "nmae");
+/// assert_eq!(unused[0].1, "nmae");
+/// assert_eq!(unused[0].2, "typo");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UnusedKeyCollector {
+    keys: Vec<(OwnedPath, Value, Value)>,
+}
+
+impl UnusedKeyCollector {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an unused key callback that records every key it is called
+    /// with into this collector, for use with [Value::into_typed] and the
+    /// other entry points taking such a callback.
+    pub fn collect(&mut self) -> impl FnMut(Path<'_>, &Value, &Value) + '_ {
+        |path, key, value| {
+            self.keys
+                .push((path.to_owned_path(), key.clone(), value.clone()));
+        }
+    }
+
+    /// Returns the number of unused keys collected so far.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if no unused key has been collected.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns an iterator over the collected unused keys, in the order they
+    /// were reported.
+    pub fn iter(&self) -> std::slice::Iter<'_, (OwnedPath, Value, Value)> {
+        self.keys.iter()
+    }
+
+    /// Returns the collected unused keys, in the order they were reported.
+    pub fn into_vec(self) -> Vec<(OwnedPath, Value, Value)> {
+        self.keys
+    }
+}
+
+/// What to do with mapping keys that the target type does not use; see
+/// [Value::into_typed_with].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum UnusedKeys {
+    /// Ignore unused keys.
+    #[default]
+    Ignore,
+    /// Fail with an error naming the first unused key and its path, located
+    /// at the key's span, like `#[serde(deny_unknown_fields)]` but applied to
+    /// every struct and map in the target type.
+    Deny,
+}
+
+/// Which fields the field transformer is applied to; see
+/// [Value::into_typed_with_policy].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TransformPolicy {
+    /// Leave fields inside a [Verbatim](crate::Verbatim) value untransformed,
+    /// except those opted back in with [Transformed](crate::Transformed).
+    #[default]
+    RespectVerbatim,
+    /// Transform fields inside [Verbatim](crate::Verbatim) values too, as if
+    /// they were not wrapped, for example to fully render a value.
+    IncludeVerbatim,
+}
+
+/// Options for [Value::into_typed_with_options], and for the `from_value`
+/// constructor generated by [`YamlDeserialize`](crate::YamlDeserialize).
+///
+/// ```
+/// use dbt_serde_yaml::value::{FromValueOptions, UnusedKeys};
+/// use dbt_serde_yaml::Value;
+///
+/// let options = FromValueOptions::new()
+///     .unused_keys(UnusedKeys::Deny)
+///     .field_transformer(|v: &Value| Ok(v.as_str().map(|s| Value::from(s.to_uppercase()))));
+/// # let _ = options;
+/// ```
+#[derive(Default)]
+pub struct FromValueOptions<'f> {
+    unused_keys: UnusedKeys,
+    transform_policy: TransformPolicy,
+    field_transformer: Option<BoxedFieldTransformer<'f>>,
+}
+
+type BoxedFieldTransformer<'f> = Box<dyn for<'v> FnMut(&'v Value) -> TransformedResult + 'f>;
+
+impl<'f> FromValueOptions<'f> {
+    /// Creates options that ignore unused keys and transform no fields.
+    pub fn new() -> Self {
+        FromValueOptions::default()
+    }
+
+    /// Sets what to do with mapping keys that the target type does not use.
+    /// The default is [UnusedKeys::Ignore].
+    pub fn unused_keys(mut self, unused_keys: UnusedKeys) -> Self {
+        self.unused_keys = unused_keys;
+        self
+    }
+
+    /// Sets which fields the field transformer is applied to. The default is
+    /// [TransformPolicy::RespectVerbatim].
+    pub fn transform_policy(mut self, policy: TransformPolicy) -> Self {
+        self.transform_policy = policy;
+        self
+    }
+
+    /// Sets the field transformer, as passed to [Value::into_typed].
+    pub fn field_transformer<F>(mut self, field_transformer: F) -> Self
+    where
+        F: for<'v> FnMut(&'v Value) -> TransformedResult + 'f,
+    {
+        self.field_transformer = Some(Box::new(field_transformer));
+        self
+    }
+}
+
+impl fmt::Debug for FromValueOptions<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("FromValueOptions")
+            .field("unused_keys", &self.unused_keys)
+            .field("transform_policy", &self.transform_policy)
+            .field("field_transformer", &self.field_transformer.is_some())
+            .finish()
+    }
+}
+
 /// A transformer function for modifying field values during deserialization.
 pub type FieldTransformer<'f> = &'f mut dyn for<'v> FnMut(&'v Value) -> TransformedResult;
 
@@ -37,10 +201,54 @@ impl Value {
         F: FnMut(Path<'_>, &Self, &Self) -> DuplicateKey,
     {
         let de = crate::de::Deserializer::from_str(s);
-        spanned::set_marker(spanned::Marker::start());
-        let res = deserialize(de, duplicate_key_callback);
-        spanned::reset_marker();
-        res
+        let _marker = spanned::enter_marker(spanned::Marker::start());
+        deserialize_yaml(de, duplicate_key_callback)
+    }
+
+    /// Deserialize a [Value] from a string of YAML text, with a fallible
+    /// duplicate key callback.
+    ///
+    /// An error returned by the callback stops deserialization. It is
+    /// returned located at the duplicate key, and can be recovered with
+    /// [Error::into_external].
+    pub fn try_from_str<F>(s: &str, mut duplicate_key_callback: F) -> Result<Self, Error>
+    where
+        F: FnMut(
+            Path<'_>,
+            &Self,
+            &Self,
+        )
+            -> Result<DuplicateKey, Box<dyn std::error::Error + 'static + Send + Sync>>,
+    {
+        Value::from_str(s, |path, key, existing| {
+            duplicate_key_callback(path, key, existing).unwrap_or_else(|err| {
+                callback::fail(err);
+                DuplicateKey::Error
+            })
+        })
+    }
+
+    /// Deserialize a [Value] from a string of YAML text, parsed with the given
+    /// [ParseOptions](crate::ParseOptions).
+    pub fn from_str_with<F>(
+        s: &str,
+        options: crate::ParseOptions,
+        duplicate_key_callback: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(Path<'_>, &Self, &Self) -> DuplicateKey,
+    {
+        let de = crate::de::Deserializer::from_str_with(s, options);
+        Self::from_deserializer(de, duplicate_key_callback)
+    }
+
+    /// Deserialize a [Value] from a string of YAML text with the given
+    /// [Config](crate::Config), resolving duplicate keys by its policy.
+    pub fn from_str_with_config(s: &str, config: &crate::Config) -> Result<Self, Error> {
+        let _scope = config.enter();
+        let de = crate::de::Deserializer::from_str(s);
+        let policy = config.duplicate_keys;
+        Self::from_deserializer(de, |_, _, _| policy)
     }
 
     /// Deserialize a [Value] from an IO stream of YAML text.
@@ -50,10 +258,8 @@ impl Value {
         F: FnMut(Path<'_>, &Self, &Self) -> DuplicateKey,
     {
         let de = crate::de::Deserializer::from_reader(rdr);
-        spanned::set_marker(spanned::Marker::start());
-        let res = deserialize(de, duplicate_key_callback);
-        spanned::reset_marker();
-        res
+        let _marker = spanned::enter_marker(spanned::Marker::start());
+        deserialize_yaml(de, duplicate_key_callback)
     }
 
     /// Deserialize a [Value] from a byte slice of YAML text.
@@ -62,23 +268,93 @@ impl Value {
         F: FnMut(Path<'_>, &Self, &Self) -> DuplicateKey,
     {
         let de = crate::de::Deserializer::from_slice(s);
-        spanned::set_marker(spanned::Marker::start());
-        let res = deserialize(de, duplicate_key_callback);
-        spanned::reset_marker();
-        res
+        let _marker = spanned::enter_marker(spanned::Marker::start());
+        deserialize_yaml(de, duplicate_key_callback)
+    }
+
+    /// Deserialize a [Value] from a YAML [Deserializer](crate::Deserializer),
+    /// for example one using the strict YAML 1.2 core schema.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::{Deserializer, ScalarSchema, Value};
+    ///
+    /// let de = Deserializer::from_str("port: 08080").with_scalar_schema(ScalarSchema::Core);
+    /// let value = Value::from_deserializer(de, |_, _, _| DuplicateKey::Error).unwrap();
+    /// assert_eq!(value["port"], Value::from(8080));
+    /// ```
+    pub fn from_deserializer<F>(
+        de: crate::de::Deserializer<'_>,
+        duplicate_key_callback: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(Path<'_>, &Self, &Self) -> DuplicateKey,
+    {
+        let _marker = spanned::enter_marker(spanned::Marker::start());
+        deserialize_yaml(de, duplicate_key_callback)
     }
 
     /// Deserialize a [Value] into an instance of some [Deserialize] type `T`.
+    ///
+    /// The field transformer is applied according to the
+    /// [`transform_policy`](crate::Config::transform_policy) of the thread's
+    /// current config.
     pub fn into_typed<'de, T, U, F>(
+        self,
+        unused_key_callback: U,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(Path<'_>, &Value, &Value),
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        self.into_typed_with_policy(
+            unused_key_callback,
+            field_transformer,
+            crate::config::transform_policy(),
+        )
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// applying the field transformer according to `policy` for this call
+    /// only.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::TransformPolicy;
+    /// use dbt_serde_yaml::{Value, Verbatim};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Model {
+    ///     sql: Verbatim<String>,
+    /// }
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("sql: '{{ x }}'").unwrap();
+    /// let render = |v: &Value| Ok(v.as_str().map(|_| Value::from("rendered")));
+    /// let model: Model = value
+    ///     .into_typed_with_policy(|_, _, _| {}, render, TransformPolicy::IncludeVerbatim)
+    ///     .unwrap();
+    /// assert_eq!(*model.sql, "rendered");
+    /// ```
+    pub fn into_typed_with_policy<'de, T, U, F>(
         self,
         mut unused_key_callback: U,
         mut field_transformer: F,
+        policy: TransformPolicy,
     ) -> Result<T, Error>
     where
         T: Deserialize<'de>,
         U: FnMut(Path<'_>, &Value, &Value),
         F: for<'v> FnMut(&'v Value) -> TransformedResult,
     {
+        let _scope = crate::config::enter_changed(|config| config.transform_policy = policy);
+        let _marker = spanned::enter_marker(self.span().start);
+        let mut unused_keys = 0;
+        let mut unused_key_callback = |path: Path<'_>, key: &Value, value: &Value| {
+            unused_keys += 1;
+            unused_key_callback(path, key, value)
+        };
         let de = ValueDeserializer::new_with(
             self,
             Path::Root,
@@ -86,21 +362,310 @@ impl Value {
             Some(&mut field_transformer),
         );
 
-        T::deserialize(de)
+        let res = T::deserialize(de);
+        trace::callback_count("unused_key", unused_keys);
+        res
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// recording unused keys into `collector`.
+    ///
+    /// Keys reported before a failure are kept in `collector` even if
+    /// deserialization fails.
+    pub fn into_typed_collecting<'de, T, F>(
+        self,
+        collector: &mut UnusedKeyCollector,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        self.into_typed(collector.collect(), field_transformer)
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// with a fallible unused key callback.
+    ///
+    /// An error returned by the callback stops deserialization. It is
+    /// returned located at the unused key's value, with the key's path, and
+    /// can be recovered with [Error::into_external].
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct Model {
+    ///     name: String,
+    /// }
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("name: orders\na: 1\nb: 2\n").unwrap();
+    /// let mut budget = 1;
+    /// let err = value
+    ///     .try_into_typed::<Model, _, _>(
+    ///         |_, _, _| {
+    ///             if budget == 0 {
+    ///                 return Err("too many unused keys".into());
+    ///             }
+    ///             budget -= 1;
+    ///             Ok(())
+    ///         },
+    ///         |_| Ok(None),
+    ///     )
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "b: too many unused keys at line 3 column 4");
+    /// ```
+    pub fn try_into_typed<'de, T, U, F>(
+        self,
+        mut unused_key_callback: U,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(
+            Path<'_>,
+            &Value,
+            &Value,
+        ) -> Result<(), Box<dyn std::error::Error + 'static + Send + Sync>>,
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        self.into_typed(
+            |path, key, value| {
+                if let Err(err) = unused_key_callback(path, key, value) {
+                    callback::fail(err);
+                }
+            },
+            field_transformer,
+        )
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// handling unused keys according to `unused_keys`.
+    ///
+    /// With [UnusedKeys::Deny], an unused key found before deserialization
+    /// fails is reported in preference to the failure, since a misspelled key
+    /// is the usual cause of e.g. a missing field.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::UnusedKeys;
+    /// use dbt_serde_yaml::Value;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct Model {
+    ///     name: String,
+    /// }
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("name: orders\nnmae: typo\n").unwrap();
+    /// let err = value
+    ///     .into_typed_with::<Model, _>(UnusedKeys::Deny, |_| Ok(None))
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "nmae: unknown field `nmae` at line 2 column 1");
+    /// ```
+    pub fn into_typed_with<'de, T, F>(
+        self,
+        unused_keys: UnusedKeys,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        let mut first_unused = None;
+        let res = self.into_typed(
+            deny_unused(unused_keys, &mut first_unused),
+            field_transformer,
+        );
+        check_unused(res, first_unused)
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// with the unused key policy and field transformer given in `options`.
+    ///
+    /// All the problems found are returned: with [UnusedKeys::Deny], an error
+    /// for each unused key, followed by the error deserialization failed
+    /// with, if any.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::{FromValueOptions, UnusedKeys};
+    /// use dbt_serde_yaml::Value;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct Model {
+    ///     name: String,
+    ///     version: u32,
+    /// }
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("nmae: orders\nvresion: 2\n").unwrap();
+    /// let options = FromValueOptions::new().unused_keys(UnusedKeys::Deny);
+    /// let errors = value
+    ///     .into_typed_with_options::<Model>(options)
+    ///     .unwrap_err();
+    /// let messages: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+    /// assert_eq!(
+    ///     messages,
+    ///     [
+    ///         "nmae: unknown field `nmae` at line 1 column 1",
+    ///         "vresion: unknown field `vresion` at line 2 column 1",
+    ///         "missing field `name` at line 1 column 1",
+    ///     ],
+    /// );
+    /// ```
+    pub fn into_typed_with_options<'de, T>(
+        self,
+        options: FromValueOptions<'_>,
+    ) -> Result<T, Vec<Error>>
+    where
+        T: Deserialize<'de>,
+    {
+        let FromValueOptions {
+            unused_keys,
+            transform_policy,
+            mut field_transformer,
+        } = options;
+        let mut errors = Vec::new();
+        let res = self.into_typed_with_policy(
+            |path, key, _| {
+                if unused_keys == UnusedKeys::Deny {
+                    errors.push(error::unknown_field(key, &path));
+                }
+            },
+            |v| match &mut field_transformer {
+                Some(transformer) => transformer(v),
+                None => Ok(None),
+            },
+            transform_policy,
+        );
+        match res {
+            Ok(value) if errors.is_empty() => Ok(value),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.push(err);
+                Err(errors)
+            }
+        }
+    }
+
+    /// Deserialize each element of a [Value::Sequence] into an instance of
+    /// some [Deserialize] type `T`.
+    ///
+    /// Elements are deserialized independently, so an element that fails to
+    /// deserialize does not prevent the remaining elements from being
+    /// deserialized. Each element's error carries that element's span. An
+    /// error is returned only if the value is not a sequence; see
+    /// [Sequence::into_typed_elements].
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// let value: Value = dbt_serde_yaml::from_str("[1, two, 3]").unwrap();
+    /// let elements = value
+    ///     .into_typed_elements::<u32, _, _>(|_, _, _| {}, |_| Ok(None))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(elements[0].as_ref().unwrap(), &1);
+    /// assert!(elements[1].is_err());
+    /// assert_eq!(elements[2].as_ref().unwrap(), &3);
+    /// ```
+    pub fn into_typed_elements<'de, T, U, F>(
+        self,
+        unused_key_callback: U,
+        field_transformer: F,
+    ) -> Result<Vec<Result<T, Error>>, Error>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(Path<'_>, &Value, &Value),
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        match self {
+            Value::Sequence(sequence, ..) => {
+                Ok(sequence.into_typed_elements(unused_key_callback, field_transformer))
+            }
+            other => Err(other.invalid_type(&"a YAML sequence")),
+        }
     }
 
     /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
     /// without consuming the [Value].
     pub fn to_typed<'de, T, U, F>(
+        &'de self,
+        unused_key_callback: U,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(Path<'_>, &Value, &Value),
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        self.to_typed_with_policy(
+            unused_key_callback,
+            field_transformer,
+            crate::config::transform_policy(),
+        )
+    }
+
+    /// Deserialize the value at `path` relative to this [Value], looked up as
+    /// with [Value::get_path], into an instance of some [Deserialize] type
+    /// `T`, without consuming the [Value].
+    ///
+    /// Errors report their [path](Error::path) from this [Value], `path`
+    /// included, as if the whole [Value] had been deserialized.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::OwnedPath;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let value: Value = dbt_serde_yaml::from_str("models: [{name: orders, version: two}]")?;
+    ///
+    /// let path: OwnedPath = "models[0].name".parse()?;
+    /// assert_eq!(value.get_typed_at::<String>(path.as_path())?, "orders");
+    ///
+    /// let path: OwnedPath = "models[0].version".parse()?;
+    /// let err = value.get_typed_at::<u32>(path.as_path()).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "invalid type: string \"two\", expected u32 at line 1 column 34",
+    /// );
+    /// assert_eq!(err.path().unwrap().to_string(), "models[0].version");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_typed_at<'de, T>(&'de self, path: &Path) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let value = self
+            .get_path(path)
+            .ok_or_else(|| error::new(error::ErrorImpl::NoValueAtPath(path.to_string())))?;
+        let _marker = spanned::enter_marker(value.span().start);
+        T::deserialize(ValueRefDeserializer::new_with(value, *path, None, None))
+            .map_err(|err| error::set_location(err, value.span().clone(), path))
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// without consuming the [Value], applying the field transformer
+    /// according to `policy` for this call only; see
+    /// [Value::into_typed_with_policy].
+    pub fn to_typed_with_policy<'de, T, U, F>(
         &'de self,
         mut unused_key_callback: U,
         mut field_transformer: F,
+        policy: TransformPolicy,
     ) -> Result<T, Error>
     where
         T: Deserialize<'de>,
         U: FnMut(Path<'_>, &Value, &Value),
         F: for<'v> FnMut(&'v Value) -> TransformedResult,
     {
+        let _scope = crate::config::enter_changed(|config| config.transform_policy = policy);
+        let _marker = spanned::enter_marker(self.span().start);
+        let mut unused_keys = 0;
+        let mut unused_key_callback = |path: Path<'_>, key: &Value, value: &Value| {
+            unused_keys += 1;
+            unused_key_callback(path, key, value)
+        };
         let de = ValueRefDeserializer::new_with(
             self,
             Path::Root,
@@ -108,7 +673,107 @@ impl Value {
             Some(&mut field_transformer),
         );
 
-        T::deserialize(de)
+        let res = T::deserialize(de);
+        trace::callback_count("unused_key", unused_keys);
+        res
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// without consuming the [Value], with a fallible unused key callback;
+    /// see [Value::try_into_typed].
+    pub fn try_to_typed<'de, T, U, F>(
+        &'de self,
+        mut unused_key_callback: U,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(
+            Path<'_>,
+            &Value,
+            &Value,
+        ) -> Result<(), Box<dyn std::error::Error + 'static + Send + Sync>>,
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        self.to_typed(
+            |path, key, value| {
+                if let Err(err) = unused_key_callback(path, key, value) {
+                    callback::fail(err);
+                }
+            },
+            field_transformer,
+        )
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// without consuming the [Value], handling unused keys according to
+    /// `unused_keys`; see [Value::into_typed_with].
+    pub fn to_typed_with<'de, T, F>(
+        &'de self,
+        unused_keys: UnusedKeys,
+        field_transformer: F,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        let mut first_unused = None;
+        let res = self.to_typed(
+            deny_unused(unused_keys, &mut first_unused),
+            field_transformer,
+        );
+        check_unused(res, first_unused)
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// ignoring unused keys and without transforming any fields.
+    ///
+    /// This is [Value::into_typed] with no-op callbacks.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// # use std::collections::BTreeMap;
+    /// let value: Value = dbt_serde_yaml::from_str("a: 1\nb: 2").unwrap();
+    /// let map: BTreeMap<String, u32> = value.into_typed_default().unwrap();
+    /// assert_eq!(map["b"], 2);
+    /// ```
+    pub fn into_typed_default<'de, T>(self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.into_typed(|_, _, _| {}, |_| Ok(None))
+    }
+
+    /// Deserialize a [Value] into an instance of some [Deserialize] type `T`,
+    /// without consuming the [Value], ignoring unused keys and without
+    /// transforming any fields.
+    ///
+    /// This is [Value::to_typed] with no-op callbacks.
+    pub fn to_typed_default<'de, T>(&'de self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.to_typed(|_, _, _| {}, |_| Ok(None))
+    }
+}
+
+/// Returns an unused key callback that records the first unused key if
+/// `unused_keys` is [UnusedKeys::Deny].
+fn deny_unused(
+    unused_keys: UnusedKeys,
+    first_unused: &mut Option<Error>,
+) -> impl FnMut(Path<'_>, &Value, &Value) + '_ {
+    move |path, key, _| {
+        if unused_keys == UnusedKeys::Deny && first_unused.is_none() {
+            *first_unused = Some(error::unknown_field(key, &path));
+        }
+    }
+}
+
+fn check_unused<T>(res: Result<T, Error>, first_unused: Option<Error>) -> Result<T, Error> {
+    match first_unused {
+        Some(err) => Err(err),
+        None => res,
     }
 }
 
@@ -152,6 +817,26 @@ impl<'de> serde::de::Visitor<'de> for ValueVisitor<'_, '_> {
         Ok(Value::number(f.into()))
     }
 
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_i128<E>(self, i: i128) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Value::number(crate::Number::big(
+            itoa::Buffer::new().format(i),
+        )))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_u128<E>(self, u: u128) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Value::number(crate::Number::big(
+            itoa::Buffer::new().format(u),
+        )))
+    }
+
     fn visit_str<E>(self, s: &str) -> Result<Value, E>
     where
         E: serde::de::Error,
@@ -210,6 +895,10 @@ impl<'de> serde::de::Visitor<'de> for ValueVisitor<'_, '_> {
             path: self.path,
         };
         let mapping = de.deserialize_map(visitor)?;
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(number) = crate::number::from_token_mapping(&mapping) {
+            return Ok(Value::number(number));
+        }
         Ok(Value::mapping(mapping))
     }
 
@@ -270,23 +959,31 @@ impl<'de> serde::de::Visitor<'de> for SequenceVisitor<'_, '_> {
             values.push(value);
         }
 
-        Ok(values)
+        Ok(values.into())
     }
 }
 
-fn deserialize<'de, D, F>(deserializer: D, mut duplicate_key_callback: F) -> Result<Value, D::Error>
+fn deserialize<'de, D>(deserializer: D, policy: DuplicateKeyPolicy) -> Result<Value, D::Error>
 where
     D: serde::Deserializer<'de>,
-    F: FnMut(Path<'_>, &Value, &Value) -> DuplicateKey,
 {
     let start = spanned::get_marker();
-    set_is_deserializing_value();
+    let mut duplicate_keys = 0;
+    let deserializing = DeserializingValue::start(policy);
     let res = deserializer.deserialize_any(ValueVisitor {
-        callback: &mut duplicate_key_callback,
+        callback: &mut |path, key, value| {
+            duplicate_keys += 1;
+            policy(path, key, value)
+        },
         path: Path::Root,
     });
     let maybe_state = unsafe { load_deserializer_state() };
-    reset_is_deserializing_value();
+    let built = deserializing.take_built();
+    drop(deserializing);
+    if let Some((_, built_duplicate_keys)) = &built {
+        duplicate_keys += built_duplicate_keys;
+    }
+    trace::callback_count("duplicate_key", duplicate_keys);
 
     // Fast path: if the deserializer has returned a value through the side
     // channel, then we use it and ignore the result of the deserializer.
@@ -295,6 +992,7 @@ where
     }
 
     let val = res?;
+    let val = built.map_or(val, |(value, _)| value);
     let span = Span::from(start..spanned::get_marker());
 
     #[cfg(feature = "filename")]
@@ -303,12 +1001,36 @@ where
     Ok(val.with_span(span))
 }
 
+/// Deserializes a [Value] from the YAML deserializer `de`, which builds it
+/// itself if its options allow.
+fn deserialize_yaml<F>(
+    de: crate::de::Deserializer<'_>,
+    mut duplicate_key_callback: F,
+) -> Result<Value, Error>
+where
+    F: FnMut(Path<'_>, &Value, &Value) -> DuplicateKey,
+{
+    let start = spanned::get_marker();
+    let mut duplicate_keys = 0;
+    let res = de.deserialize_value(&mut |path, key, value| {
+        duplicate_keys += 1;
+        duplicate_key_callback(path, key, value)
+    });
+    trace::callback_count("duplicate_key", duplicate_keys);
+    let span = Span::from(start..spanned::get_marker());
+
+    #[cfg(feature = "filename")]
+    let span = span.maybe_capture_filename();
+
+    Ok(res?.with_span(span))
+}
+
 impl<'de> Deserialize<'de> for Value {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserialize(deserializer, |_, _, _| DuplicateKey::Error)
+        deserialize(deserializer, |_, _, _| crate::config::duplicate_keys())
     }
 }
 
@@ -330,6 +1052,63 @@ macro_rules! maybe_why_not {
 }
 pub(crate) use maybe_why_not;
 
+/// Deserializes the target of a [ShouldBe](crate::ShouldBe) with `attempt`,
+/// holding back the unused keys it reports until it succeeds, so that a
+/// failed attempt leaves no trace in `unused_key_callback`.
+fn attempt_should_be<R>(
+    unused_key_callback: Option<UnusedKeyCallback<'_>>,
+    attempt: impl FnOnce(Option<UnusedKeyCallback<'_>>) -> Result<R, Error>,
+) -> Result<R, Error> {
+    let Some(callback) = unused_key_callback else {
+        return attempt(None);
+    };
+    let mut unused_keys = Vec::new();
+    let res = attempt(Some(&mut |path: Path<'_>, key: &Value, value: &Value| {
+        unused_keys.push((path.to_owned_path(), key.clone(), value.clone()));
+    }))?;
+    for (path, key, value) in &unused_keys {
+        let path = *path.as_path();
+        callback::guard(&path, value.span(), || callback(path, key, value))?;
+    }
+    Ok(res)
+}
+
+/// Fails if `string` was [elided](super::YamlString::is_elided) while parsing,
+/// since there is nothing left to deserialize it from.
+#[cfg(feature = "large_scalars")]
+fn check_not_elided(string: &super::YamlString) -> Result<(), Error> {
+    if string.is_elided() {
+        return Err(Error::custom(
+            "cannot deserialize a string elided while parsing",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "large_scalars"))]
+#[inline]
+fn check_not_elided(_string: &super::YamlString) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Reports `value`, at `path`, to the unused key callback if it is thrown away
+/// by `deserialize_ignored_any` and the thread's config asks for it; see
+/// [`Config::report_ignored_values`](crate::Config::report_ignored_values).
+fn report_ignored(
+    path: Path<'_>,
+    value: &Value,
+    unused_key_callback: &mut Option<UnusedKeyCallback<'_>>,
+) -> Result<(), Error> {
+    let (Path::Map { key, .. }, Some(callback)) = (path, unused_key_callback) else {
+        return Ok(());
+    };
+    if !crate::config::report_ignored_values() {
+        return Ok(());
+    }
+    let key = Value::string(key.to_owned());
+    callback::guard(&path, value.span(), || callback(path, &key, value))
+}
+
 impl Value {
     fn deserialize_number<'de, V>(&self, visitor: V) -> Result<V::Value, Error>
     where
@@ -348,7 +1127,7 @@ impl Value {
     }
 
     #[cold]
-    fn invalid_type(&self, exp: &dyn Expected) -> Error {
+    pub(crate) fn invalid_type(&self, exp: &dyn Expected) -> Error {
         error::set_span(
             de::Error::invalid_type(self.unexpected(), exp),
             self.span().clone(),
@@ -370,6 +1149,86 @@ impl Value {
     }
 }
 
+/// Reads a YAML `!!set`, `!!omap`, `!!pairs` or `!!binary` as a sequence: the
+/// keys of a set, all of whose values are null, the entries of an ordered map
+/// or pairs as key-value sequences, which deserialize into 2-tuples, or the
+/// decoded bytes of binary data as integers.
+fn tagged_collection(value: &Value) -> Option<Sequence> {
+    let Value::Tagged(tagged, ..) = value else {
+        return None;
+    };
+    if let Some(bytes) = tagged_binary(value) {
+        let bytes = bytes.ok()?;
+        return Some(
+            bytes
+                .into_iter()
+                .map(|b| Value::number(Number::from(b)))
+                .collect(),
+        );
+    }
+    match &tagged.value {
+        Value::Mapping(mapping, ..) if tagged.tag == "!!set" => mapping
+            .values()
+            .all(Value::is_null)
+            .then(|| mapping.keys().cloned().collect()),
+        Value::Sequence(entries, ..) if tagged.tag == "!!omap" || tagged.tag == "!!pairs" => {
+            let pair = |entry: &Value| match entry.untag_ref() {
+                Value::Mapping(mapping, ..) if mapping.len() == 1 => Value::Sequence(
+                    mapping
+                        .iter()
+                        .flat_map(|(key, value)| [key.clone(), value.clone()])
+                        .collect(),
+                    entry.span().clone(),
+                ),
+                _ => entry.clone(),
+            };
+            Some(entries.iter().map(pair).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a YAML `!!binary` scalar, or returns `None` if `value` is not one.
+fn tagged_binary(value: &Value) -> Option<Result<Vec<u8>, Error>> {
+    let Value::Tagged(tagged, ..) = value else {
+        return None;
+    };
+    let text = match &tagged.value {
+        Value::String(..) if tagged.tag == "!!binary" => tagged.value.as_str()?,
+        _ => return None,
+    };
+    Some(
+        binary::decode(text.as_bytes()).ok_or_else(|| {
+            Error::invalid_value(Unexpected::Str(text), &"base64-encoded binary data")
+        }),
+    )
+}
+
+/// Takes the entries of `rest` that the current config's flatten router pins
+/// to a flatten field other than the first out of it, and returns them by
+/// field. Returns no fields if there is no router or fewer than two flatten
+/// fields.
+fn route_flattened<K, V>(rest: &mut Vec<(K, V)>, fields: &[&'static str]) -> Vec<Vec<(K, V)>>
+where
+    K: std::borrow::Borrow<Value>,
+    V: std::borrow::Borrow<Value>,
+{
+    let router = match crate::config::flatten_router() {
+        Some(router) if fields.len() > 1 => router,
+        _ => return Vec::new(),
+    };
+    let mut routed: Vec<Vec<(K, V)>> = fields.iter().map(|_| Vec::new()).collect();
+    let mut unrouted = Vec::with_capacity(rest.len());
+    for (key, value) in rest.drain(..) {
+        match router(key.borrow(), value.borrow(), fields) {
+            Some(index) if index > 0 && index < fields.len() => routed[index].push((key, value)),
+            _ => unrouted.push((key, value)),
+        }
+    }
+    *rest = unrouted;
+    routed
+}
+
 #[inline]
 fn should_short_circuit_any(has_transformer: bool) -> bool {
     if !is_deserializing_value_then_reset() {
@@ -382,20 +1241,72 @@ fn should_short_circuit_any(has_transformer: bool) -> bool {
 
 #[inline]
 fn is_deserializing_value_then_reset() -> bool {
-    clear_deserializer_state();
-    private::IS_DESERIALIZING_VALUE.with(|cell| cell.replace(false))
+    take_deserializing_value().is_some()
 }
 
+/// Takes the duplicate key policy of the [Value] being deserialized, if the
+/// caller is the deserializer it was passed to. The caller may then build the
+/// value itself and hand it over with [save_built_value].
+///
+/// This is how the YAML deserializer constructs deeply nested values without
+/// recursion; see
+/// [`ParseOptions::iterative_values`](crate::ParseOptions::iterative_values).
 #[inline]
-fn set_is_deserializing_value() {
+pub(crate) fn take_deserializing_value() -> Option<DuplicateKeyPolicy> {
     clear_deserializer_state();
-    private::IS_DESERIALIZING_VALUE.with(|cell| cell.set(true));
+    match private::DESERIALIZING.with(std::cell::Cell::take) {
+        Deserializing::Value(policy) => Some(policy),
+        _ => None,
+    }
 }
 
 #[inline]
-fn reset_is_deserializing_value() {
+pub(crate) fn reset_is_deserializing_value() {
     clear_deserializer_state();
-    private::IS_DESERIALIZING_VALUE.with(|cell| cell.set(false));
+    private::DESERIALIZING.with(|cell| cell.set(Deserializing::Other));
+}
+
+/// What is being deserialized, as far as the deserializer it is passed to is
+/// concerned.
+#[derive(Default)]
+enum Deserializing {
+    #[default]
+    Other,
+    /// A [Value], with this policy for duplicate keys.
+    Value(DuplicateKeyPolicy),
+    /// A [Value] that the deserializer has built itself, resolving this many
+    /// duplicate keys.
+    Built(Value, usize),
+}
+
+/// Marks a [Value] as being deserialized with a duplicate key policy until
+/// dropped, then restores whatever was marked before, even on panic.
+struct DeserializingValue(Deserializing);
+
+impl DeserializingValue {
+    fn start(policy: DuplicateKeyPolicy) -> Self {
+        clear_deserializer_state();
+        let previous =
+            private::DESERIALIZING.with(|cell| cell.replace(Deserializing::Value(policy)));
+        DeserializingValue(previous)
+    }
+
+    /// Takes the value handed over with [save_built_value], if any, along
+    /// with the number of duplicate keys it resolved.
+    fn take_built(&self) -> Option<(Value, usize)> {
+        match private::DESERIALIZING.with(std::cell::Cell::take) {
+            Deserializing::Built(value, duplicate_keys) => Some((value, duplicate_keys)),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for DeserializingValue {
+    fn drop(&mut self) {
+        clear_deserializer_state();
+        let previous = std::mem::take(&mut self.0);
+        private::DESERIALIZING.with(|cell| cell.set(previous));
+    }
 }
 
 #[inline]
@@ -428,6 +1339,18 @@ unsafe fn save_deserializer_state<'u, 'f>(
     });
 }
 
+/// A duplicate key callback that borrows nothing, so that it can be passed
+/// on to the deserializer through [take_deserializing_value].
+pub(crate) type DuplicateKeyPolicy = for<'p, 'v> fn(Path<'p>, &'v Value, &'v Value) -> DuplicateKey;
+
+/// Hands over a value built by the deserializer that took the policy from
+/// [take_deserializing_value], which resolved `duplicate_keys` duplicate
+/// keys. Whatever the deserializer then returns is ignored in favor of the
+/// value, unless it is an error.
+pub(crate) fn save_built_value(value: Value, duplicate_keys: usize) {
+    private::DESERIALIZING.with(|cell| cell.set(Deserializing::Built(value, duplicate_keys)));
+}
+
 /// Consumes a [Deserializer] and converts it into a [DeserializerState], which
 /// can be used to construct reusable deserializers for deserializing untagged
 /// enum variants.
@@ -437,7 +1360,7 @@ pub fn extract_reusable_deserializer_state<'de, D>(
 where
     D: Deserializer<'de>,
 {
-    set_is_deserializing_value();
+    let deserializing = DeserializingValue::start(|_, _, _| DuplicateKey::Error);
     // Also disable field transformation for this part:
     let _g = crate::verbatim::with_should_not_transform_any();
     let res = deserializer.deserialize_any(ValueVisitor {
@@ -445,12 +1368,14 @@ where
         path: Path::Root,
     });
     let maybe_state = unsafe { load_deserializer_state() };
-    reset_is_deserializing_value();
+    let built = deserializing.take_built();
+    drop(deserializing);
 
     if let Some(state) = maybe_state {
         Ok(state)
     } else {
         let val = res?;
+        let val = built.map_or(val, |(value, _)| value);
         Ok(DeserializerState::new(val, OwnedPath::Root, None, None))
     }
 }
@@ -474,7 +1399,10 @@ where
                 )));
             };
             if let Some(transformer) = &mut state.field_transformer {
-                if let Some(transformed) = transformer(&tag)
+                let transformed =
+                    crate::callback::guard(&Path::Root, tag.span(), || transformer(&tag))
+                        .map_err(D::Error::custom)?;
+                if let Some(transformed) = transformed
                     .map_err(|e| D::Error::custom(format!("Failed to transform tag: {e}")))?
                 {
                     tag = transformed;
@@ -545,6 +1473,11 @@ impl DeserializerState {
         )
     }
 
+    /// Returns the span of the captured value.
+    pub fn span(&self) -> &Span {
+        self.value.span()
+    }
+
     /// Extracts the unused key callback from the state, if any.
     pub fn take_unused_key_callback(&mut self) -> Option<UnusedKeyCallback<'static>> {
         self.unused_key_callback.take()
@@ -574,7 +1507,7 @@ mod private {
     use crate::{path::OwnedPath, Value};
 
     thread_local! {
-        pub static IS_DESERIALIZING_VALUE: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+        pub static DESERIALIZING: std::cell::Cell<super::Deserializing> = const { std::cell::Cell::new(super::Deserializing::Other) };
 
         pub static THE_VALUE: std::cell::Cell<Option<Value>> = const { std::cell::Cell::new(None) };
         pub static THE_PATH: std::cell::Cell<Option<OwnedPath>> = const { std::cell::Cell::new(None) };
@@ -9,10 +9,13 @@ use serde::{
 };
 
 use crate::{
-    error,
+    error, trace,
     value::{
-        de::{reset_is_deserializing_value, save_deserializer_state, ValueDeserializer},
-        tagged,
+        de::{
+            check_not_elided, report_ignored, reset_is_deserializing_value,
+            save_deserializer_state, ValueDeserializer,
+        },
+        tagged, variant,
     },
     Error, Mapping, Path, Sequence, Value,
 };
@@ -56,6 +59,9 @@ where
     V: Visitor<'de>,
 {
     let len = mapping.len();
+    let _raw_entries = field_transformer
+        .is_some()
+        .then(|| crate::verbatim::keep_raw_entries(&current_path));
     let mut deserializer = MapRefDeserializer::new_with(
         mapping,
         current_path,
@@ -394,7 +400,12 @@ macro_rules! maybe_transform_and_forward_to_value_deserializer {
     ($self:expr, $method:ident, $($args:expr),*) => {
         if let Some(transformer) = &mut $self.field_transformer {
             if !$self.is_transformed && crate::verbatim::should_transform_any() {
-                if let Some(v) = transformer(&$self.value)? {
+                let (path, span) = (&$self.path, $self.value.span());
+                let transformed =
+                    crate::callback::guard(path, span, || transformer(&$self.value))??;
+                crate::trace::transformer_invoked(&$self.path, transformed.is_some());
+                if let Some(v) = transformed {
+                    crate::verbatim::keep_raw(&$self.path, || $self.value.clone());
                     return ValueDeserializer::new_with_transformed(
                         v,
                         $self.path,
@@ -408,6 +419,39 @@ macro_rules! maybe_transform_and_forward_to_value_deserializer {
     };
 }
 
+/// If a [ShouldBe](crate::ShouldBe) whose target may report unused keys is
+/// being deserialized, deserializes the target through a deserializer that
+/// holds them back until it succeeds.
+macro_rules! maybe_expecting_should_be {
+    ($self:expr, $method:ident, $($args:expr),*) => {
+        if $self.unused_key_callback.is_some()
+            && crate::shouldbe::is_expecting_should_be_then_reset()
+        {
+            let (value, path) = ($self.value, $self.path);
+            // The field transformer has been applied already.
+            let res = super::attempt_should_be($self.unused_key_callback, |unused_key_callback| {
+                ValueRefDeserializer::new_with_transformed(
+                    value,
+                    path,
+                    unused_key_callback,
+                    $self.field_transformer,
+                )
+                .$method($($args),*)
+            });
+            return match res {
+                Ok(value) => Ok(value),
+                Err(err) => {
+                    let msg = err.to_string();
+                    crate::shouldbe::set_why_not(value.clone(), err);
+                    // ShouldBe will ignore this and use the error in `why_not`,
+                    // but we still need to return an error here nonetheless.
+                    Err(Error::custom(msg))
+                }
+            };
+        }
+    };
+}
+
 use super::maybe_why_not;
 
 impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
@@ -433,6 +477,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
             return Err(Error::custom("Value deserialized via fast path"));
         }
         maybe_transform_and_forward_to_value_deserializer!(self, deserialize_any, visitor);
+        maybe_expecting_should_be!(self, deserialize_any, visitor);
 
         maybe_why_not!(
             self.value,
@@ -440,7 +485,9 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 Value::Null(..) => visitor.visit_unit(),
                 Value::Bool(v, ..) => visitor.visit_bool(*v),
                 Value::Number(n, ..) => n.deserialize_any(visitor),
-                Value::String(v, ..) => visitor.visit_borrowed_str(v),
+                Value::String(v, ..) => {
+                    check_not_elided(v).and_then(|()| visitor.visit_borrowed_str(v))
+                }
                 Value::Sequence(v, ..) => visit_sequence_ref(
                     v,
                     self.path,
@@ -457,7 +504,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 ),
                 Value::Tagged(tagged, ..) => visitor.visit_enum(&**tagged),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -475,7 +522,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 Value::Bool(v, ..) => visitor.visit_bool(*v),
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -605,10 +652,12 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
         maybe_why_not!(
             self.value,
             match self.value.untag_ref() {
-                Value::String(v, ..) => visitor.visit_borrowed_str(v),
+                Value::String(v, ..) => {
+                    check_not_elided(v).and_then(|()| visitor.visit_borrowed_str(v))
+                }
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -627,10 +676,20 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
+        if let Some(bytes) = super::tagged_binary(self.value) {
+            return maybe_why_not!(
+                self.value,
+                bytes
+                    .and_then(|bytes| visitor.visit_byte_buf(bytes))
+                    .map_err(|e| error::set_location(e, span, &self.path))
+            );
+        }
         maybe_why_not!(
             self.value,
             match self.value.untag_ref() {
-                Value::String(v, ..) => visitor.visit_borrowed_str(v),
+                Value::String(v, ..) => {
+                    check_not_elided(v).and_then(|()| visitor.visit_borrowed_str(v))
+                }
                 Value::Sequence(v, ..) => visit_sequence_ref(
                     v,
                     self.path,
@@ -640,7 +699,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 ),
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -656,20 +715,24 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
         V: Visitor<'de>,
     {
         maybe_transform_and_forward_to_value_deserializer!(self, deserialize_option, visitor);
+        maybe_expecting_should_be!(self, deserialize_option, visitor);
 
         let span = self.value.span().clone();
         maybe_why_not!(
             self.value,
             match self.value {
                 Value::Null(..) => visitor.visit_unit(),
-                _ => visitor.visit_some(ValueRefDeserializer::new_with_transformed(
-                    self.value,
-                    self.path,
-                    self.unused_key_callback,
-                    self.field_transformer,
-                )),
+                // If transformation is currently disabled, leave it to the
+                // inner value, which may re-enable it (see `Transformed`).
+                _ => visitor.visit_some(ValueRefDeserializer {
+                    value: self.value,
+                    path: self.path,
+                    unused_key_callback: self.unused_key_callback,
+                    field_transformer: self.field_transformer,
+                    is_transformed: self.is_transformed || crate::verbatim::should_transform_any(),
+                }),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -687,7 +750,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 Value::Null(..) => visitor.visit_unit(),
                 _ => Err(self.value.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -723,6 +786,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
             name,
             visitor
         );
+        maybe_expecting_should_be!(self, deserialize_newtype_struct, name, visitor);
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
@@ -735,7 +799,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                     self.unused_key_callback,
                     self.field_transformer
                 ))
-                .map_err(|e| error::set_span(e, span))
+                .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -746,9 +810,19 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
         static EMPTY: Sequence = Sequence::new();
 
         maybe_transform_and_forward_to_value_deserializer!(self, deserialize_seq, visitor);
+        maybe_expecting_should_be!(self, deserialize_seq, visitor);
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
+        if let Some(sequence) = super::tagged_collection(self.value) {
+            return ValueDeserializer::new_with_transformed(
+                Value::Sequence(sequence, span),
+                self.path,
+                self.unused_key_callback,
+                self.field_transformer,
+            )
+            .deserialize_seq(visitor);
+        }
         maybe_why_not!(
             self.value,
             match self.value.untag_ref() {
@@ -768,7 +842,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 ),
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -796,6 +870,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
         V: Visitor<'de>,
     {
         maybe_transform_and_forward_to_value_deserializer!(self, deserialize_map, visitor);
+        maybe_expecting_should_be!(self, deserialize_map, visitor);
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
@@ -812,7 +887,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 Value::Null(..) => visitor.visit_map(&mut MapRefDeserializer::new_empty(self.path)),
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -832,6 +907,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
             fields,
             visitor
         );
+        maybe_expecting_should_be!(self, deserialize_struct, name, fields, visitor);
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
@@ -849,7 +925,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
                 Value::Null(..) => visitor.visit_map(&mut MapRefDeserializer::new_empty(self.path)),
                 other => Err(other.invalid_type(&visitor)),
             }
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -869,6 +945,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
             variants,
             visitor
         );
+        maybe_expecting_should_be!(self, deserialize_enum, name, variants, visitor);
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
@@ -877,27 +954,37 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
             visitor
                 .visit_enum(match self.value {
                     Value::Tagged(tagged, ..) => EnumRefDeserializer {
-                        tag: tagged::nobang(&tagged.tag.string),
+                        tag: variant::resolve(
+                            tagged::nobang(&tagged.tag.string),
+                            variants,
+                            &self.path,
+                            &span,
+                        ),
                         path: self.path,
                         value: Some(&tagged.value),
                         unused_key_callback: self.unused_key_callback,
                         field_transformer: self.field_transformer,
                     },
-                    Value::String(variant, ..) => EnumRefDeserializer {
-                        tag: variant,
+                    Value::String(tag, ..) => EnumRefDeserializer {
+                        tag: {
+                            check_not_elided(tag)
+                                .map_err(|e| error::set_location(e, span.clone(), &self.path))?;
+                            variant::resolve(tag, variants, &self.path, &span)
+                        },
                         path: self.path,
                         value: None,
                         unused_key_callback: self.unused_key_callback,
                         field_transformer: self.field_transformer,
                     },
                     other => {
-                        return Err(error::set_span(
+                        return Err(error::set_location(
                             Error::invalid_type(other.unexpected(), &"a Value::Tagged enum"),
                             span,
+                            &self.path,
                         ));
                     }
                 })
-                .map_err(|e| error::set_span(e, span))
+                .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 
@@ -908,15 +995,19 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueRefDeserializer<'de, '_, 'u, 'f> {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
+        maybe_expecting_should_be!(self, deserialize_ignored_any, visitor);
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
+        report_ignored(self.path, self.value, &mut self.unused_key_callback)?;
         maybe_why_not!(
             self.value,
-            visitor.visit_unit().map_err(|e| error::set_span(e, span))
+            visitor
+                .visit_unit()
+                .map_err(|e| error::set_location(e, span, &self.path))
         )
     }
 }
@@ -1199,7 +1290,7 @@ impl<'de> SeqAccess<'de> for SeqRefDeserializer<'de, '_, '_, '_> {
                 );
                 seed.deserialize(deserializer)
                     .map(Some)
-                    .map_err(|e| error::set_span(e, span))
+                    .map_err(|e| error::set_location(e, span, &self.path))
             }
             None => Ok(None),
         }
@@ -1279,7 +1370,10 @@ impl<'de> MapAccess<'de> for MapRefDeserializer<'de, '_, '_, '_> {
                 let deserializer = ValueRefDeserializer::new_with(key, self.path, None, None);
                 seed.deserialize(deserializer).map(Some)
             }
-            None => Ok(None),
+            None => {
+                crate::verbatim::raw_entries_visited(&self.path);
+                Ok(None)
+            }
         }
     }
 
@@ -1288,22 +1382,26 @@ impl<'de> MapAccess<'de> for MapRefDeserializer<'de, '_, '_, '_> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(ValueRefDeserializer::new_with(
-                value,
-                match self.current_key {
+            Some(value) => {
+                let path = match self.current_key {
                     Some(ref key) => Path::Map {
                         parent: &self.path,
                         key,
                     },
                     None => Path::Unknown { parent: &self.path },
-                },
-                self.unused_key_callback
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
-                self.field_transformer
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as FieldTransformer<'_>),
-            )),
+                };
+                let _span = trace::enter_top_level_key(&path);
+                seed.deserialize(ValueRefDeserializer::new_with(
+                    value,
+                    path,
+                    self.unused_key_callback
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
+                    self.field_transformer
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as FieldTransformer<'_>),
+                ))
+            }
             None => panic!("visit_value called before visit_key"),
         }
     }
@@ -1360,7 +1458,7 @@ impl<'de> Deserializer<'de> for MapRefDeserializer<'de, '_, '_, '_> {
         let (normal_keys, flatten_keys): (Vec<_>, Vec<_>) = fields
             .iter()
             .copied()
-            .partition(|key| !crate::is_flatten_key(key.as_bytes()));
+            .partition(|key| !crate::is_flatten_key(key));
         visitor.visit_map(StructRefDeserializer {
             iter: self.iter,
             current_key: None,
@@ -1372,6 +1470,7 @@ impl<'de> Deserializer<'de> for MapRefDeserializer<'de, '_, '_, '_> {
             field_transformer: self.field_transformer,
             rest: Vec::new(),
             flatten_keys_done: 0,
+            routed: Vec::new(),
         })
     }
 
@@ -1400,6 +1499,7 @@ pub(crate) struct StructRefDeserializer<'de, 'p, 'u, 'f> {
     field_transformer: Option<FieldTransformer<'f>>,
     rest: Vec<(&'de Value, &'de Value)>,
     flatten_keys_done: usize,
+    routed: Vec<Vec<(&'de Value, &'de Value)>>,
 }
 
 impl<'de, 'p, 'u, 'f> StructRefDeserializer<'de, 'p, 'u, 'f> {
@@ -1413,7 +1513,7 @@ impl<'de, 'p, 'u, 'f> StructRefDeserializer<'de, 'p, 'u, 'f> {
         let (normal_keys, flatten_keys): (Vec<_>, Vec<_>) = known_keys
             .iter()
             .copied()
-            .partition(|key| !crate::is_flatten_key(key.as_bytes()));
+            .partition(|key| !crate::is_flatten_key(key));
         StructRefDeserializer {
             iter: Some(Box::new(map.iter())),
             current_key: None,
@@ -1425,6 +1525,7 @@ impl<'de, 'p, 'u, 'f> StructRefDeserializer<'de, 'p, 'u, 'f> {
             field_transformer,
             rest: Vec::new(),
             flatten_keys_done: 0,
+            routed: Vec::new(),
         }
     }
 
@@ -1449,7 +1550,7 @@ impl<'de> MapAccess<'de> for StructRefDeserializer<'de, '_, '_, '_> {
             match self.iter.as_mut().and_then(Iterator::next) {
                 Some((key, value)) => {
                     match key.as_str() {
-                        Some(key_str) if crate::is_flatten_key(key_str.as_bytes()) => {
+                        Some(key_str) if crate::is_flatten_key(key_str) => {
                             self.rest.push((key, value));
                             continue;
                         }
@@ -1464,7 +1565,9 @@ impl<'de> MapAccess<'de> for StructRefDeserializer<'de, '_, '_, '_> {
                                     parent: &self.path,
                                     key: &key_string,
                                 };
-                                callback(path, key, value);
+                                crate::callback::guard(&path, value.span(), || {
+                                    callback(path, key, value)
+                                })?;
                                 continue;
                             }
                         }
@@ -1492,23 +1595,33 @@ impl<'de> MapAccess<'de> for StructRefDeserializer<'de, '_, '_, '_> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(ValueRefDeserializer::new_with(
-                value,
-                match self.current_key {
+            Some(value) => {
+                let path = match self.current_key {
                     Some(ref key) => Path::Map {
                         parent: &self.path,
                         key,
                     },
                     None => Path::Unknown { parent: &self.path },
-                },
-                self.unused_key_callback
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
-                self.field_transformer
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as FieldTransformer<'_>),
-            )),
+                };
+                let _span = trace::enter_top_level_key(&path);
+                seed.deserialize(ValueRefDeserializer::new_with(
+                    value,
+                    path,
+                    self.unused_key_callback
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
+                    self.field_transformer
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as FieldTransformer<'_>),
+                ))
+            }
             None if self.has_unprocessed_flatten_keys() => {
+                if self.flatten_keys_done == 0 {
+                    self.routed = super::route_flattened(&mut self.rest, &self.flatten_keys);
+                }
+                if let Some(routed) = self.routed.get_mut(self.flatten_keys_done) {
+                    self.rest.append(routed);
+                }
                 self.flatten_keys_done += 1;
 
                 let flattened = self.rest.drain(..).collect::<Vec<_>>();
@@ -1678,7 +1791,7 @@ impl<'de> Deserializer<'de> for FlattenRefDeserializer<'de, '_, '_, '_> {
         let (normal_keys, flatten_keys): (Vec<_>, Vec<_>) = fields
             .iter()
             .copied()
-            .partition(|key| !crate::is_flatten_key(key.as_bytes()));
+            .partition(|key| !crate::is_flatten_key(key));
         let deserializer = StructRefDeserializer {
             iter: self.iter,
             current_key: None,
@@ -1690,6 +1803,7 @@ impl<'de> Deserializer<'de> for FlattenRefDeserializer<'de, '_, '_, '_> {
             field_transformer: self.field_transformer,
             rest: Vec::new(),
             flatten_keys_done: 0,
+            routed: Vec::new(),
         };
         visitor.visit_map(deserializer)
     }
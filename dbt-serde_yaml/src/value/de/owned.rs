@@ -1,4 +1,4 @@
-use std::{collections::HashSet, vec};
+use std::{collections::HashSet, mem, vec};
 
 use serde::{
     de::{
@@ -9,12 +9,13 @@ use serde::{
 };
 
 use crate::{
-    error,
+    callback, error, trace,
     value::{
         de::{
-            borrowed::ValueRefDeserializer, reset_is_deserializing_value, save_deserializer_state,
+            borrowed::ValueRefDeserializer, check_not_elided, report_ignored,
+            reset_is_deserializing_value, save_deserializer_state,
         },
-        tagged,
+        tagged, variant,
     },
     Error, Mapping, Path, Sequence, Value,
 };
@@ -33,7 +34,7 @@ where
 {
     let len = sequence.len();
     let mut deserializer = SeqDeserializer::new(
-        sequence,
+        sequence.into_vec(),
         current_path,
         unused_key_callback,
         field_transformer,
@@ -58,6 +59,9 @@ where
     V: Visitor<'de>,
 {
     let len = mapping.len();
+    let _raw_entries = field_transformer
+        .is_some()
+        .then(|| crate::verbatim::keep_raw_entries(&current_path));
     let mut deserializer = MapDeserializer::new(
         mapping,
         current_path,
@@ -381,13 +385,15 @@ impl<'a, 'u, 'f> ValueDeserializer<'a, 'u, 'f> {
         }
     }
 
-    fn maybe_apply_transformation(
-        &mut self,
-    ) -> Result<(), Box<dyn std::error::Error + 'static + Send + Sync>> {
+    fn maybe_apply_transformation(&mut self) -> Result<(), Error> {
         if let Some(transformer) = &mut self.field_transformer {
             if !self.is_transformed && crate::verbatim::should_transform_any() {
-                if let Some(v) = transformer(&self.value)? {
-                    self.value = v;
+                let (path, span) = (&self.path, self.value.span());
+                let transformed = callback::guard(path, span, || transformer(&self.value))??;
+                trace::transformer_invoked(&self.path, transformed.is_some());
+                if let Some(v) = transformed {
+                    let raw = mem::replace(&mut self.value, v);
+                    crate::verbatim::keep_raw(&self.path, || raw);
                 }
             }
         }
@@ -397,16 +403,21 @@ impl<'a, 'u, 'f> ValueDeserializer<'a, 'u, 'f> {
 
 macro_rules! maybe_expecting_should_be {
     ($self:expr, $method:ident, $($args:expr),*) => {{
-        if $crate::shouldbe::is_expecting_should_be_then_reset() {
-            let res = ValueRefDeserializer::new_with_transformed(
-                // SAFETY: ShouldBe<T>::Deserialize is only implemented for T:DeserializeOwned,
-                // so we know that `res` can not contain references to `self.value`.
-                unsafe { std::mem::transmute::<&Value, &'de Value>(&$self.value) },
-                $self.path,
-                $self.unused_key_callback,
-                $self.field_transformer,
-            )
-            .$method($($args),*);
+        // Data borrowed from `self.value` would not outlive it, so a
+        // `ShouldBeRef` is deserialized like any other value, without raw
+        // value on failure.
+        if $crate::shouldbe::take_expecting_should_be() == Some($crate::shouldbe::Expecting::ShouldBe) {
+            let res = super::attempt_should_be($self.unused_key_callback, |unused_key_callback| {
+                ValueRefDeserializer::new_with_transformed(
+                    // SAFETY: ShouldBe<T>::Deserialize is only implemented for T:DeserializeOwned,
+                    // so we know that `res` can not contain references to `self.value`.
+                    unsafe { std::mem::transmute::<&Value, &'de Value>(&$self.value) },
+                    $self.path,
+                    unused_key_callback,
+                    $self.field_transformer,
+                )
+                .$method($($args),*)
+            });
             return match res {
                 Ok(value) => Ok(value),
                 Err(e) => {
@@ -450,7 +461,8 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             Value::Null(..) => visitor.visit_unit(),
             Value::Bool(v, ..) => visitor.visit_bool(v),
             Value::Number(n, ..) => n.deserialize_any(visitor),
-            Value::String(v, ..) => visitor.visit_string(v),
+            Value::String(v, ..) => check_not_elided(&v)
+                .and_then(|()| visitor.visit_string(crate::value::into_string(v))),
             Value::Sequence(v, ..) => visit_sequence(
                 v,
                 self.path,
@@ -467,7 +479,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             ),
             Value::Tagged(tagged, ..) => visitor.visit_enum(*tagged),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_bool<V>(mut self, visitor: V) -> Result<V::Value, Error>
@@ -483,7 +495,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             Value::Bool(v, ..) => visitor.visit_bool(v),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_i8<V>(mut self, visitor: V) -> Result<V::Value, Error>
@@ -606,10 +618,11 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
         match self.value.untag() {
-            Value::String(v, ..) => visitor.visit_string(v),
+            Value::String(v, ..) => check_not_elided(&v)
+                .and_then(|()| visitor.visit_string(crate::value::into_string(v))),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
@@ -628,8 +641,14 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
+        if let Some(bytes) = super::tagged_binary(&self.value) {
+            return bytes
+                .and_then(|bytes| visitor.visit_byte_buf(bytes))
+                .map_err(|e| error::set_location(e, span, &self.path));
+        }
         match self.value.untag() {
-            Value::String(v, ..) => visitor.visit_string(v),
+            Value::String(v, ..) => check_not_elided(&v)
+                .and_then(|()| visitor.visit_string(crate::value::into_string(v))),
             Value::Sequence(v, ..) => visit_sequence(
                 v,
                 self.path,
@@ -639,7 +658,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             ),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value, Error>
@@ -652,15 +671,17 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
         let span = self.value.span().clone();
         match self.value {
             Value::Null(..) => visitor.visit_unit(),
+            // If transformation is currently disabled, leave it to the inner
+            // value, which may re-enable it (see `Transformed`).
             _ => visitor.visit_some(ValueDeserializer {
                 value: self.value,
                 path: self.path,
                 unused_key_callback: self.unused_key_callback,
                 field_transformer: self.field_transformer,
-                is_transformed: true,
+                is_transformed: self.is_transformed || crate::verbatim::should_transform_any(),
             }),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_unit<V>(mut self, visitor: V) -> Result<V::Value, Error>
@@ -676,7 +697,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             Value::Null(..) => visitor.visit_unit(),
             _ => Err(self.value.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
@@ -713,7 +734,11 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
-        match self.value.untag() {
+        let value = match super::tagged_collection(&self.value) {
+            Some(sequence) => Value::Sequence(sequence, span.clone()),
+            None => self.value,
+        };
+        match value.untag() {
             Value::Sequence(v, ..) => visit_sequence(
                 v,
                 self.path,
@@ -730,7 +755,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             ),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
@@ -778,7 +803,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             ),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_struct<V>(
@@ -814,7 +839,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
             ),
             other => Err(other.invalid_type(&visitor)),
         }
-        .map_err(|e| error::set_span(e, span))
+        .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_enum<V>(
@@ -838,17 +863,18 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
                 Value::Tagged(tagged, ..) => EnumDeserializer {
                     tag: {
                         tag = tagged.tag.string;
-                        tagged::nobang(&tag)
+                        variant::resolve(tagged::nobang(&tag), variants, &self.path, &span)
                     },
                     path: self.path,
                     value: Some(tagged.value),
                     unused_key_callback: self.unused_key_callback,
                     field_transformer: self.field_transformer,
                 },
-                Value::String(variant, ..) => EnumDeserializer {
+                Value::String(string, ..) => EnumDeserializer {
                     tag: {
-                        tag = variant;
-                        &tag
+                        check_not_elided(&string)?;
+                        tag = crate::value::into_string(string);
+                        variant::resolve(&tag, variants, &self.path, &span)
                     },
                     path: self.path,
                     value: None,
@@ -862,7 +888,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
                     ));
                 }
             })
-            .map_err(|e| error::set_span(e, span))
+            .map_err(|e| error::set_location(e, span, &self.path))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
@@ -872,7 +898,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
@@ -880,6 +906,7 @@ impl<'de, 'u, 'f> Deserializer<'de> for ValueDeserializer<'_, 'u, 'f> {
 
         let span = self.value.span().clone();
         self.value.broadcast_end_mark();
+        report_ignored(self.path, &self.value, &mut self.unused_key_callback)?;
         drop(self);
         visitor.visit_unit().map_err(|e| error::set_span(e, span))
     }
@@ -955,7 +982,7 @@ impl<'de, 'u, 'f> VariantAccess<'de> for VariantDeserializer<'_, 'f, 'u> {
         match self.value {
             Some(Value::Sequence(v, ..)) => Deserializer::deserialize_any(
                 SeqDeserializer::new(
-                    v,
+                    v.into_vec(),
                     self.path,
                     self.unused_key_callback,
                     self.field_transformer,
@@ -1019,7 +1046,7 @@ impl<'de, 'u, 'f> VariantAccess<'de> for ValueDeserializer<'_, 'u, 'f> {
         if let Value::Sequence(v, ..) = self.value {
             Deserializer::deserialize_any(
                 SeqDeserializer::new(
-                    v,
+                    v.into_vec(),
                     self.path,
                     self.unused_key_callback,
                     self.field_transformer,
@@ -1155,7 +1182,7 @@ impl<'de, 'u, 'f> SeqAccess<'de> for SeqDeserializer<'_, 'u, 'f> {
                 );
                 seed.deserialize(deserializer)
                     .map(Some)
-                    .map_err(|e| error::set_span(e, span))
+                    .map_err(|e| error::set_location(e, span, &self.path))
             }
             None => Ok(None),
         }
@@ -1211,7 +1238,10 @@ impl<'de, 'u, 'f> MapAccess<'de> for MapDeserializer<'_, 'u, 'f> {
                 let deserializer = ValueDeserializer::new_with(key, self.path, None, None);
                 seed.deserialize(deserializer).map(Some)
             }
-            None => Ok(None),
+            None => {
+                crate::verbatim::raw_entries_visited(&self.path);
+                Ok(None)
+            }
         }
     }
 
@@ -1220,22 +1250,26 @@ impl<'de, 'u, 'f> MapAccess<'de> for MapDeserializer<'_, 'u, 'f> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(ValueDeserializer::new_with(
-                value,
-                match self.current_key {
+            Some(value) => {
+                let path = match self.current_key {
                     Some(ref key) => Path::Map {
                         parent: &self.path,
                         key,
                     },
                     None => Path::Unknown { parent: &self.path },
-                },
-                self.unused_key_callback
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
-                self.field_transformer
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as FieldTransformer<'_>),
-            )),
+                };
+                let _span = trace::enter_top_level_key(&path);
+                seed.deserialize(ValueDeserializer::new_with(
+                    value,
+                    path,
+                    self.unused_key_callback
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
+                    self.field_transformer
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as FieldTransformer<'_>),
+                ))
+            }
             None => panic!("visit_value called before visit_key"),
         }
     }
@@ -1300,6 +1334,7 @@ pub(crate) struct StructDeserializer<'a, 'u, 'f> {
     field_transformer: Option<FieldTransformer<'f>>,
     rest: Vec<(Value, Value)>,
     flatten_keys_done: usize,
+    routed: Vec<Vec<(Value, Value)>>,
 }
 
 impl<'a, 'u, 'f> StructDeserializer<'a, 'u, 'f> {
@@ -1313,7 +1348,7 @@ impl<'a, 'u, 'f> StructDeserializer<'a, 'u, 'f> {
         let (normal_keys, flatten_keys): (Vec<_>, Vec<_>) = known_keys
             .iter()
             .copied()
-            .partition(|key| !crate::is_flatten_key(key.as_bytes()));
+            .partition(|key| !crate::is_flatten_key(key));
         StructDeserializer {
             iter: map.into_iter(),
             current_key: None,
@@ -1325,6 +1360,7 @@ impl<'a, 'u, 'f> StructDeserializer<'a, 'u, 'f> {
             field_transformer,
             rest: Vec::new(),
             flatten_keys_done: 0,
+            routed: Vec::new(),
         }
     }
 
@@ -1349,7 +1385,7 @@ impl<'de> MapAccess<'de> for StructDeserializer<'_, '_, '_> {
             match self.iter.next() {
                 Some((key, value)) => {
                     match key.as_str() {
-                        Some(key_str) if crate::is_flatten_key(key_str.as_bytes()) => {
+                        Some(key_str) if crate::is_flatten_key(key_str) => {
                             self.rest.push((key, value));
                             continue;
                         }
@@ -1364,7 +1400,9 @@ impl<'de> MapAccess<'de> for StructDeserializer<'_, '_, '_> {
                                     parent: &self.path,
                                     key: &key_string,
                                 };
-                                callback(path, &key, &value);
+                                callback::guard(&path, value.span(), || {
+                                    callback(path, &key, &value)
+                                })?;
                                 continue;
                             }
                         }
@@ -1392,23 +1430,33 @@ impl<'de> MapAccess<'de> for StructDeserializer<'_, '_, '_> {
         T: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(ValueDeserializer::new_with(
-                value,
-                match self.current_key {
+            Some(value) => {
+                let path = match self.current_key {
                     Some(ref key) => Path::Map {
                         parent: &self.path,
                         key,
                     },
                     None => Path::Unknown { parent: &self.path },
-                },
-                self.unused_key_callback
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
-                self.field_transformer
-                    .as_deref_mut()
-                    .map(|cb| &mut *cb as FieldTransformer<'_>),
-            )),
+                };
+                let _span = trace::enter_top_level_key(&path);
+                seed.deserialize(ValueDeserializer::new_with(
+                    value,
+                    path,
+                    self.unused_key_callback
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as UnusedKeyCallback<'_>),
+                    self.field_transformer
+                        .as_deref_mut()
+                        .map(|cb| &mut *cb as FieldTransformer<'_>),
+                ))
+            }
             None if self.has_unprocessed_flatten_keys() => {
+                if self.flatten_keys_done == 0 {
+                    self.routed = super::route_flattened(&mut self.rest, &self.flatten_keys);
+                }
+                if let Some(routed) = self.routed.get_mut(self.flatten_keys_done) {
+                    self.rest.append(routed);
+                }
                 self.flatten_keys_done += 1;
 
                 let path = match self.current_key {
@@ -1559,7 +1607,7 @@ impl<'de, 'r, 'f> Deserializer<'de> for FlattenDeserializer<'_, 'r, 'f> {
         let (normal_keys, flatten_keys): (Vec<_>, Vec<_>) = fields
             .iter()
             .copied()
-            .partition(|key| !crate::is_flatten_key(key.as_bytes()));
+            .partition(|key| !crate::is_flatten_key(key));
         let deserializer = StructDeserializer {
             iter: self.iter,
             current_key: None,
@@ -1571,6 +1619,7 @@ impl<'de, 'r, 'f> Deserializer<'de> for FlattenDeserializer<'_, 'r, 'f> {
             field_transformer: self.field_transformer,
             rest: Vec::new(),
             flatten_keys_done: 0,
+            routed: Vec::new(),
         };
         visitor.visit_map(deserializer)
     }
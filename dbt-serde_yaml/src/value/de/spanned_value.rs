@@ -0,0 +1,142 @@
+use serde::de::{Deserializer, IntoDeserializer, Visitor};
+
+use crate::{spanned, Error, Span, Spanned, Value};
+
+use super::ValueDeserializer;
+
+/// A deserializer for a [`Spanned<Value>`](Spanned), returned by its
+/// [IntoDeserializer] impls.
+///
+/// Deserializing through it starts from the span recorded in the `Spanned`,
+/// as [`from_value`](crate::from_value) does from the span of a [Value], so
+/// that the spans captured in the target type, including that of a
+/// [Spanned] wrapping all of it, point into the document the value was read
+/// from rather than being left without a start.
+///
+/// ```
+/// use dbt_serde_yaml::{Spanned, Value};
+/// use serde::de::IntoDeserializer;
+/// use serde::Deserialize;
+/// use std::collections::BTreeMap;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: Spanned<String>,
+/// }
+///
+/// let yaml = "models:\n  orders:\n    name: orders\n";
+/// let doc: BTreeMap<String, BTreeMap<String, Spanned<Value>>> =
+///     dbt_serde_yaml::from_str(yaml).unwrap();
+/// let orders = &doc["models"]["orders"];
+///
+/// let model = Spanned::<Model>::deserialize(orders.clone().into_deserializer()).unwrap();
+/// assert_eq!(model.span().start.line, 3);
+/// assert_eq!(model.name.span().start.line, 3);
+/// assert_eq!(model.name.span().start.column, 11);
+///
+/// let model = Spanned::<Model>::deserialize(orders.into_deserializer()).unwrap();
+/// assert_eq!(model.span().start.line, 3);
+/// ```
+pub struct SpannedValueDeserializer<D> {
+    span: Span,
+    de: D,
+}
+
+impl<D> SpannedValueDeserializer<D> {
+    fn new(span: Span, de: D) -> Self {
+        let de = SpannedValueDeserializer { span, de };
+        // `Spanned<T>` reads the marker before calling into the deserializer.
+        de.broadcast_start_mark();
+        de
+    }
+
+    fn broadcast_start_mark(&self) {
+        spanned::set_marker(self.span.start);
+        #[cfg(feature = "filename")]
+        if let Some(filename) = &self.span.filename {
+            spanned::set_filename(std::sync::Arc::clone(filename));
+        }
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Spanned<Value> {
+    type Deserializer = SpannedValueDeserializer<ValueDeserializer<'static, 'static, 'static>>;
+
+    /// Returns a deserializer for the inner value, which gets the span of
+    /// this `Spanned` if it has none of its own.
+    fn into_deserializer(self) -> Self::Deserializer {
+        let span = self.span().clone();
+        let mut value = self.into_inner();
+        if !value.span().is_valid() {
+            value = value.with_span(span.clone());
+        }
+        SpannedValueDeserializer::new(span, ValueDeserializer::new(value))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Spanned<Value> {
+    type Deserializer = SpannedValueDeserializer<&'de Value>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        SpannedValueDeserializer::new(self.span().clone(), &**self)
+    }
+}
+
+macro_rules! forward_in_span {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.broadcast_start_mark();
+                self.de.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, D> Deserializer<'de> for SpannedValueDeserializer<D>
+where
+    D: Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    forward_in_span! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::{self, Error, ErrorImpl};
+use crate::path::OwnedPath;
+use crate::value::Value;
+
+/// The differences between two values, as computed by [`Value::diff`].
+///
+/// Applying the patch to the first value with [`Value::apply_patch`] turns it
+/// into the second one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValuePatch {
+    changes: Vec<ValueChange>,
+}
+
+impl ValuePatch {
+    /// Returns true if the two values compared were equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the changes in the order they are applied.
+    pub fn changes(&self) -> &[ValueChange] {
+        &self.changes
+    }
+}
+
+impl IntoIterator for ValuePatch {
+    type Item = ValueChange;
+    type IntoIter = std::vec::IntoIter<ValueChange>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ValuePatch {
+    type Item = &'a ValueChange;
+    type IntoIter = std::slice::Iter<'a, ValueChange>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+/// A single change of a [`ValuePatch`].
+///
+/// The values are clones of the nodes of the values compared, spans included,
+/// so that removed and old values point into the first value's source and
+/// added and new values into the second's.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueChange {
+    /// A mapping entry or sequence element only present in the second value.
+    Added {
+        /// Where the value was added.
+        path: OwnedPath,
+        /// The added value.
+        value: Value,
+    },
+    /// A mapping entry or sequence element only present in the first value.
+    Removed {
+        /// Where the value was removed from.
+        path: OwnedPath,
+        /// The removed value.
+        value: Value,
+    },
+    /// A value that differs between the two values compared.
+    Changed {
+        /// Where the value changed.
+        path: OwnedPath,
+        /// The value in the first value.
+        old: Value,
+        /// The value in the second value.
+        new: Value,
+    },
+}
+
+impl ValueChange {
+    /// Returns the path of the value that changed.
+    pub fn path(&self) -> &OwnedPath {
+        match self {
+            ValueChange::Added { path, .. }
+            | ValueChange::Removed { path, .. }
+            | ValueChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl Value {
+    /// Compares this value with `other` and returns what changed between them.
+    ///
+    /// Mappings whose keys are all strings are compared key by key, sequences
+    /// element by element at the same index, and tagged values with the same
+    /// tag by their inner values. Anything else that differs, including
+    /// mappings with non-string keys, is reported as changed as a whole.
+    /// Spans are ignored in the comparison.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::value::ValueChange;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let old: Value = dbt_serde_yaml::from_str("
+    /// models:
+    ///   orders:
+    ///     materialized: view
+    ///     tags: [finance]
+    /// ")?;
+    /// let new: Value = dbt_serde_yaml::from_str("
+    /// models:
+    ///   orders:
+    ///     materialized: table
+    ///     schema: marts
+    /// ")?;
+    ///
+    /// let patch = old.diff(&new);
+    /// let summary: Vec<String> = patch
+    ///     .changes()
+    ///     .iter()
+    ///     .map(|change| match change {
+    ///         ValueChange::Added { path, value } => {
+    ///             format!("+ {} at line {}", path, value.span().start.line())
+    ///         }
+    ///         ValueChange::Removed { path, .. } => format!("- {}", path),
+    ///         ValueChange::Changed { path, .. } => format!("~ {}", path),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(
+    ///     summary,
+    ///     [
+    ///         "~ models.orders.materialized",
+    ///         "- models.orders.tags",
+    ///         "+ models.orders.schema at line 5",
+    ///     ],
+    /// );
+    ///
+    /// let mut patched = old.clone();
+    /// patched.apply_patch(&patch)?;
+    /// assert_eq!(patched, new);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &Value) -> ValuePatch {
+        let mut changes = Vec::new();
+        diff_into(self, other, OwnedPath::Root, &mut changes);
+        ValuePatch { changes }
+    }
+
+    /// Applies the changes of `patch`, computed by [`Value::diff`], to this
+    /// value.
+    ///
+    /// Added and changed values are inserted with
+    /// [`insert_at_path`](Value::insert_at_path), so keys added to a mapping
+    /// come after the ones it already has, and removed values are taken out
+    /// with [`take_at_path`](Value::take_at_path). The old values recorded in
+    /// the patch are not checked against this value.
+    ///
+    /// Fails if a path of the patch leads nowhere in this value, in which case
+    /// the changes before it have already been applied.
+    pub fn apply_patch(&mut self, patch: &ValuePatch) -> Result<(), Error> {
+        for change in patch {
+            match change {
+                ValueChange::Added { path, value }
+                | ValueChange::Changed {
+                    path, new: value, ..
+                } => {
+                    self.insert_at_path(path.as_path(), value.clone())?;
+                }
+                ValueChange::Removed { path, .. } => {
+                    if self.take_at_path(path.as_path()).is_none() {
+                        return Err(error::new(ErrorImpl::NoValueAtPath(path.to_string())));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn diff_into(old: &Value, new: &Value, path: OwnedPath, changes: &mut Vec<ValueChange>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Mapping(old_map, ..), Value::Mapping(new_map, ..))
+            if old_map.keys().chain(new_map.keys()).all(Value::is_string) =>
+        {
+            for (key, old_value) in old_map {
+                let key_path = path.clone().push_key(key.as_str().unwrap());
+                match new_map.get(key) {
+                    Some(new_value) => diff_into(old_value, new_value, key_path, changes),
+                    None => changes.push(ValueChange::Removed {
+                        path: key_path,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    changes.push(ValueChange::Added {
+                        path: path.clone().push_key(key.as_str().unwrap()),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Sequence(old_seq, ..), Value::Sequence(new_seq, ..)) => {
+            let common = old_seq.len().min(new_seq.len());
+            for (index, (old_value, new_value)) in old_seq.iter().zip(new_seq).enumerate() {
+                diff_into(
+                    old_value,
+                    new_value,
+                    path.clone().push_index(index),
+                    changes,
+                );
+            }
+            // Removed from the end first so that the indices of the elements
+            // still to be removed don't shift.
+            for index in (common..old_seq.len()).rev() {
+                changes.push(ValueChange::Removed {
+                    path: path.clone().push_index(index),
+                    value: old_seq[index].clone(),
+                });
+            }
+            for (index, new_value) in new_seq.iter().enumerate().skip(common) {
+                changes.push(ValueChange::Added {
+                    path: path.clone().push_index(index),
+                    value: new_value.clone(),
+                });
+            }
+        }
+        (Value::Tagged(old_tagged, ..), Value::Tagged(new_tagged, ..))
+            if old_tagged.tag == new_tagged.tag =>
+        {
+            diff_into(&old_tagged.value, &new_tagged.value, path, changes);
+        }
+        _ => changes.push(ValueChange::Changed {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::mapping::Mapping;
+use crate::value::{Sequence, TaggedValue, Value};
+use std::fmt::{self, Display, Write as _};
+
+/// Formats the value as single-line YAML in flow style, e.g.
+/// `{name: orders, tags: [nightly], version: "2"}`.
+///
+/// Every value can be formatted, including mappings whose keys are sequences,
+/// mappings or tagged values; such keys are written in flow style as well.
+/// Strings are quoted when they would otherwise not read back as the same
+/// string.
+impl Display for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null(..) => formatter.write_str("null"),
+            Value::Bool(boolean, ..) => write!(formatter, "{}", boolean),
+            Value::Number(number, ..) => Display::fmt(number, formatter),
+            Value::String(string, ..) => write_str(string, formatter),
+            Value::Sequence(sequence, ..) => Display::fmt(sequence, formatter),
+            Value::Mapping(mapping, ..) => Display::fmt(mapping, formatter),
+            Value::Tagged(tagged, ..) => Display::fmt(&**tagged, formatter),
+        }
+    }
+}
+
+impl Display for Sequence {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_char('[')?;
+        for (i, element) in self.iter().enumerate() {
+            if i > 0 {
+                formatter.write_str(", ")?;
+            }
+            Display::fmt(element, formatter)?;
+        }
+        formatter.write_char(']')
+    }
+}
+
+impl Display for Mapping {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_char('{')?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                formatter.write_str(", ")?;
+            }
+            write!(formatter, "{}: {}", key, value)?;
+        }
+        formatter.write_char('}')
+    }
+}
+
+impl Display for TaggedValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} {}", self.tag, self.value)
+    }
+}
+
+fn write_str(string: &str, formatter: &mut fmt::Formatter) -> fmt::Result {
+    if is_plain_safe(string) {
+        return formatter.write_str(string);
+    }
+    formatter.write_char('"')?;
+    for ch in string.chars() {
+        match ch {
+            '"' => formatter.write_str("\\\"")?,
+            '\\' => formatter.write_str("\\\\")?,
+            '\n' => formatter.write_str("\\n")?,
+            '\r' => formatter.write_str("\\r")?,
+            '\t' => formatter.write_str("\\t")?,
+            '\0' => formatter.write_str("\\0")?,
+            ch if ch.is_control() => write!(formatter, "\\u{:04X}", ch as u32)?,
+            ch => formatter.write_char(ch)?,
+        }
+    }
+    formatter.write_char('"')
+}
+
+/// Whether `string` reads back as itself when written as a plain scalar
+/// inside a flow collection.
+fn is_plain_safe(string: &str) -> bool {
+    let mut chars = string.chars();
+    let first_ok = matches!(chars.next(), Some(ch) if ch.is_alphanumeric() || ch == '_' || ch == '.' || ch == '/');
+    first_ok
+        && !string.ends_with(' ')
+        && string
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.' | '/' | ' '))
+        && !crate::ser::is_ambiguous_plain(string)
+}
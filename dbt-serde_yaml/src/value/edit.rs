@@ -0,0 +1,636 @@
+use crate::error::{self, Error};
+use crate::libyaml::parser::{Event, Parser};
+use crate::value::{Mapping, Sequence, Value};
+use serde::de::Error as _;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::ops::Range;
+
+/// A replacement of part of a source text: the bytes in `range` are replaced
+/// by `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The replaced bytes of the source text.
+    pub range: Range<usize>,
+    /// The text that replaces them.
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Applies `edits` to `source` and returns the new text.
+    ///
+    /// The ranges of all edits refer to `source` as it was before any of them
+    /// were applied, so they must not overlap; edits that insert text at the
+    /// same position are applied in the order they are given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two edits overlap, or if a range is out of bounds or doesn't
+    /// fall on a character boundary.
+    pub fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        let mut text = String::with_capacity(source.len());
+        let mut pos = 0;
+        for edit in edits {
+            text.push_str(&source[pos..edit.range.start]);
+            text.push_str(&edit.replacement);
+            pos = edit.range.end;
+        }
+        text.push_str(&source[pos..]);
+        text
+    }
+}
+
+impl Value {
+    /// Computes a small set of edits of `source`, the YAML text this value was
+    /// parsed from, that make it describe `new` instead, leaving comments,
+    /// quoting and layout of everything that didn't change untouched.
+    ///
+    /// `new` is typically a modified copy of `self`. The two are compared node
+    /// by node:
+    ///
+    /// - scalars and collections that changed are rewritten in place,
+    /// - entries of block mappings and items of block sequences that were
+    ///   removed are deleted along with the rest of their lines,
+    /// - keys added to a block mapping are appended after its last entry, and
+    ///   items added to a block sequence are inserted next to the items that
+    ///   were kept around them.
+    ///
+    /// New text is written the way [to_string](crate::to_string) writes it,
+    /// indented to fit its surroundings. Flow collections are rewritten as a
+    /// whole, in flow style. The order of the keys of a mapping is not
+    /// considered a change.
+    ///
+    /// Fails if `source` is not valid YAML, uses aliases, or doesn't contain
+    /// a node at the position recorded in the span of a node of `self` that
+    /// needs to be edited, e.g. because `self` was modified after parsing.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::TextEdit;
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let source = "\
+    /// models:
+    ///   - name: orders  # the main fact table
+    ///     materialized: view
+    ///     tags: [nightly]
+    /// ";
+    /// let old: Value = dbt_serde_yaml::from_str(source).unwrap();
+    ///
+    /// let mut new = old.clone();
+    /// let model = new["models"][0].as_mapping_mut().unwrap();
+    /// model.insert("materialized".into(), "table".into());
+    /// model.remove("tags");
+    /// model.insert("enabled".into(), false.into());
+    ///
+    /// let edits = old.source_edits(source, &new).unwrap();
+    /// assert_eq!(
+    ///     TextEdit::apply(source, &edits),
+    ///     "\
+    /// models:
+    ///   - name: orders  # the main fact table
+    ///     materialized: table
+    ///     enabled: false
+    /// ",
+    /// );
+    /// ```
+    pub fn source_edits(&self, source: &str, new: &Value) -> Result<Vec<TextEdit>, Error> {
+        let mut editor = Editor::new(source, self)?;
+        editor.diff(self, new, Place::Root)?;
+        let mut edits = editor.edits;
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        Ok(edits)
+    }
+}
+
+/// Where a value sits in the source, which decides how its replacement is
+/// written.
+#[derive(Clone, Copy)]
+enum Place {
+    Root,
+    /// The value of a block mapping entry whose key is at column `indent` and
+    /// whose colon ends at `colon`.
+    Entry {
+        indent: usize,
+        colon: usize,
+    },
+    /// A block sequence item whose indicator is at column `indent`.
+    Item {
+        indent: usize,
+    },
+}
+
+/// A value written out for a mapping entry or sequence item.
+enum Rendered {
+    /// Text that starts on the line of the key or indicator.
+    Inline(String),
+    /// A block collection on the lines after the key or indicator, already
+    /// indented, without the leading line break.
+    Block(String),
+}
+
+struct Editor<'a> {
+    source: &'a str,
+    /// The end of the text of each scalar, by the index at which it starts.
+    scalars: HashMap<usize, usize>,
+    /// The end of the text of each collection, and whether it is a flow
+    /// collection, by the index at which it starts.
+    collections: HashMap<usize, (usize, bool)>,
+    /// The index at which the root node starts, which may come after the
+    /// start of its span if the document starts with comments or `---`.
+    root: usize,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Editor<'a> {
+    fn new(source: &'a str, value: &Value) -> Result<Self, Error> {
+        let mut scalars = HashMap::new();
+        let mut collections = HashMap::new();
+        let mut root = None;
+        let mut open = Vec::new();
+        let mut last = 0;
+        let mut parser = Parser::new(Cow::Borrowed(source.as_bytes()));
+        loop {
+            let (event, mark) = parser.next()?;
+            let start = mark.index() as usize;
+            if let Event::Scalar(_) | Event::SequenceStart(_) | Event::MappingStart(_) = event {
+                root.get_or_insert(start);
+            }
+            match event {
+                Event::StreamEnd => break,
+                Event::Alias(_) => {
+                    return Err(Error::custom("cannot edit a document with aliases"))
+                }
+                Event::Scalar(scalar) => {
+                    // Block scalars take in the line breaks and indentation
+                    // after them.
+                    let repr = scalar.repr.unwrap_or_default();
+                    let trailing = repr
+                        .iter()
+                        .rev()
+                        .take_while(|b| b.is_ascii_whitespace())
+                        .count();
+                    last = start + repr.len() - trailing;
+                    scalars.insert(start, last);
+                }
+                Event::SequenceStart(sequence) => open.push((start, sequence.flow)),
+                Event::MappingStart(mapping) => open.push((start, mapping.flow)),
+                Event::SequenceEnd | Event::MappingEnd => {
+                    let Some((begin, flow)) = open.pop() else {
+                        continue;
+                    };
+                    if flow {
+                        // Just after the closing bracket.
+                        last = start + 1;
+                    }
+                    collections.insert(begin, (last, flow));
+                }
+                Event::StreamStart | Event::DocumentStart | Event::DocumentEnd => {}
+            }
+        }
+        let root = root.unwrap_or_default();
+        let span_start = value.span().start.index;
+        if root != span_start {
+            if is_collection(value) {
+                if let Some(&extent) = collections.get(&root) {
+                    collections.insert(span_start, extent);
+                }
+            } else if let Some(&end) = scalars.get(&root) {
+                scalars.insert(span_start, end);
+            }
+        }
+        Ok(Editor {
+            source,
+            scalars,
+            collections,
+            root,
+            edits: Vec::new(),
+        })
+    }
+
+    fn diff(&mut self, old: &Value, new: &Value, place: Place) -> Result<(), Error> {
+        if old == new {
+            return Ok(());
+        }
+        match (old, new) {
+            (Value::Mapping(old_mapping, ..), Value::Mapping(new_mapping, ..))
+                if !self.is_flow(old)
+                    && old_mapping.keys().any(|key| new_mapping.contains_key(key)) =>
+            {
+                self.diff_mapping(old_mapping, new_mapping)
+            }
+            (Value::Sequence(old_sequence, ..), Value::Sequence(new_sequence, ..))
+                if !self.is_flow(old) && !old_sequence.is_empty() && !new_sequence.is_empty() =>
+            {
+                self.diff_sequence(old_sequence, new_sequence)
+            }
+            _ => self.replace(old, new, place),
+        }
+    }
+
+    /// Edits a non-empty block mapping, some of whose keys are kept.
+    fn diff_mapping(&mut self, old: &Mapping, new: &Mapping) -> Result<(), Error> {
+        let mut units = Vec::with_capacity(old.len());
+        let mut removed = Vec::with_capacity(old.len());
+        for (key, value) in old {
+            units.push(key.span().start.index..self.end(value)?);
+            match new.get(key) {
+                Some(new_value) => {
+                    let place = self.entry_place(key)?;
+                    self.diff(value, new_value, place)?;
+                    removed.push(false);
+                }
+                None => removed.push(true),
+            }
+        }
+        self.remove(&units, &removed);
+
+        let mut added = new
+            .iter()
+            .filter(|(key, _)| !old.contains_key(*key))
+            .peekable();
+        if added.peek().is_some() {
+            let indent = self.column(units[0].start);
+            let at = self.line_end(units[units.len() - 1].end);
+            let mut text = self.line_break_before(at);
+            for (key, value) in added {
+                let key_text = to_yaml(key)?;
+                if matches!(key.untag_ref(), Value::Sequence(..) | Value::Mapping(..))
+                    || key_text.contains('\n')
+                {
+                    return Err(Error::custom(
+                        "cannot insert a mapping key that is not a scalar",
+                    ));
+                }
+                text.push_str(&" ".repeat(indent));
+                text.push_str(&key_text);
+                text.push(':');
+                match render(value, indent, false)? {
+                    Rendered::Inline(value) => {
+                        text.push(' ');
+                        text.push_str(&value);
+                    }
+                    Rendered::Block(value) => {
+                        text.push('\n');
+                        text.push_str(&value);
+                    }
+                }
+                text.push('\n');
+            }
+            self.insert(at, text);
+        }
+        Ok(())
+    }
+
+    /// Edits a non-empty block sequence into a non-empty sequence: the items
+    /// that are equal at either end are kept, the ones in between are edited
+    /// pairwise, and the rest are removed or inserted.
+    fn diff_sequence(&mut self, old: &Sequence, new: &Sequence) -> Result<(), Error> {
+        let prefix = old
+            .iter()
+            .zip(new.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take(old.len().min(new.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let paired = (old.len() - suffix - prefix).min(new.len() - suffix - prefix);
+
+        let mut units = Vec::with_capacity(old.len());
+        for item in old {
+            let end = self.end(item)?;
+            units.push(self.indicator(item)?..end);
+        }
+        let indent = self.column(units[0].start);
+        for i in prefix..prefix + paired {
+            self.diff(&old[i], &new[i], Place::Item { indent })?;
+        }
+        let removed: Vec<bool> = (0..old.len())
+            .map(|i| i >= prefix + paired && i < old.len() - suffix)
+            .collect();
+        self.remove(&units, &removed);
+
+        let added = &new[prefix + paired..new.len() - suffix];
+        if !added.is_empty() {
+            let mut items = Vec::with_capacity(added.len());
+            for item in added {
+                items.push(match render(item, indent, false)? {
+                    Rendered::Inline(text) => text,
+                    Rendered::Block(text) => text.trim_start_matches(' ').to_owned(),
+                });
+            }
+            let kept = prefix + paired;
+            if kept > 0 {
+                // After the last item kept before them.
+                let at = self.line_end(units[kept - 1].end);
+                let mut text = self.line_break_before(at);
+                for item in items {
+                    let _ = writeln!(text, "{}- {}", " ".repeat(indent), item);
+                }
+                self.insert(at, text);
+            } else {
+                // Before the first item kept after them, which may share its
+                // line with the indicator of an enclosing item.
+                let mut text = String::new();
+                for item in items {
+                    let _ = write!(text, "- {}\n{}", item, " ".repeat(indent));
+                }
+                self.insert(units[old.len() - suffix].start, text);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrites `old` as a whole.
+    fn replace(&mut self, old: &Value, new: &Value, place: Place) -> Result<(), Error> {
+        let end = self.end(old)?;
+        let start = match place {
+            Place::Root => self.root,
+            _ => old.span().start.index,
+        };
+        let flow = self.is_flow(old);
+        let (range, replacement) = match place {
+            Place::Root => {
+                let text = if flow && is_collection(new) {
+                    to_flow(new)?
+                } else {
+                    to_yaml(new)?
+                };
+                (start..end, text)
+            }
+            Place::Entry { indent, colon } => {
+                let same_line = !self.source[colon..start].contains('\n');
+                match render(new, indent, flow)? {
+                    Rendered::Inline(text) if same_line && start > colon => (start..end, text),
+                    Rendered::Inline(text) => (colon..end, format!(" {}", text)),
+                    Rendered::Block(text) => (colon..end, format!("\n{}", text)),
+                }
+            }
+            Place::Item { indent } => match render(new, indent, flow)? {
+                Rendered::Inline(text) => (start..end, text),
+                Rendered::Block(text) => (start..end, text.trim_start_matches(' ').to_owned()),
+            },
+        };
+        let (range, replacement) = self.keep_trailing_comment(range, replacement);
+        self.edits.push(TextEdit { range, replacement });
+        Ok(())
+    }
+
+    /// Moves a comment after the text at `range` to the end of the first line
+    /// of its `replacement`, if that spans several lines: left where it is,
+    /// the comment would end up after the last line, inside a block scalar.
+    fn keep_trailing_comment(
+        &self,
+        mut range: Range<usize>,
+        replacement: String,
+    ) -> (Range<usize>, String) {
+        let Some((first, rest)) = replacement.split_once('\n') else {
+            return (range, replacement);
+        };
+        let after = &self.source[range.end..self.line_end(range.end)];
+        let after = after.trim_end_matches(['\n', '\r']);
+        if !after.trim_start_matches([' ', '\t']).starts_with('#') {
+            return (range, replacement);
+        }
+        // The comment keeps the whitespace that separates it from the text.
+        let replacement = format!("{}{}\n{}", first, after, rest);
+        range.end += after.len();
+        (range, replacement)
+    }
+
+    /// Deletes the runs of consecutive entries or items whose `removed` flag
+    /// is set, given the range from the first token of each to the end of its
+    /// value. At least one of them must be kept.
+    fn remove(&mut self, units: &[Range<usize>], removed: &[bool]) {
+        let mut i = 0;
+        while i < units.len() {
+            if !removed[i] {
+                i += 1;
+                continue;
+            }
+            let run = i;
+            while i < units.len() && removed[i] {
+                i += 1;
+            }
+            let start = units[run].start;
+            let range = if self.starts_line(start) {
+                self.line_start(start)..self.line_end(units[i - 1].end)
+            } else {
+                // The first entry or item shares its line with the indicator
+                // of an enclosing item, which the next one kept takes over.
+                match units.get(i) {
+                    Some(next) => start..next.start,
+                    None => start..units[i - 1].end,
+                }
+            };
+            self.edits.push(TextEdit {
+                range,
+                replacement: String::new(),
+            });
+        }
+    }
+
+    fn insert(&mut self, at: usize, replacement: String) {
+        self.edits.push(TextEdit {
+            range: at..at,
+            replacement,
+        });
+    }
+
+    /// The end of the text of `value`, without any comments or line breaks
+    /// after it.
+    fn end(&self, value: &Value) -> Result<usize, Error> {
+        let span = value.span();
+        let start = span.start.index;
+        let end = if !span.is_valid() {
+            None
+        } else if is_collection(value) {
+            self.collections.get(&start).map(|(end, _)| *end)
+        } else {
+            self.scalars.get(&start).copied()
+        };
+        end.ok_or_else(|| {
+            error::set_span(
+                Error::custom("cannot find the value in the source"),
+                span.clone(),
+            )
+        })
+    }
+
+    fn is_flow(&self, value: &Value) -> bool {
+        is_collection(value)
+            && self
+                .collections
+                .get(&value.span().start.index)
+                .is_some_and(|(_, flow)| *flow)
+    }
+
+    fn entry_place(&self, key: &Value) -> Result<Place, Error> {
+        let end = self.end(key)?;
+        let rest = &self.source[end..];
+        let colon = end + rest.len() - rest.trim_start_matches(' ').len();
+        if !self.source[colon..].starts_with(':') {
+            return Err(error::set_span(
+                Error::custom("cannot edit the value of a complex mapping key"),
+                key.span().clone(),
+            ));
+        }
+        Ok(Place::Entry {
+            indent: self.column(key.span().start.index),
+            colon: colon + 1,
+        })
+    }
+
+    /// The index of the indicator of a block sequence item.
+    fn indicator(&self, item: &Value) -> Result<usize, Error> {
+        let before = self.source[..item.span().start.index].trim_end();
+        match before.strip_suffix('-') {
+            Some(before) => Ok(before.len()),
+            None => Err(error::set_span(
+                Error::custom("cannot find the indicator of the sequence item"),
+                item.span().clone(),
+            )),
+        }
+    }
+
+    fn line_start(&self, index: usize) -> usize {
+        self.source[..index].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// The start of the line after the one containing `index`.
+    fn line_end(&self, index: usize) -> usize {
+        self.source[index..]
+            .find('\n')
+            .map_or(self.source.len(), |i| index + i + 1)
+    }
+
+    fn column(&self, index: usize) -> usize {
+        index - self.line_start(index)
+    }
+
+    fn starts_line(&self, index: usize) -> bool {
+        self.source[self.line_start(index)..index]
+            .bytes()
+            .all(|b| b == b' ')
+    }
+
+    /// A line break to insert at `at` before new lines, if the source ends
+    /// there without one.
+    fn line_break_before(&self, at: usize) -> String {
+        if at == self.source.len() && !self.source.is_empty() && !self.source.ends_with('\n') {
+            "\n".to_owned()
+        } else {
+            String::new()
+        }
+    }
+}
+
+fn is_collection(value: &Value) -> bool {
+    matches!(value.untag_ref(), Value::Sequence(..) | Value::Mapping(..))
+}
+
+fn to_yaml(value: &Value) -> Result<String, Error> {
+    let mut text = crate::to_string(value)?;
+    text.truncate(text.trim_end_matches('\n').len());
+    Ok(text)
+}
+
+/// Writes `value` for a mapping entry or sequence item whose key or indicator
+/// is at column `indent`, in flow style if it is a collection and `flow` is
+/// set.
+fn render(value: &Value, indent: usize, flow: bool) -> Result<Rendered, Error> {
+    if flow && is_collection(value) {
+        return Ok(Rendered::Inline(to_flow(value)?));
+    }
+    let text = to_yaml(value)?;
+    let nested = match value {
+        Value::Sequence(sequence, ..) => !sequence.is_empty(),
+        Value::Mapping(mapping, ..) => !mapping.is_empty(),
+        _ => false,
+    };
+    if nested {
+        return Ok(Rendered::Block(indent_lines(&text, indent + 2)));
+    }
+    // Block scalars are already indented relative to their parent, tagged
+    // collections are not.
+    let rest_indent = if is_collection(value) {
+        indent + 2
+    } else {
+        indent
+    };
+    Ok(Rendered::Inline(match text.split_once('\n') {
+        Some((first, rest)) => format!("{}\n{}", first, indent_lines(rest, rest_indent)),
+        None => text,
+    }))
+}
+
+fn indent_lines(text: &str, indent: usize) -> String {
+    let prefix = " ".repeat(indent);
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Writes `value` on a single line, in flow style.
+fn to_flow(value: &Value) -> Result<String, Error> {
+    Ok(match value {
+        Value::Sequence(sequence, ..) => {
+            let items = sequence
+                .iter()
+                .map(to_flow)
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Mapping(mapping, ..) => {
+            let mut entries = Vec::with_capacity(mapping.len());
+            for (key, value) in mapping {
+                entries.push(format!("{}: {}", to_flow(key)?, to_flow(value)?));
+            }
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::Tagged(tagged, ..) => format!("{} {}", tagged.tag, to_flow(&tagged.value)?),
+        Value::String(string, ..) => {
+            let text = to_yaml(value)?;
+            let plain = !text.starts_with(['\'', '"']);
+            if text.contains('\n') || plain && text.contains([',', '[', ']', '{', '}']) {
+                double_quoted(string)
+            } else {
+                text
+            }
+        }
+        Value::Null(..) | Value::Bool(..) | Value::Number(..) => to_yaml(value)?,
+    })
+}
+
+fn double_quoted(string: &str) -> String {
+    let mut text = String::with_capacity(string.len() + 2);
+    text.push('"');
+    for ch in string.chars() {
+        match ch {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(text, "\\u{:04x}", ch as u32);
+            }
+            ch => text.push(ch),
+        }
+    }
+    text.push('"');
+    text
+}
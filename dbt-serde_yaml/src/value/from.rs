@@ -1,4 +1,5 @@
-use crate::{Mapping, Value};
+use crate::error::{self, Error};
+use crate::{Mapping, Sequence, Value};
 
 // Implement a bunch of conversion to make it easier to create YAML values
 // on the fly.
@@ -113,6 +114,22 @@ impl From<Mapping> for Value {
     }
 }
 
+impl From<Sequence> for Value {
+    /// Convert a `Sequence` to `Value`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dbt_serde_yaml::{Sequence, Value};
+    ///
+    /// let s: Sequence = vec![Value::from("lorem")].into();
+    /// let x: Value = s.into();
+    /// ```
+    fn from(f: Sequence) -> Self {
+        Value::sequence(f)
+    }
+}
+
 impl<T: Into<Value>> From<Vec<T>> for Value {
     /// Convert a `Vec` to `Value`
     ///
@@ -125,7 +142,7 @@ impl<T: Into<Value>> From<Vec<T>> for Value {
     /// let x: Value = v.into();
     /// ```
     fn from(f: Vec<T>) -> Self {
-        Value::sequence(f.into_iter().map(Into::into).collect())
+        Value::sequence(f.into_iter().map(Into::into).collect::<Sequence>())
     }
 }
 
@@ -141,7 +158,7 @@ impl<'a, T: Clone + Into<Value>> From<&'a [T]> for Value {
     /// let x: Value = v.into();
     /// ```
     fn from(f: &'a [T]) -> Self {
-        Value::sequence(f.iter().cloned().map(Into::into).collect())
+        Value::sequence(f.iter().cloned().map(Into::into).collect::<Sequence>())
     }
 }
 
@@ -171,8 +188,137 @@ impl<T: Into<Value>> FromIterator<T> for Value {
     /// let x: Value = Value::from_iter(vec!["lorem", "ipsum", "dolor"]);
     /// ```
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let vec = iter.into_iter().map(T::into).collect();
+        let vec: Sequence = iter.into_iter().map(T::into).collect();
 
         Value::sequence(vec)
     }
 }
+
+// Checked conversions out of a `Value`. Errors carry the span of the value
+// that failed to convert.
+
+macro_rules! try_from_value {
+    ($($ty:ty, $expected:literal, |$v:ident| $convert:expr;)*) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Error> {
+                    let $v = &value;
+                    match $convert {
+                        Some(converted) => Ok(converted),
+                        None => Err(value.invalid_type(&$expected)),
+                    }
+                }
+            }
+
+            impl TryFrom<&Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &Value) -> Result<Self, Error> {
+                    let $v = value;
+                    match $convert {
+                        Some(converted) => Ok(converted),
+                        None => Err(value.invalid_type(&$expected)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_value! {
+    i64, "i64", |v| v.as_i64();
+    u64, "u64", |v| v.as_u64();
+    f64, "f64", |v| v.as_f64();
+    bool, "a boolean", |v| v.as_bool();
+}
+
+macro_rules! try_from_value_ref {
+    ($($ty:ty, $expected:literal, |$v:ident| $convert:expr;)*) => {
+        $(
+            impl<'a> TryFrom<&'a Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &'a Value) -> Result<Self, Error> {
+                    let $v = value;
+                    match $convert {
+                        Some(converted) => Ok(converted),
+                        None => Err(value.invalid_type(&$expected)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_value_ref! {
+    &'a str, "a string", |v| v.as_str();
+    &'a Sequence, "a sequence", |v| match v.untag_ref() {
+        Value::Sequence(seq, ..) => Some(seq),
+        _ => None,
+    };
+    &'a Vec<Value>, "a sequence", |v| v.as_sequence();
+    &'a Mapping, "a mapping", |v| v.as_mapping();
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    /// Convert a YAML string to `String`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let v: Value = dbt_serde_yaml::from_str("name: orders").unwrap();
+    /// let name = String::try_from(v["name"].clone()).unwrap();
+    /// assert_eq!(name, "orders");
+    ///
+    /// let err = String::try_from(Value::from(1)).unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid type: integer `1`, expected a string");
+    /// ```
+    fn try_from(value: Value) -> Result<Self, Error> {
+        let span = value.span().clone();
+        match value.untag() {
+            Value::String(s, ..) => Ok(super::into_string(s)),
+            other => Err(error::set_span(other.invalid_type(&"a string"), span)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Sequence {
+    type Error = Error;
+
+    /// Convert a YAML sequence to `Sequence`
+    fn try_from(value: Value) -> Result<Self, Error> {
+        let span = value.span().clone();
+        match value.untag() {
+            Value::Sequence(seq, ..) => Ok(seq),
+            other => Err(error::set_span(other.invalid_type(&"a sequence"), span)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<Value> {
+    type Error = Error;
+
+    /// Convert a YAML sequence to `Vec<Value>`
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Sequence::try_from(value).map(Sequence::into_vec)
+    }
+}
+
+impl TryFrom<Value> for Mapping {
+    type Error = Error;
+
+    /// Convert a YAML mapping to `Mapping`
+    fn try_from(value: Value) -> Result<Self, Error> {
+        let span = value.span().clone();
+        match value.untag() {
+            Value::Mapping(map, ..) => Ok(map),
+            other => Err(error::set_span(other.invalid_type(&"a mapping"), span)),
+        }
+    }
+}
@@ -23,6 +23,10 @@ pub trait Index: private::Sealed {
     /// object.
     #[doc(hidden)]
     fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value;
+
+    /// Like `index_or_insert`, but return None where it would panic.
+    #[doc(hidden)]
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
 }
 
 impl Index for usize {
@@ -61,6 +65,16 @@ impl Index for usize {
             }
         }
     }
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v.untag_mut() {
+            Value::Sequence(vec, ..) => vec.get_mut(*self),
+            Value::Mapping(map, ..) => {
+                let n = Value::number((*self).into());
+                Some(map.entry(n).or_insert(Value::null()))
+            }
+            _ => None,
+        }
+    }
 }
 
 fn index_into_mapping<'v, I>(index: &I, v: &'v Value) -> Option<&'v Value>
@@ -83,6 +97,23 @@ where
     }
 }
 
+fn try_index_or_insert_mapping<'v, I>(index: &I, v: &'v mut Value) -> Option<&'v mut Value>
+where
+    I: ?Sized + mapping::Index + ToOwned,
+    Value: From<I::Owned>,
+{
+    let v = v.untag_mut();
+    if let Value::Null(span) = v {
+        *v = Value::Mapping(Mapping::new(), span.clone());
+    }
+    match v {
+        Value::Mapping(map, ..) => {
+            Some(map.entry(index.to_owned().into()).or_insert(Value::null()))
+        }
+        _ => None,
+    }
+}
+
 fn index_or_insert_mapping<'v, I>(index: &I, mut v: &'v mut Value) -> &'v mut Value
 where
     I: ?Sized + mapping::Index + ToOwned + Debug,
@@ -119,6 +150,9 @@ impl Index for Value {
     fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value {
         index_or_insert_mapping(self, v)
     }
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        try_index_or_insert_mapping(self, v)
+    }
 }
 
 impl Index for str {
@@ -131,6 +165,9 @@ impl Index for str {
     fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value {
         index_or_insert_mapping(self, v)
     }
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        try_index_or_insert_mapping(self, v)
+    }
 }
 
 impl Index for String {
@@ -143,6 +180,9 @@ impl Index for String {
     fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value {
         self.as_str().index_or_insert(v)
     }
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_str().try_index_or_insert(v)
+    }
 }
 
 impl<T> Index for &T
@@ -158,6 +198,9 @@ where
     fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value {
         (**self).index_or_insert(v)
     }
+    fn try_index_or_insert<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).try_index_or_insert(v)
+    }
 }
 
 /// Used in panic messages.
@@ -0,0 +1,248 @@
+use crate::error::{self, Error};
+use crate::path::Path;
+use crate::value::{Mapping, Number, Sequence, Value};
+use crate::{Marker, Span};
+use serde::de::Error as _;
+use std::ops::Range;
+
+impl From<serde_json::Value> for Value {
+    /// Converts a JSON value into a YAML value without source locations; see
+    /// [Value::from_json_with_spans] to attach them.
+    fn from(json: serde_json::Value) -> Self {
+        Value::from_json_with_spans(json, |_, _| None)
+    }
+}
+
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    /// Converts a YAML value into a JSON value, dropping source locations.
+    ///
+    /// A tagged value `!Tag value` becomes `{"Tag": value}`, the way serde
+    /// represents an externally tagged enum variant. Mapping keys that are
+    /// null, booleans or numbers become strings of their YAML form, so that
+    /// `1: a` becomes `{"1": "a"}`.
+    ///
+    /// Fails on keys that are sequences, mappings or tagged values, on keys
+    /// that are equal once converted to strings, and on infinite and NaN
+    /// numbers, which JSON cannot represent. The error carries the span of the
+    /// offending node.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("
+    /// materialized: !Incremental id
+    /// 2024: [.inf]
+    /// ").unwrap();
+    ///
+    /// let json = serde_json::Value::try_from(value["materialized"].clone()).unwrap();
+    /// assert_eq!(json, serde_json::json!({"Incremental": "id"}));
+    ///
+    /// let err = serde_json::Value::try_from(value).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "invalid value: floating point `inf`, expected a finite number at line 3 column 8",
+    /// );
+    /// ```
+    fn try_from(value: Value) -> Result<Self, Error> {
+        to_json(&value)
+    }
+}
+
+impl TryFrom<&Value> for serde_json::Value {
+    type Error = Error;
+
+    /// Converts a YAML value into a JSON value, the same way as from an owned
+    /// [`Value`].
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        to_json(value)
+    }
+}
+
+impl Value {
+    /// Converts a JSON value into a YAML value, giving each node the span
+    /// returned by `span_of` for its path, so that diagnostics on data
+    /// ingested as JSON can point into its source like those on YAML input.
+    ///
+    /// Nodes for which `span_of` returns `None` get an invalid
+    /// ([zero](Span::zero)) span. Mapping keys get the span of their value.
+    /// Object entries are converted in the iteration order of
+    /// `serde_json::Map`, which is sorted unless serde_json's
+    /// `preserve_order` feature is enabled.
+    pub fn from_json_with_spans<F>(json: serde_json::Value, mut span_of: F) -> Value
+    where
+        F: FnMut(Path<'_>, &serde_json::Value) -> Option<Span>,
+    {
+        convert(json, Path::Root, &mut span_of)
+    }
+
+    /// Converts a JSON value parsed from `source` into a YAML value, giving
+    /// each node the span of the byte range returned by `offsets` for its
+    /// path, as recorded by a position-tracking JSON parser.
+    ///
+    /// Lines and columns are computed from `source`; offsets out of its bounds
+    /// or not on a character boundary are clamped to the nearest preceding
+    /// one.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let source = "{\"name\": \"orders\"}";
+    /// let json: serde_json::Value = serde_json::from_str(source).unwrap();
+    /// let value = Value::from_json_with_offsets(json, source, |path| {
+    ///     match path.to_string().as_str() {
+    ///         "name" => Some(9..17),
+    ///         _ => None,
+    ///     }
+    /// });
+    /// let span = value["name"].span();
+    /// assert_eq!((span.start.line, span.start.column), (1, 10));
+    /// ```
+    pub fn from_json_with_offsets<F>(json: serde_json::Value, source: &str, mut offsets: F) -> Value
+    where
+        F: FnMut(Path<'_>) -> Option<Range<usize>>,
+    {
+        let lines = LineIndex::new(source);
+        Value::from_json_with_spans(json, |path, _| {
+            offsets(path).map(|range| Span::new(lines.marker(range.start), lines.marker(range.end)))
+        })
+    }
+}
+
+fn convert(
+    json: serde_json::Value,
+    path: Path,
+    span_of: &mut dyn FnMut(Path<'_>, &serde_json::Value) -> Option<Span>,
+) -> Value {
+    let span = span_of(path, &json).unwrap_or_default();
+    match json {
+        serde_json::Value::Null => Value::Null(span),
+        serde_json::Value::Bool(b) => Value::Bool(b, span),
+        serde_json::Value::Number(n) => Value::Number(number(&n), span),
+        serde_json::Value::String(s) => Value::String(s.into(), span),
+        serde_json::Value::Array(array) => {
+            let sequence = array
+                .into_iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    convert(
+                        element,
+                        Path::Seq {
+                            parent: &path,
+                            index,
+                        },
+                        span_of,
+                    )
+                })
+                .collect::<Sequence>();
+            Value::Sequence(sequence, span)
+        }
+        serde_json::Value::Object(object) => {
+            let mut mapping = Mapping::with_capacity(object.len());
+            for (key, value) in object {
+                let value = convert(
+                    value,
+                    Path::Map {
+                        parent: &path,
+                        key: &key,
+                    },
+                    span_of,
+                );
+                let key = Value::String(key.into(), value.span().clone());
+                mapping.insert(key, value);
+            }
+            Value::Mapping(mapping, span)
+        }
+    }
+}
+
+fn to_json(value: &Value) -> Result<serde_json::Value, Error> {
+    Ok(match value {
+        Value::Null(..) => serde_json::Value::Null,
+        Value::Bool(b, ..) => serde_json::Value::Bool(*b),
+        Value::Number(n, ..) => serde_json::Value::Number(json_number(n).ok_or_else(|| {
+            error::set_span(
+                Error::invalid_value(value.unexpected(), &"a finite number"),
+                value.span().clone(),
+            )
+        })?),
+        Value::String(s, ..) => serde_json::Value::String(s.to_string()),
+        Value::Sequence(sequence, ..) => {
+            serde_json::Value::Array(sequence.iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Value::Mapping(mapping, ..) => {
+            let mut object = serde_json::Map::with_capacity(mapping.len());
+            for (key, value) in mapping {
+                let name = match key {
+                    Value::String(s, ..) => s.to_string(),
+                    Value::Null(..) => "null".to_owned(),
+                    Value::Bool(b, ..) => b.to_string(),
+                    Value::Number(n, ..) => n.to_string(),
+                    _ => return Err(key.invalid_type(&"a scalar mapping key")),
+                };
+                if object.contains_key(&name) {
+                    let msg = format!("duplicate entry with key {:?}", name);
+                    return Err(error::set_span(Error::custom(msg), key.span().clone()));
+                }
+                object.insert(name, to_json(value)?);
+            }
+            serde_json::Value::Object(object)
+        }
+        Value::Tagged(tagged, ..) => {
+            let mut object = serde_json::Map::with_capacity(1);
+            let tag = tagged.tag.to_string();
+            let tag = tag.strip_prefix('!').unwrap_or(&tag).to_owned();
+            object.insert(tag, to_json(&tagged.value)?);
+            serde_json::Value::Object(object)
+        }
+    })
+}
+
+fn json_number(n: &Number) -> Option<serde_json::Number> {
+    if let Some(i) = n.as_i64() {
+        Some(i.into())
+    } else if let Some(u) = n.as_u64() {
+        Some(u.into())
+    } else {
+        n.as_f64().and_then(serde_json::Number::from_f64)
+    }
+}
+
+fn number(n: &serde_json::Number) -> Number {
+    if let Some(u) = n.as_u64() {
+        Number::from(u)
+    } else if let Some(i) = n.as_i64() {
+        Number::from(i)
+    } else {
+        n.to_string()
+            .parse()
+            .unwrap_or_else(|_| Number::from(n.as_f64().unwrap_or(f64::NAN)))
+    }
+}
+
+/// Byte offsets of the start of each line of a source text.
+struct LineIndex<'a> {
+    source: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { source, starts }
+    }
+
+    fn marker(&self, index: usize) -> Marker {
+        let mut index = index.min(self.source.len());
+        while !self.source.is_char_boundary(index) {
+            index -= 1;
+        }
+        let line = self.starts.partition_point(|&start| start <= index);
+        let line_start = self.starts[line - 1];
+        let column = self.source[line_start..index].chars().count() + 1;
+        Marker::new(index, line, column)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::Value;
+
+/// Controls how [`Value::deep_merge`] combines two values.
+///
+/// The default strategy recurses into mappings, replaces sequences, and lets a
+/// null on the incoming side overwrite the existing value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeStrategy {
+    recurse_mappings: bool,
+    append_sequences: bool,
+    ignore_nulls: bool,
+}
+
+impl MergeStrategy {
+    /// Creates the default merge strategy.
+    pub fn new() -> Self {
+        MergeStrategy {
+            recurse_mappings: true,
+            append_sequences: false,
+            ignore_nulls: false,
+        }
+    }
+
+    /// Whether two mappings are merged key by key (the default) or the
+    /// incoming mapping replaces the existing one wholesale.
+    pub fn recurse_mappings(mut self, recurse: bool) -> Self {
+        self.recurse_mappings = recurse;
+        self
+    }
+
+    /// Whether the elements of an incoming sequence are appended to an
+    /// existing sequence, rather than replacing it (the default).
+    pub fn append_sequences(mut self, append: bool) -> Self {
+        self.append_sequences = append;
+        self
+    }
+
+    /// Whether an incoming null leaves the existing value untouched, rather
+    /// than overwriting it (the default).
+    pub fn ignore_nulls(mut self, ignore: bool) -> Self {
+        self.ignore_nulls = ignore;
+        self
+    }
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        MergeStrategy::new()
+    }
+}
+
+impl Value {
+    /// Merges `other` into this value according to `strategy`.
+    ///
+    /// Values that cannot be combined, such as two scalars or a mapping and a
+    /// sequence, are resolved in favour of `other`. Unlike
+    /// [`apply_merge`](Value::apply_merge), `<<` keys are not treated
+    /// specially.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::MergeStrategy;
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let mut base: Value = dbt_serde_yaml::from_str("
+    /// target: dev
+    /// outputs:
+    ///   dev: {threads: 1, schema: analytics}
+    /// tags: [a]
+    /// ").unwrap();
+    /// let overlay: Value = dbt_serde_yaml::from_str("
+    /// outputs:
+    ///   dev: {threads: 4}
+    /// tags: [b]
+    /// ").unwrap();
+    ///
+    /// base.deep_merge(overlay, MergeStrategy::new().append_sequences(true));
+    ///
+    /// assert_eq!(base["target"], "dev");
+    /// assert_eq!(base["outputs"]["dev"]["threads"], 4);
+    /// assert_eq!(base["outputs"]["dev"]["schema"], "analytics");
+    /// assert_eq!(base["tags"][0], "a");
+    /// assert_eq!(base["tags"][1], "b");
+    /// ```
+    pub fn deep_merge(&mut self, other: Value, strategy: MergeStrategy) {
+        match (self, other) {
+            (_, Value::Null(..)) if strategy.ignore_nulls => {}
+            (Value::Mapping(mapping, ..), Value::Mapping(other, ..))
+                if strategy.recurse_mappings =>
+            {
+                for (key, value) in other {
+                    match mapping.get_mut(&key) {
+                        Some(existing) => existing.deep_merge(value, strategy),
+                        None => {
+                            if !(strategy.ignore_nulls && value.is_null()) {
+                                mapping.insert(key, value);
+                            }
+                        }
+                    }
+                }
+            }
+            (Value::Sequence(sequence, ..), Value::Sequence(other, ..))
+                if strategy.append_sequences =>
+            {
+                sequence.extend(other);
+            }
+            (this, other) => *this = other,
+        }
+    }
+}
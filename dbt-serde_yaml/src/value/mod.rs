@@ -2,35 +2,88 @@
 
 mod de;
 mod debug;
+mod diff;
+mod display;
+mod edit;
 mod from;
 mod index;
+#[cfg(feature = "json")]
+mod json;
+mod merge;
 mod partial_eq;
+mod reparse;
+mod scalar;
+mod sequence;
 mod ser;
+#[cfg(feature = "yaml_string")]
+mod string;
 pub(crate) mod tagged;
+#[cfg(feature = "toml")]
+mod toml;
+pub mod transform;
+pub mod unused_keys;
+mod variant;
+mod walk;
 
 use crate::error::{self, Error, ErrorImpl};
+use crate::path::{Path, PathComponent};
 use crate::{spanned, Span};
 use serde::de::{Deserialize, DeserializeOwned, IntoDeserializer};
 use serde::Serialize;
 use std::hash::{Hash, Hasher};
 use std::mem;
 
+pub use self::diff::{ValueChange, ValuePatch};
+pub use self::edit::TextEdit;
 pub use self::index::Index;
+pub use self::merge::MergeStrategy;
+pub use self::reparse::SourceEdit;
+pub use self::scalar::ScalarInfo;
+pub use self::sequence::Sequence;
 pub use self::ser::Serializer;
+#[cfg(feature = "yaml_string")]
+pub use self::string::YamlString;
 pub use self::tagged::{Tag, TaggedValue};
+pub use self::variant::{with_lenient_variants, NormalizedVariant};
 #[doc(inline)]
 pub use crate::mapping::Mapping;
 pub use crate::number::Number;
 #[doc(inline)]
-pub(crate) use de::ValueVisitor;
+pub(crate) use de::{save_built_value, take_deserializing_value, ValueDeserializer, ValueVisitor};
+#[cfg(feature = "round_trip")]
+pub(crate) use ser::serializing_style;
+pub(crate) use ser::{offer_emit, serializing_value};
+#[cfg(feature = "intern")]
+pub(crate) use string::intern;
+#[cfg(feature = "round_trip")]
+pub(crate) use string::with_parsing_style;
 
 pub use de::extract_reusable_deserializer_state;
 pub use de::extract_tag_and_deserializer_state;
 pub use de::DeserializerState;
 pub use de::DuplicateKeyCallback;
 pub use de::FieldTransformer;
+pub use de::FromValueOptions;
+pub use de::SpannedValueDeserializer;
+pub use de::TransformPolicy;
 pub use de::TransformedResult;
 pub use de::UnusedKeyCallback;
+pub use de::UnusedKeyCollector;
+pub use de::UnusedKeys;
+
+/// The string held by a [Value::String].
+///
+/// This is a `String`, unless one of the `intern`, `round_trip` and
+/// `large_scalars` features is enabled, which need the string to carry more.
+#[cfg(not(feature = "yaml_string"))]
+pub type YamlString = String;
+
+/// Converts the string of a [Value::String] into a `String`, which it may
+/// already be.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn into_string(string: YamlString) -> String {
+    string.into()
+}
 
 /// Represents any valid YAML value.
 #[derive(Clone)]
@@ -42,7 +95,7 @@ pub enum Value {
     /// Represents a YAML numerical value, whether integer or floating point.
     Number(Number, Span),
     /// Represents a YAML string.
-    String(String, Span),
+    String(YamlString, Span),
     /// Represents a YAML sequence in which the elements are
     /// `dbt_serde_yaml::Value`.
     Sequence(Sequence, Span),
@@ -119,9 +172,6 @@ impl Default for Value {
     }
 }
 
-/// A YAML sequence in which the elements are `dbt_serde_yaml::Value`.
-pub type Sequence = Vec<Value>;
-
 /// Convert a `T` into `dbt_serde_yaml::Value` which is an enum that can represent
 /// any valid YAML data.
 ///
@@ -140,6 +190,48 @@ where
     value.serialize(Serializer)
 }
 
+/// Convert a `T` into a [Value] like [to_value], taking the spans of the
+/// result from `template`, a value of the same shape such as the one `T` was
+/// deserialized from.
+///
+/// This keeps values that are modified and serialized again pointing at where
+/// they were written in the source, for diagnostics about them later on.
+/// Nodes of the result are matched with those of the template at the same
+/// place: mapping keys and values by key, sequence items by index, and the
+/// contents of tagged values to each other, or to the template looked through
+/// its tags. Each node that has no valid span of its own, i.e. that wasn't
+/// serialized from a [Spanned](crate::Spanned), gets the span of its match.
+/// Nodes with no match, like entries added since, keep the span they have.
+///
+/// ```
+/// use dbt_serde_yaml::Value;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Model {
+///     name: String,
+///     tags: Vec<String>,
+/// }
+///
+/// let yaml = "name: orders\ntags: [daily]\n";
+/// let template: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+/// let mut model: Model = dbt_serde_yaml::from_value(template.clone()).unwrap();
+/// model.tags.push("finance".to_owned());
+///
+/// let value = dbt_serde_yaml::to_value_with_spans(&model, &template).unwrap();
+/// assert_eq!(value["name"].span().start.line, 1);
+/// assert_eq!(value["tags"][0].span().start.column, 8);
+/// assert!(!value["tags"][1].span().is_valid());
+/// ```
+pub fn to_value_with_spans<T>(value: &T, template: &Value) -> Result<Value, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut value = to_value(value)?;
+    value.copy_spans_from(template);
+    Ok(value)
+}
+
 /// Interpret a `dbt_serde_yaml::Value` as an instance of type `T`.
 ///
 /// This conversion can fail if the structure of the Value does not match the
@@ -160,10 +252,9 @@ pub fn from_value<T>(value: Value) -> Result<T, Error>
 where
     T: DeserializeOwned,
 {
+    let _marker = spanned::enter_marker(value.span().start);
     value.broadcast_start_mark();
-    let res = Deserialize::deserialize(value.into_deserializer());
-    spanned::reset_marker();
-    res
+    Deserialize::deserialize(value.into_deserializer())
 }
 
 impl Value {
@@ -233,6 +324,246 @@ impl Value {
         index.index_into_mut(self)
     }
 
+    /// Index into a YAML sequence or map like `value[index]` does mutably,
+    /// but returning `None` where that would panic.
+    ///
+    /// Unlike [`get_mut`](Value::get_mut), a key missing from a map is
+    /// inserted with a null value, and a null is first replaced by an empty
+    /// map when indexed with a string, so that nested values can be filled in
+    /// one step at a time. Returns `None` if the index is out of the bounds of
+    /// a sequence, or if `self` cannot be indexed by it, like a string indexed
+    /// by a key.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let mut value: Value = dbt_serde_yaml::from_str("models: [{name: orders}]")?;
+    ///
+    /// let config = value
+    ///     .try_index_mut("models")
+    ///     .and_then(|models| models.try_index_mut(0))
+    ///     .and_then(|model| model.try_index_mut("config"))
+    ///     .unwrap();
+    /// *config.try_index_mut("materialized").unwrap() = Value::from("table");
+    /// assert_eq!(value["models"][0]["config"]["materialized"], "table");
+    ///
+    /// assert!(value.try_index_mut("models").unwrap().try_index_mut(1).is_none());
+    /// assert!(value["models"][0]["name"].try_index_mut("first").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_index_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.try_index_or_insert(self)
+    }
+
+    /// Looks up the value at `path`, as reported to callbacks or parsed into an
+    /// [`OwnedPath`](crate::path::OwnedPath), relative to this value.
+    ///
+    /// Each component is resolved like [`get`](Value::get). Aliases are
+    /// skipped, and a path with an unknown component matches nothing.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::OwnedPath;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let value: Value = dbt_serde_yaml::from_str("models: [{name: a, tags: [x, y]}]")?;
+    /// let path: OwnedPath = "models[0].tags[1]".parse()?;
+    ///
+    /// assert_eq!(value.get_path(path.as_path()).unwrap(), "y");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: &Path) -> Option<&Value> {
+        path.components()
+            .try_fold(self, |value, component| match component {
+                PathComponent::Index(index) => value.get(index),
+                PathComponent::Key(key) => value.get(key),
+                PathComponent::Alias => Some(value),
+                PathComponent::Unknown => None,
+            })
+    }
+
+    /// Mutably looks up the value at `path` relative to this value. See
+    /// [`get_path`](Value::get_path).
+    pub fn get_path_mut(&mut self, path: &Path) -> Option<&mut Value> {
+        get_components_mut(self, path.components())
+    }
+
+    /// Mutably looks up the value at `path` relative to this value, creating
+    /// it if it is missing. Each component is resolved like
+    /// [`try_index_mut`](Value::try_index_mut), so missing keys are inserted
+    /// with null values and nulls along the way are replaced by maps.
+    ///
+    /// Returns `None` if an index is out of bounds, a scalar other than null
+    /// is in the way, or the path has an unknown component. The keys inserted
+    /// before that point are left in place.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::OwnedPath;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let mut value: Value = dbt_serde_yaml::from_str("models: [{name: orders}]")?;
+    ///
+    /// let path: OwnedPath = "models[0].config.tags".parse()?;
+    /// let tags = value.get_path_mut_or_insert(path.as_path()).unwrap();
+    /// assert!(tags.is_null());
+    /// *tags = Value::from(vec!["nightly"]);
+    /// assert_eq!(value["models"][0]["config"]["tags"][0], "nightly");
+    ///
+    /// let path: OwnedPath = "models[1].config".parse()?;
+    /// assert!(value.get_path_mut_or_insert(path.as_path()).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_path_mut_or_insert(&mut self, path: &Path) -> Option<&mut Value> {
+        path.components()
+            .try_fold(self, |value, component| match component {
+                PathComponent::Index(index) => value.try_index_mut(index),
+                PathComponent::Key(key) => value.try_index_mut(key),
+                PathComponent::Alias => Some(value),
+                PathComponent::Unknown => None,
+            })
+    }
+
+    /// Removes the value at `path` relative to this value and returns it,
+    /// together with its span.
+    ///
+    /// A mapping entry is removed like [`Mapping::shift_remove`], keeping the
+    /// order of the other entries, and a sequence element like
+    /// [`Vec::remove`]. Taking the root path leaves null in place of this
+    /// value. Returns `None`, and leaves this value unchanged, if there is no
+    /// value at `path`.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::OwnedPath;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let mut value: Value = dbt_serde_yaml::from_str("
+    /// models:
+    ///   orders:
+    ///     config: {materialized: table}
+    /// ")?;
+    /// let path: OwnedPath = "models.orders.config".parse()?;
+    ///
+    /// let config = value.take_at_path(path.as_path()).unwrap();
+    /// assert_eq!(config["materialized"], "table");
+    /// assert_eq!(config.span().start.line(), 4);
+    /// assert_eq!(value, dbt_serde_yaml::from_str::<Value>("models: {orders: {}}")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_at_path(&mut self, path: &Path) -> Option<Value> {
+        let mut components: Vec<PathComponent> = path
+            .components()
+            .filter(|component| *component != PathComponent::Alias)
+            .collect();
+        let Some(last) = components.pop() else {
+            return Some(mem::replace(self, Value::null()));
+        };
+        let parent = get_components_mut(self, components)?;
+        match (last, parent.untag_mut()) {
+            (PathComponent::Key(key), Value::Mapping(map, ..)) => map.shift_remove(key),
+            (PathComponent::Index(index), Value::Sequence(seq, ..)) => {
+                (index < seq.len()).then(|| seq.remove(index))
+            }
+            (PathComponent::Index(index), Value::Mapping(map, ..)) => {
+                map.shift_remove(Value::number(index.into()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Inserts `value` at `path` relative to this value, creating the
+    /// mappings leading to it as needed, and returns the value it replaces.
+    ///
+    /// Missing keys along `path` are added with empty mappings, and nulls
+    /// along it are replaced by mappings, so that overrides can be applied to
+    /// configs that don't mention them yet. An existing key keeps its
+    /// position and span. An index one past the end of a sequence appends
+    /// `value` to it.
+    ///
+    /// Fails, leaving the mappings created so far in place, if an index is
+    /// out of bounds or if a scalar other than null is in the way.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::path::OwnedPath;
+    /// # use dbt_serde_yaml::Value;
+    /// #
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// let mut value: Value = dbt_serde_yaml::from_str("models: {orders: {}}")?;
+    /// let path: OwnedPath = "models.orders.config.materialized".parse()?;
+    ///
+    /// let previous = value.insert_at_path(path.as_path(), Value::from("table"))?;
+    /// assert_eq!(previous, None);
+    /// assert_eq!(value["models"]["orders"]["config"]["materialized"], "table");
+    ///
+    /// let path: OwnedPath = "models.orders.config.materialized.kind".parse()?;
+    /// let err = value.insert_at_path(path.as_path(), Value::from("x")).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "expected a mapping or sequence at path models.orders.config.materialized",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_at_path(&mut self, path: &Path, value: Value) -> Result<Option<Value>, Error> {
+        // The paths to each node from the root down, one per component.
+        let mut nodes = Vec::new();
+        let mut node = *path;
+        loop {
+            node = match node {
+                Path::Root => break,
+                Path::Alias { parent } => *parent,
+                Path::Seq { parent, .. } | Path::Map { parent, .. } | Path::Unknown { parent } => {
+                    nodes.push(node);
+                    *parent
+                }
+            };
+        }
+
+        let mut current = self;
+        while let Some(node) = nodes.pop() {
+            let target = current.untag_mut();
+            if let (Path::Map { .. }, Value::Null(span)) = (node, &*target) {
+                *target = Value::Mapping(Mapping::new(), span.clone());
+            }
+            current = match (node, target) {
+                (Path::Map { key, .. }, Value::Mapping(map, ..)) => {
+                    let key = Value::string(key.to_owned());
+                    if nodes.is_empty() {
+                        return Ok(map.insert(key, value));
+                    }
+                    map.entry(key).or_insert(Value::null())
+                }
+                (Path::Seq { index, .. }, Value::Sequence(seq, ..)) => {
+                    if index == seq.len() && nodes.is_empty() {
+                        seq.push(value);
+                        return Ok(None);
+                    }
+                    let Some(element) = seq.get_mut(index) else {
+                        return Err(error::new(ErrorImpl::NoValueAtPath(node.to_string())));
+                    };
+                    if nodes.is_empty() {
+                        return Ok(Some(mem::replace(element, value)));
+                    }
+                    element
+                }
+                (Path::Unknown { .. }, _) => {
+                    return Err(error::new(ErrorImpl::NoValueAtPath(node.to_string())));
+                }
+                (Path::Seq { parent, .. } | Path::Map { parent, .. }, _) => {
+                    return Err(error::new(ErrorImpl::NotACollection(parent.to_string())));
+                }
+                (Path::Root | Path::Alias { .. }, _) => unreachable!(),
+            };
+        }
+        Ok(Some(mem::replace(current, value)))
+    }
+
     /// Returns true if the `Value` is a Null. Returns false otherwise.
     ///
     /// For any Value on which `is_null` returns true, `as_null` is guaranteed
@@ -541,7 +872,7 @@ impl Value {
     /// let v: Value = dbt_serde_yaml::from_str("false").unwrap();
     /// assert_eq!(v.as_sequence(), None);
     /// ```
-    pub fn as_sequence(&self) -> Option<&Sequence> {
+    pub fn as_sequence(&self) -> Option<&Vec<Value>> {
         match self.untag_ref() {
             Value::Sequence(seq, ..) => Some(seq),
             _ => None,
@@ -564,7 +895,7 @@ impl Value {
     /// let mut v: Value = dbt_serde_yaml::from_str("false").unwrap();
     /// assert_eq!(v.as_sequence_mut(), None);
     /// ```
-    pub fn as_sequence_mut(&mut self) -> Option<&mut Sequence> {
+    pub fn as_sequence_mut(&mut self) -> Option<&mut Vec<Value>> {
         match self.untag_mut() {
             Value::Sequence(seq, ..) => Some(seq),
             _ => None,
@@ -765,6 +1096,74 @@ impl Value {
         }
     }
 
+    /// Resets the spans of this value and of everything nested in it, keys
+    /// included, to [Span::zero], releasing their filenames.
+    ///
+    /// Spans don't take part in equality or hashing, but are part of the
+    /// serialized form of a [Spanned](crate::Spanned) and of the `Debug`
+    /// output, which become independent of the source once cleared.
+    pub fn clear_spans(&mut self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.set_span(Span::zero());
+            match node {
+                Value::Sequence(sequence, ..) => stack.extend(sequence),
+                Value::Mapping(mapping, ..) => {
+                    for (key, value) in mapping.iter_mut_with_keys() {
+                        stack.push(key);
+                        stack.push(value);
+                    }
+                }
+                Value::Tagged(tagged, ..) => stack.push(&mut tagged.value),
+                _ => {}
+            }
+        }
+    }
+
+    /// Gives every node of this value without a valid span the span of the
+    /// node at the same place in `template`; see [to_value_with_spans].
+    fn copy_spans_from(&mut self, template: &Value) {
+        let mut stack = vec![(self, template)];
+        while let Some((node, template)) = stack.pop() {
+            if !node.span().is_valid() {
+                node.set_span(template.span().clone());
+            }
+            let template = match node {
+                Value::Tagged(..) => template,
+                _ => template.untag_ref(),
+            };
+            match (node, template) {
+                (Value::Sequence(sequence, ..), Value::Sequence(template, ..)) => {
+                    stack.extend(sequence.iter_mut().zip(template));
+                }
+                (Value::Mapping(mapping, ..), Value::Mapping(template, ..)) => {
+                    for (key, value) in mapping.iter_mut_with_keys() {
+                        if let Some((template_key, template_value)) = template.get_key_value(&*key)
+                        {
+                            stack.push((key, template_key));
+                            stack.push((value, template_value));
+                        }
+                    }
+                }
+                (Value::Tagged(tagged, ..), Value::Tagged(template, ..)) => {
+                    stack.push((&mut tagged.value, &template.value));
+                }
+                (Value::Tagged(tagged, ..), template) => {
+                    stack.push((&mut tagged.value, template));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns a copy of this value with all spans reset, as by
+    /// [clear_spans](Value::clear_spans).
+    pub fn without_spans(&self) -> Value {
+        let mut value = self.clone();
+        value.clear_spans();
+        value
+    }
+
     fn broadcast_start_mark(&self) {
         spanned::set_marker(self.span().start);
         #[cfg(feature = "filename")]
@@ -800,13 +1199,20 @@ impl Value {
     }
 
     /// Construct a String Value with no location information.
+    #[cfg(not(feature = "yaml_string"))]
     pub const fn string(s: String) -> Value {
         Value::String(s, Span::zero())
     }
 
+    /// Construct a String Value with no location information.
+    #[cfg(feature = "yaml_string")]
+    pub fn string(s: String) -> Value {
+        Value::String(s.into(), Span::zero())
+    }
+
     /// Construct a Sequence Value with no location information.
-    pub fn sequence(seq: Sequence) -> Value {
-        Value::Sequence(seq, Span::zero())
+    pub fn sequence(seq: impl Into<Sequence>) -> Value {
+        Value::Sequence(seq.into(), Span::zero())
     }
 
     /// Construct a Mapping Value with no location information.
@@ -857,3 +1263,17 @@ impl schemars::JsonSchema for Value {
         true.into()
     }
 }
+
+fn get_components_mut<'v, 'p>(
+    value: &'v mut Value,
+    components: impl IntoIterator<Item = PathComponent<'p>>,
+) -> Option<&'v mut Value> {
+    components
+        .into_iter()
+        .try_fold(value, |value, component| match component {
+            PathComponent::Index(index) => value.get_mut(index),
+            PathComponent::Key(key) => value.get_mut(key),
+            PathComponent::Alias => Some(value),
+            PathComponent::Unknown => None,
+        })
+}
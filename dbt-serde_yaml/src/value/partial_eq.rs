@@ -10,7 +10,7 @@ impl PartialEq<str> for Value {
     /// assert!(Value::string("lorem".into()) == *"lorem");
     /// ```
     fn eq(&self, other: &str) -> bool {
-        self.as_str() == Some(other)
+        matches!(self.untag_ref(), Value::String(s, ..) if s == other)
     }
 }
 
@@ -24,7 +24,7 @@ impl PartialEq<&str> for Value {
     /// assert!(Value::string("lorem".into()) == "lorem");
     /// ```
     fn eq(&self, other: &&str) -> bool {
-        self.as_str() == Some(*other)
+        matches!(self.untag_ref(), Value::String(s, ..) if s == *other)
     }
 }
 
@@ -38,7 +38,7 @@ impl PartialEq<String> for Value {
     /// assert!(Value::string("lorem".into()) == "lorem".to_string());
     /// ```
     fn eq(&self, other: &String) -> bool {
-        self.as_str() == Some(other.as_str())
+        matches!(self.untag_ref(), Value::String(s, ..) if s == other)
     }
 }
 
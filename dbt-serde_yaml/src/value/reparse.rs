@@ -0,0 +1,481 @@
+use crate::path::OwnedPath;
+use crate::value::Value;
+use crate::{Error, Marker, Span};
+use std::cell::OnceCell;
+use std::ops::Range;
+
+/// An edit of the source text of a [Value]: the bytes in `range` of the old
+/// text were replaced by `new_len` bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceEdit {
+    /// The replaced bytes of the old text.
+    pub range: Range<usize>,
+    /// The length in bytes of the text that replaced them.
+    pub new_len: usize,
+}
+
+impl Value {
+    /// Updates a value parsed from YAML text with [from_str](crate::from_str)
+    /// to reflect an edit of that text, re-parsing only the part of the text
+    /// affected by the edit.
+    ///
+    /// The smallest block mapping entry or block sequence item whose lines
+    /// contain the edit is parsed again on its own and spliced into the tree in
+    /// place of the old one; the spans of everything after it are shifted to
+    /// match the new text. If no such entry or item can be re-parsed in
+    /// isolation with the same meaning it has in the whole document, e.g.
+    /// because the edit changes its indentation or the document uses anchors,
+    /// the whole of `new_source` is parsed again instead. Either way, the
+    /// result is the same as parsing `new_source` from scratch.
+    ///
+    /// Returns the path of the value that was re-parsed, which is the root for
+    /// a full parse. On error, e.g. if the edit left the text invalid, the
+    /// value is unchanged.
+    ///
+    /// `self` must not have been modified since it was parsed from the old
+    /// text, or since its last update.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::value::SourceEdit;
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let old = "models:\n  - name: orders\n  - name: customers\n";
+    /// let mut value: Value = dbt_serde_yaml::from_str(old).unwrap();
+    ///
+    /// let new = "models:\n  - name: order_items\n  - name: customers\n";
+    /// let edit = SourceEdit { range: 18..24, new_len: 11 };
+    /// let path = value.reparse(new, &edit).unwrap();
+    ///
+    /// assert_eq!(path.to_string(), "models[0].name");
+    /// assert_eq!(value, dbt_serde_yaml::from_str::<Value>(new).unwrap());
+    /// assert_eq!(value["models"][1]["name"].span().start.line, 3);
+    /// ```
+    pub fn reparse(&mut self, new_source: &str, edit: &SourceEdit) -> Result<OwnedPath, Error> {
+        #[cfg(feature = "filename")]
+        let _scope = crate::spanned::with_filename(self.span().get_filename().map(Into::into));
+
+        if let Some(path) = Reparse::new(new_source, edit).and_then(|reparse| reparse.run(self)) {
+            return Ok(path);
+        }
+        *self = crate::from_str(new_source)?;
+        Ok(OwnedPath::Root)
+    }
+}
+
+/// The position of a value in its parent collection.
+#[derive(Clone, Copy)]
+enum Slot {
+    Entry(usize),
+    Item(usize),
+}
+
+/// A block mapping entry or block sequence item containing the edit.
+struct Unit {
+    slots: Vec<Slot>,
+    /// The bytes of the lines of the unit in the old text, up to the next
+    /// token after it or the end of the text.
+    region: Range<usize>,
+    /// The line on which the region starts.
+    first_line: usize,
+    /// The index of the first token of the unit: the key of an entry, or the
+    /// value of an item.
+    first_index: usize,
+    /// The column of the first token of the unit.
+    first_column: usize,
+    /// The position of the next token after the unit in the old text, where
+    /// the region ends unless it ends at the end of the text.
+    next: Marker,
+}
+
+struct Reparse<'a> {
+    source: &'a str,
+    /// The start of the edit.
+    start: usize,
+    /// The end of the edit in the old text.
+    old_end: usize,
+    /// The end of the edit in the new text.
+    new_end: usize,
+    delta: isize,
+    old_len: usize,
+}
+
+impl<'a> Reparse<'a> {
+    fn new(source: &'a str, edit: &SourceEdit) -> Option<Self> {
+        let new_end = edit.range.start.checked_add(edit.new_len)?;
+        let old_len = (source.len() + edit.range.len()).checked_sub(edit.new_len)?;
+        if edit.range.start > edit.range.end || new_end > source.len() {
+            return None;
+        }
+        Some(Reparse {
+            source,
+            start: edit.range.start,
+            old_end: edit.range.end,
+            new_end,
+            delta: edit.new_len as isize - edit.range.len() as isize,
+            old_len,
+        })
+    }
+
+    fn run(&self, root: &mut Value) -> Option<OwnedPath> {
+        if has_anchors(self.source) {
+            return None;
+        }
+        let units = self.units(root);
+        units
+            .iter()
+            .rev()
+            .find_map(|unit| self.reparse_unit(root, unit))
+    }
+
+    /// The units containing the edit, outermost first.
+    fn units(&self, root: &Value) -> Vec<Unit> {
+        let mut units = Vec::new();
+        let mut slots = Vec::new();
+        let mut node = root;
+        let mut node_start = content_start(self.source);
+        loop {
+            // Flow collections can't be split into lines.
+            if node_start > self.start
+                || matches!(self.source.as_bytes().get(node_start), Some(b'[' | b'{'))
+            {
+                break;
+            }
+            let (slot, first, end, child) = match node {
+                Value::Mapping(mapping, ..) => {
+                    let Some((i, (key, value))) = mapping
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, (key, _))| key.span().start.index <= self.start)
+                        .last()
+                    else {
+                        break;
+                    };
+                    if !key.span().is_valid() {
+                        break;
+                    }
+                    (Slot::Entry(i), &key.span().start, &value.span().end, value)
+                }
+                Value::Sequence(sequence, ..) => {
+                    let Some((i, item)) = sequence
+                        .iter()
+                        .enumerate()
+                        .rfind(|(_, item)| item.span().start.index <= self.start)
+                    else {
+                        break;
+                    };
+                    (Slot::Item(i), &item.span().start, &item.span().end, item)
+                }
+                _ => break,
+            };
+            if !child.span().is_valid() {
+                break;
+            }
+
+            let Some(before) = self.source.get(..first.index) else {
+                break;
+            };
+            let region_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let prefix = &before[region_start..];
+            let prefix_ok = prefix.bytes().all(|b| b == b' ' || b == b'-')
+                && (matches!(slot, Slot::Entry(_)) || prefix.trim_end().ends_with('-'));
+            if !prefix_ok {
+                break;
+            }
+
+            let region_end = if end.index == self.old_len {
+                self.old_len
+            } else {
+                // The region ends at the start of the line of the next token,
+                // which must come after the edit.
+                if end.index < self.old_end {
+                    break;
+                }
+                let next = (end.index as isize + self.delta) as usize;
+                let Some(line_start) = self.source.get(..next).and_then(|s| s.rfind('\n')) else {
+                    break;
+                };
+                let line_start = line_start + 1;
+                if line_start <= self.new_end
+                    || !self.source[line_start..next]
+                        .bytes()
+                        .all(|b| b == b' ' || b == b'-')
+                {
+                    break;
+                }
+                (line_start as isize - self.delta) as usize
+            };
+
+            slots.push(slot);
+            units.push(Unit {
+                slots: slots.clone(),
+                region: region_start..region_end,
+                first_line: first.line,
+                first_index: first.index,
+                first_column: first.column,
+                next: *end,
+            });
+            node = child;
+            node_start = child.span().start.index;
+        }
+        units
+    }
+
+    fn reparse_unit(&self, root: &mut Value, unit: &Unit) -> Option<OwnedPath> {
+        let new_region_end = (unit.region.end as isize + self.delta) as usize;
+        let text = self.source.get(unit.region.start..new_region_end)?;
+
+        // Blank out the indicators of enclosing sequence items that share the
+        // first line, keeping the columns of everything else.
+        let prefix_len = unit.first_index - unit.region.start;
+        let keep = match unit.slots.last()? {
+            Slot::Entry(_) => None,
+            Slot::Item(_) => text[..prefix_len].rfind('-'),
+        };
+        let mut fragment = String::with_capacity(text.len());
+        for (i, ch) in text[..prefix_len].char_indices() {
+            fragment.push(if ch == '-' && Some(i) != keep {
+                ' '
+            } else {
+                ch
+            });
+        }
+        fragment.push_str(&text[prefix_len..]);
+
+        // Values that end at the end of a text that doesn't end with a line
+        // break end at different positions depending on their indentation,
+        // which is only known for those in the fragment.
+        let eof = unit.region.end == self.old_len;
+        if eof && !text.ends_with('\n') {
+            return None;
+        }
+
+        let mut parsed: Value = crate::from_str(&fragment).ok()?;
+        let base = unit.region.start;
+        let line_offset = unit.first_line - 1;
+        let newlines = text.bytes().filter(|&b| b == b'\n').count();
+        let line_delta = (unit.first_line + newlines) as isize - unit.next.line as isize;
+
+        // At the end of the text, everything ends where the fragment does.
+        // Otherwise, in the whole text, the value of the unit ends at the next
+        // token after it, and block collections nested in the unit end at the
+        // first token on the line of that token, where they are closed; in the
+        // fragment, both end at its end.
+        let (next, block_end) = if eof {
+            let mut end = parsed.span().end;
+            end.index += base;
+            end.line += line_offset;
+            (end, end)
+        } else {
+            let line = (unit.next.line as isize + line_delta) as usize;
+            let indent = self.source[new_region_end..]
+                .bytes()
+                .take_while(|&b| b == b' ')
+                .count();
+            (
+                Marker::new(
+                    (unit.next.index as isize + self.delta) as usize,
+                    line,
+                    unit.next.column,
+                ),
+                Marker::new(new_region_end + indent, line, indent + 1),
+            )
+        };
+        shift_markers(&mut parsed, &mut |marker| {
+            if marker.index == fragment.len() && !eof {
+                *marker = block_end;
+            } else {
+                marker.index += base;
+                marker.line += line_offset;
+            }
+        });
+
+        let (key, mut value) = match (unit.slots.last()?, parsed) {
+            (Slot::Entry(_), Value::Mapping(mapping, ..)) if mapping.len() == 1 => {
+                let (key, value) = mapping.into_iter().next()?;
+                (Some(key), value)
+            }
+            (Slot::Item(_), Value::Sequence(sequence, ..)) if sequence.len() == 1 => {
+                (None, sequence.into_vec().pop()?)
+            }
+            _ => return None,
+        };
+        if !eof && value.span().end == block_end {
+            let mut span = value.span().clone();
+            span.end = next;
+            value.set_span(span);
+        }
+
+        // The unit must stay where it was, or it could become part of another.
+        let new_first = match &key {
+            Some(key) => key.span().start,
+            None => value.span().start,
+        };
+        if new_first.line != unit.first_line || new_first.column != unit.first_column {
+            return None;
+        }
+
+        let (parent_slots, slot) = unit.slots.split_at(unit.slots.len() - 1);
+        if let (Slot::Entry(index), Some(key)) = (slot[0], &key) {
+            let Value::Mapping(mapping, ..) = slot_ref(root, parent_slots)? else {
+                return None;
+            };
+            let duplicate = mapping
+                .keys()
+                .enumerate()
+                .any(|(i, other)| i != index && other == key);
+            if duplicate {
+                return None;
+            }
+        }
+
+        // Shift everything at or after the end of the region, and point
+        // anything that ended at the old first token at the new one.
+        let first_index = unit.first_index;
+        let region = unit.region.clone();
+        let delta = self.delta;
+        shift_markers_from(root, region.start, &mut |marker| {
+            if marker.index >= region.end {
+                if eof {
+                    *marker = next;
+                } else {
+                    marker.index = (marker.index as isize + delta) as usize;
+                    marker.line = (marker.line as isize + line_delta) as usize;
+                }
+            } else if marker.index == first_index {
+                *marker = new_first;
+            }
+        });
+
+        let mut path = OwnedPath::Root;
+        let mut node = &*root;
+        for slot in parent_slots {
+            node = match (*slot, node) {
+                (Slot::Entry(index), Value::Mapping(mapping, ..)) => {
+                    let (key, value) = mapping.iter().nth(index)?;
+                    path = push_key(path, key);
+                    value
+                }
+                (Slot::Item(index), Value::Sequence(sequence, ..)) => {
+                    path = path.push_index(index);
+                    sequence.get(index)?
+                }
+                _ => return None,
+            };
+        }
+
+        match (slot[0], slot_mut(root, parent_slots)?, key) {
+            (Slot::Entry(index), Value::Mapping(mapping, ..), Some(key)) => {
+                path = push_key(path, &key);
+                mapping.replace_index(index, key, value);
+            }
+            (Slot::Item(index), Value::Sequence(sequence, ..), None) => {
+                path = path.push_index(index);
+                *sequence.get_mut(index)? = value;
+            }
+            _ => return None,
+        }
+        Some(path)
+    }
+}
+
+fn slot_ref<'v>(root: &'v Value, slots: &[Slot]) -> Option<&'v Value> {
+    slots
+        .iter()
+        .try_fold(root, |node, slot| match (*slot, node) {
+            (Slot::Entry(index), Value::Mapping(mapping, ..)) => {
+                mapping.iter().nth(index).map(|(_, value)| value)
+            }
+            (Slot::Item(index), Value::Sequence(sequence, ..)) => sequence.get(index),
+            _ => None,
+        })
+}
+
+fn slot_mut<'v>(root: &'v mut Value, slots: &[Slot]) -> Option<&'v mut Value> {
+    slots
+        .iter()
+        .try_fold(root, |node, slot| match (*slot, node) {
+            (Slot::Entry(index), Value::Mapping(mapping, ..)) => {
+                mapping.iter_mut().nth(index).map(|(_, value)| value)
+            }
+            (Slot::Item(index), Value::Sequence(sequence, ..)) => sequence.get_mut(index),
+            _ => None,
+        })
+}
+
+fn push_key(path: OwnedPath, key: &Value) -> OwnedPath {
+    match key.as_str() {
+        Some(key) => path.push_key(key),
+        None => OwnedPath::Unknown {
+            parent: Box::new(path),
+            borrowed: OnceCell::new(),
+        },
+    }
+}
+
+/// Applies `f` to the start and end of every valid span in `value`.
+fn shift_markers(value: &mut Value, f: &mut dyn FnMut(&mut Marker)) {
+    shift_markers_from(value, 0, f);
+}
+
+/// Like [shift_markers], but skips values that end before `from`.
+fn shift_markers_from(value: &mut Value, from: usize, f: &mut dyn FnMut(&mut Marker)) {
+    let span = value.span();
+    if span.is_valid() && span.end.index < from {
+        return;
+    }
+    let mut span: Span = span.clone();
+    if span.is_valid() {
+        f(&mut span.start);
+        f(&mut span.end);
+        value.set_span(span);
+    }
+    match value {
+        Value::Sequence(sequence, ..) => {
+            for item in sequence.iter_mut() {
+                shift_markers_from(item, from, f);
+            }
+        }
+        Value::Mapping(mapping, ..) => {
+            for (key, value) in mapping.iter_mut_with_keys() {
+                shift_markers_from(key, from, f);
+                shift_markers_from(value, from, f);
+            }
+        }
+        Value::Tagged(tagged, ..) => shift_markers_from(&mut tagged.value, from, f),
+        Value::Null(..) | Value::Bool(..) | Value::Number(..) | Value::String(..) => {}
+    }
+}
+
+/// The index of the first token of the document in `source`, after any
+/// comments, directives and document start marker.
+fn content_start(source: &str) -> usize {
+    let mut index = 0;
+    loop {
+        let rest = &source[index..];
+        let trimmed = rest.trim_start();
+        index += rest.len() - trimmed.len();
+        if trimmed.starts_with('#') || trimmed.starts_with('%') {
+            index += trimmed.find('\n').map_or(trimmed.len(), |i| i + 1);
+        } else if trimmed.starts_with("---")
+            && matches!(
+                trimmed.as_bytes().get(3),
+                None | Some(b' ' | b'\t' | b'\r' | b'\n')
+            )
+        {
+            index += 3;
+        } else {
+            return index;
+        }
+    }
+}
+
+/// Whether `source` might contain anchors or aliases, whose nodes can't be
+/// re-parsed separately.
+fn has_anchors(source: &str) -> bool {
+    let bytes = source.as_bytes();
+    bytes.iter().enumerate().any(|(i, &b)| {
+        (b == b'&' || b == b'*')
+            && (i == 0 || matches!(bytes[i - 1], b' ' | b'\t' | b'\n' | b'[' | b'{' | b','))
+            && !matches!(bytes.get(i + 1), None | Some(b' ' | b'\t' | b'\r' | b'\n'))
+    })
+}
@@ -0,0 +1,196 @@
+use crate::event::ScalarStyle;
+use crate::libyaml::parser::{Event, Parser, Scalar};
+use crate::value::Value;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Where a scalar is in its source text and how it is written, as returned by
+/// [`Value::scalar_info`].
+///
+/// The ranges are byte offsets into the source text, like the `index` of a
+/// [Marker](crate::Marker).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScalarInfo {
+    /// How the scalar is written.
+    pub style: ScalarStyle,
+    /// The text of the scalar, including its quotes or block indicator, but
+    /// not its tag or anchor.
+    pub range: Range<usize>,
+    /// The contents of the scalar: the text between the quotes of a quoted
+    /// scalar, or the lines after the header of a block scalar, indentation
+    /// included. For a plain scalar it is the same as `range`.
+    ///
+    /// Text replacing the contents must be escaped for `style`, e.g. with
+    /// `'` doubled in a single-quoted scalar.
+    pub content: Range<usize>,
+}
+
+impl Value {
+    /// Looks up this scalar in `source`, the YAML text it was parsed from,
+    /// and returns how it is written there.
+    ///
+    /// This is meant for tools that rewrite the contents of a string in place
+    /// and need to keep its quoting style. The source is parsed again up to
+    /// the scalar, which is found by the start of its span. A tagged value
+    /// gives the information of the scalar it tags.
+    ///
+    /// Returns `None` if this value is a sequence or a mapping, was written as
+    /// an alias, or if no matching scalar starts where its span does in
+    /// `source`, e.g. because this value was built or modified after parsing.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::event::ScalarStyle;
+    /// use dbt_serde_yaml::value::TextEdit;
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let source = "name: 'orders'  # the main fact table\n";
+    /// let value: Value = dbt_serde_yaml::from_str(source).unwrap();
+    ///
+    /// let info = value["name"].scalar_info(source).unwrap();
+    /// assert_eq!(info.style, ScalarStyle::SingleQuoted);
+    /// assert_eq!(&source[info.range.clone()], "'orders'");
+    /// assert_eq!(&source[info.content.clone()], "orders");
+    ///
+    /// let edit = TextEdit {
+    ///     range: info.content,
+    ///     replacement: "customers".to_owned(),
+    /// };
+    /// assert_eq!(
+    ///     TextEdit::apply(source, &[edit]),
+    ///     "name: 'customers'  # the main fact table\n",
+    /// );
+    /// ```
+    pub fn scalar_info(&self, source: &str) -> Option<ScalarInfo> {
+        let (event, range) = self.find_scalar(source)?;
+        if let Value::String(string, ..) = self.untag_ref() {
+            #[cfg(feature = "large_scalars")]
+            let elided = string.is_elided();
+            #[cfg(not(feature = "large_scalars"))]
+            let elided = false;
+            if !elided && *event.value != *string.as_bytes() {
+                return None;
+            }
+        }
+        Some(locate(source, range, ScalarStyle::from_parser(event.style)))
+    }
+
+    /// Decodes this scalar again from `source`, the YAML text it was parsed
+    /// from, and returns its contents with quotes and escapes resolved.
+    ///
+    /// Unlike [scalar_info](Value::scalar_info), this doesn't check the
+    /// contents against this value, so it recovers strings that were elided
+    /// while parsing by `LargeScalars::Elide` with the `large_scalars`
+    /// feature.
+    ///
+    /// Returns `None` under the same conditions as
+    /// [scalar_info](Value::scalar_info), other than the contents differing.
+    ///
+    /// ```
+    /// # #[cfg(feature = "large_scalars")] {
+    /// use dbt_serde_yaml::mapping::DuplicateKey;
+    /// use dbt_serde_yaml::{LargeScalars, ParseOptions, Value};
+    ///
+    /// let source = "name: orders\nsql: \"select *\\nfrom raw.orders\"\n";
+    /// let options = ParseOptions::new().large_scalars_over(Some(8), LargeScalars::Elide);
+    /// let value = Value::from_str_with(source, options, |_, _, _| DuplicateKey::Error).unwrap();
+    /// assert_eq!(value["name"], "orders");
+    /// let Value::String(elided, _) = &value["sql"] else { unreachable!() };
+    /// assert!(elided.is_elided());
+    ///
+    /// let sql = value["sql"].decode_scalar(source).unwrap();
+    /// assert_eq!(sql, "select *\nfrom raw.orders");
+    /// # }
+    /// ```
+    pub fn decode_scalar(&self, source: &str) -> Option<String> {
+        let (event, _) = self.find_scalar(source)?;
+        String::from_utf8(event.value.to_vec()).ok()
+    }
+
+    /// Finds the scalar event for this value in `source`, along with the text
+    /// of the event.
+    fn find_scalar<'s>(&self, source: &'s str) -> Option<(Scalar<'s>, Range<usize>)> {
+        if let Value::Sequence(..) | Value::Mapping(..) = self.untag_ref() {
+            return None;
+        }
+        // The span of the value inside a tagged value doesn't start at the
+        // scalar, so look for the scalar where the tagged value starts.
+        let span = self.span();
+        if !span.is_valid() {
+            return None;
+        }
+        find_scalar_at(source.as_bytes(), span.start.index)
+    }
+}
+
+/// Finds the scalar event starting at `target` in `source`, or that is the
+/// root of the document whose span starts there, along with the text of the
+/// event.
+fn find_scalar_at(source: &[u8], target: usize) -> Option<(Scalar<'_>, Range<usize>)> {
+    let mut parser = Parser::new(Cow::Borrowed(source));
+    // The span of the root of a document starts before any comments or
+    // `---` ahead of it.
+    let mut is_root = false;
+    loop {
+        let (event, start, end) = parser.next_with_end().ok()?;
+        let start = start.index() as usize;
+        let at_target = start == target || (is_root && start > target);
+        match event {
+            Event::StreamEnd => return None,
+            Event::DocumentStart => is_root = true,
+            Event::Scalar(event) if at_target => {
+                return Some((event, start..end.index() as usize));
+            }
+            Event::Scalar(_)
+            | Event::SequenceStart(_)
+            | Event::MappingStart(_)
+            | Event::Alias(_) => {
+                if start > target {
+                    return None;
+                }
+                is_root = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Splits the text of a scalar event, which begins with the tag and anchor of
+/// the scalar, into the parts described by [ScalarInfo].
+fn locate(source: &str, event: Range<usize>, style: ScalarStyle) -> ScalarInfo {
+    let text = &source[event.clone()];
+    let mut offset = 0;
+    while text[offset..].starts_with(['!', '&']) {
+        let property = &text[offset..];
+        offset += property.find(char::is_whitespace).unwrap_or(property.len());
+        // Properties may be followed by comments and line breaks.
+        loop {
+            let rest = &text[offset..];
+            let trimmed = rest.trim_start();
+            offset += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                break;
+            }
+            offset += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    let start = event.start + offset;
+    let mut end = event.end;
+    if let ScalarStyle::Literal | ScalarStyle::Folded = style {
+        // Block scalars take in the line breaks and indentation after them.
+        end = start + source[start..end].trim_end().len();
+    }
+    let content = match style {
+        ScalarStyle::Plain => start..end,
+        ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted => start + 1..end - 1,
+        ScalarStyle::Literal | ScalarStyle::Folded => {
+            let header = source[start..end].find('\n').map_or(end, |i| start + i + 1);
+            header.min(end)..end
+        }
+    };
+    ScalarInfo {
+        style,
+        range: start..end,
+        content,
+    }
+}
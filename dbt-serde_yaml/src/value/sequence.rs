@@ -0,0 +1,288 @@
+use crate::mapping::Index;
+use crate::path::Path;
+use crate::value::de::{TransformedResult, ValueDeserializer};
+use crate::{spanned, trace, Error, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{self, Debug};
+use std::ops::{Deref, DerefMut};
+
+/// A YAML sequence in which the elements are `dbt_serde_yaml::Value`.
+///
+/// `Sequence` dereferences to `Vec<Value>`, so every `Vec` and slice method is
+/// available on it directly.
+#[derive(Clone, Default, Eq, Hash, PartialEq, PartialOrd)]
+pub struct Sequence {
+    vec: Vec<Value>,
+}
+
+impl Sequence {
+    /// Creates an empty YAML sequence.
+    #[inline]
+    pub const fn new() -> Self {
+        Sequence { vec: Vec::new() }
+    }
+
+    /// Creates an empty YAML sequence with the given initial capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Sequence {
+            vec: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the underlying `Vec`.
+    #[inline]
+    pub fn into_vec(self) -> Vec<Value> {
+        self.vec
+    }
+
+    /// Returns an iterator that deserializes each element as a `T`.
+    ///
+    /// Each element is deserialized on its own, so an element that fails to
+    /// deserialize does not prevent the following ones from being visited.
+    ///
+    /// ```
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// use dbt_serde_yaml::{Sequence, Value};
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("[1, 2, three]")?;
+    /// let sequence = <&Sequence>::try_from(&value)?;
+    ///
+    /// let numbers: Vec<_> = sequence.iter_typed::<u32>().collect();
+    /// assert_eq!(numbers[0].as_ref().unwrap(), &1);
+    /// assert_eq!(numbers[1].as_ref().unwrap(), &2);
+    /// assert!(numbers[2].is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_typed<'a, T>(&'a self) -> impl Iterator<Item = Result<T, Error>> + 'a
+    where
+        T: Deserialize<'a> + 'a,
+    {
+        self.vec.iter().map(T::deserialize)
+    }
+
+    /// Deserialize each element into an instance of some [Deserialize] type
+    /// `T`.
+    ///
+    /// Elements are deserialized independently, so an element that fails to
+    /// deserialize does not prevent the remaining elements from being
+    /// deserialized. Each element's error carries that element's span.
+    ///
+    /// ```
+    /// # use dbt_serde_yaml::{Sequence, Value};
+    /// let value: Value = dbt_serde_yaml::from_str("[1, two, 3]").unwrap();
+    /// let sequence = Sequence::try_from(value).unwrap();
+    /// let elements = sequence.into_typed_elements::<u32, _, _>(|_, _, _| {}, |_| Ok(None));
+    ///
+    /// assert_eq!(elements[0].as_ref().unwrap(), &1);
+    /// assert!(elements[1].is_err());
+    /// assert_eq!(elements[2].as_ref().unwrap(), &3);
+    /// ```
+    pub fn into_typed_elements<'de, T, U, F>(
+        self,
+        mut unused_key_callback: U,
+        mut field_transformer: F,
+    ) -> Vec<Result<T, Error>>
+    where
+        T: Deserialize<'de>,
+        U: FnMut(Path<'_>, &Value, &Value),
+        F: for<'v> FnMut(&'v Value) -> TransformedResult,
+    {
+        let _marker = spanned::enter_marker(spanned::Marker::start());
+        let mut unused_keys = 0;
+        let mut unused_key_callback = |path: Path<'_>, key: &Value, value: &Value| {
+            unused_keys += 1;
+            unused_key_callback(path, key, value)
+        };
+        let elements = self
+            .vec
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let de = ValueDeserializer::new_with(
+                    value,
+                    Path::Seq {
+                        parent: &Path::Root,
+                        index,
+                    },
+                    Some(&mut unused_key_callback),
+                    Some(&mut field_transformer),
+                );
+                T::deserialize(de)
+            })
+            .collect();
+
+        trace::callback_count("unused_key", unused_keys);
+        elements
+    }
+
+    /// Returns the first element that is a mapping whose entry at `key` equals
+    /// `value`.
+    ///
+    /// ```
+    /// # fn main() -> dbt_serde_yaml::Result<()> {
+    /// use dbt_serde_yaml::{Sequence, Value};
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str(
+    ///     "
+    ///     - name: orders
+    ///       materialized: table
+    ///     - name: customers
+    ///       materialized: view
+    ///     ",
+    /// )?;
+    /// let sequence = <&Sequence>::try_from(&value)?;
+    ///
+    /// let customers = sequence.find_by_key("name", "customers").unwrap();
+    /// assert_eq!(customers["materialized"], "view");
+    /// assert!(sequence.find_by_key("name", "payments").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_by_key<I, V>(&self, key: I, value: V) -> Option<&Value>
+    where
+        I: Index,
+        Value: PartialEq<V>,
+    {
+        self.vec.iter().find(|element| {
+            element
+                .as_mapping()
+                .and_then(|mapping| mapping.get(&key))
+                .is_some_and(|found| *found == value)
+        })
+    }
+
+    /// Mutable version of [Sequence::find_by_key].
+    pub fn find_by_key_mut<I, V>(&mut self, key: I, value: V) -> Option<&mut Value>
+    where
+        I: Index,
+        Value: PartialEq<V>,
+    {
+        self.vec.iter_mut().find(|element| {
+            element
+                .as_mapping()
+                .and_then(|mapping| mapping.get(&key))
+                .is_some_and(|found| *found == value)
+        })
+    }
+}
+
+impl Deref for Sequence {
+    type Target = Vec<Value>;
+
+    #[inline]
+    fn deref(&self) -> &Vec<Value> {
+        &self.vec
+    }
+}
+
+impl DerefMut for Sequence {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Vec<Value> {
+        &mut self.vec
+    }
+}
+
+impl Debug for Sequence {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_list().entries(&self.vec).finish()
+    }
+}
+
+impl From<Vec<Value>> for Sequence {
+    #[inline]
+    fn from(vec: Vec<Value>) -> Self {
+        Sequence { vec }
+    }
+}
+
+impl From<Sequence> for Vec<Value> {
+    #[inline]
+    fn from(sequence: Sequence) -> Self {
+        sequence.vec
+    }
+}
+
+impl PartialEq<Vec<Value>> for Sequence {
+    fn eq(&self, other: &Vec<Value>) -> bool {
+        self.vec == *other
+    }
+}
+
+impl PartialEq<[Value]> for Sequence {
+    fn eq(&self, other: &[Value]) -> bool {
+        self.vec == other
+    }
+}
+
+impl<const N: usize> PartialEq<[Value; N]> for Sequence {
+    fn eq(&self, other: &[Value; N]) -> bool {
+        self.vec == other
+    }
+}
+
+impl Extend<Value> for Sequence {
+    #[inline]
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        self.vec.extend(iter);
+    }
+}
+
+impl FromIterator<Value> for Sequence {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Sequence {
+            vec: Vec::from_iter(iter),
+        }
+    }
+}
+
+impl IntoIterator for Sequence {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Sequence {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Sequence {
+    type Item = &'a mut Value;
+    type IntoIter = std::slice::IterMut<'a, Value>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.iter_mut()
+    }
+}
+
+impl Serialize for Sequence {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.vec.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer).map(Sequence::from)
+    }
+}
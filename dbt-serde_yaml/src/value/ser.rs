@@ -1,9 +1,11 @@
+use crate::binary;
 use crate::error::{self, Error, ErrorImpl};
 use crate::spanned;
 use crate::value::tagged::{self, MaybeTag};
 use crate::value::{to_value, Mapping, Number, Sequence, Tag, TaggedValue, Value};
 use crate::Span;
 use serde::ser::{self, Serialize};
+use std::cell::Cell;
 use std::fmt::Display;
 use std::mem;
 
@@ -15,10 +17,138 @@ impl Serialize for Value {
         S: serde::Serializer,
     {
         spanned::set_span(self.span().clone());
+        let offered = handover(Handover::None);
         match self {
+            // Let the YAML serializer emit collections without recursing into
+            // them, if it is the one serializing this value.
+            Value::Sequence(..) | Value::Mapping(..) if offered.is_emit_offered() => {
+                let _passed = handover(Handover::Passed(self));
+                serializer.serialize_newtype_struct(TOKEN, Recursive::new(self))
+            }
+            // Let the YAML serializer write the string in the style it was
+            // parsed in.
+            #[cfg(feature = "round_trip")]
+            Value::String(string, ..) => {
+                let _style = string.style().map(|style| handover(Handover::Style(style)));
+                Recursive::new(self).serialize(serializer)
+            }
+            _ => Recursive::new(self).serialize(serializer),
+        }
+    }
+}
+
+/// The name of the newtype struct through which a sequence or mapping [Value]
+/// is passed to the YAML serializer.
+pub(crate) const TOKEN: &str = "$dbt_serde_yaml::private::Value";
+
+/// What the `Serialize` impl of [Value] and the YAML serializer have told each
+/// other about the value being serialized.
+#[derive(Clone, Copy)]
+enum Handover {
+    None,
+    /// The YAML serializer is passing itself to a `Serialize` impl; see
+    /// [offer_emit].
+    EmitOffered,
+    /// The `Serialize` impl of this [Value] is passing it under [TOKEN].
+    Passed(*const Value),
+    /// The `Serialize` impl of a [Value] is passing a string parsed in this
+    /// style to `serialize_str`.
+    #[cfg(feature = "round_trip")]
+    Style(crate::event::ScalarStyle),
+}
+
+thread_local! {
+    static HANDOVER: Cell<Handover> = const { Cell::new(Handover::None) };
+}
+
+/// Restores the [Handover] it replaced when dropped, so that a handover never
+/// outlives the call it was made for, even if that call panics.
+struct Restore(Handover);
+
+impl Restore {
+    fn is_emit_offered(&self) -> bool {
+        matches!(self.0, Handover::EmitOffered)
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        HANDOVER.with(|cell| cell.set(self.0));
+    }
+}
+
+fn handover(handover: Handover) -> Restore {
+    Restore(HANDOVER.with(|cell| cell.replace(handover)))
+}
+
+/// Runs `f`, in which the YAML serializer passes itself to a `Serialize`
+/// impl. If that is the impl of a sequence or mapping [Value], the value is
+/// passed to the serializer under [TOKEN] so that it can be emitted without
+/// recursion. Other serializers only ever see plain sequences and mappings.
+pub(crate) fn offer_emit<R>(f: impl FnOnce() -> R) -> R {
+    let _offered = handover(Handover::EmitOffered);
+    f()
+}
+
+/// Returns the style recorded for the string being passed to `serialize_str`
+/// by the `Serialize` impl of `Value`, if that is where it comes from.
+#[cfg(feature = "round_trip")]
+pub(crate) fn serializing_style() -> Option<crate::event::ScalarStyle> {
+    match HANDOVER.with(Cell::get) {
+        Handover::Style(style) => {
+            HANDOVER.with(|cell| cell.set(Handover::None));
+            Some(style)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the [Value] passed as `value` to `serialize_newtype_struct` under
+/// [TOKEN] by the `Serialize` impl of `Value`, if that is where it comes
+/// from.
+pub(crate) fn serializing_value<'a, T>(name: &str, value: &'a T) -> Option<&'a Value>
+where
+    T: ?Sized,
+{
+    if name != TOKEN {
+        return None;
+    }
+    let Handover::Passed(ptr) = HANDOVER.with(Cell::get) else {
+        return None;
+    };
+    if !std::ptr::eq(ptr.cast::<()>(), (value as *const T).cast::<()>()) {
+        return None;
+    }
+    // SAFETY: `value` is the `Recursive` wrapping the Value at `ptr`, which
+    // is only handed over for as long as `Value::serialize` borrows it.
+    Some(unsafe { &*ptr })
+}
+
+/// Serializes a [Value] by serializing the contents of its collections in
+/// turn.
+#[repr(transparent)]
+struct Recursive(Value);
+
+impl Recursive {
+    fn new(value: &Value) -> &Recursive {
+        // SAFETY: `Recursive` is a transparent wrapper around `Value`.
+        unsafe { &*(value as *const Value).cast::<Recursive>() }
+    }
+}
+
+impl Serialize for Recursive {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match &self.0 {
             Value::Null(..) => serializer.serialize_unit(),
             Value::Bool(b, ..) => serializer.serialize_bool(*b),
             Value::Number(n, ..) => n.serialize(serializer),
+            #[cfg(feature = "large_scalars")]
+            Value::String(s, ..) if s.is_elided() => Err(ser::Error::custom(
+                "cannot serialize a string elided while parsing",
+            )),
             Value::String(s, ..) => serializer.serialize_str(s),
             Value::Sequence(seq, ..) => seq.serialize(serializer),
             Value::Mapping(mapping, ..) => {
@@ -100,7 +230,7 @@ impl ser::Serializer for Serializer {
             self.serialize_i64(v)
         } else {
             let span = spanned::take_span().unwrap_or_default();
-            Ok(Value::String(v.to_string(), span))
+            Ok(Value::string(v.to_string()).with_span(span))
         }
     }
 
@@ -129,7 +259,7 @@ impl ser::Serializer for Serializer {
             self.serialize_u64(v)
         } else {
             let span = spanned::take_span().unwrap_or_default();
-            Ok(Value::String(v.to_string(), span))
+            Ok(Value::string(v.to_string()).with_span(span))
         }
     }
 
@@ -145,21 +275,22 @@ impl ser::Serializer for Serializer {
 
     fn serialize_char(self, value: char) -> Result<Value> {
         let span = spanned::take_span().unwrap_or_default();
-        Ok(Value::String(value.to_string(), span))
+        Ok(Value::string(value.to_string()).with_span(span))
     }
 
     fn serialize_str(self, value: &str) -> Result<Value> {
         let span = spanned::take_span().unwrap_or_default();
-        Ok(Value::String(value.to_owned(), span))
+        Ok(Value::string(value.to_owned()).with_span(span))
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Value> {
+        // A YAML `!!binary` scalar, as written by the text serializer.
         let span = spanned::take_span().unwrap_or_default();
-        let vec = value
-            .iter()
-            .map(|&b| Value::number(Number::from(b)))
-            .collect();
-        Ok(Value::Sequence(vec, span))
+        let tagged = TaggedValue {
+            tag: Tag::new("!!binary"),
+            value: Value::string(binary::encode(value)),
+        };
+        Ok(Value::Tagged(Box::new(tagged), span))
     }
 
     fn serialize_unit(self) -> Result<Value> {
@@ -178,13 +309,19 @@ impl ser::Serializer for Serializer {
         variant: &str,
     ) -> Result<Value> {
         let span = spanned::take_span().unwrap_or_default();
-        Ok(Value::String(variant.to_owned(), span))
+        Ok(Value::string(variant.to_owned()).with_span(span))
     }
 
     fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Value>
     where
         T: ?Sized + ser::Serialize,
     {
+        #[cfg(feature = "arbitrary_precision")]
+        if _name == crate::number::TOKEN {
+            if let Value::String(repr, span) = value.serialize(Serializer)? {
+                return Ok(Value::Number(Number::big(&repr), span));
+            }
+        }
         value.serialize(self)
     }
 
@@ -795,7 +932,13 @@ impl ser::SerializeMap for SerializeMap {
                 *self = match key {
                     MaybeTag::Tag(string) => SerializeMap::Tagged(
                         TaggedValue {
-                            tag: Tag::new(string),
+                            // The tag is found without its first '!', so
+                            // that of a `!!set` is found as `!set`.
+                            tag: if string.starts_with('!') {
+                                Tag::new(format!("!{string}"))
+                            } else {
+                                Tag::new(string)
+                            },
                             value: to_value(value)?,
                         },
                         span.clone(),
@@ -852,7 +995,7 @@ impl ser::SerializeStruct for SerializeStruct {
     where
         V: ?Sized + ser::Serialize,
     {
-        if crate::is_flatten_key(key.as_bytes()) {
+        if crate::is_flatten_key(key) {
             let flattened = value.serialize(Serializer)?;
             if let Value::Mapping(flattened, ..) = flattened {
                 for (k, v) in flattened {
@@ -886,7 +1029,7 @@ impl ser::SerializeStructVariant for SerializeStructVariant {
     where
         V: ?Sized + ser::Serialize,
     {
-        if crate::is_flatten_key(field.as_bytes()) {
+        if crate::is_flatten_key(field) {
             let flattened = v.serialize(Serializer)?;
             if let Value::Mapping(flattened, ..) = flattened {
                 for (k, v) in flattened {
@@ -0,0 +1,383 @@
+#[cfg(any(feature = "round_trip", feature = "large_scalars"))]
+use crate::event::ScalarStyle;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+#[cfg(feature = "large_scalars")]
+use std::str::{self, Utf8Error};
+#[cfg(any(feature = "intern", feature = "large_scalars"))]
+use std::sync::Arc;
+
+#[cfg(not(feature = "intern"))]
+type Repr = String;
+#[cfg(feature = "intern")]
+type Repr = Arc<str>;
+
+/// The string held by a [Value::String](crate::Value::String) when one of the
+/// `intern`, `round_trip` and `large_scalars` features is enabled; it is a
+/// plain `String` otherwise.
+///
+/// `YamlString` dereferences to `str` and converts from and into `String`, so
+/// it can mostly be used like one.
+///
+/// With the `intern` feature, it is backed by an `Arc<str>` instead of a
+/// `String`, and mapping keys read from YAML share one allocation per distinct
+/// key. This cuts the memory used by large [Value](crate::Value) trees in
+/// which the same keys, like `name` or `description`, appear many times, and
+/// makes cloning strings cheap. The API is the same with or without the
+/// feature.
+///
+/// With the `round_trip` feature, it also records the [style](Self::style) in
+/// which it was written when parsed from YAML text. The style is not part of
+/// the comparison of strings.
+///
+/// With the `large_scalars` feature, a large string read with
+/// [`ParseOptions::large_scalars_over`](crate::ParseOptions::large_scalars_over)
+/// shares the buffer the parser decoded it into, or is
+/// [elided](Self::is_elided).
+#[derive(Clone, Default)]
+pub struct YamlString(Contents, #[cfg(feature = "round_trip")] Option<ScalarStyle>);
+
+#[derive(Clone)]
+enum Contents {
+    Loaded(Repr),
+    #[cfg(feature = "large_scalars")]
+    Shared(Arc<str>),
+    #[cfg(feature = "large_scalars")]
+    Elided,
+}
+
+impl Default for Contents {
+    fn default() -> Self {
+        Contents::Loaded(Repr::default())
+    }
+}
+
+impl YamlString {
+    fn new(repr: Repr) -> Self {
+        YamlString(
+            Contents::Loaded(repr),
+            #[cfg(feature = "round_trip")]
+            parsing_style(),
+        )
+    }
+
+    /// Returns a string holding on to `bytes`, written in `style`, or an
+    /// error if they aren't UTF-8.
+    #[cfg(feature = "large_scalars")]
+    #[cfg_attr(not(feature = "round_trip"), allow(unused_variables))]
+    pub(crate) fn shared(bytes: Arc<[u8]>, style: ScalarStyle) -> Result<Self, Utf8Error> {
+        str::from_utf8(&bytes)?;
+        // SAFETY: the bytes were just checked to be UTF-8, and `str` has the
+        // same layout as `[u8]`.
+        let shared = unsafe { Arc::from_raw(Arc::into_raw(bytes) as *const str) };
+        Ok(YamlString(
+            Contents::Shared(shared),
+            #[cfg(feature = "round_trip")]
+            Some(style),
+        ))
+    }
+
+    /// Returns an elided string that was written in `style`.
+    #[cfg(feature = "large_scalars")]
+    #[cfg_attr(not(feature = "round_trip"), allow(unused_variables))]
+    pub(crate) fn elided(style: ScalarStyle) -> Self {
+        YamlString(
+            Contents::Elided,
+            #[cfg(feature = "round_trip")]
+            Some(style),
+        )
+    }
+
+    /// Returns a string with the contents `repr` and the style of this one.
+    #[cfg(feature = "intern")]
+    fn with_repr(&self, repr: Repr) -> Self {
+        YamlString(
+            Contents::Loaded(repr),
+            #[cfg(feature = "round_trip")]
+            self.1,
+        )
+    }
+
+    /// Returns the string as a `&str`.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Contents::Loaded(repr) => repr,
+            #[cfg(feature = "large_scalars")]
+            Contents::Shared(shared) => shared,
+            #[cfg(feature = "large_scalars")]
+            Contents::Elided => "",
+        }
+    }
+
+    /// Returns true if this string was left out while parsing by
+    /// [`LargeScalars::Elide`](crate::LargeScalars::Elide).
+    ///
+    /// An elided string reads as empty, but compares unequal to every string
+    /// that isn't elided, and serializing or deserializing it fails. Its
+    /// contents can be recovered from the source text with
+    /// [`Value::decode_scalar`](crate::Value::decode_scalar).
+    #[cfg(feature = "large_scalars")]
+    pub fn is_elided(&self) -> bool {
+        matches!(self.0, Contents::Elided)
+    }
+
+    /// Converts the string into a `String`.
+    ///
+    /// With the `intern` feature, or if the string is
+    /// [large](crate::ParseOptions::large_scalars_over), this copies the
+    /// string.
+    #[inline]
+    pub fn into_string(self) -> String {
+        match self.0 {
+            #[cfg(not(feature = "intern"))]
+            Contents::Loaded(repr) => repr,
+            #[cfg(feature = "intern")]
+            Contents::Loaded(repr) => String::from(&*repr),
+            #[cfg(feature = "large_scalars")]
+            Contents::Shared(shared) => String::from(&*shared),
+            #[cfg(feature = "large_scalars")]
+            Contents::Elided => String::new(),
+        }
+    }
+}
+
+#[cfg(feature = "round_trip")]
+impl YamlString {
+    /// Returns the style in which the string was written, if it was parsed
+    /// from YAML text.
+    ///
+    /// Serializers built with
+    /// [`preserve_scalar_styles`](crate::SerializerBuilder::preserve_scalar_styles)
+    /// write the string in this style again where possible.
+    pub fn style(&self) -> Option<ScalarStyle> {
+        self.1
+    }
+
+    /// Sets the style in which the string is to be written.
+    pub fn with_style(mut self, style: Option<ScalarStyle>) -> Self {
+        self.1 = style;
+        self
+    }
+}
+
+/// Records `style` as the style of the strings created until the returned
+/// guard is dropped, i.e. of the string of the scalar being parsed.
+#[cfg(feature = "round_trip")]
+pub(crate) fn with_parsing_style(style: ScalarStyle) -> ParsingStyleGuard {
+    ParsingStyleGuard(PARSING_STYLE.with(|cell| cell.replace(Some(style))))
+}
+
+#[cfg(feature = "round_trip")]
+pub(crate) struct ParsingStyleGuard(Option<ScalarStyle>);
+
+#[cfg(feature = "round_trip")]
+impl Drop for ParsingStyleGuard {
+    fn drop(&mut self) {
+        PARSING_STYLE.with(|cell| cell.set(self.0));
+    }
+}
+
+#[cfg(feature = "round_trip")]
+fn parsing_style() -> Option<ScalarStyle> {
+    PARSING_STYLE.with(std::cell::Cell::get)
+}
+
+#[cfg(feature = "round_trip")]
+thread_local! {
+    static PARSING_STYLE: std::cell::Cell<Option<ScalarStyle>> = const {
+        std::cell::Cell::new(None)
+    };
+}
+
+/// Returns `s` as a string shared with every other key interned on this
+/// thread that is equal to it.
+#[cfg(feature = "intern")]
+pub(crate) fn intern(s: &YamlString) -> YamlString {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    // Strings referenced only by the pool are dropped each time it doubles in
+    // size, so that it doesn't keep alive keys of values dropped since.
+    const MIN_PURGE_LEN: usize = 1024;
+
+    struct Pool {
+        strings: HashSet<Arc<str>>,
+        purge_len: usize,
+    }
+
+    thread_local! {
+        static POOL: RefCell<Pool> = RefCell::new(Pool {
+            strings: HashSet::new(),
+            purge_len: MIN_PURGE_LEN,
+        });
+    }
+
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if let Some(shared) = pool.strings.get(s.as_str()) {
+            return s.with_repr(Arc::clone(shared));
+        }
+        if pool.strings.len() >= pool.purge_len {
+            pool.strings.retain(|shared| Arc::strong_count(shared) > 1);
+            pool.purge_len = MIN_PURGE_LEN.max(2 * pool.strings.len());
+        }
+        let shared: Arc<str> = Arc::from(s.as_str());
+        pool.strings.insert(Arc::clone(&shared));
+        s.with_repr(shared)
+    })
+}
+
+impl Deref for YamlString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for YamlString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for YamlString {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for YamlString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self.as_str(), formatter)
+    }
+}
+
+impl Debug for YamlString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self.as_str(), formatter)
+    }
+}
+
+impl From<String> for YamlString {
+    #[inline]
+    #[allow(clippy::useless_conversion)]
+    fn from(s: String) -> Self {
+        YamlString::new(s.into())
+    }
+}
+
+impl From<&str> for YamlString {
+    #[inline]
+    fn from(s: &str) -> Self {
+        YamlString::new(s.into())
+    }
+}
+
+impl From<&String> for YamlString {
+    #[inline]
+    fn from(s: &String) -> Self {
+        YamlString::from(s.as_str())
+    }
+}
+
+impl From<Box<str>> for YamlString {
+    #[inline]
+    fn from(s: Box<str>) -> Self {
+        YamlString::new(s.into())
+    }
+}
+
+impl From<YamlString> for String {
+    #[inline]
+    fn from(s: YamlString) -> Self {
+        s.into_string()
+    }
+}
+
+impl YamlString {
+    /// Returns what the string compares and hashes by: its contents, or
+    /// `None` if it was elided, so that an elided string is unequal to every
+    /// string that isn't.
+    #[inline]
+    fn contents(&self) -> Option<&str> {
+        match &self.0 {
+            #[cfg(feature = "large_scalars")]
+            Contents::Elided => None,
+            _ => Some(self.as_str()),
+        }
+    }
+}
+
+impl PartialEq for YamlString {
+    fn eq(&self, other: &YamlString) -> bool {
+        self.contents() == other.contents()
+    }
+}
+
+impl Eq for YamlString {}
+
+impl PartialOrd for YamlString {
+    fn partial_cmp(&self, other: &YamlString) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for YamlString {
+    fn cmp(&self, other: &YamlString) -> Ordering {
+        self.contents().cmp(&other.contents())
+    }
+}
+
+impl Hash for YamlString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Strings hash like `str`, as lookups through `Borrow<str>` require.
+        match self.contents() {
+            Some(contents) => contents.hash(state),
+            None => state.write_u8(0),
+        }
+    }
+}
+
+impl PartialEq<str> for YamlString {
+    fn eq(&self, other: &str) -> bool {
+        self.contents() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for YamlString {
+    fn eq(&self, other: &&str) -> bool {
+        self.contents() == Some(*other)
+    }
+}
+
+impl PartialEq<String> for YamlString {
+    fn eq(&self, other: &String) -> bool {
+        self.contents() == Some(other.as_str())
+    }
+}
+
+impl PartialEq<YamlString> for str {
+    fn eq(&self, other: &YamlString) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<YamlString> for &str {
+    fn eq(&self, other: &YamlString) -> bool {
+        other == *self
+    }
+}
+
+impl PartialEq<YamlString> for String {
+    fn eq(&self, other: &YamlString) -> bool {
+        other == self
+    }
+}
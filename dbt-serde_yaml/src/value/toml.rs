@@ -0,0 +1,109 @@
+use crate::error::{self, Error};
+use crate::value::{Mapping, Number, Sequence, Value};
+use serde::de::{Error as _, Unexpected};
+
+impl From<toml::Value> for Value {
+    /// Converts a TOML value into a YAML value without source locations.
+    ///
+    /// Date and time values become strings in their TOML form, such as
+    /// `1979-05-27T07:32:00Z`.
+    fn from(toml: toml::Value) -> Self {
+        match toml {
+            toml::Value::String(s) => Value::string(s),
+            toml::Value::Integer(i) => Value::number(Number::from(i)),
+            toml::Value::Float(f) => Value::number(Number::from(f)),
+            toml::Value::Boolean(b) => Value::bool(b),
+            toml::Value::Datetime(datetime) => Value::string(datetime.to_string()),
+            toml::Value::Array(array) => {
+                Value::sequence(array.into_iter().map(Value::from).collect::<Sequence>())
+            }
+            toml::Value::Table(table) => {
+                let mut mapping = Mapping::with_capacity(table.len());
+                for (key, value) in table {
+                    mapping.insert(Value::string(key), Value::from(value));
+                }
+                Value::mapping(mapping)
+            }
+        }
+    }
+}
+
+impl TryFrom<Value> for toml::Value {
+    type Error = Error;
+
+    /// Converts a YAML value into a TOML value, dropping source locations.
+    ///
+    /// Fails on values that TOML cannot represent: nulls, tagged values,
+    /// integers beyond the range of `i64`, and mapping keys that are not
+    /// strings. The error carries the span of the offending node.
+    ///
+    /// Since `toml::Value` has an inherent `try_from` method that serializes
+    /// any value, call this conversion through `try_into` instead.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("
+    /// name: jaffle_shop
+    /// models: {+materialized: table}
+    /// ").unwrap();
+    /// let toml: toml::Value = value.try_into().unwrap();
+    /// assert_eq!(toml["models"]["+materialized"].as_str(), Some("table"));
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("description: ~").unwrap();
+    /// let err: Result<toml::Value, _> = value.try_into();
+    /// assert_eq!(err.unwrap_err().to_string(), "TOML has no null value at line 1 column 14");
+    /// ```
+    fn try_from(value: Value) -> Result<Self, Error> {
+        to_toml(&value)
+    }
+}
+
+impl TryFrom<&Value> for toml::Value {
+    type Error = Error;
+
+    /// Converts a YAML value into a TOML value, the same way as from an owned
+    /// [`Value`].
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        to_toml(value)
+    }
+}
+
+fn to_toml(value: &Value) -> Result<toml::Value, Error> {
+    let fail = |err: Error| Err(error::set_span(err, value.span().clone()));
+    Ok(match value {
+        Value::Null(..) => return fail(Error::custom("TOML has no null value")),
+        Value::Bool(b, ..) => toml::Value::Boolean(*b),
+        Value::Number(n, ..) => {
+            if let Some(i) = n.as_i64() {
+                toml::Value::Integer(i)
+            } else if n.is_f64() {
+                toml::Value::Float(n.as_f64().unwrap_or(f64::NAN))
+            } else {
+                let unexpected = match n.as_u64() {
+                    Some(u) => Unexpected::Unsigned(u),
+                    None => value.unexpected(),
+                };
+                return fail(Error::invalid_value(unexpected, &"a 64-bit signed integer"));
+            }
+        }
+        Value::String(s, ..) => toml::Value::String(s.to_string()),
+        Value::Sequence(sequence, ..) => {
+            toml::Value::Array(sequence.iter().map(to_toml).collect::<Result<_, _>>()?)
+        }
+        Value::Mapping(mapping, ..) => {
+            let mut table = toml::Table::new();
+            for (key, value) in mapping {
+                let Value::String(name, ..) = key else {
+                    return Err(key.invalid_type(&"a string key"));
+                };
+                table.insert(name.to_string(), to_toml(value)?);
+            }
+            toml::Value::Table(table)
+        }
+        Value::Tagged(tagged, ..) => {
+            let msg = format!("TOML has no tagged values like `{}`", tagged.tag);
+            return fail(Error::custom(msg));
+        }
+    })
+}
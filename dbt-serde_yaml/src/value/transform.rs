@@ -0,0 +1,301 @@
+//! Ready-made field transformers for [`Value::into_typed`] and the other
+//! entry points taking one.
+//!
+//! Each transformer has the signature of a field transformer, so it can be
+//! passed as is, and they can be combined with [chain] and restricted to
+//! some fields with [at_path].
+//!
+//! ```
+//! use dbt_serde_yaml::value::transform;
+//! use dbt_serde_yaml::Value;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Profile {
+//!     target: String,
+//!     path: String,
+//!     enabled: bool,
+//! }
+//!
+//! let yaml = "
+//! target: \"{{ env_var('DBT_TARGET', 'dev') }}\"
+//! path: ~/.dbt/${DBT_TARGET}
+//! enabled: 'yes'
+//! ";
+//! let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+//! let env = |name: &str| (name == "DBT_TARGET").then(|| "prod".to_owned());
+//! let transformer = transform::chain(
+//!     transform::chain(
+//!         transform::resolve_env_with(env),
+//!         transform::expand_home_with("/home/dbt"),
+//!     ),
+//!     transform::coerce_bool,
+//! );
+//! let profile: Profile = value.into_typed(|_, _, _| {}, transformer).unwrap();
+//! assert_eq!(profile.target, "prod");
+//! assert_eq!(profile.path, "/home/dbt/.dbt/prod");
+//! assert!(profile.enabled);
+//! ```
+
+use crate::callback;
+use crate::path::PathPattern;
+use crate::value::{TransformedResult, Value};
+
+impl Value {
+    /// Substitutes environment variables of the current process into this
+    /// value if it is a string; see [transform::resolve_env](resolve_env).
+    ///
+    /// This has the signature of a field transformer, so `Value::resolve_env`
+    /// can be passed to [Value::into_typed] directly.
+    pub fn resolve_env(&self) -> TransformedResult {
+        resolve_env(self)
+    }
+}
+
+/// Substitutes environment variables of the current process into string
+/// values, like [resolve_env_with] reading [`std::env::var`].
+pub fn resolve_env(value: &Value) -> TransformedResult {
+    substitute_env(value, &|name| std::env::var(name).ok())
+}
+
+/// Returns a transformer that substitutes variables looked up with `lookup`
+/// into string values.
+///
+/// Both `${NAME}` and the dbt form `{{ env_var('NAME') }}` are replaced, the
+/// latter with an optional default as a second argument, in single or double
+/// quotes. Other text, including other `{{ ... }}` expressions, is left
+/// alone. A variable that is not set and has no default is an error.
+///
+/// ```
+/// use dbt_serde_yaml::value::transform;
+/// use dbt_serde_yaml::Value;
+/// use std::collections::BTreeMap;
+///
+/// let value: Value = dbt_serde_yaml::from_str("schema: ${USER}_dev\nuser: ${NOBODY}\n").unwrap();
+/// let lookup = |name: &str| (name == "USER").then(|| "alice".to_owned());
+///
+/// let schema = value["schema"].clone();
+/// let schema: String = schema.into_typed(|_, _, _| {}, transform::resolve_env_with(lookup)).unwrap();
+/// assert_eq!(schema, "alice_dev");
+///
+/// let err = value
+///     .into_typed::<BTreeMap<String, String>, _, _>(|_, _, _| {}, transform::resolve_env_with(lookup))
+///     .unwrap_err();
+/// assert_eq!(err.to_string(), "environment variable `NOBODY` is not set");
+/// ```
+pub fn resolve_env_with<L>(lookup: L) -> impl FnMut(&Value) -> TransformedResult
+where
+    L: Fn(&str) -> Option<String>,
+{
+    move |value| substitute_env(value, &lookup)
+}
+
+/// Replaces a leading `~` in string values, alone or followed by `/`, with
+/// the home directory of the current user, taken from the `HOME` or, failing
+/// that, `USERPROFILE` environment variable.
+///
+/// It is an error to expand a value if neither variable is set.
+pub fn expand_home(value: &Value) -> TransformedResult {
+    if !is_home_relative(value) {
+        return Ok(None);
+    }
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .map_err(|_| "cannot expand `~`: the home directory is not known")?;
+    Ok(replace_home(value, &home))
+}
+
+/// Returns a transformer that replaces a leading `~` in string values, alone
+/// or followed by `/`, with `home`.
+pub fn expand_home_with(home: impl Into<String>) -> impl FnMut(&Value) -> TransformedResult {
+    let home = home.into();
+    move |value| Ok(replace_home(value, &home))
+}
+
+/// Turns the strings `true`, `yes` and `on` into `true`, and `false`, `no`
+/// and `off` into `false`, ignoring case.
+///
+/// This is meant for booleans written as strings, or produced by
+/// substitution as with [resolve_env]. The transformer does not know the type
+/// of the field, so a string field holding one of these words no longer
+/// deserializes; use it only on values whose strings are never meant
+/// literally.
+pub fn coerce_bool(value: &Value) -> TransformedResult {
+    let Value::String(string, span) = value else {
+        return Ok(None);
+    };
+    let boolean = match string.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => true,
+        "false" | "no" | "off" => false,
+        _ => return Ok(None),
+    };
+    Ok(Some(Value::Bool(boolean, span.clone())))
+}
+
+/// Returns a transformer that applies `first`, then `second` to the value
+/// `first` produced, or to the original value if `first` left it unchanged.
+///
+/// An error from either transformer is returned as is.
+pub fn chain<A, B>(mut first: A, mut second: B) -> impl FnMut(&Value) -> TransformedResult
+where
+    A: FnMut(&Value) -> TransformedResult,
+    B: FnMut(&Value) -> TransformedResult,
+{
+    move |value| match first(value)? {
+        Some(value) => Ok(Some(second(&value)?.unwrap_or(value))),
+        None => second(value),
+    }
+}
+
+/// Returns a transformer that applies `transformer` only to the values whose
+/// path matches `pattern`, a [PathPattern] like `models[*].config.*`, and
+/// leaves all others unchanged.
+///
+/// The path is that of the value within the [Value] being deserialized, as
+/// shown in error messages. Values passed to the returned transformer other
+/// than by deserialization have no path and are left unchanged.
+///
+/// # Panics
+///
+/// Panics if `pattern` is not a valid [PathPattern].
+///
+/// ```
+/// use dbt_serde_yaml::value::transform;
+/// use dbt_serde_yaml::Value;
+/// use std::collections::BTreeMap;
+///
+/// let yaml = "
+/// enabled: 'yes'
+/// config:
+///   enabled: 'yes'
+/// ";
+/// let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+/// let transformer = transform::at_path("config.enabled", transform::coerce_bool);
+/// let value: Value = value.into_typed(|_, _, _| {}, transformer).unwrap();
+/// assert_eq!(value["enabled"], "yes");
+/// assert_eq!(value["config"]["enabled"], true);
+/// ```
+pub fn at_path<F>(pattern: &str, mut transformer: F) -> impl FnMut(&Value) -> TransformedResult
+where
+    F: FnMut(&Value) -> TransformedResult,
+{
+    let pattern: PathPattern = match pattern.parse() {
+        Ok(pattern) => pattern,
+        Err(_) => panic!("invalid path pattern: {:?}", pattern),
+    };
+    move |value| {
+        if current_path_matches(&pattern) {
+            transformer(value)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn current_path_matches(pattern: &PathPattern) -> bool {
+    callback::with_current(|path, _| pattern.matches(path)).unwrap_or(false)
+}
+
+fn substitute_env(value: &Value, lookup: &dyn Fn(&str) -> Option<String>) -> TransformedResult {
+    let Value::String(string, span) = value else {
+        return Ok(None);
+    };
+    let mut out = String::new();
+    let mut rest = string.as_str();
+    let mut changed = false;
+    while let Some(start) = rest.find(['$', '{']) {
+        let (before, from) = rest.split_at(start);
+        out.push_str(before);
+        let Some((reference, len)) = parse_reference(from) else {
+            let skip = from.chars().next().map_or(1, char::len_utf8);
+            out.push_str(&from[..skip]);
+            rest = &from[skip..];
+            continue;
+        };
+        match lookup(reference.name).or(reference.default) {
+            Some(replacement) => out.push_str(&replacement),
+            None => {
+                return Err(format!("environment variable `{}` is not set", reference.name).into())
+            }
+        }
+        changed = true;
+        rest = &from[len..];
+    }
+    if !changed {
+        return Ok(None);
+    }
+    out.push_str(rest);
+    Ok(Some(Value::string(out).with_span(span.clone())))
+}
+
+struct Reference<'a> {
+    name: &'a str,
+    default: Option<String>,
+}
+
+/// Parses a `${NAME}` or `{{ env_var('NAME', 'default') }}` reference at the
+/// start of `s`, returning it and its length.
+fn parse_reference(s: &str) -> Option<(Reference<'_>, usize)> {
+    if let Some(inner) = s.strip_prefix("${") {
+        let end = inner.find('}')?;
+        let name = &inner[..end];
+        if !is_variable_name(name) {
+            return None;
+        }
+        let reference = Reference {
+            name,
+            default: None,
+        };
+        return Some((reference, end + 3));
+    }
+
+    let inner = s.strip_prefix("{{")?;
+    let end = inner.find("}}")?;
+    let call = inner[..end].trim().strip_prefix("env_var")?.trim_start();
+    let args = call.strip_prefix('(')?.strip_suffix(')')?;
+    let (name, args) = parse_quoted(args)?;
+    let default = match args.strip_prefix(',') {
+        Some(args) => {
+            let (default, args) = parse_quoted(args)?;
+            if !args.is_empty() {
+                return None;
+            }
+            Some(default.to_owned())
+        }
+        None if args.is_empty() => None,
+        None => return None,
+    };
+    Some((Reference { name, default }, end + 4))
+}
+
+/// Parses a quoted string at the start of `s`, surrounded by whitespace, and
+/// returns its contents and what follows it.
+fn parse_quoted(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let quote = s.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let s = &s[1..];
+    let end = s.find(quote)?;
+    Some((&s[..end], s[end + 1..].trim_start()))
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_home_relative(value: &Value) -> bool {
+    match value {
+        Value::String(string, _) => string.as_str() == "~" || string.starts_with("~/"),
+        _ => false,
+    }
+}
+
+fn replace_home(value: &Value, home: &str) -> Option<Value> {
+    if !is_home_relative(value) {
+        return None;
+    }
+    let Value::String(string, span) = value else {
+        return None;
+    };
+    let expanded = format!("{}{}", home.trim_end_matches('/'), &string[1..]);
+    Some(Value::string(expanded).with_span(span.clone()))
+}
@@ -0,0 +1,57 @@
+//! Adaptors for unused key callbacks, as passed to [`Value::into_typed`].
+//!
+//! [`Value::into_typed`]: crate::Value::into_typed
+
+use crate::path::{Path, PathPattern};
+use crate::Value;
+
+/// Returns an unused key callback that passes on to `callback` only the keys
+/// whose path matches none of `patterns`, each a [PathPattern] like
+/// `models[*].meta.*`.
+///
+/// The path of an unused key is that of its value, e.g. `models[0].nmae`.
+///
+/// # Panics
+///
+/// Panics if one of `patterns` is not a valid [PathPattern].
+///
+/// ```
+/// use dbt_serde_yaml::value::{unused_keys, UnusedKeyCollector};
+/// use dbt_serde_yaml::Value;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     name: String,
+/// }
+///
+/// let yaml = "name: orders\nx-owner: data\nnmae: typo\n";
+/// let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+/// let mut unused = UnusedKeyCollector::new();
+/// let callback = unused_keys::ignore_paths(["x-owner"], unused.collect());
+/// let model: Model = value.into_typed(callback, |_| Ok(None)).unwrap();
+///
+/// assert_eq!(model.name, "orders");
+/// let unused = unused.into_vec();
+/// assert_eq!(unused.len(), 1);
+/// assert_eq!(unused[0].0.to_string(), "nmae");
+/// ```
+pub fn ignore_paths<I, U>(patterns: I, mut callback: U) -> impl FnMut(Path<'_>, &Value, &Value)
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+    U: FnMut(Path<'_>, &Value, &Value),
+{
+    let patterns: Vec<PathPattern> = patterns
+        .into_iter()
+        .map(|pattern| match pattern.as_ref().parse() {
+            Ok(parsed) => parsed,
+            Err(_) => panic!("invalid path pattern: {:?}", pattern.as_ref()),
+        })
+        .collect();
+    move |path, key, value| {
+        if !patterns.iter().any(|pattern| pattern.matches(&path)) {
+            callback(path, key, value);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::config::{self, Sink};
+use crate::path::Path;
+use crate::{trace, Config, Span};
+
+/// An enum variant that was matched only after normalizing its spelling.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NormalizedVariant {
+    /// The path of the value naming the variant, like `models[0].materialized`.
+    pub path: String,
+    /// The span of the value naming the variant.
+    pub span: Span,
+    /// The variant name as it appeared in the input.
+    pub found: String,
+    /// The name of the variant it was matched to.
+    pub variant: &'static str,
+}
+
+/// Runs `f` with lenient enum variant matching enabled for deserialization
+/// from a [`Value`](crate::Value), returning every variant name that had to
+/// be normalized to match.
+///
+/// A variant name that doesn't match exactly is compared ignoring ASCII case,
+/// `-` and `_`, so `Table`, `TABLE` and `incremental-merge` match the variants
+/// `table` and `incremental_merge`. Names that are ambiguous under this
+/// comparison are left as they are, and fail as usual.
+///
+/// This sets [`Config::lenient_variants`](crate::Config::lenient_variants)
+/// and [`Config::normalized_variants`](crate::Config::normalized_variants) in
+/// the thread's current config while `f` runs. Calls may be nested; each call
+/// only returns the normalizations performed within its own scope.
+///
+/// ```
+/// use dbt_serde_yaml::value::with_lenient_variants;
+/// use dbt_serde_yaml::Value;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// #[serde(rename_all = "snake_case")]
+/// enum Materialized {
+///     Table,
+///     IncrementalMerge,
+/// }
+///
+/// let value: Value = dbt_serde_yaml::from_str("[Table, incremental-merge]").unwrap();
+/// let (result, normalized) = with_lenient_variants(|| {
+///     value.into_typed::<Vec<Materialized>, _, _>(|_, _, _| {}, |_| Ok(None))
+/// });
+///
+/// assert_eq!(
+///     result.unwrap(),
+///     [Materialized::Table, Materialized::IncrementalMerge]
+/// );
+/// assert_eq!(normalized.len(), 2);
+/// assert_eq!(normalized[0].path, ".[0]");
+/// assert_eq!(normalized[0].found, "Table");
+/// assert_eq!(normalized[0].variant, "table");
+/// ```
+pub fn with_lenient_variants<R>(f: impl FnOnce() -> R) -> (R, Vec<NormalizedVariant>) {
+    let sink = Sink::new();
+    let change = |config: &mut Config| {
+        config.lenient_variants = true;
+        config.normalized_variants = Some(sink.clone());
+    };
+    let result = config::with_changed(change, f);
+    (result, sink.take())
+}
+
+/// Resolves `tag` against the `variants` of the enum being deserialized.
+///
+/// Returns `tag` itself unless lenient matching is enabled and `tag` matches
+/// exactly one of `variants` only after normalization.
+pub(crate) fn resolve<'a>(
+    tag: &'a str,
+    variants: &'static [&'static str],
+    path: &Path,
+    span: &Span,
+) -> &'a str {
+    if variants.contains(&tag) || !config::lenient_variants() {
+        return tag;
+    }
+    let mut candidates = variants.iter().filter(|variant| same_variant(tag, variant));
+    match (candidates.next(), candidates.next()) {
+        (Some(variant), None) => {
+            trace::variant_normalized(path, tag, variant);
+            if let Some(normalized) = config::sink(|config| &config.normalized_variants) {
+                normalized.push(NormalizedVariant {
+                    path: path.to_string(),
+                    span: span.clone(),
+                    found: tag.to_string(),
+                    variant,
+                });
+            }
+            variant
+        }
+        _ => tag,
+    }
+}
+
+fn same_variant(a: &str, b: &str) -> bool {
+    let significant = |s: &str| {
+        s.bytes()
+            .filter(|b| *b != b'_' && *b != b'-')
+            .map(|b| b.to_ascii_lowercase())
+            .collect::<Vec<u8>>()
+    };
+    significant(a) == significant(b)
+}
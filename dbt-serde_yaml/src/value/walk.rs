@@ -0,0 +1,111 @@
+use crate::path::Path;
+use crate::Value;
+
+impl Value {
+    /// Visits this value and every value nested inside it, depth-first and in
+    /// document order, together with its path relative to this value.
+    ///
+    /// Parents are visited before their children. The span of each node is
+    /// available from [`Value::span`]. Mapping keys are not visited; values
+    /// under non-string keys are reported with an unknown path component. The
+    /// value inside a tagged value is not visited separately from the tagged
+    /// value itself, but its children are.
+    ///
+    /// ```
+    /// use dbt_serde_yaml::Value;
+    ///
+    /// let value: Value = dbt_serde_yaml::from_str("
+    /// models:
+    ///   - name: orders
+    ///     description: ''
+    /// ").unwrap();
+    ///
+    /// let mut empty = Vec::new();
+    /// value.walk(|path, node| {
+    ///     if node.as_str() == Some("") {
+    ///         empty.push((path.to_string(), node.span().start.line()));
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(empty, [("models[0].description".to_string(), 4)]);
+    /// ```
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(Path<'_>, &Value),
+    {
+        walk(self, Path::Root, &mut f);
+    }
+
+    /// Like [`walk`](Value::walk), but with mutable access to each value.
+    ///
+    /// Since parents are visited before their children, changes made to a
+    /// value determine which children are visited next.
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Path<'_>, &mut Value),
+    {
+        walk_mut(self, Path::Root, &mut f);
+    }
+}
+
+fn walk(value: &Value, path: Path, f: &mut dyn FnMut(Path<'_>, &Value)) {
+    f(path, value);
+    let mut value = value;
+    while let Value::Tagged(tagged, ..) = value {
+        value = &tagged.value;
+    }
+    match value {
+        Value::Sequence(sequence, ..) => {
+            for (index, element) in sequence.iter().enumerate() {
+                walk(
+                    element,
+                    Path::Seq {
+                        parent: &path,
+                        index,
+                    },
+                    f,
+                );
+            }
+        }
+        Value::Mapping(mapping, ..) => {
+            for (key, value) in mapping {
+                match key.as_str() {
+                    Some(key) => walk(value, Path::Map { parent: &path, key }, f),
+                    None => walk(value, Path::Unknown { parent: &path }, f),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn walk_mut(value: &mut Value, path: Path, f: &mut dyn FnMut(Path<'_>, &mut Value)) {
+    f(path, value);
+    let mut value = value;
+    while let Value::Tagged(tagged, ..) = value {
+        value = &mut tagged.value;
+    }
+    match value {
+        Value::Sequence(sequence, ..) => {
+            for (index, element) in sequence.iter_mut().enumerate() {
+                walk_mut(
+                    element,
+                    Path::Seq {
+                        parent: &path,
+                        index,
+                    },
+                    f,
+                );
+            }
+        }
+        Value::Mapping(mapping, ..) => {
+            for (key, value) in mapping.iter_mut() {
+                match key.as_str() {
+                    Some(key) => walk_mut(value, Path::Map { parent: &path, key }, f),
+                    None => walk_mut(value, Path::Unknown { parent: &path }, f),
+                }
+            }
+        }
+        _ => {}
+    }
+}
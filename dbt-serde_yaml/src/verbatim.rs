@@ -1,16 +1,24 @@
 //! This module defines the `Verbatim` type, which is a wrapper type that can be
 //! used to in `#[derive(Deserialize)]` structs to protect fields from the
-//! `field_transfomer` when deserialized by the `Value::into_typed` method.
+//! `field_transfomer` when deserialized by the `Value::into_typed` method, and
+//! the `Transformed` type, which opts fields nested inside a `Verbatim` value
+//! back into transformation.
 
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     fmt::{self, Debug},
     hash::Hash,
     hash::Hasher,
     ops::{Deref, DerefMut},
 };
 
+use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::value::{TransformPolicy, ValueDeserializer};
+use crate::{Path, Value};
+
 ////////////////////////////////////////////////////////////////////////
 
 /// A wrapper type that protects the inner value from being transformed by the
@@ -23,186 +31,606 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 /// but still treat the field *as though* it were some primitive type like
 /// `bool` in the Json schema -- in which case you would use `Verbatim<Value,
 /// bool>`.
+///
+/// To transform `Verbatim` fields anyway for a single call, use
+/// [Value::into_typed_with_policy](crate::Value::into_typed_with_policy) with
+/// [TransformPolicy::IncludeVerbatim].
+///
+/// # Flattened fields
+///
+/// A `Verbatim` field flattened with `#[serde(flatten)]`, like
+/// `rest: Verbatim<HashMap<String, Value>>`, keeps the raw values of the
+/// entries it collects, even though serde buffers them before `Verbatim` gets
+/// to see them. The entries of a flattened field that is not itself
+/// `Verbatim`, like `rest: HashMap<String, Verbatim<Value>>`, are transformed
+/// while buffered; use a dunder-flatten field (`__rest__`, with the
+/// `flatten_dunder` feature) for those instead, which this crate flattens
+/// itself without buffering.
 pub struct Verbatim<T, Sch = T>(pub T, pub std::marker::PhantomData<Sch>);
 
-impl<T, Sch> Deref for Verbatim<T, Sch> {
-    type Target = T;
+/// A wrapper type that re-enables the `field_transformer` for the inner value
+/// when it is nested inside a [Verbatim] value.
+///
+/// This allows preserving most of a subtree verbatim while still transforming
+/// chosen fields, for example keeping raw Jinja strings untouched while still
+/// normalizing numeric defaults:
+///
+/// ```
+/// use dbt_serde_yaml::{Transformed, Value, Verbatim};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     sql: String,
+///     threads: Transformed<i64>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Model {
+///     config: Verbatim<Config>,
+/// }
+///
+/// let value: Value =
+///     dbt_serde_yaml::from_str("config: {sql: '{{ x }}', threads: '{{ n }}'}").unwrap();
+/// let model: Model = value
+///     .into_typed(
+///         |_, _, _| {},
+///         |v| match v.as_str() {
+///             Some("{{ n }}") => Ok(Some(Value::from(4))),
+///             Some(_) => Ok(Some(Value::from("rendered"))),
+///             None => Ok(None),
+///         },
+///     )
+///     .unwrap();
+///
+/// assert_eq!(model.config.sql, "{{ x }}");
+/// assert_eq!(*model.config.threads, 4);
+/// ```
+///
+/// Outside of a [Verbatim] value, `Transformed<T>` behaves exactly like `T`.
+/// The type parameters have the same meaning as for [Verbatim].
+pub struct Transformed<T, Sch = T>(pub T, pub std::marker::PhantomData<Sch>);
+
+// Trait impls shared by the `Verbatim` and `Transformed` wrappers, which only
+// differ in how they deserialize.
+macro_rules! impl_wrapper {
+    ($name:ident) => {
+        impl<T, Sch> Deref for $name<T, Sch> {
+            type Target = T;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T, Sch> DerefMut for $name<T, Sch> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl<T, Sch> AsRef<T> for $name<T, Sch> {
+            fn as_ref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T, Sch> AsMut<T> for $name<T, Sch> {
+            fn as_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+
+        impl<T, Sch> Clone for $name<T, Sch>
+        where
+            T: Clone,
+        {
+            fn clone(&self) -> Self {
+                $name(self.0.clone(), std::marker::PhantomData::<Sch>)
+            }
+        }
+
+        impl<T, Sch> Copy for $name<T, Sch> where T: Copy {}
+
+        impl<T, Sch> Debug for $name<T, Sch>
+        where
+            T: Debug,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl<T, Sch> PartialEq for $name<T, Sch>
+        where
+            T: PartialEq,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl<T, Sch> Eq for $name<T, Sch> where T: Eq {}
+
+        impl<T, Sch> PartialOrd for $name<T, Sch>
+        where
+            T: PartialOrd,
+        {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                self.0.partial_cmp(&other.0)
+            }
+        }
+
+        impl<T, Sch> Ord for $name<T, Sch>
+        where
+            T: Ord,
+        {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        impl<T, Sch> Hash for $name<T, Sch>
+        where
+            T: Hash,
+        {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state)
+            }
+        }
+
+        impl<T, Sch> Default for $name<T, Sch>
+        where
+            T: Default,
+        {
+            fn default() -> Self {
+                $name(T::default(), std::marker::PhantomData::<Sch>)
+            }
+        }
+
+        impl<T, Sch> From<T> for $name<T, Sch> {
+            fn from(value: T) -> Self {
+                $name(value, std::marker::PhantomData::<Sch>)
+            }
+        }
+
+        impl<T, Sch> Serialize for $name<T, Sch>
+        where
+            T: Serialize,
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                self.0.serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "schemars")]
+        impl<T, Sch> schemars::JsonSchema for $name<T, Sch>
+        where
+            Sch: schemars::JsonSchema,
+        {
+            fn schema_name() -> String {
+                Sch::schema_name()
+            }
+
+            fn json_schema(
+                generator: &mut schemars::gen::SchemaGenerator,
+            ) -> schemars::schema::Schema {
+                Sch::json_schema(generator)
+            }
+
+            fn is_referenceable() -> bool {
+                Sch::is_referenceable()
+            }
+
+            fn schema_id() -> std::borrow::Cow<'static, str> {
+                Sch::schema_id()
+            }
+
+            #[doc(hidden)]
+            fn _schemars_private_non_optional_json_schema(
+                generator: &mut schemars::gen::SchemaGenerator,
+            ) -> schemars::schema::Schema {
+                Sch::_schemars_private_non_optional_json_schema(generator)
+            }
+
+            #[doc(hidden)]
+            fn _schemars_private_is_option() -> bool {
+                Sch::_schemars_private_is_option()
+            }
+        }
+    };
+}
+
+impl_wrapper!(Verbatim);
+impl_wrapper!(Transformed);
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl<'de, T, Sch> Deserialize<'de> for Verbatim<T, Sch>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let include_verbatim =
+            crate::config::transform_policy() == TransformPolicy::IncludeVerbatim;
+        let _g = (!include_verbatim).then(with_should_not_transform_any);
+        let value = if !include_verbatim && has_flattened_raw_entries() {
+            T::deserialize(FlattenedRaw(deserializer))?
+        } else {
+            T::deserialize(deserializer)?
+        };
+        Ok(Verbatim(value, std::marker::PhantomData::<Sch>))
     }
 }
 
-impl<T, Sch> DerefMut for Verbatim<T, Sch> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl<'de, T, Sch> Deserialize<'de> for Transformed<T, Sch>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let _g = with_should_transform_any();
+        T::deserialize(deserializer)
+            .map(|value| Transformed(value, std::marker::PhantomData::<Sch>))
     }
 }
 
-impl<T, Sch> AsRef<T> for Verbatim<T, Sch> {
-    fn as_ref(&self) -> &T {
-        &self.0
-    }
+pub(crate) fn should_transform_any() -> bool {
+    SHOULD_TRANSFORM_ANY.with(|flag| flag.get())
 }
 
-impl<T, Sch> AsMut<T> for Verbatim<T, Sch> {
-    fn as_mut(&mut self) -> &mut T {
-        &mut self.0
+pub(crate) struct ShouldTransformAnyGuard(bool);
+
+impl Drop for ShouldTransformAnyGuard {
+    fn drop(&mut self) {
+        SHOULD_TRANSFORM_ANY.with(|flag| flag.set(self.0));
     }
 }
 
-impl<T, Sch> Clone for Verbatim<T, Sch>
-where
-    T: Clone,
-{
-    fn clone(&self) -> Self {
-        Verbatim(self.0.clone(), std::marker::PhantomData::<Sch>)
-    }
+pub(crate) fn with_should_not_transform_any() -> ShouldTransformAnyGuard {
+    ShouldTransformAnyGuard(SHOULD_TRANSFORM_ANY.with(|flag| flag.replace(false)))
 }
 
-impl<T, Sch> Copy for Verbatim<T, Sch> where T: Copy {}
+pub(crate) fn with_should_transform_any() -> ShouldTransformAnyGuard {
+    ShouldTransformAnyGuard(SHOULD_TRANSFORM_ANY.with(|flag| flag.replace(true)))
+}
 
-impl<T, Sch> Debug for Verbatim<T, Sch>
-where
-    T: Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
-    }
+thread_local! {
+    static SHOULD_TRANSFORM_ANY: std::cell::Cell<bool>  = const {
+        std::cell::Cell::new(true)
+    };
+    static RAW_ENTRIES: RefCell<Vec<RawEntries>> = const { RefCell::new(Vec::new()) };
 }
 
-impl<T, Sch> PartialEq for Verbatim<T, Sch>
-where
-    T: PartialEq,
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
-    }
+////////////////////////////////////////////////////////////////////////
+
+/// The raw values of the transformed entries of a mapping visited with
+/// `deserialize_map`.
+///
+/// Serde buffers the entries that a `#[serde(flatten)]` field collects, and
+/// they are transformed on their way into the buffer. A [Verbatim] flattened
+/// field reads from that buffer once all entries have been visited, and swaps
+/// the raw values back in.
+struct RawEntries {
+    /// The number of components of the mapping's path.
+    depth: usize,
+    /// Whether all entries of the mapping have been visited.
+    visited: bool,
+    entries: HashMap<String, Value>,
+}
+
+/// Keeps the raw values of the entries of the mapping at `path` that are
+/// transformed until the guard is dropped.
+pub(crate) fn keep_raw_entries(path: &Path) -> RawEntriesGuard {
+    RAW_ENTRIES.with(|cell| {
+        cell.borrow_mut().push(RawEntries {
+            depth: path.components().count(),
+            visited: false,
+            entries: HashMap::new(),
+        })
+    });
+    RawEntriesGuard(())
 }
 
-impl<T, Sch> Eq for Verbatim<T, Sch> where T: Eq {}
+pub(crate) struct RawEntriesGuard(());
 
-impl<T, Sch> PartialOrd for Verbatim<T, Sch>
-where
-    T: PartialOrd,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+impl Drop for RawEntriesGuard {
+    fn drop(&mut self) {
+        RAW_ENTRIES.with(|cell| cell.borrow_mut().pop());
     }
 }
 
-impl<T, Sch> Ord for Verbatim<T, Sch>
-where
-    T: Ord,
-{
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.0.cmp(&other.0)
-    }
+/// Records that all entries of the mapping at `path` have been visited, if it
+/// is the innermost mapping kept by [keep_raw_entries].
+pub(crate) fn raw_entries_visited(path: &Path) {
+    RAW_ENTRIES.with(|cell| {
+        if let Some(raw_entries) = cell.borrow_mut().last_mut() {
+            if path.components().count() == raw_entries.depth {
+                raw_entries.visited = true;
+            }
+        }
+    });
 }
 
-impl<T, Sch> Hash for Verbatim<T, Sch>
-where
-    T: Hash,
-{
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
-    }
+/// Keeps `raw` as the value the entry at `path` had before it was
+/// transformed, if `path` is that of an entry of the innermost mapping kept
+/// by [keep_raw_entries].
+pub(crate) fn keep_raw(path: &Path, raw: impl FnOnce() -> Value) {
+    let Path::Map { key, .. } = path else {
+        return;
+    };
+    RAW_ENTRIES.with(|cell| {
+        let mut stack = cell.borrow_mut();
+        if let Some(raw_entries) = stack.last_mut() {
+            if !raw_entries.visited && path.components().count() == raw_entries.depth + 1 {
+                raw_entries.entries.insert((*key).to_owned(), raw());
+            }
+        }
+    });
 }
 
-impl<T, Sch> Default for Verbatim<T, Sch>
-where
-    T: Default,
-{
-    fn default() -> Self {
-        Verbatim(T::default(), std::marker::PhantomData::<Sch>)
-    }
+fn has_flattened_raw_entries() -> bool {
+    RAW_ENTRIES.with(|cell| {
+        cell.borrow()
+            .last()
+            .is_some_and(|raw_entries| raw_entries.visited && !raw_entries.entries.is_empty())
+    })
 }
 
-impl<T, Sch> From<T> for Verbatim<T, Sch> {
-    fn from(value: T) -> Self {
-        Verbatim(value, std::marker::PhantomData::<Sch>)
-    }
+fn take_flattened_raw(key: &str) -> Option<Value> {
+    RAW_ENTRIES.with(|cell| {
+        let mut stack = cell.borrow_mut();
+        let raw_entries = stack.last_mut().filter(|raw_entries| raw_entries.visited)?;
+        raw_entries.entries.remove(key)
+    })
 }
 
-impl<T, Sch> Serialize for Verbatim<T, Sch>
+/// Deserializes a flattened field from serde's buffer, reading the entries
+/// kept by [keep_raw] from their raw values instead.
+struct FlattenedRaw<D>(D);
+
+/// The visitor of a [FlattenedRaw] deserializer.
+struct FlattenedRawVisitor<V>(V);
+
+/// The entries of a [FlattenedRaw] deserializer.
+struct FlattenedRawMap<A> {
+    map: A,
+    raw: Option<Value>,
+}
+
+macro_rules! forward_to_inner {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.0.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, D> Deserializer<'de> for FlattenedRaw<D>
 where
-    T: Serialize,
+    D: Deserializer<'de>,
 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, D::Error>
     where
-        S: Serializer,
+        V: Visitor<'de>,
     {
-        self.0.serialize(serializer)
+        self.0.deserialize_any(FlattenedRawVisitor(visitor))
     }
-}
 
-impl<'de, T, Sch> Deserialize<'de> for Verbatim<T, Sch>
-where
-    T: Deserialize<'de>,
-{
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, D::Error>
     where
-        D: Deserializer<'de>,
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_map(FlattenedRawVisitor(visitor))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0
+            .deserialize_struct(name, fields, FlattenedRawVisitor(visitor))
+    }
+
+    forward_to_inner! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_option
+        deserialize_unit deserialize_seq deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
     {
-        let _g = with_should_not_transform_any();
-        T::deserialize(deserializer).map(|value| Verbatim(value, std::marker::PhantomData::<Sch>))
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
     }
 }
 
-#[cfg(feature = "schemars")]
-impl<T, Sch> schemars::JsonSchema for Verbatim<T, Sch>
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty))*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                self.0.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V> Visitor<'de> for FlattenedRawVisitor<V>
 where
-    Sch: schemars::JsonSchema,
+    V: Visitor<'de>,
 {
-    fn schema_name() -> String {
-        Sch::schema_name()
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool) visit_i8(i8) visit_i16(i16) visit_i32(i32) visit_i64(i64)
+        visit_i128(i128) visit_u8(u8) visit_u16(u16) visit_u32(u32) visit_u64(u64)
+        visit_u128(u128) visit_f32(f32) visit_f64(f64) visit_char(char) visit_str(&str)
+        visit_borrowed_str(&'de str) visit_string(String) visit_bytes(&[u8])
+        visit_borrowed_bytes(&'de [u8]) visit_byte_buf(Vec<u8>)
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.visit_none()
     }
 
-    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
-        Sch::json_schema(generator)
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0.visit_some(deserializer)
     }
 
-    fn is_referenceable() -> bool {
-        Sch::is_referenceable()
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.visit_unit()
     }
 
-    fn schema_id() -> std::borrow::Cow<'static, str> {
-        Sch::schema_id()
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0.visit_newtype_struct(deserializer)
     }
 
-    #[doc(hidden)]
-    fn _schemars_private_non_optional_json_schema(
-        generator: &mut schemars::gen::SchemaGenerator,
-    ) -> schemars::schema::Schema {
-        Sch::_schemars_private_non_optional_json_schema(generator)
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.0.visit_seq(seq)
     }
 
-    #[doc(hidden)]
-    fn _schemars_private_is_option() -> bool {
-        Sch::_schemars_private_is_option()
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.0.visit_map(FlattenedRawMap { map, raw: None })
     }
-}
 
-pub(crate) fn should_transform_any() -> bool {
-    SHOULD_TRANSFORM_ANY.with(|flag| flag.get())
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.0.visit_enum(data)
+    }
 }
 
-pub(crate) struct ShouldTransformAnyGuard(bool);
+impl<'de, A> MapAccess<'de> for FlattenedRawMap<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
 
-impl Drop for ShouldTransformAnyGuard {
-    fn drop(&mut self) {
-        SHOULD_TRANSFORM_ANY.with(|flag| flag.set(self.0));
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some(key) = self.map.next_key::<Value>()? else {
+            return Ok(None);
+        };
+        self.raw = key.as_str().and_then(take_flattened_raw);
+        seed.deserialize(ValueDeserializer::new(key))
+            .map(Some)
+            .map_err(de::Error::custom)
     }
-}
 
-pub(crate) fn with_should_not_transform_any() -> ShouldTransformAnyGuard {
-    let current = SHOULD_TRANSFORM_ANY.with(|flag| flag.get());
-    SHOULD_TRANSFORM_ANY.with(|flag| flag.set(false));
-    ShouldTransformAnyGuard(current)
-}
+    fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        match self.raw.take() {
+            Some(raw) => {
+                self.map.next_value::<de::IgnoredAny>()?;
+                seed.deserialize(ValueDeserializer::new(raw))
+                    .map_err(de::Error::custom)
+            }
+            None => self.map.next_value_seed(seed),
+        }
+    }
 
-thread_local! {
-    static SHOULD_TRANSFORM_ANY: std::cell::Cell<bool>  = const {
-        std::cell::Cell::new(true)
-    };
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
 }
@@ -0,0 +1,260 @@
+//! Collection of non-fatal issues found while deserializing.
+//!
+//! Running a deserialization inside [collect_warnings] gathers lint-grade
+//! diagnostics about the input that don't make it fail: redefined anchors,
+//! plain scalars that YAML 1.1 would read differently, tolerated tabs in
+//! indentation, heavy use of aliases and duplicate keys resolved by a
+//! recording [DuplicateKey](crate::mapping::DuplicateKey) policy.
+//!
+//! Custom `Deserialize` implementations and field transformers can add their
+//! own with [record_warning].
+
+use crate::config::{self, Sink};
+use crate::path::OwnedPath;
+use crate::{spanned, Marker, ScalarSchema, Span};
+use std::fmt::{self, Display};
+
+/// A non-fatal issue found while deserializing, and where it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// What the issue is.
+    pub kind: WarningKind,
+    /// Where the issue is in the input.
+    pub span: Span,
+}
+
+/// The kinds of [Warning].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// An anchor name was defined again, shadowing its definition at
+    /// `first`. Only reported if the
+    /// [DuplicateAnchor](crate::DuplicateAnchor) policy lets it pass.
+    DuplicateAnchor {
+        /// The name of the anchor.
+        name: String,
+        /// Where the anchor was defined before.
+        first: Marker,
+    },
+    /// A plain scalar like `no`, `on` or `010`, which YAML 1.1 resolves to a
+    /// boolean or an octal integer, but which is read by the rules of YAML 1.2:
+    /// as a string, or under [ScalarSchema::Core] as a decimal integer if it
+    /// has leading zeros.
+    AmbiguousScalar {
+        /// The text of the scalar.
+        value: String,
+        /// What YAML 1.1 resolves the scalar to.
+        yaml11: Yaml11Type,
+        /// The schema the scalar was resolved by.
+        schema: ScalarSchema,
+    },
+    /// A tab in the indentation of a line with content, let through by the
+    /// [TabIndentation](crate::TabIndentation) policy.
+    TabIndentation,
+    /// Aliases were expanded more than half as many times as
+    /// [`ParseOptions::max_alias_expansions`](crate::ParseOptions::max_alias_expansions)
+    /// allows. Reported once per document, at the alias that crossed the
+    /// threshold.
+    AliasExpansions {
+        /// The number of times aliases were expanded so far.
+        count: usize,
+        /// The limit on the number of expansions.
+        limit: usize,
+    },
+    /// A key was repeated in a mapping, and the repeat was let through by
+    /// [`DuplicateKey::IgnoreAndRecord`](crate::mapping::DuplicateKey::IgnoreAndRecord)
+    /// or
+    /// [`DuplicateKey::OverwriteAndRecord`](crate::mapping::DuplicateKey::OverwriteAndRecord).
+    DuplicateKey {
+        /// The path of the repeated entry within the value being
+        /// deserialized, like `models[0].name`, as passed to a duplicate key
+        /// callback.
+        path: OwnedPath,
+        /// Where the key was read first.
+        first: Marker,
+    },
+    /// A warning reported by [record_warning].
+    Custom(String),
+}
+
+/// The type YAML 1.1 resolves an [ambiguous](WarningKind::AmbiguousScalar)
+/// plain scalar to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Yaml11Type {
+    /// A boolean, like `yes` or `off`.
+    Boolean,
+    /// An octal integer, like `0755`.
+    OctalInteger,
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::DuplicateAnchor { name, first } => {
+                write!(
+                    f,
+                    "anchor `{}` redefined (first defined at {})",
+                    name, first
+                )
+            }
+            WarningKind::AmbiguousScalar {
+                value,
+                yaml11,
+                schema,
+            } => match (yaml11, schema) {
+                (Yaml11Type::Boolean, _) => write!(
+                    f,
+                    "`{}` is a string, but YAML 1.1 reads it as a boolean",
+                    value
+                ),
+                (Yaml11Type::OctalInteger, ScalarSchema::Core) => write!(
+                    f,
+                    "`{}` is a decimal integer, but YAML 1.1 reads it as an octal integer",
+                    value
+                ),
+                (Yaml11Type::OctalInteger, ScalarSchema::Lenient) => write!(
+                    f,
+                    "`{}` is a string, but YAML 1.1 reads it as an octal integer",
+                    value
+                ),
+            },
+            WarningKind::TabIndentation => f.write_str("tab character in indentation"),
+            WarningKind::AliasExpansions { count, limit } => write!(
+                f,
+                "aliases expanded {} times, against a limit of {}",
+                count, limit
+            ),
+            WarningKind::DuplicateKey { path, first } => {
+                write!(f, "duplicate entry `{}` (first at {})", path, first)
+            }
+            WarningKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span.is_valid() {
+            write!(f, "{} at {}", self.kind, self.span.start)
+        } else {
+            Display::fmt(&self.kind, f)
+        }
+    }
+}
+
+/// The warnings collected by [collect_warnings], in the order they were
+/// reported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    /// Returns the number of warnings.
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Returns true if there are no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Returns an iterator over the warnings.
+    pub fn iter(&self) -> std::slice::Iter<'_, Warning> {
+        self.warnings.iter()
+    }
+}
+
+impl IntoIterator for Warnings {
+    type Item = Warning;
+    type IntoIter = std::vec::IntoIter<Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.iter()
+    }
+}
+
+/// Runs `f`, collecting every warning reported while it runs.
+///
+/// This sets [`Config::warnings`](crate::Config::warnings) in the thread's
+/// current config while `f` runs. Calls may be nested; each call only returns
+/// the warnings reported within its own scope.
+///
+/// Warnings about the input are found as it is parsed, so they are reported
+/// even if deserializing it then fails.
+///
+/// ```
+/// use dbt_serde_yaml::{collect_warnings, Value};
+///
+/// let (result, warnings) = collect_warnings(|| {
+///     dbt_serde_yaml::from_str::<Value>("country: NO\nlist: [&a 1, &a 2]\n")
+/// });
+///
+/// assert_eq!(result.unwrap()["country"], "NO");
+/// let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     warnings,
+///     [
+///         "`NO` is a string, but YAML 1.1 reads it as a boolean at line 1 column 10",
+///         "anchor `a` redefined (first defined at line 2 column 8) at line 2 column 14",
+///     ],
+/// );
+/// ```
+pub fn collect_warnings<R>(f: impl FnOnce() -> R) -> (R, Warnings) {
+    let sink = Sink::new();
+    let result = config::with_changed(|config| config.warnings = Some(sink.clone()), f);
+    let warnings = Warnings {
+        warnings: sink.take(),
+    };
+    (result, warnings)
+}
+
+/// Reports a warning with `message` at the current source location. Does
+/// nothing outside of [collect_warnings].
+pub fn record_warning(message: impl Into<String>) {
+    if is_collecting() {
+        let span = spanned::get_marker().map(Span::from).unwrap_or_default();
+        warn(WarningKind::Custom(message.into()), span);
+    }
+}
+
+/// Whether warnings are being collected, so that looking for them is worth
+/// it.
+pub(crate) fn is_collecting() -> bool {
+    config::sink(|config| &config.warnings).is_some()
+}
+
+/// Reports a warning of `kind` at `span`, if warnings are being collected.
+pub(crate) fn warn(kind: WarningKind, span: Span) {
+    #[cfg(feature = "filename")]
+    let span = span.maybe_capture_filename();
+
+    if let Some(warnings) = config::sink(|config| &config.warnings) {
+        warnings.push(Warning { kind, span });
+    }
+}
+
+/// What YAML 1.1 resolves the plain scalar `value` to, if it is a boolean or
+/// an octal integer where YAML 1.2 does not read it as one.
+pub(crate) fn yaml11_ambiguity(value: &[u8]) -> Option<Yaml11Type> {
+    match value {
+        b"y" | b"Y" | b"yes" | b"Yes" | b"YES" | b"n" | b"N" | b"no" | b"No" | b"NO" | b"on"
+        | b"On" | b"ON" | b"off" | b"Off" | b"OFF" => Some(Yaml11Type::Boolean),
+        [b'0', digits @ ..]
+            if !digits.is_empty() && digits.iter().all(|b| (b'0'..=b'7').contains(b)) =>
+        {
+            Some(Yaml11Type::OctalInteger)
+        }
+        _ => None,
+    }
+}
@@ -2122,3 +2122,437 @@ pub mod singleton_map_recursive {
         }
     }
 }
+
+/// Serialize/deserialize a date or date-time as a YAML [timestamp].
+///
+/// Deserialization accepts every form the YAML timestamp type allows, whether
+/// or not the scalar carries an explicit `!!timestamp` tag: a plain date like
+/// `2002-12-14`, a date-time separated by `T` or whitespace, an optional
+/// fractional second, and a time zone written as `Z`, `+5`, `-05` or `-05:00`.
+/// A date-time without a time zone is in UTC. Serialization writes the
+/// canonical form, like `2001-12-15T02:59:43.1Z`.
+///
+/// The supported types are those implementing [`Timestamp`]: chrono's
+/// `DateTime<Utc>`, `DateTime<FixedOffset>`, `NaiveDateTime` and `NaiveDate`
+/// with the `chrono` feature, and time's `OffsetDateTime`, `PrimitiveDateTime`
+/// and `Date` with the `time` feature.
+///
+/// [timestamp]: https://yaml.org/type/timestamp.html
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "chrono")]
+/// # mod example {
+/// # use serde_derive::{Deserialize, Serialize};
+/// use chrono::{DateTime, NaiveDate, Utc};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Snapshot {
+///     #[serde(with = "dbt_serde_yaml::with::timestamp")]
+///     updated_at: DateTime<Utc>,
+///     #[serde(with = "dbt_serde_yaml::with::timestamp")]
+///     valid_from: NaiveDate,
+/// }
+///
+/// # pub
+/// fn main() {
+///     let yaml = "updated_at: !!timestamp 2001-12-14 21:59:43.10 -5\nvalid_from: 2002-12-14\n";
+///     let snapshot: Snapshot = dbt_serde_yaml::from_str(yaml).unwrap();
+///     assert_eq!(snapshot.updated_at.to_rfc3339(), "2001-12-15T02:59:43.100+00:00");
+///
+///     let yaml = dbt_serde_yaml::to_string(&snapshot).unwrap();
+///     assert_eq!(yaml, "updated_at: 2001-12-15T02:59:43.1Z\nvalid_from: 2002-12-14\n");
+/// }
+/// # }
+/// # fn main() {
+/// #     #[cfg(feature = "chrono")]
+/// #     example::main();
+/// # }
+/// ```
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod timestamp {
+    use serde::de::{self, Deserializer, Unexpected, Visitor};
+    use serde::ser::{self, Serializer};
+    use std::fmt::{self, Write as _};
+    use std::marker::PhantomData;
+
+    /// A date or date-time type that can be read from and written as a YAML
+    /// timestamp.
+    ///
+    /// This trait is sealed; it is implemented for the chrono and time types
+    /// listed on the [module](self) documentation.
+    pub trait Timestamp: Sized + private::Sealed {
+        #[doc(hidden)]
+        fn from_parts(parts: &private::Parts) -> Option<Self>;
+
+        #[doc(hidden)]
+        fn to_parts(&self) -> Option<private::Parts>;
+    }
+
+    #[allow(missing_docs)]
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Timestamp,
+        S: Serializer,
+    {
+        match value.to_parts() {
+            Some(parts) => serializer.collect_str(&parts),
+            None => Err(ser::Error::custom("timestamp is out of range for YAML")),
+        }
+    }
+
+    #[allow(missing_docs)]
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Timestamp,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(TimestampVisitor(PhantomData))
+    }
+
+    struct TimestampVisitor<T>(PhantomData<T>);
+
+    impl<T> Visitor<'_> for TimestampVisitor<T>
+    where
+        T: Timestamp,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a YAML timestamp")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<T, E>
+        where
+            E: de::Error,
+        {
+            private::Parts::parse(v)
+                .and_then(|parts| T::from_parts(&parts))
+                .ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))
+        }
+    }
+
+    /// Not public API.
+    #[doc(hidden)]
+    pub mod private {
+        use super::*;
+
+        pub trait Sealed {}
+
+        /// The fields of a YAML timestamp.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Parts {
+            pub year: i32,
+            pub month: u8,
+            pub day: u8,
+            /// Hour, minute, second and nanosecond, absent for a plain date.
+            pub time: Option<(u8, u8, u8, u32)>,
+            /// Offset from UTC in seconds, absent if unspecified (UTC).
+            pub offset: Option<i32>,
+        }
+
+        impl Parts {
+            /// Returns `None` if the year does not fit the four digits of the
+            /// YAML 1.1 timestamp grammar, so that it could not be read back.
+            pub fn in_range(self) -> Option<Self> {
+                (0..=9999).contains(&self.year).then_some(self)
+            }
+
+            /// Parses the YAML 1.1 timestamp grammar.
+            pub fn parse(s: &str) -> Option<Self> {
+                let mut rest = s.as_bytes();
+                let year = digits(&mut rest, 4, 4)?;
+                let month = dash_digits(&mut rest)?;
+                let day = dash_digits(&mut rest)?;
+                let date_only = rest.is_empty();
+                let mut parts = Parts {
+                    year: year as i32,
+                    month: month as u8,
+                    day: day as u8,
+                    time: None,
+                    offset: None,
+                };
+                if date_only {
+                    // `2002-12-14`, but not `2002-1-14`.
+                    return (s.len() == 10).then_some(parts);
+                }
+                match rest.first()? {
+                    b'T' | b't' => rest = &rest[1..],
+                    b' ' | b'\t' => rest = skip_blanks(rest),
+                    _ => return None,
+                }
+                let hour = digits(&mut rest, 1, 2)?;
+                let minute = colon_digits(&mut rest)?;
+                let second = colon_digits(&mut rest)?;
+                let mut nanos = 0;
+                if let Some(fraction) = rest.strip_prefix(b".") {
+                    let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+                    for (i, b) in fraction[..len].iter().enumerate().take(9) {
+                        nanos += (b - b'0') as u32 * 10u32.pow(8 - i as u32);
+                    }
+                    rest = &fraction[len..];
+                }
+                parts.time = Some((hour as u8, minute as u8, second as u8, nanos));
+                rest = skip_blanks(rest);
+                if rest == b"Z" {
+                    parts.offset = Some(0);
+                } else if let Some((&sign, mut offset)) = rest.split_first() {
+                    let sign = match sign {
+                        b'+' => 1,
+                        b'-' => -1,
+                        _ => return None,
+                    };
+                    let hours = digits(&mut offset, 1, 2)? as i32;
+                    let minutes = match offset.is_empty() {
+                        true => 0,
+                        false => colon_digits(&mut offset)? as i32,
+                    };
+                    if !offset.is_empty() {
+                        return None;
+                    }
+                    parts.offset = Some(sign * (hours * 3600 + minutes * 60));
+                }
+                Some(parts)
+            }
+        }
+
+        fn digits(rest: &mut &[u8], min: usize, max: usize) -> Option<u32> {
+            let len = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+            if len < min || len > max {
+                return None;
+            }
+            let value = rest[..len]
+                .iter()
+                .fold(0, |n, b| n * 10 + (b - b'0') as u32);
+            *rest = &rest[len..];
+            Some(value)
+        }
+
+        fn dash_digits(rest: &mut &[u8]) -> Option<u32> {
+            *rest = rest.strip_prefix(b"-")?;
+            digits(rest, 1, 2)
+        }
+
+        fn colon_digits(rest: &mut &[u8]) -> Option<u32> {
+            *rest = rest.strip_prefix(b":")?;
+            digits(rest, 2, 2)
+        }
+
+        fn skip_blanks(rest: &[u8]) -> &[u8] {
+            let len = rest
+                .iter()
+                .take_while(|b| matches!(b, b' ' | b'\t'))
+                .count();
+            &rest[len..]
+        }
+
+        impl fmt::Display for Parts {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+                let Some((hour, minute, second, nanos)) = self.time else {
+                    return Ok(());
+                };
+                write!(f, "T{:02}:{:02}:{:02}", hour, minute, second)?;
+                if nanos != 0 {
+                    let fraction = format!("{:09}", nanos);
+                    write!(f, ".{}", fraction.trim_end_matches('0'))?;
+                }
+                match self.offset {
+                    None => Ok(()),
+                    Some(0) => f.write_char('Z'),
+                    Some(offset) => {
+                        let sign = if offset < 0 { '-' } else { '+' };
+                        let offset = offset.unsigned_abs();
+                        write!(f, "{}{:02}:{:02}", sign, offset / 3600, offset / 60 % 60)
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "chrono")]
+        mod chrono_impls {
+            use super::super::Timestamp;
+            use super::{Parts, Sealed};
+            use chrono::{
+                DateTime, Datelike as _, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime,
+                Timelike as _, Utc,
+            };
+
+            fn naive(parts: &Parts) -> Option<NaiveDateTime> {
+                let date =
+                    NaiveDate::from_ymd_opt(parts.year, parts.month as u32, parts.day as u32)?;
+                let (hour, minute, second, nanos) = parts.time.unwrap_or_default();
+                let time =
+                    NaiveTime::from_hms_nano_opt(hour as u32, minute as u32, second as u32, nanos)?;
+                Some(date.and_time(time))
+            }
+
+            fn fixed(parts: &Parts) -> Option<DateTime<FixedOffset>> {
+                let offset = FixedOffset::east_opt(parts.offset.unwrap_or(0))?;
+                naive(parts)?.and_local_timezone(offset).single()
+            }
+
+            fn parts(datetime: NaiveDateTime, offset: Option<i32>) -> Parts {
+                Parts {
+                    year: datetime.year(),
+                    month: datetime.month() as u8,
+                    day: datetime.day() as u8,
+                    time: Some((
+                        datetime.hour() as u8,
+                        datetime.minute() as u8,
+                        datetime.second() as u8,
+                        datetime.nanosecond(),
+                    )),
+                    offset,
+                }
+            }
+
+            impl Sealed for DateTime<Utc> {}
+            impl Timestamp for DateTime<Utc> {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    fixed(parts).map(|datetime| datetime.with_timezone(&Utc))
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    parts(self.naive_utc(), Some(0)).in_range()
+                }
+            }
+
+            impl Sealed for DateTime<FixedOffset> {}
+            impl Timestamp for DateTime<FixedOffset> {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    fixed(parts)
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    let offset = self.offset().local_minus_utc();
+                    parts(self.naive_local(), Some(offset)).in_range()
+                }
+            }
+
+            impl Sealed for NaiveDateTime {}
+            impl Timestamp for NaiveDateTime {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    match parts.offset {
+                        None => naive(parts),
+                        Some(_) => fixed(parts).map(|datetime| datetime.naive_utc()),
+                    }
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    parts(*self, None).in_range()
+                }
+            }
+
+            impl Sealed for NaiveDate {}
+            impl Timestamp for NaiveDate {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    match parts.time {
+                        None => naive(parts).map(|datetime| datetime.date()),
+                        Some(_) => None,
+                    }
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    Parts {
+                        year: self.year(),
+                        month: self.month() as u8,
+                        day: self.day() as u8,
+                        time: None,
+                        offset: None,
+                    }
+                    .in_range()
+                }
+            }
+        }
+
+        #[cfg(feature = "time")]
+        mod time_impls {
+            use super::super::Timestamp;
+            use super::{Parts, Sealed};
+            use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+            fn date(parts: &Parts) -> Option<Date> {
+                let month = Month::try_from(parts.month).ok()?;
+                Date::from_calendar_date(parts.year, month, parts.day).ok()
+            }
+
+            fn primitive(parts: &Parts) -> Option<PrimitiveDateTime> {
+                let (hour, minute, second, nanos) = parts.time.unwrap_or_default();
+                let time = Time::from_hms_nano(hour, minute, second, nanos).ok()?;
+                Some(PrimitiveDateTime::new(date(parts)?, time))
+            }
+
+            fn offset(parts: &Parts) -> Option<OffsetDateTime> {
+                let offset = UtcOffset::from_whole_seconds(parts.offset.unwrap_or(0)).ok()?;
+                Some(primitive(parts)?.assume_offset(offset))
+            }
+
+            fn parts(datetime: PrimitiveDateTime, offset: Option<i32>) -> Parts {
+                Parts {
+                    year: datetime.year(),
+                    month: datetime.month() as u8,
+                    day: datetime.day(),
+                    time: Some((
+                        datetime.hour(),
+                        datetime.minute(),
+                        datetime.second(),
+                        datetime.nanosecond(),
+                    )),
+                    offset,
+                }
+            }
+
+            impl Sealed for OffsetDateTime {}
+            impl Timestamp for OffsetDateTime {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    offset(parts)
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    let local = PrimitiveDateTime::new(self.date(), self.time());
+                    parts(local, Some(self.offset().whole_seconds())).in_range()
+                }
+            }
+
+            impl Sealed for PrimitiveDateTime {}
+            impl Timestamp for PrimitiveDateTime {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    match parts.offset {
+                        None => primitive(parts),
+                        Some(_) => offset(parts).map(|datetime| {
+                            let utc = datetime.to_offset(UtcOffset::UTC);
+                            PrimitiveDateTime::new(utc.date(), utc.time())
+                        }),
+                    }
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    parts(*self, None).in_range()
+                }
+            }
+
+            impl Sealed for Date {}
+            impl Timestamp for Date {
+                fn from_parts(parts: &Parts) -> Option<Self> {
+                    match parts.time {
+                        None => date(parts),
+                        Some(_) => None,
+                    }
+                }
+
+                fn to_parts(&self) -> Option<Parts> {
+                    Parts {
+                        year: self.year(),
+                        month: self.month() as u8,
+                        day: self.day(),
+                        time: None,
+                        offset: None,
+                    }
+                    .in_range()
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Behavioral parity with serde_yaml 0.9, for code migrating from it.
+//!
+//! Every case in the corpus is run through both crates and the results are
+//! compared. Cases on which this crate intentionally behaves differently name
+//! the [Divergence], and the test checks that the difference is exactly the
+//! documented one, so that an exception can neither hide an unrelated change
+//! nor outlive the behavior it describes.
+//!
+//! Run with `cargo test --features compat --test test_compat`.
+
+use dbt_serde_yaml::Value;
+use indoc::indoc;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+
+/// An intentional difference from serde_yaml.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Divergence {
+    /// Errors report the location of the node they are about: the message is
+    /// serde_yaml's with ` at line L column C` appended where serde_yaml has
+    /// no location.
+    Spans,
+    /// Through [Value::into_typed], keys that the target type ignores are
+    /// reported to a callback; serde_yaml drops them silently. The typed
+    /// result is the same.
+    Callbacks,
+    /// A duplicate key in a [Value] mapping also reports where the key was
+    /// first defined, and the location reported is that of the duplicate
+    /// rather than of the mapping.
+    DuplicateKeys,
+    /// Byte arrays serialize as `!!binary` scalars, which serde_yaml refuses.
+    Binary,
+}
+
+impl Divergence {
+    /// Whether this divergence accounts for all of the difference between the
+    /// two results.
+    fn explains(self, outcome: &Outcome) -> bool {
+        match (self, &outcome.dbt, &outcome.serde_yaml) {
+            (Divergence::Spans, Err(dbt), Err(serde_yaml)) => dbt
+                .strip_prefix(serde_yaml.as_str())
+                .is_some_and(is_location),
+            (Divergence::Callbacks, Ok(dbt), Ok(serde_yaml)) => dbt
+                .strip_prefix(serde_yaml.as_str())
+                .and_then(|rest| rest.strip_prefix(" unused "))
+                .is_some(),
+            (Divergence::DuplicateKeys, Err(dbt), Err(serde_yaml)) => {
+                let (message, rest) = match dbt.split_once(" (first defined") {
+                    Some(split) => split,
+                    None => return false,
+                };
+                message == strip_location(serde_yaml)
+                    && rest
+                        .split_once(')')
+                        .is_some_and(|(first, rest)| is_location(first) && is_location(rest))
+            }
+            (Divergence::Binary, Ok(dbt), Err(serde_yaml)) => {
+                dbt.starts_with("!!binary ") && serde_yaml.contains("bytes")
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns `message` without its trailing ` at line L column C`, if any.
+fn strip_location(message: &str) -> &str {
+    match message.rfind(" at line ") {
+        Some(i) if is_location(&message[i..]) => &message[..i],
+        _ => message,
+    }
+}
+
+/// Whether `s` is ` at line L column C`.
+fn is_location(s: &str) -> bool {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    s.strip_prefix(" at line ")
+        .and_then(|s| s.split_once(" column "))
+        .is_some_and(|(line, column)| is_number(line) && is_number(column))
+}
+
+/// The results of one case from each crate: the `Debug` form of the typed
+/// value or the YAML text on success, the error message on failure.
+#[derive(Debug)]
+struct Outcome {
+    dbt: Result<String, String>,
+    serde_yaml: Result<String, String>,
+}
+
+struct Case {
+    name: String,
+    run: Box<dyn Fn() -> Outcome>,
+    divergence: Option<Divergence>,
+}
+
+impl Case {
+    fn diverges(mut self, divergence: Divergence) -> Self {
+        self.divergence = Some(divergence);
+        self
+    }
+}
+
+fn outcome<T: Debug, E1: ToString, E2: ToString>(
+    dbt: Result<T, E1>,
+    serde_yaml: Result<T, E2>,
+) -> Outcome {
+    Outcome {
+        dbt: dbt.map(|t| format!("{:?}", t)).map_err(|e| e.to_string()),
+        serde_yaml: serde_yaml
+            .map(|t| format!("{:?}", t))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Deserializes `yaml` as a `T` with each crate's `from_str`.
+fn de<T: DeserializeOwned + Debug>(yaml: &'static str) -> Case {
+    Case {
+        name: format!("from_str::<{}>({:?})", std::any::type_name::<T>(), yaml),
+        run: Box::new(move || {
+            outcome(
+                dbt_serde_yaml::from_str::<T>(yaml),
+                serde_yaml::from_str::<T>(yaml),
+            )
+        }),
+        divergence: None,
+    }
+}
+
+/// Deserializes `yaml` into each crate's `Value`, and compares them after
+/// converting this crate's into serde_yaml's.
+fn value(yaml: &'static str) -> Case {
+    Case {
+        name: format!("from_str::<Value>({:?})", yaml),
+        run: Box::new(move || {
+            let dbt = dbt_serde_yaml::from_str::<Value>(yaml).map_err(|e| e.to_string());
+            let dbt = dbt.and_then(|v| serde_yaml::to_value(v).map_err(|e| e.to_string()));
+            outcome(dbt, serde_yaml::from_str::<serde_yaml::Value>(yaml))
+        }),
+        divergence: None,
+    }
+}
+
+/// Deserializes `yaml` as a `T` through [Value::into_typed], recording unused
+/// keys, and with serde_yaml's `from_str`.
+fn typed<T: DeserializeOwned + Debug>(yaml: &'static str) -> Case {
+    Case {
+        name: format!("into_typed::<{}>({:?})", std::any::type_name::<T>(), yaml),
+        run: Box::new(move || {
+            let mut unused = Vec::new();
+            let dbt = dbt_serde_yaml::from_str::<Value>(yaml).and_then(|v| {
+                v.into_typed::<T, _, _>(|path, _, _| unused.push(path.to_string()), |_| Ok(None))
+            });
+            let mut outcome = outcome(dbt, serde_yaml::from_str::<T>(yaml));
+            if let (Ok(dbt), false) = (&mut outcome.dbt, unused.is_empty()) {
+                *dbt = format!("{} unused {:?}", dbt, unused);
+            }
+            outcome
+        }),
+        divergence: None,
+    }
+}
+
+/// Serializes `value` with each crate's `to_string`.
+fn ser<T: serde::Serialize + Debug + 'static>(value: T) -> Case {
+    Case {
+        name: format!("to_string({:?})", value),
+        run: Box::new(move || Outcome {
+            dbt: dbt_serde_yaml::to_string(&value).map_err(|e| e.to_string()),
+            serde_yaml: serde_yaml::to_string(&value).map_err(|e| e.to_string()),
+        }),
+        divergence: None,
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct Model {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    columns: Vec<Column>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Column {
+    name: String,
+    #[serde(default)]
+    tests: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+enum Materialization {
+    View,
+    Incremental(String),
+    Snapshot(String, u32),
+    Table { partitions: u32 },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "type")]
+enum Source {
+    Table { name: String },
+    Seed,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct Flattened {
+    name: String,
+    #[serde(flatten)]
+    rest: BTreeMap<String, i32>,
+}
+
+fn corpus() -> Vec<Case> {
+    use Divergence::*;
+
+    vec![
+        // Scalars.
+        de::<i32>("1"),
+        de::<i32>("-0x1f"),
+        de::<i32>("0o17"),
+        de::<i32>("+1"),
+        de::<i32>("x").diverges(Spans),
+        de::<i32>("1.5").diverges(Spans),
+        de::<i32>("'1'").diverges(Spans),
+        de::<u8>("256").diverges(Spans),
+        de::<i64>("-9223372036854775809").diverges(Spans),
+        de::<u128>("340282366920938463463374607431768211455"),
+        de::<f64>("3.14"),
+        de::<f64>(".inf"),
+        de::<f64>("-.Inf"),
+        de::<bool>("true"),
+        de::<bool>("'true'").diverges(Spans),
+        de::<bool>("yes").diverges(Spans),
+        de::<String>("1"),
+        de::<String>("true"),
+        de::<String>("~"),
+        de::<String>("- a").diverges(Spans),
+        de::<char>("a"),
+        de::<char>("ab").diverges(Spans),
+        de::<Option<i32>>(""),
+        de::<Option<i32>>("null"),
+        de::<Option<i32>>("1"),
+        de::<()>(""),
+        // Collections.
+        de::<Vec<i32>>("[1, 2, 3]"),
+        de::<Vec<i32>>("- 1\n- 2\n"),
+        de::<(i32, String)>("[1, x]"),
+        de::<(i32, String)>("[1, x, 3]").diverges(Spans),
+        de::<BTreeMap<String, i32>>("a: 1\nb: 2\n"),
+        de::<BTreeMap<String, i32>>("a: 1\na: 2\n"),
+        de::<BTreeMap<i32, bool>>("1: true\n2: false\n"),
+        de::<BTreeMap<String, i32>>("a: &x 1\nb: *x\n"),
+        de::<BTreeMap<String, i32>>("a: *x\n"),
+        de::<BTreeSet<String>>("[b, a, b]"),
+        // Structs and enums.
+        de::<Model>(indoc! {"
+            name: orders
+            columns:
+              - name: id
+                tests: [unique, not_null]
+        "}),
+        de::<Model>("name: orders\nextra: 1\n"),
+        de::<Model>("description: x\n").diverges(Spans),
+        de::<Model>("name: a\nname: b\n").diverges(Spans),
+        de::<Model>("name: orders\ncolumns:\n  - name: id\n    extra: 1\n"),
+        de::<Model>("name: orders\ncolumns:\n  - tests: []\n"),
+        de::<Materialization>("View"),
+        de::<Materialization>("!Incremental id"),
+        de::<Materialization>("!Snapshot [ts, 1]"),
+        de::<Materialization>("!Table {partitions: 4}"),
+        de::<Materialization>("Incremental: id").diverges(Spans),
+        de::<Materialization>("Ephemeral").diverges(Spans),
+        de::<StringOrList>("a"),
+        de::<StringOrList>("[a, b]"),
+        de::<StringOrList>("{a: b}"),
+        de::<Source>("type: Table\nname: raw\n"),
+        de::<Source>("type: Seed\n"),
+        de::<Flattened>("name: a\nx: 1\ny: 2\n"),
+        // Documents and syntax errors.
+        de::<Vec<i32>>("[1, 2"),
+        de::<Model>("name: [\n"),
+        de::<i32>("---\n1\n---\n2\n"),
+        de::<i32>("--- 1\n...\n"),
+        // Values.
+        value(""),
+        value("a: 1\nb: [x, y]\nc: {d: ~}\n"),
+        value("a: .inf\nb: 0x10\nc: 0o7\nd: 1_000\ne: yes\nf: TRUE\ng: Null\n"),
+        value("a: &x [1, 2]\nb: *x\n"),
+        value("<<: {a: 1}\nb: 2\n"),
+        value("a: !tag 1\n"),
+        value("? [1, 2]\n: 3\n"),
+        value("a: 1\na: 2\n").diverges(DuplicateKeys),
+        value("x: {a: 1, a: 2}\n").diverges(DuplicateKeys),
+        de::<serde_json::Value>("a: 1\na: 2\n"),
+        // Callbacks.
+        typed::<Model>("name: orders\n"),
+        typed::<Model>("name: orders\nextra: 1\n").diverges(Callbacks),
+        typed::<Model>("name: orders\ncolumns: [{name: id}]\nmeta: {}\n").diverges(Callbacks),
+        // Serialization.
+        ser(1),
+        ser(-1.5f64),
+        ser(1e20f64),
+        ser(0.1f32),
+        ser(f64::NAN),
+        ser(f64::NEG_INFINITY),
+        ser(u128::MAX),
+        ser(i128::MIN),
+        ser('x'),
+        ser(true),
+        ser(()),
+        ser(Some(1)),
+        ser(None::<i32>),
+        ser("plain"),
+        ser(""),
+        ser("~"),
+        ser("null"),
+        ser("true"),
+        ser("yes"),
+        ser("1.0"),
+        ser("0x1"),
+        ser("- x"),
+        ser("a: b"),
+        ser("#c"),
+        ser(" leading"),
+        ser("multi\nline\n"),
+        ser(Vec::<i32>::new()),
+        ser(vec![vec![1, 2], vec![]]),
+        ser(BTreeMap::<String, i32>::new()),
+        ser(BTreeMap::from([("a", 1), ("b", 2)])),
+        ser(Model {
+            name: "orders".to_owned(),
+            description: Some("All orders.\nOne per row.\n".to_owned()),
+            columns: vec![Column {
+                name: "id".to_owned(),
+                tests: vec!["unique".to_owned()],
+            }],
+        }),
+        ser(Materialization::View),
+        ser(Materialization::Incremental("id".to_owned())),
+        ser(Materialization::Snapshot("ts".to_owned(), 1)),
+        ser(Materialization::Table { partitions: 4 }),
+        ser(vec![Materialization::Incremental("id".to_owned())]),
+        ser(StringOrList::List(vec!["a".to_owned()])),
+        ser(Source::Table {
+            name: "raw".to_owned(),
+        }),
+        ser(serde_bytes::ByteBuf::from(b"dbt".to_vec())).diverges(Binary),
+    ]
+}
+
+#[test]
+fn test_compat() {
+    let mut failures = Vec::new();
+    for case in corpus() {
+        let outcome = (case.run)();
+        let same = outcome.dbt == outcome.serde_yaml;
+        match case.divergence {
+            None if same => {}
+            None => failures.push(format!(
+                "{}: unexpected divergence {:?}",
+                case.name, outcome
+            )),
+            Some(divergence) if same => failures.push(format!(
+                "{}: {:?} is listed but the results agree",
+                case.name, divergence,
+            )),
+            Some(divergence) if divergence.explains(&outcome) => {}
+            Some(divergence) => failures.push(format!(
+                "{}: {:?} does not explain {:?}",
+                case.name, divergence, outcome,
+            )),
+        }
+    }
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}
+
+#[test]
+fn test_compat_divergences_exercised() {
+    let exercised: BTreeSet<Divergence> =
+        corpus().iter().filter_map(|case| case.divergence).collect();
+    let all = [
+        Divergence::Spans,
+        Divergence::Callbacks,
+        Divergence::DuplicateKeys,
+        Divergence::Binary,
+    ];
+    for divergence in all {
+        assert!(
+            exercised.contains(&divergence),
+            "{:?} has no case",
+            divergence
+        );
+    }
+}
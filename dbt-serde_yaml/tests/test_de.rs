@@ -9,7 +9,7 @@
 use dbt_serde_yaml::{Deserializer, Number, Value};
 use indoc::indoc;
 use serde_derive::Deserialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 
 fn test_de<T>(yaml: &str, expected: &T)
@@ -86,6 +86,82 @@ fn test_alias() {
     test_de(yaml, &expected);
 }
 
+#[test]
+fn test_alias_redefined_anchor() {
+    let yaml = indoc! {"
+        a: &x 1
+        b: &x 2
+        c: &y 3
+        d: *x
+        e: *y
+    "};
+    let mut expected = BTreeMap::new();
+    expected.insert("a".to_owned(), 1);
+    expected.insert("b".to_owned(), 2);
+    expected.insert("c".to_owned(), 3);
+    expected.insert("d".to_owned(), 2);
+    expected.insert("e".to_owned(), 3);
+    test_de(yaml, &expected);
+}
+
+#[test]
+fn test_config() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::{Config, Mapping, ParseOptions, ScalarSchema};
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        version: Value,
+        meta: Mapping,
+    }
+
+    let yaml = indoc! {"
+        version: 01
+        meta:
+          owner: a
+          owner: b
+    "};
+
+    let err = dbt_serde_yaml::from_str::<Model>(yaml).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("meta: duplicate entry with key \"owner\""));
+
+    let mut config = Config::new();
+    config.scalar_schema = ScalarSchema::Core;
+    config.duplicate_keys = DuplicateKey::Overwrite;
+    let model: Model = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(model.version, 1);
+    assert_eq!(model.meta["owner"], "b");
+
+    let value = Value::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(value["meta"]["owner"], "b");
+
+    {
+        let _scope = config.enter();
+        assert_eq!(Config::current().duplicate_keys, DuplicateKey::Overwrite);
+        let model: Model = dbt_serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(model.version, 1);
+
+        let mut inner = Config::new();
+        inner.duplicate_keys = DuplicateKey::Ignore;
+        inner.parse_options = ParseOptions::new().max_nodes(Some(5));
+        {
+            let _scope = inner.enter();
+            let err = dbt_serde_yaml::from_str::<Model>(yaml).unwrap_err();
+            assert_eq!(err.to_string(), "node limit exceeded at line 3 column 3");
+            let value: Value = dbt_serde_yaml::from_str("{a: 1, a: 2}").unwrap();
+            assert_eq!(value["a"], 1);
+        }
+
+        assert_eq!(Config::current().scalar_schema, ScalarSchema::Core);
+    }
+
+    assert_eq!(Config::current().duplicate_keys, DuplicateKey::Error);
+    let model: Model = dbt_serde_yaml::from_str("version: 01\nmeta: {}\n").unwrap();
+    assert_eq!(model.version, "01");
+}
+
 #[test]
 fn test_option() {
     #[derive(Deserialize, PartialEq, Debug)]
@@ -716,6 +792,118 @@ fn test_parse_number() {
     assert_eq!(err.to_string(), "failed to parse YAML number");
 }
 
+#[test]
+fn test_number_literal_helpers() {
+    assert_eq!(Number::from_str_radix("7f", 16).unwrap(), Number::from(127));
+    assert_eq!(Number::from_str_radix("-17", 8).unwrap(), Number::from(-15));
+    assert_eq!(
+        Number::from_str_radix("18446744073709551615", 10).unwrap(),
+        Number::from(u64::MAX),
+    );
+    assert!(Number::from_str_radix("12", 2).is_err());
+
+    for (repr, expected) in [
+        ("12", Number::from(12)),
+        ("+12", Number::from(12)),
+        ("-12", Number::from(-12)),
+        ("0x1f", Number::from(31)),
+        ("-0x1f", Number::from(-31)),
+        ("0o17", Number::from(15)),
+        ("0b101", Number::from(5)),
+    ] {
+        assert_eq!(Number::from_int_literal(repr).unwrap(), expected);
+        assert_eq!(repr.parse::<Number>().unwrap(), expected);
+    }
+    for repr in ["1.5", ".inf", "007", "0x", "null", ""] {
+        let err = Number::from_int_literal(repr).unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse YAML number");
+    }
+
+    assert_eq!(
+        Number::from_float_literal("-1.5").unwrap(),
+        Number::from(-1.5)
+    );
+    assert_eq!(Number::from_float_literal("3").unwrap(), Number::from(3.0));
+    assert!(Number::from_float_literal(".NaN").unwrap().is_nan());
+    assert!(Number::from_float_literal("007").is_err());
+    assert!(Number::from_float_literal("0x10").is_err());
+
+    for (repr, expected) in [
+        ("1_000", Number::from(1000)),
+        ("+1__0", Number::from(10)),
+        ("-1_000_000", Number::from(-1_000_000)),
+        ("0b1010_0111", Number::from(0b1010_0111)),
+        ("0x_0A_74_AE", Number::from(0x0A74AE)),
+        ("-0o1_7", Number::from(-15)),
+        ("1_000.5", Number::from(1000.5)),
+        ("1_0e3", Number::from(10000.0)),
+        ("12", Number::from(12)),
+    ] {
+        assert_eq!(
+            Number::from_literal_with_separators(repr).unwrap(),
+            expected,
+        );
+    }
+    for repr in ["_1", "-_1", "1.0_5", "1e1_0", "0_7", "_", "1_000x"] {
+        let err = Number::from_literal_with_separators(repr).unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse YAML number");
+    }
+    let value: Value = dbt_serde_yaml::from_str("1_000").unwrap();
+    assert_eq!(value, Value::from("1_000"));
+}
+
+#[test]
+fn test_number_conversions() {
+    let big = Number::from(u64::MAX);
+    assert_eq!(u64::try_from(&big).unwrap(), u64::MAX);
+    assert_eq!(i128::try_from(big.clone()).unwrap(), u64::MAX as i128);
+    assert_eq!(u128::try_from(&big).unwrap(), u64::MAX as u128);
+    let err = i64::try_from(&big).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot represent 18446744073709551615 as i64"
+    );
+
+    let negative = Number::from(-300);
+    assert_eq!(i16::try_from(&negative).unwrap(), -300);
+    assert_eq!(isize::try_from(&negative).unwrap(), -300);
+    assert!(i8::try_from(&negative).is_err());
+    assert!(usize::try_from(&negative).is_err());
+    assert!(u128::try_from(&negative).is_err());
+
+    let err = u32::try_from(Number::from(2.0)).unwrap_err();
+    assert_eq!(err.to_string(), "cannot represent 2.0 as u32");
+
+    assert_eq!(Number::try_from(-1i128).unwrap(), Number::from(-1));
+    assert_eq!(
+        Number::try_from(u64::MAX as i128).unwrap(),
+        Number::from(u64::MAX)
+    );
+    assert!(Number::try_from(u64::MAX as i128 + 1).is_err());
+    assert!(Number::try_from(i64::MIN as i128 - 1).is_err());
+
+    let one = Number::from(1);
+    assert_eq!(big.checked_add(&one), None);
+    assert_eq!(big.checked_sub(&one), Some(Number::from(u64::MAX - 1)));
+    assert_eq!(Number::from(i64::MIN).checked_sub(&one), None,);
+    assert_eq!(
+        Number::from(i64::MAX).checked_add(&one),
+        Some(Number::from(i64::MAX as u64 + 1)),
+    );
+    assert_eq!(
+        Number::from(-2).checked_mul(&Number::from(-3)),
+        Some(Number::from(6))
+    );
+    assert_eq!(
+        one.checked_add(&Number::from(f64::INFINITY)),
+        Some(Number::from(f64::INFINITY))
+    );
+    assert_eq!(
+        Number::from(0.5).checked_mul(&Number::from(3)),
+        Some(Number::from(1.5))
+    );
+}
+
 #[test]
 fn test_multiline_string() {
     #[derive(Deserialize, PartialEq, Debug)]
@@ -756,3 +944,865 @@ fn test_multiline_string() {
 
     test_de(yaml, &expected);
 }
+
+#[test]
+fn test_set() {
+    let yaml = indoc! {"
+        !!set
+        ? orders
+        ? customers
+    "};
+    let expected = BTreeSet::from(["customers".to_owned(), "orders".to_owned()]);
+    test_de(yaml, &expected);
+
+    let yaml = "!!set {orders, customers: ~}";
+    let expected = vec!["orders".to_owned(), "customers".to_owned()];
+    test_de(yaml, &expected);
+
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(dbt_serde_yaml::to_value(&value).unwrap(), value);
+    let yaml = dbt_serde_yaml::to_string(&value).unwrap();
+    assert_eq!(yaml, "!!set\norders: null\ncustomers: null\n");
+
+    let yaml = "!!set {}";
+    test_de(yaml, &Vec::<String>::new());
+}
+
+#[test]
+fn test_omap() {
+    let yaml = indoc! {"
+        !!omap
+        - seed: 1
+        - run: 2
+        - test: 3
+    "};
+    let expected = vec![
+        ("seed".to_owned(), 1),
+        ("run".to_owned(), 2),
+        ("test".to_owned(), 3),
+    ];
+    test_de(yaml, &expected);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Pipeline {
+        steps: Vec<(String, BTreeMap<String, i32>)>,
+    }
+    let yaml = indoc! {"
+        steps: !!omap
+          - &build
+            build: {threads: 4}
+          - *build
+    "};
+    let expected = Pipeline {
+        steps: vec![
+            (
+                "build".to_owned(),
+                BTreeMap::from([("threads".to_owned(), 4)]),
+            ),
+            (
+                "build".to_owned(),
+                BTreeMap::from([("threads".to_owned(), 4)]),
+            ),
+        ],
+    };
+    test_de(yaml, &expected);
+}
+
+#[test]
+fn test_core_schema() {
+    use dbt_serde_yaml::ScalarSchema;
+    use serde::Deserialize as _;
+
+    let yaml = "[01, -007, +12, 0x1F, 0o17, 0b11, -0x1F, 1., .5, 1e3, -.inf, yes, ~]";
+    let lenient = Value::deserialize(Deserializer::from_str(yaml)).unwrap();
+    let core =
+        Value::deserialize(Deserializer::from_str(yaml).with_scalar_schema(ScalarSchema::Core))
+            .unwrap();
+
+    let expected_lenient: Vec<Value> = vec![
+        "01".into(),
+        "-007".into(),
+        12.into(),
+        31.into(),
+        15.into(),
+        3.into(),
+        (-31).into(),
+        1.0.into(),
+        0.5.into(),
+        1000.0.into(),
+        f64::NEG_INFINITY.into(),
+        "yes".into(),
+        Value::null(),
+    ];
+    let expected_core: Vec<Value> = vec![
+        1.into(),
+        (-7).into(),
+        12.into(),
+        31.into(),
+        15.into(),
+        "0b11".into(),
+        "-0x1F".into(),
+        1.0.into(),
+        0.5.into(),
+        1000.0.into(),
+        f64::NEG_INFINITY.into(),
+        "yes".into(),
+        Value::null(),
+    ];
+    assert_eq!(lenient, Value::from(expected_lenient));
+    assert_eq!(core, Value::from(expected_core));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        port: u16,
+        offset: i32,
+        ratio: f64,
+    }
+    let yaml = "{port: 08080, offset: -010, ratio: 1e-1}";
+    assert!(dbt_serde_yaml::from_str::<Config>(yaml).is_err());
+    let de = Deserializer::from_str(yaml).with_scalar_schema(ScalarSchema::Core);
+    let expected = Config {
+        port: 8080,
+        offset: -10,
+        ratio: 0.1,
+    };
+    assert_eq!(Config::deserialize(de).unwrap(), expected);
+
+    let de = Deserializer::from_str("port: 0b1").with_scalar_schema(ScalarSchema::Core);
+    assert!(u16::deserialize(de).is_err());
+
+    let mut documents =
+        Deserializer::from_str("--- 01\n--- 02\n").with_scalar_schema(ScalarSchema::Core);
+    assert_eq!(u8::deserialize(documents.next().unwrap()).unwrap(), 1);
+    assert_eq!(u8::deserialize(documents.next().unwrap()).unwrap(), 2);
+}
+
+#[test]
+fn test_deeply_nested() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::ParseOptions;
+
+    let depth = 4_000;
+    let innermost = |value: &Value, key: &str| {
+        let mut node = value;
+        for _ in 0..depth {
+            node = &node[key];
+        }
+        node.clone()
+    };
+
+    let yaml = "{a: ".repeat(depth) + "1" + &"}".repeat(depth);
+    let value: Value = dbt_serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(innermost(&value, "a"), 1);
+    let serialized = dbt_serde_yaml::to_string(&value).unwrap();
+    let value: Value = dbt_serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(innermost(&value, "a"), 1);
+
+    let yaml = "[".repeat(depth) + "x" + &"]".repeat(depth);
+    let value: Value = dbt_serde_yaml::from_str(&yaml).unwrap();
+    let serialized = dbt_serde_yaml::to_string(&value).unwrap();
+    assert!(serialized.ends_with("- x\n"));
+
+    let values: Vec<Value> = dbt_serde_yaml::from_str(&format!("- {yaml}")).unwrap();
+    let serialized = dbt_serde_yaml::to_string(&values).unwrap();
+    assert!(serialized.ends_with("- x\n"));
+
+    let recursive = ParseOptions::new().iterative_values(false);
+    let err = Value::from_str_with(&yaml, recursive, |_, _, _| DuplicateKey::Error).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "recursion limit exceeded at line 1 column 129",
+    );
+}
+
+#[cfg(feature = "large_scalars")]
+#[test]
+fn test_elide_large_scalars() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::{LargeScalars, ParseOptions};
+
+    fn is_elided(value: &Value) -> bool {
+        match value {
+            Value::String(string, _) => string.is_elided(),
+            _ => panic!("not a string: {:?}", value),
+        }
+    }
+
+    let yaml = indoc! {"
+        name: orders
+        a_long_key_name: |
+          select *
+          from raw.orders
+        columns:
+          - 12345678901
+          - 'quoted text'
+        description: short
+        empty: ''
+    "};
+    let options = ParseOptions::new().large_scalars_over(Some(10), LargeScalars::Elide);
+    let value = Value::from_str_with(yaml, options.clone(), |_, _, _| DuplicateKey::Error).unwrap();
+    assert_eq!(value["name"], "orders");
+    assert!(is_elided(&value["a_long_key_name"]));
+    assert_eq!(value["columns"][0], 12345678901u64);
+    assert!(is_elided(&value["columns"][1]));
+    assert_eq!(value["description"], "short");
+    assert!(!is_elided(&value["description"]));
+    assert!(!is_elided(&value["empty"]));
+    assert_eq!(value["a_long_key_name"].span().start.line, 2);
+    assert_eq!(
+        value["a_long_key_name"].decode_scalar(yaml).unwrap(),
+        "select *\nfrom raw.orders\n",
+    );
+    assert_eq!(
+        value["columns"][1].decode_scalar(yaml).unwrap(),
+        "quoted text"
+    );
+    let err = dbt_serde_yaml::to_string(&value).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot serialize a string elided while parsing",
+    );
+
+    // An elided string is not the empty string, and can't be deserialized.
+    assert_ne!(value["a_long_key_name"], Value::from(""));
+    assert_ne!(value["a_long_key_name"], "");
+    let err = <String as serde::Deserialize>::deserialize(&value["a_long_key_name"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot deserialize a string elided while parsing at line 2 column 18",
+    );
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        a_long_key_name: String,
+    }
+    let err = value
+        .to_typed::<Model, _, _>(|_, _, _| {}, |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot deserialize a string elided while parsing at line 2 column 18",
+    );
+
+    // Only values are affected.
+    let model: Model = dbt_serde_yaml::from_str_with_options(yaml, options).unwrap();
+    assert_eq!(model.a_long_key_name, "select *\nfrom raw.orders\n");
+}
+
+#[cfg(feature = "large_scalars")]
+#[test]
+fn test_share_large_scalars() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::{LargeScalars, Marker, ParseOptions};
+
+    let yaml = indoc! {"
+        name: orders
+        sql: |
+          select *
+          from raw.orders
+        columns:
+          - 12345678901
+          - 'it''s quoted'
+          - &a \"escaped\\ttext\"
+          - *a
+    "};
+    let options = ParseOptions::new().large_scalars_over(Some(10), LargeScalars::Share);
+    let value = Value::from_str_with(yaml, options.clone(), |_, _, _| DuplicateKey::Error).unwrap();
+    assert_eq!(value["name"], "orders");
+    assert_eq!(value["sql"], "select *\nfrom raw.orders\n");
+    assert_eq!(value["sql"].span().start.line, 2);
+    assert_eq!(value["columns"][0], 12345678901u64);
+    assert_eq!(value["columns"][1].as_str(), Some("it's quoted"));
+    assert_eq!(value["columns"][2], "escaped\ttext");
+
+    // An alias shares the string of its anchor, as both are read from the
+    // same buffer.
+    let anchor = value["columns"][2].as_str().unwrap();
+    let alias = value["columns"][3].as_str().unwrap();
+    assert_eq!(anchor.as_ptr(), alias.as_ptr());
+    let value = Value::from_str_with(yaml, ParseOptions::new(), |_, _, _| DuplicateKey::Error);
+    let value = value.unwrap();
+    let anchor = value["columns"][2].as_str().unwrap();
+    let alias = value["columns"][3].as_str().unwrap();
+    assert_ne!(anchor.as_ptr(), alias.as_ptr());
+
+    // Later documents and fragments at an origin work alike.
+    let stream = "first document\n---\nsecond document\n";
+    let documents: Vec<Value> =
+        dbt_serde_yaml::Deserializer::from_str_with(stream, options.clone())
+            .map(|document| serde::Deserialize::deserialize(document).unwrap())
+            .collect();
+    assert_eq!(documents[1], "second document");
+    let options = options.origin(Marker::new(100, 10, 5));
+    let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+    assert_eq!(value["sql"], "select *\nfrom raw.orders\n");
+}
+
+#[test]
+fn test_events() {
+    use dbt_serde_yaml::event::{events, CollectionStart, Event, Scalar, ScalarStyle};
+
+    fn scalar(value: &str, style: ScalarStyle, tag: Option<&str>, anchor: Option<&str>) -> Event {
+        Event::Scalar(Scalar {
+            value: value.to_owned(),
+            style,
+            tag: tag.map(str::to_owned),
+            anchor: anchor.map(str::to_owned),
+        })
+    }
+    let plain = |value| scalar(value, ScalarStyle::Plain, None, None);
+    let block = CollectionStart {
+        flow: false,
+        tag: None,
+        anchor: None,
+    };
+
+    let yaml = "a: &x !T 1\nb: [*x, 'q']\n";
+    let events = events(yaml)
+        .map(|event| {
+            let (event, span) = event.unwrap();
+            (event, &yaml[span.start.index..span.end.index])
+        })
+        .collect::<Vec<_>>();
+    let expected = [
+        (Event::DocumentStart, ""),
+        (Event::MappingStart(block), ""),
+        (plain("a"), "a"),
+        (
+            scalar("1", ScalarStyle::Plain, Some("!T"), Some("x")),
+            "&x !T 1",
+        ),
+        (plain("b"), "b"),
+        (
+            Event::SequenceStart(CollectionStart {
+                flow: true,
+                tag: None,
+                anchor: None,
+            }),
+            "[",
+        ),
+        (Event::Alias("x".to_owned()), "*x"),
+        (scalar("q", ScalarStyle::SingleQuoted, None, None), "'q'"),
+        (Event::SequenceEnd, "]"),
+        (Event::MappingEnd, ""),
+        (Event::DocumentEnd, ""),
+    ];
+    assert_eq!(events, expected);
+
+    let mut events = dbt_serde_yaml::event::events("a: [\n");
+    assert_eq!(events.by_ref().filter(Result::is_err).count(), 1);
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn test_from_reader() {
+    use dbt_serde_yaml::{Config, ParseOptions, TabIndentation};
+    use serde::Deserialize as _;
+    use std::cell::Cell;
+    use std::io::{self, Read};
+    use std::rc::Rc;
+
+    struct Counting<'a> {
+        input: &'a [u8],
+        read: Rc<Cell<usize>>,
+    }
+
+    impl Read for Counting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.input.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    // Each document is parsed as the reader gets to it.
+    let document = format!("items:\n{}", "  - 1234567890\n".repeat(10_000));
+    let yaml = format!("{}---\n{}", document, document);
+    let read = Rc::new(Cell::new(0));
+    let reader = Counting {
+        input: yaml.as_bytes(),
+        read: Rc::clone(&read),
+    };
+    let mut documents = Deserializer::from_reader(reader);
+    let first = Value::deserialize(documents.next().unwrap()).unwrap();
+    assert_eq!(first["items"].as_sequence().unwrap().len(), 10_000);
+    assert!(read.get() < yaml.len());
+    let second = Value::deserialize(documents.next().unwrap()).unwrap();
+    assert_eq!(second, first);
+    assert_eq!(second["items"][0].span().start.line, 10_004);
+    assert!(documents.next().is_none());
+    assert_eq!(read.get(), yaml.len());
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("connection reset"))
+        }
+    }
+
+    let err = dbt_serde_yaml::from_reader::<_, Value>(Failing).unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+
+    let mut config = Config::new();
+    config.parse_options = ParseOptions::new().tab_indentation(TabIndentation::Reject);
+    let yaml = "a: [1,\n\t2]\n";
+    let err =
+        dbt_serde_yaml::from_reader_with_config::<_, Value>(yaml.as_bytes(), &config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "found a tab character in indentation at line 2 column 1",
+    );
+    config.parse_options = ParseOptions::new().tab_indentation(TabIndentation::AsSpace);
+    let yaml = "a:\n\tb: 1\n";
+    let value: Value = dbt_serde_yaml::from_reader_with_config(yaml.as_bytes(), &config).unwrap();
+    assert_eq!(value["a"]["b"], 1);
+}
+
+#[test]
+fn test_from_str_at() {
+    use dbt_serde_yaml::path::OwnedPath;
+
+    let yaml = indoc! {"
+        defaults: &defaults
+          materialized: view
+        models:
+          - name: orders
+            config: *defaults
+          - name: customers
+            config: !Override
+              materialized: table
+        1: one
+        ignored: [{a: [b, c]}, d]
+    "};
+
+    fn at<T: serde::de::DeserializeOwned>(yaml: &str, path: &str) -> dbt_serde_yaml::Result<T> {
+        let path: OwnedPath = path.parse().unwrap();
+        dbt_serde_yaml::from_str_at(yaml, path.as_path())
+    }
+
+    assert_eq!(at::<String>(yaml, "models[1].name").unwrap(), "customers");
+    // Aliases are expanded and tags are looked into.
+    assert_eq!(
+        at::<String>(yaml, "models[0].config.materialized").unwrap(),
+        "view"
+    );
+    assert_eq!(
+        at::<String>(yaml, "models[1].config.materialized").unwrap(),
+        "table"
+    );
+    // An index also matches an integer key.
+    assert_eq!(at::<String>(yaml, "[1]").unwrap(), "one");
+    let whole: Value = at(yaml, ".").unwrap();
+    assert_eq!(whole, dbt_serde_yaml::from_str::<Value>(yaml).unwrap());
+
+    // The node found keeps its location in the document.
+    let name: dbt_serde_yaml::Spanned<String> = at(yaml, "models[1].name").unwrap();
+    assert_eq!(name.span().start.line(), 6);
+
+    for missing in ["models[2].name", "models.name", "models[0].name.x", "nope"] {
+        let err = at::<Value>(yaml, missing).unwrap_err();
+        assert_eq!(err.to_string(), format!("no value at path {}", missing));
+    }
+
+    let err = at::<u32>(yaml, "models[1].name").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "models[1].name: invalid type: string \"customers\", expected u32 at line 6 column 11",
+    );
+    assert_eq!(err.path().unwrap().to_string(), "models[1].name");
+}
+
+#[test]
+fn test_tag_handlers() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Profile {
+        target: String,
+        threads: u32,
+        schemas: Vec<String>,
+    }
+
+    let mut config = dbt_serde_yaml::Config::new();
+    config
+        .tag_handlers
+        .insert("!env_var", |tagged| match tagged.value.as_str() {
+            Some("TARGET") => Ok(Value::from("prod")),
+            Some("THREADS") => Ok(Value::from(8)),
+            Some(name) => Err(format!("unknown variable {}", name).into()),
+            None => Err("expected a variable name".into()),
+        });
+    config.tag_handlers.insert("concat", |tagged| {
+        let parts = tagged.value.as_sequence().ok_or("expected a sequence")?;
+        let parts: Vec<&str> = parts.iter().filter_map(Value::as_str).collect();
+        Ok(Value::from(parts.concat()))
+    });
+
+    let yaml = indoc! {"
+        target: !env_var TARGET
+        threads: !env_var THREADS
+        schemas:
+          - !concat [analytics_, !env_var TARGET]
+          - raw
+    "};
+    let expected = Profile {
+        target: "prod".to_owned(),
+        threads: 8,
+        schemas: vec!["analytics_prod".to_owned(), "raw".to_owned()],
+    };
+    let profile: Profile = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(profile, expected);
+
+    let value: Value = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(value["threads"], 8);
+    assert_eq!(value["target"].span().start.line, 1);
+    assert_eq!(value["target"].span().start.column, 9);
+    assert_eq!(value["schemas"][0], "analytics_prod");
+
+    // Without handlers the tags are kept.
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert!(matches!(value["target"], Value::Tagged(..)));
+
+    let yaml = "threads: !env_var THREAD_COUNT\n";
+    let err =
+        dbt_serde_yaml::from_str_with_config::<BTreeMap<String, u32>>(yaml, &config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "threads: unknown variable THREAD_COUNT at line 1 column 10",
+    );
+
+    let yaml = "threads: !env_var TARGET\n";
+    let de = Deserializer::from_str(yaml)
+        .with_config(&config)
+        .with_tag_handler("env_var", |_| Ok(Value::from("four")));
+    let err = <BTreeMap<String, u32> as serde::Deserialize>::deserialize(de).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "threads: invalid type: string \"four\", expected u32 at line 1 column 10",
+    );
+}
+
+#[test]
+fn test_sort_keys() {
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        name: String,
+        config: Value,
+    }
+
+    let yaml = indoc! {"
+        name: orders
+        config:
+          schema: marts
+          alias: !custom {z: 1, a: [{c: 3, b: 2}]}
+          2: two
+          1: one
+    "};
+    let expected = "{1: one, 2: two, alias: !custom {a: [{b: 2, c: 3}], z: 1}, schema: marts}";
+
+    let mut config = dbt_serde_yaml::Config::new();
+    config.parse_options = dbt_serde_yaml::ParseOptions::new().sort_keys(true);
+    let value: Value = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(value["config"].to_string(), expected);
+    let model: Model = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(model.name, "orders");
+    assert_eq!(model.config.to_string(), expected);
+    // Entries keep their spans.
+    let config_mapping = model.config.as_mapping().unwrap();
+    assert_eq!(config_mapping.span_of("schema").unwrap().start.line, 3);
+
+    // The recursive implementation sorts the same way.
+    config.parse_options = config.parse_options.iterative_values(false);
+    let value: Value = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(value["config"].to_string(), expected);
+
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(
+        value["config"].to_string(),
+        "{schema: marts, alias: !custom {z: 1, a: [{c: 3, b: 2}]}, 2: two, 1: one}",
+    );
+}
+
+#[test]
+fn test_recorded_duplicate_keys() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::{collect_warnings, Warnings};
+
+    #[derive(Deserialize, Debug)]
+    struct Project {
+        models: BTreeMap<String, Value>,
+    }
+
+    let yaml = indoc! {"
+        models:
+          orders:
+            materialized: view
+            materialized: table
+          orders: {}
+    "};
+    let summarize =
+        |warnings: &Warnings| warnings.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    let mut config = dbt_serde_yaml::Config::new();
+    config.duplicate_keys = DuplicateKey::IgnoreAndRecord;
+    let (value, warnings) =
+        collect_warnings(|| dbt_serde_yaml::from_str_with_config::<Value>(yaml, &config).unwrap());
+    assert_eq!(value["models"]["orders"]["materialized"], "view");
+    assert_eq!(
+        summarize(&warnings),
+        [
+            "duplicate entry `models.orders.materialized` (first at line 3 column 5) at line 4 column 5",
+            "duplicate entry `models.orders` (first at line 2 column 3) at line 5 column 3",
+        ],
+    );
+
+    // The policy applies to values nested in typed structs too, with paths
+    // relative to the value.
+    config.duplicate_keys = DuplicateKey::OverwriteAndRecord;
+    let (project, warnings) = collect_warnings(|| {
+        dbt_serde_yaml::from_str_with_config::<Project>(yaml, &config).unwrap()
+    });
+    assert_eq!(
+        project.models["orders"],
+        Value::from(dbt_serde_yaml::Mapping::new())
+    );
+    assert_eq!(
+        summarize(&warnings),
+        ["duplicate entry `materialized` (first at line 3 column 5) at line 4 column 5"],
+    );
+
+    // Scopes nest, and nothing is reported outside of one.
+    let ((inner, outer_value), outer) = collect_warnings(|| {
+        let (_, inner) = collect_warnings(|| {
+            Value::from_str(yaml, |_, _, _| DuplicateKey::IgnoreAndRecord).unwrap()
+        });
+        let value = Value::from_str("a: 1\na: 2\n", |_, _, _| DuplicateKey::OverwriteAndRecord);
+        (inner, value.unwrap())
+    });
+    assert_eq!(inner.len(), 2);
+    assert_eq!(outer_value["a"], 2);
+    assert_eq!(
+        summarize(&outer),
+        ["duplicate entry `a` (first at line 1 column 1) at line 2 column 1"],
+    );
+    let value = Value::from_str("a: 1\na: 2\n", |_, _, _| DuplicateKey::IgnoreAndRecord).unwrap();
+    assert_eq!(value["a"], 1);
+}
+
+#[test]
+fn test_origin() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::{Marker, ParseOptions, Spanned};
+
+    // A fragment that starts on line 42 column 7 of an enclosing file.
+    let origin = Marker::new(1000, 42, 7);
+    let yaml = indoc! {"
+        a: 1
+        b: [x, y]
+    "};
+
+    let map: BTreeMap<String, Spanned<Value>> =
+        dbt_serde_yaml::from_str_with_origin(yaml, origin).unwrap();
+    let a = map["a"].span();
+    assert_eq!(
+        (a.start.line, a.start.column, a.start.index),
+        (42, 10, 1003)
+    );
+    let b = map["b"].span();
+    assert_eq!((b.start.line, b.start.column, b.start.index), (43, 4, 1008));
+
+    let options = ParseOptions::new().origin(origin);
+    let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+    let y = value["b"][1].span();
+    assert_eq!((y.start.line, y.start.column), (43, 8));
+
+    let err = dbt_serde_yaml::from_str_with_origin::<Value>("a: [1,\n", origin).unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!(location.line(), 43);
+    assert_eq!(location.column(), 1);
+
+    let err = dbt_serde_yaml::from_str_with_origin::<Value>("a: 1\n\tb: 2\n", origin).unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 43);
+}
+
+#[test]
+fn test_collect_warnings() {
+    use dbt_serde_yaml::{
+        collect_warnings, record_warning, ParseOptions, ScalarSchema, TabIndentation, WarningKind,
+        Yaml11Type,
+    };
+
+    let yaml = "enabled: off\nmode: 'off'\nperms: 0755\ntagged: !!str yes\n";
+    let (value, warnings) = collect_warnings(|| dbt_serde_yaml::from_str::<Value>(yaml));
+    assert_eq!(value.unwrap()["enabled"], "off");
+    let kinds: Vec<_> = warnings.iter().map(|w| w.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        [
+            WarningKind::AmbiguousScalar {
+                value: "off".to_owned(),
+                yaml11: Yaml11Type::Boolean,
+                schema: ScalarSchema::Lenient,
+            },
+            WarningKind::AmbiguousScalar {
+                value: "0755".to_owned(),
+                yaml11: Yaml11Type::OctalInteger,
+                schema: ScalarSchema::Lenient,
+            },
+        ],
+    );
+    let span = &warnings.iter().next().unwrap().span;
+    assert_eq!((span.start.column, span.end.column), (10, 13));
+
+    // What the message says depends on how the scalar is resolved.
+    let (value, warnings) = collect_warnings(|| {
+        let de =
+            dbt_serde_yaml::Deserializer::from_str(yaml).with_scalar_schema(ScalarSchema::Core);
+        <Value as serde::Deserialize>::deserialize(de)
+    });
+    assert_eq!(value.unwrap()["perms"], 755);
+    let messages: Vec<_> = warnings.iter().map(|w| w.kind.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "`off` is a string, but YAML 1.1 reads it as a boolean",
+            "`0755` is a decimal integer, but YAML 1.1 reads it as an octal integer",
+        ],
+    );
+
+    // Tabs let through by the policy, both when held at once and when read.
+    let yaml = "a:\n\tb: 1\nc:\n\td: 2\n";
+    let options = ParseOptions::new().tab_indentation(TabIndentation::AsSpace);
+    let (value, warnings) =
+        collect_warnings(|| dbt_serde_yaml::from_str_with_options::<Value>(yaml, options.clone()));
+    assert_eq!(value.unwrap()["c"]["d"], 2);
+    let lines: Vec<_> = warnings.iter().map(|w| w.span.start.line).collect();
+    assert_eq!(lines, [2, 4]);
+    assert!(warnings
+        .iter()
+        .all(|w| w.kind == WarningKind::TabIndentation));
+    let (value, warnings) = collect_warnings(|| {
+        let de =
+            dbt_serde_yaml::Deserializer::from_reader(yaml.as_bytes()).with_parse_options(options);
+        <Value as serde::Deserialize>::deserialize(de)
+    });
+    assert_eq!(value.unwrap()["a"]["b"], 1);
+    assert_eq!(warnings.len(), 2);
+
+    // Aliases expanded more than half as many times as allowed.
+    let yaml = "a: &a [1]\nb: [*a, *a, *a]\n";
+    let options = ParseOptions::new().max_alias_expansions(Some(4));
+    let (value, warnings) =
+        collect_warnings(|| dbt_serde_yaml::from_str_with_options::<Value>(yaml, options));
+    assert!(value.is_ok());
+    assert_eq!(warnings.len(), 1);
+    let warning = warnings.into_iter().next().unwrap();
+    assert_eq!(
+        warning.kind,
+        WarningKind::AliasExpansions { count: 3, limit: 4 },
+    );
+    assert_eq!(warning.span.start.column, 13);
+
+    // Custom warnings, and nested collection.
+    let ((inner, outer_value), outer) = collect_warnings(|| {
+        let (_, inner) = collect_warnings(|| record_warning("inner"));
+        record_warning("outer");
+        (inner, dbt_serde_yaml::from_str::<Value>("x: 1"))
+    });
+    assert!(outer_value.is_ok());
+    assert_eq!(inner.len(), 1);
+    assert_eq!(outer.len(), 1);
+    assert_eq!(
+        outer.iter().next().unwrap().kind,
+        WarningKind::Custom("outer".to_owned()),
+    );
+
+    // Nothing is collected outside of `collect_warnings`.
+    record_warning("dropped");
+    let (_, warnings) = collect_warnings(|| ());
+    assert!(warnings.is_empty());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing() {
+    use std::fmt::Write as _;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records every span and event as a line of text.
+    #[derive(Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        next_id: AtomicU64,
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                write!(self.0, "{:?}", value).unwrap();
+            } else {
+                write!(self.0, " {}={:?}", field.name(), value).unwrap();
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(format!("span {}", span.metadata().name()));
+            span.record(&mut fields);
+            self.lines.lock().unwrap().push(fields.0);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            self.lines.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Project {
+        name: String,
+    }
+
+    let recorder = Recorder::default();
+    let lines = Arc::clone(&recorder.lines);
+    tracing::subscriber::with_default(recorder, || {
+        let value: Value = dbt_serde_yaml::from_str("name: jaffle\nversion: 2\n").unwrap();
+        let project: Project = value
+            .into_typed(|_, _, _| {}, |_| Ok(None::<Value>))
+            .unwrap();
+        assert_eq!(project.name, "jaffle");
+    });
+    assert_eq!(
+        *lines.lock().unwrap(),
+        [
+            "span dbt_serde_yaml::parse_document index=0",
+            "parsed YAML document events=7 failed=false",
+            "span dbt_serde_yaml::parse_document index=1",
+            "invoked callback callback=\"duplicate_key\" count=0",
+            "invoked field transformer path=. transformed=false",
+            "span dbt_serde_yaml::deserialize_key key=\"name\"",
+            "invoked field transformer path=name transformed=false",
+            "invoked callback callback=\"unused_key\" count=1",
+        ],
+    );
+}
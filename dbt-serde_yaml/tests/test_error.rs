@@ -124,8 +124,11 @@ fn test_ignored_unknown_anchor() {
 
 #[test]
 fn test_bytes() {
-    let expected = "serialization and deserialization of bytes in YAML is not implemented";
-    test_error::<&[u8]>("...", expected);
+    let expected =
+        "invalid type: string \"abc\", expected a borrowed byte array at line 1 column 1";
+    test_error::<&[u8]>("abc", expected);
+    let expected = "invalid value: string \"not base64!\", expected base64-encoded binary data at line 1 column 1";
+    test_error::<serde_bytes::ByteBuf>("!!binary not base64!", expected);
 }
 
 #[test]
@@ -327,6 +330,44 @@ fn test_short_tuple() {
     test_error::<(u8, u8, u8)>(yaml, expected);
 }
 
+#[test]
+fn test_omap_entry_with_several_keys() {
+    let yaml = indoc! {"
+        !!omap
+        - a: 1
+        - b: 2
+          c: 3
+    "};
+    let expected = ".[1]: invalid length 2, expected map containing 1 entry at line 3 column 3";
+    test_error::<Vec<(String, u8)>>(yaml, expected);
+}
+
+#[test]
+fn test_set_with_values() {
+    let yaml = indoc! {"
+        !!set
+        a:
+        b: 1
+    "};
+    let expected = "invalid type: map, expected a sequence at line 1 column 1";
+    test_error::<Vec<String>>(yaml, expected);
+}
+
+#[test]
+fn test_untagged_set() {
+    let expected = "invalid type: map, expected a sequence at line 1 column 1";
+    test_error::<Vec<String>>("{a: ~, b: ~}", expected);
+    test_error::<Vec<String>>("{}", expected);
+}
+
+#[test]
+fn test_untagged_pair() {
+    let expected = "invalid type: map, expected an array of length 2 at line 1 column 1";
+    test_error::<[String; 2]>("{a: b}", expected);
+    let expected = "invalid type: map, expected a tuple of size 2 at line 1 column 1";
+    test_error::<(String, String)>("{a: b}", expected);
+}
+
 #[test]
 fn test_long_tuple() {
     let yaml = indoc! {"
@@ -463,7 +504,7 @@ fn test_billion_laughs() {
         h: &h [*g,*g,*g,*g,*g,*g,*g,*g,*g]
         i: &i [*h,*h,*h,*h,*h,*h,*h,*h,*h]
     "};
-    let expected = "repetition limit exceeded";
+    let expected = "repetition limit exceeded at line 2 column 29";
     test_error::<BTreeMap<String, X>>(yaml, expected);
 }
 
@@ -508,14 +549,15 @@ fn test_duplicate_keys() {
         thing: true
         thing: false
     "};
-    let expected = "duplicate entry with key \"thing\" at line 2 column 1";
+    let expected =
+        "duplicate entry with key \"thing\" (first defined at line 2 column 1) at line 3 column 1";
     test_error::<Value>(yaml, expected);
     test_ignore_duplicate_keys(
         yaml,
         &[(
             "thing".to_string(),
             Value::String(
-                "thing".to_string(),
+                "thing".into(),
                 Span::new(Marker::new(16, 3, 1), Marker::new(23, 3, 8)),
             ),
         )],
@@ -528,7 +570,8 @@ fn test_duplicate_keys() {
         null: true
         ~: false
     "};
-    let expected = "duplicate entry with null key at line 2 column 1";
+    let expected =
+        "duplicate entry with null key (first defined at line 2 column 1) at line 3 column 1";
     test_error::<Value>(yaml, expected);
     test_ignore_duplicate_keys(
         yaml,
@@ -545,7 +588,8 @@ fn test_duplicate_keys() {
         99: true
         99: false
     "};
-    let expected = "duplicate entry with key 99 at line 2 column 1";
+    let expected =
+        "duplicate entry with key 99 (first defined at line 2 column 1) at line 3 column 1";
     test_error::<Value>(yaml, expected);
     test_ignore_duplicate_keys(
         yaml,
@@ -565,7 +609,8 @@ fn test_duplicate_keys() {
         {}: true
         {}: false
     "};
-    let expected = "duplicate entry in YAML map at line 2 column 1";
+    let expected =
+        "duplicate entry in YAML map (first defined at line 2 column 1) at line 3 column 1";
     test_error::<Value>(yaml, expected);
     test_ignore_duplicate_keys(
         yaml,
@@ -587,14 +632,14 @@ fn test_duplicate_keys() {
             key: true
             key: false
     "};
-    let expected = ".[1].map: duplicate entry with key \"key\" at line 4 column 5";
+    let expected = ".[1].map: duplicate entry with key \"key\" (first defined at line 4 column 5) at line 5 column 5";
     test_error::<Value>(yaml, expected);
     test_ignore_duplicate_keys(
         yaml,
         &[(
             ".[1].map.key".to_string(),
             Value::String(
-                "key".to_string(),
+                "key".into(),
                 Span::new(Marker::new(40, 5, 5), Marker::new(45, 5, 10)),
             ),
         )],
@@ -602,3 +647,379 @@ fn test_duplicate_keys() {
         "- x: true\n- map:\n    key: false\n",
     );
 }
+
+#[test]
+fn test_error_path() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Config {
+        tags: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+        config: Config,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Project {
+        models: Vec<Model>,
+    }
+
+    let yaml = indoc! {"
+        models:
+          - name: a
+            config:
+              tags: [x]
+          - name: b
+            config:
+              tags: [x, {y: 1}]
+    "};
+
+    let error = dbt_serde_yaml::from_str::<Project>(yaml).unwrap_err();
+    assert_eq!(
+        error.path().unwrap().to_string(),
+        "models[1].config.tags[1]"
+    );
+
+    // The path is recorded when deserializing from a Value, and points at the
+    // offending value even though it was inserted without a span of its own.
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    value["models"][1]["config"]["tags"][1] = Value::from(vec![1]);
+    let error = value
+        .into_typed::<Project, _, _>(|_, _, _| {}, |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        error.path().unwrap().to_string(),
+        "models[1].config.tags[1]"
+    );
+
+    // Errors not tied to a value have no path.
+    let error = dbt_serde_yaml::from_str::<Value>("a: [").unwrap_err();
+    assert!(error.path().is_none());
+}
+
+#[test]
+fn test_error_path_after_value() {
+    #[derive(Deserialize, Debug)]
+    #[serde(try_from = "i64")]
+    struct Port(#[allow(dead_code)] u16);
+
+    impl TryFrom<i64> for Port {
+        type Error = String;
+
+        fn try_from(port: i64) -> Result<Self, String> {
+            u16::try_from(port)
+                .map(Port)
+                .map_err(|_| format!("port {} out of range", port))
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(untagged)]
+    #[allow(dead_code)]
+    enum Target {
+        Port(Port),
+        Host(String),
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Service {
+        port: Option<Port>,
+        #[serde(default)]
+        targets: Vec<Target>,
+    }
+
+    // Errors raised once the value has been read are located at the value,
+    // rather than at the enclosing mapping.
+    let yaml = indoc! {"
+        services:
+          - port: 80
+          - port: 70000
+    "};
+    test_error::<BTreeMap<String, Vec<Service>>>(
+        yaml,
+        "services[1].port: port 70000 out of range at line 3 column 11",
+    );
+    let error = dbt_serde_yaml::from_str::<BTreeMap<String, Vec<Service>>>(yaml).unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "services[1].port");
+
+    let yaml = indoc! {"
+        services:
+          - targets: [80, [x]]
+    "};
+    test_error::<BTreeMap<String, Vec<Service>>>(
+        yaml,
+        "services[0].targets[1]: data did not match any variant of untagged enum Target at line 2 column 19",
+    );
+}
+
+#[test]
+fn test_syntax_hint() {
+    use dbt_serde_yaml::{ExpectedToken, SyntaxContext};
+
+    let hint = |yaml| {
+        let error = dbt_serde_yaml::from_str::<Value>(yaml).unwrap_err();
+        let hint = error.syntax_hint().unwrap();
+        (
+            hint.context(),
+            hint.expected().to_vec(),
+            hint.path().to_string(),
+        )
+    };
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            description
+            columns: []
+    "};
+    assert_eq!(
+        hint(yaml),
+        (
+            SyntaxContext::BlockMappingKey,
+            vec![ExpectedToken::Value],
+            "models[0]".to_owned(),
+        )
+    );
+
+    let yaml = indoc! {"
+        seeds:
+          - a
+          b: 1
+    "};
+    assert_eq!(
+        hint(yaml),
+        (
+            SyntaxContext::BlockSequenceEntry,
+            vec![ExpectedToken::BlockEntry],
+            "seeds[1]".to_owned(),
+        )
+    );
+
+    assert_eq!(
+        hint("config: {materialized: table, tags: [a]"),
+        (
+            SyntaxContext::FlowMappingKey,
+            vec![ExpectedToken::FlowEntry, ExpectedToken::FlowMappingEnd],
+            "config".to_owned(),
+        )
+    );
+
+    assert_eq!(
+        hint("version: 2\nname: @x"),
+        (
+            SyntaxContext::BlockMappingValue,
+            vec![ExpectedToken::Node],
+            "name".to_owned(),
+        )
+    );
+
+    // Errors that are not syntax errors have no hint.
+    let error = dbt_serde_yaml::from_str::<i16>("str").unwrap_err();
+    assert!(error.syntax_hint().is_none());
+}
+
+#[test]
+fn test_parse_options() {
+    use dbt_serde_yaml::{DuplicateAnchor, ParseOptions, TabIndentation};
+
+    let from_str = |yaml: &str, options: ParseOptions| {
+        Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error)
+    };
+
+    // Tabs on the continuation line of a flow sequence get past the parser.
+    let yaml = "a: [1,\n\t2]\n";
+    assert!(from_str(yaml, ParseOptions::new()).is_ok());
+    let reject = ParseOptions::new().tab_indentation(TabIndentation::Reject);
+    let err = from_str(yaml, reject).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "found a tab character in indentation at line 2 column 1",
+    );
+
+    let yaml = "a:\n\tb: 1\n";
+    assert!(from_str(yaml, ParseOptions::new()).is_err());
+    let as_space = ParseOptions::new().tab_indentation(TabIndentation::AsSpace);
+    let value = from_str(yaml, as_space).unwrap();
+    assert_eq!(value["a"]["b"], 1);
+    assert_eq!(value["a"]["b"].span().start, Marker::new(7, 2, 5));
+
+    let yaml = indoc! {"
+        a: &a [x, x, x, x]
+        b: &b [*a, *a, *a, *a]
+        c: [*b, *b, *b, *b]
+    "};
+    assert!(from_str(yaml, ParseOptions::new()).is_ok());
+    let limited = ParseOptions::new().max_alias_expansions(Some(10));
+    let err = from_str(yaml, limited).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "repetition limit exceeded at line 2 column 8",
+    );
+
+    let yaml = indoc! {"
+        a: &x 1
+        b: &x 2
+        c: *x
+    "};
+    let value = from_str(yaml, ParseOptions::new()).unwrap();
+    assert_eq!(value["c"], 2);
+    let unique = ParseOptions::new().duplicate_anchors(DuplicateAnchor::Error);
+    let err = from_str(yaml, unique).unwrap_err();
+    assert_eq!(err.to_string(), "duplicate anchor `x` at line 2 column 4");
+}
+
+#[test]
+fn test_duplicate_anchor_warning() {
+    use dbt_serde_yaml::{DuplicateAnchor, Marker, ParseOptions};
+    use std::sync::{Arc, Mutex};
+
+    let warnings = Arc::new(Mutex::new(Vec::<(String, Marker, Marker)>::new()));
+    let warn = {
+        let warnings = Arc::clone(&warnings);
+        Arc::new(move |name: &str, first, second| {
+            warnings
+                .lock()
+                .unwrap()
+                .push((name.to_owned(), first, second));
+        })
+    };
+
+    let yaml = indoc! {"
+        a: &x 1
+        b: &y [2]
+        c: &x {d: 3}
+        e: *x
+    "};
+    let options = ParseOptions::new().duplicate_anchors(DuplicateAnchor::Warn(warn));
+    assert_eq!(options.clone(), options);
+    assert_ne!(options, ParseOptions::new());
+    let value = Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error).unwrap();
+    assert_eq!(value["e"]["d"], 3);
+
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    let (name, first, second) = &warnings[0];
+    assert_eq!(name, "x");
+    assert_eq!((first.line(), first.column()), (1, 4));
+    assert_eq!((second.line(), second.column()), (3, 4));
+}
+
+#[test]
+fn test_node_limit() {
+    use dbt_serde_yaml::ParseOptions;
+
+    let yaml = indoc! {"
+        a: &a [x, x, x, x, x, x, x, x, x, x]
+        b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a, *a]
+        c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b, *b]
+    "};
+    let from_str =
+        |options: ParseOptions| Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error);
+
+    // Root mapping and its 3 keys, then 11 + 10 * 11 + 10 * 111 nodes.
+    assert!(from_str(ParseOptions::new().max_nodes(Some(1237))).is_ok());
+    let err = from_str(ParseOptions::new().max_nodes(Some(200))).unwrap_err();
+    assert_eq!(err.to_string(), "node limit exceeded at line 1 column 23");
+    assert_eq!(err.location().unwrap().line(), 1);
+}
+
+#[test]
+fn test_scalar_bytes_and_time_limits() {
+    use dbt_serde_yaml::ParseOptions;
+    use std::time::Duration;
+
+    let yaml = indoc! {"
+        a: &a xxxxxxxxxx
+        b: [*a, *a, *a]
+    "};
+    let from_str =
+        |options: ParseOptions| Value::from_str_with(yaml, options, |_, _, _| DuplicateKey::Error);
+
+    // Keys `a` and `b`, then 10 bytes for the anchored scalar and for each of
+    // its 3 expansions.
+    assert!(from_str(ParseOptions::new().max_scalar_bytes(Some(42))).is_ok());
+    let err = from_str(ParseOptions::new().max_scalar_bytes(Some(41))).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "scalar size limit exceeded at line 1 column 4"
+    );
+
+    // A limit that is exceeded before any alias is expanded is hit while
+    // parsing, before the rest of the input is read.
+    let err = from_str(ParseOptions::new().max_scalar_bytes(Some(5))).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "scalar size limit exceeded at line 1 column 4"
+    );
+    let err = from_str(ParseOptions::new().max_nodes(Some(3))).unwrap_err();
+    assert_eq!(err.to_string(), "node limit exceeded at line 2 column 1");
+
+    assert!(from_str(ParseOptions::new().time_limit(Some(Duration::from_secs(60)))).is_ok());
+    let err = from_str(ParseOptions::new().time_limit(Some(Duration::ZERO))).unwrap_err();
+    assert_eq!(err.to_string(), "time limit exceeded at line 1 column 1");
+
+    // A document over budget ends the stream.
+    let options = ParseOptions::new().max_nodes(Some(2));
+    let documents: Vec<Result<Value, _>> =
+        Deserializer::from_str_with("[1, 2, 3]\n---\n[4]\n", options)
+            .map(Value::deserialize)
+            .collect();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(
+        documents[0].as_ref().unwrap_err().to_string(),
+        "node limit exceeded at line 1 column 5"
+    );
+}
+
+#[cfg(feature = "filename")]
+#[test]
+fn test_error_filename() {
+    use std::path::{Path, PathBuf};
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            columns: 1
+    "};
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        #[allow(dead_code)]
+        columns: Vec<String>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Schema {
+        #[allow(dead_code)]
+        models: Vec<Model>,
+    }
+
+    let err = dbt_serde_yaml::from_str_named::<Schema>(yaml, "models/schema.yml").unwrap_err();
+    assert_eq!(err.filename(), Some(Path::new("models/schema.yml")));
+    assert_eq!(
+        err.display_with_location().to_string(),
+        "models/schema.yml:3:14: models[0].columns: invalid type: integer `1`, expected a sequence",
+    );
+
+    // Without `from_str_named`, the filename comes from the span of the error.
+    let err = {
+        let _scope = dbt_serde_yaml::with_filename(Some(PathBuf::from("schema.yml")));
+        dbt_serde_yaml::from_str::<Schema>(yaml).unwrap_err()
+    };
+    assert_eq!(err.filename(), Some(Path::new("schema.yml")));
+    assert!(err
+        .display_with_location()
+        .to_string()
+        .starts_with("schema.yml:3:14: "));
+
+    let err = dbt_serde_yaml::from_str::<Schema>(yaml).unwrap_err();
+    assert_eq!(err.filename(), None);
+    assert!(err
+        .display_with_location()
+        .to_string()
+        .starts_with("3:14: "));
+}
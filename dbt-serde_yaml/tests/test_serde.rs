@@ -300,18 +300,21 @@ fn test_strings_needing_quote() {
         integer: String,
         void: String,
         leading_zeros: String,
+        huge_float: String,
     }
     let thing = Struct {
         boolean: "true".to_owned(),
         integer: "1".to_owned(),
         void: "null".to_owned(),
         leading_zeros: "007".to_owned(),
+        huge_float: "1e400".to_owned(),
     };
     let yaml = indoc! {"
         boolean: 'true'
         integer: '1'
         void: 'null'
         leading_zeros: '007'
+        huge_float: '1e400'
     "};
     test_serde(&thing, yaml);
 }
@@ -576,3 +579,544 @@ fn test_long_string() {
 
     test_serde(&thing, yaml);
 }
+
+#[test]
+fn test_serializer_builder() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Inner {
+        name: String,
+        enabled: String,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Data {
+        model: Inner,
+        tags: Vec<String>,
+    }
+
+    let thing = Data {
+        model: Inner {
+            name: "my_model".to_owned(),
+            enabled: "true".to_owned(),
+        },
+        tags: vec!["a".to_owned()],
+    };
+
+    let builder = dbt_serde_yaml::SerializerBuilder::new().indent(4);
+    let yaml = indoc! {"
+        model:
+            name: my_model
+            enabled: 'true'
+        tags:
+        - a
+    "};
+    assert_eq!(
+        yaml,
+        dbt_serde_yaml::to_string_with(&thing, builder).unwrap()
+    );
+    assert_eq!(thing, dbt_serde_yaml::from_str::<Data>(yaml).unwrap());
+
+    let builder =
+        dbt_serde_yaml::SerializerBuilder::new().quote_style(dbt_serde_yaml::QuoteStyle::Double);
+    let yaml = indoc! {r#"
+        model:
+          name: "my_model"
+          enabled: "true"
+        tags:
+        - "a"
+    "#};
+    assert_eq!(
+        yaml,
+        dbt_serde_yaml::to_string_with(&thing, builder).unwrap()
+    );
+    assert_eq!(thing, dbt_serde_yaml::from_str::<Data>(yaml).unwrap());
+
+    let builder =
+        dbt_serde_yaml::SerializerBuilder::new().quote_style(dbt_serde_yaml::QuoteStyle::Single);
+    let yaml = indoc! {"
+        model:
+          name: 'my_model'
+          enabled: 'true'
+        tags:
+        - 'a'
+    "};
+    assert_eq!(
+        yaml,
+        dbt_serde_yaml::to_string_with(&thing, builder).unwrap()
+    );
+}
+
+#[test]
+fn test_serializer_builder_sort_keys() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Materialization {
+        Incremental {
+            unique_key: String,
+            strategy: String,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        materialized: Materialization,
+        meta: Mapping,
+        columns: Vec<BTreeMap<String, String>>,
+    }
+
+    let mut meta = Mapping::new();
+    meta.insert("owner".into(), "data".into());
+    meta.insert(2.into(), "two".into());
+    meta.insert("contains_pii".into(), false.into());
+    meta.insert(1.into(), "one".into());
+    let thing = Model {
+        name: "orders".to_owned(),
+        materialized: Materialization::Incremental {
+            unique_key: "id".to_owned(),
+            strategy: "merge".to_owned(),
+        },
+        meta,
+        columns: vec![BTreeMap::from([("name".to_owned(), "id".to_owned())])],
+    };
+
+    let builder = dbt_serde_yaml::SerializerBuilder::new().sort_keys(true);
+    let yaml = indoc! {"
+        columns:
+        - name: id
+        materialized: !Incremental
+          strategy: merge
+          unique_key: id
+        meta:
+          1: one
+          2: two
+          contains_pii: false
+          owner: data
+        name: orders
+    "};
+    let serialized = dbt_serde_yaml::to_string_with(&thing, builder.clone()).unwrap();
+    assert_eq!(yaml, serialized);
+    assert_eq!(thing, dbt_serde_yaml::from_str::<Model>(yaml).unwrap());
+
+    let value = dbt_serde_yaml::to_value(&thing).unwrap();
+    assert_eq!(
+        yaml,
+        dbt_serde_yaml::to_string_with(&value, builder).unwrap()
+    );
+}
+
+#[test]
+fn test_serializer_comments() {
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        version: 2
+        models:
+        - name: orders
+          config:
+            materialized: table
+          tags: [nightly, finance]
+        - name: customers
+    "})
+    .unwrap();
+
+    let yaml = indoc! {"
+        # generated file
+        version: 2
+        models:
+        - name: orders
+          # added by migration 1.8
+          # see the changelog
+          config:
+            materialized: table
+          # end of config
+          tags:
+          - nightly
+          - finance
+          # most recent tag
+        # retired
+        #
+        - name: customers
+          # last model
+        # end of models
+    "};
+    let serialized = dbt_serde_yaml::to_string_with_comments(
+        &value,
+        dbt_serde_yaml::SerializerBuilder::new(),
+        |path| {
+            let lines = |lines: &[&str]| lines.iter().map(|&line| line.to_owned()).collect();
+            let (before, after) = match path.to_string().as_str() {
+                "." => (lines(&["generated file"]), Vec::new()),
+                "models" => (Vec::new(), lines(&["end of models"])),
+                "models[0].config" => (
+                    lines(&["added by migration 1.8\nsee the changelog"]),
+                    lines(&["end of config"]),
+                ),
+                "models[0].tags[1]" => (Vec::new(), lines(&["most recent tag"])),
+                "models[1]" => (lines(&["retired", ""]), Vec::new()),
+                "models[1].name" => (Vec::new(), lines(&["last model"])),
+                _ => (Vec::new(), Vec::new()),
+            };
+            dbt_serde_yaml::Comments { before, after }
+        },
+    )
+    .unwrap();
+    assert_eq!(yaml, serialized);
+    assert_eq!(value, dbt_serde_yaml::from_str::<Value>(yaml).unwrap());
+}
+
+#[test]
+fn test_serializer_builder_width() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Data {
+        pub string: String,
+    }
+
+    let thing = Data {
+        string: iter::repeat(["word", " "]).flatten().take(29).collect(),
+    };
+
+    let builder = dbt_serde_yaml::SerializerBuilder::new().width(Some(40));
+    let yaml = indoc! {"
+        string: word word word word word word word
+          word word word word word word word word
+    "};
+    assert_eq!(
+        yaml,
+        dbt_serde_yaml::to_string_with(&thing, builder).unwrap()
+    );
+    assert_eq!(thing, dbt_serde_yaml::from_str::<Data>(yaml).unwrap());
+}
+
+#[test]
+fn test_to_writer_streams() {
+    struct ChunkWriter {
+        bytes: Vec<u8>,
+        largest_chunk: usize,
+    }
+
+    impl std::io::Write for ChunkWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.largest_chunk = self.largest_chunk.max(buf.len());
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let thing: Vec<String> = (0..20_000).map(|i| format!("item {}", i)).collect();
+    let mut writer = ChunkWriter {
+        bytes: Vec::new(),
+        largest_chunk: 0,
+    };
+    let builder = dbt_serde_yaml::SerializerBuilder::new().indent(4);
+    dbt_serde_yaml::to_writer_with(&mut writer, &thing, builder.clone()).unwrap();
+
+    assert!(writer.largest_chunk < writer.bytes.len() / 4);
+    assert_eq!(
+        String::from_utf8(writer.bytes).unwrap(),
+        dbt_serde_yaml::to_string_with(&thing, builder).unwrap()
+    );
+}
+
+#[test]
+fn test_serializer_driver() {
+    use dbt_serde_yaml::{SerializerBuilder, SerializerDriver};
+
+    let thing: Vec<String> = (0..20_000).map(|i| format!("item {}", i)).collect();
+    let expected = dbt_serde_yaml::to_string(&thing).unwrap();
+    let chunks: Vec<Vec<u8>> = SerializerDriver::new(thing.clone(), SerializerBuilder::new())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(chunks.len() > 1);
+    assert_eq!(String::from_utf8(chunks.concat()).unwrap(), expected);
+
+    // Dropping the driver early stops serialization.
+    let mut driver = SerializerDriver::new(thing, SerializerBuilder::new());
+    assert!(driver.next().unwrap().is_ok());
+    drop(driver);
+
+    struct Failing;
+
+    impl serde::Serialize for Failing {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_entry("k", "v")?;
+            Err(serde::ser::Error::custom("failed"))
+        }
+    }
+
+    let mut driver = SerializerDriver::new(Failing, SerializerBuilder::new());
+    assert_eq!(driver.next().unwrap().unwrap_err().to_string(), "failed");
+    assert!(driver.next().is_none());
+
+    // The value is serialized with the config current at creation.
+    struct Lenient;
+
+    impl serde::Serialize for Lenient {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.serialize_bool(dbt_serde_yaml::Config::current().lenient_variants)
+        }
+    }
+
+    let mut config = dbt_serde_yaml::Config::new();
+    config.lenient_variants = true;
+    let driver = {
+        let _scope = config.enter();
+        SerializerDriver::new(Lenient, SerializerBuilder::new())
+    };
+    let chunks: Vec<Vec<u8>> = driver.collect::<Result<_, _>>().unwrap();
+    assert_eq!(chunks.concat(), b"true\n");
+}
+
+#[test]
+fn test_to_string_at() {
+    use dbt_serde_yaml::path::OwnedPath;
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            columns:
+              - name: id
+                data_tests:
+                  - unique
+                  - not_null
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let path: OwnedPath = "models[0].columns[0]".parse().unwrap();
+    let expected = indoc! {"
+        name: id
+        data_tests:
+        - unique
+        - not_null
+    "};
+    assert_eq!(
+        dbt_serde_yaml::to_string_at(&value, path.as_path()).unwrap(),
+        expected
+    );
+    let mut buffer = Vec::new();
+    dbt_serde_yaml::to_writer_at(&mut buffer, &value, path.as_path()).unwrap();
+    assert_eq!(buffer, expected.as_bytes());
+
+    let path = path.push_key("name");
+    assert_eq!(
+        dbt_serde_yaml::to_string_at(&value, path.as_path()).unwrap(),
+        "id\n"
+    );
+
+    let path: OwnedPath = "models[1]".parse().unwrap();
+    let error = dbt_serde_yaml::to_string_at(&value, path.as_path()).unwrap_err();
+    assert_eq!(error.to_string(), "no value at path models[1]");
+}
+
+#[test]
+fn test_binary() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Blob {
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+        checksum: Vec<u8>,
+    }
+
+    let yaml = indoc! {"
+        data: !!binary aGVsbG8gd29ybGQ=
+        checksum: !!binary |
+          3q2+
+          7w==
+    "};
+    let blob: Blob = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(blob.data, b"hello world");
+    assert_eq!(blob.checksum, [0xDE, 0xAD, 0xBE, 0xEF]);
+
+    let expected = indoc! {"
+        data: !!binary aGVsbG8gd29ybGQ=
+        checksum:
+        - 222
+        - 173
+        - 190
+        - 239
+    "};
+    let serialized = dbt_serde_yaml::to_string(&blob).unwrap();
+    assert_eq!(serialized, expected);
+    let deserialized: Blob = dbt_serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, blob);
+
+    let empty = serde_bytes::ByteBuf::new();
+    let serialized = dbt_serde_yaml::to_string(&empty).unwrap();
+    let deserialized: serde_bytes::ByteBuf = dbt_serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, empty);
+
+    // Through a Value, which keeps the tag.
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(value["data"].to_string(), "!!binary \"aGVsbG8gd29ybGQ=\"");
+    let from_ref: Blob = value.to_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+    assert_eq!(from_ref, blob);
+    let from_owned: Blob = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+    assert_eq!(from_owned, blob);
+    let value = dbt_serde_yaml::to_value(&blob).unwrap();
+    assert_eq!(value["data"].to_string(), "!!binary \"aGVsbG8gd29ybGQ=\"");
+    assert_eq!(dbt_serde_yaml::to_string(&value).unwrap(), expected);
+    let deserialized: Blob = dbt_serde_yaml::from_value(value).unwrap();
+    assert_eq!(deserialized, blob);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_timestamp_chrono() {
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Snapshot {
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        canonical: DateTime<Utc>,
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        spaced: DateTime<FixedOffset>,
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        naive: NaiveDateTime,
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        date: NaiveDate,
+    }
+
+    let yaml = indoc! {"
+        canonical: 2001-12-15T02:59:43.1Z
+        spaced: !!timestamp 2001-12-14 21:59:43.10 -5
+        naive: 2001-12-14t21:59:43
+        date: 2002-12-14
+    "};
+    let snapshot: Snapshot = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(snapshot.canonical, snapshot.spaced);
+    assert_eq!(snapshot.spaced.offset().local_minus_utc(), -5 * 3600);
+    assert_eq!(snapshot.naive.to_string(), "2001-12-14 21:59:43");
+    assert_eq!(
+        snapshot.date,
+        NaiveDate::from_ymd_opt(2002, 12, 14).unwrap()
+    );
+
+    let expected = indoc! {"
+        canonical: 2001-12-15T02:59:43.1Z
+        spaced: 2001-12-14T21:59:43.1-05:00
+        naive: 2001-12-14T21:59:43
+        date: 2002-12-14
+    "};
+    test_serde(&snapshot, expected);
+
+    let result = dbt_serde_yaml::from_str::<Snapshot>(&expected.replace("-12-14\n", "-13-14\n"));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "date: invalid value: string \"2002-13-14\", expected a YAML timestamp at line 4 column 7",
+    );
+
+    let out_of_range = Snapshot {
+        date: NaiveDate::from_ymd_opt(10000, 1, 1).unwrap(),
+        ..snapshot
+    };
+    assert_eq!(
+        dbt_serde_yaml::to_string(&out_of_range)
+            .unwrap_err()
+            .to_string(),
+        "timestamp is out of range for YAML",
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_timestamp_time() {
+    use time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Snapshot {
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        offset: OffsetDateTime,
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        primitive: PrimitiveDateTime,
+        #[serde(with = "dbt_serde_yaml::with::timestamp")]
+        date: Date,
+    }
+
+    let yaml = indoc! {"
+        offset: 2001-12-14 21:59:43.10 +05:30
+        primitive: 2001-12-14 21:59:43.10 -5
+        date: 2002-12-14
+    "};
+    let snapshot: Snapshot = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(snapshot.offset.offset().whole_minutes(), 330);
+    assert_eq!(snapshot.primitive.hour(), 2);
+
+    let expected = indoc! {"
+        offset: 2001-12-14T21:59:43.1+05:30
+        primitive: 2001-12-15T02:59:43.1
+        date: 2002-12-14
+    "};
+    test_serde(&snapshot, expected);
+
+    let out_of_range = Snapshot {
+        date: Date::from_calendar_date(-1, time::Month::January, 1).unwrap(),
+        ..snapshot
+    };
+    assert_eq!(
+        dbt_serde_yaml::to_string(&out_of_range)
+            .unwrap_err()
+            .to_string(),
+        "timestamp is out of range for YAML",
+    );
+}
+
+#[cfg(feature = "round_trip")]
+#[test]
+fn test_preserve_scalar_styles() {
+    use dbt_serde_yaml::event::ScalarStyle;
+    use dbt_serde_yaml::SerializerBuilder;
+
+    let yaml = indoc! {r#"
+        "name": orders
+        description: >
+          The main fact table.
+        sql: |
+          select *
+          from raw
+        alias: 'o'
+        tests:
+        - unique
+        - "not_null"
+        ref: !Ref "customers"
+        flag: !!str true
+    "#};
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let builder = SerializerBuilder::new().preserve_scalar_styles(true);
+    assert_eq!(
+        dbt_serde_yaml::to_string_with(&value, builder.clone()).unwrap(),
+        yaml.replace("!!str true", "'true'"),
+    );
+    let key = value.as_mapping().unwrap().keys().next().unwrap();
+    let Value::String(key, ..) = key else {
+        unreachable!()
+    };
+    assert_eq!(key.style(), Some(ScalarStyle::DoubleQuoted));
+
+    // The styles are only used when asked for.
+    let default = dbt_serde_yaml::to_string(&value).unwrap();
+    assert!(default.starts_with("name: orders\n"));
+    assert!(default.contains("\nalias: o\n"));
+
+    // Replaced strings are written in the usual style, unless given one.
+    value["alias"] = Value::from("ord");
+    value["sql"] = Value::from("select 1");
+    if let Value::String(string, ..) = &mut value["name"] {
+        *string = string.clone().with_style(Some(ScalarStyle::SingleQuoted));
+    }
+    let lines: Vec<String> = dbt_serde_yaml::to_string_with(&value, builder)
+        .unwrap()
+        .lines()
+        .map(str::to_owned)
+        .collect();
+    assert!(lines.contains(&"\"name\": 'orders'".to_owned()));
+    assert!(lines.contains(&"sql: select 1".to_owned()));
+    assert!(lines.contains(&"alias: ord".to_owned()));
+}
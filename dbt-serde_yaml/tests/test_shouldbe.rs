@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use dbt_serde_yaml::{Error, Number, ShouldBe, Value, WhyNot};
+use dbt_serde_yaml::{
+    Error, LenientRaw, Number, ShouldBe, ShouldBeRef, Value, Verbatim, WarningKind, WhyNot,
+};
+use indoc::indoc;
 use serde::de::Error as _;
 use serde_derive::Deserialize;
 
@@ -42,6 +45,54 @@ fn test_deserialize_str() {
         map["invalid"].as_err_msg().unwrap(),
         "invalid: invalid type: map, expected i32 at line 4 column 11"
     );
+    assert_eq!(
+        map["invalid"].as_ref_raw().unwrap(),
+        &dbt_serde_yaml::from_str::<Value>("raw: 0\nwhy_not: Expected a number").unwrap()
+    );
+}
+
+#[test]
+fn test_deserialize_str_matches_value() {
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        name: ShouldBe<String>,
+        count: ShouldBe<i32>,
+        nested: ShouldBe<HashMap<String, i32>>,
+        after: i32,
+    }
+
+    let yaml = r#"
+        name: [not, a, string]
+        count: 2
+        nested:
+          a: 1
+          b: x
+        after: 3
+    "#;
+
+    let from_str: Thing = dbt_serde_yaml::from_str(yaml).unwrap();
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let from_value: Thing = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+
+    for thing in [&from_str, &from_value] {
+        assert!(thing.name.isnt());
+        assert_eq!(
+            thing.name.as_ref_raw().unwrap(),
+            &dbt_serde_yaml::from_str::<Value>("[not, a, string]").unwrap()
+        );
+        assert_eq!(thing.count, ShouldBe::AndIs(2));
+        assert!(thing.nested.isnt());
+        assert_eq!(thing.nested.as_ref_raw().unwrap()["b"], "x");
+        assert_eq!(thing.after, 3);
+    }
+    assert_eq!(
+        from_str.name.as_err_msg().unwrap(),
+        "name: invalid type: sequence, expected a string at line 2 column 15"
+    );
+    assert_eq!(
+        from_str.nested.as_err_msg().unwrap(),
+        "nested.b: invalid type: string \"x\", expected i32 at line 6 column 14"
+    );
 }
 
 #[test]
@@ -145,3 +196,273 @@ fn test_transformer_error() {
         "invalid type: floating point `3.14`, expected i32 at line 4 column 14"
     );
 }
+
+#[test]
+fn test_verbatim_composition() {
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        outer: LenientRaw<i32>,
+        inner: Verbatim<ShouldBe<i32>>,
+        nested: LenientRaw<HashMap<String, String>>,
+        transformed: String,
+    }
+
+    let yaml = r#"
+        outer: x
+        inner: x
+        nested:
+          k: x
+        transformed: x
+    "#;
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let transformer = |v: &Value| match v {
+        Value::String(s, span) if s == "x" => Ok(Some(Value::String("1".into(), span.clone()))),
+        _ => Ok(None),
+    };
+
+    for thing in [
+        value
+            .to_typed::<Thing, _, _>(|_, _, _| {}, transformer)
+            .unwrap(),
+        value
+            .clone()
+            .into_typed::<Thing, _, _>(|_, _, _| {}, transformer)
+            .unwrap(),
+    ] {
+        assert!(thing.outer.isnt());
+        assert_eq!(thing.outer.as_ref_raw(), Some(&Value::from("x")));
+        assert_eq!(
+            thing.outer.as_err_msg().unwrap(),
+            "invalid type: string \"x\", expected i32 at line 2 column 16"
+        );
+        assert!(thing.inner.isnt());
+        assert_eq!(thing.inner.as_ref_raw(), Some(&Value::from("x")));
+        assert_eq!(
+            thing.inner.as_err_msg().unwrap(),
+            "invalid type: string \"x\", expected i32 at line 3 column 16"
+        );
+        assert_eq!(thing.nested.as_ref().unwrap()["k"], "x");
+        assert_eq!(thing.transformed, "1");
+    }
+}
+
+#[test]
+fn test_deserialize_borrowed() {
+    use serde::Deserialize as _;
+    use std::borrow::Cow;
+
+    #[derive(Debug, Deserialize)]
+    struct Column<'a> {
+        #[serde(borrow)]
+        name: ShouldBeRef<&'a str>,
+        #[serde(borrow)]
+        description: ShouldBeRef<Cow<'a, str>>,
+        #[serde(borrow)]
+        tests: ShouldBeRef<Vec<HashMap<&'a str, i32>>>,
+        after: i32,
+    }
+
+    let yaml = r#"
+        name: id
+        description: "The key"
+        tests:
+          - unique: 1
+          - not_null: {}
+        after: 3
+    "#;
+
+    let from_str: Column = dbt_serde_yaml::from_str(yaml).unwrap();
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let from_value = Column::deserialize(&value).unwrap();
+
+    for column in [&from_str, &from_value] {
+        assert_eq!(column.name.as_ref(), Some(&"id"));
+        assert_eq!(column.description.as_deref(), Some("The key"));
+        assert!(column.tests.isnt());
+        assert_eq!(column.tests.as_ref_raw().unwrap(), &value["tests"]);
+        assert_eq!(column.after, 3);
+    }
+    assert!(matches!(from_str.name.as_ref(), Some(name) if yaml.contains(*name)));
+    assert_eq!(
+        from_str.tests.as_err_msg().unwrap(),
+        "tests[1].not_null: invalid type: map, expected i32 at line 6 column 23"
+    );
+
+    // An owned `Value` has nothing to borrow from, so borrowed strings fail
+    // without raw value, while the rest still deserializes.
+    let from_owned: Column = value
+        .clone()
+        .into_typed(|_, _, _| {}, |_| Ok(None))
+        .unwrap();
+    assert!(from_owned.name.isnt());
+    assert!(from_owned.name.as_ref_raw().is_none());
+    assert_eq!(from_owned.description.as_deref(), Some("The key"));
+    assert!(from_owned.tests.isnt());
+    assert_eq!(from_owned.after, 3);
+}
+
+#[test]
+fn test_expecting_flag_does_not_leak() {
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        #[allow(dead_code)]
+        any: ShouldBe<Value>,
+        #[allow(dead_code)]
+        num: i32,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str("any: 1\nnum: x\n").unwrap();
+    let err = value
+        .into_typed::<Thing, _, _>(|_, _, _| {}, |_| Ok(None))
+        .unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!((location.line, location.column), (2, 6));
+}
+
+#[test]
+fn test_failed_attempt_side_effects() {
+    #[derive(Debug, Deserialize)]
+    struct Inner {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        inner: ShouldBe<Inner>,
+        #[allow(dead_code)]
+        other: Inner,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        inner:
+          extra: 1
+        other:
+          name: x
+          stray: 2
+    "})
+    .unwrap();
+    let mut unused = Vec::new();
+    let thing: Thing = value
+        .into_typed(|path, _, _| unused.push(path.to_string()), |_| Ok(None))
+        .unwrap();
+    assert!(thing.inner.isnt());
+    assert_eq!(unused, ["other.stray"]);
+
+    struct Noted;
+
+    impl<'de> serde::Deserialize<'de> for Noted {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            dbt_serde_yaml::record_warning("noted");
+            u8::deserialize(deserializer).map(|_| Noted)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Notes {
+        failed: ShouldBe<Noted>,
+        kept: ShouldBe<Noted>,
+    }
+
+    let (notes, warnings) = dbt_serde_yaml::collect_warnings(|| {
+        dbt_serde_yaml::from_str::<Notes>("failed: x\nkept: 1\n").unwrap()
+    });
+    assert!(notes.failed.isnt());
+    assert!(notes.kept.is());
+    let kinds: Vec<_> = warnings.iter().map(|warning| &warning.kind).collect();
+    assert_eq!(kinds, [&WarningKind::Custom("noted".to_owned())]);
+}
+
+#[test]
+fn test_combinators() {
+    let name: ShouldBe<String> = Ok("orders".to_owned()).into();
+    assert_eq!(name.as_deref(), Some("orders"));
+    let upper = name.map(|name| name.to_uppercase());
+    assert_eq!(upper.as_ref().map(String::as_str), Some("ORDERS"));
+    let len = upper.and_then(|name| ShouldBe::AndIs(name.len()));
+    assert_eq!(len.ok_or_else(|err| err.to_string()), Ok(6));
+
+    let invalid: ShouldBe<String> = Err(Error::custom("not a name")).into();
+    let chained = invalid
+        .clone()
+        .map(|name| name.len())
+        .and_then(|len| ShouldBe::AndIs(len + 1));
+    assert_eq!(chained.as_err_msg(), Some("not a name"));
+    assert!(chained.as_ref_raw().is_none());
+    assert_eq!(
+        chained.ok_or_else(|err| err.to_string()),
+        Err("not a name".to_owned())
+    );
+
+    let failed: ShouldBe<i32> = ShouldBe::AndIs(1).and_then(|_| invalid.map(|_| 0));
+    assert_eq!(failed.as_err_msg(), Some("not a name"));
+
+    // A path already recorded by deserialization is kept.
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        port: ShouldBe<u16>,
+    }
+    let config: Config = dbt_serde_yaml::from_str("port: http\n").unwrap();
+    let path = dbt_serde_yaml::path::Path::Root;
+    let err = config.port.into_result_with_path(&path).unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "port");
+    assert_eq!(
+        err.to_string(),
+        "port: invalid type: string \"http\", expected u16 at line 1 column 7"
+    );
+}
+
+#[cfg(feature = "spill")]
+#[test]
+fn test_spill_raw_values() {
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        small: ShouldBe<i32>,
+        large: ShouldBe<i32>,
+    }
+
+    let yaml = "small: x\nlarge: [a, b, c, d, e, f]\n";
+    let thing: Thing =
+        dbt_serde_yaml::spill_raw_values(16, || dbt_serde_yaml::from_str(yaml)).unwrap();
+
+    assert_eq!(thing.small.as_ref_raw(), Some(&Value::from("x")));
+    assert!(thing.small.spilled_raw().is_none());
+
+    assert!(thing.large.as_ref_raw().is_none());
+    let spilled = thing.large.spilled_raw().unwrap().clone();
+    assert!(spilled.path().exists());
+    assert_eq!(spilled.span().start.line(), 2);
+    let raw = spilled.load().unwrap();
+    assert_eq!(raw.as_sequence().unwrap().len(), 6);
+    assert_eq!(
+        thing.large.as_err_msg().unwrap(),
+        "large: invalid type: sequence, expected i32 at line 2 column 8"
+    );
+    assert_eq!(
+        dbt_serde_yaml::to_string(&thing.large).unwrap(),
+        "- a\n- b\n- c\n- d\n- e\n- f\n"
+    );
+    assert_eq!(spilled.size(), 24);
+
+    // Spilled values compare by content.
+    let again: Thing =
+        dbt_serde_yaml::spill_raw_values(16, || dbt_serde_yaml::from_str(yaml)).unwrap();
+    assert_ne!(again.large.spilled_raw().unwrap().path(), spilled.path());
+    assert_eq!(again.large, thing.large);
+    let other: Thing = dbt_serde_yaml::spill_raw_values(16, || {
+        dbt_serde_yaml::from_str("small: x\nlarge: [a, b, c, d, e, g]\n")
+    })
+    .unwrap();
+    assert_ne!(other.large, thing.large);
+
+    // The temporary file goes away with the last handle.
+    let path = spilled.path().to_owned();
+    drop(thing);
+    assert!(path.exists());
+    drop(spilled);
+    assert!(!path.exists());
+
+    // Outside of the scope, raw values stay in memory.
+    let thing: Thing = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert!(thing.large.as_ref_raw().is_some());
+}
@@ -270,6 +270,150 @@ fn test_value_to_value_span() {
     assert_eq!(root_repr, expected);
 }
 
+#[test]
+fn test_mapping_spans() {
+    let yaml = indoc! {"
+        name: orders
+        version: 2
+    "};
+    let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let mapping = value.as_mapping().unwrap();
+
+    let keys: Vec<String> = mapping
+        .keys_spanned()
+        .map(|(key, span)| {
+            format!(
+                "{}@{}:{}",
+                key.as_str().unwrap(),
+                span.start.line,
+                span.start.column
+            )
+        })
+        .collect();
+    assert_eq!(keys, ["name@1:1", "version@2:1"]);
+
+    let values: Vec<String> = mapping
+        .values_spanned()
+        .map(|(_, span)| format!("{}:{}", span.start.line, span.start.column))
+        .collect();
+    assert_eq!(values, ["1:7", "2:10"]);
+
+    let span = mapping.span_of("version").unwrap();
+    assert_eq!((span.start.line, span.start.column), (2, 1));
+    assert!(mapping.span_of("missing").is_none());
+}
+
+#[test]
+fn test_spanned_keys() {
+    use std::collections::HashMap;
+
+    let yaml = indoc! {"
+        base: &base {materialized: view}
+        orders: *base
+        customers: [*base, {}]
+    "};
+    let positions = |map: &HashMap<Spanned<String>, Spanned<dbt_serde_yaml::Value>>| {
+        let mut positions: Vec<String> = map
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}@{}:{}..{}:{}",
+                    *key,
+                    key.span().start.line,
+                    key.span().start.column,
+                    value.span().end.line,
+                    value.span().end.column,
+                )
+            })
+            .collect();
+        positions.sort();
+        positions
+    };
+    let expected = ["base@1:1..2:1", "customers@3:1..4:1", "orders@2:1..3:1"];
+
+    let map: HashMap<Spanned<String>, Spanned<dbt_serde_yaml::Value>> =
+        dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(positions(&map), expected);
+
+    let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let map: HashMap<Spanned<String>, Spanned<dbt_serde_yaml::Value>> =
+        dbt_serde_yaml::from_value(value.clone()).unwrap();
+    assert_eq!(positions(&map), expected);
+
+    let mut keys: Vec<_> = value
+        .as_mapping()
+        .unwrap()
+        .keys()
+        .map(|key| (key.span().start.line, key.span().start.column))
+        .collect();
+    keys.sort();
+    assert_eq!(keys, [(1, 1), (2, 1), (3, 1)]);
+    let item = &value["customers"][1];
+    assert_eq!((item.span().start.line, item.span().start.column), (3, 20));
+}
+
+#[test]
+fn test_mapping_get_key_value() {
+    let yaml = indoc! {"
+        name: jaffle_shop
+        version: 2
+    "};
+    let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let mapping = value.as_mapping().unwrap();
+
+    let (key, value) = mapping.get_key_value("version").unwrap();
+    assert_eq!(key.as_str(), Some("version"));
+    assert_eq!(value.as_u64(), Some(2));
+    assert_eq!((key.span().start.line, key.span().start.column), (2, 1));
+    assert_eq!(value.span().start.column, 10);
+
+    let (index, key, _) = mapping
+        .get_full(dbt_serde_yaml::Value::from("version"))
+        .unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(key.span().start.line, 2);
+    assert!(mapping.get_key_value("missing").is_none());
+    assert!(mapping.get_full("missing").is_none());
+}
+
+#[test]
+fn test_without_spans() {
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            tags: !pii [a]
+    "};
+    let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let stripped = value.without_spans();
+    assert_eq!(stripped, value);
+    assert!(value.span().is_valid());
+
+    let mut cleared = value.clone();
+    cleared.clear_spans();
+    assert_eq!(format!("{:?}", cleared), format!("{:?}", stripped));
+    let repr = format!("{:?}", stripped);
+    assert!(!repr.contains("@{1:"), "{}", repr);
+    assert_eq!(stripped.span(), &Span::zero());
+    let models = &stripped["models"];
+    assert_eq!(models.span(), &Span::zero());
+    let (key, span) = models[0]
+        .as_mapping()
+        .unwrap()
+        .keys_spanned()
+        .next()
+        .unwrap();
+    assert_eq!(key, "name");
+    assert_eq!(span, &Span::zero());
+
+    #[cfg(feature = "filename")]
+    {
+        let _f = dbt_serde_yaml::with_filename(Some(std::path::PathBuf::from("models.yml")));
+        let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str(yaml).unwrap();
+        assert!(value["models"].span().filename.is_some());
+        assert!(value.without_spans()["models"].span().filename.is_none());
+    }
+}
+
 #[allow(dead_code)]
 fn my_custom_deserialize<'de, D>(deserializer: D) -> Result<Spanned<f64>, D::Error>
 where
@@ -339,6 +483,324 @@ fn test_with_filename() {
     );
 }
 
+#[cfg(feature = "filename")]
+#[test]
+fn test_from_str_named() {
+    use dbt_serde_yaml::Value;
+    use std::path::Path;
+
+    #[derive(Deserialize, Debug)]
+    struct Point {
+        #[allow(dead_code)]
+        x: u64,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str_named("x: 1\n", "models.yml").unwrap();
+    assert_eq!(value.span().get_filename(), Some(Path::new("models.yml")));
+    assert_eq!(
+        value["x"].span().get_filename(),
+        Some(Path::new("models.yml"))
+    );
+
+    let value: Value = dbt_serde_yaml::from_str("x: 1\n").unwrap();
+    assert!(value.span().filename.is_none());
+
+    let err = dbt_serde_yaml::from_str_named::<Point>("x: -1\n", "models.yml").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "models.yml: x: invalid type: integer `-1`, expected u64 at line 1 column 4",
+    );
+    assert_eq!(
+        err.display_no_mark().to_string(),
+        "x: invalid type: integer `-1`, expected u64"
+    );
+    let span = err.span().unwrap();
+    assert_eq!(span.get_filename(), Some(Path::new("models.yml")));
+    assert_eq!((span.start.line, span.start.column), (1, 4));
+
+    let err = dbt_serde_yaml::from_str_named::<Point>("x: 1\n- 2\n", "models.yml").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "models.yml: did not find expected key at line 2 column 1, while parsing a block mapping",
+    );
+    assert!(err.syntax_hint().is_some());
+}
+
+#[cfg(feature = "filename")]
+#[test]
+fn test_config_filename() {
+    use dbt_serde_yaml::{Config, Value};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    let mut config = Config::new();
+    config.filename = Some(Arc::new(PathBuf::from("models.yml")));
+
+    let value: Value = dbt_serde_yaml::from_str_with_config("x: 1\n", &config).unwrap();
+    assert_eq!(value.span().get_filename(), Some(Path::new("models.yml")));
+    assert!(Config::current().filename.is_none());
+
+    {
+        let _f = dbt_serde_yaml::with_filename(Some(PathBuf::from("sources.yml")));
+        let current = Config::current();
+        assert_eq!(
+            current.filename.as_deref(),
+            Some(&PathBuf::from("sources.yml"))
+        );
+
+        let _scope = config.enter();
+        let value: Value = dbt_serde_yaml::from_str("x: 1\n").unwrap();
+        assert_eq!(value.span().get_filename(), Some(Path::new("models.yml")));
+        drop(_scope);
+
+        let value: Value = dbt_serde_yaml::from_str("x: 1\n").unwrap();
+        assert_eq!(value.span().get_filename(), Some(Path::new("sources.yml")));
+    }
+}
+
+#[cfg(feature = "filename")]
+#[test]
+fn test_deserializer_with_filename() {
+    use dbt_serde_yaml::{Deserializer, Value};
+    use serde::Deserialize as _;
+    use std::path::{Path, PathBuf};
+    use std::thread;
+
+    let handles: Vec<_> = ["models.yml", "sources.yml"]
+        .into_iter()
+        .map(|filename| {
+            thread::spawn(move || {
+                let de = Deserializer::from_str("x: [1]\n").with_filename(filename);
+                let value = Value::deserialize(de).unwrap();
+                (value["x"].span().clone(), value["x"][0].span().clone())
+            })
+        })
+        .collect();
+    for (handle, filename) in handles.into_iter().zip(["models.yml", "sources.yml"]) {
+        let (sequence, element) = handle.join().unwrap();
+        assert_eq!(sequence.get_filename(), Some(Path::new(filename)));
+        assert_eq!(element.get_filename(), Some(Path::new(filename)));
+    }
+
+    let _f = dbt_serde_yaml::with_filename(Some(PathBuf::from("ambient.yml")));
+    let de = Deserializer::from_str("x: y\n").with_filename("models.yml");
+    let err = Point::deserialize(de).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "models.yml: x: invalid type: string \"y\", expected u64 at line 1 column 4",
+    );
+    assert_eq!(
+        err.span().unwrap().get_filename(),
+        Some(Path::new("models.yml"))
+    );
+    let value: Value = dbt_serde_yaml::from_str("x: 1\n").unwrap();
+    assert_eq!(
+        value["x"].span().get_filename(),
+        Some(Path::new("ambient.yml"))
+    );
+
+    #[derive(Deserialize, Debug)]
+    struct Point {
+        #[allow(dead_code)]
+        x: u64,
+    }
+}
+
+#[cfg(feature = "filename")]
+#[test]
+fn test_read_file() {
+    use dbt_serde_yaml::Value;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("dbt-serde-yaml-read-file-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("models.yml");
+    fs::write(&path, "a: 1\nb: x\n").unwrap();
+
+    let value = dbt_serde_yaml::read_file_value(&path).unwrap();
+    assert_eq!(value.span().get_filename(), Some(path.as_path()));
+    assert_eq!(value["b"].span().get_filename(), Some(path.as_path()));
+    assert_eq!(value["b"].span().start.line, 2);
+
+    let err = dbt_serde_yaml::read_file::<BTreeMap<String, u32>>(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "{}: b: invalid type: string \"x\", expected u32 at line 2 column 4",
+            path.display(),
+        ),
+    );
+    assert_eq!(err.span().unwrap().get_filename(), Some(path.as_path()));
+
+    let missing = dir.join("missing.yml");
+    let err = dbt_serde_yaml::read_file::<Value>(&missing).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(&format!("{}: ", missing.display())));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_span_serde() {
+    use dbt_serde_yaml::Marker;
+
+    let marker = Marker::new(13, 2, 1);
+    let yaml = dbt_serde_yaml::to_string(&marker).unwrap();
+    assert_eq!(yaml, "index: 13\nline: 2\ncolumn: 1\n");
+    assert_eq!(dbt_serde_yaml::from_str::<Marker>(&yaml).unwrap(), marker);
+
+    let span = Span::new(Marker::new(6, 1, 7), marker);
+    let yaml = dbt_serde_yaml::to_string(&span).unwrap();
+    assert_eq!(dbt_serde_yaml::from_str::<Span>(&yaml).unwrap(), span);
+
+    #[cfg(feature = "filename")]
+    {
+        let span = span
+            .clone()
+            .with_filename(std::path::PathBuf::from("models.yml"));
+        let yaml = dbt_serde_yaml::to_string(&span).unwrap();
+        assert!(yaml.ends_with("filename: models.yml\n"));
+        assert_eq!(dbt_serde_yaml::from_str::<Span>(&yaml).unwrap(), span);
+    }
+
+    let restored: Span = dbt_serde_yaml::from_str("start: {index: 6, line: 1, column: 7}").unwrap();
+    assert_eq!(restored.start, Marker::new(6, 1, 7));
+    assert_eq!(restored.end, Marker::zero());
+    assert!(!restored.is_valid());
+
+    let spanned = Spanned::with_span("orders".to_owned(), span.clone());
+    assert_eq!(spanned.span(), &span);
+    assert_eq!(*spanned, "orders");
+}
+
+#[test]
+fn test_spanned_with_meta() {
+    use dbt_serde_yaml::spanned::SpannedWithMeta;
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        name: Spanned<String>,
+        tags: Vec<Spanned<String>>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Cached {
+        name: SpannedWithMeta<String>,
+        tags: Vec<SpannedWithMeta<String>>,
+    }
+
+    let model: Model = dbt_serde_yaml::from_str("name: orders\ntags: [a]\n").unwrap();
+    let cached = Cached {
+        name: model.name.clone().into(),
+        tags: model
+            .tags
+            .iter()
+            .cloned()
+            .map(SpannedWithMeta::from)
+            .collect(),
+    };
+
+    let yaml = dbt_serde_yaml::to_string(&cached).unwrap();
+    #[cfg(not(feature = "filename"))]
+    assert_eq!(
+        yaml,
+        indoc! {"
+            name:
+              value: orders
+              span:
+                start:
+                  index: 6
+                  line: 1
+                  column: 7
+                end:
+                  index: 13
+                  line: 2
+                  column: 1
+            tags:
+            - value: a
+              span:
+                start:
+                  index: 20
+                  line: 2
+                  column: 8
+                end:
+                  index: 21
+                  line: 2
+                  column: 9
+        "},
+    );
+
+    let restored: Cached = dbt_serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(*restored.name.0, "orders");
+    assert_eq!(restored.name.span(), model.name.span());
+    assert_eq!(restored.tags[0].span(), model.tags[0].span());
+
+    // A missing span restores as an invalid one.
+    let restored: SpannedWithMeta<u32> = dbt_serde_yaml::from_str("value: 1").unwrap();
+    assert_eq!(*restored.0, 1);
+    assert!(!restored.has_valid_span());
+}
+
+#[test]
+fn test_span_context() {
+    use dbt_serde_yaml::{Marker, SpanContext};
+
+    #[derive(Deserialize)]
+    struct Point {
+        #[allow(dead_code)]
+        x: u64,
+        y: Spanned<u64>,
+    }
+
+    dbt_serde_yaml::set_marker(Marker::new(5, 2, 1));
+    let context = SpanContext::capture();
+    dbt_serde_yaml::reset_marker();
+
+    let value: dbt_serde_yaml::Value = dbt_serde_yaml::from_str("x: 1\ny: 2\n").unwrap();
+    let (marker, point) = std::thread::spawn(move || {
+        let marker = {
+            let _scope = context.enter();
+            SpanContext::capture().marker()
+        };
+        assert_eq!(SpanContext::capture().marker(), None);
+
+        let point: Spanned<Point> = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+        (marker, point)
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(marker, Some(Marker::new(5, 2, 1)));
+    assert_eq!(point.span().start.line, 1);
+    assert_eq!(point.y.span().start.line, 2);
+}
+
+#[cfg(feature = "filename")]
+#[test]
+fn test_span_context_filename() {
+    use dbt_serde_yaml::SpanContext;
+    use std::path::Path;
+
+    let context = {
+        let _f = dbt_serde_yaml::with_filename(Some(std::path::PathBuf::from("models.yml")));
+        SpanContext::capture()
+    };
+    assert_eq!(context.filename(), Some(Path::new("models.yml")));
+
+    let value = std::thread::spawn(move || {
+        let _scope = context.enter();
+        dbt_serde_yaml::from_str::<dbt_serde_yaml::Value>("x: 1\n").unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(
+        value.span().filename.as_deref().map(|f| f.as_path()),
+        Some(Path::new("models.yml"))
+    );
+}
+
 #[cfg(feature = "schemars")]
 #[test]
 fn test_schemars() {
@@ -379,3 +841,244 @@ properties:
 "}
     );
 }
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_schemars_docs_and_examples() {
+    use dbt_serde_yaml::JsonSchema;
+    use schemars::schema_for;
+
+    fn default_name() -> &'static str {
+        "orders"
+    }
+
+    /// A dbt model.
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Model {
+        /// The name of the model.
+        #[schemars(example = "default_name")]
+        name: Spanned<String>,
+        /// How many times to retry.
+        #[schemars(example = 3, example = [1, 2])]
+        retries: Option<u32>,
+    }
+
+    let schema = schema_for!(Model);
+    let yaml = dbt_serde_yaml::to_string(&schema).unwrap();
+    assert_eq!(
+        yaml,
+        indoc! {"
+$schema: http://json-schema.org/draft-07/schema#
+title: Model
+description: A dbt model.
+type: object
+required:
+- name
+properties:
+  name:
+    description: The name of the model.
+    examples:
+    - orders
+    type: string
+  retries:
+    description: How many times to retry.
+    examples:
+    - 3
+    - - 1
+      - 2
+    type:
+    - integer
+    - 'null'
+    format: uint32
+    minimum: 0.0
+"}
+    );
+}
+
+#[test]
+fn test_assert_span_support() {
+    use serde::Deserializer;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Column {
+        name: Spanned<String>,
+        data_type: Option<Spanned<String>>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Model {
+        name: Spanned<String>,
+        columns: Vec<Column>,
+    }
+
+    dbt_serde_yaml::assert_span_support::<Model>(indoc! {"
+        name: orders
+        columns:
+          - name: id
+            data_type: int
+    "});
+
+    fn via_json<'de, D: Deserializer<'de>>(d: D) -> Result<Spanned<String>, D::Error> {
+        let s = String::deserialize(d)?;
+        Spanned::deserialize(serde::de::value::StringDeserializer::<D::Error>::new(s))
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Broken {
+        #[serde(deserialize_with = "via_json")]
+        name: Spanned<String>,
+    }
+
+    let message = std::panic::catch_unwind(|| {
+        dbt_serde_yaml::assert_span_support::<Broken>("name: orders\n");
+    })
+    .unwrap_err()
+    .downcast::<String>()
+    .unwrap();
+    assert!(
+        message.contains("Spanned<alloc::string::String> deserialized from text"),
+        "{}",
+        message
+    );
+    assert!(message.contains("deserialized from a Value"), "{}", message);
+}
+
+#[test]
+fn test_nested_deserialization_spans() {
+    // A field whose YAML string holds another YAML document, parsed while the
+    // outer document is being deserialized.
+    #[derive(Debug, PartialEq)]
+    struct Embedded(dbt_serde_yaml::Value);
+
+    impl<'de> serde::Deserialize<'de> for Embedded {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            let s = String::deserialize(d)?;
+            dbt_serde_yaml::from_str(&s)
+                .map(Embedded)
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Outer {
+        config: Spanned<Embedded>,
+        after: Spanned<u32>,
+    }
+
+    let yaml = indoc! {"
+        config: 'a: [1, 2]'
+        after: 3
+    "};
+    let outer: Outer = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(outer.config.0["a"][1], 2);
+    assert!(outer.config.has_valid_span());
+    assert_eq!(outer.config.span().start.index, 8);
+    assert_eq!(outer.config.span().end.index, 20);
+    assert!(outer.after.has_valid_span());
+    assert_eq!(outer.after.span().start.line, 2);
+    assert_eq!(outer.after.span().start.column, 8);
+}
+
+#[test]
+fn test_spanned_value_into_deserializer() {
+    use dbt_serde_yaml::Value;
+    use serde::de::IntoDeserializer;
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize)]
+    struct Column {
+        name: Spanned<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Model {
+        name: Spanned<String>,
+        columns: Vec<Spanned<Column>>,
+    }
+
+    let yaml = indoc! {"
+        models:
+          orders:
+            name: orders
+            columns:
+              - name: id
+    "};
+    let doc: BTreeMap<String, BTreeMap<String, Spanned<Value>>> =
+        dbt_serde_yaml::from_str(yaml).unwrap();
+    let orders = &doc["models"]["orders"];
+    assert_eq!(orders.span().start.line, 3);
+
+    let check = |model: Spanned<Model>| {
+        assert_eq!(model.span().start.line, 3);
+        assert_eq!(model.span().start.column, 5);
+        assert_eq!(*model.name, "orders");
+        assert_eq!(model.name.span().start.line, 3);
+        assert_eq!(model.name.span().start.column, 11);
+        let column = &model.columns[0];
+        assert_eq!(column.span().start.line, 5);
+        assert_eq!(column.span().start.column, 9);
+        assert_eq!(column.name.span().start.column, 15);
+    };
+    check(Spanned::<Model>::deserialize(orders.into_deserializer()).unwrap());
+    check(Spanned::<Model>::deserialize(orders.clone().into_deserializer()).unwrap());
+
+    // The span of the `Spanned` stands in for a value built without one.
+    let built = Spanned::with_span(Value::from("built"), orders.span().clone());
+    let name = Spanned::<String>::deserialize(built.into_deserializer()).unwrap();
+    assert_eq!(*name, "built");
+    assert_eq!(name.span(), orders.span());
+}
+
+#[test]
+fn test_foreign_deserializer() {
+    use dbt_serde_yaml::{Config, Value};
+    use serde::de::value::{Error, MapDeserializer, StrDeserializer};
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        name: Spanned<String>,
+    }
+
+    let foreign = || {
+        let entries = [("name", "orders")];
+        Model::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter()))
+    };
+
+    let model = foreign().unwrap();
+    assert_eq!(*model.name, "orders");
+    assert!(model.name.is_foreign());
+    assert!(!model.name.has_valid_span());
+    assert!(model.name.clone().map(|name| name.len()).is_foreign());
+
+    // Values deserialized by this crate are not foreign, even without a
+    // location to report.
+    let model: Model = dbt_serde_yaml::from_str("name: orders").unwrap();
+    assert!(!model.name.is_foreign());
+    let value = || Value::mapping([("name".into(), "orders".into())].into_iter().collect());
+    let model = Model::deserialize(value()).unwrap();
+    assert!(!model.name.is_foreign());
+    // A `Value` used as a deserializer outside of the entry points leaves its
+    // marker behind.
+    dbt_serde_yaml::reset_marker();
+    assert!(!Spanned::new("orders".to_owned()).is_foreign());
+
+    let mut config = Config::new();
+    config.strict_spans = true;
+    let _scope = config.enter();
+    let err = foreign().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot capture the span of Spanned<alloc::string::String>: the deserializer does not report source locations",
+    );
+    let err = Spanned::<String>::deserialize(StrDeserializer::<Error>::new("orders")).unwrap_err();
+    assert!(err.to_string().starts_with("cannot capture the span"));
+
+    let model: Model = dbt_serde_yaml::from_str("name: orders").unwrap();
+    assert_eq!(model.name.span().start.column, 7);
+    let model = Model::deserialize(value()).unwrap();
+    assert!(!model.name.has_valid_span());
+}
@@ -7,7 +7,7 @@
 use std::collections::HashMap;
 
 use dbt_serde_yaml::Spanned;
-use dbt_serde_yaml::{value::TransformedResult, Number, Value, Verbatim};
+use dbt_serde_yaml::{value::TransformedResult, Mapping, Number, Sequence, Value, Verbatim};
 use dbt_serde_yaml_derive::UntaggedEnumDeserialize;
 use indoc::indoc;
 use serde::de::{DeserializeOwned, IntoDeserializer};
@@ -116,7 +116,9 @@ fn deserialize_value<T: DeserializeOwned + PartialEq + std::fmt::Debug>(
 fn test_into_typed() {
     fn transformer(v: &Value) -> Result<Option<Value>, Box<dyn std::error::Error + Send + Sync>> {
         match v {
-            Value::String(s, span) => Ok(Some(Value::String(format!("{} name", s), span.clone()))),
+            Value::String(s, span) => Ok(Some(
+                Value::string(format!("{} name", s)).with_span(span.clone()),
+            )),
             _ => Ok(None),
         }
     }
@@ -284,6 +286,77 @@ fn test_into_typed() {
     );
 }
 
+#[test]
+fn test_into_typed_elements() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Model {
+        name: String,
+        enabled: bool,
+    }
+
+    let yaml = indoc! {"
+        - name: a
+          enabled: true
+        - name: b
+          enabled: maybe
+        - name: c
+          enabled: false
+          extra: 1
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let mut unused_keys = Vec::new();
+    let models = value
+        .into_typed_elements::<Model, _, _>(
+            |path, _, _| unused_keys.push(path.to_string()),
+            |_| Ok(None),
+        )
+        .unwrap();
+    assert_eq!(models.len(), 3);
+    assert_eq!(
+        models[0].as_ref().unwrap(),
+        &Model {
+            name: "a".to_string(),
+            enabled: true
+        }
+    );
+    let err = models[1].as_ref().unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 4);
+    assert_eq!(
+        models[2].as_ref().unwrap(),
+        &Model {
+            name: "c".to_string(),
+            enabled: false
+        }
+    );
+    assert_eq!(unused_keys, [".[2].extra"]);
+
+    let value: Value = dbt_serde_yaml::from_str("a: 1").unwrap();
+    let err = value
+        .into_typed_elements::<Model, _, _>(|_, _, _| {}, |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: map, expected a YAML sequence at line 1 column 1"
+    );
+
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let sequence = Sequence::try_from(value).unwrap();
+    let mut unused_keys = Vec::new();
+    let models = sequence.into_typed_elements::<Model, _, _>(
+        |path, _, _| unused_keys.push(path.to_string()),
+        |_| Ok(None),
+    );
+    assert_eq!(models.len(), 3);
+    assert!(models[0].is_ok());
+    assert_eq!(
+        models[1].as_ref().unwrap_err().location().unwrap().line(),
+        4
+    );
+    assert!(models[2].is_ok());
+    assert_eq!(unused_keys, [".[2].extra"]);
+}
+
 #[test]
 fn test_into_typed_external_err() {
     #[derive(Debug, PartialEq)]
@@ -366,6 +439,561 @@ fn test_merge() {
     }
 }
 
+#[test]
+fn test_deep_merge() {
+    use dbt_serde_yaml::value::MergeStrategy;
+
+    let base: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: base
+        vars:
+          a: 1
+          b: 2
+        tags: [x]
+        owner: me
+    "})
+    .unwrap();
+    let overlay: Value = dbt_serde_yaml::from_str(indoc! {"
+        vars:
+          b: 3
+          c: 4
+        tags: [y]
+        owner: null
+    "})
+    .unwrap();
+
+    let mut merged = base.clone();
+    merged.deep_merge(overlay.clone(), MergeStrategy::default());
+    let expected: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: base
+        vars: {a: 1, b: 3, c: 4}
+        tags: [y]
+        owner: null
+    "})
+    .unwrap();
+    assert_eq!(merged, expected);
+
+    let mut merged = base.clone();
+    merged.deep_merge(
+        overlay.clone(),
+        MergeStrategy::new()
+            .append_sequences(true)
+            .ignore_nulls(true),
+    );
+    let expected: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: base
+        vars: {a: 1, b: 3, c: 4}
+        tags: [x, y]
+        owner: me
+    "})
+    .unwrap();
+    assert_eq!(merged, expected);
+
+    let mut merged = base;
+    merged.deep_merge(overlay, MergeStrategy::new().recurse_mappings(false));
+    let expected: Value = dbt_serde_yaml::from_str(indoc! {"
+        vars: {b: 3, c: 4}
+        tags: [y]
+        owner: null
+    "})
+    .unwrap();
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn test_collect_dependencies() {
+    use dbt_serde_yaml::{collect_dependencies, record_dependency, DependencyEdge, Span};
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+        depends_on: Vec<String>,
+    }
+
+    let yaml = indoc! {"
+        - name: a
+          depends_on: [ref(b), ref(c)]
+        - name: b
+          depends_on: []
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let (models, edges) = collect_dependencies(|| {
+        value.into_typed::<Vec<Model>, _, _>(
+            |_, _, _| {},
+            |v| {
+                if let Some(name) = v.as_str().and_then(|s| s.strip_prefix("ref(")) {
+                    record_dependency(name.trim_end_matches(')'));
+                }
+                Ok(None)
+            },
+        )
+    });
+    assert_eq!(models.unwrap().len(), 2);
+    let edges: Vec<_> = edges
+        .into_iter()
+        .map(|DependencyEdge { path, span, target }| (path, span.start.line, target))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            (".[0].depends_on[0]".to_string(), 2, "b".to_string()),
+            (".[0].depends_on[1]".to_string(), 2, "c".to_string()),
+        ]
+    );
+
+    // Outside of a transformer, the current source location is used.
+    struct Ref;
+    impl<'de> serde::Deserialize<'de> for Ref {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            record_dependency(name);
+            Ok(Ref)
+        }
+    }
+    let ((), edges) = collect_dependencies(|| {
+        let ((), inner) = collect_dependencies(|| {
+            dbt_serde_yaml::from_str::<Vec<Ref>>("- x\n- y\n").unwrap();
+        });
+        assert_eq!(inner.len(), 2);
+        record_dependency("z");
+    });
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].target, "z");
+    assert_eq!(edges[0].span, Span::default());
+
+    let ((), edges) = collect_dependencies(|| {
+        dbt_serde_yaml::from_str::<Vec<Ref>>("- x\n- y\n").unwrap();
+    });
+    assert_eq!(edges[0].target, "x");
+    assert_eq!(edges[1].target, "y");
+    assert!(edges[1].span.is_valid());
+
+    // A transformer converting a nested value of its own leaves the location
+    // of the outer value in place for what it reports afterwards.
+    let value: Value = dbt_serde_yaml::from_str("outer: {inner: x}\n").unwrap();
+    let (result, edges) = collect_dependencies(|| {
+        value.into_typed::<Value, _, _>(
+            |_, _, _| {},
+            |v| {
+                if v.is_mapping() && v.get("inner").is_some() {
+                    v.to_typed::<Value, _, _>(
+                        |_, _, _| {},
+                        |v| {
+                            if v.is_string() {
+                                record_dependency("inner");
+                            }
+                            Ok(None)
+                        },
+                    )?;
+                    record_dependency("outer");
+                }
+                Ok(None)
+            },
+        )
+    });
+    assert!(result.is_ok());
+    let edges: Vec<_> = edges.into_iter().map(|e| (e.path, e.target)).collect();
+    assert_eq!(
+        edges,
+        [
+            ("inner".to_string(), "inner".to_string()),
+            ("outer".to_string(), "outer".to_string()),
+        ]
+    );
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision() {
+    let yaml = indoc! {"
+        scale: 123456789012345678901234567890
+        negative: -99999999999999999999
+        pi: 3.14159265358979323846
+        huge: 1e400
+        small: 0.1
+        quoted: '3.14159265358979323846'
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let number = |value: &Value| match value {
+        Value::Number(number, ..) => number.clone(),
+        _ => panic!("expected a number"),
+    };
+    let scale = number(&value["scale"]);
+    assert_eq!(
+        scale.as_arbitrary_precision(),
+        Some("123456789012345678901234567890"),
+    );
+    assert_eq!(scale.as_u64(), None);
+    assert_eq!(scale.as_f64(), Some(1.2345678901234568e29));
+    assert_eq!(value["scale"].span().start.line(), 1);
+    for key in ["negative", "pi", "huge"] {
+        assert!(number(&value[key]).as_arbitrary_precision().is_some());
+    }
+    assert_eq!(value["small"].as_f64(), Some(0.1));
+    assert!(value["quoted"].is_string());
+
+    // Round trips exactly, through text and through to_value.
+    assert_eq!(dbt_serde_yaml::to_string(&value).unwrap(), yaml);
+    assert_eq!(dbt_serde_yaml::to_value(&value).unwrap(), value);
+
+    let number: Number = "-1e400".parse().unwrap();
+    assert_eq!(number.to_string(), "-1e400");
+    let number: Number = dbt_serde_yaml::from_str("18446744073709551616").unwrap();
+    assert_eq!(
+        number.as_arbitrary_precision(),
+        Some("18446744073709551616")
+    );
+
+    let err = dbt_serde_yaml::from_str::<bool>("1e400").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: 1e400, expected a boolean at line 1 column 1",
+    );
+}
+
+#[test]
+fn test_sequence() {
+    use dbt_serde_yaml::Sequence;
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            version: 1
+          - name: customers
+            version: two
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let models = <&Sequence>::try_from(&value["models"]).unwrap();
+
+    let customers = models.find_by_key("name", "customers").unwrap();
+    assert_eq!(customers.span().start.line(), 4);
+    assert!(models.find_by_key("version", 1).is_some());
+    assert!(models.find_by_key("name", "payments").is_none());
+
+    let versions: Vec<_> = models
+        .iter()
+        .map(|model| &model["version"])
+        .cloned()
+        .collect::<Sequence>()
+        .iter_typed::<u32>()
+        .collect();
+    assert_eq!(*versions[0].as_ref().unwrap(), 1);
+    assert_eq!(
+        versions[1].as_ref().unwrap_err().to_string(),
+        "invalid type: string \"two\", expected u32 at line 5 column 14",
+    );
+
+    // Still usable as a Vec.
+    let mut sequence = models.clone();
+    sequence.push(Value::from("extra"));
+    assert_eq!(sequence.len(), 3);
+    assert_eq!(sequence[2], "extra");
+    let vec: Vec<Value> = sequence.into();
+    assert_eq!(Sequence::from(vec.clone()), vec);
+}
+
+#[test]
+fn test_unused_key_collector() {
+    use dbt_serde_yaml::value::UnusedKeyCollector;
+
+    #[derive(Deserialize, Debug)]
+    struct Column {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        #[allow(dead_code)]
+        columns: Vec<Column>,
+        #[allow(dead_code)]
+        version: u32,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        columns:
+          - name: id
+            descripton: key
+        extra: 1
+        version: 2
+    "})
+    .unwrap();
+
+    let mut unused = UnusedKeyCollector::new();
+    let _: Model = value.to_typed(unused.collect(), |_| Ok(None)).unwrap();
+    let paths: Vec<_> = unused.iter().map(|(path, ..)| path.to_string()).collect();
+    assert_eq!(paths, ["columns[0].descripton", "extra"]);
+
+    let mut unused = UnusedKeyCollector::new();
+    let _: Model = value
+        .clone()
+        .into_typed_collecting(&mut unused, |_| Ok(None))
+        .unwrap();
+    let unused = unused.into_vec();
+    assert_eq!(unused.len(), 2);
+    assert_eq!(unused[0].1, "descripton");
+    assert_eq!(unused[0].2, "key");
+    assert_eq!(unused[1].2.span().start.line, 4);
+
+    let mut value = value;
+    value["version"] = Value::from("two");
+    let mut unused = UnusedKeyCollector::new();
+    assert!(value
+        .into_typed_collecting::<Model, _>(&mut unused, |_| Ok(None))
+        .is_err());
+    assert_eq!(unused.len(), 2);
+}
+
+#[test]
+fn test_report_ignored_values() {
+    use dbt_serde_yaml::value::UnusedKeyCollector;
+    use dbt_serde_yaml::Config;
+    use serde::de::IgnoredAny;
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        #[allow(dead_code)]
+        name: String,
+        // Still accepted, but no longer used.
+        #[allow(dead_code)]
+        materialized: IgnoredAny,
+        #[allow(dead_code)]
+        meta: HashMap<String, IgnoredAny>,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: orders
+        materialized: table
+        meta: {owner: finance}
+        extra: 1
+    "})
+    .unwrap();
+
+    let mut unused = UnusedKeyCollector::new();
+    let _: Model = value.to_typed(unused.collect(), |_| Ok(None)).unwrap();
+    let paths: Vec<_> = unused.iter().map(|(path, ..)| path.to_string()).collect();
+    assert_eq!(paths, ["extra"]);
+
+    let mut config = Config::new();
+    config.report_ignored_values = true;
+    let _scope = config.enter();
+
+    let mut unused = UnusedKeyCollector::new();
+    let _: Model = value.to_typed(unused.collect(), |_| Ok(None)).unwrap();
+    let paths: Vec<_> = unused.iter().map(|(path, ..)| path.to_string()).collect();
+    assert_eq!(paths, ["materialized", "meta.owner", "extra"]);
+
+    let mut unused = UnusedKeyCollector::new();
+    let _: Model = value
+        .into_typed_collecting(&mut unused, |_| Ok(None))
+        .unwrap();
+    let unused = unused.into_vec();
+    assert_eq!(unused.len(), 3);
+    assert_eq!(unused[0].1, "materialized");
+    assert_eq!(unused[0].2, "table");
+    assert_eq!(unused[1].2.span().start.line, 3);
+}
+
+#[test]
+fn test_unused_keys_deny() {
+    use dbt_serde_yaml::value::UnusedKeys;
+
+    #[derive(Deserialize, Debug)]
+    struct Column {
+        #[allow(dead_code)]
+        name: String,
+        #[allow(dead_code)]
+        description: Option<String>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        #[allow(dead_code)]
+        columns: Vec<Column>,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        columns:
+          - name: id
+          - name: amount
+            descripton: typo
+          - {name: status, extra: 1}
+    "})
+    .unwrap();
+
+    let _: Model = value
+        .to_typed_with(UnusedKeys::Ignore, |_| Ok(None))
+        .unwrap();
+
+    let err = value
+        .to_typed_with::<Model, _>(UnusedKeys::Deny, |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "columns[1].descripton: unknown field `descripton` at line 4 column 5",
+    );
+    assert_eq!(err.path().unwrap().to_string(), "columns[1].descripton");
+
+    let mut value = value;
+    value["columns"][2] = Value::from("not a column");
+    let err = value
+        .into_typed_with::<Model, _>(UnusedKeys::Deny, |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "columns[1].descripton: unknown field `descripton` at line 4 column 5",
+    );
+}
+
+#[test]
+fn test_into_typed_default() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        version: u32,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: orders
+        version: 2
+        extra: ignored
+    "})
+    .unwrap();
+
+    let expected = Model {
+        name: "orders".to_owned(),
+        version: 2,
+    };
+    assert_eq!(expected, value.to_typed_default::<Model>().unwrap());
+    assert_eq!(expected, value.into_typed_default::<Model>().unwrap());
+}
+
+#[test]
+fn test_display() {
+    let value: Value = dbt_serde_yaml::from_str(indoc! {r##"
+        name: orders
+        "on": true
+        "123": "with \"quotes\"\tand\x07controls"
+        ? [a, b]
+        : sequence key
+        ? {c: 1, d: [2]}
+        : mapping key
+        !Tagged x: !Other {e: ~}
+        ~: .nan
+        "": ""
+        ? - nested
+          - [1.5, -2]
+        : [x: 1, "a: b", "#c", ' padded ']
+    "##})
+    .unwrap();
+
+    let display = value.to_string();
+    assert_eq!(
+        display,
+        concat!(
+            r#"{name: orders, on: true, "123": "with \"quotes\"\tand\u0007controls", "#,
+            r#"[a, b]: sequence key, {c: 1, d: [2]}: mapping key, !Tagged x: !Other {e: null}, "#,
+            r##"null: .nan, "": "", [nested, [1.5, -2]]: [{x: 1}, "a: b", "#c", " padded "]}"##,
+        ),
+    );
+    assert_eq!(value, dbt_serde_yaml::from_str::<Value>(&display).unwrap());
+}
+
+#[test]
+fn test_sort_keys() {
+    let yaml = indoc! {"
+        b: 1
+        a:
+          d: [{f: 1, e: 2}]
+          c: !tag {h: 3, g: 4}
+        2: x
+        null: y
+    "};
+    let mut mapping: Mapping = dbt_serde_yaml::from_str(yaml).unwrap();
+    let original = mapping.clone();
+
+    mapping.sort_keys();
+    let expected = indoc! {"
+        null: y
+        2: x
+        a:
+          d:
+          - f: 1
+            e: 2
+          c: !tag
+            h: 3
+            g: 4
+        b: 1
+    "};
+    assert_eq!(dbt_serde_yaml::to_string(&mapping).unwrap(), expected);
+    assert_eq!(mapping, original);
+
+    mapping.sort_keys_recursive();
+    let expected = indoc! {"
+        null: y
+        2: x
+        a:
+          c: !tag
+            g: 4
+            h: 3
+          d:
+          - e: 2
+            f: 1
+        b: 1
+    "};
+    assert_eq!(dbt_serde_yaml::to_string(&mapping).unwrap(), expected);
+}
+
+#[test]
+fn test_try_from() {
+    use dbt_serde_yaml::Mapping;
+
+    let yaml = indoc! {"
+        name: orders
+        version: 2
+        ratio: 0.5
+        enabled: true
+        tags: [a, b]
+        config: {materialized: table}
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    assert_eq!(<&str>::try_from(&value["name"]).unwrap(), "orders");
+    assert_eq!(i64::try_from(&value["version"]).unwrap(), 2);
+    assert_eq!(u64::try_from(&value["version"]).unwrap(), 2);
+    assert_eq!(f64::try_from(&value["ratio"]).unwrap(), 0.5);
+    assert_eq!(f64::try_from(&value["version"]).unwrap(), 2.0);
+    assert!(bool::try_from(&value["enabled"]).unwrap());
+    assert_eq!(<&Vec<Value>>::try_from(&value["tags"]).unwrap().len(), 2);
+    assert_eq!(<&Mapping>::try_from(&value["config"]).unwrap().len(), 1);
+
+    let extract = |value: Value| -> Result<(String, Vec<Value>, Mapping), dbt_serde_yaml::Error> {
+        let mut mapping = Mapping::try_from(value)?;
+        let name = String::try_from(mapping.remove("name").unwrap())?;
+        let tags = Vec::try_from(mapping.remove("tags").unwrap())?;
+        let config = Mapping::try_from(mapping.remove("config").unwrap())?;
+        Ok((name, tags, config))
+    };
+    let (name, tags, config) = extract(value.clone()).unwrap();
+    assert_eq!(name, "orders");
+    assert_eq!(tags, [Value::from("a"), Value::from("b")]);
+    assert_eq!(config["materialized"], "table");
+
+    let err = i64::try_from(&value["name"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"orders\", expected i64 at line 1 column 7"
+    );
+    let err = String::try_from(value["version"].clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: integer `2`, expected a string at line 2 column 10"
+    );
+    let err = Mapping::try_from(value["tags"].clone()).unwrap_err();
+    assert_eq!(err.span().unwrap().start.line(), 5);
+}
+
 #[test]
 fn test_debug() {
     let yaml = indoc! {"
@@ -564,6 +1192,52 @@ fn test_verbatim() {
     assert_eq!(thing, thing2);
 }
 
+#[test]
+fn test_verbatim_include_policy() {
+    use dbt_serde_yaml::value::TransformPolicy;
+    use dbt_serde_yaml::Transformed;
+
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Inner {
+        a: i32,
+        b: Transformed<i32>,
+    }
+
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Thing {
+        x: i32,
+        y: Verbatim<i32>,
+        inner: Verbatim<Inner>,
+    }
+
+    let value = dbt_serde_yaml::from_str::<Value>("{x: 1, y: 2, inner: {a: 3, b: 4}}").unwrap();
+    let add_100 = |v: &Value| Ok(v.as_i64().map(|v| Value::from(v + 100)));
+
+    let thing: Thing = value
+        .to_typed_with_policy(|_, _, _| {}, add_100, TransformPolicy::IncludeVerbatim)
+        .unwrap();
+    assert_eq!((thing.x, *thing.y), (101, 102));
+    assert_eq!((thing.inner.a, *thing.inner.b), (103, 104));
+
+    // The policy only applies to the call it is passed to.
+    let thing: Thing = value.to_typed(|_, _, _| {}, add_100).unwrap();
+    assert_eq!((thing.x, *thing.y), (101, 2));
+    assert_eq!((thing.inner.a, *thing.inner.b), (3, 104));
+
+    let thing: Thing = value
+        .clone()
+        .into_typed_with_policy(|_, _, _| {}, add_100, TransformPolicy::IncludeVerbatim)
+        .unwrap();
+    assert_eq!(*thing.y, 102);
+
+    // Or to every call made while a config setting it is entered.
+    let mut config = dbt_serde_yaml::Config::new();
+    config.transform_policy = TransformPolicy::IncludeVerbatim;
+    let _scope = config.enter();
+    let thing: Thing = value.into_typed(|_, _, _| {}, add_100).unwrap();
+    assert_eq!(*thing.y, 102);
+}
+
 #[test]
 fn test_verbatim_flatten() {
     #[derive(Deserialize, Serialize, PartialEq, Eq, Debug)]
@@ -580,24 +1254,34 @@ fn test_verbatim_flatten() {
         z: 3
     "})
     .unwrap();
+    let null_ints = |v: &Value| {
+        if v.is_i64() {
+            Ok(Some(Value::null()))
+        } else {
+            Ok(None)
+        }
+    };
+    let thing2: Thing2 = value.to_typed(|_, _, _| {}, null_ints).unwrap();
+    assert_eq!(thing2.x, None);
+    assert_eq!(
+        *thing2.rest,
+        HashMap::from_iter([("z".to_string(), Some(3))])
+    );
+
     let thing2: Thing2 = value
         .into_typed(
             |path, key: &Value, _| {
                 panic!("unexpected key {:?} at path {:?}", key, path.to_string());
             },
-            |v| {
-                if v.is_i64() {
-                    Ok(Some(Value::null()))
-                } else {
-                    Ok(None)
-                }
-            },
+            null_ints,
         )
         .unwrap();
     assert_eq!(thing2.x, None);
     assert_eq!(*thing2.y, 2);
-    // Note: unfortunately `Verbatim` does not work in `flatten` fields:
-    assert_eq!(*thing2.rest, HashMap::from_iter([("z".to_string(), None)]));
+    assert_eq!(
+        *thing2.rest,
+        HashMap::from_iter([("z".to_string(), Some(3))])
+    );
 
     let value = dbt_serde_yaml::to_value(thing2).unwrap();
     assert_eq!(
@@ -605,7 +1289,7 @@ fn test_verbatim_flatten() {
         dbt_serde_yaml::from_str::<Value>(indoc! {"
             x: null
             y: 2
-            z: null
+            z: 3
         "})
         .unwrap()
     );
@@ -866,62 +1550,239 @@ definitions:
     );
 }
 
-#[cfg(all(feature = "schemars", feature = "flatten_dunder"))]
+#[cfg(feature = "schemars")]
 #[test]
-fn test_schemars_flatten() {
+fn test_schemars_validate() {
     #![allow(dead_code)]
 
     use dbt_serde_yaml::JsonSchema;
     use schemars::schema_for;
 
-    #[derive(Deserialize, JsonSchema)]
-    struct Thing {
-        a: i32,
-        #[serde(flatten)]
-        rest: Verbatim<HashMap<String, Option<i32>>>,
-    }
-
-    #[derive(Deserialize, JsonSchema)]
-    struct Thing2 {
-        x: Option<i32>,
-        y: Verbatim<Value, i32>,
-        //        #[serde(flatten)]
-        __thing__: Option<Thing>,
+    #[derive(JsonSchema)]
+    struct Config {
+        #[validate(range(min = 1, max = 10))]
+        threads: i32,
+        #[validate(range(exclusive_min = 0))]
+        timeout: Option<f64>,
+        #[validate(length(min = 1, max = 63))]
+        schema: Verbatim<String>,
+        #[schemars(pattern = "^[a-z_]+$")]
+        alias: Option<String>,
+        #[validate(regex(pattern = "^v[0-9]+$"))]
+        version: String,
+        #[validate(length(equal = 2))]
+        tags: Vec<String>,
     }
 
-    let schema = schema_for!(Thing2);
+    let schema = schema_for!(Config);
     let schema_string = dbt_serde_yaml::to_string(&schema).unwrap();
-    println!("{}", schema_string);
     assert_eq!(
         schema_string,
         indoc! {"
 $schema: http://json-schema.org/draft-07/schema#
-title: Thing2
+title: Config
 type: object
 required:
-- y
+- schema
+- tags
+- threads
+- version
 properties:
-  a:
-    type: integer
-    format: int32
-  x:
+  alias:
     type:
-    - integer
+    - string
     - 'null'
-    format: int32
-  y:
+    pattern: ^[a-z_]+$
+  schema:
+    type: string
+    maxLength: 63
+    minLength: 1
+  tags:
+    type: array
+    items:
+      type: string
+    maxItems: 2
+    minItems: 2
+  threads:
     type: integer
     format: int32
+    maximum: 10.0
+    minimum: 1.0
+  timeout:
+    type:
+    - number
+    - 'null'
+    format: double
+    exclusiveMinimum: 0.0
+  version:
+    type: string
+    pattern: ^v[0-9]+$
 "}
     );
 }
 
+#[cfg(feature = "validate")]
 #[test]
-fn test_untagged_enum() {
-    #[derive(Deserialize, PartialEq, Eq, Debug)]
-    struct Thing {
-        a: Verbatim<i32>,
-        b: Verbatim<Option<i32>>,
+fn test_validate() {
+    #![allow(dead_code)]
+
+    use dbt_serde_yaml::validate::validate;
+    use dbt_serde_yaml::JsonSchema;
+    use schemars::schema_for;
+
+    #[derive(JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Project {
+        name: String,
+        #[validate(range(min = 1, max = 64))]
+        threads: Option<u32>,
+        models: Vec<Model>,
+    }
+
+    #[derive(JsonSchema)]
+    struct Model {
+        #[schemars(pattern = "^[a-z_]+$")]
+        name: String,
+        materialized: Option<Materialization>,
+        tags: Option<StringOrList>,
+    }
+
+    #[derive(JsonSchema)]
+    enum Materialization {
+        View,
+        Table,
+        Incremental(String),
+    }
+
+    #[derive(JsonSchema)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    let yaml = indoc! {"
+        name: jaffle_shop
+        threads: 0
+        modles: []
+        models:
+          - name: orders
+            materialized: !Incremental id
+            tags: [a, b]
+          - name: Customers
+            materialized: Ephemeral
+            tags: [a, 1]
+          - materialized: !Incremental [id]
+            tags: {a: b}
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let errors = validate(&value, &schema_for!(Project));
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        errors,
+        [
+            "threads: expected a number at least 1, found integer `0` at line 2 column 10",
+            "modles: expected a known key, found unknown key `modles` at line 3 column 1",
+            "models[1].name: expected a string matching `^[a-z_]+$`, found string \"Customers\" at line 8 column 11",
+            "models[1].materialized: expected `View` or `Table`, found string \"Ephemeral\" at line 9 column 19",
+            "models[1].tags[1]: expected string, found integer `1` at line 10 column 15",
+            "models[2].materialized.Incremental: expected string, found sequence at line 11 column 33",
+            "models[2].tags: expected string, sequence or null, found map at line 12 column 11",
+            "models[2].name: expected a value, found nothing at line 11 column 5",
+        ],
+    );
+}
+
+#[cfg(all(feature = "schemars", feature = "flatten_dunder"))]
+#[test]
+fn test_schemars_flatten() {
+    #![allow(dead_code)]
+
+    use dbt_serde_yaml::JsonSchema;
+    use schemars::schema_for;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct Thing {
+        a: i32,
+        #[serde(flatten)]
+        rest: Verbatim<HashMap<String, Option<i32>>>,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct Thing2 {
+        x: Option<i32>,
+        y: Verbatim<Value, i32>,
+        //        #[serde(flatten)]
+        __thing__: Option<Thing>,
+    }
+
+    let schema = schema_for!(Thing2);
+    let schema_string = dbt_serde_yaml::to_string(&schema).unwrap();
+    println!("{}", schema_string);
+    assert_eq!(
+        schema_string,
+        indoc! {"
+$schema: http://json-schema.org/draft-07/schema#
+title: Thing2
+type: object
+required:
+- y
+properties:
+  a:
+    type: integer
+    format: int32
+  x:
+    type:
+    - integer
+    - 'null'
+    format: int32
+  y:
+    type: integer
+    format: int32
+"}
+    );
+}
+
+#[cfg(all(feature = "schemars", feature = "flatten_dunder"))]
+#[test]
+fn test_schemars_flatten_dunder_map() {
+    #![allow(dead_code)]
+
+    use dbt_serde_yaml::JsonSchema;
+    use schemars::schema_for;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct Model {
+        name: String,
+        __rest__: HashMap<String, i32>,
+    }
+
+    let schema = schema_for!(Model);
+    let schema_string = dbt_serde_yaml::to_string(&schema).unwrap();
+    assert_eq!(
+        schema_string,
+        indoc! {"
+$schema: http://json-schema.org/draft-07/schema#
+title: Model
+type: object
+required:
+- name
+properties:
+  name:
+    type: string
+additionalProperties:
+  type: integer
+  format: int32
+"}
+    );
+}
+
+#[test]
+fn test_untagged_enum() {
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Thing {
+        a: Verbatim<i32>,
+        b: Verbatim<Option<i32>>,
         c: bool,
     }
 
@@ -989,9 +1850,22 @@ fn test_untagged_enum() {
         .to_string();
     assert_eq!(
         expected_err,
-        "data did not match any variant of untagged enum Untagged"
+        "missing field `c` (no variant of untagged enum Untagged matched; closest was Thing) at line 1 column 1"
     );
 
+    let yaml = indoc! {"
+        a: 3
+        b: four
+        c: true
+    "};
+    let value = dbt_serde_yaml::from_str::<Value>(yaml).unwrap();
+    let err = Untagged::<Thing>::deserialize(value.into_deserializer()).unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 2);
+    assert!(err
+        .to_string()
+        .starts_with("invalid type: string \"four\", expected i32"));
+    assert!(err.to_string().contains("closest was Thing"));
+
     #[derive(Deserialize)]
     struct ThingWithSpanned {
         v: Spanned<Vec<Spanned<i32>>>,
@@ -1077,6 +1951,55 @@ fn test_tagged_enum() {
     assert_eq!(tagged, Tagged::Unit);
 }
 
+#[test]
+fn test_enum_variant_rename() {
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Thing {
+        c: bool,
+    }
+
+    #[derive(UntaggedEnumDeserialize, PartialEq, Eq, Debug)]
+    #[serde(tag = "type")]
+    #[serde(rename_all = "snake_case")]
+    enum Tagged<T> {
+        #[serde(rename = "thing", alias = "object")]
+        T(T),
+        #[serde(alias = "none")]
+        Unit,
+    }
+
+    for tag in ["thing", "object"] {
+        let value = dbt_serde_yaml::from_str::<Value>(&format!("type: {tag}\nc: true")).unwrap();
+        let tagged = deserialize_value::<Tagged<Thing>>(value, |_| Ok(None)).0;
+        assert_eq!(tagged, Tagged::T(Thing { c: true }));
+    }
+
+    for tag in ["unit", "none"] {
+        let value = dbt_serde_yaml::from_str::<Value>(&format!("type: {tag}")).unwrap();
+        let tagged = deserialize_value::<Tagged<Thing>>(value, |_| Ok(None)).0;
+        assert_eq!(tagged, Tagged::Unit);
+    }
+
+    let value = dbt_serde_yaml::from_str::<Value>("type: t").unwrap();
+    let err = Tagged::<Thing>::deserialize(value.into_deserializer()).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("unknown variant `t`, expected `thing` or `unit`"));
+
+    #[derive(UntaggedEnumDeserialize, PartialEq, Eq, Debug)]
+    #[serde(untagged)]
+    enum Untagged<T> {
+        #[serde(rename(serialize = "str", deserialize = "string"))]
+        String(String),
+        #[serde(rename = "thing")]
+        Thing(T),
+    }
+
+    let value = dbt_serde_yaml::from_str::<Value>("c: 1").unwrap();
+    let err = Untagged::<Thing>::deserialize(value.into_deserializer()).unwrap_err();
+    assert!(err.to_string().contains("closest was thing"));
+}
+
 #[cfg(feature = "flatten_dunder")]
 #[test]
 fn test_untagged_enum_flatten_dunder() {
@@ -1167,3 +2090,1744 @@ fn test_untagged_enum_flatten_dunder() {
     assert_eq!(list[2], Untagged::Number(101, 102));
     assert_eq!(list[3], Untagged::String("hello".to_string()));
 }
+
+#[test]
+fn test_yaml_deserialize_derive() {
+    use dbt_serde_yaml::value::{FromValueOptions, UnusedKeys};
+    use dbt_serde_yaml::YamlDeserialize;
+
+    #[derive(Deserialize, YamlDeserialize, PartialEq, Debug)]
+    struct Model<T> {
+        name: Spanned<String>,
+        config: T,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        name: orders
+        extra: 1
+        config: {enabled: true}
+    "})
+    .unwrap();
+
+    let model =
+        Model::<HashMap<String, bool>>::from_value(value.clone(), FromValueOptions::new()).unwrap();
+    assert_eq!(*model.name, "orders");
+    assert_eq!(model.name.span().start.line, 1);
+    assert!(model.config["enabled"]);
+
+    let options = FromValueOptions::new()
+        .unused_keys(UnusedKeys::Deny)
+        .field_transformer(|v: &Value| Ok(v.as_bool().map(|b| Value::from(!b))));
+    let errors = Model::<HashMap<String, bool>>::from_value(value.clone(), options).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "extra: unknown field `extra` at line 2 column 1"
+    );
+
+    let options = FromValueOptions::new()
+        .field_transformer(|v: &Value| Ok(v.as_bool().map(|b| Value::from(!b))));
+    let model = Model::<HashMap<String, bool>>::from_value(value.clone(), options).unwrap();
+    assert!(!model.config["enabled"]);
+
+    let options = FromValueOptions::new().unused_keys(UnusedKeys::Deny);
+    let errors = Model::<u32>::from_value(value, options).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].location().unwrap().line(), 3);
+}
+
+#[test]
+fn test_private_facade() {
+    // Code generated by `UntaggedEnumDeserialize` names these items through
+    // `__private`; this pins their paths and signatures.
+    use dbt_serde_yaml::__private;
+
+    let extract: fn(Value) -> Result<__private::DeserializerState, dbt_serde_yaml::Error> =
+        __private::extract_reusable_deserializer_state::<Value>;
+    let mut state = extract(Value::string("x".to_owned())).unwrap();
+    let mut unused = vec![];
+    let mut collect_unused = |path: __private::Path<'_>, key: &__private::Value, _: &Value| {
+        unused.push((path.to_owned_path(), key.clone()));
+    };
+    let s = String::deserialize(state.get_deserializer(Some(&mut collect_unused))).unwrap();
+    assert_eq!(s, "x");
+    assert!(unused.is_empty());
+
+    type ExtractTag =
+        fn(Value, &str) -> Result<(Value, __private::DeserializerState), dbt_serde_yaml::Error>;
+    let extract_tag: ExtractTag = __private::extract_tag_and_deserializer_state::<Value>;
+    let value: Value = dbt_serde_yaml::from_str("type: a\nx: 1").unwrap();
+    let (tag, mut state) = extract_tag(value, "type").unwrap();
+    assert_eq!(tag.as_str(), Some("a"));
+    let x: HashMap<String, i32> =
+        <HashMap<String, i32> as __private::serde::Deserialize>::deserialize(
+            state.get_owned_deserializer(),
+        )
+        .unwrap();
+    assert_eq!(x["x"], 1);
+
+    let options: __private::FromValueOptions<'_> = Default::default();
+    let models: Result<i32, __private::vec::Vec<__private::Error>> =
+        Value::from(1).into_typed_with_options(options);
+    assert_eq!(models.unwrap(), 1);
+}
+
+#[test]
+fn test_lenient_variants() {
+    use dbt_serde_yaml::value::{with_lenient_variants, NormalizedVariant};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum Materialized {
+        Table,
+        View,
+        IncrementalMerge,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Ambiguous {
+        #[serde(rename = "a_b")]
+        Snake,
+        #[serde(rename = "a-b")]
+        Kebab,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        materialized: Materialized,
+    }
+
+    let yaml = indoc! {"
+        - materialized: Table
+        - materialized: view
+        - materialized: !INCREMENTAL-MERGE
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    // Matching is strict by default.
+    assert!(value
+        .to_typed::<Vec<Config>, _, _>(|_, _, _| {}, |_| Ok(None))
+        .is_err());
+
+    let expected = [
+        Config {
+            materialized: Materialized::Table,
+        },
+        Config {
+            materialized: Materialized::View,
+        },
+        Config {
+            materialized: Materialized::IncrementalMerge,
+        },
+    ];
+    let (configs, normalized) =
+        with_lenient_variants(|| value.to_typed::<Vec<Config>, _, _>(|_, _, _| {}, |_| Ok(None)));
+    assert_eq!(configs.unwrap(), expected);
+    let normalized: Vec<_> = normalized
+        .into_iter()
+        .map(
+            |NormalizedVariant {
+                 path,
+                 span,
+                 found,
+                 variant,
+             }| (path, span.start.line, found, variant),
+        )
+        .collect();
+    let expected_normalized = [
+        (
+            ".[0].materialized".to_string(),
+            1,
+            "Table".to_string(),
+            "table",
+        ),
+        (
+            ".[2].materialized".to_string(),
+            3,
+            "INCREMENTAL-MERGE".to_string(),
+            "incremental_merge",
+        ),
+    ];
+    assert_eq!(normalized, expected_normalized);
+
+    let (configs, _) = with_lenient_variants(|| {
+        value
+            .clone()
+            .into_typed::<Vec<Config>, _, _>(|_, _, _| {}, |_| Ok(None))
+    });
+    assert_eq!(configs.unwrap(), expected);
+
+    // Names matching more than one variant are not normalized.
+    let value = Value::from("A_B");
+    let (result, normalized) =
+        with_lenient_variants(|| value.to_typed::<Ambiguous, _, _>(|_, _, _| {}, |_| Ok(None)));
+    assert!(result.is_err());
+    assert!(normalized.is_empty());
+
+    // The same settings can be entered as part of a config.
+    let normalized = dbt_serde_yaml::Sink::new();
+    let mut config = dbt_serde_yaml::Config::new();
+    config.lenient_variants = true;
+    config.normalized_variants = Some(normalized.clone());
+    let _scope = config.enter();
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let configs: Vec<Config> = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+    assert_eq!(configs, expected);
+    assert_eq!(normalized.len(), 2);
+}
+
+#[test]
+fn test_owned_path() {
+    use dbt_serde_yaml::path::{OwnedPath, PathComponent};
+
+    for repr in [
+        ".",
+        "models",
+        "models[2].config.tags",
+        ".[0].name",
+        "a.?",
+        "a[0][1]",
+    ] {
+        let path: OwnedPath = repr.parse().unwrap();
+        assert_eq!(path.to_string(), repr);
+    }
+    assert_eq!(
+        "[0].name".parse::<OwnedPath>().unwrap().to_string(),
+        ".[0].name"
+    );
+    for repr in ["", "a..b", "a.", "a.[0]", "a[x]", "a[0", "a[0]b"] {
+        assert!(repr.parse::<OwnedPath>().is_err(), "{}", repr);
+    }
+
+    let path: OwnedPath = "models[2].config".parse().unwrap();
+    assert_eq!(
+        path.components().collect::<Vec<_>>(),
+        [
+            PathComponent::Key("models"),
+            PathComponent::Index(2),
+            PathComponent::Key("config"),
+        ]
+    );
+    assert_eq!(
+        path.clone(),
+        OwnedPath::Root
+            .push_key("models")
+            .push_index(2)
+            .push_key("config")
+    );
+
+    // Paths from unused key callbacks can be kept and resolved afterwards.
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+    }
+
+    let yaml = indoc! {"
+        - name: a
+        - name: b
+          descrption: typo
+    "};
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let mut unused = Vec::new();
+    value
+        .to_typed::<Vec<Model>, _, _>(
+            |path, _, _| unused.push(OwnedPath::from(path)),
+            |_| Ok(None),
+        )
+        .unwrap();
+    assert_eq!(unused.len(), 1);
+    assert_eq!(unused[0].to_string(), ".[1].descrption");
+    assert_eq!(value.get_path(unused[0].as_path()).unwrap(), "typo");
+
+    *value.get_path_mut(unused[0].as_path()).unwrap() = Value::from("fixed");
+    assert_eq!(value[1]["descrption"], "fixed");
+    assert!(value
+        .get_path(OwnedPath::Root.push_index(5).as_path())
+        .is_none());
+}
+
+#[test]
+fn test_insert_and_take_at_path() {
+    use dbt_serde_yaml::path::OwnedPath;
+
+    let path = |repr: &str| repr.parse::<OwnedPath>().unwrap();
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            config: ~
+            tags: [a]
+        seeds: !custom {}
+    "};
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let previous = value
+        .insert_at_path(
+            path("models[0].config.materialized").as_path(),
+            Value::from("table"),
+        )
+        .unwrap();
+    assert_eq!(previous, None);
+    let previous = value
+        .insert_at_path(path("models[0].name").as_path(), Value::from("customers"))
+        .unwrap();
+    assert_eq!(previous.unwrap(), "orders");
+    value
+        .insert_at_path(path("models[0].tags[1]").as_path(), Value::from("b"))
+        .unwrap();
+    value
+        .insert_at_path(path("seeds.raw.enabled").as_path(), Value::from(false))
+        .unwrap();
+    value
+        .insert_at_path(path("vars.start").as_path(), Value::from(2020))
+        .unwrap();
+
+    let expected = indoc! {"
+        models:
+        - name: customers
+          config:
+            materialized: table
+          tags:
+          - a
+          - b
+        seeds: !custom
+          raw:
+            enabled: false
+        vars:
+          start: 2020
+    "};
+    assert_eq!(dbt_serde_yaml::to_string(&value).unwrap(), expected);
+    // Keys that were replaced keep their position and span.
+    let name_span = value["models"][0].as_mapping().unwrap().span_of("name");
+    assert_eq!(name_span.unwrap().start.line(), 2);
+
+    let err = value
+        .insert_at_path(path("models[3].name").as_path(), Value::from("x"))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "no value at path models[3]");
+    let err = value
+        .insert_at_path(path("models[0].name.first").as_path(), Value::from("x"))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a mapping or sequence at path models[0].name"
+    );
+
+    let tags = value
+        .take_at_path(path("models[0].tags").as_path())
+        .unwrap();
+    assert_eq!(tags.span().start.line(), 4);
+    assert_eq!(value.take_at_path(path("models[0].tags").as_path()), None);
+    let first = value.take_at_path(path("models[0]").as_path()).unwrap();
+    assert_eq!(first["name"], "customers");
+    assert_eq!(value["models"].as_sequence().unwrap().len(), 0);
+    assert_eq!(
+        value.take_at_path(path("seeds.raw.enabled.x").as_path()),
+        None
+    );
+
+    let whole = value.take_at_path(path(".").as_path()).unwrap();
+    assert!(value.is_null());
+    assert_eq!(whole["vars"]["start"], 2020);
+}
+
+#[test]
+fn test_transformed_inside_verbatim() {
+    use dbt_serde_yaml::Transformed;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Defaults {
+        threads: Transformed<i64>,
+        retries: Option<Transformed<i64>>,
+        query: Value,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        sql: String,
+        defaults: Defaults,
+        post_hook: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Model {
+        name: String,
+        config: Verbatim<Config>,
+    }
+
+    let yaml = indoc! {"
+        name: '{{ name }}'
+        config:
+          sql: '{{ sql }}'
+          defaults:
+            threads: '{{ threads }}'
+            retries: '{{ retries }}'
+            query: '{{ query }}'
+          post_hook: ['{{ hook }}']
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let transformer = |v: &Value| -> TransformedResult {
+        Ok(match v.as_str() {
+            Some("{{ threads }}") => Some(Value::from(4)),
+            Some("{{ retries }}") => Some(Value::from(2)),
+            Some(s) => Some(Value::from(s.trim_matches(['{', '}', ' ']))),
+            None => None,
+        })
+    };
+
+    let model: Model = value.to_typed(|_, _, _| {}, transformer).unwrap();
+    assert_eq!(model.name, "name");
+    assert_eq!(model.config.sql, "{{ sql }}");
+    assert_eq!(*model.config.defaults.threads, 4);
+    assert_eq!(model.config.defaults.retries.as_deref(), Some(&2));
+    assert_eq!(model.config.defaults.query, "{{ query }}");
+    assert_eq!(model.config.post_hook, ["{{ hook }}"]);
+
+    let owned: Model = value.into_typed(|_, _, _| {}, transformer).unwrap();
+    assert_eq!(owned, model);
+}
+
+#[cfg(feature = "flatten_dunder")]
+#[test]
+fn test_verbatim_in_flatten_dunder() {
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Meta {
+        owner: Verbatim<Option<String>>,
+        __meta_rest__: HashMap<String, Verbatim<Value>>,
+    }
+
+    #[derive(Deserialize, PartialEq, Eq, Debug)]
+    struct Model {
+        name: String,
+        __meta__: Meta,
+        __rest__: HashMap<String, Verbatim<Value>>,
+    }
+
+    let value = dbt_serde_yaml::from_str::<Value>(indoc! {"
+        name: '{{ name }}'
+        owner: '{{ owner }}'
+        sql: '{{ sql }}'
+    "})
+    .unwrap();
+    let (model, unused_keys) = deserialize_value::<Model>(value, |v| {
+        Ok(v.as_str()
+            .map(|s| Value::from(s.trim_matches(['{', '}', ' ']))))
+    });
+    assert!(unused_keys.is_empty(), "{:?}", unused_keys);
+    assert_eq!(model.name, "name");
+    assert_eq!(model.__meta__.owner.as_deref(), Some("{{ owner }}"));
+    assert_eq!(*model.__meta__.__meta_rest__["sql"], "{{ sql }}");
+    assert!(model.__rest__.is_empty());
+}
+
+#[test]
+fn test_callback_panic() {
+    use dbt_serde_yaml::Spanned;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: Spanned<String>,
+        tags: Vec<String>,
+    }
+
+    let yaml = indoc! {"
+        - name: a
+          tags: [x]
+          typo: 1
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let error = value
+        .to_typed::<Vec<Model>, _, _>(|_, _, _| panic!("unused key"), |_| Ok(None))
+        .unwrap_err();
+    assert!(error.is_callback_panic());
+    assert_eq!(error.path().unwrap().to_string(), ".[0].typo");
+    assert_eq!(
+        error.to_string(),
+        ".[0].typo: callback panicked: unused key at line 3 column 9"
+    );
+
+    let error = value
+        .clone()
+        .into_typed::<Vec<Model>, _, _>(
+            |_, _, _| {},
+            |v| {
+                if v.as_str() == Some("x") {
+                    panic!("transformer for {:?}", v.as_str().unwrap());
+                }
+                Ok(None)
+            },
+        )
+        .unwrap_err();
+    assert!(error.is_callback_panic());
+    assert_eq!(error.path().unwrap().to_string(), ".[0].tags[0]");
+    assert!(error.to_string().contains("transformer for \"x\""));
+
+    let error = Value::from_str("a: 1\na: 2\n", |_, _, _| panic!("duplicate")).unwrap_err();
+    assert!(error.to_string().contains("callback panicked: duplicate"));
+
+    // The thread-local state is intact for subsequent deserializations.
+    let models: Vec<Model> = value
+        .to_typed(
+            |_, _, _| {},
+            |v| Ok(v.as_str().map(|s| Value::from(s.to_uppercase()))),
+        )
+        .unwrap();
+    assert_eq!(*models[0].name, "A");
+    assert_eq!(models[0].name.span().start.line, 1);
+    assert_eq!(models[0].tags, ["X"]);
+    let models: Vec<Model> = dbt_serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(models[0].name.span().start.line, 1);
+}
+
+#[test]
+fn test_callback_error() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+    }
+
+    let yaml = indoc! {"
+        - name: a
+          typo: 1
+        - name: b
+          other: 2
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let mut calls = 0;
+    let error = value
+        .try_to_typed::<Vec<Model>, _, _>(
+            |path, _, _| {
+                calls += 1;
+                Err(format!("unexpected key at {}", path).into())
+            },
+            |_| Ok(None),
+        )
+        .unwrap_err();
+    assert_eq!(calls, 1);
+    assert!(!error.is_callback_panic());
+    assert_eq!(error.path().unwrap().to_string(), ".[0].typo");
+    assert_eq!(
+        error.to_string(),
+        ".[0].typo: unexpected key at .[0].typo at line 2 column 9"
+    );
+    assert_eq!(
+        error.into_external().unwrap().to_string(),
+        "unexpected key at .[0].typo"
+    );
+
+    let models: Vec<Model> = value
+        .clone()
+        .try_into_typed(|_, _, _| Ok(()), |_| Ok(None))
+        .unwrap();
+    assert_eq!(models.len(), 2);
+
+    let error =
+        Value::try_from_str("a: 1\na: 2\n", |_, _, _| Err("no duplicates".into())).unwrap_err();
+    assert_eq!(error.to_string(), "a: no duplicates at line 2 column 1");
+    let value = Value::try_from_str("a: 1\na: 2\n", |_, _, _| Ok(DuplicateKey::Overwrite)).unwrap();
+    assert_eq!(value["a"], 2);
+
+    // The failure does not leak into subsequent deserializations.
+    let models: Vec<Model> = value_with_unused()
+        .to_typed(|_, _, _| {}, |_| Ok(None))
+        .unwrap();
+    assert_eq!(models.len(), 1);
+
+    fn value_with_unused() -> Value {
+        dbt_serde_yaml::from_str("- name: c\n  extra: 3\n").unwrap()
+    }
+}
+
+#[test]
+fn test_callback_recursion_limit() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+    }
+
+    // Each name is replaced by deserializing another model with the same
+    // transformer, without end.
+    fn transformer(value: &Value) -> TransformedResult {
+        if value.is_string() {
+            let model: Value = dbt_serde_yaml::from_str("name: a").unwrap();
+            model.into_typed::<Model, _, _>(|_, _, _| {}, transformer)?;
+        }
+        Ok(None)
+    }
+
+    let value: Value = dbt_serde_yaml::from_str("name: a").unwrap();
+    let error = value
+        .to_typed::<Model, _, _>(|_, _, _| {}, transformer)
+        .unwrap_err();
+    assert!(!error.is_callback_panic());
+    assert!(error
+        .to_string()
+        .contains("callback recursion limit exceeded"));
+
+    // The limit is per thread and does not outlive the callbacks.
+    let model: Model = value.into_typed(|_, _, _| {}, |_| Ok(None)).unwrap();
+    assert_eq!(model.name, "a");
+}
+
+#[test]
+fn test_untagged_enum_callbacks() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Model {
+        name: String,
+    }
+
+    #[derive(UntaggedEnumDeserialize, Debug)]
+    #[serde(untagged)]
+    #[allow(dead_code)]
+    enum Node {
+        Model(Model),
+        Name(String),
+    }
+
+    let yaml = indoc! {"
+        - name: a
+          extra: 1
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let error = value
+        .try_to_typed::<Vec<Node>, _, _>(
+            |path, _, _| Err(format!("unexpected key at {}", path).into()),
+            |_| Ok(None),
+        )
+        .unwrap_err();
+    assert!(!error.is_callback_panic());
+    assert_eq!(
+        error.to_string(),
+        ".[0].extra: unexpected key at .[0].extra at line 2 column 10"
+    );
+
+    let error = value
+        .to_typed::<Vec<Node>, _, _>(|_, _, _| panic!("unused key"), |_| Ok(None))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        ".[0].extra: callback panicked: unused key at line 2 column 10"
+    );
+
+    // Neither leaks into subsequent deserializations.
+    let mut unused = vec![];
+    let models: Vec<Model> = value
+        .into_typed(|path, _, _| unused.push(path.to_string()), |_| Ok(None))
+        .unwrap();
+    assert_eq!(models.len(), 1);
+    assert_eq!(unused, [".[0].extra"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_from_json() {
+    let source = "{\n  \"models\": [\"orders\", 1.5],\n  \"é\": null\n}";
+    let json: serde_json::Value = serde_json::from_str(source).unwrap();
+
+    let value = Value::from(json.clone());
+    assert_eq!(value["models"][0], "orders");
+    assert_eq!(value["models"][1], 1.5);
+    assert!(value["é"].is_null());
+    assert!(!value["models"].span().is_valid());
+
+    let value =
+        Value::from_json_with_offsets(json, source, |path| match path.to_string().as_str() {
+            "." => Some(0..source.len()),
+            "models" => Some(14..29),
+            "models[0]" => Some(15..23),
+            "é" => Some(39..43),
+            _ => None,
+        });
+    let position = |value: &Value| {
+        let span = value.span();
+        (
+            span.start.line,
+            span.start.column,
+            span.end.line,
+            span.end.column,
+        )
+    };
+    assert_eq!(position(&value), (1, 1, 4, 2));
+    assert_eq!(position(&value["models"]), (2, 13, 2, 28));
+    assert_eq!(position(&value["models"][0]), (2, 14, 2, 22));
+    assert_eq!(position(&value["é"]), (3, 8, 3, 12));
+    assert!(!value["models"][1].span().is_valid());
+
+    let keys: Vec<_> = value.as_mapping().unwrap().keys().map(position).collect();
+    assert_eq!(keys, [(2, 13, 2, 28), (3, 8, 3, 12)]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_to_json() {
+    use serde_json::json;
+
+    let yaml = indoc! {"
+        name: orders
+        version: 2
+        ratio: -0.5
+        big: 18446744073709551615
+        tags: [a, ~, true]
+        materialized: !Incremental {unique_key: id}
+        1: one
+        ~: none
+        false: no
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let json = serde_json::Value::try_from(&value).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "name": "orders",
+            "version": 2,
+            "ratio": -0.5,
+            "big": 18446744073709551615u64,
+            "tags": ["a", null, true],
+            "materialized": {"Incremental": {"unique_key": "id"}},
+            "1": "one",
+            "null": "none",
+            "false": "no",
+        })
+    );
+    assert_eq!(
+        Value::from(json.clone())["materialized"]["Incremental"]["unique_key"],
+        "id"
+    );
+    assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+
+    let to_json = |yaml: &str| {
+        let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+        serde_json::Value::try_from(value).unwrap_err().to_string()
+    };
+    assert_eq!(
+        to_json("a:\n  [1, 2]: x\n"),
+        "invalid type: sequence, expected a scalar mapping key at line 2 column 3"
+    );
+    assert_eq!(
+        to_json("1: a\n'1': b\n"),
+        "duplicate entry with key \"1\" at line 2 column 1"
+    );
+    assert_eq!(
+        to_json("- .nan\n"),
+        "invalid value: floating point `NaN`, expected a finite number at line 1 column 3"
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml() {
+    let source = indoc! {r#"
+        name = "jaffle_shop"
+        version = 2
+        [models]
+        "+materialized" = "table"
+        threads = 1.5
+        created = 1979-05-27T07:32:00Z
+        tags = ["a", "b"]
+    "#};
+    let toml: toml::Value = source.parse().unwrap();
+    let value = Value::from(toml.clone());
+    assert_eq!(value["name"], "jaffle_shop");
+    assert_eq!(value["version"], 2);
+    assert_eq!(value["models"]["+materialized"], "table");
+    assert_eq!(value["models"]["threads"], 1.5);
+    assert_eq!(value["models"]["created"], "1979-05-27T07:32:00Z");
+    assert_eq!(value["models"]["tags"][1], "b");
+
+    let yaml = indoc! {"
+        name: jaffle_shop
+        version: 2
+        models:
+          +materialized: table
+          threads: 1.5
+          tags: [a, b]
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let converted: toml::Value = (&value).try_into().unwrap();
+    let mut expected = toml.clone();
+    expected["models"].as_table_mut().unwrap().remove("created");
+    assert_eq!(converted, expected);
+
+    let to_toml = |yaml: &str| {
+        let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+        let result: Result<toml::Value, _> = value.try_into();
+        result.unwrap_err().to_string()
+    };
+    assert_eq!(
+        to_toml("models:\n  description: ~\n"),
+        "TOML has no null value at line 2 column 16"
+    );
+    assert_eq!(
+        to_toml("materialized: !Incremental id\n"),
+        "TOML has no tagged values like `!Incremental` at line 1 column 15"
+    );
+    assert_eq!(
+        to_toml("1: one\n"),
+        "invalid type: integer `1`, expected a string key at line 1 column 1"
+    );
+    assert_eq!(
+        to_toml("- 18446744073709551615\n"),
+        "invalid value: integer `18446744073709551615`, expected a 64-bit signed integer at line 1 column 3"
+    );
+}
+
+#[test]
+fn test_walk() {
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            tags: [a, b]
+          - !tagged
+            name: customers
+        1: one
+    "};
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let mut visited = Vec::new();
+    value.walk(|path, node| visited.push((path.to_string(), node.span().start.line())));
+    assert_eq!(
+        visited,
+        [
+            (".".to_string(), 1),
+            ("models".to_string(), 2),
+            ("models[0]".to_string(), 2),
+            ("models[0].name".to_string(), 2),
+            ("models[0].tags".to_string(), 3),
+            ("models[0].tags[0]".to_string(), 3),
+            ("models[0].tags[1]".to_string(), 3),
+            ("models[1]".to_string(), 4),
+            ("models[1].name".to_string(), 5),
+            ("?".to_string(), 6),
+        ]
+    );
+
+    value.walk_mut(|path, node| {
+        if let Some(s) = node.as_str() {
+            *node = Value::from(format!("{}={}", path, s));
+        } else if path.to_string() == "models[0]" {
+            node["tags"] = Value::from(vec!["c"]);
+        }
+    });
+    assert_eq!(value["models"][0]["name"], "models[0].name=orders");
+    assert_eq!(value["models"][0]["tags"][0], "models[0].tags[0]=c");
+    assert!(value["models"][0]["tags"].get(1).is_none());
+}
+
+#[cfg(feature = "intern")]
+#[test]
+fn test_interned_keys() {
+    let yaml = indoc! {"
+        - name: orders
+          description: Orders
+        - name: customers
+          description: Customers
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let seq = value.as_sequence().unwrap();
+    let key = |i: usize| match seq[i].as_mapping().unwrap().keys().next().unwrap() {
+        Value::String(key, ..) => key.clone(),
+        other => panic!("expected a string key, got {:?}", other),
+    };
+    assert_eq!(key(0), "name");
+    assert_eq!(key(0).as_ptr(), key(1).as_ptr());
+
+    let names: Vec<&str> = seq.iter().map(|v| v["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["orders", "customers"]);
+    assert_ne!(seq[0]["name"], seq[1]["name"]);
+}
+
+#[test]
+fn test_reparse() {
+    use dbt_serde_yaml::value::SourceEdit;
+
+    fn spans(value: &Value, out: &mut Vec<String>) {
+        out.push(format!("{:?}", value.span()));
+        match value {
+            Value::Sequence(seq, ..) => seq.iter().for_each(|v| spans(v, out)),
+            Value::Mapping(map, ..) => map.iter().for_each(|(k, v)| {
+                spans(k, out);
+                spans(v, out);
+            }),
+            _ => {}
+        }
+    }
+
+    fn check(old: &str, range: std::ops::Range<usize>, replacement: &str, expected: &str) {
+        let mut new = old.to_owned();
+        new.replace_range(range.clone(), replacement);
+        let mut value: Value = dbt_serde_yaml::from_str(old).unwrap();
+        let edit = SourceEdit {
+            range,
+            new_len: replacement.len(),
+        };
+        let path = value.reparse(&new, &edit).unwrap();
+        assert_eq!(path.to_string(), expected);
+
+        let full: Value = dbt_serde_yaml::from_str(&new).unwrap();
+        assert_eq!(value, full);
+        let (mut got, mut want) = (Vec::new(), Vec::new());
+        spans(&value, &mut got);
+        spans(&full, &mut want);
+        assert_eq!(got, want);
+    }
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            columns:
+              - name: id
+              - name: status
+          - name: customers
+        version: 2
+    "};
+    check(yaml, 69..75, "order_status", "models[0].columns[1].name");
+    check(
+        yaml,
+        52..54,
+        "id\n      - name: amount",
+        "models[0].columns",
+    );
+    check(yaml, 69..75, "status\n    tests: [unique]", "models[0]");
+    check(yaml, 105..106, "3", "version");
+    // Anchors can't be resolved in part of the document, so the whole of it
+    // is parsed again.
+    check(yaml, 18..24, "&orders orders", ".");
+
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let edit = SourceEdit {
+        range: 18..24,
+        new_len: 1,
+    };
+    let broken = yaml.replacen("orders", "[", 1);
+    assert!(value.reparse(&broken, &edit).is_err());
+    assert_eq!(value, dbt_serde_yaml::from_str::<Value>(yaml).unwrap());
+}
+
+#[test]
+fn test_source_edits() {
+    use dbt_serde_yaml::value::TextEdit;
+
+    fn check(source: &str, edit: impl FnOnce(&mut Value), expected: &str) {
+        let old: Value = dbt_serde_yaml::from_str(source).unwrap();
+        let mut new = old.clone();
+        edit(&mut new);
+        let edits = old.source_edits(source, &new).unwrap();
+        let text = TextEdit::apply(source, &edits);
+        assert_eq!(text, expected);
+        assert_eq!(dbt_serde_yaml::from_str::<Value>(&text).unwrap(), new);
+    }
+
+    let yaml = indoc! {"
+        # Models of the shop.
+        models:
+          - name: orders  # facts
+            columns:
+              - id
+              - status
+            config: {materialized: view}
+          - name: customers
+        version: 2
+    "};
+    check(
+        yaml,
+        |value| {
+            let model = &mut value["models"][0];
+            model["name"] = "order_items".into();
+            model["config"]["materialized"] = "table".into();
+            let columns = model["columns"].as_sequence_mut().unwrap();
+            columns.insert(1, "amount".into());
+            columns.remove(2);
+            value["models"].as_sequence_mut().unwrap().remove(1);
+            value["version"] = Value::null();
+        },
+        indoc! {"
+            # Models of the shop.
+            models:
+              - name: order_items  # facts
+                columns:
+                  - id
+                  - amount
+                config: {materialized: table}
+            version: null
+        "},
+    );
+    check(
+        yaml,
+        |value| {
+            let model = value["models"][1].as_mapping_mut().unwrap();
+            model.remove("name");
+            model.insert(
+                "tests".into(),
+                dbt_serde_yaml::from_str("[unique]").unwrap(),
+            );
+            model.insert("description".into(), "A customer.\nOne per row.".into());
+            let root = value.as_mapping_mut().unwrap();
+            root.remove("version");
+            root.insert(
+                "seeds".into(),
+                dbt_serde_yaml::from_str("- name: raw").unwrap(),
+            );
+        },
+        indoc! {"
+            # Models of the shop.
+            models:
+              - name: orders  # facts
+                columns:
+                  - id
+                  - status
+                config: {materialized: view}
+              - tests:
+                - unique
+                description: |-
+                  A customer.
+                  One per row.
+            seeds:
+              - name: raw
+        "},
+    );
+    check(
+        "- a: 1\n  b: 2\n- - x\n",
+        |value| {
+            value[0].as_mapping_mut().unwrap().remove("a");
+            value[1].as_sequence_mut().unwrap().insert(0, "w".into());
+        },
+        "- b: 2\n- - w\n  - x\n",
+    );
+
+    // A comment after a value that is rewritten over several lines stays on
+    // its first line, out of any block scalar.
+    check(
+        "sql: select 1  # inline\nname: x # named\n",
+        |value| {
+            value["sql"] = "select 1\nfrom t".into();
+            value["name"] = dbt_serde_yaml::from_str("{a: 1, b: 2}").unwrap();
+        },
+        "sql: |-  # inline\n  select 1\n  from t\nname: # named\n  a: 1\n  b: 2\n",
+    );
+    check(
+        "- x  # first\n",
+        |value| value[0] = dbt_serde_yaml::from_str("{sql: \"a\\nb\"}").unwrap(),
+        "- sql: |-  # first\n    a\n    b\n",
+    );
+
+    let old: Value = dbt_serde_yaml::from_str("a: &x 1\nb: *x\n").unwrap();
+    let new = Value::null();
+    assert!(old.source_edits("a: &x 1\nb: *x\n", &new).is_err());
+}
+
+#[test]
+fn test_diff() {
+    use dbt_serde_yaml::value::ValueChange;
+
+    let old: Value = dbt_serde_yaml::from_str(indoc! {"
+        models:
+          - name: orders
+            config: {materialized: view}
+            columns: [id, status, amount]
+          - name: customers
+        vars: !env {target: dev}
+        seeds: {1: a}
+    "})
+    .unwrap();
+    let new: Value = dbt_serde_yaml::from_str(indoc! {"
+        models:
+          - name: orders
+            config: {materialized: table, schema: marts}
+            columns: [id]
+          - name: customers
+          - name: payments
+        vars: !env {target: prod}
+        seeds: {1: b}
+    "})
+    .unwrap();
+
+    let patch = old.diff(&new);
+    let changes: Vec<String> = patch
+        .changes()
+        .iter()
+        .map(|change| match change {
+            ValueChange::Added { path, value } => {
+                format!("+{}@{}", path, value.span().start.line())
+            }
+            ValueChange::Removed { path, value } => {
+                format!("-{}@{}", path, value.span().start.line())
+            }
+            ValueChange::Changed { path, old, new } => format!(
+                "~{}@{}:{}",
+                path,
+                old.span().start.line(),
+                new.span().start.line()
+            ),
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "~models[0].config.materialized@3:3",
+            "+models[0].config.schema@3",
+            "-models[0].columns[2]@4",
+            "-models[0].columns[1]@4",
+            "+models[2]@6",
+            "~vars.target@6:7",
+            "~seeds@7:8",
+        ],
+    );
+
+    let mut patched = old.clone();
+    patched.apply_patch(&patch).unwrap();
+    assert_eq!(patched, new);
+    assert!(patched.diff(&new).is_empty());
+
+    let mut other: Value = dbt_serde_yaml::from_str("models: []").unwrap();
+    let err = other.apply_patch(&patch).unwrap_err();
+    assert_eq!(err.to_string(), "no value at path models[0]");
+}
+
+#[test]
+fn test_yaml_macro() {
+    use dbt_serde_yaml::yaml;
+
+    assert_eq!(yaml!(null), Value::null());
+    assert_eq!(yaml!([]), Value::sequence(Vec::new()));
+    assert_eq!(yaml!({}), Value::mapping(Mapping::new()));
+    assert_eq!(yaml!(-1), Value::number(Number::from(-1)));
+
+    let name = String::from("orders");
+    let value = yaml!({
+        "version": 2,
+        "models": [
+            {
+                "name": name,
+                "config": {"enabled": false, "tags": [], "meta": {}},
+                "columns": [{"name": "id"}, {"name": "amount", "precision": 2.5}]
+            }
+        ],
+        -1: !Ref [null, true],
+        (1 + 1): !("custom") {"a": !Sql (-1)}
+    });
+    let expected: Value = dbt_serde_yaml::from_str(indoc! {"
+        version: 2
+        models:
+          - name: orders
+            config: {enabled: false, tags: [], meta: {}}
+            columns:
+              - name: id
+              - {name: amount, precision: 2.5}
+        -1: !Ref [null, true]
+        2: !custom {a: !Sql -1}
+    "})
+    .unwrap();
+    assert_eq!(value, expected);
+    assert_eq!(*value.span(), dbt_serde_yaml::Span::default());
+}
+
+#[test]
+fn test_duplicate_keys_keep_all() {
+    use dbt_serde_yaml::mapping::DuplicateKey;
+    use dbt_serde_yaml::Marker;
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            name: order_items
+            config: {enabled: true, enabled: false}
+        models: []
+    "};
+    let value = Value::from_str(yaml, |_, _, _| DuplicateKey::KeepAll).unwrap();
+    let root = value.as_mapping().unwrap();
+    let model = value["models"][0].as_mapping().unwrap();
+    assert_eq!(model["name"], "orders");
+    assert_eq!(model["config"]["enabled"], true);
+
+    let spans = |mapping: &Mapping| {
+        mapping
+            .duplicates()
+            .iter()
+            .map(|(key, value)| (key.span().start, value.span().start))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        spans(root),
+        [(Marker::new(91, 5, 1), Marker::new(99, 5, 9))],
+    );
+    assert_eq!(
+        spans(model),
+        [(Marker::new(29, 3, 5), Marker::new(35, 3, 11))],
+    );
+    assert_eq!(spans(model["config"].as_mapping().unwrap()).len(), 1);
+
+    // Duplicates are set aside without changing what the mapping compares
+    // equal to.
+    let first = Value::from_str(yaml, |_, _, _| DuplicateKey::Ignore).unwrap();
+    assert_eq!(value, first);
+    assert!(first.as_mapping().unwrap().duplicates().is_empty());
+
+    // The same goes for mappings deserialized through serde.
+    let mut config = dbt_serde_yaml::Config::new();
+    config.duplicate_keys = DuplicateKey::KeepAll;
+    let mut mapping: Mapping = dbt_serde_yaml::from_str_with_config(yaml, &config).unwrap();
+    assert_eq!(mapping["models"].as_sequence().unwrap().len(), 1);
+    let duplicates = mapping.take_duplicates();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].0, "models");
+    assert!(mapping.duplicates().is_empty());
+}
+
+#[test]
+fn test_get_typed_at() {
+    use dbt_serde_yaml::path::OwnedPath;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        materialized: String,
+    }
+
+    let value: Value = dbt_serde_yaml::from_str(indoc! {"
+        models:
+          - name: orders
+            config: !Override
+              materialized: table
+    "})
+    .unwrap();
+
+    let path: OwnedPath = "models[0].config".parse().unwrap();
+    let config: Config = value.get_typed_at(path.as_path()).unwrap();
+    assert_eq!(config.materialized, "table");
+
+    let path: OwnedPath = "models[0].name".parse().unwrap();
+    let name: &str = value.get_typed_at(path.as_path()).unwrap();
+    assert_eq!(name, "orders");
+    let err = value.get_typed_at::<Config>(path.as_path()).unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "models[0].name");
+    assert_eq!(err.location().unwrap().line(), 2);
+
+    let path: OwnedPath = "models[1]".parse().unwrap();
+    let err = value.get_typed_at::<Config>(path.as_path()).unwrap_err();
+    assert_eq!(err.to_string(), "no value at path models[1]");
+}
+
+#[test]
+fn test_scalar_info() {
+    use dbt_serde_yaml::event::ScalarStyle;
+
+    let source = indoc! {r#"
+        plain: two words
+        single: 'it''s'
+        double: &anchor "café é"
+        tagged: !Ref
+          # the model
+          'orders'
+        literal: |-
+          first
+          second
+
+        folded: >
+          text
+        empty:
+        alias: *anchor
+        seq: [a]
+    "#};
+    let value: Value = dbt_serde_yaml::from_str(source).unwrap();
+    let info = |key: &str| value[key].scalar_info(source);
+    let text = |range: std::ops::Range<usize>| &source[range];
+
+    let plain = info("plain").unwrap();
+    assert_eq!(plain.style, ScalarStyle::Plain);
+    assert_eq!(text(plain.range.clone()), "two words");
+    assert_eq!(plain.content, plain.range);
+
+    let single = info("single").unwrap();
+    assert_eq!(single.style, ScalarStyle::SingleQuoted);
+    assert_eq!(text(single.range), "'it''s'");
+    assert_eq!(text(single.content), "it''s");
+
+    let double = info("double").unwrap();
+    assert_eq!(double.style, ScalarStyle::DoubleQuoted);
+    assert_eq!(text(double.range), r#""café é""#);
+    assert_eq!(text(double.content), r"café é");
+
+    let tagged = info("tagged").unwrap();
+    assert_eq!(tagged.style, ScalarStyle::SingleQuoted);
+    assert_eq!(text(tagged.content), "orders");
+
+    let literal = info("literal").unwrap();
+    assert_eq!(literal.style, ScalarStyle::Literal);
+    assert_eq!(text(literal.range), "|-\n  first\n  second");
+    assert_eq!(text(literal.content), "  first\n  second");
+
+    let folded = info("folded").unwrap();
+    assert_eq!(folded.style, ScalarStyle::Folded);
+    assert_eq!(text(folded.content), "  text");
+
+    let empty = info("empty").unwrap();
+    assert_eq!(empty.style, ScalarStyle::Plain);
+    assert!(empty.range.is_empty());
+
+    assert_eq!(info("alias"), None);
+    assert_eq!(info("seq"), None);
+    let key = value.as_mapping().unwrap().keys().next().unwrap();
+    assert_eq!(text(key.scalar_info(source).unwrap().range), "plain");
+    assert_eq!(
+        value["seq"][0].scalar_info(source).unwrap().style,
+        ScalarStyle::Plain
+    );
+
+    // A value that doesn't come from `source`.
+    assert_eq!(Value::from("plain").scalar_info(source), None);
+    assert_eq!(value["plain"].scalar_info("plain: other words\n"), None);
+
+    // The root of a document starting with a comment.
+    let source = "# comment\n--- \"root\"\n";
+    let value: Value = dbt_serde_yaml::from_str(source).unwrap();
+    let root = value.scalar_info(source).unwrap();
+    assert_eq!(&source[root.content], "root");
+}
+
+#[test]
+fn test_transform_library() {
+    use dbt_serde_yaml::value::transform;
+
+    let lookup = |name: &str| match name {
+        "TARGET" => Some("prod".to_owned()),
+        "THREADS" => Some("4".to_owned()),
+        _ => None,
+    };
+    let mut resolve = transform::resolve_env_with(lookup);
+    let mut resolve = |s: &str| resolve(&Value::from(s)).unwrap();
+
+    assert_eq!(resolve("${TARGET}"), Some(Value::from("prod")));
+    assert_eq!(
+        resolve("db_${TARGET}_${THREADS}"),
+        Some(Value::from("db_prod_4"))
+    );
+    assert_eq!(
+        resolve("{{ env_var('TARGET') }}/{{env_var(\"MISSING\", \"dev\")}}"),
+        Some(Value::from("prod/dev")),
+    );
+    assert_eq!(
+        resolve("{{ env_var('MISSING', '') }}"),
+        Some(Value::from(""))
+    );
+    // Anything that is not a variable reference is kept as is.
+    assert_eq!(resolve("{{ var('TARGET') }}"), None);
+    assert_eq!(
+        resolve("$TARGET ${} ${not-a-name} {{ env_var(TARGET) }}"),
+        None
+    );
+    assert_eq!(resolve("{{ env_var('TARGET'"), None);
+    assert_eq!(resolve("plain text"), None);
+
+    let mut resolve = transform::resolve_env_with(lookup);
+    assert_eq!(resolve(&Value::from(1)).unwrap(), None);
+    let err = resolve(&Value::from("{{ env_var('MISSING') }}")).unwrap_err();
+    assert_eq!(err.to_string(), "environment variable `MISSING` is not set");
+
+    let mut expand = transform::expand_home_with("/home/dbt/");
+    assert_eq!(
+        expand(&Value::from("~")).unwrap(),
+        Some(Value::from("/home/dbt"))
+    );
+    assert_eq!(
+        expand(&Value::from("~/profiles.yml")).unwrap(),
+        Some(Value::from("/home/dbt/profiles.yml")),
+    );
+    assert_eq!(expand(&Value::from("~other/profiles.yml")).unwrap(), None);
+    assert_eq!(expand(&Value::from("a/~/b")).unwrap(), None);
+
+    assert_eq!(
+        transform::coerce_bool(&Value::from("Yes")).unwrap(),
+        Some(Value::from(true))
+    );
+    assert_eq!(
+        transform::coerce_bool(&Value::from("OFF")).unwrap(),
+        Some(Value::from(false))
+    );
+    assert_eq!(transform::coerce_bool(&Value::from("y")).unwrap(), None);
+    assert_eq!(transform::coerce_bool(&Value::from(1)).unwrap(), None);
+
+    // Replacements keep the span of the original value, and transformers
+    // compose.
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        schema: dbt_serde_yaml::Spanned<String>,
+        enabled: bool,
+        path: String,
+    }
+    let yaml = indoc! {"
+        schema: analytics_${TARGET}
+        enabled: \"{{ env_var('ENABLED', 'true') }}\"
+        path: ~/target
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let transformer = transform::chain(
+        transform::resolve_env_with(lookup),
+        transform::chain(transform::coerce_bool, transform::expand_home_with("/root")),
+    );
+    let config: Config = value.into_typed(|_, _, _| {}, transformer).unwrap();
+    assert_eq!(*config.schema, "analytics_prod");
+    assert_eq!(config.schema.span().start.line, 1);
+    assert_eq!(config.schema.span().start.column, 9);
+    assert!(config.enabled);
+    assert_eq!(config.path, "/root/target");
+
+    assert_eq!(Value::from("no variables").resolve_env().unwrap(), None);
+}
+
+#[test]
+fn test_transform_and_unused_key_combinators() {
+    use dbt_serde_yaml::path::{OwnedPath, PathPattern};
+    use dbt_serde_yaml::value::{transform, unused_keys, UnusedKeyCollector};
+
+    let matches = |pattern: &str, path: &str| {
+        let pattern: PathPattern = pattern.parse().unwrap();
+        let path: OwnedPath = path.parse().unwrap();
+        pattern.matches(path.as_path())
+    };
+    assert!(matches(".", "."));
+    assert!(matches("**", "."));
+    assert!(matches("**", "a[1].b"));
+    assert!(matches("a.**", "a"));
+    assert!(matches("a[*].b", "a[7].b"));
+    assert!(matches("a[7].b", "a[7].b"));
+    assert!(!matches("a[6].b", "a[7].b"));
+    assert!(!matches("a[*].b", "a.x.b"));
+    assert!(matches("a.*.b", "a.x.b"));
+    assert!(!matches("a.*.b", "a[0].b"));
+    assert!(matches("**.b", "a.x[2].b"));
+    assert!(!matches("**.b", "a.b.c"));
+    assert!(matches("a.?", "a.?"));
+    for invalid in ["", "a..b", "a.", "a[x]", "a[*", "a.[0]"] {
+        assert!(invalid.parse::<PathPattern>().is_err(), "{}", invalid);
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Model {
+        name: String,
+        materialized: String,
+        columns: Vec<Column>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Column {
+        name: String,
+    }
+    let yaml = indoc! {"
+        name: orders
+        materialized: '{{ upper }}'
+        x-owner: data
+        columns:
+          - name: '{{ upper }}'
+            meta: {}
+          - name: id
+            descrption: typo
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    let upper = |v: &Value| {
+        Ok(v.as_str()
+            .filter(|s| *s == "{{ upper }}")
+            .map(|_| Value::from("UPPER")))
+    };
+    let mut seen = Vec::new();
+    let record = |v: &Value| {
+        seen.push(v.clone());
+        Ok(None)
+    };
+    let transformer = transform::chain(
+        transform::at_path("columns[*].name", upper),
+        transform::at_path("name", record),
+    );
+    let mut unused = UnusedKeyCollector::new();
+    let callback = unused_keys::ignore_paths(["x-*", "x-owner", "**.meta"], unused.collect());
+    let model: Model = value.into_typed(callback, transformer).unwrap();
+    assert_eq!(model.name, "orders");
+    assert_eq!(model.materialized, "{{ upper }}");
+    assert_eq!(model.columns[0].name, "UPPER");
+    assert_eq!(model.columns[1].name, "id");
+    assert_eq!(seen, [Value::from("orders")]);
+
+    let unused: Vec<String> = unused.iter().map(|(path, ..)| path.to_string()).collect();
+    assert_eq!(unused, ["columns[1].descrption"]);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn test_assert_yaml_eq() {
+    use dbt_serde_yaml::assert_yaml_eq;
+    use std::panic;
+
+    let left: Value = dbt_serde_yaml::from_str(indoc! {"
+        models:
+          - name: orders
+            tags: [nightly, finance]
+    "})
+    .unwrap();
+    let right: Value = dbt_serde_yaml::from_str(indoc! {"
+        models:
+          - name: orders
+            tags: [nightly]
+            config: {}
+    "})
+    .unwrap();
+
+    assert_yaml_eq!(left, left.clone());
+    assert_yaml_eq!(&right, right.clone(), "with a message");
+
+    let payload = panic::catch_unwind(|| assert_yaml_eq!(left, right, "models[{}]", 0));
+    let payload = payload.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert_eq!(
+        *message,
+        indoc! {"
+            assertion `left == right` failed: models[0]
+            2 differences:
+              models[0].tags[1]:
+                left:  finance (line 3 column 21)
+                right: missing
+              models[0].config:
+                left:  missing
+                right: {} (line 4 column 13)
+        "},
+    );
+
+    let left = Value::from(1);
+    let right = Value::from(2);
+    let payload = panic::catch_unwind(|| assert_yaml_eq!(left, right)).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert_eq!(
+        *message,
+        "assertion `left == right` failed\n1 difference:\n  .:\n    left:  1\n    right: 2\n",
+    );
+}
+
+#[test]
+fn test_try_index_mut() {
+    use dbt_serde_yaml::path::OwnedPath;
+
+    let yaml = indoc! {"
+        models:
+          - name: orders
+            config: ~
+        seeds: !custom {}
+        vars: {1: one}
+    "};
+    let mut value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    // Existing values are returned as with get_mut.
+    let name = value["models"][0].try_index_mut("name").unwrap();
+    assert_eq!(*name, "orders");
+    // Nulls become mappings, through tags too, and missing keys are added.
+    *value["models"][0]["config"]
+        .try_index_mut("materialized")
+        .unwrap() = Value::from("table");
+    *value["seeds"].try_index_mut("raw").unwrap() = Value::from(true);
+    *value["vars"].try_index_mut(2).unwrap() = Value::from("two");
+    assert_eq!(*value["vars"].try_index_mut(1).unwrap(), "one");
+
+    // Where indexing would panic, nothing is returned.
+    assert!(value["models"].try_index_mut(1).is_none());
+    assert!(value["models"].try_index_mut("name").is_none());
+    assert!(value["models"][0]["name"].try_index_mut(0).is_none());
+    assert!(value["models"][0]["name"].try_index_mut("x").is_none());
+    assert!(Value::null().try_index_mut(0).is_none());
+
+    let path = |repr: &str| repr.parse::<OwnedPath>().unwrap();
+    *value
+        .get_path_mut_or_insert(path("models[0].meta.owner").as_path())
+        .unwrap() = Value::from("data");
+    assert!(value
+        .get_path_mut_or_insert(path("models[0].name.first").as_path())
+        .is_none());
+    assert!(value
+        .get_path_mut_or_insert(path("models.?").as_path())
+        .is_none());
+    let root = value.clone();
+    assert_eq!(
+        *value.get_path_mut_or_insert(path(".").as_path()).unwrap(),
+        root
+    );
+
+    let expected = indoc! {"
+        models:
+        - name: orders
+          config:
+            materialized: table
+          meta:
+            owner: data
+        seeds: !custom
+          raw: true
+        vars:
+          1: one
+          2: two
+    "};
+    assert_eq!(dbt_serde_yaml::to_string(&value).unwrap(), expected);
+}
+
+#[test]
+fn test_to_value_with_spans() {
+    use dbt_serde_yaml::Spanned;
+    use serde_derive::Serialize;
+
+    #[derive(Deserialize, Serialize)]
+    struct Config {
+        materialized: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        meta: Value,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct Model {
+        name: Spanned<String>,
+        config: Config,
+    }
+
+    let yaml = indoc! {"
+        name: orders
+        config:
+          materialized: view
+          tags: [a, b]
+          meta: !owner {team: data}
+    "};
+    let template: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let mut model: Model = dbt_serde_yaml::from_value(template.clone()).unwrap();
+    model.config.materialized = "table".to_owned();
+    model.config.tags.truncate(1);
+    model.name = Spanned::new("renamed".to_owned());
+
+    let value = dbt_serde_yaml::to_value_with_spans(&model, &template).unwrap();
+    let config = &value["config"];
+    assert_eq!(config.span(), template["config"].span());
+    assert_eq!(config["materialized"], "table");
+    assert_eq!(config["materialized"].span().start.line, 3);
+    assert_eq!(config["materialized"].span().start.column, 17);
+    assert_eq!(config["tags"].as_sequence().unwrap().len(), 1);
+    assert_eq!(config["tags"][0].span().start.column, 10);
+    assert_eq!(config["meta"]["team"].span().start.line, 5);
+    assert_eq!(config["meta"]["team"].span().start.column, 23);
+
+    let key_span = |value: &Value| {
+        let mapping = value["config"].as_mapping().unwrap();
+        let (key, _) = mapping.get_key_value("tags").unwrap();
+        key.span().clone()
+    };
+    assert_eq!(key_span(&value), key_span(&template));
+
+    // A `Spanned` without a location gets that of the template, while a
+    // valid span recorded by one is kept.
+    assert_eq!(value["name"], "renamed");
+    assert_eq!(value["name"].span(), template["name"].span());
+    let moved = template["config"]["materialized"].span().clone();
+    model.name = Spanned::with_span("moved".to_owned(), moved.clone());
+    let value = dbt_serde_yaml::to_value_with_spans(&model, &template).unwrap();
+    assert_eq!(*value["name"].span(), moved);
+
+    // Nothing matches in a template of another shape.
+    let value = dbt_serde_yaml::to_value_with_spans(&model, &Value::null()).unwrap();
+    assert!(!value["config"].span().is_valid());
+}
+
+#[test]
+fn test_flatten_keys_config() {
+    use dbt_serde_yaml::{Config, FlattenKeys};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        extra_fields: BTreeMap<String, i32>,
+    }
+
+    let mut config = Config::new();
+    config.flatten_keys = FlattenKeys::Custom(|name| name.starts_with("extra_"));
+    let scope = config.enter();
+    assert!(dbt_serde_yaml::is_flatten_key("extra_fields"));
+    assert!(!dbt_serde_yaml::is_flatten_key("__rest__"));
+
+    let yaml = indoc! {"
+        name: orders
+        a: 1
+        b: 2
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+    let (model, unused_keys) = deserialize_value::<Model>(value.clone(), |_| Ok(None));
+    assert!(unused_keys.is_empty(), "{:?}", unused_keys);
+    assert_eq!(
+        model.extra_fields,
+        BTreeMap::from([("a".into(), 1), ("b".into(), 2)])
+    );
+    assert_eq!(dbt_serde_yaml::to_value(&model).unwrap(), value);
+    drop(scope);
+
+    // Opting out keeps the field as is, whatever the features.
+    let mut config = Config::new();
+    config.flatten_keys = FlattenKeys::None;
+    let _scope = config.enter();
+    assert!(!dbt_serde_yaml::is_flatten_key("__rest__"));
+    let value = dbt_serde_yaml::to_value(&model).unwrap();
+    assert_eq!(value["extra_fields"]["a"], 1);
+}
+
+#[test]
+fn test_flatten_router() {
+    use dbt_serde_yaml::{Config, FlattenKeys};
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        __meta__: BTreeMap<String, String>,
+        __config__: ModelConfig,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Default)]
+    struct ModelConfig {
+        enabled: Option<bool>,
+    }
+
+    fn route(key: &Value, _value: &Value, fields: &[&'static str]) -> Option<usize> {
+        assert_eq!(fields, ["__meta__", "__config__"]);
+        (key == "enabled").then_some(1)
+    }
+
+    let yaml = indoc! {"
+        name: orders
+        owner: data
+        enabled: false
+    "};
+    let value: Value = dbt_serde_yaml::from_str(yaml).unwrap();
+
+    // Without a router, the map declared first takes every entry.
+    let mut config = Config::new();
+    config.flatten_keys = FlattenKeys::Dunder;
+    let scope = config.enter();
+    let err = dbt_serde_yaml::from_value::<Model>(value.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: boolean `false`, expected a string at line 3 column 10",
+    );
+    drop(scope);
+
+    config.flatten_router = Some(route);
+    let _scope = config.enter();
+    let expected = Model {
+        name: "orders".to_owned(),
+        __meta__: BTreeMap::from([("owner".to_owned(), "data".to_owned())]),
+        __config__: ModelConfig {
+            enabled: Some(false),
+        },
+    };
+    let model: Model = dbt_serde_yaml::from_value(value.clone()).unwrap();
+    assert_eq!(model, expected);
+    let model = Model::deserialize(&value).unwrap();
+    assert_eq!(model, expected);
+}
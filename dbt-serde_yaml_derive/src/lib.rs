@@ -19,24 +19,53 @@ use syn::spanned::Spanned;
 struct Variant<'a> {
     ident: syn::Ident,
     fields: &'a syn::Fields,
+    rename: Option<String>,
+    aliases: Vec<String>,
 }
 
 impl<'a> Variant<'a> {
     pub fn try_from_ast(variant: &'a syn::Variant) -> syn::Result<Self> {
-        if variant
-            .attrs
-            .iter()
-            .any(|attr| attr.path().is_ident("serde"))
-        {
-            return Err(syn::Error::new(
-                variant.span(),
-                "UntaggedEnumDeserialize: #[serde(..)] attributes on variants are not supported",
-            ));
+        let mut rename = None;
+        let mut aliases = Vec::new();
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("serde") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if meta.input.peek(syn::Token![=]) {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        rename = Some(lit.value());
+                    } else {
+                        // #[serde(rename(serialize = "..", deserialize = ".."))]
+                        meta.parse_nested_meta(|meta| {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            if meta.path.is_ident("deserialize") {
+                                rename = Some(lit.value());
+                            } else if !meta.path.is_ident("serialize") {
+                                return Err(meta.error("expected `serialize` or `deserialize`"));
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("alias") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    aliases.push(lit.value());
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "UntaggedEnumDeserialize: only #[serde(rename)] and #[serde(alias)] are supported on variants",
+                    ))
+                }
+            })?;
         }
 
         Ok(Variant {
             ident: variant.ident.clone(),
             fields: &variant.fields,
+            rename,
+            aliases,
         })
     }
 
@@ -90,7 +119,9 @@ impl<'a> Variant<'a> {
     }
 
     fn get_name(&self, default_rename_policy: Option<RenamePolicy>) -> String {
-        if let Some(policy) = default_rename_policy {
+        if let Some(rename) = &self.rename {
+            rename.clone()
+        } else if let Some(policy) = default_rename_policy {
             policy.apply(&self.ident)
         } else {
             self.ident.to_string()
@@ -144,14 +175,11 @@ impl<'a> Variant<'a> {
     ) -> syn::Result<proc_macro2::TokenStream> {
         let expr = self.gen_tagged_deserialize_expr(enum_name)?;
         let constructor = self.gen_constructor()?;
-        let tag_name = if let Some(policy) = default_rename_policy {
-            policy.apply(&self.ident)
-        } else {
-            self.ident.to_string()
-        };
+        let tag_name = self.get_name(default_rename_policy);
+        let aliases = &self.aliases;
 
         let block = quote! {
-            Some(#tag_name) => {
+            Some(#tag_name #( | #aliases )*) => {
                 let __inner = #expr.map_err(|e| {
                     __serde::de::Error::custom(e)
                 })?;
@@ -169,7 +197,7 @@ impl<'a> Variant<'a> {
             __unused_keys.clear();
             let __inner = {
                 let mut collect_unused_keys =
-                    |path: __serde_yaml::Path<'_>, key: &__serde_yaml::Value, value: &__serde_yaml::Value| {
+                    |path: __serde_yaml::__private::Path<'_>, key: &__serde_yaml::__private::Value, value: &__serde_yaml::__private::Value| {
                         __unused_keys.push((path.to_owned_path(), key.clone(), value.clone()));
                     };
 
@@ -183,17 +211,21 @@ impl<'a> Variant<'a> {
     fn gen_constructor_block(
         &self,
         enum_name: &syn::Ident,
+        default_rename_policy: Option<RenamePolicy>,
     ) -> syn::Result<proc_macro2::TokenStream> {
         let constructor = self.gen_constructor()?;
+        let variant_name = self.get_name(default_rename_policy);
 
         let block = quote! {
-            if let Ok(__inner) = __inner {
-                if let Some(mut __callback) = __unused_key_callback {
-                    for (path, key, value) in __unused_keys.iter() {
-                        __callback(*path.as_path(), key, value);
-                    }
+            match __inner {
+                Ok(__inner) => {
+                    __serde_yaml::__private::report_unused_keys(
+                        __unused_key_callback,
+                        &__unused_keys,
+                    )?;
+                    return Ok(#enum_name::#constructor);
                 }
-                return Ok(#enum_name::#constructor);
+                Err(__err) => __errors.record(#variant_name, __err),
             }
         };
 
@@ -400,14 +432,14 @@ impl<'a> EnumDef<'a> {
         let mut variant_blocks = Vec::new();
         for variant in &self.variants {
             let deserialize_block = variant.gen_untagged_deserialize_block()?;
-            let constructor_block = variant.gen_constructor_block(enum_name)?;
+            let constructor_block = variant.gen_constructor_block(enum_name, self.rename_all)?;
             variant_blocks.push(quote! {
                 #deserialize_block
                 #constructor_block
             });
         }
 
-        let err_message = format!("data did not match any variant of untagged enum {enum_name}");
+        let enum_name_str = enum_name.to_string();
 
         Ok(quote! {
             #[automatically_derived]
@@ -416,13 +448,14 @@ impl<'a> EnumDef<'a> {
                 where
                     __D: __serde::de::Deserializer<'de>,
                 {
-                    let mut __state = __serde_yaml::value::extract_reusable_deserializer_state(deserializer)?;
+                    let mut __state = __serde_yaml::__private::extract_reusable_deserializer_state(deserializer)?;
                     let __unused_key_callback = __state.take_unused_key_callback();
                     let mut __unused_keys = vec![];
+                    let mut __errors = __serde_yaml::__private::UntaggedErrors::new();
 
                     #( #variant_blocks )*
 
-                    Err(__serde::de::Error::custom(#err_message))
+                    Err(__errors.into_error(#enum_name_str, __state.span()))
                 }
             }
         })
@@ -453,7 +486,7 @@ impl<'a> EnumDef<'a> {
                 where
                     __D: __serde::de::Deserializer<'de>,
                 {
-                    let (__tag, mut __state) = __serde_yaml::value::extract_tag_and_deserializer_state(deserializer, #tag_key)?;
+                    let (__tag, mut __state) = __serde_yaml::__private::extract_tag_and_deserializer_state(deserializer, #tag_key)?;
                     let __deserializer = __state.get_owned_deserializer();
 
                     match __tag.as_str() {
@@ -494,8 +527,7 @@ fn expand_derive_deserialize(
         const _: () = {
             #[allow(unused_extern_crates, clippy::useless_attribute)]
             extern crate dbt_serde_yaml as __serde_yaml;
-            #[allow(unused_extern_crates, clippy::useless_attribute)]
-            extern crate serde as __serde;
+            use __serde_yaml::__private::serde as __serde;
             #deserialize_impl
         };
     };
@@ -511,3 +543,52 @@ pub fn derive_deserialize(input: TokenStream) -> TokenStream {
         .unwrap_or_else(syn::Error::into_compile_error)
         .into()
 }
+
+fn expand_derive_yaml_deserialize(
+    input: &syn::DeriveInput,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let syn::Data::Union(data_union) = &input.data {
+        return Err(syn::Error::new(
+            data_union.union_token.span(),
+            "YamlDeserialize: can only be derived for structs and enums",
+        ));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let block = quote! {
+        const _: () = {
+            #[allow(unused_extern_crates, clippy::useless_attribute)]
+            extern crate dbt_serde_yaml as __serde_yaml;
+            use __serde_yaml::__private::serde as __serde;
+
+            #[automatically_derived]
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Deserializes a value of this type from a YAML `Value`, with
+                /// the unused key policy and field transformer given in
+                /// `options`, returning every problem found.
+                pub fn from_value(
+                    value: __serde_yaml::__private::Value,
+                    options: __serde_yaml::__private::FromValueOptions<'_>,
+                ) -> ::core::result::Result<Self, __serde_yaml::__private::vec::Vec<__serde_yaml::__private::Error>>
+                where
+                    Self: __serde::de::DeserializeOwned,
+                {
+                    value.into_typed_with_options(options)
+                }
+            }
+        };
+    };
+
+    Ok(block)
+}
+
+#[proc_macro_derive(YamlDeserialize)]
+pub fn derive_yaml_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_derive_yaml_deserialize(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -80,22 +80,27 @@ impl Field<'_> {
     }
 
     pub fn is_flatten(&self) -> bool {
+        self.serde_attrs.flatten() || self.is_dunder_flatten()
+    }
+
+    /// Whether the field is flattened for having a name wrapped in double
+    /// underscores, like `__rest__`, as with the `flatten_dunder` feature.
+    pub fn is_dunder_flatten(&self) -> bool {
         #[cfg(feature = "flatten_dunder")]
         {
-            self.serde_attrs.flatten()
-                || self.original.ident.as_ref().is_some_and(|ident| {
-                    let ident_string = ident.to_string();
-                    let ident = ident_string.as_bytes();
-                    ident.len() > 4
-                        && ident[0] == b'_'
-                        && ident[1] == b'_'
-                        && ident[ident.len() - 1] == b'_'
-                        && ident[ident.len() - 2] == b'_'
-                })
+            self.original.ident.as_ref().is_some_and(|ident| {
+                let ident_string = ident.to_string();
+                let ident = ident_string.as_bytes();
+                ident.len() > 4
+                    && ident[0] == b'_'
+                    && ident[1] == b'_'
+                    && ident[ident.len() - 1] == b'_'
+                    && ident[ident.len() - 2] == b'_'
+            })
         }
         #[cfg(not(feature = "flatten_dunder"))]
         {
-            self.serde_attrs.flatten()
+            false
         }
     }
 }
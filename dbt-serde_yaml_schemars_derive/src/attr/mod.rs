@@ -22,12 +22,20 @@ pub struct Attrs {
     pub title: Option<String>,
     pub description: Option<String>,
     pub deprecated: bool,
-    pub examples: Vec<syn::Path>,
+    pub examples: Vec<Example>,
     pub repr: Option<syn::Type>,
     pub crate_name: Option<syn::Path>,
     pub is_renamed: bool,
 }
 
+#[derive(Debug)]
+pub enum Example {
+    /// `example = "path"`: a function returning the example.
+    Function(syn::Path),
+    /// `example = expr` for any other expression: the example itself.
+    Value(syn::Expr),
+}
+
 #[derive(Debug)]
 pub enum WithAttr {
     Type(syn::Type),
@@ -144,11 +152,18 @@ impl Attrs {
                     }
                 }
 
-                Meta::NameValue(m) if m.path.is_ident("example") => {
-                    if let Ok(fun) = parse_lit_into_path(errors, attr_type, "example", &m.value) {
-                        self.examples.push(fun)
+                Meta::NameValue(m) if m.path.is_ident("example") => match &m.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(_),
+                        ..
+                    }) => {
+                        if let Ok(fun) = parse_lit_into_path(errors, attr_type, "example", &m.value)
+                        {
+                            self.examples.push(Example::Function(fun))
+                        }
                     }
-                }
+                    value => self.examples.push(Example::Value(value.clone())),
+                },
 
                 Meta::NameValue(m) if m.path.is_ident("rename") => self.is_renamed = true,
 
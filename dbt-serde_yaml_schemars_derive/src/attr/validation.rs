@@ -7,7 +7,7 @@ use syn::{
 };
 
 pub(crate) static VALIDATION_KEYWORDS: &[&str] = &[
-    "range", "regex", "contains", "email", "phone", "url", "length", "required",
+    "range", "regex", "pattern", "contains", "email", "phone", "url", "length", "required",
 ];
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -42,6 +42,8 @@ pub struct ValidationAttrs {
     length_equal: Option<Expr>,
     range_min: Option<Expr>,
     range_max: Option<Expr>,
+    range_exclusive_min: Option<Expr>,
+    range_exclusive_max: Option<Expr>,
     regex: Option<Expr>,
     contains: Option<String>,
     required: bool,
@@ -169,6 +171,8 @@ impl ValidationAttrs {
                             Meta::NameValue(nv) if nv.path.is_ident("min") => {
                                 if self.range_min.is_some() {
                                     duplicate_error(&nv.path)
+                                } else if self.range_exclusive_min.is_some() {
+                                    mutual_exclusive_error(&nv.path, "exclusive_min")
                                 } else {
                                     self.range_min = str_or_num_to_expr(errors, "min", nv.value);
                                 }
@@ -176,10 +180,32 @@ impl ValidationAttrs {
                             Meta::NameValue(nv) if nv.path.is_ident("max") => {
                                 if self.range_max.is_some() {
                                     duplicate_error(&nv.path)
+                                } else if self.range_exclusive_max.is_some() {
+                                    mutual_exclusive_error(&nv.path, "exclusive_max")
                                 } else {
                                     self.range_max = str_or_num_to_expr(errors, "max", nv.value);
                                 }
                             }
+                            Meta::NameValue(nv) if nv.path.is_ident("exclusive_min") => {
+                                if self.range_exclusive_min.is_some() {
+                                    duplicate_error(&nv.path)
+                                } else if self.range_min.is_some() {
+                                    mutual_exclusive_error(&nv.path, "min")
+                                } else {
+                                    self.range_exclusive_min =
+                                        str_or_num_to_expr(errors, "exclusive_min", nv.value);
+                                }
+                            }
+                            Meta::NameValue(nv) if nv.path.is_ident("exclusive_max") => {
+                                if self.range_exclusive_max.is_some() {
+                                    duplicate_error(&nv.path)
+                                } else if self.range_max.is_some() {
+                                    mutual_exclusive_error(&nv.path, "max")
+                                } else {
+                                    self.range_exclusive_max =
+                                        str_or_num_to_expr(errors, "exclusive_max", nv.value);
+                                }
+                            }
                             meta => {
                                 if !ignore_errors {
                                     errors.error_spanned_by(
@@ -220,6 +246,23 @@ impl ValidationAttrs {
                     }
                 }
 
+                Meta::NameValue(nv) if nv.path.is_ident("pattern") => {
+                    match (&self.regex, &self.contains) {
+                        (Some(_), _) => duplicate_error(&nv.path),
+                        (None, Some(_)) => mutual_exclusive_error(&nv.path, "contains"),
+                        (None, None) => {
+                            self.regex = expr_as_lit_str(errors, attr_type, "pattern", &nv.value)
+                                .ok()
+                                .map(|litstr| {
+                                    Expr::Lit(syn::ExprLit {
+                                        attrs: Vec::new(),
+                                        lit: Lit::Str(litstr.clone()),
+                                    })
+                                })
+                        }
+                    }
+                }
+
                 Meta::List(meta_list) if meta_list.path.is_ident("regex") => {
                     match (&self.regex, &self.contains) {
                         (Some(_), _) => duplicate_error(&meta_list.path),
@@ -372,6 +415,18 @@ impl ValidationAttrs {
             });
         }
 
+        if let Some(range_exclusive_min) = &self.range_exclusive_min {
+            number_validation.push(quote! {
+                validation.exclusive_minimum = Some(#range_exclusive_min as f64);
+            });
+        }
+
+        if let Some(range_exclusive_max) = &self.range_exclusive_max {
+            number_validation.push(quote! {
+                validation.exclusive_maximum = Some(#range_exclusive_max as f64);
+            });
+        }
+
         if let Some(regex) = &self.regex {
             string_validation.push(quote! {
                 validation.pattern = Some(#regex.to_string());
@@ -1,3 +1,4 @@
+use crate::attr::Example;
 use proc_macro2::TokenStream;
 
 #[derive(Debug, Clone)]
@@ -7,7 +8,7 @@ pub struct SchemaMetadata<'a> {
     pub deprecated: bool,
     pub read_only: bool,
     pub write_only: bool,
-    pub examples: &'a [syn::Path],
+    pub examples: &'a [Example],
     pub default: Option<TokenStream>,
 }
 
@@ -42,10 +43,13 @@ impl SchemaMetadata<'_> {
         }
 
         if !self.examples.is_empty() {
-            let examples = self.examples.iter().map(|eg| {
-                quote! {
-                    schemars::_serde_json::value::to_value(#eg())
-                }
+            let examples = self.examples.iter().map(|eg| match eg {
+                Example::Function(fun) => quote! {
+                    schemars::_serde_json::value::to_value(#fun())
+                },
+                Example::Value(value) => quote! {
+                    schemars::_serde_json::value::to_value(#value)
+                },
             });
 
             *schema_expr = quote! {
@@ -288,11 +288,7 @@ fn expr_for_adjacent_tagged_enum<'a>(
                 enum_values: Some(vec![#name.into()]),
             });
 
-            // Check if variant has an __additional_properties__ field
-            let additional_properties_field = variant
-                .fields
-                .iter()
-                .find(|f| f.name() == "__additional_properties__");
+            let additional_properties_field = additional_properties_field(&variant.fields);
 
             let set_additional_properties =
                 if let Some(additional_field) = additional_properties_field {
@@ -467,11 +463,7 @@ fn expr_for_struct(
     let (flattened_fields, property_fields): (Vec<_>, Vec<_>) =
         filtered_fields.into_iter().partition(|f| f.is_flatten());
 
-    // Look for __additional_properties__ field among flattened fields
-    let additional_properties_field = flattened_fields
-        .iter()
-        .find(|f| f.name() == "__additional_properties__")
-        .copied();
+    let additional_properties_field = additional_properties_field(flattened_fields.iter().copied());
 
     let set_container_default = match default {
         SerdeDefault::None => None,
@@ -523,9 +515,9 @@ fn expr_for_struct(
     let flattens: Vec<_> = flattened_fields
         .into_iter()
         .filter(|field| {
-            // Exclude __additional_properties__ field from normal flatten processing
-            // since we handle it specially for additional_properties
-            field.name() != "__additional_properties__"
+            // Exclude the additional properties field from normal flatten
+            // processing since we handle it specially for additional_properties
+            !additional_properties_field.is_some_and(|f| std::ptr::eq(f, *field))
         })
         .map(|field| {
             let (ty, type_def) = type_for_field_schema(field);
@@ -542,7 +534,7 @@ fn expr_for_struct(
         })
         .collect();
 
-    // Handle additional_properties based on __additional_properties__ field
+    // Handle additional_properties based on the additional properties field
     let set_additional_properties = if let Some(additional_field) = additional_properties_field {
         // For BTreeMap<String, T> or HashMap<String, T>, we want to use T as the additional_properties schema
         // Extract the value type from Map types
@@ -564,7 +556,7 @@ fn expr_for_struct(
             }
         }
     } else if deny_unknown_fields {
-        // Fallback to the original logic if no __additional_properties__ field exists
+        // Fallback to the original logic if no additional properties field exists
         quote! {
             object_validation.additional_properties = Some(Box::new(false.into()));
         }
@@ -665,13 +657,46 @@ fn prepend_type_def(type_def: Option<TokenStream>, schema_expr: &mut TokenStream
 
 /// Extract the value type from Map types like BTreeMap<String, T> or HashMap<String, T>
 /// Returns the T type and any associated type definitions
+/// Returns the field whose entries are modeled as the `additionalProperties`
+/// of the object: one named `__additional_properties__`, or else a
+/// dunder-flatten map field like `__rest__: HashMap<String, T>`, which
+/// collects the unknown keys at runtime.
+fn additional_properties_field<'a, 'f>(
+    fields: impl IntoIterator<Item = &'a Field<'f>>,
+) -> Option<&'a Field<'f>> {
+    let mut dunder_map_field = None;
+    for field in fields {
+        if field.name() == "__additional_properties__" {
+            return Some(field);
+        }
+        if dunder_map_field.is_none()
+            && field.is_dunder_flatten()
+            && map_value_type(&type_for_field_schema(field).0).is_some()
+        {
+            dunder_map_field = Some(field);
+        }
+    }
+    dunder_map_field
+}
+
 fn extract_map_value_type(field: &Field) -> (syn::Type, Option<TokenStream>) {
     let (full_ty, type_def) = type_for_field_schema(field);
 
-    // Try to extract the value type from common map types
-    if let syn::Type::Path(type_path) = &full_ty {
+    // Fall back to the full type if we can't extract the value type
+    match map_value_type(&full_ty) {
+        Some(value_ty) => (value_ty, type_def),
+        None => (full_ty, type_def),
+    }
+}
+
+/// Returns the value type of common map types.
+fn map_value_type(ty: &syn::Type) -> Option<syn::Type> {
+    if let syn::Type::Path(type_path) = ty {
         if let Some(segment) = type_path.path.segments.last() {
-            if segment.ident == "BTreeMap" || segment.ident == "HashMap" {
+            if segment.ident == "BTreeMap"
+                || segment.ident == "HashMap"
+                || segment.ident == "IndexMap"
+            {
                 if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                     if args.args.len() == 2 {
                         if let (
@@ -679,14 +704,12 @@ fn extract_map_value_type(field: &Field) -> (syn::Type, Option<TokenStream>) {
                             syn::GenericArgument::Type(value_ty),
                         ) = (&args.args[0], &args.args[1])
                         {
-                            return (value_ty.clone(), type_def);
+                            return Some(value_ty.clone());
                         }
                     }
                 }
             }
         }
     }
-
-    // Fall back to the full type if we can't extract the value type
-    (full_ty, type_def)
+    None
 }